@@ -4,6 +4,11 @@
 
 #![no_std]
 
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::hint::black_box;
+
 /// Benchmark configuration
 #[derive(Debug, Clone)]
 pub struct BenchmarkConfig {
@@ -13,6 +18,9 @@ pub struct BenchmarkConfig {
     pub height: u32,
     /// Duration in seconds
     pub duration: u32,
+    /// Frames rendered but not measured before recording starts
+    /// (first-frame allocations and cold caches skew min FPS)
+    pub warmup_frames: u32,
     /// Which benchmark to run
     pub benchmark_type: BenchmarkType,
 }
@@ -23,6 +31,7 @@ impl Default for BenchmarkConfig {
             width: 1024,
             height: 768,
             duration: 30,
+            warmup_frames: 60,
             benchmark_type: BenchmarkType::Rendering,
         }
     }
@@ -35,7 +44,7 @@ pub enum BenchmarkType {
     Rendering,
     /// Physics/collision performance
     Physics,
-    /// Network throughput
+    /// Network throughput (see `NetworkBenchmark`)
     Network,
     /// Memory allocation stress
     Memory,
@@ -43,6 +52,116 @@ pub enum BenchmarkType {
     FullGame,
 }
 
+/// Frames of subsystem breakdowns kept for averaging
+pub const BREAKDOWN_FRAMES: usize = 256;
+
+/// Per-frame time spent in each major subsystem, in TSC cycles
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BenchmarkFrameBreakdown {
+    /// Transforming, culling and binning triangles to tiles
+    pub transform_cycles: u64,
+    /// Tile rasterization (until every core has finished)
+    pub rasterize_cycles: u64,
+    /// 2D HUD and overlays
+    pub hud_cycles: u64,
+    /// Network stack polling and packet processing
+    pub network_cycles: u64,
+    /// World simulation step
+    pub physics_cycles: u64,
+    /// Copying the back buffer to the display and the screen update
+    pub present_cycles: u64,
+}
+
+impl BenchmarkFrameBreakdown {
+    /// (name, cycles) for each subsystem, in report order
+    pub fn subsystems(&self) -> [(&'static str, u64); 6] {
+        [
+            ("transform", self.transform_cycles),
+            ("rasterize", self.rasterize_cycles),
+            ("hud", self.hud_cycles),
+            ("network", self.network_cycles),
+            ("physics", self.physics_cycles),
+            ("present", self.present_cycles),
+        ]
+    }
+
+    /// Cycles across all subsystems
+    pub fn total_cycles(&self) -> u64 {
+        self.subsystems().iter().map(|&(_, cycles)| cycles).sum()
+    }
+}
+
+/// Ring buffer of the last BREAKDOWN_FRAMES frame breakdowns
+#[derive(Debug, Clone)]
+pub struct BreakdownRing {
+    frames: [BenchmarkFrameBreakdown; BREAKDOWN_FRAMES],
+    next: usize,
+    len: usize,
+}
+
+impl Default for BreakdownRing {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BreakdownRing {
+    pub const fn new() -> Self {
+        Self {
+            frames: [BenchmarkFrameBreakdown {
+                transform_cycles: 0,
+                rasterize_cycles: 0,
+                hud_cycles: 0,
+                network_cycles: 0,
+                physics_cycles: 0,
+                present_cycles: 0,
+            }; BREAKDOWN_FRAMES],
+            next: 0,
+            len: 0,
+        }
+    }
+
+    /// Add a frame, replacing the oldest once full
+    pub fn push(&mut self, frame: BenchmarkFrameBreakdown) {
+        self.frames[self.next] = frame;
+        self.next = (self.next + 1) % BREAKDOWN_FRAMES;
+        self.len = (self.len + 1).min(BREAKDOWN_FRAMES);
+    }
+
+    /// Number of frames held
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.next = 0;
+        self.len = 0;
+    }
+
+    /// Per-subsystem average over the held frames (all zero when empty)
+    pub fn average(&self) -> BenchmarkFrameBreakdown {
+        if self.len == 0 {
+            return BenchmarkFrameBreakdown::default();
+        }
+        // Held frames are the first `len` slots until the ring wraps
+        let frames = &self.frames[..self.len];
+        let n = self.len as u64;
+        let avg = |field: fn(&BenchmarkFrameBreakdown) -> u64| frames.iter().map(field).sum::<u64>() / n;
+        BenchmarkFrameBreakdown {
+            transform_cycles: avg(|f| f.transform_cycles),
+            rasterize_cycles: avg(|f| f.rasterize_cycles),
+            hud_cycles: avg(|f| f.hud_cycles),
+            network_cycles: avg(|f| f.network_cycles),
+            physics_cycles: avg(|f| f.physics_cycles),
+            present_cycles: avg(|f| f.present_cycles),
+        }
+    }
+}
+
 /// Benchmark results
 #[derive(Debug, Clone, Default)]
 pub struct BenchmarkResults {
@@ -60,6 +179,8 @@ pub struct BenchmarkResults {
     pub total_triangles: u64,
     /// Average triangles per frame
     pub avg_triangles: u64,
+    /// Average cycles per subsystem over the last BREAKDOWN_FRAMES frames
+    pub avg_breakdown: BenchmarkFrameBreakdown,
 }
 
 /// Benchmark runner
@@ -71,6 +192,10 @@ pub struct Benchmark {
     elapsed_time: f32,
     frame_times: [f32; 256],
     frame_time_index: usize,
+    /// Warmup frames left before frames are recorded
+    warmup_remaining: u32,
+    /// Subsystem timings of recent recorded frames
+    breakdowns: BreakdownRing,
 }
 
 impl Benchmark {
@@ -83,6 +208,8 @@ impl Benchmark {
             elapsed_time: 0.0,
             frame_times: [0.0; 256],
             frame_time_index: 0,
+            warmup_remaining: 0,
+            breakdowns: BreakdownRing::new(),
         }
     }
 
@@ -94,6 +221,8 @@ impl Benchmark {
         self.results = BenchmarkResults::default();
         self.frame_times = [0.0; 256];
         self.frame_time_index = 0;
+        self.warmup_remaining = self.config.warmup_frames;
+        self.breakdowns.clear();
     }
 
     /// Stop the benchmark and compute results
@@ -109,6 +238,12 @@ impl Benchmark {
             return;
         }
 
+        // Warmup frames are rendered but not measured
+        if self.warmup_remaining > 0 {
+            self.warmup_remaining -= 1;
+            return;
+        }
+
         self.frame_count += 1;
         self.elapsed_time += frame_time;
         self.results.total_triangles += triangles;
@@ -123,13 +258,29 @@ impl Benchmark {
         }
     }
 
+    /// Record a frame's subsystem timings (call before `record_frame`
+    /// for the same frame; warmup frames are ignored)
+    pub fn record_breakdown(&mut self, breakdown: BenchmarkFrameBreakdown) {
+        if self.running && self.warmup_remaining == 0 {
+            self.breakdowns.push(breakdown);
+        }
+    }
+
     /// Check if benchmark is running
     pub fn is_running(&self) -> bool {
         self.running
     }
 
-    /// Get progress (0.0 - 1.0)
+    /// Check if the benchmark is still in its warmup phase
+    pub fn is_warming_up(&self) -> bool {
+        self.running && self.warmup_remaining > 0
+    }
+
+    /// Get progress (0.0 - 1.0, stays 0.0 during warmup)
     pub fn progress(&self) -> f32 {
+        if self.warmup_remaining > 0 {
+            return 0.0;
+        }
         (self.elapsed_time / self.config.duration as f32).min(1.0)
     }
 
@@ -145,6 +296,8 @@ impl Benchmark {
             self.results.avg_triangles = self.results.total_triangles / self.frame_count;
         }
 
+        self.results.avg_breakdown = self.breakdowns.average();
+
         // Compute min/max/percentile FPS from frame times
         let mut valid_times: [f32; 256] = [0.0; 256];
         let valid_count = self.frame_count.min(256) as usize;
@@ -196,3 +349,480 @@ impl Benchmark {
         &self.config
     }
 }
+
+/// UDP payload per benchmark packet (largest that fits one Ethernet frame
+/// without IP fragmentation: 1500 MTU - 20 IPv4 - 8 UDP, rounded down)
+pub const NET_BENCH_PAYLOAD: usize = 1400;
+
+/// Ethernet + IPv4 + UDP header bytes in front of the payload
+const NET_BENCH_HEADERS: usize = 14 + 20 + 8;
+
+/// Full benchmark frame size
+pub const NET_BENCH_FRAME: usize = NET_BENCH_HEADERS + NET_BENCH_PAYLOAD;
+
+/// Payload tag identifying benchmark frames among any other received traffic
+const NET_BENCH_MAGIC: [u8; 4] = *b"BRNB";
+
+/// Frames queued back to back in one flood burst (fits the E1000 TX ring)
+pub const NET_BENCH_FLOOD_BURST: u32 = 64;
+
+/// Device under test: a NIC that loops transmitted frames back to its receiver,
+/// plus the clock used to time it
+pub trait LoopbackDevice {
+    /// MAC address (benchmark frames are addressed to it)
+    fn mac_address(&self) -> [u8; 6];
+    /// Queue a frame for transmit (false if it could not be queued)
+    fn send(&mut self, frame: &[u8]) -> bool;
+    /// Copy the next received frame into `buf`, returning its length
+    fn receive(&mut self, buf: &mut [u8]) -> Option<usize>;
+    /// Monotonic timestamp in ticks
+    fn now_ticks(&self) -> u64;
+    /// Ticks per second of `now_ticks`
+    fn ticks_per_second(&self) -> u64;
+}
+
+/// Network benchmark results
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct NetworkBenchResults {
+    /// Send-then-wait round trips completed per second
+    pub round_trips_per_second: f32,
+    /// Packets received per second while flooding the receiver
+    pub packets_per_second: f32,
+    /// Payload bytes received per second while flooding
+    pub bytes_per_second: f32,
+    /// Packets sent but never received (timed out or lost)
+    pub dropped_packets: u64,
+}
+
+/// E1000 packet-rate benchmark (`BenchmarkType::Network`)
+///
+/// The first half of the run measures latency-bound round trips (one packet in
+/// flight); the second half floods the receiver with bursts queued from a
+/// pre-filled TX ring and measures receive throughput.
+pub struct NetworkBenchmark<D: LoopbackDevice> {
+    device: D,
+    frame: [u8; NET_BENCH_FRAME],
+    rx_buf: [u8; NET_BENCH_FRAME + 64],
+    sequence: u32,
+}
+
+impl<D: LoopbackDevice> NetworkBenchmark<D> {
+    pub fn new(device: D) -> Self {
+        let frame = udp_frame(device.mac_address());
+        Self {
+            device,
+            frame,
+            rx_buf: [0; NET_BENCH_FRAME + 64],
+            sequence: 0,
+        }
+    }
+
+    /// Give the device back (e.g. to take it out of loopback)
+    pub fn into_device(self) -> D {
+        self.device
+    }
+
+    /// Run for `duration_secs` seconds (split evenly between the two phases)
+    pub fn run(&mut self, duration_secs: u32) -> NetworkBenchResults {
+        let tps = self.device.ticks_per_second();
+        let phase_ticks = tps * duration_secs.max(1) as u64 / 2;
+        // A packet not back within 10ms is counted as dropped
+        let timeout = (tps / 100).max(1);
+        let mut results = NetworkBenchResults::default();
+
+        // Phase 1: round trips, one packet in flight
+        let start = self.device.now_ticks();
+        let mut round_trips = 0u64;
+        while self.device.now_ticks().wrapping_sub(start) < phase_ticks {
+            let seq = self.send_next();
+            if self.wait_for(seq, timeout) {
+                round_trips += 1;
+            } else {
+                results.dropped_packets += 1;
+            }
+        }
+        let secs = self.device.now_ticks().wrapping_sub(start) as f32 / tps as f32;
+        results.round_trips_per_second = round_trips as f32 / secs;
+
+        // Phase 2: flood bursts, then drain everything that came back
+        let start = self.device.now_ticks();
+        let (mut sent, mut received) = (0u64, 0u64);
+        while self.device.now_ticks().wrapping_sub(start) < phase_ticks {
+            for _ in 0..NET_BENCH_FLOOD_BURST {
+                self.send_next();
+                sent += 1;
+            }
+            received += self.drain(timeout);
+        }
+        let secs = self.device.now_ticks().wrapping_sub(start) as f32 / tps as f32;
+        results.packets_per_second = received as f32 / secs;
+        results.bytes_per_second = results.packets_per_second * NET_BENCH_PAYLOAD as f32;
+        results.dropped_packets += sent.saturating_sub(received);
+
+        results
+    }
+
+    /// Send the next sequence-numbered frame, returning its sequence number
+    fn send_next(&mut self) -> u32 {
+        let seq = self.sequence;
+        self.sequence = self.sequence.wrapping_add(1);
+        let tag = NET_BENCH_HEADERS + NET_BENCH_MAGIC.len();
+        self.frame[tag..tag + 4].copy_from_slice(&seq.to_le_bytes());
+        self.device.send(&self.frame);
+        seq
+    }
+
+    /// Sequence number of a received benchmark frame
+    fn received_sequence(&self, len: usize) -> Option<u32> {
+        let payload = self.rx_buf[..len].get(NET_BENCH_HEADERS..)?;
+        if payload.len() < 8 || payload[..4] != NET_BENCH_MAGIC {
+            return None;
+        }
+        Some(u32::from_le_bytes([payload[4], payload[5], payload[6], payload[7]]))
+    }
+
+    /// Receive until frame `seq` arrives or `timeout` ticks pass
+    fn wait_for(&mut self, seq: u32, timeout: u64) -> bool {
+        let start = self.device.now_ticks();
+        while self.device.now_ticks().wrapping_sub(start) < timeout {
+            if let Some(len) = self.device.receive(&mut self.rx_buf) {
+                if self.received_sequence(len) == Some(seq) {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /// Receive benchmark frames until none arrives for `timeout` ticks
+    fn drain(&mut self, timeout: u64) -> u64 {
+        let mut received = 0;
+        let mut last = self.device.now_ticks();
+        while self.device.now_ticks().wrapping_sub(last) < timeout {
+            if let Some(len) = self.device.receive(&mut self.rx_buf) {
+                if self.received_sequence(len).is_some() {
+                    received += 1;
+                }
+                last = self.device.now_ticks();
+            }
+        }
+        received
+    }
+}
+
+/// Large benchmark allocation (a mesh vertex buffer)
+pub const MEM_BENCH_LARGE_BLOCK: usize = 64 * 1024;
+
+/// Small benchmark allocation (a game object)
+pub const MEM_BENCH_SMALL_BLOCK: usize = 64;
+
+/// Large blocks allocated and then freed together in one large round (4MB)
+pub const MEM_BENCH_LARGE_BATCH: usize = 64;
+
+/// Small blocks kept alive across rounds; half of them are replaced per small round
+pub const MEM_BENCH_SMALL_LIVE: usize = 4096;
+
+/// Heap under test: the clock used to time it plus a view of its free space
+pub trait HeapProbe {
+    /// Monotonic timestamp in ticks
+    fn now_ticks(&self) -> u64;
+    /// Ticks per second of `now_ticks`
+    fn ticks_per_second(&self) -> u64;
+    /// (largest single free block, total free bytes)
+    fn free_space(&self) -> (usize, usize);
+}
+
+/// Memory benchmark results
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MemoryBenchResults {
+    /// Allocations completed per second of run time
+    pub allocations_per_second: f32,
+    /// Average time to free one block
+    pub free_latency_ns: f32,
+    /// Largest free block as a percentage of all free bytes (100 = one hole)
+    pub fragmentation_percent: f32,
+    /// Allocations made during the run
+    pub allocations: u64,
+}
+
+/// Allocator throughput benchmark (`BenchmarkType::Memory`)
+///
+/// Alternates between large rounds, which allocate a batch of
+/// `MEM_BENCH_LARGE_BLOCK` buffers and free them all, and small rounds, which
+/// replace every other one of `MEM_BENCH_SMALL_LIVE` live game-object sized
+/// blocks. The survivors of each small round sit between the holes the next
+/// large round has to work around.
+pub struct MemoryBenchmark<P: HeapProbe> {
+    probe: P,
+}
+
+impl<P: HeapProbe> MemoryBenchmark<P> {
+    pub fn new(probe: P) -> Self {
+        Self { probe }
+    }
+
+    /// Give the probe back
+    pub fn into_probe(self) -> P {
+        self.probe
+    }
+
+    /// Run for `duration_secs` seconds
+    pub fn run(&mut self, duration_secs: u32) -> MemoryBenchResults {
+        let tps = self.probe.ticks_per_second();
+        let duration = tps * duration_secs.max(1) as u64;
+        let mut results = MemoryBenchResults::default();
+
+        let mut large: Vec<Vec<u8>> = Vec::with_capacity(MEM_BENCH_LARGE_BATCH);
+        let mut small: Vec<Vec<u8>> =
+            (0..MEM_BENCH_SMALL_LIVE).map(|_| Vec::with_capacity(MEM_BENCH_SMALL_BLOCK)).collect();
+        let (mut frees, mut free_ticks) = (0u64, 0u64);
+
+        let start = self.probe.now_ticks();
+        let mut round = 0u64;
+        while self.probe.now_ticks().wrapping_sub(start) < duration {
+            if round.is_multiple_of(2) {
+                for _ in 0..MEM_BENCH_LARGE_BATCH {
+                    large.push(black_box(Vec::with_capacity(MEM_BENCH_LARGE_BLOCK)));
+                }
+                let t = self.probe.now_ticks();
+                large.clear();
+                free_ticks += self.probe.now_ticks().wrapping_sub(t);
+                frees += MEM_BENCH_LARGE_BATCH as u64;
+                results.allocations += MEM_BENCH_LARGE_BATCH as u64;
+            } else {
+                // Alternate halves so consecutive small rounds free different blocks
+                let half = (round / 2 % 2) as usize;
+                let t = self.probe.now_ticks();
+                for slot in small.iter_mut().skip(half).step_by(2) {
+                    *slot = Vec::new();
+                }
+                free_ticks += self.probe.now_ticks().wrapping_sub(t);
+                for slot in small.iter_mut().skip(half).step_by(2) {
+                    *slot = black_box(Vec::with_capacity(MEM_BENCH_SMALL_BLOCK));
+                }
+                frees += (MEM_BENCH_SMALL_LIVE / 2) as u64;
+                results.allocations += (MEM_BENCH_SMALL_LIVE / 2) as u64;
+            }
+            round += 1;
+        }
+        let secs = self.probe.now_ticks().wrapping_sub(start) as f32 / tps as f32;
+        results.allocations_per_second = results.allocations as f32 / secs;
+        if frees > 0 {
+            results.free_latency_ns = free_ticks as f32 * 1e9 / tps as f32 / frees as f32;
+        }
+
+        // Measured while the small blocks are still live
+        let (largest, total) = self.probe.free_space();
+        if total > 0 {
+            results.fragmentation_percent = largest as f32 / total as f32 * 100.0;
+        }
+        drop(small);
+
+        results
+    }
+}
+
+/// Ethernet/IPv4/UDP frame to `mac` carrying a tagged NET_BENCH_PAYLOAD payload
+/// (UDP checksum 0 = none; the IPv4 header checksum is filled in)
+fn udp_frame(mac: [u8; 6]) -> [u8; NET_BENCH_FRAME] {
+    const IP_LEN: u16 = (20 + 8 + NET_BENCH_PAYLOAD) as u16;
+    const UDP_LEN: u16 = (8 + NET_BENCH_PAYLOAD) as u16;
+    const PORT: u16 = 9; // discard
+
+    let mut frame = [0u8; NET_BENCH_FRAME];
+    frame[0..6].copy_from_slice(&mac);
+    frame[6..12].copy_from_slice(&mac);
+    frame[12..14].copy_from_slice(&0x0800u16.to_be_bytes());
+
+    let ip = &mut frame[14..34];
+    ip[0] = 0x45; // IPv4, 5-word header
+    ip[2..4].copy_from_slice(&IP_LEN.to_be_bytes());
+    ip[8] = 64; // TTL
+    ip[9] = 17; // UDP
+    ip[12..16].copy_from_slice(&[127, 0, 0, 1]);
+    ip[16..20].copy_from_slice(&[127, 0, 0, 1]);
+    let sum = ip.chunks(2).fold(0u32, |sum, w| sum + u16::from_be_bytes([w[0], w[1]]) as u32);
+    let sum = (sum & 0xFFFF) + (sum >> 16);
+    ip[10..12].copy_from_slice(&(!((sum & 0xFFFF) + (sum >> 16)) as u16).to_be_bytes());
+
+    let udp = &mut frame[34..42];
+    udp[0..2].copy_from_slice(&PORT.to_be_bytes());
+    udp[2..4].copy_from_slice(&PORT.to_be_bytes());
+    udp[4..6].copy_from_slice(&UDP_LEN.to_be_bytes());
+
+    frame[NET_BENCH_HEADERS..NET_BENCH_HEADERS + 4].copy_from_slice(&NET_BENCH_MAGIC);
+    frame
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_warmup_frames_not_recorded() {
+        let mut bench = Benchmark::new(BenchmarkConfig { duration: 1, warmup_frames: 3, ..Default::default() });
+        bench.start();
+
+        // Slow warmup frames are dropped and leave progress at zero
+        for _ in 0..3 {
+            assert!(bench.is_warming_up());
+            bench.record_frame(0.5, 100);
+            assert_eq!(bench.progress(), 0.0);
+        }
+        assert!(!bench.is_warming_up());
+
+        bench.record_frame(0.25, 10);
+        assert_eq!(bench.progress(), 0.25);
+        for _ in 0..3 {
+            bench.record_frame(0.25, 10);
+        }
+        assert!(!bench.is_running());
+
+        let results = bench.results();
+        assert_eq!(results.total_frames, 4);
+        assert_eq!(results.total_triangles, 40);
+        assert_eq!(results.min_fps, 4.0);
+    }
+
+    #[test]
+    fn test_breakdown_averages_recent_frames() {
+        let mut bench = Benchmark::new(BenchmarkConfig { warmup_frames: 1, ..Default::default() });
+        bench.start();
+        let frame = |cycles: u64| BenchmarkFrameBreakdown {
+            transform_cycles: cycles,
+            rasterize_cycles: cycles * 2,
+            ..Default::default()
+        };
+
+        // The warmup frame's breakdown is ignored
+        bench.record_breakdown(frame(1_000_000));
+        bench.record_frame(0.01, 0);
+
+        // Only the newest BREAKDOWN_FRAMES frames count: 100 old ones are overwritten
+        for i in 0..BREAKDOWN_FRAMES as u64 + 100 {
+            bench.record_breakdown(frame(if i < 100 { 5000 } else { 10 }));
+            bench.record_frame(0.01, 0);
+        }
+
+        let avg = bench.stop().avg_breakdown;
+        assert_eq!(avg, frame(10));
+        assert_eq!(avg.total_cycles(), 30);
+        assert_eq!(avg.subsystems()[1], ("rasterize", 20));
+    }
+
+    /// Loopback with a fixed-size queue; every `drop_every`th frame is lost
+    struct FakeLoopback {
+        queue: [[u8; NET_BENCH_FRAME]; 128],
+        head: usize,
+        len: usize,
+        sent: u32,
+        drop_every: u32,
+        ticks: core::cell::Cell<u64>,
+    }
+
+    impl LoopbackDevice for FakeLoopback {
+        fn mac_address(&self) -> [u8; 6] {
+            [2, 0, 0, 0, 0, 1]
+        }
+
+        fn send(&mut self, frame: &[u8]) -> bool {
+            self.sent += 1;
+            if self.sent.is_multiple_of(self.drop_every) || self.len == self.queue.len() {
+                return true;
+            }
+            let slot = (self.head + self.len) % self.queue.len();
+            self.queue[slot].copy_from_slice(frame);
+            self.len += 1;
+            true
+        }
+
+        fn receive(&mut self, buf: &mut [u8]) -> Option<usize> {
+            if self.len == 0 {
+                return None;
+            }
+            buf[..NET_BENCH_FRAME].copy_from_slice(&self.queue[self.head]);
+            self.head = (self.head + 1) % self.queue.len();
+            self.len -= 1;
+            Some(NET_BENCH_FRAME)
+        }
+
+        // Every clock read advances 1ms
+        fn now_ticks(&self) -> u64 {
+            self.ticks.set(self.ticks.get() + 1);
+            self.ticks.get()
+        }
+
+        fn ticks_per_second(&self) -> u64 {
+            1000
+        }
+    }
+
+    #[test]
+    fn test_network_benchmark_counts_drops() {
+        let device = FakeLoopback {
+            queue: [[0; NET_BENCH_FRAME]; 128],
+            head: 0,
+            len: 0,
+            sent: 0,
+            drop_every: 10,
+            ticks: core::cell::Cell::new(0),
+        };
+        let mut bench = NetworkBenchmark::new(device);
+        let results = bench.run(2);
+
+        assert!(results.round_trips_per_second > 0.0);
+        assert!(results.packets_per_second > 0.0);
+        assert_eq!(results.bytes_per_second, results.packets_per_second * NET_BENCH_PAYLOAD as f32);
+
+        // One frame in ten never came back
+        let device = bench.into_device();
+        assert_eq!(results.dropped_packets, (device.sent / 10) as u64);
+        assert_eq!(device.len, 0);
+    }
+
+    /// Clock advancing 1ms per read over a heap reporting fixed free space
+    struct FakeHeap {
+        ticks: core::cell::Cell<u64>,
+    }
+
+    impl HeapProbe for FakeHeap {
+        fn now_ticks(&self) -> u64 {
+            self.ticks.set(self.ticks.get() + 1);
+            self.ticks.get()
+        }
+
+        fn ticks_per_second(&self) -> u64 {
+            1000
+        }
+
+        fn free_space(&self) -> (usize, usize) {
+            (3 << 20, 4 << 20)
+        }
+    }
+
+    #[test]
+    fn test_memory_benchmark_alternates_rounds() {
+        let mut bench = MemoryBenchmark::new(FakeHeap { ticks: core::cell::Cell::new(0) });
+        let results = bench.run(1);
+
+        // Every round costs three clock reads; large and small rounds alternate
+        let rounds: u64 = 1000 / 3;
+        let expected = rounds.div_ceil(2) * MEM_BENCH_LARGE_BATCH as u64
+            + rounds / 2 * (MEM_BENCH_SMALL_LIVE / 2) as u64;
+        assert_eq!(results.allocations, expected);
+        assert!(results.allocations_per_second > 0.0);
+        assert!(results.free_latency_ns > 0.0);
+        assert_eq!(results.fragmentation_percent, 75.0);
+    }
+
+    #[test]
+    fn test_udp_frame_header_checksum() {
+        let frame = udp_frame([2, 0, 0, 0, 0, 1]);
+        assert_eq!(frame.len(), 1442);
+        assert_eq!(&frame[12..14], &[0x08, 0x00]);
+        assert_eq!(u16::from_be_bytes([frame[16], frame[17]]), 1428);
+        // A valid IPv4 header sums to 0xFFFF (ones' complement)
+        let sum = frame[14..34].chunks(2).fold(0u32, |s, w| s + u16::from_be_bytes([w[0], w[1]]) as u32);
+        assert_eq!((sum & 0xFFFF) + (sum >> 16), 0xFFFF);
+    }
+}
@@ -16,6 +16,8 @@ pub enum AppMode {
     Benchmark,
     /// Test harness
     TestHarness,
+    /// Play back a recorded match (no simulation)
+    Replay,
 }
 
 impl Default for AppMode {
@@ -26,23 +28,40 @@ impl Default for AppMode {
 
 impl AppMode {
     /// Parse from command line string
+    /// An explicit `mode=` value wins; otherwise the mode keywords match
+    /// case-insensitively as whole words outside quoted values.
     pub fn from_cmdline(cmdline: &str) -> Self {
-        let cmdline_lower = cmdline.to_lowercase_bytes();
-
-        if contains_bytes(&cmdline_lower, b"server") {
+        if let Some(mode) = find_value(cmdline, "mode=").and_then(Self::from_keyword) {
+            mode
+        } else if find_token(cmdline, "server").is_some() {
             Self::GameServer
-        } else if contains_bytes(&cmdline_lower, b"benchmark") {
+        } else if find_token(cmdline, "benchmark").is_some() {
             Self::Benchmark
-        } else if contains_bytes(&cmdline_lower, b"test") {
+        } else if find_token(cmdline, "replay").is_some() {
+            Self::Replay
+        } else if find_token(cmdline, "test").is_some() {
             Self::TestHarness
         } else {
             Self::GameClient
         }
     }
 
+    /// Mode named by a command line keyword (case-insensitive)
+    fn from_keyword(keyword: &str) -> Option<Self> {
+        [
+            ("server", Self::GameServer),
+            ("benchmark", Self::Benchmark),
+            ("replay", Self::Replay),
+            ("test", Self::TestHarness),
+        ]
+        .into_iter()
+        .find(|(name, _)| keyword.eq_ignore_ascii_case(name))
+        .map(|(_, mode)| mode)
+    }
+
     /// Whether this mode requires graphics
     pub fn needs_graphics(&self) -> bool {
-        matches!(self, Self::GameClient | Self::Benchmark)
+        matches!(self, Self::GameClient | Self::Benchmark | Self::Replay)
     }
 
     /// Whether this mode is headless (no rendering)
@@ -50,6 +69,29 @@ impl AppMode {
         matches!(self, Self::GameServer | Self::TestHarness)
     }
 
+    /// Stable numeric id used by `BootConfig::to_bytes`
+    pub fn to_u8(self) -> u8 {
+        match self {
+            Self::GameClient => 0,
+            Self::GameServer => 1,
+            Self::Benchmark => 2,
+            Self::TestHarness => 3,
+            Self::Replay => 4,
+        }
+    }
+
+    /// Inverse of `to_u8`
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::GameClient),
+            1 => Some(Self::GameServer),
+            2 => Some(Self::Benchmark),
+            3 => Some(Self::TestHarness),
+            4 => Some(Self::Replay),
+            _ => None,
+        }
+    }
+
     /// Get mode name
     pub fn name(&self) -> &'static str {
         match self {
@@ -57,18 +99,125 @@ impl AppMode {
             Self::GameServer => "Game Server",
             Self::Benchmark => "Benchmark",
             Self::TestHarness => "Test Harness",
+            Self::Replay => "Replay",
+        }
+    }
+}
+
+/// Debug visualization the renderer presents instead of the shaded scene
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RenderMode {
+    /// Shaded scene
+    #[default]
+    Normal,
+    /// Triangle edges only
+    Wireframe,
+    /// Heatmap of how many times each pixel was written
+    Overdraw,
+    /// Scene tinted per tile by the number of triangles binned to it
+    Tiles,
+}
+
+impl RenderMode {
+    /// Parse a `rendermode=` value
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "normal" => Some(Self::Normal),
+            "wireframe" => Some(Self::Wireframe),
+            "overdraw" => Some(Self::Overdraw),
+            "tiles" => Some(Self::Tiles),
+            _ => None,
+        }
+    }
+
+    /// Next mode when cycling at runtime (NORMAL -> WIREFRAME -> OVERDRAW -> TILES -> NORMAL)
+    pub fn next(self) -> Self {
+        match self {
+            Self::Normal => Self::Wireframe,
+            Self::Wireframe => Self::Overdraw,
+            Self::Overdraw => Self::Tiles,
+            Self::Tiles => Self::Normal,
+        }
+    }
+
+    /// Stable numeric id used by `BootConfig::to_bytes`
+    pub fn to_u8(self) -> u8 {
+        match self {
+            Self::Normal => 0,
+            Self::Wireframe => 1,
+            Self::Overdraw => 2,
+            Self::Tiles => 3,
+        }
+    }
+
+    /// Inverse of `to_u8`
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::Normal),
+            1 => Some(Self::Wireframe),
+            2 => Some(Self::Overdraw),
+            3 => Some(Self::Tiles),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Normal => "normal",
+            Self::Wireframe => "wireframe",
+            Self::Overdraw => "overdraw",
+            Self::Tiles => "tiles",
         }
     }
 }
 
+/// Size of the serialized `BootConfig`
+pub const BOOT_CONFIG_BYTES: usize = 32;
+
+/// Longest benchmark in seconds (about 18 hours, the most the serialized form holds)
+pub const MAX_BENCHMARK_DURATION: u32 = u16::MAX as u32;
+
+/// `BootConfig` flag bits (byte 1 of the serialized form)
+const FLAG_DEBUG: u8 = 1 << 0;
+const FLAG_SERVER_IP: u8 = 1 << 1;
+const FLAG_SERVER_IP6: u8 = 1 << 2;
+const FLAG_RECORD: u8 = 1 << 3;
+const FLAG_NETWORK_BENCHMARK: u8 = 1 << 4;
+const FLAG_MEMORY_BENCHMARK: u8 = 1 << 5;
+const FLAG_NO_CHECKSUM_OFFLOAD: u8 = 1 << 6;
+const FLAG_SQUADS: u8 = 1 << 7;
+
 /// Boot configuration parsed from command line
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct BootConfig {
     pub mode: AppMode,
     pub debug: bool,
+    /// Record the match and dump it over serial when it ends
+    pub record: bool,
     pub server_port: u16,
     pub server_ip: Option<[u8; 4]>,
+    pub server_ip6: Option<[u8; 16]>,
     pub benchmark_duration: u32,
+    /// Benchmark the NIC packet rate (`benchmark=network`) instead of only rendering
+    pub network_benchmark: bool,
+    /// Stress the heap allocator (`benchmark=memory`) instead of only rendering
+    pub memory_benchmark: bool,
+    /// Debug visualization to start in (`rendermode=`)
+    pub render_mode: RenderMode,
+    /// Let the NIC compute transmit checksums (off with `csum=off`)
+    pub checksum_offload: bool,
+    /// Group players into squads of four that share respawn tokens (`squads`)
+    pub squads: bool,
+    /// Present through a third buffer so rendering overlaps the copy to the
+    /// display (`buffers=3`)
+    pub triple_buffering: bool,
+    /// Display mode to switch to at boot instead of the bootloader's
+    /// (`res=WxH`); the GPU driver checks it against the device limits
+    pub resolution: Option<(u32, u32)>,
+    /// Name to join with (`name=Alice` or `name="My Server"`), as written on
+    /// the command line: quotes dropped but `\"` escapes still in it. Read it
+    /// through `player_name_chars` for the name itself.
+    pub player_name: Option<&'static str>,
     pub test_filter: Option<&'static str>,
 }
 
@@ -77,9 +226,19 @@ impl Default for BootConfig {
         Self {
             mode: AppMode::GameClient,
             debug: false,
+            record: false,
             server_port: 5000,
             server_ip: None,
+            server_ip6: None,
             benchmark_duration: 30,
+            network_benchmark: false,
+            memory_benchmark: false,
+            render_mode: RenderMode::Normal,
+            checksum_offload: true,
+            squads: false,
+            triple_buffering: false,
+            resolution: None,
+            player_name: None,
             test_filter: None,
         }
     }
@@ -87,16 +246,26 @@ impl Default for BootConfig {
 
 impl BootConfig {
     /// Parse boot configuration from command line
-    pub fn from_cmdline(cmdline: &str) -> Self {
+    pub fn from_cmdline(cmdline: &'static str) -> Self {
         let mut config = Self::default();
 
         config.mode = AppMode::from_cmdline(cmdline);
 
         // Check for debug flag
-        if cmdline.contains("debug") {
+        if find_token(cmdline, "debug").is_some() {
             config.debug = true;
         }
 
+        // Check for match recording flag
+        if find_token(cmdline, "record").is_some() {
+            config.record = true;
+        }
+
+        // Check for squad mode flag
+        if find_token(cmdline, "squads").is_some() {
+            config.squads = true;
+        }
+
         // Parse server port if specified (format: port=XXXX)
         if let Some(port_str) = find_value(cmdline, "port=") {
             if let Some(port) = parse_u16(port_str) {
@@ -109,62 +278,237 @@ impl BootConfig {
             config.server_ip = parse_ip(ip_str);
         }
 
+        // Parse IPv6 server address if specified (format: ip6=fe80::1)
+        // IPv4-mapped addresses (::ffff:X.X.X.X) go to the IPv4 field
+        if let Some(ip6_str) = find_value(cmdline, "ip6=") {
+            if let Some(addr) = parse_ipv6(ip6_str) {
+                match ipv4_mapped(&addr) {
+                    Some(v4) => config.server_ip = Some(v4),
+                    None => config.server_ip6 = Some(addr),
+                }
+            }
+        }
+
+        // Benchmark variant (format: benchmark=network or benchmark=memory)
+        match find_value(cmdline, "benchmark=") {
+            Some("network") => config.network_benchmark = true,
+            Some("memory") => config.memory_benchmark = true,
+            _ => {}
+        }
+
+        // Debug render mode (format: rendermode=wireframe|overdraw|tiles|normal)
+        if let Some(mode) = find_value(cmdline, "rendermode=").and_then(RenderMode::from_name) {
+            config.render_mode = mode;
+        }
+
+        // Software checksums for A/B against NIC offload (format: csum=off)
+        if find_value(cmdline, "csum=") == Some("off") {
+            config.checksum_offload = false;
+        }
+
+        // Display buffer count (format: buffers=3); anything else double buffers
+        if find_value(cmdline, "buffers=") == Some("3") {
+            config.triple_buffering = true;
+        }
+
+        // Display mode (format: res=1280x720)
+        if let Some(res_str) = find_value(cmdline, "res=") {
+            config.resolution = parse_resolution(res_str);
+        }
+
+        // Player name (format: name=Alice or name="My Server")
+        if let Some(name) = find_value(cmdline, "name=").filter(|name| !name.is_empty()) {
+            config.player_name = Some(name);
+        }
+
         // Parse benchmark duration (format: duration=XX)
         if let Some(dur_str) = find_value(cmdline, "duration=") {
             if let Some(dur) = parse_u32(dur_str) {
-                config.benchmark_duration = dur;
+                config.benchmark_duration = dur.min(MAX_BENCHMARK_DURATION);
             }
         }
 
         config
     }
-}
 
-/// Simple lowercase conversion for ASCII bytes
-trait ToLowercaseBytes {
-    fn to_lowercase_bytes(&self) -> [u8; 256];
-}
+    /// Characters of `player_name` with its escapes resolved
+    pub fn player_name_chars(&self) -> Option<impl Iterator<Item = char> + 'static> {
+        self.player_name.map(unescape)
+    }
+
+    /// Serialize to a fixed little-endian layout that stays valid across reboots
+    ///
+    /// | offset | size | field                                   |
+    /// |--------|------|-----------------------------------------|
+    /// | 0      | 1    | mode (`AppMode::to_u8`)                  |
+    /// | 1      | 1    | flags (debug, ip/ip6, record, benches, csum, squads) |
+    /// | 2      | 2    | server_port                             |
+    /// | 4      | 4    | server_ip                               |
+    /// | 8      | 2    | benchmark_duration                      |
+    /// | 10     | 2    | resolution width (0 for the bootloader's) |
+    /// | 12     | 16   | server_ip6                              |
+    /// | 28     | 1    | render_mode (`RenderMode::to_u8`)        |
+    /// | 29     | 1    | display buffers (3, or 0 for the default) |
+    /// | 30     | 2    | resolution height                       |
+    ///
+    /// The duration used to take 4 bytes; its upper half is zero for any
+    /// duration up to `MAX_BENCHMARK_DURATION`, so older layouts read back
+    /// the same. `player_name` and `test_filter` borrow the command line and
+    /// are not carried over.
+    pub fn to_bytes(&self) -> [u8; BOOT_CONFIG_BYTES] {
+        let mut bytes = [0u8; BOOT_CONFIG_BYTES];
+        let mut flags = 0;
+        if self.debug {
+            flags |= FLAG_DEBUG;
+        }
+        if self.record {
+            flags |= FLAG_RECORD;
+        }
+        if self.network_benchmark {
+            flags |= FLAG_NETWORK_BENCHMARK;
+        }
+        if self.memory_benchmark {
+            flags |= FLAG_MEMORY_BENCHMARK;
+        }
+        if !self.checksum_offload {
+            flags |= FLAG_NO_CHECKSUM_OFFLOAD;
+        }
+        if self.squads {
+            flags |= FLAG_SQUADS;
+        }
+        if let Some(ip) = self.server_ip {
+            flags |= FLAG_SERVER_IP;
+            bytes[4..8].copy_from_slice(&ip);
+        }
+        if let Some(ip6) = self.server_ip6 {
+            flags |= FLAG_SERVER_IP6;
+            bytes[12..28].copy_from_slice(&ip6);
+        }
+        bytes[0] = self.mode.to_u8();
+        bytes[1] = flags;
+        bytes[2..4].copy_from_slice(&self.server_port.to_le_bytes());
+        bytes[8..10].copy_from_slice(&(self.benchmark_duration.min(MAX_BENCHMARK_DURATION) as u16).to_le_bytes());
+        bytes[28] = self.render_mode.to_u8();
+        bytes[29] = if self.triple_buffering { 3 } else { 0 };
+        if let Some((width, height)) = self.resolution {
+            bytes[10..12].copy_from_slice(&(width as u16).to_le_bytes());
+            bytes[30..32].copy_from_slice(&(height as u16).to_le_bytes());
+        }
+        bytes
+    }
 
-impl ToLowercaseBytes for str {
-    fn to_lowercase_bytes(&self) -> [u8; 256] {
-        let mut result = [0u8; 256];
-        for (i, b) in self.bytes().take(255).enumerate() {
-            result[i] = if b >= b'A' && b <= b'Z' {
-                b + 32
-            } else {
-                b
-            };
+    /// Deserialize a layout written by `to_bytes`
+    /// Unknown mode bytes fall back to the default modes
+    pub fn from_bytes(bytes: &[u8; BOOT_CONFIG_BYTES]) -> Self {
+        let flags = bytes[1];
+        let mut ip = [0u8; 4];
+        ip.copy_from_slice(&bytes[4..8]);
+        let mut ip6 = [0u8; 16];
+        ip6.copy_from_slice(&bytes[12..28]);
+        let width = u16::from_le_bytes([bytes[10], bytes[11]]) as u32;
+        let height = u16::from_le_bytes([bytes[30], bytes[31]]) as u32;
+
+        Self {
+            mode: AppMode::from_u8(bytes[0]).unwrap_or_default(),
+            debug: flags & FLAG_DEBUG != 0,
+            record: flags & FLAG_RECORD != 0,
+            server_port: u16::from_le_bytes([bytes[2], bytes[3]]),
+            server_ip: (flags & FLAG_SERVER_IP != 0).then_some(ip),
+            server_ip6: (flags & FLAG_SERVER_IP6 != 0).then_some(ip6),
+            benchmark_duration: u16::from_le_bytes([bytes[8], bytes[9]]) as u32,
+            network_benchmark: flags & FLAG_NETWORK_BENCHMARK != 0,
+            memory_benchmark: flags & FLAG_MEMORY_BENCHMARK != 0,
+            render_mode: RenderMode::from_u8(bytes[28]).unwrap_or_default(),
+            checksum_offload: flags & FLAG_NO_CHECKSUM_OFFLOAD == 0,
+            squads: flags & FLAG_SQUADS != 0,
+            triple_buffering: bytes[29] == 3,
+            resolution: (width != 0 && height != 0).then_some((width, height)),
+            player_name: None,
+            test_filter: None,
         }
-        result
     }
 }
 
-/// Check if byte slice contains pattern
-fn contains_bytes(haystack: &[u8], needle: &[u8]) -> bool {
-    if needle.is_empty() {
-        return true;
-    }
-    if needle.len() > haystack.len() {
-        return false;
-    }
-    for i in 0..=(haystack.len() - needle.len()) {
-        if &haystack[i..i + needle.len()] == needle {
-            return true;
+/// Byte offset of the first case-insensitive match of `needle` at the start
+/// of a word outside quoted values
+///
+/// Walks the command line once without copying it, so there is no length
+/// limit. A word starts the line or follows whitespace, after any leading
+/// dashes (`--name=` is found by `name=`), so keys and keywords never match
+/// inside another key (`hostname=`) or a value (`name=Tester`). A keyword
+/// without `=` must also end the word or be followed by `=`. A `"` opens a
+/// quoted value that runs to the next unescaped `"`; inside it a backslash
+/// escapes the following byte.
+fn find_token(cmdline: &str, needle: &str) -> Option<usize> {
+    let bytes = cmdline.as_bytes();
+    let needle = needle.as_bytes();
+    let mut in_quotes = false;
+    let mut word_start = true;
+    let mut i = 0;
+    while i < bytes.len() {
+        let byte = bytes[i];
+        if in_quotes {
+            match byte {
+                b'\\' => i += 1,
+                b'"' => in_quotes = false,
+                _ => {}
+            }
+        } else if byte.is_ascii_whitespace() {
+            word_start = true;
+        } else if !(word_start && byte == b'-') {
+            if word_start && starts_word(&bytes[i..], needle) {
+                return Some(i);
+            }
+            word_start = false;
+            in_quotes = byte == b'"';
         }
+        i += 1;
     }
-    false
+    None
+}
+
+/// Whether `rest` starts with `needle` as a whole word (case-insensitive)
+fn starts_word(rest: &[u8], needle: &[u8]) -> bool {
+    rest.len() >= needle.len()
+        && rest[..needle.len()].eq_ignore_ascii_case(needle)
+        && (needle.ends_with(b"=") || rest.get(needle.len()).is_none_or(|&b| b == b'=' || b.is_ascii_whitespace()))
 }
 
 /// Find value after a key in command line
+///
+/// A plain value runs to the next space. A value starting with `"` runs to
+/// the closing quote and may hold spaces; the quotes are dropped but `\"`
+/// escapes are kept (see `unescape`). A quoted value that is never closed is
+/// treated as missing rather than swallowing the rest of the line.
 fn find_value<'a>(cmdline: &'a str, key: &str) -> Option<&'a str> {
-    if let Some(pos) = cmdline.find(key) {
-        let start = pos + key.len();
-        let remaining = &cmdline[start..];
+    let start = find_token(cmdline, key)? + key.len();
+    let remaining = &cmdline[start..];
+    let Some(quoted) = remaining.strip_prefix('"') else {
         let end = remaining.find(' ').unwrap_or(remaining.len());
-        Some(&remaining[..end])
-    } else {
-        None
+        return Some(&remaining[..end]);
+    };
+
+    let bytes = quoted.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 1,
+            b'"' => return Some(&quoted[..i]),
+            _ => {}
+        }
+        i += 1;
     }
+    None
+}
+
+/// Characters of a quoted command line value with its escapes resolved
+/// (`\"` becomes `"`, `\\` becomes `\`)
+pub fn unescape(value: &str) -> impl Iterator<Item = char> + '_ {
+    let mut chars = value.chars();
+    core::iter::from_fn(move || match chars.next()? {
+        '\\' => chars.next().or(Some('\\')),
+        c => Some(c),
+    })
 }
 
 /// Parse u16 from string
@@ -195,6 +539,19 @@ fn parse_u32(s: &str) -> Option<u32> {
     if result > 0 { Some(result) } else { None }
 }
 
+/// Parse a display mode from string (WxH format, e.g. 1280x720)
+/// Each side must be a non-zero number that fits in 16 bits.
+fn parse_resolution(s: &str) -> Option<(u32, u32)> {
+    let (width, height) = s.split_once(['x', 'X'])?;
+    let side = |part: &str| {
+        if part.is_empty() || !part.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        parse_u16(part).map(u32::from)
+    };
+    Some((side(width)?, side(height)?))
+}
+
 /// Parse IP address from string (X.X.X.X format)
 fn parse_ip(s: &str) -> Option<[u8; 4]> {
     let mut parts = [0u8; 4];
@@ -230,6 +587,82 @@ fn parse_ip(s: &str) -> Option<[u8; 4]> {
     }
 }
 
+/// Parse IPv6 address from string (RFC 5952 text form, `::` compression allowed)
+///
+/// The last 32 bits may be written as dotted-decimal IPv4 (`::ffff:10.0.2.15`).
+fn parse_ipv6(s: &str) -> Option<[u8; 16]> {
+    let mut words = [0u16; 8];
+
+    if let Some(pos) = s.find("::") {
+        let (head, tail) = (&s[..pos], &s[pos + 2..]);
+        if tail.contains("::") {
+            return None;
+        }
+        let mut head_words = [0u16; 8];
+        let mut tail_words = [0u16; 8];
+        let head_len = parse_ipv6_groups(head, false, &mut head_words)?;
+        let tail_len = parse_ipv6_groups(tail, true, &mut tail_words)?;
+        // "::" stands for at least one zero group
+        if head_len + tail_len > 7 {
+            return None;
+        }
+        words[..head_len].copy_from_slice(&head_words[..head_len]);
+        words[8 - tail_len..].copy_from_slice(&tail_words[..tail_len]);
+    } else if parse_ipv6_groups(s, true, &mut words)? != 8 {
+        return None;
+    }
+
+    let mut addr = [0u8; 16];
+    for (i, word) in words.iter().enumerate() {
+        addr[i * 2..i * 2 + 2].copy_from_slice(&word.to_be_bytes());
+    }
+    Some(addr)
+}
+
+/// Parse colon-separated hex groups into `out`, returning how many were written
+///
+/// An empty string yields no groups. With `v4_tail`, a dotted-decimal last
+/// group is accepted and fills two words.
+fn parse_ipv6_groups(s: &str, v4_tail: bool, out: &mut [u16; 8]) -> Option<usize> {
+    if s.is_empty() {
+        return Some(0);
+    }
+
+    let mut count = 0;
+    let mut groups = s.split(':').peekable();
+    while let Some(group) = groups.next() {
+        if v4_tail && groups.peek().is_none() && group.contains('.') {
+            if count + 2 > 8 || !group.bytes().all(|b| b.is_ascii_digit() || b == b'.') {
+                return None;
+            }
+            let v4 = parse_ip(group)?;
+            out[count] = u16::from_be_bytes([v4[0], v4[1]]);
+            out[count + 1] = u16::from_be_bytes([v4[2], v4[3]]);
+            count += 2;
+        } else {
+            if count >= 8
+                || group.is_empty()
+                || group.len() > 4
+                || !group.bytes().all(|b| b.is_ascii_hexdigit())
+            {
+                return None;
+            }
+            out[count] = u16::from_str_radix(group, 16).ok()?;
+            count += 1;
+        }
+    }
+    Some(count)
+}
+
+/// IPv4 address embedded in an IPv4-mapped IPv6 address (::ffff:X.X.X.X)
+fn ipv4_mapped(addr: &[u8; 16]) -> Option<[u8; 4]> {
+    if addr[..10].iter().all(|&b| b == 0) && addr[10] == 0xff && addr[11] == 0xff {
+        Some([addr[12], addr[13], addr[14], addr[15]])
+    } else {
+        None
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -241,6 +674,9 @@ mod tests {
         assert_eq!(AppMode::from_cmdline("--mode=SERVER"), AppMode::GameServer);
         assert_eq!(AppMode::from_cmdline("benchmark"), AppMode::Benchmark);
         assert_eq!(AppMode::from_cmdline("test"), AppMode::TestHarness);
+        assert_eq!(AppMode::from_cmdline("replay"), AppMode::Replay);
+        assert!(AppMode::Replay.needs_graphics());
+        assert!(!AppMode::Replay.is_headless());
     }
 
     #[test]
@@ -250,4 +686,219 @@ mod tests {
         assert_eq!(parse_ip("invalid"), None);
         assert_eq!(parse_ip("256.0.0.1"), None);
     }
+
+    #[test]
+    fn test_ipv6_parsing() {
+        let mut loopback = [0u8; 16];
+        loopback[15] = 1;
+        assert_eq!(parse_ipv6("::1"), Some(loopback));
+        assert_eq!(parse_ipv6("::"), Some([0u8; 16]));
+
+        let mut link_local = [0u8; 16];
+        link_local[0] = 0xfe;
+        link_local[1] = 0x80;
+        link_local[15] = 1;
+        assert_eq!(parse_ipv6("fe80::1"), Some(link_local));
+        assert_eq!(parse_ipv6("FE80::1"), Some(link_local));
+
+        assert_eq!(
+            parse_ipv6("2001:db8:0:0:1:0:0:1"),
+            Some([0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 1])
+        );
+        assert_eq!(parse_ipv6("2001:db8::1:0:0:1"), parse_ipv6("2001:db8:0:0:1:0:0:1"));
+        assert_eq!(
+            parse_ipv6("::ffff:10.0.2.15"),
+            Some([0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xff, 0xff, 10, 0, 2, 15])
+        );
+    }
+
+    #[test]
+    fn test_ipv6_rejects_invalid() {
+        assert_eq!(parse_ipv6(""), None);
+        assert_eq!(parse_ipv6("1:2:3:4:5:6:7"), None);
+        assert_eq!(parse_ipv6("1:2:3:4:5:6:7:8:9"), None);
+        assert_eq!(parse_ipv6("1:2:3:4::5:6:7:8"), None);
+        assert_eq!(parse_ipv6("1::2::3"), None);
+        assert_eq!(parse_ipv6("fe80::g1"), None);
+        assert_eq!(parse_ipv6("fe80::12345"), None);
+        assert_eq!(parse_ipv6("fe80::+1"), None);
+        assert_eq!(parse_ipv6(":::"), None);
+        assert_eq!(parse_ipv6("1:2:3:4:5:6:7:"), None);
+        assert_eq!(parse_ipv6("::ffff:10.0.2"), None);
+        assert_eq!(parse_ipv6("::10.0.2.15:1"), None);
+    }
+
+    #[test]
+    fn test_ip6_cmdline() {
+        let config = BootConfig::from_cmdline("client ip6=fe80::1 port=5000");
+        assert_eq!(config.server_ip, None);
+        assert_eq!(config.server_ip6.map(|a| a[0]), Some(0xfe));
+
+        let mapped = BootConfig::from_cmdline("ip6=::ffff:10.0.2.2");
+        assert_eq!(mapped.server_ip, Some([10, 0, 2, 2]));
+        assert_eq!(mapped.server_ip6, None);
+    }
+
+    #[test]
+    fn test_boot_config_bytes_roundtrip() {
+        let config = BootConfig::from_cmdline("benchmark debug record port=7777 ip=10.0.2.2 duration=90 ip6=fe80::1");
+        assert!(config.record);
+        let bytes = config.to_bytes();
+        assert_eq!(bytes[0], AppMode::Benchmark.to_u8());
+        assert_eq!(&bytes[2..4], &7777u16.to_le_bytes());
+        assert_eq!(&bytes[28..], &[0; 4]);
+        assert_eq!(BootConfig::from_bytes(&bytes), config);
+
+        let default = BootConfig::default();
+        assert_eq!(BootConfig::from_bytes(&default.to_bytes()), default);
+
+        let network = BootConfig::from_cmdline("benchmark=network duration=10");
+        assert_eq!(network.mode, AppMode::Benchmark);
+        assert!(network.network_benchmark && !config.network_benchmark);
+        assert_eq!(BootConfig::from_bytes(&network.to_bytes()), network);
+
+        let marathon = BootConfig::from_cmdline("benchmark duration=100000");
+        assert_eq!(marathon.benchmark_duration, MAX_BENCHMARK_DURATION);
+        assert_eq!(BootConfig::from_bytes(&marathon.to_bytes()), marathon);
+
+        let memory = BootConfig::from_cmdline("benchmark=memory");
+        assert!(memory.memory_benchmark && !memory.network_benchmark);
+        assert_eq!(memory.benchmark_duration, 30);
+        assert_eq!(BootConfig::from_bytes(&memory.to_bytes()), memory);
+
+        let overdraw = BootConfig::from_cmdline("rendermode=overdraw");
+        assert_eq!(overdraw.render_mode, RenderMode::Overdraw);
+        assert_eq!(overdraw.to_bytes()[28], 2);
+        assert_eq!(BootConfig::from_bytes(&overdraw.to_bytes()), overdraw);
+        assert_eq!(BootConfig::from_cmdline("rendermode=bogus").render_mode, RenderMode::Normal);
+
+        let software = BootConfig::from_cmdline("csum=off");
+        assert!(!software.checksum_offload && default.checksum_offload);
+        assert_eq!(BootConfig::from_bytes(&software.to_bytes()), software);
+
+        let squads = BootConfig::from_cmdline("server squads");
+        assert!(squads.squads && !default.squads);
+        assert_eq!(squads.to_bytes()[1], FLAG_SQUADS);
+        assert_eq!(BootConfig::from_bytes(&squads.to_bytes()), squads);
+
+        let triple = BootConfig::from_cmdline("benchmark buffers=3");
+        assert!(triple.triple_buffering && !default.triple_buffering);
+        assert!(!BootConfig::from_cmdline("buffers=2").triple_buffering);
+        assert_eq!(triple.to_bytes()[29], 3);
+        assert_eq!(BootConfig::from_bytes(&triple.to_bytes()), triple);
+
+        let wide = BootConfig::from_cmdline("res=1280x720 buffers=3");
+        assert_eq!(wide.resolution, Some((1280, 720)));
+        assert!(wide.triple_buffering && default.resolution.is_none());
+        assert_eq!(&wide.to_bytes()[10..12], &[0x00, 0x05]);
+        assert_eq!(&wide.to_bytes()[30..32], &[0xD0, 0x02]);
+        assert_eq!(BootConfig::from_bytes(&wide.to_bytes()), wide);
+    }
+
+    #[test]
+    fn test_resolution_parsing() {
+        assert_eq!(parse_resolution("1920x1080"), Some((1920, 1080)));
+        assert_eq!(parse_resolution("800X600"), Some((800, 600)));
+        assert_eq!(parse_resolution("1280"), None);
+        assert_eq!(parse_resolution("x720"), None);
+        assert_eq!(parse_resolution("1280x"), None);
+        assert_eq!(parse_resolution("0x720"), None);
+        assert_eq!(parse_resolution("1280x72O"), None);
+        assert_eq!(parse_resolution("70000x720"), None);
+        assert_eq!(BootConfig::from_cmdline("res=wide").resolution, None);
+    }
+
+    #[test]
+    fn test_long_cmdline() {
+        // Everything past the old 256-byte buffer used to be dropped
+        let cmdline = concat!(
+            "quiet ",
+            "console=ttyS0,115200 loglevel=7 earlyprintk=serial ",
+            "console=ttyS0,115200 loglevel=7 earlyprintk=serial ",
+            "console=ttyS0,115200 loglevel=7 earlyprintk=serial ",
+            "console=ttyS0,115200 loglevel=7 earlyprintk=serial ",
+            "console=ttyS0,115200 loglevel=7 earlyprintk=serial ",
+            "console=ttyS0,115200 loglevel=7 earlyprintk=serial ",
+            "SERVER port=7777 debug"
+        );
+        assert!(cmdline.len() > 300 && cmdline.find("SERVER").unwrap() > 256);
+        assert_eq!(AppMode::from_cmdline(cmdline), AppMode::GameServer);
+        let config = BootConfig::from_cmdline(cmdline);
+        assert_eq!(config.server_port, 7777);
+        assert!(config.debug);
+    }
+
+    #[test]
+    fn test_quoted_values() {
+        let config = BootConfig::from_cmdline("name=\"My Server\" port=6000");
+        assert_eq!(config.player_name, Some("My Server"));
+        assert_eq!(config.server_port, 6000);
+        // Keywords inside the quotes don't pick the mode or set flags
+        assert_eq!(config.mode, AppMode::GameClient);
+        assert!(!BootConfig::from_cmdline("name=\"debug record\"").debug);
+        assert_eq!(BootConfig::from_cmdline("name=\"port=1\" port=2").server_port, 2);
+
+        assert_eq!(BootConfig::from_cmdline("name=Alice benchmark").player_name, Some("Alice"));
+        assert_eq!(BootConfig::from_cmdline("name=\"\"").player_name, None);
+        assert_eq!(BootConfig::default().player_name, None);
+
+        // Not serialized
+        assert_eq!(BootConfig::from_bytes(&config.to_bytes()).player_name, None);
+    }
+
+    #[test]
+    fn test_quoted_escapes() {
+        let config = BootConfig::from_cmdline(r#"name="The \"Best\" \\o/" server"#);
+        assert_eq!(config.player_name, Some(r#"The \"Best\" \\o/"#));
+        assert!(config.player_name_chars().unwrap().eq(r#"The "Best" \o/"#.chars()));
+        assert_eq!(config.mode, AppMode::GameServer);
+        assert!(unescape("trailing\\").eq("trailing\\".chars()));
+    }
+
+    #[test]
+    fn test_keys_match_whole_words() {
+        // A key inside a longer key, or a keyword inside a value, doesn't count
+        assert_eq!(BootConfig::from_cmdline("hostname=box").player_name, None);
+        assert_eq!(BootConfig::from_cmdline("hostname=box name=Alice").player_name, Some("Alice"));
+        assert_eq!(BootConfig::from_cmdline("sport=1 port=2").server_port, 2);
+        let tester = BootConfig::from_cmdline("name=Tester");
+        assert_eq!(tester.mode, AppMode::GameClient);
+        assert_eq!(tester.player_name, Some("Tester"));
+        assert!(!BootConfig::from_cmdline("name=Debugger").debug);
+        assert_eq!(AppMode::from_cmdline("name=server"), AppMode::GameClient);
+
+        // Keywords are whole words, dashed keys still work
+        assert_eq!(AppMode::from_cmdline("tests"), AppMode::GameClient);
+        assert!(!BootConfig::from_cmdline("debugger").debug);
+        assert!(BootConfig::from_cmdline("quiet --debug").debug);
+        assert_eq!(BootConfig::from_cmdline("--name=\"My Very Long Player Name\"").player_name, Some("My Very Long Player Name"));
+        assert_eq!(AppMode::from_cmdline("mode=replay server"), AppMode::Replay);
+    }
+
+    #[test]
+    fn test_unclosed_quotes() {
+        // An unclosed quote hides the rest of the line and leaves the name unset
+        let config = BootConfig::from_cmdline("port=6000 name=\"My Server debug");
+        assert_eq!(config.player_name, None);
+        assert_eq!(config.server_port, 6000);
+        assert!(!config.debug);
+
+        // An escaped closing quote doesn't close the value
+        assert_eq!(BootConfig::from_cmdline(r#"name="Bob\""#).player_name, None);
+        assert_eq!(find_value("name=\"", "name="), None);
+    }
+
+    #[test]
+    fn test_boot_config_bytes_absent_addresses() {
+        let config = BootConfig::from_cmdline("server");
+        let decoded = BootConfig::from_bytes(&config.to_bytes());
+        assert_eq!(decoded.mode, AppMode::GameServer);
+        assert_eq!(decoded.server_ip, None);
+        assert_eq!(decoded.server_ip6, None);
+
+        // Unknown mode byte from a newer kernel
+        let mut bytes = config.to_bytes();
+        bytes[0] = 0xEE;
+        assert_eq!(BootConfig::from_bytes(&bytes).mode, AppMode::GameClient);
+    }
 }
@@ -14,7 +14,7 @@ impl TimeService {
     /// Create a new time service
     pub fn new() -> KernelResult<Self> {
         Ok(Self {
-            tsc_frequency: 2_000_000_000, // Assume ~2GHz for QEMU
+            tsc_frequency: crate::drivers::pit::tsc_per_second(),
             start_tsc: read_tsc(),
         })
     }
@@ -57,7 +57,7 @@ impl TimeService {
 impl Default for TimeService {
     fn default() -> Self {
         Self {
-            tsc_frequency: 2_000_000_000,
+            tsc_frequency: crate::drivers::pit::tsc_per_second(),
             start_tsc: 0,
         }
     }
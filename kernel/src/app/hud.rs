@@ -5,12 +5,28 @@
 extern crate alloc;
 
 use alloc::format;
-use crate::game::inventory::{Inventory, Materials};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, Ordering};
+use glam::{Mat4, Vec3};
+use renderer::mesh::Mesh;
+use spin::Mutex;
+use crate::game::chat::CHAT_LOG;
+use crate::game::combat;
+use crate::game::inventory::{Inventory, InventoryItem, Materials};
+use crate::game::loot::{LootItem, LootSpawnType};
+use crate::game::map::{MAP_HALF, MAP_SIZE};
+use crate::game::party::REVIVE_WINDOW;
+use crate::game::state::{MatchEvent, MATCH_EVENTS};
 use crate::game::storm::Storm;
 use crate::game::weapon;
 use crate::game::world::GameWorld;
 use crate::graphics::font;
-use crate::graphics::framebuffer::{rgb, Framebuffer, FRAMEBUFFER};
+use crate::graphics::framebuffer::{lerp_color, mark_dirty, rgb, DirtyRect, Framebuffer, FRAMEBUFFER};
+use crate::graphics::offscreen::OffscreenTarget;
+use crate::graphics::pipeline::{look_at, project_point, CullMode};
+use crate::graphics::ui::{colors, Rect};
+use crate::graphics::ui::panel::{draw_circle_dashed_clipped, draw_circle_outline_clipped, draw_panel_blended, PanelBorder};
 
 /// Draw storm overlay effect when player is in storm
 pub fn draw_storm_overlay(fb_width: usize, fb_height: usize) {
@@ -21,6 +37,8 @@ pub fn draw_storm_overlay(fb_width: usize, fb_height: usize) {
             let edge_width = 30;
             // Use pitch for correct row stride
             let pitch = fb.pitch / 4;
+            mark_dirty(DirtyRect::new(0, 0, fb_width, edge_width));
+            mark_dirty(DirtyRect::new(0, fb_height - edge_width, fb_width, edge_width));
 
             // Top edge
             for y in 0..edge_width {
@@ -28,7 +46,7 @@ pub fn draw_storm_overlay(fb_width: usize, fb_height: usize) {
                 for x in 0..fb_width {
                     let idx = y * pitch + x;
                     let existing = fb.pixel_at(idx);
-                    let blended = blend_color(existing, purple, alpha * 0.5);
+                    let blended = lerp_color(existing, purple, alpha * 0.5);
                     fb.set_pixel_at(idx, blended);
                 }
             }
@@ -39,7 +57,7 @@ pub fn draw_storm_overlay(fb_width: usize, fb_height: usize) {
                 for x in 0..fb_width {
                     let idx = y * pitch + x;
                     let existing = fb.pixel_at(idx);
-                    let blended = blend_color(existing, purple, alpha * 0.5);
+                    let blended = lerp_color(existing, purple, alpha * 0.5);
                     fb.set_pixel_at(idx, blended);
                 }
             }
@@ -47,32 +65,20 @@ pub fn draw_storm_overlay(fb_width: usize, fb_height: usize) {
     }
 }
 
-/// Blend two colors
-pub fn blend_color(base: u32, overlay: u32, alpha: f32) -> u32 {
-    let br = ((base >> 16) & 0xFF) as f32;
-    let bg = ((base >> 8) & 0xFF) as f32;
-    let bb = (base & 0xFF) as f32;
-
-    let or = ((overlay >> 16) & 0xFF) as f32;
-    let og = ((overlay >> 8) & 0xFF) as f32;
-    let ob = (overlay & 0xFF) as f32;
-
-    let r = (br * (1.0 - alpha) + or * alpha) as u32;
-    let g = (bg * (1.0 - alpha) + og * alpha) as u32;
-    let b = (bb * (1.0 - alpha) + ob * alpha) as u32;
-
-    (r << 16) | (g << 8) | b
-}
+/// Hotbar slot size in pixels
+const HOTBAR_SLOT_SIZE: usize = 50;
+/// Gap between the hotbar and the bottom of the screen (room for the health bar)
+const HOTBAR_BOTTOM_MARGIN: usize = 80;
 
 /// Draw inventory hotbar
 pub fn draw_inventory_hotbar(inv: &Inventory, fb_width: usize, fb_height: usize) {
     if let Some(fb_guard) = FRAMEBUFFER.try_lock() {
         if let Some(fb) = fb_guard.as_ref() {
-            let slot_size = 50;
+            let slot_size = HOTBAR_SLOT_SIZE;
             let slot_spacing = 5;
             let total_width = 6 * slot_size + 5 * slot_spacing; // 6 slots (pickaxe + 5 weapons)
             let start_x = (fb_width - total_width) / 2;
-            let start_y = fb_height - slot_size - 80; // Above health bar
+            let start_y = fb_height - slot_size - HOTBAR_BOTTOM_MARGIN; // Above health bar
 
             // Draw pickaxe slot
             let is_selected = inv.pickaxe_selected;
@@ -91,36 +97,41 @@ pub fn draw_inventory_hotbar(inv: &Inventory, fb_width: usize, fb_height: usize)
                 draw_slot(fb, x, start_y, slot_size, bg_color, border_color);
 
                 // Draw weapon info if slot is filled
-                if let Some(weapon) = &inv.slots[i] {
-                    let rarity_color = match weapon.rarity {
-                        weapon::Rarity::Common => rgb(150, 150, 150),
-                        weapon::Rarity::Uncommon => rgb(50, 200, 50),
-                        weapon::Rarity::Rare => rgb(50, 100, 255),
-                        weapon::Rarity::Epic => rgb(200, 50, 200),
-                        weapon::Rarity::Legendary => rgb(255, 180, 0),
-                    };
-
-                    // Draw rarity indicator bar at bottom of slot
-                    for dy in (slot_size - 5)..slot_size {
-                        for dx in 2..(slot_size - 2) {
-                            fb.set_pixel(x + dx, start_y + dy, rarity_color);
-                        }
+                match &inv.slots[i] {
+                    Some(InventoryItem::Weapon(weapon)) => {
+                        let rarity_color = match weapon.rarity {
+                            weapon::Rarity::Common => rgb(150, 150, 150),
+                            weapon::Rarity::Uncommon => rgb(50, 200, 50),
+                            weapon::Rarity::Rare => rgb(50, 100, 255),
+                            weapon::Rarity::Epic => rgb(200, 50, 200),
+                            weapon::Rarity::Legendary => rgb(255, 180, 0),
+                        };
+                        draw_slot_bar(fb, x, start_y, slot_size, rarity_color);
+
+                        // Draw weapon type letter
+                        let letter = match weapon.weapon_type {
+                            weapon::WeaponType::Pistol => "Pi",
+                            weapon::WeaponType::Shotgun => "SG",
+                            weapon::WeaponType::AssaultRifle => "AR",
+                            weapon::WeaponType::Smg => "SM",
+                            weapon::WeaponType::Sniper => "SR",
+                            weapon::WeaponType::Pickaxe => "PX",
+                        };
+                        font::draw_string_raw(fb, x + 10, start_y + 15, letter, rgb(255, 255, 255), 1);
+
+                        // Draw ammo count
+                        let ammo_str = format!("{}", weapon.ammo);
+                        font::draw_string_raw(fb, x + 15, start_y + 32, &ammo_str, rgb(200, 200, 200), 1);
                     }
+                    Some(InventoryItem::Consumable { kind, count, .. }) => {
+                        draw_slot_bar(fb, x, start_y, slot_size, LootItem::from_consumable(*kind).rarity_color());
+                        font::draw_string_raw(fb, x + 10, start_y + 15, kind.short_name(), rgb(255, 255, 255), 1);
 
-                    // Draw weapon type letter
-                    let letter = match weapon.weapon_type {
-                        weapon::WeaponType::Pistol => "Pi",
-                        weapon::WeaponType::Shotgun => "SG",
-                        weapon::WeaponType::AssaultRifle => "AR",
-                        weapon::WeaponType::Smg => "SM",
-                        weapon::WeaponType::Sniper => "SR",
-                        weapon::WeaponType::Pickaxe => "PX",
-                    };
-                    font::draw_string_raw(fb, x + 10, start_y + 15, letter, rgb(255, 255, 255), 1);
-
-                    // Draw ammo count
-                    let ammo_str = format!("{}", weapon.ammo);
-                    font::draw_string_raw(fb, x + 15, start_y + 32, &ammo_str, rgb(200, 200, 200), 1);
+                        // Draw stack size
+                        let count_str = format!("x{}", count);
+                        font::draw_string_raw(fb, x + 15, start_y + 32, &count_str, rgb(200, 200, 200), 1);
+                    }
+                    None => {}
                 }
 
                 // Draw slot number
@@ -131,49 +142,99 @@ pub fn draw_inventory_hotbar(inv: &Inventory, fb_width: usize, fb_height: usize)
     }
 }
 
-/// Draw a UI slot/box
-pub fn draw_slot(fb: &Framebuffer, x: usize, y: usize, size: usize, bg: u32, border: u32) {
-    // Background
-    for dy in 0..size {
-        for dx in 0..size {
-            fb.set_pixel(x + dx, y + dy, bg);
+/// Draw the colored indicator bar along the bottom of a hotbar slot
+fn draw_slot_bar(fb: &Framebuffer, x: usize, y: usize, size: usize, color: u32) {
+    for dy in (size - 5)..size {
+        for dx in 2..(size - 2) {
+            fb.set_pixel(x + dx, y + dy, color);
         }
     }
-    // Border
-    for dx in 0..size {
-        fb.set_pixel(x + dx, y, border);
-        fb.set_pixel(x + dx, y + size - 1, border);
-    }
-    for dy in 0..size {
-        fb.set_pixel(x, y + dy, border);
-        fb.set_pixel(x + size - 1, y + dy, border);
-    }
+}
+
+/// Opacity of hotbar slot backgrounds
+const HOTBAR_SLOT_ALPHA: f32 = 0.55;
+
+/// Draw a UI slot/box: a translucent rounded panel, outlined in `border`
+pub fn draw_slot(fb: &Framebuffer, x: usize, y: usize, size: usize, bg: u32, border: u32) {
+    draw_panel_blended(fb, Rect::new(x, y, size, size), bg, HOTBAR_SLOT_ALPHA, 4, PanelBorder::new(border, 1).with_shadow(1));
 }
 
 /// Draw materials HUD
-pub fn draw_materials_hud(materials: &Materials, fb_width: usize, fb_height: usize) {
+pub fn draw_materials_hud(materials: &Materials, fb_width: usize, fb_height: usize, scale: u8) {
     if let Some(fb_guard) = FRAMEBUFFER.try_lock() {
         if let Some(fb) = fb_guard.as_ref() {
-            let x = fb_width - 150;
-            let y = fb_height - 100;
+            let scale = (scale as usize).max(1);
+            let line_height = font::char_height(scale) + 4 * scale;
+            let x = fb_width.saturating_sub(font::string_width("W: 999", scale) + fb_width / 50);
+            let y = fb_height.saturating_sub(line_height * 3 + fb_height / 20);
 
             // Wood
             let wood_str = format!("W: {}", materials.wood);
-            font::draw_string_raw(fb, x, y, &wood_str, rgb(180, 120, 60), 1);
+            font::draw_string_raw(fb, x, y, &wood_str, rgb(180, 120, 60), scale);
 
             // Brick
             let brick_str = format!("B: {}", materials.brick);
-            font::draw_string_raw(fb, x, y + 20, &brick_str, rgb(180, 80, 80), 1);
+            font::draw_string_raw(fb, x, y + line_height, &brick_str, rgb(180, 80, 80), scale);
 
             // Metal
             let metal_str = format!("M: {}", materials.metal);
-            font::draw_string_raw(fb, x, y + 40, &metal_str, rgb(150, 150, 170), 1);
+            font::draw_string_raw(fb, x, y + line_height * 2, &metal_str, rgb(150, 150, 170), scale);
         }
     }
 }
 
+/// Draw squadmates' health stacked above the health/shield panel in the bottom-left
+/// Eliminated teammates show the seconds left to revive them, or OUT once they can't be
+pub fn draw_squad_hud(world: &GameWorld, local_player_id: Option<u8>, fb_width: usize, fb_height: usize, scale: u8) {
+    let Some(local) = local_player_id.and_then(|id| world.get_player(id)) else {
+        return;
+    };
+    let Some(squad) = local.squad_id.and_then(|s| world.squads.get(s as usize)) else {
+        return;
+    };
+
+    let fb_guard = FRAMEBUFFER.lock();
+    let fb = match fb_guard.as_ref() {
+        Some(f) => f,
+        None => return,
+    };
+
+    // Same layout as font::draw_hud, whose panel is three lines plus padding tall
+    let scale = (scale as usize).max(1);
+    let line_height = font::char_height(scale) + 4 * scale;
+    let padding = fb_width / 100;
+    let panel_top = fb_height.saturating_sub(padding * 2 + line_height * 3);
+    let name_width = font::string_width("WWWWWWWWWWWW ", scale);
+
+    let teammates: Vec<_> = world.teammates(local.id).collect();
+    let mut y = panel_top.saturating_sub(padding + line_height * (teammates.len() + 1));
+
+    let tokens = format!("SQUAD  REVIVES: {}", squad.respawn_tokens);
+    font::draw_string_raw(fb, padding, y, &tokens, colors::SUBTITLE, scale);
+
+    for mate in teammates {
+        y += line_height;
+        let name: String = mate.name.chars().take(12).collect();
+        font::draw_string_raw(fb, padding, y, &name, colors::WHITE, scale);
+
+        let (value, color) = if mate.is_alive() {
+            let color = match mate.health {
+                51.. => colors::HEALTH_HIGH,
+                26..=50 => colors::HEALTH_MED,
+                _ => colors::HEALTH_LOW,
+            };
+            (format!("{}", mate.health), color)
+        } else if squad.respawn_tokens > 0 && mate.eliminated_timer <= REVIVE_WINDOW {
+            (format!("DOWN {:.0}s", REVIVE_WINDOW - mate.eliminated_timer), colors::FN_YELLOW)
+        } else {
+            (String::from("OUT"), colors::NOT_READY)
+        };
+        font::draw_string_raw(fb, padding + name_width, y, &value, color, scale);
+    }
+}
+
 /// Draw storm timer
-pub fn draw_storm_timer(storm: &Storm, fb_width: usize, _fb_height: usize) {
+pub fn draw_storm_timer(storm: &Storm, _fb_width: usize, fb_height: usize, scale: u8) {
     if let Some(fb_guard) = FRAMEBUFFER.try_lock() {
         if let Some(fb) = fb_guard.as_ref() {
             let phase_str = if storm.shrinking {
@@ -182,86 +243,346 @@ pub fn draw_storm_timer(storm: &Storm, fb_width: usize, _fb_height: usize) {
                 format!("SAFE ZONE: {:.0}s", storm.timer)
             };
 
-            let x = (fb_width - phase_str.len() * 8) / 2;
+            let y = fb_height / 16;
             let color = if storm.shrinking { rgb(200, 50, 200) } else { rgb(255, 255, 255) };
-            font::draw_string_raw(fb, x, 50, &phase_str, color, 1);
+            font::draw_string_centered(fb, y, &phase_str, color, scale.max(1));
         }
     }
 }
 
-/// Draw minimap
-pub fn draw_minimap(local_player_id: Option<u8>, world: &GameWorld, fb_width: usize, _fb_height: usize) {
+/// Side of the minimap's top-down map texture (scaled to the minimap when drawn)
+const MINIMAP_TEXTURE_SIZE: usize = 256;
+
+/// Height of the minimap's top-down camera above the map
+const MINIMAP_EYE_HEIGHT: f32 = 1000.0;
+
+/// Minimap background before its texture is rendered
+const MINIMAP_BACKGROUND: u32 = rgb(20, 40, 20);
+/// Width of the translucent frame around the minimap
+const MINIMAP_FRAME: usize = 4;
+
+/// Orthographic top-down render of the terrain and map buildings
+static MINIMAP_TEXTURE: Mutex<Option<OffscreenTarget>> = Mutex::new(None);
+
+/// Render the minimap texture once (later calls return immediately)
+/// Covers the same 2000-unit square centered on the origin as `draw_minimap`
+pub fn ensure_minimap_texture(world: &GameWorld, terrain: &Mesh, house_mesh: &Mesh) {
+    let mut texture = MINIMAP_TEXTURE.lock();
+    if texture.is_some() {
+        return;
+    }
+
+    // Looking straight down with -Z up, so screen x/y follow world x/z like the minimap
+    let view = look_at(Vec3::new(0.0, MINIMAP_EYE_HEIGHT, 0.0), Vec3::ZERO, Vec3::NEG_Z);
+    let projection = Mat4::orthographic_rh(-1000.0, 1000.0, -1000.0, 1000.0, 1.0, MINIMAP_EYE_HEIGHT * 2.0);
+    let view_projection = projection * view;
+
+    let mut target = OffscreenTarget::new(MINIMAP_TEXTURE_SIZE, MINIMAP_TEXTURE_SIZE);
+    target.clear(MINIMAP_BACKGROUND);
+
+    // Orthographic depth (1/w) is the same everywhere, so the first triangle drawn
+    // keeps a pixel: roofs go before the terrain under them
+    for building in world.map.buildings[..world.map.building_count].iter().flatten() {
+        let model = Mat4::from_translation(building.position)
+            * Mat4::from_rotation_y(building.rotation)
+            * Mat4::from_scale(Vec3::splat(1.5));
+        target.draw_mesh(house_mesh, &model, &view_projection, CullMode::Back);
+    }
+    target.draw_mesh(terrain, &Mat4::IDENTITY, &view_projection, CullMode::Back);
+
+    *texture = Some(target);
+}
+
+/// Whether the map is shown fullscreen instead of in the corner
+static FULLSCREEN_MAP: AtomicBool = AtomicBool::new(false);
+
+/// Switch between the corner minimap and the fullscreen map (M key)
+pub fn toggle_fullscreen_map() {
+    FULLSCREEN_MAP.fetch_xor(true, Ordering::Relaxed);
+}
+
+/// Draw minimap, or the fullscreen map when toggled on
+/// Both scale the same cached top-down texture
+pub fn draw_minimap(local_player_id: Option<u8>, world: &GameWorld, fb_width: usize, fb_height: usize) {
     if let Some(fb_guard) = FRAMEBUFFER.try_lock() {
         if let Some(fb) = fb_guard.as_ref() {
-            let map_size = 150;
-            let map_x = fb_width - map_size - 20;
-            let map_y = 20;
-
-            // Draw map background
-            for dy in 0..map_size {
-                for dx in 0..map_size {
-                    fb.set_pixel(map_x + dx, map_y + dy, rgb(20, 40, 20));
+            let (map_x, map_y, map_size) = if FULLSCREEN_MAP.load(Ordering::Relaxed) {
+                // Fullscreen map fills most of the screen height, centered
+                let size = (fb_height * 9 / 10).min(fb_width).max(16);
+                ((fb_width - size) / 2, (fb_height - size) / 2, size)
+            } else {
+                // Minimap is a fifth of the screen height, inset from the top-right corner
+                let size = (fb_height / 5).max(16);
+                (fb_width.saturating_sub(size + fb_width / 50), fb_height / 40, size)
+            };
+            mark_dirty(DirtyRect::new(map_x, map_y, map_size, map_size));
+
+            // Draw map background: a translucent frame, then the top-down map texture scaled into it
+            let frame = Rect::new(
+                map_x.saturating_sub(MINIMAP_FRAME),
+                map_y.saturating_sub(MINIMAP_FRAME),
+                map_size + 2 * MINIMAP_FRAME,
+                map_size + 2 * MINIMAP_FRAME,
+            );
+            let border = PanelBorder::new(rgb(100, 100, 100), 1).with_shadow(2);
+            draw_panel_blended(fb, frame, MINIMAP_BACKGROUND, 0.6, 6, border);
+            if let Some(t) = MINIMAP_TEXTURE.lock().as_ref() {
+                for dy in 0..map_size {
+                    for dx in 0..map_size {
+                        fb.set_pixel(map_x + dx, map_y + dy, t.pixel(dx * t.width / map_size, dy * t.height / map_size));
+                    }
                 }
             }
 
-            // Draw map border
-            for dx in 0..map_size {
-                fb.set_pixel(map_x + dx, map_y, rgb(100, 100, 100));
-                fb.set_pixel(map_x + dx, map_y + map_size - 1, rgb(100, 100, 100));
-            }
-            for dy in 0..map_size {
-                fb.set_pixel(map_x, map_y + dy, rgb(100, 100, 100));
-                fb.set_pixel(map_x + map_size - 1, map_y + dy, rgb(100, 100, 100));
-            }
+            // Scale: map is 2000 units, minimap is map_size pixels
+            let scale = map_size as f32 / MAP_SIZE;
+            let to_map = |x: f32, z: f32| {
+                (map_x as f32 + (x + MAP_HALF) * scale, map_y as f32 + (z + MAP_HALF) * scale)
+            };
+            let clip = (map_x, map_y, map_x + map_size, map_y + map_size);
 
-            // Scale: map is 2000 units, minimap is 150 pixels
-            let scale = map_size as f32 / 2000.0;
-            let offset = 1000.0; // Center offset
-
-            // Draw storm circle
-            let storm_cx = ((world.storm.center.x + offset) * scale) as i32;
-            let storm_cz = ((world.storm.center.z + offset) * scale) as i32;
-            let storm_r = (world.storm.radius * scale) as i32;
-
-            // Draw circle outline (simplified)
-            for angle in 0..64 {
-                let a = (angle as f32 / 64.0) * core::f32::consts::TAU;
-                let px = storm_cx + (libm::cosf(a) * storm_r as f32) as i32;
-                let py = storm_cz + (libm::sinf(a) * storm_r as f32) as i32;
-                if px >= 0 && px < map_size as i32 && py >= 0 && py < map_size as i32 {
-                    fb.set_pixel(map_x + px as usize, map_y + py as usize, rgb(255, 255, 255));
+            // Building and chest markers, a little larger on the fullscreen map
+            let marker = (map_size / MINIMAP_MARKER_DIVISOR).max(1) as i32;
+            for building in world.map.buildings[..world.map.building_count].iter().flatten() {
+                let (x, y) = to_map(building.position.x, building.position.z);
+                fill_square_clipped(fb, x as i32, y as i32, marker, rgb(120, 90, 60), clip);
+            }
+            for spawn in world.map.loot_spawns[..world.map.loot_spawn_count].iter().flatten() {
+                if let LootSpawnType::Chest(_) = spawn.spawn_type {
+                    let (x, y) = to_map(spawn.position.x, spawn.position.z);
+                    fill_square_clipped(fb, x as i32, y as i32, (marker / 2).max(1), rgb(255, 200, 0), clip);
                 }
             }
 
-            // Draw player positions
+            // Draw the current storm circle, then the next safe zone dashed inside it
+            let storm = &world.storm;
+            let (storm_x, storm_y) = to_map(storm.center.x, storm.center.z);
+            let storm_r = (storm.radius * scale) as i32;
+            draw_circle_outline_clipped(fb, storm_x as i32, storm_y as i32, storm_r, rgb(200, 50, 200), clip);
+            let (target_x, target_y) = to_map(storm.target_center.x, storm.target_center.z);
+            let target_r = (storm.target_radius * scale) as i32;
+            draw_circle_dashed_clipped(fb, target_x as i32, target_y as i32, target_r, 3, rgb(255, 255, 255), clip);
+
+            // Draw player positions: a dot for others, a wedge facing the view direction for us
+            let wedge = (map_size as f32 / MINIMAP_WEDGE_DIVISOR).max(4.0);
             for player in &world.players {
                 if !player.is_alive() {
                     continue;
                 }
-                let px = ((player.position.x + offset) * scale) as usize;
-                let py = ((player.position.z + offset) * scale) as usize;
-
-                if px < map_size && py < map_size {
-                    let color = if Some(player.id) == local_player_id {
-                        rgb(0, 255, 0) // Green for local player
-                    } else {
-                        rgb(255, 0, 0) // Red for others
-                    };
-
-                    // Draw 3x3 dot
-                    for dx in 0..3 {
-                        for dy in 0..3 {
-                            if px + dx < map_size && py + dy < map_size {
-                                fb.set_pixel(map_x + px + dx, map_y + py + dy, color);
-                            }
-                        }
-                    }
+                let (x, y) = to_map(player.position.x, player.position.z);
+
+                if Some(player.id) == local_player_id {
+                    // World x/z run along screen x/y, so the facing maps straight across
+                    let (dir_x, dir_y) = (libm::sinf(player.yaw), libm::cosf(player.yaw));
+                    let tip = (x + dir_x * wedge, y + dir_y * wedge);
+                    let back = (x - dir_x * wedge * 0.4, y - dir_y * wedge * 0.4);
+                    let side = (-dir_y * wedge * 0.5, dir_x * wedge * 0.5);
+                    let left = (back.0 + side.0, back.1 + side.1);
+                    let right = (back.0 - side.0, back.1 - side.1);
+                    fill_triangle_clipped(fb, [tip, left, right], rgb(0, 255, 0), clip);
+                } else {
+                    fill_square_clipped(fb, x as i32, y as i32, 1, rgb(255, 0, 0), clip);
                 }
             }
         }
     }
 }
 
+/// Map pixels per building marker half-width
+const MINIMAP_MARKER_DIVISOR: usize = 100;
+/// Map pixels per unit of the local player's wedge length
+const MINIMAP_WEDGE_DIVISOR: f32 = 25.0;
+
+/// Fill a (2 * half + 1)-pixel square centered on (cx, cy), clipped to `clip`
+fn fill_square_clipped(fb: &Framebuffer, cx: i32, cy: i32, half: i32, color: u32, (x0, y0, x1, y1): (usize, usize, usize, usize)) {
+    for y in (cy - half).max(y0 as i32)..(cy + half + 1).min(y1 as i32) {
+        for x in (cx - half).max(x0 as i32)..(cx + half + 1).min(x1 as i32) {
+            fb.set_pixel(x as usize, y as usize, color);
+        }
+    }
+}
+
+/// Fill a triangle by testing pixel centers in its bounding box, clipped to `clip`
+fn fill_triangle_clipped(fb: &Framebuffer, [a, b, c]: [(f32, f32); 3], color: u32, (x0, y0, x1, y1): (usize, usize, usize, usize)) {
+    let edge = |p: (f32, f32), q: (f32, f32), x: f32, y: f32| (q.0 - p.0) * (y - p.1) - (q.1 - p.1) * (x - p.0);
+    let min_x = (a.0.min(b.0).min(c.0) as i32).max(x0 as i32);
+    let max_x = (a.0.max(b.0).max(c.0) as i32 + 1).min(x1 as i32);
+    let min_y = (a.1.min(b.1).min(c.1) as i32).max(y0 as i32);
+    let max_y = (a.1.max(b.1).max(c.1) as i32 + 1).min(y1 as i32);
+
+    for y in min_y..max_y {
+        for x in min_x..max_x {
+            let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+            let (e0, e1, e2) = (edge(a, b, px, py), edge(b, c, px, py), edge(c, a, px, py));
+            // Inside when all edges agree, whichever way the triangle winds
+            if (e0 >= 0.0 && e1 >= 0.0 && e2 >= 0.0) || (e0 <= 0.0 && e1 <= 0.0 && e2 <= 0.0) {
+                fb.set_pixel(x as usize, y as usize, color);
+            }
+        }
+    }
+}
+
+/// Draw recent chat lines stacked above the hotbar, newest at the bottom
+/// Server announcements are yellow, player messages white
+pub fn draw_chat(fb_width: usize, fb_height: usize, scale: u8) {
+    let log = CHAT_LOG.lock();
+
+    let fb_guard = FRAMEBUFFER.lock();
+    let fb = match fb_guard.as_ref() {
+        Some(f) => f,
+        None => return,
+    };
+
+    let scale = (scale as usize).max(1);
+    let line_height = font::char_height(scale) + 2 * scale;
+    let bottom = fb_height.saturating_sub(HOTBAR_SLOT_SIZE + HOTBAR_BOTTOM_MARGIN + 10);
+    let count = log.lines().count();
+
+    for (i, line) in log.lines().enumerate() {
+        let y = bottom.saturating_sub((count - i) * line_height);
+        let color = if line.is_server() { rgb(255, 220, 80) } else { rgb(255, 255, 255) };
+        let x = fb_width.saturating_sub(font::string_width(&line.text, scale)) / 2;
+        font::draw_string_blended_raw(fb, x, y, &line.text, color, scale, line.alpha());
+    }
+}
+
+/// Draw the match event feed right-aligned below the minimap, newest entry last
+/// Eliminations are tinted with the rarity color of the weapon used. Lines are
+/// drawn piece by piece from borrowed names, so nothing is formatted on the heap.
+pub fn draw_kill_feed(world: &GameWorld, fb_width: usize, fb_height: usize, scale: u8) {
+    let log = MATCH_EVENTS.lock();
+    if log.feed().next().is_none() {
+        return;
+    }
+
+    let fb_guard = FRAMEBUFFER.lock();
+    let fb = match fb_guard.as_ref() {
+        Some(f) => f,
+        None => return,
+    };
+
+    // Same inset as the minimap in draw_minimap
+    let map_size = (fb_height / 5).max(16);
+    let right = fb_width.saturating_sub(fb_width / 50);
+    let top = fb_height / 40 + map_size + 10;
+
+    let scale = (scale as usize).max(1);
+    let line_height = font::char_height(scale) + 2 * scale;
+    let name = |id: u8| world.get_player(id).map_or("???", |p| p.name.as_str());
+
+    for (i, entry) in log.feed().enumerate() {
+        let mut number = [0u8; 3];
+        let (parts, color): ([&str; 6], u32) = match entry.event {
+            MatchEvent::Elimination(kill) => (
+                [
+                    name(kill.killer_id),
+                    " eliminated ",
+                    name(kill.victim_id),
+                    " (",
+                    kill.weapon_type.short_name(),
+                    ")",
+                ],
+                kill.rarity.color(),
+            ),
+            MatchEvent::StormPhaseStarted { phase } => (
+                ["Storm phase ", font::format_number(phase as u32, &mut number), " closing", "", "", ""],
+                rgb(200, 50, 200),
+            ),
+            MatchEvent::ChestOpened { player_id } => {
+                ([name(player_id), " opened a chest", "", "", "", ""], colors::FN_YELLOW)
+            }
+        };
+
+        let width: usize = parts.iter().map(|part| font::string_width(part, scale)).sum();
+        let mut x = right.saturating_sub(width);
+        let y = top + i * line_height;
+        let alpha = entry.alpha(log.now());
+        for part in parts {
+            font::draw_string_blended_raw(fb, x, y, part, color, scale, alpha);
+            x += font::string_width(part, scale);
+        }
+    }
+}
+
+/// Distance from screen center to the middle of a hit indicator arc
+const HIT_INDICATOR_RADIUS: f32 = 80.0;
+/// Radial thickness of the arc in pixels
+const HIT_INDICATOR_THICKNESS: usize = 6;
+/// Half the angular width of the arc
+const HIT_INDICATOR_HALF_SPAN: f32 = 0.35;
+
+/// Draw arcs around screen center pointing at recent damage sources
+/// `yaw` is the local player's facing; straight ahead is the top of the screen
+pub fn draw_hit_indicators(yaw: f32, fb_width: usize, fb_height: usize) {
+    let indicators = *combat::HIT_INDICATORS.lock();
+
+    let fb_guard = FRAMEBUFFER.lock();
+    let fb = match fb_guard.as_ref() {
+        Some(f) => f,
+        None => return,
+    };
+
+    let cx = fb_width as f32 / 2.0;
+    let cy = fb_height as f32 / 2.0;
+    let color = rgb(255, 40, 40);
+    if indicators.iter().any(Option::is_some) {
+        let reach = (HIT_INDICATOR_RADIUS + HIT_INDICATOR_THICKNESS as f32) as i32;
+        let (cx, cy) = (cx as i32, cy as i32);
+        mark_dirty(DirtyRect::from_bounds(cx - reach, cy - reach, cx + reach, cy + reach));
+    }
+
+    for h in indicators.iter().flatten() {
+        // Positive relative angle is to the player's left (yaw increases turning left)
+        let center = h.angle_radians - yaw;
+        let alpha = h.intensity * 0.8;
+
+        for ring in 0..HIT_INDICATOR_THICKNESS {
+            let radius = HIT_INDICATOR_RADIUS - (HIT_INDICATOR_THICKNESS / 2) as f32 + ring as f32;
+            // About one step per pixel of arc length
+            let steps = (radius * HIT_INDICATOR_HALF_SPAN * 2.0) as usize;
+            for i in 0..=steps {
+                let a = center - HIT_INDICATOR_HALF_SPAN + 2.0 * HIT_INDICATOR_HALF_SPAN * i as f32 / steps as f32;
+                let x = cx - radius * libm::sinf(a);
+                let y = cy - radius * libm::cosf(a);
+                if x < 0.0 || y < 0.0 || x >= fb_width as f32 || y >= fb_height as f32 {
+                    continue;
+                }
+                let (x, y) = (x as usize, y as usize);
+                fb.set_pixel(x, y, lerp_color(fb.get_pixel(x, y), color, alpha));
+            }
+        }
+    }
+}
+
+/// Draw floating damage numbers projected from their world positions
+/// Headshots are drawn one scale step larger
+pub fn draw_damage_numbers(view: &Mat4, projection: &Mat4, fb_width: usize, fb_height: usize, scale: u8) {
+    let numbers = *combat::DAMAGE_NUMBERS.lock();
+
+    let fb_guard = FRAMEBUFFER.lock();
+    let fb = match fb_guard.as_ref() {
+        Some(f) => f,
+        None => return,
+    };
+
+    for n in numbers.iter().flatten() {
+        let screen = match project_point(n.position, &Mat4::IDENTITY, view, projection, fb_width as f32, fb_height as f32) {
+            Some(p) => p,
+            None => continue,
+        };
+        if screen.x < 0.0 || screen.y < 0.0 || screen.x >= fb_width as f32 || screen.y >= fb_height as f32 {
+            continue;
+        }
+
+        let text_scale = scale as usize + n.critical as usize;
+        let mut buf = [0u8; 8];
+        let text = font::format_number(n.value as u32, &mut buf);
+        let x = (screen.x as usize).saturating_sub(font::string_width(text, text_scale) / 2);
+        let y = (screen.y as usize).saturating_sub(font::char_height(text_scale) / 2);
+        font::draw_string_blended_raw(fb, x, y, text, n.color, text_scale, n.alpha());
+    }
+}
+
 /// Linear interpolation for u8
 pub fn lerp_u8(a: u8, b: u8, t: f32) -> u8 {
     ((a as f32) + (b as f32 - a as f32) * t) as u8
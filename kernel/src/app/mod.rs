@@ -11,5 +11,5 @@ pub mod run;
 pub mod terrain;
 
 pub use input::get_menu_action;
-pub use render::{render_worker, set_gpu_batch_available, GPU_BATCH_AVAILABLE};
-pub use run::{run, set_benchmark_mode, set_test_mode, network_worker};
+pub use render::{bin_worker, gpu_path_active, render_worker, set_gpu_batch_available, GPU_BATCH_AVAILABLE};
+pub use run::{run, network_worker};
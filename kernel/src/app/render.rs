@@ -4,32 +4,54 @@
 
 extern crate alloc;
 
-use core::sync::atomic::{AtomicBool, Ordering};
-use glam::{Mat4, Vec3};
+use alloc::vec::Vec;
+use benchmark::BenchmarkFrameBreakdown;
+use boot_config::RenderMode;
+use core::ops::Range;
+use core::sync::atomic::{AtomicBool, AtomicPtr, AtomicU32, AtomicUsize, Ordering};
+use glam::{Mat3, Mat4, Vec3};
 use renderer::mesh::Mesh;
-use crate::game::input;
-use crate::game::state::{PlayerPhase, PLAYER_CUSTOMIZATION};
+use renderer::texture::Material;
+use renderer::voxel::MeshingOptions;
+use spin::Mutex;
+use crate::api::types::Color;
+use crate::game::map::{WaterBody, WATER_BODIES};
+use crate::game::state::{PlayerPhase, PLAYER_CUSTOMIZATION, SETTINGS};
 use crate::game::world::GAME_WORLD;
-use crate::graphics::culling::CullContext;
+use crate::gfx::{self, device::Backend, RenderPassDesc};
+use crate::graphics::culling::{CullContext, AABB};
 use crate::graphics::font;
 use crate::graphics::framebuffer::{rgb, FRAMEBUFFER};
 use crate::graphics::gpu;
 use crate::graphics::gpu_batch;
 use crate::graphics::gpu_render;
 use crate::graphics::cursor;
-use crate::graphics::pipeline::{look_at, transform_and_bin_fast, transform_triangle};
-use crate::graphics::rasterizer::{rasterize_screen_triangle_simple, RenderContext};
-use crate::graphics::tiles::{self, TILE_BINS_LOCKFREE, TILE_QUEUE};
+use crate::graphics::pipeline::{
+    self, look_at, normal_matrix, shade_vertex, transform_and_bin_fast, transform_and_bin_fast_lit,
+    transform_triangle, CullMode,
+};
+use crate::graphics::rasterizer::{
+    draw_triangle_wireframe, lighting_enabled, present_tile_debug, rasterize_screen_triangle_blended,
+    rasterize_screen_triangle_overdraw, rasterize_tile_opaque, render_mode, set_render_settings, Fog,
+    RenderContext, RenderSettings,
+};
+use crate::graphics::tiles::{self, ScreenTriangle, MAX_TRIANGLES_PER_TILE, TILE_QUEUE};
+use crate::graphics::zbuffer;
+use crate::graphics::ui::colors as ui_colors;
 use crate::graphics::ui::panel;
+use crate::read_tsc;
+use crate::serial_println;
 use crate::smp;
 use crate::ui;
 
 use super::hud::{
-    draw_inventory_hotbar, draw_materials_hud, draw_minimap,
-    draw_storm_overlay, draw_storm_timer, lerp_u8,
+    draw_chat, draw_damage_numbers, draw_hit_indicators, draw_inventory_hotbar, draw_kill_feed,
+    draw_materials_hud, draw_minimap, draw_squad_hud, draw_storm_overlay, ensure_minimap_texture, draw_storm_timer,
+    lerp_u8,
 };
 
-/// Global GPU batch enabled flag - checked once at init, used per-frame without locks
+/// Global GPU batch enabled flag - set at init if the GPU passed its self-test,
+/// cleared if it fails later in the session
 pub static GPU_BATCH_AVAILABLE: AtomicBool = AtomicBool::new(false);
 
 /// Set GPU batch availability (called during init)
@@ -37,6 +59,78 @@ pub fn set_gpu_batch_available(available: bool) {
     GPU_BATCH_AVAILABLE.store(available, Ordering::Release);
 }
 
+/// Whether this frame renders through the GPU batch path: the GPU is
+/// available, hasn't failed since boot and software mode isn't forced
+pub fn gpu_path_active() -> bool {
+    if !GPU_BATCH_AVAILABLE.load(Ordering::Acquire) {
+        return false;
+    }
+    if gpu_batch::has_failed() {
+        // gpu_batch logged the reason; every later frame goes to software
+        set_gpu_batch_available(false);
+        return false;
+    }
+    !SETTINGS.lock().force_software
+}
+
+/// Sky clear color, also used as the fog color so distant geometry fades into it
+pub const SKY_COLOR: u32 = rgb(50, 70, 100);
+
+/// Benchmark progress bar value as f32 bits (NaN = no bar)
+static BENCHMARK_PROGRESS: AtomicU32 = AtomicU32::new(f32::NAN.to_bits());
+
+/// Show the benchmark progress bar filled to `progress` (0.0 to 1.0)
+pub fn set_benchmark_progress(progress: f32) {
+    BENCHMARK_PROGRESS.store(progress.to_bits(), Ordering::Relaxed);
+}
+
+/// GPU mesh cache keys of the static meshes (see gpu_batch::draw_cached_mesh)
+const MESH_TERRAIN: gpu_batch::MeshId = 1;
+const MESH_BUS: gpu_batch::MeshId = 2;
+const MESH_HOUSE: gpu_batch::MeshId = 3;
+const MESH_TREE_PINE: gpu_batch::MeshId = 4;
+const MESH_TREE_OAK: gpu_batch::MeshId = 5;
+const MESH_ROCK: gpu_batch::MeshId = 6;
+const MESH_STUMP: gpu_batch::MeshId = 7;
+const MESH_WALL: gpu_batch::MeshId = 8;
+
+/// Mesh triangles submitted through bin_mesh_gpu this frame, cached or batched
+/// (before culling, so the count doesn't depend on which path drew them)
+static GPU_MESH_TRIANGLES: AtomicUsize = AtomicUsize::new(0);
+
+/// Mesh triangles submitted on the GPU path in the last frame
+pub fn gpu_mesh_triangles() -> usize {
+    GPU_MESH_TRIANGLES.load(Ordering::Relaxed)
+}
+
+/// Subsystem cycles accumulated for the current frame
+static FRAME_BREAKDOWN: Mutex<BenchmarkFrameBreakdown> = Mutex::new(BenchmarkFrameBreakdown {
+    transform_cycles: 0,
+    rasterize_cycles: 0,
+    hud_cycles: 0,
+    network_cycles: 0,
+    physics_cycles: 0,
+    present_cycles: 0,
+});
+
+/// Add the cycles since `start_tsc` to the subsystem picked by `field`
+pub fn record_subsystem_cycles(field: fn(&mut BenchmarkFrameBreakdown) -> &mut u64, start_tsc: u64) {
+    let cycles = read_tsc().wrapping_sub(start_tsc);
+    *field(&mut FRAME_BREAKDOWN.lock()) += cycles;
+}
+
+/// Present the frame, charging the back buffer copy and screen update to the profiler
+pub fn present_frame() {
+    let start = read_tsc();
+    gpu::present();
+    record_subsystem_cycles(|b| &mut b.present_cycles, start);
+}
+
+/// Take the breakdown accumulated since the last call (one call per frame)
+pub fn take_frame_breakdown() -> BenchmarkFrameBreakdown {
+    core::mem::take(&mut *FRAME_BREAKDOWN.lock())
+}
+
 /// Render a menu frame (2D UI only) with mouse cursor
 pub fn render_menu_frame<F>(fb_width: usize, fb_height: usize, draw_fn: F)
 where
@@ -57,24 +151,16 @@ where
     // Drop context
     drop(render_ctx);
 
-    // Draw cursor and present
-    {
-        let fb_guard = FRAMEBUFFER.lock();
-        if let Some(fb) = fb_guard.as_ref() {
-            // Draw mouse cursor on top of everything
-            let mouse = input::get_mouse_state();
-            cursor::draw_cursor(fb, mouse.x, mouse.y);
-            drop(fb_guard);
-            gpu::present();
-        }
-    }
+    // Present with the mouse cursor on top of everything
+    cursor::set_visible(true);
+    present_frame();
 }
 
 /// Render the test map / model gallery
 pub fn render_test_map_frame(
     fb_width: usize,
     fb_height: usize,
-    test_map: &ui::test_map::TestMapScreen,
+    test_map: &mut ui::test_map::TestMapScreen,
     projection: &Mat4,
 ) {
     use renderer::voxel_models;
@@ -95,33 +181,54 @@ pub fn render_test_map_frame(
     let rotation = test_map.get_rotation();
     let zoom = test_map.get_zoom();
 
-    // Create mesh based on model index
-    let model_mesh = match model_index {
-        0 => voxel_models::create_player_model(&CharacterCustomization::default()).to_mesh(0.1 * zoom),
-        1 => voxel_models::create_shotgun_model().to_mesh(0.15 * zoom),
-        2 => voxel_models::create_ar_model().to_mesh(0.15 * zoom),
-        3 => voxel_models::create_pistol_model().to_mesh(0.2 * zoom),
-        4 => voxel_models::create_smg_model().to_mesh(0.15 * zoom),
-        5 => voxel_models::create_sniper_model().to_mesh(0.12 * zoom),
-        6 => voxel_models::create_pickaxe_model().to_mesh(0.15 * zoom),
-        7 => voxel_models::create_glider_model(0).to_mesh(0.08 * zoom),
-        8 => voxel_models::create_glider_model(1).to_mesh(0.08 * zoom),
-        9 => voxel_models::create_glider_model(2).to_mesh(0.08 * zoom),
-        10 => voxel_models::create_glider_model(3).to_mesh(0.08 * zoom),
-        11 => voxel_models::create_pine_tree().to_mesh(0.1 * zoom),
-        12 => voxel_models::create_oak_tree().to_mesh(0.1 * zoom),
-        13 => voxel_models::create_rock(0).to_mesh(0.2 * zoom),
-        14 => voxel_models::create_wall_wood().to_mesh(0.1 * zoom),
-        15 => voxel_models::create_wall_brick().to_mesh(0.1 * zoom),
-        16 => voxel_models::create_wall_metal().to_mesh(0.1 * zoom),
-        17 => voxel_models::create_floor_wood().to_mesh(0.1 * zoom),
-        18 => voxel_models::create_ramp_wood().to_mesh(0.1 * zoom),
-        19 => voxel_models::create_battle_bus().to_mesh(0.05 * zoom),
-        20 => voxel_models::create_chest().to_mesh(0.2 * zoom),
-        21 => voxel_models::create_backpack_model(1).to_mesh(0.2 * zoom),
-        22 => voxel_models::create_backpack_model(2).to_mesh(0.2 * zoom),
-        _ => voxel_models::create_backpack_model(3).to_mesh(0.2 * zoom),
+    // Voxel model, voxel size and material by model index
+    let (model, scale, material) = match model_index {
+        0 => (voxel_models::create_player_model(&CharacterCustomization::default()), 0.1, None),
+        1 => (voxel_models::create_shotgun_model(), 0.15, None),
+        2 => (voxel_models::create_ar_model(), 0.15, None),
+        3 => (voxel_models::create_pistol_model(), 0.2, None),
+        4 => (voxel_models::create_smg_model(), 0.15, None),
+        5 => (voxel_models::create_sniper_model(), 0.12, None),
+        6 => (voxel_models::create_pickaxe_model(), 0.15, None),
+        7 => (voxel_models::create_glider_model(0), 0.08, None),
+        8 => (voxel_models::create_glider_model(1), 0.08, None),
+        9 => (voxel_models::create_glider_model(2), 0.08, None),
+        10 => (voxel_models::create_glider_model(3), 0.08, None),
+        11 => (voxel_models::create_pine_tree(), 0.1, None),
+        12 => (voxel_models::create_oak_tree(), 0.1, None),
+        13 => (voxel_models::create_rock(0), 0.2, None),
+        14 => (voxel_models::create_wall_wood(), 0.1, Some(Material::Wood)),
+        15 => (voxel_models::create_wall_brick(), 0.1, Some(Material::Brick)),
+        16 => (voxel_models::create_wall_metal(), 0.1, Some(Material::Metal)),
+        17 => (voxel_models::create_floor_wood(), 0.1, Some(Material::Wood)),
+        18 => (voxel_models::create_ramp_wood(), 0.1, Some(Material::Wood)),
+        19 => (voxel_models::create_battle_bus(), 0.05, None),
+        20 => (voxel_models::create_chest(), 0.2, None),
+        21 => (voxel_models::create_backpack_model(1), 0.2, None),
+        22 => (voxel_models::create_backpack_model(2), 0.2, None),
+        _ => (voxel_models::create_backpack_model(3), 0.2, None),
     };
+    let mesh_with = |greedy: bool| {
+        let mesh = if greedy {
+            model.to_mesh_greedy(scale * zoom, MeshingOptions::SHADED)
+        } else {
+            model.to_mesh(scale * zoom, MeshingOptions::SHADED)
+        };
+        match material {
+            Some(material) => mesh.with_material(material),
+            None => mesh,
+        }
+    };
+    let model_mesh = mesh_with(test_map.greedy);
+
+    // Triangle counts of both meshers, once per model
+    if test_map.triangle_counts.is_none() {
+        let other = mesh_with(!test_map.greedy).triangle_count();
+        let (naive, greedy) = if test_map.greedy { (other, model_mesh.triangle_count()) } else { (model_mesh.triangle_count(), other) };
+        serial_println!("Test map: {} greedy meshing {} -> {} triangles ({}% fewer)",
+            test_map.model_name(), naive, greedy, 100 - greedy * 100 / naive.max(1));
+        test_map.triangle_counts = Some((naive, greedy));
+    }
 
     // Camera setup - orbit around the model
     let camera_dist = 8.0;
@@ -134,22 +241,27 @@ pub fn render_test_map_frame(
     let camera_target = Vec3::new(0.0, 1.0, 0.0);
     let view = look_at(camera_pos, camera_target, Vec3::Y);
 
-    // Clear tile bins
-    tiles::clear_lockfree_bins();
-    tiles::reset_triangle_buffer();
+    if let Some(backend) = test_map.get_gfx_backend() {
+        // Same model through the gfx device, to compare backends
+        drop(render_ctx);
+        render_model_gfx(backend, model_mesh, &view, projection);
+    } else {
+        // Clear tile bins
+        tiles::clear_lockfree_bins();
+        tiles::reset_triangle_buffer();
 
-    // Transform and bin the model
-    let model_matrix = Mat4::IDENTITY;
-    bin_mesh(&model_mesh, &model_matrix, &view, projection, fb_width as f32, fb_height as f32);
+        // Transform and bin the model
+        let model_matrix = Mat4::IDENTITY;
+        bin_mesh(&model_mesh, &model_matrix, &view, projection, fb_width as f32, fb_height as f32, CullMode::Back);
 
-    // Reset and render tiles
-    tiles::reset();
-    smp::scheduler::start_render();
-    render_worker(0);
-    smp::sync::RENDER_BARRIER.wait();
-    smp::scheduler::end_render();
+        // Reset and render tiles
+        tiles::reset();
+        smp::scheduler::start_render();
+        render_worker(0);
+        smp::sync::RENDER_BARRIER.wait();
 
-    drop(render_ctx);
+        drop(render_ctx);
+    }
 
     // Draw UI overlay
     let ctx = match RenderContext::acquire() {
@@ -159,15 +271,30 @@ pub fn render_test_map_frame(
     test_map.draw(&ctx, fb_width, fb_height);
     drop(ctx);
 
-    // Draw cursor and present
-    {
-        let fb_guard = FRAMEBUFFER.lock();
-        if let Some(fb) = fb_guard.as_ref() {
-            let mouse = input::get_mouse_state();
-            cursor::draw_cursor(fb, mouse.x, mouse.y);
-            drop(fb_guard);
-            gpu::present();
+    // Present with the mouse cursor on top
+    cursor::set_visible(true);
+    present_frame();
+}
+
+/// Draw a gallery model through a gfx device on `backend`
+fn render_model_gfx(backend: Backend, mesh: Mesh, view: &Mat4, projection: &Mat4) {
+    let mut device = match gfx::Device::with_backend(backend) {
+        Ok(device) => device,
+        Err(e) => {
+            serial_println!("Test map: gfx {:?} backend unavailable: {:?}", backend, e);
+            return;
         }
+    };
+    let mesh_id = device.create_mesh(mesh);
+
+    let mut encoder = device.begin_commands();
+    {
+        let mut pass = encoder.begin_render_pass(&RenderPassDesc::clear(Color::rgb(20, 25, 40)));
+        pass.set_camera(*view, *projection);
+        pass.draw_mesh(mesh_id, Mat4::IDENTITY);
+    }
+    if let Err(e) = encoder.finish().submit(&mut device) {
+        serial_println!("Test map: gfx submit failed: {:?}", e);
     }
 }
 
@@ -199,7 +326,7 @@ pub fn render_lobby_frame(
     drop(custom);
 
     // Create player mesh from voxel model
-    let player_mesh = voxel_models::create_player_model(&renderer_custom).to_mesh(0.15);
+    let player_mesh = voxel_models::create_player_model(&renderer_custom).to_mesh(0.15, MeshingOptions::SHADED);
 
     // Calculate layout based on number of players
     let player_count = lobby.player_count();
@@ -231,13 +358,13 @@ pub fn render_lobby_frame(
 
     // Transform and bin the platform (centered)
     let platform_model = Mat4::from_translation(Vec3::new(0.0, -0.1, 0.0));
-    bin_mesh(&platform_mesh, &platform_model, &view, projection, fb_width as f32, fb_height as f32);
+    bin_mesh(&platform_mesh, &platform_model, &view, projection, fb_width as f32, fb_height as f32, CullMode::Back);
 
     // Transform and bin each player model in the party
     for i in 0..player_count {
         let player_x = start_x + i as f32 * spacing;
         let player_model = Mat4::from_translation(Vec3::new(player_x, 0.0, 0.0));
-        bin_mesh(&player_mesh, &player_model, &view, projection, fb_width as f32, fb_height as f32);
+        bin_mesh_lod(&player_mesh, &player_model, camera_pos, &view, projection, fb_width as f32, fb_height as f32, CullMode::Back);
     }
 
     // Reset and render tiles
@@ -245,7 +372,6 @@ pub fn render_lobby_frame(
     smp::scheduler::start_render();
     render_worker(0);
     smp::sync::RENDER_BARRIER.wait();
-    smp::scheduler::end_render();
 
     drop(render_ctx);
 }
@@ -313,6 +439,8 @@ pub fn render_game_frame(
     chest_mesh: &Mesh,
     house_mesh: &Mesh,
     storm_wall_mesh: &Mesh,
+    water_mesh: &Mesh,
+    stump_mesh: &Mesh,
     // LOD meshes for distant objects
     tree_pine_lod: &Mesh,
     tree_oak_lod: &Mesh,
@@ -330,12 +458,12 @@ pub fn render_game_frame(
     };
 
     // Clear back buffer and z-buffer (double buffering prevents flicker)
-    render_ctx.clear(rgb(50, 70, 100)); // Sky blue background
+    render_ctx.clear(SKY_COLOR);
     render_ctx.clear_zbuffer();
 
     // Get camera position from local player (or default orbit)
     let (camera_pos, camera_target, local_player_phase) = {
-        let world = GAME_WORLD.lock();
+        let world = GAME_WORLD.read();
         if let (Some(w), Some(id)) = (world.as_ref(), local_player_id) {
             if let Some(player) = w.get_player(id) {
                 // Camera distance based on phase
@@ -351,15 +479,17 @@ pub fn render_game_frame(
                 };
                 // Third-person camera: behind and above player, looking AT the player
                 // Camera orbits around player based on yaw, staying behind them
+                // Crouching lowers the whole rig along with the player's eyes
+                let crouch_drop = player.crouch_drop();
                 let cam_offset = Vec3::new(
                     -libm::sinf(player.yaw) * cam_dist,
-                    cam_height,
+                    cam_height - crouch_drop,
                     -libm::cosf(player.yaw) * cam_dist,
                 );
                 let pos = player.position + cam_offset;
 
                 // Camera looks at player's upper body (not the ground)
-                let target = player.position + Vec3::new(0.0, 1.5, 0.0);
+                let target = player.position + Vec3::new(0.0, 1.5 - crouch_drop, 0.0);
                 (pos, target, Some(player.phase))
             } else {
                 let dist = 20.0;
@@ -372,16 +502,24 @@ pub fn render_game_frame(
     };
     let view = look_at(camera_pos, camera_target, Vec3::Y);
 
-    // Check GPU batch availability ONCE at frame start (lock-free atomic read)
-    let use_gpu_batch = GPU_BATCH_AVAILABLE.load(Ordering::Acquire);
+    // Per-frame render settings, picked up by the rasterizer workers' contexts
+    let settings = *SETTINGS.lock();
+    let fog = settings.fog.range().map(|(start, end)| Fog::new(start, end, SKY_COLOR));
+    let light = settings.lighting_enabled.then(|| pipeline::light().to_view(&view));
+    set_render_settings(RenderSettings { fog, mode: render_mode(), light });
+
+    // Pick the path ONCE at frame start
+    let use_gpu_batch = gpu_path_active();
 
     if use_gpu_batch {
         // === GPU RENDERING PATH ===
+        // (bin_mesh_gpu counts as transform and the final flush as
+        // rasterization in the benchmark breakdown)
         render_game_gpu(
             fb_width, fb_height,
             terrain, player_mesh, wall_mesh, bus_mesh,
             glider_mesh, tree_pine_mesh, tree_oak_mesh, rock_mesh,
-            chest_mesh, house_mesh, storm_wall_mesh,
+            chest_mesh, house_mesh, storm_wall_mesh, water_mesh, stump_mesh,
             &view, projection, camera_pos, rotation,
         );
         drop(render_ctx);
@@ -391,29 +529,51 @@ pub fn render_game_frame(
             fb_width, fb_height,
             terrain, player_mesh, wall_mesh, bus_mesh,
             glider_mesh, tree_pine_mesh, tree_oak_mesh, rock_mesh,
-            chest_mesh, house_mesh, storm_wall_mesh,
+            chest_mesh, house_mesh, storm_wall_mesh, water_mesh, stump_mesh,
             tree_pine_lod, tree_oak_lod, rock_lod, chest_lod,
             &view, projection, camera_pos, rotation,
         );
         drop(render_ctx);
     }
 
+    // Menus and overlays rendered with later contexts are never fogged
+    set_render_settings(RenderSettings::default());
+
     // === 2D UI RENDERING ===
+    let hud_start = read_tsc();
+    let font_scale = SETTINGS.lock().font_scale;
+
+    // Damage numbers float in world space, so draw them before the flat HUD
+    draw_damage_numbers(&view, projection, fb_width, fb_height, font_scale);
 
     // Draw FPS counter
-    font::draw_fps(current_fps, fb_width);
+    font::draw_fps(current_fps, fb_width, font_scale);
 
-    // Draw crosshair at center of screen
+    // Draw crosshair at center of screen (and benchmark progress along the top)
     {
         let fb_guard = FRAMEBUFFER.lock();
         if let Some(fb) = fb_guard.as_ref() {
             panel::draw_crosshair_raw(fb, fb_width, fb_height, 0xFFFFFFFF);
+
+            let progress = f32::from_bits(BENCHMARK_PROGRESS.load(Ordering::Relaxed));
+            if progress >= 0.0 {
+                let bar = panel::ProgressBar {
+                    x: fb_width / 4,
+                    y: fb_height / 100,
+                    width: fb_width / 2,
+                    height: 8 * font_scale as usize,
+                    color_low: ui_colors::FN_PURPLE,
+                    color_high: ui_colors::FN_BLUE,
+                    direction: panel::FillDirection::LeftToRight,
+                };
+                bar.draw(fb, progress);
+            }
         }
     }
 
     // Draw storm indicator if player is in storm
     {
-        let world_guard = GAME_WORLD.lock();
+        let world_guard = GAME_WORLD.read();
         if let Some(world) = world_guard.as_ref() {
             if let Some(id) = local_player_id {
                 if let Some(player) = world.get_player(id) {
@@ -428,7 +588,7 @@ pub fn render_game_frame(
 
     // Draw game HUD (health, shield, materials, alive count)
     {
-        let world_guard = GAME_WORLD.lock();
+        let world_guard = GAME_WORLD.read();
         if let Some(world) = world_guard.as_ref() {
             let (health, shield, materials, inventory) = if let Some(id) = local_player_id {
                 if let Some(player) = world.get_player(id) {
@@ -443,26 +603,46 @@ pub fn render_game_frame(
             let total = world.players.len();
 
             // Draw main HUD
-            font::draw_hud(health, shield as u32, alive, total, fb_width, fb_height);
+            font::draw_hud(health, shield, alive, total, fb_width, fb_height, font_scale);
+
+            // Squadmates' health above the main HUD
+            draw_squad_hud(world, local_player_id, fb_width, fb_height, font_scale);
+
+            // Damage direction arcs around the crosshair
+            if let Some(player) = local_player_id.and_then(|id| world.get_player(id)) {
+                draw_hit_indicators(player.yaw, fb_width, fb_height);
+            }
 
             // Draw inventory hotbar
             if let Some(inv) = inventory {
                 draw_inventory_hotbar(inv, fb_width, fb_height);
             }
 
+            // Chat/announcement lines above the hotbar
+            draw_chat(fb_width, fb_height, font_scale);
+
             // Draw materials count
-            draw_materials_hud(&materials, fb_width, fb_height);
+            draw_materials_hud(&materials, fb_width, fb_height, font_scale);
 
             // Draw storm timer
-            draw_storm_timer(&world.storm, fb_width, fb_height);
+            draw_storm_timer(&world.storm, fb_width, fb_height, font_scale);
 
-            // Draw minimap with storm circle
+            // Draw minimap (or the fullscreen map) over the cached top-down texture, with storm circles
+            ensure_minimap_texture(world, terrain, house_mesh);
             draw_minimap(local_player_id, world, fb_width, fb_height);
+
+            // Recent eliminations below the minimap
+            draw_kill_feed(world, fb_width, fb_height, font_scale);
         }
     }
+    record_subsystem_cycles(|b| &mut b.hud_cycles, hud_start);
 
     // End frame and present to display (uses GPU acceleration if available)
+    // Gameplay steers with the mouse, so there is no pointer
+    cursor::set_visible(false);
+    let present_start = read_tsc();
     gpu_render::end_frame();
+    record_subsystem_cycles(|b| &mut b.present_cycles, present_start);
 }
 
 /// GPU rendering path for game frame
@@ -480,6 +660,8 @@ fn render_game_gpu(
     chest_mesh: &Mesh,
     house_mesh: &Mesh,
     storm_wall_mesh: &Mesh,
+    water_mesh: &Mesh,
+    stump_mesh: &Mesh,
     view: &Mat4,
     projection: &Mat4,
     camera_pos: Vec3,
@@ -487,6 +669,8 @@ fn render_game_gpu(
 ) {
     // Begin GPU batch (clears GPU buffers)
     gpu_batch::begin_batch();
+    gpu_batch::set_scene_transforms(view, projection);
+    GPU_MESH_TRIANGLES.store(0, Ordering::Relaxed);
 
     // Create culling context for frustum + distance culling
     let cull_ctx = CullContext::new(view, projection, camera_pos)
@@ -494,16 +678,24 @@ fn render_game_gpu(
 
     // Transform and batch terrain
     let terrain_model = Mat4::from_translation(Vec3::new(0.0, 0.0, 0.0));
-    bin_mesh_gpu(terrain, &terrain_model, view, projection, fb_width as f32, fb_height as f32);
+    bin_mesh_gpu(terrain, Some(MESH_TERRAIN), &terrain_model, view, projection, fb_width as f32, fb_height as f32, CullMode::Back);
+
+    // Water surfaces over the terrain valleys
+    for water in &WATER_BODIES {
+        if cull_ctx.should_render(water.center, water.radius) {
+            let model = water_model(water);
+            bin_mesh_gpu(water_mesh, None, &model, view, projection, fb_width as f32, fb_height as f32, CullMode::None);
+        }
+    }
 
     // Batch game world entities with frustum culling
     {
-        let world = GAME_WORLD.lock();
+        let world = GAME_WORLD.read();
         if let Some(w) = world.as_ref() {
             // Render battle bus if active and visible
             if w.bus.active && cull_ctx.should_render(w.bus.position, 10.0) {
                 let bus_model = Mat4::from_translation(w.bus.position);
-                bin_mesh_gpu(bus_mesh, &bus_model, view, projection, fb_width as f32, fb_height as f32);
+                bin_mesh_gpu(bus_mesh, Some(MESH_BUS), &bus_model, view, projection, fb_width as f32, fb_height as f32, CullMode::Back);
             }
 
             // Render map buildings with frustum culling
@@ -515,7 +707,7 @@ fn render_game_gpu(
                     let model = Mat4::from_translation(building.position)
                         * Mat4::from_rotation_y(building.rotation)
                         * Mat4::from_scale(Vec3::splat(1.5));
-                    bin_mesh_gpu(house_mesh, &model, view, projection, fb_width as f32, fb_height as f32);
+                    bin_mesh_gpu(house_mesh, Some(MESH_HOUSE), &model, view, projection, fb_width as f32, fb_height as f32, CullMode::Back);
                 }
             }
 
@@ -531,22 +723,30 @@ fn render_game_gpu(
 
                     match veg.veg_type {
                         crate::game::map::VegetationType::TreePine => {
-                            bin_mesh_gpu(tree_pine_mesh, &model, view, projection, fb_width as f32, fb_height as f32);
+                            bin_mesh_gpu(tree_pine_mesh, Some(MESH_TREE_PINE), &model, view, projection, fb_width as f32, fb_height as f32, CullMode::None);
                         }
                         crate::game::map::VegetationType::TreeOak | crate::game::map::VegetationType::TreeBirch => {
-                            bin_mesh_gpu(tree_oak_mesh, &model, view, projection, fb_width as f32, fb_height as f32);
+                            bin_mesh_gpu(tree_oak_mesh, Some(MESH_TREE_OAK), &model, view, projection, fb_width as f32, fb_height as f32, CullMode::None);
                         }
                         crate::game::map::VegetationType::Rock => {
-                            bin_mesh_gpu(rock_mesh, &model, view, projection, fb_width as f32, fb_height as f32);
+                            bin_mesh_gpu(rock_mesh, Some(MESH_ROCK), &model, view, projection, fb_width as f32, fb_height as f32, CullMode::Back);
                         }
                         crate::game::map::VegetationType::Bush => {
                             let bush_model = model * Mat4::from_scale(Vec3::splat(0.5));
-                            bin_mesh_gpu(tree_oak_mesh, &bush_model, view, projection, fb_width as f32, fb_height as f32);
+                            bin_mesh_gpu(tree_oak_mesh, Some(MESH_TREE_OAK), &bush_model, view, projection, fb_width as f32, fb_height as f32, CullMode::None);
                         }
                     }
                 }
             }
 
+            // Stumps of harvested trees
+            for &(position, _) in w.map.stumps() {
+                if cull_ctx.should_render(position, 1.0) {
+                    let model = Mat4::from_translation(position);
+                    bin_mesh_gpu(stump_mesh, Some(MESH_STUMP), &model, view, projection, fb_width as f32, fb_height as f32, CullMode::Back);
+                }
+            }
+
             // Render loot drops with culling
             for drop in w.loot.get_active_drops() {
                 if !cull_ctx.should_render(drop.position, 2.0) {
@@ -554,7 +754,7 @@ fn render_game_gpu(
                 }
                 let model = Mat4::from_translation(drop.position)
                     * Mat4::from_rotation_y(rotation * 2.0);
-                bin_mesh_gpu(chest_mesh, &model, view, projection, fb_width as f32, fb_height as f32);
+                bin_mesh_gpu(chest_mesh, None, &model, view, projection, fb_width as f32, fb_height as f32, CullMode::Back);
             }
 
             // Render all players (always render, they're important)
@@ -566,13 +766,14 @@ fn render_game_gpu(
                 // Player model faces -Z naturally, add PI to face forward (away from camera)
                 let model = Mat4::from_translation(player.position)
                     * Mat4::from_rotation_y(player.yaw);
-                bin_mesh_gpu(player_mesh, &model, view, projection, fb_width as f32, fb_height as f32);
+                let lod = player_mesh.select_lod(model_distance(&model, camera_pos));
+                bin_mesh_gpu(lod, None, &model, view, projection, fb_width as f32, fb_height as f32, CullMode::Back);
 
                 if player.phase == PlayerPhase::Gliding {
                     let glider_offset = Vec3::new(0.0, 2.5, 0.0);
                     let glider_model = Mat4::from_translation(player.position + glider_offset)
                         * Mat4::from_rotation_y(player.yaw);
-                    bin_mesh_gpu(glider_mesh, &glider_model, view, projection, fb_width as f32, fb_height as f32);
+                    bin_mesh_gpu(glider_mesh, None, &glider_model, view, projection, fb_width as f32, fb_height as f32, CullMode::None);
                 }
             }
 
@@ -583,18 +784,20 @@ fn render_game_gpu(
                 }
                 let model = Mat4::from_translation(building.position)
                     * Mat4::from_rotation_y(building.rotation);
-                bin_mesh_gpu(wall_mesh, &model, view, projection, fb_width as f32, fb_height as f32);
+                bin_mesh_gpu(wall_mesh, Some(MESH_WALL), &model, view, projection, fb_width as f32, fb_height as f32, CullMode::Back);
             }
 
             // Render 3D storm wall (always render, important visual)
             let storm_model = Mat4::from_translation(Vec3::new(w.storm.center.x, 0.0, w.storm.center.z))
                 * Mat4::from_scale(Vec3::new(w.storm.radius, 1.0, w.storm.radius));
-            bin_mesh_gpu(storm_wall_mesh, &storm_model, view, projection, fb_width as f32, fb_height as f32);
+            bin_mesh_gpu(storm_wall_mesh, None, &storm_model, view, projection, fb_width as f32, fb_height as f32, CullMode::None);
         }
     }
 
     // End GPU batch (flushes remaining triangles and presents)
+    let flush_start = read_tsc();
     gpu_batch::end_batch();
+    record_subsystem_cycles(|b| &mut b.rasterize_cycles, flush_start);
 }
 
 /// Software rendering path for game frame
@@ -612,6 +815,8 @@ fn render_game_software(
     chest_mesh: &Mesh,
     house_mesh: &Mesh,
     storm_wall_mesh: &Mesh,
+    water_mesh: &Mesh,
+    stump_mesh: &Mesh,
     // LOD meshes for distant objects
     tree_pine_lod: &Mesh,
     tree_oak_lod: &Mesh,
@@ -622,27 +827,41 @@ fn render_game_software(
     camera_pos: Vec3,
     rotation: f32,
 ) {
-    // 1. Clear lock-free bins and reset triangle buffer
-    tiles::clear_lockfree_bins();
-    tiles::reset_triangle_buffer();
-
-    // 2. Create culling context for frustum + distance culling
+    // 1. Create culling context for frustum + distance culling
     // AGGRESSIVE culling for software rendering performance
     let cull_ctx = CullContext::new(view, projection, camera_pos)
         .with_distances(0.5, 80.0); // Near 0.5, Far 80 units (was 500!)
 
-    // 3. Transform and bin terrain (always render, but reduced complexity)
+    // 2. Queue terrain first (always render, but reduced complexity)
+    // Terrain and buildings are the occluders: they are drawn in a pass of
+    // their own, and the depth they leave decides which players and loot
+    // drops are worth binning in the second pass
     let terrain_model = Mat4::from_translation(Vec3::new(0.0, 0.0, 0.0));
-    bin_mesh(terrain, &terrain_model, view, projection, fb_width as f32, fb_height as f32);
+    let mut occluders = Vec::new();
+    occluders.push(MeshDraw::new(terrain, terrain_model, CullMode::Back, camera_pos));
+    let mut scene = Vec::new();
+
+    // Water surfaces: translucent, so they blend over the valley floor beneath
+    for water in &WATER_BODIES {
+        if cull_ctx.should_render(water.center, water.radius) {
+            scene.push(MeshDraw::new(water_mesh, water_model(water), CullMode::None, camera_pos));
+        }
+    }
 
-    // 4. Render game world entities with frustum culling
+    // 3. Render game world entities with frustum culling
+    // Entities are queued, then binned nearest first so near occluders fill each tile's Hi-Z early
+    let mut building_draws: Vec<MeshDraw> = Vec::new();
+    let mut draws: Vec<MeshDraw> = Vec::new();
+    // Players and loot drops with their world bounds, tested against the occluders
+    let mut occludees: Vec<(MeshDraw, AABB)> = Vec::new();
+    let mut storm_model = None;
     {
-        let world = GAME_WORLD.lock();
+        let world = GAME_WORLD.read();
         if let Some(w) = world.as_ref() {
             // Render battle bus if active and visible
             if w.bus.active && cull_ctx.should_render(w.bus.position, 10.0) {
                 let bus_model = Mat4::from_translation(w.bus.position);
-                bin_mesh(bus_mesh, &bus_model, view, projection, fb_width as f32, fb_height as f32);
+                draws.push(MeshDraw::new(bus_mesh, bus_model, CullMode::Back, camera_pos));
             }
 
             // Render map buildings with frustum culling
@@ -655,7 +874,7 @@ fn render_game_software(
                     let model = Mat4::from_translation(building.position)
                         * Mat4::from_rotation_y(building.rotation)
                         * Mat4::from_scale(Vec3::splat(1.5));
-                    bin_mesh(house_mesh, &model, view, projection, fb_width as f32, fb_height as f32);
+                    building_draws.push(MeshDraw::new(house_mesh, model, CullMode::Back, camera_pos));
                 }
             }
 
@@ -700,27 +919,41 @@ fn render_game_software(
                     match veg.veg_type {
                         crate::game::map::VegetationType::TreePine => {
                             let mesh = if use_lod { tree_pine_lod } else { tree_pine_mesh };
-                            bin_mesh(mesh, &model, view, projection, fb_width as f32, fb_height as f32);
+                            draws.push(MeshDraw::new(mesh, model, CullMode::None, camera_pos));
                         }
                         crate::game::map::VegetationType::TreeOak | crate::game::map::VegetationType::TreeBirch => {
                             let mesh = if use_lod { tree_oak_lod } else { tree_oak_mesh };
-                            bin_mesh(mesh, &model, view, projection, fb_width as f32, fb_height as f32);
+                            draws.push(MeshDraw::new(mesh, model, CullMode::None, camera_pos));
                         }
                         crate::game::map::VegetationType::Rock => {
                             let mesh = if use_lod { rock_lod } else { rock_mesh };
-                            bin_mesh(mesh, &model, view, projection, fb_width as f32, fb_height as f32);
+                            draws.push(MeshDraw::new(mesh, model, CullMode::Back, camera_pos));
                         }
                         crate::game::map::VegetationType::Bush => {
                             // Bushes use oak tree LOD for simplicity
                             let mesh = if use_lod { tree_oak_lod } else { tree_oak_mesh };
                             let bush_model = model * Mat4::from_scale(Vec3::splat(0.5));
-                            bin_mesh(mesh, &bush_model, view, projection, fb_width as f32, fb_height as f32);
+                            draws.push(MeshDraw::new(mesh, bush_model, CullMode::None, camera_pos));
                         }
                     }
                 }
             }
 
+            // Stumps of harvested trees (small, so they share the bush distance)
+            for &(position, _) in w.map.stumps() {
+                let dx = position.x - camera_pos.x;
+                let dz = position.z - camera_pos.z;
+                if dx * dx + dz * dz > BUSH_RENDER_DIST * BUSH_RENDER_DIST {
+                    continue;
+                }
+                if cull_ctx.should_render(position, 1.0) {
+                    draws.push(MeshDraw::new(stump_mesh, Mat4::from_translation(position), CullMode::Back, camera_pos));
+                }
+            }
+
             // Render loot drops with distance culling and LOD (25m max)
+            let chest_bounds = mesh_bounds(chest_mesh);
+            let chest_lod_bounds = mesh_bounds(chest_lod);
             const LOOT_RENDER_DIST: f32 = 25.0;
             const LOOT_LOD_THRESHOLD_SQ: f32 = 15.0 * 15.0; // LOD beyond 15m for loot (balanced)
             for drop in w.loot.get_active_drops() {
@@ -735,11 +968,16 @@ fn render_game_software(
                 }
                 let model = Mat4::from_translation(drop.position)
                     * Mat4::from_rotation_y(rotation * 2.0);
-                let mesh = if dist_sq > LOOT_LOD_THRESHOLD_SQ { chest_lod } else { chest_mesh };
-                bin_mesh(mesh, &model, view, projection, fb_width as f32, fb_height as f32);
+                let (mesh, bounds) = if dist_sq > LOOT_LOD_THRESHOLD_SQ {
+                    (chest_lod, chest_lod_bounds)
+                } else {
+                    (chest_mesh, chest_bounds)
+                };
+                occludees.push((MeshDraw::new(mesh, model, CullMode::Back, camera_pos), bounds.transform(&model)));
             }
 
             // Render all players (always render, they're important)
+            let player_bounds = mesh_bounds(player_mesh);
             for player in &w.players {
                 if !player.is_alive() || player.phase == PlayerPhase::OnBus {
                     continue;
@@ -748,13 +986,13 @@ fn render_game_software(
                 // Player model faces -Z naturally, add PI to face forward (away from camera)
                 let model = Mat4::from_translation(player.position)
                     * Mat4::from_rotation_y(player.yaw);
-                bin_mesh(player_mesh, &model, view, projection, fb_width as f32, fb_height as f32);
+                occludees.push((MeshDraw::with_lod(player_mesh, model, CullMode::Back, camera_pos), player_bounds.transform(&model)));
 
                 if player.phase == PlayerPhase::Gliding {
                     let glider_offset = Vec3::new(0.0, 2.5, 0.0);
                     let glider_model = Mat4::from_translation(player.position + glider_offset)
                         * Mat4::from_rotation_y(player.yaw);
-                    bin_mesh(glider_mesh, &glider_model, view, projection, fb_width as f32, fb_height as f32);
+                    draws.push(MeshDraw::new(glider_mesh, glider_model, CullMode::None, camera_pos));
                 }
             }
 
@@ -765,33 +1003,205 @@ fn render_game_software(
                 }
                 let model = Mat4::from_translation(building.position)
                     * Mat4::from_rotation_y(building.rotation);
-                bin_mesh(wall_mesh, &model, view, projection, fb_width as f32, fb_height as f32);
+                building_draws.push(MeshDraw::new(wall_mesh, model, CullMode::Back, camera_pos));
             }
 
             // Render 3D storm wall (always render, important visual)
-            let storm_model = Mat4::from_translation(Vec3::new(w.storm.center.x, 0.0, w.storm.center.z))
-                * Mat4::from_scale(Vec3::new(w.storm.radius, 1.0, w.storm.radius));
-            bin_mesh(storm_wall_mesh, &storm_model, view, projection, fb_width as f32, fb_height as f32);
+            storm_model = Some(
+                Mat4::from_translation(Vec3::new(w.storm.center.x, 0.0, w.storm.center.z))
+                    * Mat4::from_scale(Vec3::new(w.storm.radius, 1.0, w.storm.radius)),
+            );
         }
     }
 
-    // 4. Reset tile work queue
-    tiles::reset();
+    // 4. First pass: terrain and buildings, then their depth at cell resolution
+    building_draws.sort_unstable_by(|a, b| a.dist_sq.total_cmp(&b.dist_sq));
+    occluders.append(&mut building_draws);
+    render_pass(&occluders, view, projection, fb_width, fb_height, false);
+    zbuffer::build_occlusion();
+
+    // 5. Players and loot hidden behind a hill or a house are never transformed
+    {
+        let view_proj = *projection * *view;
+        let occlusion = zbuffer::OCCLUSION.lock();
+        for (draw, bounds) in occludees {
+            let visible = bounds
+                .screen_rect(&view_proj, fb_width as f32, fb_height as f32)
+                .is_none_or(|(rect, z)| occlusion.is_visible(rect, z));
+            if visible {
+                draws.push(draw);
+            }
+        }
+    }
+
+    // 6. Second pass: everything else, nearest first
+    draws.sort_unstable_by(|a, b| a.dist_sq.total_cmp(&b.dist_sq));
+    scene.append(&mut draws);
+
+    // Storm wall last: it is translucent, so its bin order doesn't matter
+    if let Some(storm_model) = storm_model {
+        scene.push(MeshDraw::new(storm_wall_mesh, storm_model, CullMode::None, camera_pos));
+    }
+    render_pass(&scene, view, projection, fb_width, fb_height, true);
+}
 
-    // 5. Signal worker cores (1-3) to start rendering
+/// Transform, bin and rasterize `draws` on all render cores, over what
+/// earlier passes this frame left in the framebuffer and z-buffer
+/// Debug views are only applied by the pass that finishes the frame
+/// (`last`), so the tile tint counts that pass's triangles
+fn render_pass(draws: &[MeshDraw], view: &Mat4, projection: &Mat4, fb_width: usize, fb_height: usize, last: bool) {
+    // 1. Clear lock-free bins and reset triangle buffer
+    let transform_start = read_tsc();
+    tiles::clear_lockfree_bins();
+    tiles::reset_triangle_buffer();
+
+    // 2. Signal rasterizer cores to start: all render cores transform and bin
+    //    their share of the draws, and the last one done resets the tile work queue
+    let job = BinJob::new(draws, view, projection, fb_width as f32, fb_height as f32, last);
+    job.publish();
     smp::scheduler::start_render();
+    bin_worker();
+    record_subsystem_cycles(|b| &mut b.transform_cycles, transform_start);
 
-    // 6. Core 0 also helps rasterize tiles
+    // 3. Core 0 also helps rasterize tiles
+    let raster_start = read_tsc();
     render_worker(0);
 
-    // 7. Wait for all cores (0-3) to finish at the barrier
+    // 4. Wait for all render cores to finish at the barrier (none reads the job after it)
     smp::sync::RENDER_BARRIER.wait();
+    BinJob::unpublish();
+    record_subsystem_cycles(|b| &mut b.rasterize_cycles, raster_start);
+}
+
+/// Model-space bounds of a mesh's vertices
+fn mesh_bounds(mesh: &Mesh) -> AABB {
+    AABB::from_points(mesh.vertices.iter().map(|v| v.position))
+}
+
+/// Model matrix placing the unit water plane over a water body
+fn water_model(water: &WaterBody) -> Mat4 {
+    Mat4::from_translation(water.center) * Mat4::from_scale(Vec3::new(water.radius, 1.0, water.radius))
+}
+
+/// A mesh instance queued for binning (sorted by distance before binning)
+struct MeshDraw<'a> {
+    mesh: &'a Mesh,
+    model: Mat4,
+    cull: CullMode,
+    /// Squared distance from the camera to the model origin
+    dist_sq: f32,
+}
+
+impl<'a> MeshDraw<'a> {
+    fn new(mesh: &'a Mesh, model: Mat4, cull: CullMode, camera_pos: Vec3) -> Self {
+        let dist_sq = model.w_axis.truncate().distance_squared(camera_pos);
+        Self { mesh, model, cull, dist_sq }
+    }
+
+    /// Queue the level of detail of `mesh` for its distance (see Mesh::select_lod)
+    fn with_lod(mesh: &'a Mesh, model: Mat4, cull: CullMode, camera_pos: Vec3) -> Self {
+        let mut draw = Self::new(mesh, model, cull, camera_pos);
+        draw.mesh = mesh.select_lod(libm::sqrtf(draw.dist_sq));
+        draw
+    }
+}
+
+/// Triangles per binning work item (larger meshes are split across cores)
+const BIN_CHUNK_TRIANGLES: usize = 256;
+
+/// A frame's scene to transform and bin, shared by all render cores
+/// Work items are triangle ranges of the queued meshes, claimed in queue order
+/// so near meshes still tend to be binned first
+struct BinJob<'a> {
+    draws: &'a [MeshDraw<'a>],
+    /// (draw index, triangle range) work items
+    chunks: Vec<(usize, Range<usize>)>,
+    next: AtomicUsize,
+    view: Mat4,
+    projection: Mat4,
+    fb_width: f32,
+    fb_height: f32,
+    /// Whether rasterizing this job finishes the frame (see `render_pass`)
+    present_debug: bool,
+}
+
+/// The frame's published bin job (null outside the software game render)
+static BIN_JOB: AtomicPtr<BinJob<'static>> = AtomicPtr::new(core::ptr::null_mut());
+
+impl<'a> BinJob<'a> {
+    fn new(draws: &'a [MeshDraw<'a>], view: &Mat4, projection: &Mat4, fb_width: f32, fb_height: f32, present_debug: bool) -> Self {
+        let mut chunks = Vec::new();
+        for (i, draw) in draws.iter().enumerate() {
+            let count = draw.mesh.triangle_count();
+            for start in (0..count).step_by(BIN_CHUNK_TRIANGLES) {
+                chunks.push((i, start..(start + BIN_CHUNK_TRIANGLES).min(count)));
+            }
+        }
+        Self {
+            draws,
+            chunks,
+            next: AtomicUsize::new(0),
+            view: *view,
+            projection: *projection,
+            fb_width,
+            fb_height,
+            present_debug,
+        }
+    }
+
+    /// Whether the tiles being rasterized get their debug view: always,
+    /// except in passes that more of the frame is drawn over
+    fn presents_debug() -> bool {
+        // Safety: core 0 keeps the published job alive until all cores pass RENDER_BARRIER
+        unsafe { BIN_JOB.load(Ordering::Acquire).as_ref() }.is_none_or(|job| job.present_debug)
+    }
+
+    /// Make the job visible to the rasterizer cores (before `start_render`)
+    /// It must stay alive until every core has passed RENDER_BARRIER
+    fn publish(&self) {
+        let job = self as *const BinJob<'a> as *mut BinJob<'static>;
+        BIN_JOB.store(job, Ordering::Release);
+    }
+
+    /// Withdraw the published job (after RENDER_BARRIER)
+    fn unpublish() {
+        BIN_JOB.store(core::ptr::null_mut(), Ordering::Release);
+    }
 
-    // 8. Signal render complete (allows worker cores to wait for next frame)
-    smp::scheduler::end_render();
+    /// Claim and bin work items until none remain
+    fn run(&self) {
+        while let Some((draw, range)) = self.chunks.get(self.next.fetch_add(1, Ordering::Relaxed)) {
+            let draw = &self.draws[*draw];
+            let mvp = self.projection * self.view * draw.model;
+            bin_triangles(
+                draw.mesh,
+                range.clone(),
+                &mvp,
+                &normal_matrix(&draw.model),
+                &self.view,
+                self.fb_width,
+                self.fb_height,
+                draw.cull,
+            );
+        }
+    }
+}
+
+/// Binning phase of the render cores (including Core 0)
+/// Bins a share of the published job, if any, then waits at BIN_BARRIER for
+/// the other render cores; the last to arrive resets the tile work queue so
+/// rasterization starts from complete bins
+pub fn bin_worker() {
+    // Safety: core 0 keeps the published job alive until all cores pass RENDER_BARRIER
+    let Some(job) = (unsafe { BIN_JOB.load(Ordering::Acquire).as_ref() }) else {
+        return; // Core 0 binned this frame alone
+    };
+    job.run();
+    smp::sync::BIN_BARRIER.wait_with(tiles::reset);
 }
 
 /// Transform mesh triangles, create ScreenTriangles, and bin them to tiles
+/// `cull` selects back-face culling; double-sided meshes pass `CullMode::None`
 /// Uses GPU batch rendering when available, falls back to software rasterization
 /// Returns the number of triangles successfully processed
 pub fn bin_mesh(
@@ -801,25 +1211,80 @@ pub fn bin_mesh(
     projection: &Mat4,
     fb_width: f32,
     fb_height: f32,
+    cull: CullMode,
 ) -> usize {
-    let mut binned = 0;
-
     // Precompute MVP matrix ONCE per mesh (instead of 3 matrix muls per vertex!)
     let mvp = *projection * *view * *model;
+    bin_triangles(mesh, 0..mesh.triangle_count(), &mvp, &normal_matrix(model), view, fb_width, fb_height, cull)
+}
+
+/// bin_mesh with the mesh's level of detail for how far `model` places it
+/// from the camera (see Mesh::select_lod)
+pub fn bin_mesh_lod(
+    mesh: &Mesh,
+    model: &Mat4,
+    camera_pos: Vec3,
+    view: &Mat4,
+    projection: &Mat4,
+    fb_width: f32,
+    fb_height: f32,
+    cull: CullMode,
+) -> usize {
+    let lod = mesh.select_lod(model_distance(model, camera_pos));
+    bin_mesh(lod, model, view, projection, fb_width, fb_height, cull)
+}
+
+/// Distance from the camera to the translation of a model matrix
+fn model_distance(model: &Mat4, camera_pos: Vec3) -> f32 {
+    model.w_axis.truncate().distance(camera_pos)
+}
+
+/// Transform, bin and count a range of a mesh's triangles (any render core)
+/// Opaque triangles are lit per pixel when the frame's render settings have
+/// a light, per vertex otherwise, and carry the mesh's material so the tile
+/// pass textures them
+fn bin_triangles(
+    mesh: &Mesh,
+    range: Range<usize>,
+    mvp: &Mat4,
+    nm: &Mat3,
+    view: &Mat4,
+    fb_width: f32,
+    fb_height: f32,
+    cull: CullMode,
+) -> usize {
+    let mut binned = 0;
+    // Normals to view space (the view is a rotation, so it is its own normal matrix)
+    let view_nm = lighting_enabled().then(|| Mat3::from_mat4(*view) * *nm);
+    let material = mesh.material as u8;
 
     // Use the simple software path - GPU batch will be used when SVGA3D is available
     // The is_enabled() check is done once at startup, not per-triangle
-    for i in 0..mesh.triangle_count() {
+    for i in range {
         if let Some((v0, v1, v2)) = mesh.get_triangle(i) {
-            // Transform and create ScreenTriangle using precomputed MVP
-            if let Some(screen_tri) = transform_and_bin_fast(
-                v0,
-                v1,
-                v2,
-                &mvp,
-                fb_width,
-                fb_height,
-            ) {
+            let alpha = mesh.triangle_alpha(i);
+            // Transform and create ScreenTriangles using precomputed MVP
+            // (clipping can split one triangle into several), lit per pixel
+            // or with per-vertex sun lighting. The blended path only does
+            // the latter, so translucent triangles always get it.
+            let screen_tris = match view_nm {
+                Some(ref view_nm) if alpha == 255 => {
+                    transform_and_bin_fast_lit(v0, v1, v2, mvp, view_nm, fb_width, fb_height, cull)
+                }
+                _ => transform_and_bin_fast(
+                    &shade_vertex(v0, nm),
+                    &shade_vertex(v1, nm),
+                    &shade_vertex(v2, nm),
+                    mvp,
+                    fb_width,
+                    fb_height,
+                    cull,
+                ),
+            };
+            for screen_tri in screen_tris.into_iter().flatten() {
+                // Translucent triangles are routed to their own bins
+                let screen_tri = ScreenTriangle { alpha, material, ..screen_tri };
+
                 // Add to frame buffer and get index
                 if let Some(tri_idx) = tiles::add_triangle(screen_tri) {
                     // Bin to overlapping tiles
@@ -834,38 +1299,70 @@ pub fn bin_mesh(
 }
 
 /// Bin mesh triangles directly to GPU batch (GPU rendering path)
-/// Transforms vertices and adds them to the GPU batch for hardware rasterization
-/// This is the GPU-accelerated alternative to bin_mesh() for software rendering
+/// Static meshes (`cache_id` set) are drawn from the GPU mesh cache with the
+/// model as the world transform. Everything else, and static meshes the cache
+/// can't take, is transformed here and added to the GPU batch for hardware
+/// rasterization. This is the GPU-accelerated alternative to bin_mesh() for
+/// software rendering
 pub fn bin_mesh_gpu(
+    mesh: &Mesh,
+    cache_id: Option<gpu_batch::MeshId>,
+    model: &Mat4,
+    view: &Mat4,
+    projection: &Mat4,
+    fb_width: f32,
+    fb_height: f32,
+    cull: CullMode,
+) -> usize {
+    let start = read_tsc();
+    GPU_MESH_TRIANGLES.fetch_add(mesh.triangle_count(), Ordering::Relaxed);
+    // Debug no-cull mode keeps back faces on the CPU path too
+    let cull = if gpu_batch::render_state().backface_cull { cull } else { CullMode::None };
+    let cached = cache_id.and_then(|id| gpu_batch::draw_cached_mesh(id, mesh, model, cull));
+    let added = cached.unwrap_or_else(|| batch_mesh_triangles(mesh, model, view, projection, fb_width, fb_height, cull));
+    record_subsystem_cycles(|b| &mut b.transform_cycles, start);
+    added
+}
+
+/// Transform, clip and cull a mesh on the CPU and add it to the GPU batch
+fn batch_mesh_triangles(
     mesh: &Mesh,
     model: &Mat4,
     view: &Mat4,
     projection: &Mat4,
     fb_width: f32,
     fb_height: f32,
+    cull: CullMode,
 ) -> usize {
     let mut added = 0;
+    let nm = normal_matrix(model);
 
     for i in 0..mesh.triangle_count() {
         if let Some((v0, v1, v2)) = mesh.get_triangle(i) {
-            // Transform and perform culling (same as software path)
-            if let Some((tv0, tv1, tv2)) = transform_triangle(
-                v0,
-                v1,
-                v2,
+            // Light, transform, clip and cull (same as software path)
+            for (tv0, tv1, tv2) in transform_triangle(
+                &shade_vertex(v0, &nm),
+                &shade_vertex(v1, &nm),
+                &shade_vertex(v2, &nm),
                 model,
                 view,
                 projection,
                 fb_width,
                 fb_height,
-            ) {
-                // Add transformed triangle to GPU batch
+                cull,
+            )
+            .into_iter()
+            .flatten()
+            {
+                // Add transformed triangle to GPU batch (z is 1/w; the GPU
+                // depth-tests the same depth it computes for cached meshes)
+                let depth = |inv_w: f32| gpu_batch::screen_depth(projection, inv_w);
                 let success = gpu_batch::add_screen_triangle(
-                    tv0.position.x, tv0.position.y, tv0.position.z,
+                    tv0.position.x, tv0.position.y, depth(tv0.position.z),
                     tv0.color.x, tv0.color.y, tv0.color.z,
-                    tv1.position.x, tv1.position.y, tv1.position.z,
+                    tv1.position.x, tv1.position.y, depth(tv1.position.z),
                     tv1.color.x, tv1.color.y, tv1.color.z,
-                    tv2.position.x, tv2.position.y, tv2.position.z,
+                    tv2.position.x, tv2.position.y, depth(tv2.position.z),
                     tv2.color.x, tv2.color.y, tv2.color.z,
                 );
 
@@ -887,7 +1384,12 @@ pub fn bin_mesh_gpu(
 /// Steals tiles from the work queue and rasterizes all triangles binned to each tile
 /// IMPORTANT: This function must always complete normally - never return early
 /// because all cores must hit the barrier after this returns
-pub fn render_worker(_rasterizer_id: u8) {
+pub fn render_worker(core_id: u8) {
+    let start_tsc = read_tsc();
+    let mut tiles_processed = 0u64;
+    let mut triangles_rasterized = 0u64;
+    let mut triangles_hiz_skipped = 0u64;
+
     // Acquire render context for this worker
     let ctx = match RenderContext::acquire() {
         Some(c) => c,
@@ -917,14 +1419,31 @@ pub fn render_worker(_rasterizer_id: u8) {
         match tile_info {
             Some((tile_idx, tile_x, tile_y, tile_w, tile_h)) => {
                 // Rasterize all triangles in this tile's bin
-                rasterize_tile(tile_idx, tile_x, tile_y, tile_w, tile_h, &ctx);
+                let (drawn, skipped) = rasterize_tile(tile_idx, tile_x, tile_y, tile_w, tile_h, &ctx);
+                triangles_rasterized += drawn as u64;
+                triangles_hiz_skipped += skipped as u64;
+                tiles_processed += 1;
+                // Core 0 also copies the last frame to the display between
+                // tiles when triple buffering
+                if core_id == 0 {
+                    gpu::present_step();
+                }
             }
             None => break, // No more tiles to process
         }
     }
+
+    smp::stats::record(
+        core_id as usize,
+        tiles_processed,
+        triangles_rasterized,
+        triangles_hiz_skipped,
+        read_tsc().wrapping_sub(start_tsc),
+    );
 }
 
 /// Rasterize all triangles binned to a specific tile
+/// Returns the number of triangles rasterized and the number skipped by Hi-Z
 fn rasterize_tile(
     tile_idx: usize,
     tile_x: i32,
@@ -932,9 +1451,10 @@ fn rasterize_tile(
     tile_w: i32,
     tile_h: i32,
     ctx: &RenderContext,
-) {
-    let bin = &TILE_BINS_LOCKFREE[tile_idx];
-    let tri_count = bin.len();
+) -> (usize, usize) {
+    let Some(bin) = tiles::opaque_bin(tile_idx) else {
+        return (0, 0); // Tile grid not initialized
+    };
 
     // Tile bounds
     let tile_min_x = tile_x;
@@ -942,19 +1462,39 @@ fn rasterize_tile(
     let tile_min_y = tile_y;
     let tile_max_y = tile_y + tile_h - 1;
 
-    // Rasterize each triangle in the bin
-    for i in 0..tri_count {
-        if let Some(tri_idx) = bin.get(i) {
-            if let Some(tri) = tiles::get_triangle(tri_idx) {
-                rasterize_screen_triangle_simple(
-                    ctx,
-                    &tri,
-                    tile_min_x,
-                    tile_max_x,
-                    tile_min_y,
-                    tile_max_y,
-                );
+    // Rasterize each triangle in the bin, skipping those hidden behind what's already drawn
+    let tris = (0..bin.len()).filter_map(|i| bin.get(i)).filter_map(tiles::get_triangle);
+    let (mut rasterized, mut skipped) =
+        rasterize_tile_opaque(ctx, tris, tile_min_x, tile_max_x, tile_min_y, tile_max_y);
+
+    // Then blend translucent triangles over the finished opaque tile, farthest first
+    let mut order = [0u16; MAX_TRIANGLES_PER_TILE];
+    let translucent_count = tiles::translucent_draw_order(tile_idx, &mut order);
+    let far_depth = if ctx.hiz_enabled() && translucent_count > 0 {
+        ctx.farthest_depth(tile_min_x, tile_max_x, tile_min_y, tile_max_y)
+    } else {
+        f32::NEG_INFINITY
+    };
+    let raster = match ctx.settings().mode {
+        RenderMode::Wireframe => draw_triangle_wireframe,
+        RenderMode::Overdraw => rasterize_screen_triangle_overdraw,
+        RenderMode::Normal | RenderMode::Tiles => rasterize_screen_triangle_blended,
+    };
+    for &tri_idx in &order[..translucent_count] {
+        if let Some(tri) = tiles::get_triangle(tri_idx) {
+            if tri.nearest_depth() < far_depth {
+                skipped += 1;
+                continue;
             }
+            raster(ctx, &tri, tile_min_x, tile_max_x, tile_min_y, tile_max_y);
+            rasterized += 1;
         }
     }
+
+    // Debug views replace or tint the finished tile
+    if BinJob::presents_debug() {
+        present_tile_debug(ctx, bin.len() + translucent_count, tile_min_x, tile_max_x, tile_min_y, tile_max_y);
+    }
+
+    (rasterized, skipped)
 }
@@ -4,50 +4,69 @@
 
 extern crate alloc;
 
-use core::sync::atomic::{AtomicBool, Ordering};
+use benchmark::{BenchmarkFrameBreakdown, BreakdownRing};
+use boot_config::{AppMode, RenderMode};
 use glam::{Mat4, Vec3};
 use renderer::mesh;
+use renderer::texture::Material;
+use renderer::voxel::MeshingOptions;
+use crate::boot;
+use crate::console;
+use crate::game::audio;
 use crate::game::input::{self, KeyState};
-use crate::game::state::{GameState, PlayerPhase, get_state, set_state, MenuAction};
+use crate::game::replay;
+use crate::game::state::{GameState, NetworkMode, PlayerPhase, get_network_mode, get_state, set_state, MenuAction, MATCH_EVENTS, SETTINGS};
 use crate::game::world::GAME_WORLD;
-use crate::graphics::framebuffer::FRAMEBUFFER;
-use crate::graphics::gpu;
+use crate::graphics::framebuffer::{self, FRAMEBUFFER};
 use crate::graphics::cursor;
+use crate::graphics::gpu;
+use crate::graphics::gpu_batch;
 use crate::graphics::pipeline::{look_at, perspective};
-use crate::graphics::rasterizer::RenderContext;
+use crate::graphics::post;
+use crate::graphics::rasterizer::{render_mode, set_render_mode, RenderContext};
+use crate::graphics::tiles;
+use crate::graphics::ui::{colors, Rect};
+use crate::graphics::ui::panel::{draw_panel_blended, draw_panel_raw, PanelBorder};
 use crate::graphics::vsync::FrameTimer;
+use crate::graphics::zbuffer::{self, DepthMode, ZBUFFER};
+use crate::memory;
 use crate::net;
+use crate::smp;
 use crate::ui;
 use crate::{halt_loop, read_tsc};
 use crate::serial_println;
 
+use super::hud;
 use super::input::get_menu_action;
 use super::render::{
-    render_game_frame, render_lobby_frame, render_menu_frame, render_test_map_frame,
-    set_gpu_batch_available, GPU_BATCH_AVAILABLE,
+    gpu_mesh_triangles, gpu_path_active, present_frame, record_subsystem_cycles, render_game_frame, render_lobby_frame,
+    render_menu_frame, render_test_map_frame, set_benchmark_progress, set_gpu_batch_available, take_frame_breakdown,
+    SKY_COLOR,
 };
 use super::terrain::{create_3d_terrain, sample_terrain_height};
 
-/// Global benchmark mode flag
-static BENCHMARK_MODE: AtomicBool = AtomicBool::new(false);
+/// Frames between benchmark FPS reports
+const BENCHMARK_REPORT_FRAMES: u32 = 60;
 
-/// Global test mode flag
-static TEST_MODE: AtomicBool = AtomicBool::new(false);
+/// Benchmark frames rendered before measuring starts (one-time allocations
+/// and cold caches make the first frames unrepresentative)
+const BENCHMARK_WARMUP_FRAMES: u32 = 60;
 
-/// Set benchmark mode
-pub fn set_benchmark_mode(enabled: bool) {
-    BENCHMARK_MODE.store(enabled, Ordering::SeqCst);
-}
+/// Opacity of the battle bus windows
+const BUS_GLASS_ALPHA: u8 = 96;
 
-/// Set test mode
-pub fn set_test_mode(enabled: bool) {
-    TEST_MODE.store(enabled, Ordering::SeqCst);
-}
+/// Frame at which the test harness dumps a screenshot for visual regression checks
+/// (auto-start happens after frame 10, so the scene has settled by then)
+const TEST_SCREENSHOT_FRAME: u32 = 120;
+
+/// Frame at which benchmark runs dump a screenshot of the benchmark scene
+const BENCHMARK_SCREENSHOT_FRAME: u32 = 300;
 
 /// Main game loop entry point (runs on Core 0)
 /// Called from kernel after hardware initialization is complete.
-pub fn run(fb_width: usize, fb_height: usize, gpu_batch_available: bool) -> ! {
+pub fn run(mut fb_width: usize, mut fb_height: usize, gpu_batch_available: bool) -> ! {
     set_gpu_batch_available(gpu_batch_available);
+    input::set_mouse_bounds(fb_width, fb_height);
 
     let mut frame_count = 0u32;
     let mut rotation = 0.0f32;
@@ -56,8 +75,8 @@ pub fn run(fb_width: usize, fb_height: usize, gpu_batch_available: bool) -> ! {
     // Uses HLT instruction for CPU idle when waiting, reducing power consumption
     let mut frame_timer = FrameTimer::new();
 
-    // TSC frequency for benchmark reporting (assume ~2GHz for QEMU)
-    let tsc_per_second: u64 = 2_000_000_000;
+    // TSC frequency for benchmark reporting (calibrated at boot)
+    let tsc_per_second = crate::drivers::pit::tsc_per_second();
 
     // Create reusable meshes for game entities using VOXEL MODELS
     // Terrain: 3D heightmap with proper hills
@@ -67,35 +86,40 @@ pub fn run(fb_width: usize, fb_height: usize, gpu_batch_available: bool) -> ! {
 
     // Player mesh from detailed voxel model (use default customization for now)
     let default_custom = renderer::voxel::CharacterCustomization::default();
-    let player_mesh = renderer::voxel_models::create_player_model(&default_custom).to_mesh(0.15);
+    let player_mesh = renderer::voxel_models::create_player_model(&default_custom).to_mesh(0.15, MeshingOptions::SHADED);
 
     // Building pieces from voxel models
-    let wall_mesh = renderer::voxel_models::create_wall_wood().to_mesh(0.25);
+    let wall_mesh = renderer::voxel_models::create_wall_wood().to_mesh(0.25, MeshingOptions::LIT).with_material(Material::Wood);
 
-    // Battle bus from voxel model (includes balloon)
-    let bus_mesh = renderer::voxel_models::create_battle_bus().to_mesh(0.30);
+    // Battle bus from voxel model (includes balloon); windows are see-through glass
+    let mut bus_model = renderer::voxel_models::create_battle_bus();
+    let bus_glass = bus_model.extract_color(renderer::voxel::palette::GLASS).to_mesh(0.30, MeshingOptions::SHADED);
+    let mut bus_mesh = bus_model.to_mesh(0.30, MeshingOptions::SHADED);
+    bus_mesh.append_translucent(&bus_glass, BUS_GLASS_ALPHA);
 
     // Additional meshes for complete game rendering
-    let glider_mesh = renderer::voxel_models::create_glider_model(0).to_mesh(0.15);
-    let tree_pine_mesh = renderer::voxel_models::create_pine_tree().to_mesh(0.5);
-    let tree_oak_mesh = renderer::voxel_models::create_oak_tree().to_mesh(0.5);
-    let rock_mesh = renderer::voxel_models::create_rock(0).to_mesh(0.4);
-    let chest_mesh = renderer::voxel_models::create_chest().to_mesh(0.15);
+    let glider_mesh = renderer::voxel_models::create_glider_model(0).to_mesh(0.15, MeshingOptions::LIT);
+    let tree_pine_mesh = renderer::voxel_models::create_pine_tree().to_mesh(0.5, MeshingOptions::SHADED);
+    let tree_oak_mesh = renderer::voxel_models::create_oak_tree().to_mesh(0.5, MeshingOptions::SHADED);
+    let rock_mesh = renderer::voxel_models::create_rock(0).to_mesh(0.4, MeshingOptions::LIT);
+    let chest_mesh = renderer::voxel_models::create_chest().to_mesh(0.15, MeshingOptions::LIT);
     let house_mesh = renderer::map_mesh::create_house_mesh_simple(Vec3::new(0.7, 0.6, 0.5));
     let storm_wall_mesh = mesh::create_storm_wall(24, 200.0); // 24 segments for performance
+    let water_mesh = mesh::create_water_plane();
+    let stump_mesh = mesh::create_stump(8, 0.35, 0.5);
 
     // LOD meshes for distant objects (much fewer triangles)
     // Scale factors compensate for smaller voxel dimensions to match world-space size
     // Full pine: 10 voxels * 0.5 = 5 units; LOD pine: 4 voxels * 1.25 = 5 units
-    let tree_pine_lod = renderer::voxel_models::create_pine_tree_lod().to_mesh(1.25);
-    let tree_oak_lod = renderer::voxel_models::create_oak_tree_lod().to_mesh(1.2);
-    let rock_lod = renderer::voxel_models::create_rock_lod().to_mesh(0.8);
-    let chest_lod = renderer::voxel_models::create_chest_lod().to_mesh(0.3);
+    let tree_pine_lod = renderer::voxel_models::create_pine_tree_lod().to_mesh(1.25, MeshingOptions::LIT);
+    let tree_oak_lod = renderer::voxel_models::create_oak_tree_lod().to_mesh(1.2, MeshingOptions::LIT);
+    let rock_lod = renderer::voxel_models::create_rock_lod().to_mesh(0.8, MeshingOptions::LIT);
+    let chest_lod = renderer::voxel_models::create_chest_lod().to_mesh(0.3, MeshingOptions::LIT);
 
     // Weapon meshes from detailed voxel models
-    let shotgun_mesh = renderer::voxel_models::create_shotgun_model().to_mesh(0.08);
-    let ar_mesh = renderer::voxel_models::create_ar_model().to_mesh(0.08);
-    let sniper_mesh = renderer::voxel_models::create_sniper_model().to_mesh(0.08);
+    let shotgun_mesh = renderer::voxel_models::create_shotgun_model().to_mesh(0.08, MeshingOptions::LIT);
+    let ar_mesh = renderer::voxel_models::create_ar_model().to_mesh(0.08, MeshingOptions::LIT);
+    let sniper_mesh = renderer::voxel_models::create_sniper_model().to_mesh(0.08, MeshingOptions::LIT);
 
     serial_println!("Meshes: terrain={} player={} wall={} bus={} glider={} tree={} chest={}",
         terrain.triangle_count(), player_mesh.triangle_count(),
@@ -103,13 +127,19 @@ pub fn run(fb_width: usize, fb_height: usize, gpu_batch_available: bool) -> ! {
         glider_mesh.triangle_count(), tree_pine_mesh.triangle_count(),
         chest_mesh.triangle_count());
 
+    // Render the map texture now rather than on the first in-game frame
+    if let Some(world) = GAME_WORLD.read().as_ref() {
+        hud::ensure_minimap_texture(world, &terrain, &house_mesh);
+    }
+
     // Camera setup
     // Far plane increased to 3000.0 to see across the 2000x2000 map from bus height
     let aspect = fb_width as f32 / fb_height as f32;
     let fov_radians = core::f32::consts::PI / 3.0;
-    let projection = perspective(fov_radians, aspect, 0.5, 3000.0);
+    let mut projection = perspective(fov_radians, aspect, 0.5, 3000.0);
+    let mut mode_generation = gpu::mode_generation();
 
-    serial_println!("Parallel rendering: 4 cores active");
+    serial_println!("Parallel rendering: {} cores active", smp::scheduler::render_core_count());
 
     // Menu state
     let mut main_menu = ui::main_menu::MainMenuScreen::new(fb_width, fb_height);
@@ -125,9 +155,7 @@ pub fn run(fb_width: usize, fb_height: usize, gpu_batch_available: bool) -> ! {
 
     // Local player tracking
     let mut local_player_id: Option<u8> = None;
-    let mut player_yaw: f32 = 0.0;
-    let mut player_pitch: f32 = 0.0;
-    let mut input_sequence: u32 = 0;
+    let mut controls = LocalControls::default();
 
     // Previous mouse state for click detection
     let mut prev_mouse_left = false;
@@ -136,12 +164,22 @@ pub fn run(fb_width: usize, fb_height: usize, gpu_batch_available: bool) -> ! {
     let mut countdown_timer = 0.0f32;
 
     // Check for benchmark/test mode - auto-start game
-    let benchmark = BENCHMARK_MODE.load(Ordering::SeqCst);
-    let test_mode = TEST_MODE.load(Ordering::SeqCst);
-    let auto_start = benchmark || test_mode;
+    let mode = boot::app_mode();
+    let benchmark = mode == AppMode::Benchmark;
+    let test_mode = mode == AppMode::TestHarness;
+    let replay_mode = mode == AppMode::Replay;
+    let auto_start = benchmark || test_mode || replay_mode;
     let mut auto_started = false;
     let mut benchmark_frames = 0u32;
+    let mut benchmark_warmup = BENCHMARK_WARMUP_FRAMES;
     let mut benchmark_start_time = 0u64;
+    let mut benchmark_breakdowns = BreakdownRing::new();
+    let mut depth_ab = DepthModeAb::new();
+    let mut buffering_ab = BufferingAb::new(gpu::is_triple_buffered());
+    let mut mesh_cache_ab = MeshCacheAb::new(gpu_batch_available);
+    let mut perspective_ab = PerspectiveAb::new();
+    // Present cost summed over the frames between FPS log lines
+    let mut present_cycles = 0u64;
 
     loop {
         // Auto-start mode (benchmark or test): start game after a few frames
@@ -149,14 +187,20 @@ pub fn run(fb_width: usize, fb_height: usize, gpu_batch_available: bool) -> ! {
             auto_started = true;
             benchmark_start_time = read_tsc();
 
-            if test_mode {
+            if replay_mode {
+                start_replay(&mut local_player_id);
+            } else if test_mode {
                 serial_println!("TEST MODE: Starting with all items spawned...");
             } else {
+                run_network_benchmark(tsc_per_second);
+                run_memory_benchmark(tsc_per_second);
+                run_panel_benchmark(tsc_per_second);
+                run_fire_rate_check();
                 serial_println!("BENCHMARK: Starting InGame test...");
             }
 
-            // Create a local player and put them in the game
-            if let Some(world) = GAME_WORLD.lock().as_mut() {
+            // Create a local player and put them in the game (a replay brings its own players)
+            if let Some(world) = GAME_WORLD.write().as_mut().filter(|_| !replay_mode) {
                 // Add a player if none exists
                 if world.players.is_empty() {
                     use smoltcp::wire::Ipv4Address;
@@ -199,25 +243,91 @@ pub fn run(fb_width: usize, fb_height: usize, gpu_batch_available: bool) -> ! {
             set_state(GameState::InGame);
         }
 
-        // Benchmark: report FPS every 60 frames
-        if benchmark && auto_started {
+        // Subsystem timings of the previous frame (warmup frames are discarded)
+        let frame_breakdown = take_frame_breakdown();
+        present_cycles += frame_breakdown.present_cycles;
+
+        // Benchmark: warm up, then report FPS every BENCHMARK_REPORT_FRAMES frames
+        // (progress bar shows the window and stays empty during warmup)
+        if benchmark && auto_started && benchmark_warmup > 0 {
+            benchmark_warmup -= 1;
+            set_benchmark_progress(0.0);
+            if benchmark_warmup == 0 {
+                benchmark_start_time = read_tsc();
+                depth_ab.start(benchmark_start_time);
+                buffering_ab.start(benchmark_start_time);
+                mesh_cache_ab.start();
+                perspective_ab.start();
+                serial_println!("BENCHMARK: {} warmup frames done, recording", BENCHMARK_WARMUP_FRAMES);
+            }
+        } else if benchmark && auto_started {
             benchmark_frames += 1;
-            if benchmark_frames % 60 == 0 {
+            benchmark_breakdowns.push(frame_breakdown);
+            depth_ab.record(&frame_breakdown);
+            buffering_ab.record(&frame_breakdown);
+            mesh_cache_ab.record(&frame_breakdown);
+            perspective_ab.record(&frame_breakdown);
+            set_benchmark_progress((benchmark_frames % BENCHMARK_REPORT_FRAMES) as f32 / BENCHMARK_REPORT_FRAMES as f32);
+            if benchmark_frames.is_multiple_of(BENCHMARK_REPORT_FRAMES) {
                 let elapsed = read_tsc().wrapping_sub(benchmark_start_time);
                 let secs = elapsed as f64 / tsc_per_second as f64;
                 let avg_fps = benchmark_frames as f64 / secs;
                 serial_println!("BENCHMARK: {} frames in {:.2}s = {:.1} avg FPS (current: {})",
                     benchmark_frames, secs, avg_fps, frame_timer.fps());
+
+                // Receive batching: > 1 pkts/batch means one NIC lock served several packets
+                let rx = net::device::take_rx_batch_stats();
+                serial_println!("BENCHMARK: NET {} pkts, {:.2} pkts/poll, {:.2} pkts/batch",
+                    rx.packets, rx.packets_per_poll(), rx.packets_per_batch());
+
+                // Binning cost of the last frame (guard-band clipping keeps off-screen tiles out)
+                serial_println!("BENCHMARK: BIN {} triangles, {} tile-triangle pairs, {} dropped",
+                    tiles::triangle_count(), tiles::binned_pair_count(), tiles::dropped_this_frame());
+
+                // Where the frame goes, averaged over the last BREAKDOWN_FRAMES frames
+                let us = |cycles: u64| cycles / (tsc_per_second / 1_000_000);
+                let avg = benchmark_breakdowns.average();
+                serial_println!("BENCHMARK_SUBSYSTEM:transform:{}us rasterize:{}us hud:{}us network:{}us physics:{}us present:{}us",
+                    us(avg.transform_cycles), us(avg.rasterize_cycles), us(avg.hud_cycles),
+                    us(avg.network_cycles), us(avg.physics_cycles), us(avg.present_cycles));
+
+                depth_ab.report(read_tsc(), tsc_per_second);
+                buffering_ab.report(read_tsc(), tsc_per_second);
+                mesh_cache_ab.report(tsc_per_second);
+                perspective_ab.report(tsc_per_second);
+                run_fog_pass_benchmark(tsc_per_second);
             }
         }
 
+        // Serial console commands (e.g. `screenshot`)
+        console::poll();
+
+        // End and start PC speaker tones
+        audio::update();
+
+        // Display mode switched (settings screen): everything that cached the
+        // old size follows, and the projection takes the new aspect ratio
+        if gpu::mode_generation() != mode_generation {
+            mode_generation = gpu::mode_generation();
+            (fb_width, fb_height) = gpu::dimensions();
+            projection = perspective(fov_radians, fb_width as f32 / fb_height as f32, 0.5, 3000.0);
+            main_menu.resize(fb_width, fb_height);
+            settings_screen.resize(fb_width, fb_height);
+            customization_screen.resize(fb_width, fb_height);
+            server_select_screen.resize(fb_width, fb_height);
+            fortnite_lobby.resize(fb_width, fb_height);
+            test_map_screen.resize(fb_width, fb_height);
+            lobby_screen.resize(fb_width, fb_height);
+            input::set_mouse_bounds(fb_width, fb_height);
+        }
+
         // Poll keyboard
         input::poll_keyboard();
         let key_state = input::KEY_STATE.lock().clone();
 
         // Sync local player ID from world if not set
         if local_player_id.is_none() {
-            if let Some(world) = GAME_WORLD.lock().as_ref() {
+            if let Some(world) = GAME_WORLD.read().as_ref() {
                 local_player_id = world.local_player_id;
             }
         }
@@ -261,6 +371,11 @@ pub fn run(fb_width: usize, fb_height: usize, gpu_batch_available: bool) -> ! {
                 if let Some(new_state) = settings_screen.update(menu_action) {
                     set_state(new_state);
                 }
+                // A new resolution is drawn from the next frame, once the
+                // screens have been resized to it
+                if gpu::mode_generation() != mode_generation {
+                    continue;
+                }
 
                 // Render settings
                 render_menu_frame(fb_width, fb_height, |ctx| {
@@ -304,7 +419,7 @@ pub fn run(fb_width: usize, fb_height: usize, gpu_batch_available: bool) -> ! {
                 if countdown_timer <= 0.0 {
                     set_state(GameState::BusPhase);
                     // Spawn bots for single-player mode
-                    if let Some(world) = GAME_WORLD.lock().as_mut() {
+                    if let Some(world) = GAME_WORLD.write().as_mut() {
                         world.spawn_bots(10); // 10 bots for a battle
                     }
                 } else {
@@ -326,11 +441,15 @@ pub fn run(fb_width: usize, fb_height: usize, gpu_batch_available: bool) -> ! {
                 if let Some(new_state) = test_map_screen.update(menu_action) {
                     set_state(new_state);
                 }
+                // Tab switches between the naive and greedy mesher
+                if key_state.tab && !prev_key_state.tab {
+                    test_map_screen.greedy = !test_map_screen.greedy;
+                }
 
                 // Render test map with 3D model preview
                 render_test_map_frame(
                     fb_width, fb_height,
-                    &test_map_screen,
+                    &mut test_map_screen,
                     &projection,
                 );
             }
@@ -341,9 +460,7 @@ pub fn run(fb_width: usize, fb_height: usize, gpu_batch_available: bool) -> ! {
                     &prev_key_state,
                     menu_action,
                     &mut local_player_id,
-                    &mut player_yaw,
-                    &mut player_pitch,
-                    &mut input_sequence,
+                    &mut controls,
                     current_state,
                     fb_width,
                     fb_height,
@@ -358,6 +475,8 @@ pub fn run(fb_width: usize, fb_height: usize, gpu_batch_available: bool) -> ! {
                     &chest_mesh,
                     &house_mesh,
                     &storm_wall_mesh,
+                    &water_mesh,
+                    &stump_mesh,
                     &tree_pine_lod,
                     &tree_oak_lod,
                     &rock_lod,
@@ -377,22 +496,41 @@ pub fn run(fb_width: usize, fb_height: usize, gpu_batch_available: bool) -> ! {
                 }
 
                 // Render victory screen
+                let winner_eliminations = winner_id.map_or(0, |id| MATCH_EVENTS.lock().eliminations_by(id));
                 render_menu_frame(fb_width, fb_height, |ctx| {
-                    ui::game_ui::draw_victory(ctx, fb_width, fb_height, winner_id);
+                    ui::game_ui::draw_victory(ctx, fb_width, fb_height, winner_id, winner_eliminations);
                 });
             }
         }
 
+        // Benchmark: per-core tile/triangle/cycle breakdown for this frame
+        if benchmark && auto_started && benchmark_warmup == 0 {
+            smp::stats::print_render_stats();
+        }
+
+        // Test harness and benchmark: capture one frame for visual regression
+        if auto_started
+            && ((test_mode && frame_count == TEST_SCREENSHOT_FRAME)
+                || (benchmark && frame_count == BENCHMARK_SCREENSHOT_FRAME))
+        {
+            framebuffer::dump_screenshot();
+        }
+
         frame_count = frame_count.wrapping_add(1);
 
         // End frame - handles vsync/frame timing with HLT for CPU idle
         let on_time = frame_timer.end_frame();
 
-        // Log FPS periodically
+        // Log FPS periodically, with the average present time (what dirty
+        // rectangles save on mostly static screens like settings)
         let current_fps = frame_timer.fps();
-        if frame_count % 60 == 0 && current_fps > 0 {
-            serial_println!("FPS: {} (state: {:?}) vsync:{} on_time:{}",
-                current_fps, current_state, frame_timer.vsync_enabled(), on_time);
+        if frame_count % 60 == 0 {
+            if current_fps > 0 {
+                let present_us = present_cycles / 60 / (tsc_per_second / 1_000_000).max(1);
+                serial_println!("FPS: {} (state: {:?}) vsync:{} on_time:{} present:{}us",
+                    current_fps, current_state, frame_timer.vsync_enabled(), on_time, present_us);
+            }
+            present_cycles = 0;
         }
 
         // Begin next frame timing
@@ -433,7 +571,7 @@ fn handle_party_lobby(
 
             // Add local player
             *local_player_id = {
-                let mut world = GAME_WORLD.lock();
+                let mut world = GAME_WORLD.write();
                 if let Some(w) = world.as_mut() {
                     let id = w.add_player("LocalPlayer", smoltcp::wire::Ipv4Address::new(127, 0, 0, 1), 5000);
                     w.local_player_id = id;
@@ -459,16 +597,18 @@ fn handle_party_lobby(
     fortnite_lobby.draw_ui_only(&ctx, fb_width, fb_height, true);
     drop(ctx);
 
-    // Draw cursor and present
-    {
-        let fb_guard = FRAMEBUFFER.lock();
-        if let Some(fb) = fb_guard.as_ref() {
-            let mouse = input::get_mouse_state();
-            cursor::draw_cursor(fb, mouse.x, mouse.y);
-            drop(fb_guard);
-            gpu::present();
-        }
-    }
+    // Present with the mouse cursor on top
+    cursor::set_visible(true);
+    present_frame();
+}
+
+/// Camera angles and input numbering of the local player, kept across frames
+#[derive(Debug, Clone, Copy, Default)]
+struct LocalControls {
+    yaw: f32,
+    pitch: f32,
+    /// Sequence number of the last ClientInput sent
+    input_sequence: u32,
 }
 
 /// Handle gameplay state (BusPhase and InGame)
@@ -477,9 +617,7 @@ fn handle_gameplay(
     prev_key_state: &KeyState,
     menu_action: MenuAction,
     local_player_id: &mut Option<u8>,
-    player_yaw: &mut f32,
-    player_pitch: &mut f32,
-    input_sequence: &mut u32,
+    controls: &mut LocalControls,
     current_state: GameState,
     fb_width: usize,
     fb_height: usize,
@@ -494,6 +632,8 @@ fn handle_gameplay(
     chest_mesh: &mesh::Mesh,
     house_mesh: &mesh::Mesh,
     storm_wall_mesh: &mesh::Mesh,
+    water_mesh: &mesh::Mesh,
+    stump_mesh: &mesh::Mesh,
     // LOD meshes for distant objects
     tree_pine_lod: &mesh::Mesh,
     tree_oak_lod: &mesh::Mesh,
@@ -513,27 +653,67 @@ fn handle_gameplay(
     // Get mouse state for camera control
     let mouse = input::get_mouse_state();
 
-    // Apply keyboard and mouse input to local player
-    if let Some(id) = *local_player_id {
+    // Debug: F3 toggles wireframe rendering, F4 cycles every render mode
+    if key_state.f3 && !prev_key_state.f3 {
+        let mode = if render_mode() == RenderMode::Wireframe { RenderMode::Normal } else { RenderMode::Wireframe };
+        set_render_mode(mode);
+        serial_println!("DEBUG: render mode {}", mode.label());
+    }
+    if key_state.f4 && !prev_key_state.f4 {
+        let mode = render_mode().next();
+        set_render_mode(mode);
+        serial_println!("DEBUG: render mode {}", mode.label());
+    }
+
+    // Debug: F5/F6 toggle backface culling and depth testing on the GPU path
+    if (key_state.f5 && !prev_key_state.f5) || (key_state.f6 && !prev_key_state.f6) {
+        let mut state = gpu_batch::render_state();
+        if key_state.f5 && !prev_key_state.f5 {
+            state.backface_cull = !state.backface_cull;
+        }
+        if key_state.f6 && !prev_key_state.f6 {
+            state.depth_test = !state.depth_test;
+        }
+        gpu_batch::set_render_state(state);
+        serial_println!("DEBUG: GPU cull {} depth test {}",
+            if state.backface_cull { "on" } else { "off" },
+            if state.depth_test { "on" } else { "off" });
+    }
+
+    // Debug: F12 dumps the last rendered frame over serial
+    if key_state.f12 && !prev_key_state.f12 {
+        framebuffer::dump_screenshot();
+    }
+
+    // M switches between the minimap and the fullscreen map
+    if key_state.m && !prev_key_state.m {
+        hud::toggle_fullscreen_map();
+    }
+
+    // Apply keyboard and mouse input to local player (a replay only plays back)
+    let replaying = replay::is_playing();
+    // As a client the input is predicted locally, then sent to the server
+    let mut local_input = None;
+    if let Some(id) = local_player_id.filter(|_| !replaying) {
         // Mouse look sensitivity (adjusted for smooth camera)
         const MOUSE_SENSITIVITY: f32 = 0.002;
 
         // Update camera rotation with mouse movement
         // Invert X for proper third-person camera orbit (mouse right = look right)
-        *player_yaw -= mouse.delta_x as f32 * MOUSE_SENSITIVITY;
-        *player_pitch -= mouse.delta_y as f32 * MOUSE_SENSITIVITY;
+        controls.yaw -= mouse.delta_x as f32 * MOUSE_SENSITIVITY;
+        controls.pitch -= mouse.delta_y as f32 * MOUSE_SENSITIVITY;
 
         // Clamp pitch to prevent camera flipping (roughly -85 to +85 degrees)
-        *player_pitch = player_pitch.clamp(-1.48, 1.48);
+        controls.pitch = controls.pitch.clamp(-1.48, 1.48);
 
         // Reset mouse deltas after reading (important!)
         input::reset_mouse_deltas();
 
         // Create input from keyboard state
-        *input_sequence += 1;
+        controls.input_sequence += 1;
         let input = protocol::packets::ClientInput {
             player_id: id,
-            sequence: *input_sequence,
+            sequence: controls.input_sequence,
             forward: if key_state.w { 1 } else if key_state.s { -1 } else { 0 },
             strafe: if key_state.a { 1 } else if key_state.d { -1 } else { 0 },
             jump: key_state.space,
@@ -541,13 +721,14 @@ fn handle_gameplay(
             fire: mouse.left_button || key_state.shift,
             build: key_state.b || mouse.right_button,
             exit_bus: key_state.space,
-            yaw: (player_yaw.to_degrees() * 100.0) as i16,
-            pitch: (player_pitch.to_degrees() * 100.0) as i16,
+            yaw: (controls.yaw.to_degrees() * 100.0) as i16,
+            pitch: (controls.pitch.to_degrees() * 100.0) as i16,
         };
 
         // Apply input to game world
-        if let Some(world) = GAME_WORLD.lock().as_mut() {
+        if let Some(world) = GAME_WORLD.write().as_mut() {
             world.apply_input(id, &input);
+            local_input = Some(input);
 
             // Handle weapon slot selection (1-5 keys)
             if let Some(player) = world.get_player_mut(id) {
@@ -573,6 +754,11 @@ fn handle_gameplay(
             if key_state.e && !prev_key_state.e {
                 world.try_pickup(id);
             }
+
+            // Drop selected weapon (Q key)
+            if key_state.q && !prev_key_state.q {
+                world.drop_selected_weapon(id);
+            }
         }
     }
 
@@ -580,49 +766,478 @@ fn handle_gameplay(
     input::reset_mouse_deltas();
 
     // Update game world physics and check for victory
-    if let Some(world) = GAME_WORLD.lock().as_mut() {
-        world.update(1.0 / 60.0);
-
-        // Transition from BusPhase to InGame when bus finishes or all players have jumped
-        if current_state == GameState::BusPhase {
-            let all_jumped = world.players.iter().all(|p| p.phase != PlayerPhase::OnBus);
-            if !world.bus.active || all_jumped {
-                set_state(GameState::InGame);
+    let physics_start = read_tsc();
+    if let Some(world) = GAME_WORLD.write().as_mut() {
+        // A replay steps through recorded snapshots instead of simulating
+        if replaying {
+            if replay::advance_playback(world, 1.0 / 60.0) == Some(false) {
+                serial_println!("REPLAY: finished");
+                set_state(GameState::Victory { winner_id: world.check_victory() });
+            }
+        } else {
+            world.update(1.0 / 60.0);
+            replay::record(world);
+            if let (Some(input), NetworkMode::Client { .. }) = (&local_input, get_network_mode()) {
+                world.record_prediction(input.clone(), 1.0 / 60.0);
             }
-        }
 
-        // Check for victory condition (skip in benchmark mode)
-        if !BENCHMARK_MODE.load(Ordering::Relaxed) {
-            if let Some(id) = world.check_victory() {
-                set_state(GameState::Victory { winner_id: Some(id) });
+            // Transition from BusPhase to InGame when bus finishes or all players have jumped
+            if current_state == GameState::BusPhase {
+                let all_jumped = world.players.iter().all(|p| p.phase != PlayerPhase::OnBus);
+                if !world.bus.active || all_jumped {
+                    set_state(GameState::InGame);
+                }
+            }
+
+            // Check for victory condition (skip in benchmark mode)
+            if boot::app_mode() != AppMode::Benchmark {
+                if let Some(id) = world.check_victory() {
+                    set_state(GameState::Victory { winner_id: Some(id) });
+                    replay::finish_recording();
+                }
             }
         }
     }
 
+    record_subsystem_cycles(|b| &mut b.physics_cycles, physics_start);
+
     // Process network (less frequently)
+    let network_start = read_tsc();
+    if let (Some(input), NetworkMode::Client { server_ip: [a, b, c, d], .. }) = (&local_input, get_network_mode()) {
+        net::protocol::send_input(input, smoltcp::wire::Ipv4Address::new(a, b, c, d));
+    }
     if frame_count % 10 == 0 {
         net::protocol::process_incoming();
         net::protocol::broadcast_world_state();
+        net::protocol::broadcast_events();
     }
 
     // Poll network stack every frame
     net::stack::poll(frame_count as i64);
+    record_subsystem_cycles(|b| &mut b.network_cycles, network_start);
 
     // Render game world
     render_game_frame(
         fb_width, fb_height,
         terrain, player_mesh, wall_mesh, bus_mesh,
         glider_mesh, tree_pine_mesh, tree_oak_mesh, rock_mesh,
-        chest_mesh, house_mesh, storm_wall_mesh,
+        chest_mesh, house_mesh, storm_wall_mesh, water_mesh, stump_mesh,
         tree_pine_lod, tree_oak_lod, rock_lod, chest_lod,
         projection, *local_player_id, rotation,
         frame_timer.fps(),
     );
 }
 
+/// `benchmark=network`: measure the NIC packet rate in loopback before the render benchmark
+fn run_network_benchmark(tsc_per_second: u64) {
+    let config = boot::config();
+    if !config.network_benchmark {
+        return;
+    }
+
+    serial_println!(
+        "BENCHMARK: network loopback for {}s (TX checksum offload {})...",
+        config.benchmark_duration,
+        if config.checksum_offload { "on" } else { "off, csum=off" }
+    );
+    match net::bench::run(config.benchmark_duration, tsc_per_second) {
+        Some(results) => serial_println!(
+            "BENCHMARK_NET: {:.0} round trips/s, {:.0} pkts/s, {:.1} MB/s, {} dropped",
+            results.round_trips_per_second,
+            results.packets_per_second,
+            results.bytes_per_second / 1_000_000.0,
+            results.dropped_packets
+        ),
+        None => serial_println!("BENCHMARK: no network device, skipping network benchmark"),
+    }
+}
+
+/// `benchmark=memory`: stress the heap allocator before the render benchmark
+fn run_memory_benchmark(tsc_per_second: u64) {
+    let config = boot::config();
+    if !config.memory_benchmark {
+        return;
+    }
+
+    serial_println!("BENCHMARK: heap allocator stress for {}s...", config.benchmark_duration);
+    let results = memory::bench::run(config.benchmark_duration, tsc_per_second);
+    let stats = memory::allocator::stats();
+    serial_println!(
+        "BENCHMARK_MEM: {:.0} allocs/s, {:.0}ns free latency, {:.1}% largest free block",
+        results.allocations_per_second,
+        results.free_latency_ns,
+        results.fragmentation_percent
+    );
+    serial_println!(
+        "BENCHMARK_MEM: heap {} KB used of {} KB, {} allocations since boot",
+        stats.used_bytes / 1024,
+        stats.total_bytes / 1024,
+        stats.alloc_count
+    );
+}
+
+/// Panels drawn per variant by the UI panel micro-benchmark
+const PANEL_BENCH_ITERATIONS: usize = 200;
+
+/// Time opaque against alpha-blended panel fills on the back buffer
+/// The game frame drawn afterwards overwrites whatever this leaves behind
+fn run_panel_benchmark(tsc_per_second: u64) {
+    let fb_guard = FRAMEBUFFER.lock();
+    let Some(fb) = fb_guard.as_ref() else {
+        return;
+    };
+    let rect = Rect::new(0, 0, fb.width.min(512), fb.height.min(256));
+    let border = PanelBorder::new(colors::PANEL_BORDER, 2).with_shadow(2);
+
+    let time = |draw: &dyn Fn()| {
+        let start = read_tsc();
+        for _ in 0..PANEL_BENCH_ITERATIONS {
+            draw();
+        }
+        read_tsc().wrapping_sub(start).max(1)
+    };
+    let opaque = time(&|| draw_panel_raw(fb, rect.x, rect.y, rect.width, rect.height, colors::PANEL_BG));
+    let blended = time(&|| draw_panel_blended(fb, rect, colors::PANEL_BG, 0.6, 8, border));
+
+    let megapixels = (rect.width * rect.height * PANEL_BENCH_ITERATIONS) as f64 / 1_000_000.0;
+    let rate = |cycles: u64| megapixels * tsc_per_second as f64 / cycles as f64;
+    serial_println!(
+        "BENCHMARK_UI: {}x{} panel, opaque {:.1} Mpix/s, blended {:.1} Mpix/s ({:.0} cycles/panel)",
+        rect.width,
+        rect.height,
+        rate(opaque),
+        rate(blended),
+        blended as f64 / PANEL_BENCH_ITERATIONS as f64
+    );
+}
+
+/// Screen-space fog passes timed per benchmark report
+const FOG_BENCH_PASSES: u64 = 4;
+
+/// Time the screen-space fog pass (`post::apply_fog`) over the last frame
+///
+/// The game fogs pixels while rasterizing, which is part of the rasterize
+/// time; this is what a separate full-screen pass would add to each frame.
+/// Skipped with fog off and on the GPU batch path, which fogs on the host.
+fn run_fog_pass_benchmark(tsc_per_second: u64) {
+    let Some((start, end)) = SETTINGS.lock().fog.range() else {
+        return;
+    };
+    if gpu_path_active() {
+        return;
+    }
+    let fb_guard = FRAMEBUFFER.lock();
+    let zb_guard = ZBUFFER.lock();
+    let (Some(fb), Some(zb)) = (fb_guard.as_ref(), zb_guard.as_ref()) else {
+        return;
+    };
+
+    // The frame has been presented; the next one clears the back buffer
+    let begin = read_tsc();
+    for _ in 0..FOG_BENCH_PASSES {
+        post::apply_fog(fb, zb, start, end, SKY_COLOR);
+    }
+    let cycles = (read_tsc().wrapping_sub(begin) / FOG_BENCH_PASSES).max(1);
+    let megapixels = (fb.width * fb.height) as f64 / 1_000_000.0;
+    serial_println!(
+        "BENCHMARK_FOG: post-process pass {}us/frame ({:.1} Mpix/s) at {}x{}",
+        cycles / (tsc_per_second / 1_000_000),
+        megapixels * tsc_per_second as f64 / cycles as f64,
+        fb.width,
+        fb.height
+    );
+}
+
+/// Game ticks of held fire simulated per weapon by the fire rate check
+const FIRE_CHECK_TICKS: u32 = 1000;
+
+/// Hold the trigger on every weapon type for FIRE_CHECK_TICKS ticks and check
+/// the shot count against the weapon's tick interval
+/// Ammo is topped up after every shot so reloads don't skew the count.
+fn run_fire_rate_check() {
+    use crate::game::weapon::{Rarity, Weapon, WeaponType};
+
+    let mut all_ok = true;
+    for id in 0.. {
+        let Some(weapon_type) = WeaponType::from_u8(id) else {
+            break;
+        };
+        let mut weapon = Weapon::new(weapon_type, Rarity::Common);
+        let mut shots = 0u32;
+        for tick in 0..FIRE_CHECK_TICKS {
+            if weapon.fire(tick == 0) {
+                shots += 1;
+                weapon.ammo = weapon.max_ammo;
+            }
+            weapon.update(1.0 / 60.0);
+        }
+
+        let interval = weapon.fire_rate_ticks() as u32;
+        let expected = if weapon_type.is_semi_auto() { 1 } else { FIRE_CHECK_TICKS.div_ceil(interval) };
+        let ok = shots == expected;
+        all_ok &= ok;
+        serial_println!(
+            "BENCHMARK_FIRE: {} {} shots in {} ticks (expected {}, every {} ticks){}",
+            weapon_type.name(),
+            shots,
+            FIRE_CHECK_TICKS,
+            expected,
+            interval,
+            if ok { "" } else { " MISMATCH" }
+        );
+    }
+    serial_println!("BENCHMARK_FIRE: {}", if all_ok { "PASS" } else { "FAIL" });
+}
+
+/// Same-scene A/B of the z-buffer formats in benchmark mode
+/// Each report window renders in one depth mode and the next in the other,
+/// so consecutive windows compare the two on the same benchmark scene
+struct DepthModeAb {
+    window_start: u64,
+    frames: u32,
+    rasterize_cycles: u64,
+    /// Last window's (FPS, rasterize us/frame), indexed [float, integer]
+    last: [Option<(f64, u64)>; 2],
+}
+
+impl DepthModeAb {
+    const fn new() -> Self {
+        Self { window_start: 0, frames: 0, rasterize_cycles: 0, last: [None; 2] }
+    }
+
+    fn start(&mut self, now: u64) {
+        self.window_start = now;
+        self.frames = 0;
+        self.rasterize_cycles = 0;
+    }
+
+    fn record(&mut self, frame: &BenchmarkFrameBreakdown) {
+        self.frames += 1;
+        self.rasterize_cycles += frame.rasterize_cycles;
+    }
+
+    /// Print this window's result (and the comparison once both modes ran),
+    /// then switch the z-buffer to the other mode for the next window
+    fn report(&mut self, now: u64, tsc_per_second: u64) {
+        let mode = zbuffer::depth_mode();
+        let frames = self.frames.max(1);
+        let secs = now.wrapping_sub(self.window_start) as f64 / tsc_per_second as f64;
+        let fps = frames as f64 / secs;
+        let raster_us = self.rasterize_cycles / frames as u64 / (tsc_per_second / 1_000_000);
+        serial_println!("BENCHMARK_ZBUF: {} {:.1} FPS, rasterize {}us/frame", mode.label(), fps, raster_us);
+
+        let slot = match mode {
+            DepthMode::Float => 0,
+            DepthMode::Integer => 1,
+        };
+        self.last[slot] = Some((fps, raster_us));
+        if let [Some((float_fps, float_us)), Some((int_fps, int_us))] = self.last {
+            serial_println!("BENCHMARK_ZBUF: float {:.1} FPS / {}us vs int24 {:.1} FPS / {}us rasterize",
+                float_fps, float_us, int_fps, int_us);
+        }
+
+        zbuffer::set_depth_mode(match mode {
+            DepthMode::Float => DepthMode::Integer,
+            DepthMode::Integer => DepthMode::Float,
+        });
+        self.start(now);
+    }
+}
+
+/// Same-scene A/B of double and triple buffering in benchmark mode (`buffers=3`)
+/// Each mode runs for two report windows so it sees both z-buffer formats of
+/// DepthModeAb, then the other mode takes over
+struct BufferingAb {
+    enabled: bool,
+    round_start: u64,
+    windows: u32,
+    frames: u32,
+    present_cycles: u64,
+    /// Last round's (FPS, present us/frame), indexed [double, triple]
+    last: [Option<(f64, u64)>; 2],
+}
+
+impl BufferingAb {
+    /// Report windows per buffering mode
+    const ROUND_WINDOWS: u32 = 2;
+
+    const fn new(enabled: bool) -> Self {
+        Self { enabled, round_start: 0, windows: 0, frames: 0, present_cycles: 0, last: [None; 2] }
+    }
+
+    fn start(&mut self, now: u64) {
+        self.round_start = now;
+        self.windows = 0;
+        self.frames = 0;
+        self.present_cycles = 0;
+    }
+
+    fn record(&mut self, frame: &BenchmarkFrameBreakdown) {
+        self.frames += 1;
+        self.present_cycles += frame.present_cycles;
+    }
+
+    /// At the end of a round print its frame time (and the comparison once
+    /// both modes ran), then switch to the other buffering mode
+    fn report(&mut self, now: u64, tsc_per_second: u64) {
+        self.windows += 1;
+        if !self.enabled || self.windows < Self::ROUND_WINDOWS {
+            return;
+        }
+        let triple = gpu::is_triple_buffered();
+        let label = |triple: bool| if triple { "triple" } else { "double" };
+        let frames = self.frames.max(1);
+        let secs = now.wrapping_sub(self.round_start) as f64 / tsc_per_second as f64;
+        let fps = frames as f64 / secs;
+        let present_us = self.present_cycles / frames as u64 / (tsc_per_second / 1_000_000);
+        serial_println!("BENCHMARK_BUFFERS: {} {:.1} FPS ({:.2}ms/frame), present {}us/frame",
+            label(triple), fps, 1000.0 / fps, present_us);
+
+        self.last[triple as usize] = Some((fps, present_us));
+        if let [Some((double_fps, double_us)), Some((triple_fps, triple_us))] = self.last {
+            serial_println!("BENCHMARK_BUFFERS: double {:.2}ms / {}us vs triple {:.2}ms / {}us present",
+                1000.0 / double_fps, double_us, 1000.0 / triple_fps, triple_us);
+        }
+
+        gpu::set_triple_buffering(!triple);
+        self.start(now);
+    }
+}
+
+/// Same-scene A/B of perspective-correct and affine interpolation in benchmark mode
+/// Each mode runs for four report windows so it sees both z-buffer formats of
+/// DepthModeAb and both buffering modes of BufferingAb (whose rounds are two
+/// windows long), then the other mode takes over
+struct PerspectiveAb {
+    windows: u32,
+    frames: u32,
+    rasterize_cycles: u64,
+    /// Last round's rasterize us/frame, indexed [affine, perspective]
+    last: [Option<u64>; 2],
+}
+
+impl PerspectiveAb {
+    /// Report windows per interpolation mode (twice BufferingAb's rounds)
+    const ROUND_WINDOWS: u32 = 2 * BufferingAb::ROUND_WINDOWS;
+
+    const fn new() -> Self {
+        Self { windows: 0, frames: 0, rasterize_cycles: 0, last: [None; 2] }
+    }
+
+    fn start(&mut self) {
+        self.windows = 0;
+        self.frames = 0;
+        self.rasterize_cycles = 0;
+    }
+
+    fn record(&mut self, frame: &BenchmarkFrameBreakdown) {
+        self.frames += 1;
+        self.rasterize_cycles += frame.rasterize_cycles;
+    }
+
+    /// At the end of a round print its rasterize time (and the comparison
+    /// once both modes ran), then switch to the other interpolation mode
+    fn report(&mut self, tsc_per_second: u64) {
+        self.windows += 1;
+        if self.windows < Self::ROUND_WINDOWS {
+            return;
+        }
+        let perspective = tiles::perspective_correct_enabled();
+        let label = |perspective: bool| if perspective { "perspective" } else { "affine" };
+        let frames = self.frames.max(1) as u64;
+        let raster_us = self.rasterize_cycles / frames / (tsc_per_second / 1_000_000);
+        serial_println!("BENCHMARK_PERSPECTIVE: {} rasterize {}us/frame", label(perspective), raster_us);
+
+        self.last[perspective as usize] = Some(raster_us);
+        if let [Some(affine_us), Some(perspective_us)] = self.last {
+            serial_println!("BENCHMARK_PERSPECTIVE: affine {}us vs perspective {}us rasterize",
+                affine_us, perspective_us);
+        }
+
+        tiles::set_perspective_correct(!perspective);
+        self.start();
+    }
+}
+
+/// Same-scene A/B of the GPU mesh cache on the GPU batch path
+/// Alternates report windows with the cache on (static meshes drawn from VRAM)
+/// and off (every mesh transformed on the CPU and batched), comparing the
+/// triangles submitted per frame and the CPU time spent in bin_mesh_gpu
+struct MeshCacheAb {
+    enabled: bool,
+    frames: u32,
+    triangles: u64,
+    transform_cycles: u64,
+    /// Last window's (triangles/frame, bin_mesh_gpu us/frame), indexed [off, on]
+    last: [Option<(u64, u64)>; 2],
+}
+
+impl MeshCacheAb {
+    const fn new(enabled: bool) -> Self {
+        Self { enabled, frames: 0, triangles: 0, transform_cycles: 0, last: [None; 2] }
+    }
+
+    fn start(&mut self) {
+        self.frames = 0;
+        self.triangles = 0;
+        self.transform_cycles = 0;
+    }
+
+    fn record(&mut self, frame: &BenchmarkFrameBreakdown) {
+        self.frames += 1;
+        self.triangles += gpu_mesh_triangles() as u64;
+        self.transform_cycles += frame.transform_cycles;
+    }
+
+    /// Print this window's result (and the comparison once both ran), then
+    /// flip the mesh cache for the next window
+    fn report(&mut self, tsc_per_second: u64) {
+        if !self.enabled {
+            return;
+        }
+        let cached = gpu_batch::is_mesh_cache_enabled();
+        let label = |cached: bool| if cached { "cached" } else { "immediate" };
+        let frames = self.frames.max(1) as u64;
+        let triangles = self.triangles / frames;
+        let bin_us = self.transform_cycles / frames / (tsc_per_second / 1_000_000);
+        serial_println!("BENCHMARK_MESH_CACHE: {} {} triangles/frame, bin_mesh_gpu {}us/frame",
+            label(cached), triangles, bin_us);
+
+        self.last[cached as usize] = Some((triangles, bin_us));
+        if let [Some((off_tris, off_us)), Some((on_tris, on_us))] = self.last {
+            serial_println!("BENCHMARK_MESH_CACHE: immediate {} tris / {}us vs cached {} tris / {}us bin_mesh_gpu",
+                off_tris, off_us, on_tris, on_us);
+        }
+
+        gpu_batch::set_mesh_cache_enabled(!cached);
+        self.start();
+    }
+}
+
+/// Replay mode: load the recording from the boot module and spectate its first player
+fn start_replay(local_player_id: &mut Option<u8>) {
+    let Some(bytes) = boot::first_module() else {
+        serial_println!("REPLAY: no recording module loaded");
+        return;
+    };
+    serial_println!("REPLAY: starting playback ({} bytes)", bytes.len());
+    replay::start_playback(bytes);
+
+    if let Some(world) = GAME_WORLD.write().as_mut() {
+        world.players.clear();
+        // Apply the first snapshot so the camera has someone to follow
+        replay::advance_playback(world, 0.0);
+        if !world.players.is_empty() {
+            world.local_player_id = Some(0);
+            *local_player_id = Some(0);
+        }
+    }
+}
+
 /// Spawn test items for test mode
 fn spawn_test_items(world: &mut crate::game::world::GameWorld) {
     use crate::game::weapon::{WeaponType, Weapon, Rarity};
+    use crate::game::inventory::ConsumableKind;
     use crate::game::loot::{LootItem, ChestTier};
 
     // Spawn weapons in a circle around the player
@@ -677,11 +1292,8 @@ fn spawn_test_items(world: &mut crate::game::world::GameWorld) {
         let z = center_z + libm::sinf(angle) * 12.0;
         let y = sample_terrain_height(x, z) + 0.5;
         let pos = Vec3::new(x, y, z);
-        let item = if i == 0 {
-            LootItem::Health { amount: 100, use_time: 10.0, max_health: 100 }
-        } else {
-            LootItem::Shield { amount: 50, use_time: 5.0 }
-        };
+        let kind = if i == 0 { ConsumableKind::MedKit } else { ConsumableKind::ShieldLarge };
+        let item = LootItem::from_consumable(kind);
         world.loot.spawn_drop(pos, item, false);
         spawn_count += 1;
     }
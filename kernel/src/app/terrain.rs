@@ -4,6 +4,7 @@
 
 use glam::{Vec2, Vec3};
 use renderer::mesh::Mesh;
+use renderer::texture::Material;
 use renderer::vertex::Vertex;
 
 /// Create a 3D terrain mesh with proper hills and valleys
@@ -51,7 +52,7 @@ pub fn create_3d_terrain(size: f32, subdivisions: usize) -> Mesh {
                 Vec3::new(fx, height, fz),
                 Vec3::Y, // Will be recalculated
                 color,
-                Vec2::new(x as f32 / subdivisions as f32, z as f32 / subdivisions as f32),
+                Vec2::new(x as f32, z as f32), // Grass repeats once per cell
             ));
         }
     }
@@ -74,7 +75,7 @@ pub fn create_3d_terrain(size: f32, subdivisions: usize) -> Mesh {
     // Recalculate normals for proper lighting
     recalculate_normals(&mut terrain_mesh);
 
-    terrain_mesh
+    terrain_mesh.with_material(Material::Grass)
 }
 
 /// Recalculate vertex normals from face normals
@@ -1,8 +1,11 @@
 //! Limine bootloader requests and responses
 
+use boot_config::{AppMode, BootConfig};
+use spin::Mutex;
+
 use limine::request::{
-    FramebufferRequest, HhdmRequest, KernelFileRequest, MemoryMapRequest, MpRequest,
-    RequestsEndMarker, RequestsStartMarker,
+    FramebufferRequest, HhdmRequest, KernelFileRequest, MemoryMapRequest, ModuleRequest,
+    MpRequest, RequestsEndMarker, RequestsStartMarker,
 };
 
 #[used]
@@ -42,3 +45,34 @@ pub static SMP_REQUEST: MpRequest = MpRequest::new();
 #[used]
 #[unsafe(link_section = ".requests")]
 pub static KERNEL_FILE_REQUEST: KernelFileRequest = KernelFileRequest::new();
+
+/// Module request for files loaded alongside the kernel (replay recordings)
+#[used]
+#[unsafe(link_section = ".requests")]
+pub static MODULE_REQUEST: ModuleRequest = ModuleRequest::new();
+
+/// Boot configuration parsed from the kernel command line
+/// Set once in `_start`; every later mode check reads it from here
+pub static BOOT_CONFIG: Mutex<Option<BootConfig>> = Mutex::new(None);
+
+/// Store the boot configuration (called once, before anything reads it)
+pub fn set_config(config: BootConfig) {
+    *BOOT_CONFIG.lock() = Some(config);
+}
+
+/// Mode the kernel was booted in (GameClient until the config is set)
+pub fn app_mode() -> AppMode {
+    BOOT_CONFIG.lock().as_ref().map(|c| c.mode).unwrap_or_default()
+}
+
+/// The boot configuration (defaults until it is set)
+pub fn config() -> BootConfig {
+    BOOT_CONFIG.lock().clone().unwrap_or_default()
+}
+
+/// Contents of the first boot module, if the bootloader loaded one
+pub fn first_module() -> Option<&'static [u8]> {
+    let file = MODULE_REQUEST.get_response()?.modules().first()?;
+    // SAFETY: Limine maps modules in bootloader-reclaimable memory that is never freed
+    Some(unsafe { core::slice::from_raw_parts(file.addr(), file.size() as usize) })
+}
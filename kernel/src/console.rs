@@ -0,0 +1,216 @@
+//! Serial admin console
+//!
+//! Reads line-based commands from COM1 and prints results to the serial log.
+//! Polled from the main loop, so it never blocks.
+
+use crate::drivers::serial::{LineBuffer, SERIAL1};
+use crate::game::world::GAME_WORLD;
+use crate::graphics::framebuffer;
+use crate::graphics::rasterizer::{render_mode, set_render_mode};
+use crate::graphics::vsync;
+use crate::net;
+use crate::serial_println;
+use boot_config::RenderMode;
+use core::net::Ipv4Addr;
+use protocol::packets::MAX_CHAT_LEN;
+use smoltcp::wire::Ipv4Address;
+use spin::Mutex;
+
+/// Partially typed command line
+static LINE: Mutex<LineBuffer> = Mutex::new(LineBuffer::new());
+
+/// A console command line, split into its command and arguments
+#[derive(Debug, PartialEq)]
+enum Command<'a> {
+    Help,
+    Arp,
+    ArpFlush,
+    Route,
+    Send(&'a str, &'a str),
+    Say(&'a str),
+    Screenshot,
+    Fps,
+    Wireframe,
+    Players,
+    Unknown(&'a str),
+    Empty,
+}
+
+impl<'a> Command<'a> {
+    /// Parse a trimmed command line
+    fn parse(line: &'a str) -> Self {
+        // `say` takes the rest of the line verbatim
+        if let Some(message) = line.strip_prefix("say ") {
+            return Self::Say(message.trim());
+        }
+
+        let mut words = line.split_whitespace();
+        match (words.next(), words.next(), words.next()) {
+            (Some("help"), _, _) => Self::Help,
+            (Some("arp"), None, _) => Self::Arp,
+            (Some("arp"), Some("flush"), _) => Self::ArpFlush,
+            (Some("screenshot"), None, _) => Self::Screenshot,
+            (Some("route"), _, _) => Self::Route,
+            (Some("send"), Some(ip), Some(port)) => Self::Send(ip, port),
+            (Some("say"), _, _) => Self::Say(""),
+            (Some("fps"), _, _) => Self::Fps,
+            (Some("wireframe"), _, _) => Self::Wireframe,
+            (Some("players"), _, _) => Self::Players,
+            (Some(cmd), _, _) => Self::Unknown(cmd),
+            (None, _, _) => Self::Empty,
+        }
+    }
+}
+
+/// Drain pending serial input and run any completed commands
+pub fn poll() {
+    let mut line = LINE.lock();
+    loop {
+        // Release the port before dispatching (commands print to it)
+        let Some(text) = SERIAL1.lock().try_read_line(&mut line) else {
+            return;
+        };
+        execute(text.trim());
+    }
+}
+
+/// Run one console command
+pub fn execute(line: &str) {
+    match Command::parse(line) {
+        Command::Help => serial_println!(
+            "CONSOLE: commands: help | arp | arp flush | route | send <ip> <port> | say <msg> | screenshot | fps | wireframe | players"
+        ),
+        Command::Arp => print_arp_table(),
+        Command::ArpFlush => {
+            net::stack::flush_arp();
+            serial_println!("ARP: cache flushed");
+        }
+        Command::Route => print_route(),
+        Command::Send(ip, port) => send_probe(ip, port),
+        Command::Say(message) => say(message),
+        Command::Screenshot => framebuffer::dump_screenshot(),
+        Command::Fps => print_fps(),
+        Command::Wireframe => {
+            let mode = if render_mode() == RenderMode::Wireframe { RenderMode::Normal } else { RenderMode::Wireframe };
+            set_render_mode(mode);
+            serial_println!("CONSOLE: render mode {}", mode.label());
+        }
+        Command::Players => print_players(),
+        Command::Unknown(cmd) => serial_println!("CONSOLE: unknown command '{}' (try help)", cmd),
+        Command::Empty => {}
+    }
+}
+
+fn print_fps() {
+    let (frames, dropped, drop_rate) = vsync::get_stats();
+    if frames == 0 {
+        serial_println!("FPS: no frames rendered");
+        return;
+    }
+    serial_println!("FPS: {} ({} frames, {} dropped, {:.1}%)", vsync::current_fps(), frames, dropped, drop_rate);
+}
+
+fn print_players() {
+    let world = GAME_WORLD.read();
+    let Some(world) = world.as_ref() else {
+        serial_println!("PLAYERS: world not initialized");
+        return;
+    };
+    serial_println!("PLAYERS: {}", world.players.len());
+    for p in &world.players {
+        serial_println!(
+            "PLAYERS: #{} {} hp {} shield {} at ({:.0}, {:.0}, {:.0}){}",
+            p.id, p.name, p.health, p.shield,
+            p.position.x, p.position.y, p.position.z,
+            if p.is_alive() { "" } else { " (eliminated)" }
+        );
+    }
+}
+
+/// Broadcast a server announcement to all clients
+fn say(message: &str) {
+    match net::protocol::broadcast_chat(message) {
+        Some(count) => serial_println!("SAY: sent to {} clients", count),
+        None => serial_println!("SAY: message must be 1-{} bytes", MAX_CHAT_LEN),
+    }
+}
+
+fn print_arp_table() {
+    let entries = net::stack::arp_table();
+    serial_println!("ARP: {} entries", entries.len());
+    for e in &entries {
+        serial_println!(
+            "ARP: {}.{}.{}.{} -> {:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x} (seen {}ms)",
+            e.ip[0], e.ip[1], e.ip[2], e.ip[3],
+            e.mac[0], e.mac[1], e.mac[2], e.mac[3], e.mac[4], e.mac[5],
+            e.last_seen_ms
+        );
+    }
+}
+
+fn print_route() {
+    match net::stack::NETWORK_STACK.lock().as_ref() {
+        Some(stack) => {
+            let cfg = stack.config;
+            serial_println!("ROUTE: {}/{} dev e1000", cfg.ip, cfg.prefix_len);
+            match cfg.gateway {
+                Some(gw) => serial_println!("ROUTE: default via {}", gw),
+                None => serial_println!("ROUTE: no default gateway"),
+            }
+        }
+        None => serial_println!("ROUTE: network not initialized"),
+    }
+}
+
+/// Send a small UDP datagram (used to exercise routing/ARP from the console)
+fn send_probe(ip: &str, port: &str) {
+    let (ip, port): (Ipv4Address, u16) = match (ip.parse::<Ipv4Addr>(), port.parse()) {
+        (Ok(ip), Ok(port)) => (ip, port),
+        _ => {
+            serial_println!("CONSOLE: usage: send <ip> <port>");
+            return;
+        }
+    };
+
+    let mut guard = net::stack::NETWORK_STACK.lock();
+    match guard.as_mut() {
+        Some(stack) => {
+            let hop = stack.next_hop(ip);
+            let sent = stack.send_udp(ip, port, b"probe");
+            match hop {
+                Some(hop) => serial_println!("SEND: {}:{} via {} queued={}", ip, port, hop, sent),
+                None => serial_println!("SEND: {}:{} has no route", ip, port),
+            }
+        }
+        None => serial_println!("SEND: network not initialized"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(Command::parse("fps"), Command::Fps);
+        assert_eq!(Command::parse("arp"), Command::Arp);
+        assert_eq!(Command::parse("arp   flush"), Command::ArpFlush);
+        assert_eq!(Command::parse("send 10.0.2.2 5000"), Command::Send("10.0.2.2", "5000"));
+        // say keeps the message's own spacing
+        assert_eq!(Command::parse("say gg  wp "), Command::Say("gg  wp"));
+        assert_eq!(Command::parse("screenshot now"), Command::Unknown("screenshot"));
+        assert_eq!(Command::parse("warp 3"), Command::Unknown("warp"));
+        assert_eq!(Command::parse(""), Command::Empty);
+    }
+
+    #[test]
+    fn test_line_buffer_editing() {
+        let mut line = LineBuffer::new();
+        let (last, typed) = b"plx\x08ayers\r".split_last().unwrap();
+        assert!(typed.iter().all(|&b| !line.push(b)));
+        assert!(line.push(*last));
+        assert_eq!(line.take(), "players");
+        // A bare newline ends nothing
+        assert!(!line.push(b'\n'));
+    }
+}
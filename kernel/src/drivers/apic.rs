@@ -0,0 +1,108 @@
+//! Local APIC
+//!
+//! Only used to wake HLTed render cores: core 0 broadcasts an IPI when it
+//! starts a render pass. Device interrupts still go through the 8259 PICs.
+//! Both register interfaces are handled, MMIO (xAPIC) and MSRs (x2APIC),
+//! whichever the firmware left enabled.
+
+use crate::memory;
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use x86_64::registers::model_specific::Msr;
+
+const IA32_APIC_BASE: u32 = 0x1B;
+const APIC_BASE_X2APIC: u64 = 1 << 10;
+const APIC_BASE_ENABLE: u64 = 1 << 11;
+const APIC_BASE_ADDRESS: u64 = 0x000F_FFFF_F000;
+
+/// MSR of x2APIC register 0 (a register's MMIO offset / 16 is added)
+const X2APIC_MSR_BASE: u32 = 0x800;
+
+const REG_TPR: u32 = 0x080;
+const REG_EOI: u32 = 0x0B0;
+const REG_SVR: u32 = 0x0F0;
+const REG_ICR_LOW: u32 = 0x300;
+const REG_ICR_HIGH: u32 = 0x310;
+
+/// SVR: APIC software enable
+const SVR_ENABLE: u32 = 1 << 8;
+/// ICR: every core but the sender (fixed delivery, edge)
+const ICR_ALL_EXCLUDING_SELF: u32 = 0b11 << 18;
+/// ICR: previous IPI still being sent (xAPIC only)
+const ICR_SEND_PENDING: u32 = 1 << 12;
+
+/// Vector the APIC reports spurious interrupts on (takes no EOI)
+pub const SPURIOUS_VECTOR: u8 = 0xFF;
+
+/// Virtual address of the xAPIC registers (unused in x2APIC mode)
+static MMIO_BASE: AtomicU64 = AtomicU64::new(0);
+static X2APIC: AtomicBool = AtomicBool::new(false);
+static READY: AtomicBool = AtomicBool::new(false);
+
+/// Find the APIC registers (on the BSP, before the APs start)
+/// Every core sees its own APIC at the same address, so one mapping serves
+/// all. Returns false if the APIC is disabled or can't be mapped.
+pub fn init() -> bool {
+    let base = unsafe { Msr::new(IA32_APIC_BASE).read() };
+    if base & APIC_BASE_ENABLE == 0 {
+        return false;
+    }
+    if base & APIC_BASE_X2APIC != 0 {
+        X2APIC.store(true, Ordering::Release);
+    } else {
+        let Some(mmio) = memory::paging::map_mmio(base & APIC_BASE_ADDRESS, 0x1000) else {
+            return false;
+        };
+        MMIO_BASE.store(mmio, Ordering::Release);
+    }
+    READY.store(true, Ordering::Release);
+    true
+}
+
+/// Whether `init` found the APIC
+pub fn is_ready() -> bool {
+    READY.load(Ordering::Acquire)
+}
+
+fn read(reg: u32) -> u32 {
+    if X2APIC.load(Ordering::Relaxed) {
+        unsafe { Msr::new(X2APIC_MSR_BASE + reg / 16).read() as u32 }
+    } else {
+        let addr = MMIO_BASE.load(Ordering::Relaxed) + reg as u64;
+        unsafe { core::ptr::read_volatile(addr as *const u32) }
+    }
+}
+
+fn write(reg: u32, value: u32) {
+    if X2APIC.load(Ordering::Relaxed) {
+        unsafe { Msr::new(X2APIC_MSR_BASE + reg / 16).write(value as u64) };
+    } else {
+        let addr = MMIO_BASE.load(Ordering::Relaxed) + reg as u64;
+        unsafe { core::ptr::write_volatile(addr as *mut u32, value) };
+    }
+}
+
+/// Let the calling core's APIC accept interrupts of every priority
+pub fn enable() {
+    write(REG_TPR, 0);
+    write(REG_SVR, SVR_ENABLE | SPURIOUS_VECTOR as u32);
+}
+
+/// Send `vector` to every other core
+pub fn broadcast_ipi(vector: u8) {
+    let command = ICR_ALL_EXCLUDING_SELF | vector as u32;
+    if X2APIC.load(Ordering::Relaxed) {
+        // One 64-bit register; the destination field is ignored with a shorthand
+        unsafe { Msr::new(X2APIC_MSR_BASE + REG_ICR_LOW / 16).write(command as u64) };
+        return;
+    }
+    while read(REG_ICR_LOW) & ICR_SEND_PENDING != 0 {
+        core::hint::spin_loop();
+    }
+    write(REG_ICR_HIGH, 0);
+    write(REG_ICR_LOW, command);
+}
+
+/// Acknowledge the interrupt being handled on the calling core
+pub fn end_of_interrupt() {
+    write(REG_EOI, 0);
+}
@@ -0,0 +1,334 @@
+//! Bochs/QEMU standard VGA (DISPI) display driver
+//!
+//! QEMU's `-vga std` and VirtualBox's VBoxVGA expose the Bochs VBE "DISPI"
+//! interface: a few 16-bit registers that set the resolution and color depth
+//! of a linear framebuffer (LFB, BAR0). The registers are reached through
+//! I/O ports 0x1CE/0x1CF or, when the PCI device has an MMIO BAR2, at offset
+//! 0x500 of it.
+//!
+//! There is no acceleration. Frames are drawn into the framebuffer module's
+//! back buffer and presented by copying to the LFB, as with the Limine
+//! framebuffer, but the mode can be chosen at boot and changed while running.
+
+use crate::drivers::pci::{self, Bar, PciDevice};
+use crate::memory::paging;
+use crate::serial_println;
+use spin::Mutex;
+use x86_64::instructions::port::Port;
+
+/// QEMU/Bochs standard VGA PCI IDs
+pub const BOCHS_VENDOR_ID: u16 = 0x1234;
+pub const BOCHS_DEVICE_ID: u16 = 0x1111;
+
+/// DISPI register index and data ports
+const DISPI_IOPORT_INDEX: u16 = 0x01CE;
+const DISPI_IOPORT_DATA: u16 = 0x01CF;
+
+/// Offset of the DISPI registers in the MMIO BAR (one u16 per register)
+const DISPI_MMIO_OFFSET: u64 = 0x500;
+
+/// Interface revisions this driver understands (VBE_DISPI_ID0..ID5)
+const DISPI_ID_FIRST: u16 = 0xB0C0;
+const DISPI_ID_LAST: u16 = 0xB0C5;
+
+/// Largest mode Bochs documents, for revisions that can't report their own
+const DISPI_DEFAULT_MAX: (u32, u32) = (2560, 1600);
+
+/// DISPI register indices
+#[derive(Debug, Clone, Copy)]
+#[repr(u16)]
+enum DispiReg {
+    Id = 0,
+    XRes = 1,
+    YRes = 2,
+    Bpp = 3,
+    Enable = 4,
+    VirtWidth = 6,
+    VirtHeight = 7,
+    XOffset = 8,
+    YOffset = 9,
+    VideoMemory64K = 0xA,
+}
+
+/// Bits of the Enable register
+mod enable {
+    pub const ENABLED: u16 = 0x01;
+    /// While set, XRes/YRes/Bpp read back the maximum supported values
+    pub const GET_CAPS: u16 = 0x02;
+    pub const LFB_ENABLED: u16 = 0x40;
+}
+
+/// How the DISPI registers are reached
+#[derive(Debug, Clone, Copy)]
+enum DispiIo {
+    /// Index/data port pair
+    Ports,
+    /// Memory-mapped at this virtual address (already offset to the registers)
+    Mmio(u64),
+}
+
+impl DispiIo {
+    fn read(self, reg: DispiReg) -> u16 {
+        match self {
+            // Safety: the DISPI ports only select and access display registers
+            Self::Ports => unsafe {
+                Port::<u16>::new(DISPI_IOPORT_INDEX).write(reg as u16);
+                Port::<u16>::new(DISPI_IOPORT_DATA).read()
+            },
+            // Safety: the register block is mapped for the device's lifetime
+            Self::Mmio(base) => unsafe { core::ptr::read_volatile((base + reg as u64 * 2) as *const u16) },
+        }
+    }
+
+    fn write(self, reg: DispiReg, value: u16) {
+        match self {
+            // Safety: as in read
+            Self::Ports => unsafe {
+                Port::<u16>::new(DISPI_IOPORT_INDEX).write(reg as u16);
+                Port::<u16>::new(DISPI_IOPORT_DATA).write(value);
+            },
+            // Safety: as in read
+            Self::Mmio(base) => unsafe { core::ptr::write_volatile((base + reg as u64 * 2) as *mut u16, value) },
+        }
+    }
+}
+
+/// Bochs DISPI device state
+pub struct BochsDevice {
+    io: DispiIo,
+    /// Virtual address of the linear framebuffer
+    lfb_virt: u64,
+    /// Mapped framebuffer size in bytes
+    lfb_size: usize,
+    width: u32,
+    height: u32,
+    /// Bytes per line
+    pitch: u32,
+    /// Largest mode the device reports
+    max_width: u32,
+    max_height: u32,
+    initialized: bool,
+}
+
+impl BochsDevice {
+    /// Create an uninitialized device
+    pub const fn new() -> Self {
+        Self {
+            io: DispiIo::Ports,
+            lfb_virt: 0,
+            lfb_size: 0,
+            width: 0,
+            height: 0,
+            pitch: 0,
+            max_width: 0,
+            max_height: 0,
+            initialized: false,
+        }
+    }
+
+    /// Check if device is initialized
+    pub fn is_initialized(&self) -> bool {
+        self.initialized
+    }
+
+    /// Get framebuffer dimensions
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.width as usize, self.height as usize)
+    }
+
+    /// Get framebuffer pitch (bytes per line)
+    pub fn pitch(&self) -> usize {
+        self.pitch as usize
+    }
+
+    /// Get pointer to the linear framebuffer (the display)
+    pub fn front_buffer(&self) -> *mut u32 {
+        self.lfb_virt as *mut u32
+    }
+
+    /// Switch the display to `width` x `height` at 32 bpp
+    ///
+    /// The mode must fit within the device maximum and the mapped LFB;
+    /// otherwise nothing changes and None is returned. Returns the mode the
+    /// device reports after the switch.
+    pub fn set_mode(&mut self, width: u32, height: u32) -> Option<(usize, usize)> {
+        if !self.initialized {
+            return None;
+        }
+        if !mode_fits(width, height, self.max_width, self.max_height, self.lfb_size) {
+            serial_println!(
+                "BOCHS: Mode {}x{} rejected (max {}x{}, {} KiB LFB mapped)",
+                width, height, self.max_width, self.max_height, self.lfb_size / 1024
+            );
+            return None;
+        }
+        self.program_mode(width, height)?;
+        serial_println!("BOCHS: Mode set to {}x{}x32", self.width, self.height);
+        Some((self.width as usize, self.height as usize))
+    }
+
+    /// Write the mode registers and read back what the device set
+    /// None if the device did not take a 32 bpp mode.
+    fn program_mode(&mut self, width: u32, height: u32) -> Option<()> {
+        let io = self.io;
+        io.write(DispiReg::Enable, 0);
+        io.write(DispiReg::XRes, width as u16);
+        io.write(DispiReg::YRes, height as u16);
+        io.write(DispiReg::Bpp, 32);
+        io.write(DispiReg::VirtWidth, width as u16);
+        io.write(DispiReg::VirtHeight, height as u16);
+        io.write(DispiReg::XOffset, 0);
+        io.write(DispiReg::YOffset, 0);
+        io.write(DispiReg::Enable, enable::ENABLED | enable::LFB_ENABLED);
+
+        if io.read(DispiReg::Bpp) != 32 {
+            serial_println!("BOCHS: Device refused 32 bpp");
+            return None;
+        }
+        self.width = io.read(DispiReg::XRes) as u32;
+        self.height = io.read(DispiReg::YRes) as u32;
+        self.pitch = io.read(DispiReg::VirtWidth) as u32 * 4;
+        Some(())
+    }
+}
+
+impl Default for BochsDevice {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Global Bochs DISPI device instance
+pub static BOCHS_DEVICE: Mutex<BochsDevice> = Mutex::new(BochsDevice::new());
+
+/// Check if a Bochs/QEMU standard VGA device is present without initializing
+pub fn is_available() -> bool {
+    find_device().is_some()
+}
+
+/// Find the standard VGA PCI device
+fn find_device() -> Option<PciDevice> {
+    pci::find_device(BOCHS_VENDOR_ID, BOCHS_DEVICE_ID)
+}
+
+/// Whether an ID register value is a DISPI revision this driver supports
+fn is_dispi_id(id: u16) -> bool {
+    (DISPI_ID_FIRST..=DISPI_ID_LAST).contains(&id)
+}
+
+/// Whether a 32-bit `width` x `height` mode is within the device maximum
+/// and fits in `lfb_size` bytes of framebuffer
+fn mode_fits(width: u32, height: u32, max_width: u32, max_height: u32, lfb_size: usize) -> bool {
+    width > 0
+        && height > 0
+        && width <= max_width
+        && height <= max_height
+        && width as usize * height as usize * 4 <= lfb_size
+}
+
+/// Read the maximum mode with the GET_CAPS bit (display disabled meanwhile)
+/// Early revisions report nothing, so fall back to the Bochs limits.
+fn read_max_resolution(io: DispiIo) -> (u32, u32) {
+    io.write(DispiReg::Enable, enable::GET_CAPS);
+    let max = (io.read(DispiReg::XRes) as u32, io.read(DispiReg::YRes) as u32);
+    io.write(DispiReg::Enable, 0);
+    if max.0 == 0 || max.1 == 0 {
+        DISPI_DEFAULT_MAX
+    } else {
+        max
+    }
+}
+
+/// Initialize the Bochs DISPI driver with the specified resolution
+/// Returns (width, height) on success
+pub fn init_with_resolution(target_width: u32, target_height: u32) -> Option<(usize, usize)> {
+    let Some(pci_dev) = find_device() else {
+        serial_println!("BOCHS: Device not found");
+        return None;
+    };
+    pci_dev.enable_memory_space();
+
+    // QEMU's device has the registers in BAR2; otherwise use the VBE ports
+    let mmio = match pci::decode_bar(pci_dev.read_config(0x18), pci_dev.read_config(0x1C)) {
+        Bar::Memory(phys) if phys != 0 && pci_dev.bar_size(2) >= DISPI_MMIO_OFFSET + 0x20 => {
+            paging::map_mmio(phys, pci_dev.bar_size(2) as usize)
+        }
+        _ => None,
+    };
+    let io = match mmio {
+        Some(virt) => DispiIo::Mmio(virt + DISPI_MMIO_OFFSET),
+        None => DispiIo::Ports,
+    };
+
+    let id = io.read(DispiReg::Id);
+    if !is_dispi_id(id) {
+        serial_println!("BOCHS: Unsupported DISPI ID {:#06x}", id);
+        return None;
+    }
+
+    // Map no more of the LFB than both the BAR and the video memory cover
+    let lfb_phys = pci_dev.bar0_address();
+    let vram_size = io.read(DispiReg::VideoMemory64K) as usize * 64 * 1024;
+    let bar_size = pci_dev.bar_size(0) as usize;
+    let lfb_size = match (vram_size, bar_size) {
+        (0, size) | (size, 0) => size,
+        (vram, bar) => vram.min(bar),
+    };
+    if lfb_phys == 0 || lfb_size == 0 {
+        serial_println!("BOCHS: No linear framebuffer");
+        return None;
+    }
+    let Some(lfb_virt) = paging::map_mmio(lfb_phys, lfb_size) else {
+        serial_println!("BOCHS: Failed to map framebuffer");
+        return None;
+    };
+
+    let (max_width, max_height) = read_max_resolution(io);
+    let mut device = BOCHS_DEVICE.lock();
+    device.io = io;
+    device.lfb_virt = lfb_virt;
+    device.lfb_size = lfb_size;
+    device.max_width = max_width;
+    device.max_height = max_height;
+
+    let (width, height) = (target_width.min(max_width), target_height.min(max_height));
+    if !mode_fits(width, height, max_width, max_height, lfb_size) || device.program_mode(width, height).is_none() {
+        serial_println!("BOCHS: Cannot set {}x{}", width, height);
+        return None;
+    }
+    device.initialized = true;
+
+    serial_println!(
+        "BOCHS: Initialized {}x{}x32 (DISPI {:#06x}, {} registers, {} KiB LFB)",
+        device.width,
+        device.height,
+        id,
+        if matches!(io, DispiIo::Mmio(_)) { "MMIO" } else { "port" },
+        lfb_size / 1024
+    );
+    Some(device.dimensions())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_dispi_id() {
+        assert!(is_dispi_id(0xB0C0));
+        assert!(is_dispi_id(0xB0C5));
+        assert!(!is_dispi_id(0xB0C6));
+        assert!(!is_dispi_id(0xFFFF));
+        assert!(!is_dispi_id(0));
+    }
+
+    #[test]
+    fn test_mode_fits() {
+        let lfb = 16 << 20;
+        assert!(mode_fits(1920, 1080, 2560, 1600, lfb));
+        assert!(mode_fits(2560, 1600, 2560, 1600, lfb));
+        assert!(!mode_fits(2561, 1600, 2560, 1600, lfb));
+        assert!(!mode_fits(1920, 1080, 2560, 1600, 1920 * 1080 * 4 - 1));
+        assert!(!mode_fits(1024, 0, 2560, 1600, lfb));
+    }
+}
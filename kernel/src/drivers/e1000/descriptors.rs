@@ -1,5 +1,8 @@
 //! E1000 TX/RX Descriptors
 
+use super::offload::ChecksumOffsets;
+use super::regs::*;
+
 /// Transmit descriptor (16 bytes)
 #[repr(C, packed)]
 #[derive(Debug, Clone, Copy, Default)]
@@ -13,6 +16,56 @@ pub struct TxDescriptor {
     pub special: u16,
 }
 
+/// Transmit context descriptor (16 bytes, shares the TX ring)
+///
+/// Loads the checksum offsets used by the extended data descriptors that
+/// follow it, until the next context descriptor. Fields sit at the same byte
+/// offsets as `TxDescriptor`'s so either view can be written to a slot.
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TxContextDescriptor {
+    /// IP checksum start, field offset and end (inclusive)
+    pub ipcss: u8,
+    pub ipcso: u8,
+    pub ipcse: u16,
+    /// TCP/UDP checksum start, field offset and end (0 = end of packet)
+    pub tucss: u8,
+    pub tucso: u8,
+    pub tucse: u16,
+    /// Payload length for segmentation (unused without TSO)
+    pub paylen: u16,
+    /// Descriptor type in the upper nibble (`TX_DTYP_CONTEXT`)
+    pub dtyp: u8,
+    pub tucmd: u8,
+    pub status: u8,
+    pub hdrlen: u8,
+    pub mss: u16,
+}
+
+impl TxContextDescriptor {
+    /// Context for frames laid out as `offsets`
+    pub fn checksum(offsets: &ChecksumOffsets) -> Self {
+        let (ipcss, ipcso, ipcse) = offsets.ip.unwrap_or((0, 0, 0));
+        let mut tucmd = TX_CMD_DEXT | TX_CMD_RS;
+        if offsets.tcp {
+            tucmd |= TX_TUCMD_TCP;
+        }
+        if offsets.ip.is_some() {
+            tucmd |= TX_TUCMD_IP;
+        }
+        Self {
+            ipcss,
+            ipcso,
+            ipcse,
+            tucss: offsets.tucss,
+            tucso: offsets.tucso,
+            dtyp: TX_DTYP_CONTEXT,
+            tucmd,
+            ..Default::default()
+        }
+    }
+}
+
 /// Receive descriptor (16 bytes)
 #[repr(C, packed)]
 #[derive(Debug, Clone, Copy, Default)]
@@ -37,6 +90,17 @@ impl TxDescriptor {
             special: 0,
         }
     }
+
+    /// Turn this into an extended data descriptor whose checksums the NIC fills
+    /// in from the current context (TCP/UDP, plus the IPv4 header if `ipv4`)
+    ///
+    /// In the extended layout the `cso` byte carries the descriptor type and
+    /// `css` the packet options.
+    pub fn enable_checksum_offload(&mut self, ipv4: bool) {
+        self.cmd |= TX_CMD_DEXT;
+        self.cso = TX_DTYP_DATA;
+        self.css = TX_POPTS_TXSM | if ipv4 { TX_POPTS_IXSM } else { 0 };
+    }
 }
 
 impl RxDescriptor {
@@ -1,8 +1,11 @@
 //! Intel E1000 Network Driver
 
 mod descriptors;
+pub mod irq;
+mod offload;
 mod regs;
 mod ring;
+mod stats;
 
 use crate::memory::dma::{phys_to_virt, virt_to_phys};
 use crate::serial_println;
@@ -10,9 +13,11 @@ use alloc::vec::Vec;
 use core::ptr::{read_volatile, write_volatile};
 use spin::Mutex;
 
-pub use descriptors::{RxDescriptor, TxDescriptor};
+pub use descriptors::{RxDescriptor, TxContextDescriptor, TxDescriptor};
+pub use offload::ChecksumOffsets;
 pub use regs::*;
 pub use ring::{RxRing, TxRing};
+pub use stats::E1000HwStats;
 
 /// Network device statistics
 #[derive(Debug, Clone, Copy, Default)]
@@ -29,6 +34,51 @@ pub const RX_RING_SIZE: usize = 256;
 pub const TX_RING_SIZE: usize = 128;
 /// Size of each packet buffer
 pub const BUFFER_SIZE: usize = 2048;
+/// Size of a receive batch buffer (max Ethernet frame, rounded up)
+pub const PACKET_BUF_SIZE: usize = 1536;
+
+/// Fixed-size packet buffer for allocation-free batched receive
+#[derive(Clone)]
+pub struct PacketBuf {
+    pub data: [u8; PACKET_BUF_SIZE],
+    pub len: usize,
+}
+
+impl PacketBuf {
+    pub const fn new() -> Self {
+        Self {
+            data: [0; PACKET_BUF_SIZE],
+            len: 0,
+        }
+    }
+
+    /// Received bytes
+    pub fn as_slice(&self) -> &[u8] {
+        &self.data[..self.len]
+    }
+}
+
+impl Default for PacketBuf {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Driver options chosen at boot
+#[derive(Debug, Clone, Copy)]
+pub struct E1000Config {
+    /// Let the NIC fill in IPv4 and TCP/UDP checksums on transmit
+    pub checksum_offload: bool,
+    /// Accept every unicast and multicast frame, not just our own address
+    /// and broadcasts (see `set_promiscuous`)
+    pub promiscuous: bool,
+}
+
+impl Default for E1000Config {
+    fn default() -> Self {
+        Self { checksum_offload: true, promiscuous: true }
+    }
+}
 
 /// E1000 Network Interface Controller
 pub struct E1000 {
@@ -37,17 +87,28 @@ pub struct E1000 {
     tx_ring: TxRing,
     mac_address: [u8; 6],
     stats: DeviceStats,
+    /// Running totals of the clear-on-read hardware counters
+    hw_stats: E1000HwStats,
+    config: E1000Config,
+    /// Hardware verifies IP/TCP/UDP checksums on receive
+    rx_checksum_offload: bool,
+    /// Checksum offsets loaded by the last TX context descriptor
+    tx_context: Option<ChecksumOffsets>,
 }
 
 impl E1000 {
     /// Create a new E1000 driver instance
-    pub fn new(mmio_base: u64) -> Self {
+    pub fn new(mmio_base: u64, config: E1000Config) -> Self {
         Self {
             mmio_base,
             rx_ring: RxRing::new(),
             tx_ring: TxRing::new(),
             mac_address: [0; 6],
             stats: DeviceStats::default(),
+            hw_stats: E1000HwStats::default(),
+            config,
+            rx_checksum_offload: false,
+            tx_context: None,
         }
     }
 
@@ -99,8 +160,8 @@ impl E1000 {
 
         // Enable RX interrupts (some E1000 implementations need this even for polling)
         self.write_reg(REG_IMC, 0xFFFFFFFF); // Clear all interrupt causes
-        // Enable RX-related interrupts
-        self.write_reg(REG_IMS, 0x000000FF); // Enable RX interrupts (RXDMT0, RXO, RXT0, etc.)
+        // Enable RX-related interrupts, drained by `irq::handle_interrupt` once routed
+        self.write_reg(REG_IMS, ICR_RX);
 
         // Set link up
         let ctrl = self.read_reg(REG_CTRL);
@@ -181,14 +242,22 @@ impl E1000 {
         // Set head pointer to 0
         self.write_reg(REG_RDH, 0);
 
+        // No multicast groups until add_multicast
+        self.clear_multicast();
+
         // Configure RX control (but not enabled yet)
         let rctl = RCTL_SBP |          // Store bad packets
-            RCTL_UPE |          // Unicast promiscuous
-            RCTL_MPE |          // Multicast promiscuous
             RCTL_BAM |          // Accept broadcast
             RCTL_BSIZE_2048 |   // Buffer size 2048
             RCTL_SECRC;         // Strip CRC
+        let rctl = rctl_promiscuous(rctl, self.config.promiscuous);
         self.write_reg(REG_RCTL, rctl);
+        serial_println!("E1000: Promiscuous mode {}", if self.config.promiscuous { "on" } else { "off" });
+
+        // Enable receive checksum offload (IP + TCP/UDP), bad frames are flagged in descriptor errors
+        self.write_reg(REG_RXCSUM, RXCSUM_IPOFLD | RXCSUM_TUOFLD);
+        self.rx_checksum_offload = self.read_reg(REG_RXCSUM) & (RXCSUM_IPOFLD | RXCSUM_TUOFLD) != 0;
+        serial_println!("E1000: RX checksum offload {}", if self.rx_checksum_offload { "enabled" } else { "unavailable" });
 
         // Set tail pointer - this makes descriptors available to hardware
         self.write_reg(REG_RDT, (RX_RING_SIZE - 1) as u32);
@@ -231,6 +300,13 @@ impl E1000 {
         // Set inter-packet gap
         self.write_reg(REG_TIPG, 10 | (10 << 10) | (10 << 20));
 
+        // No checksum context is loaded until the first offloaded frame
+        self.tx_context = None;
+        serial_println!(
+            "E1000: TX checksum offload {}",
+            if self.config.checksum_offload { "enabled" } else { "disabled" }
+        );
+
         serial_println!("E1000: TX ring initialized");
         Ok(())
     }
@@ -246,26 +322,29 @@ impl E1000 {
             return Err("Packet too large");
         }
 
-        let tail = self.read_reg(REG_TDT) as usize;
-        let desc = self.tx_ring.get_descriptor(tail);
+        // IPv4/IPv6 TCP/UDP frames get their checksums from the NIC; a new
+        // context descriptor goes first whenever the header layout changes
+        let offload = if self.config.checksum_offload { ChecksumOffsets::parse(data) } else { None };
+        let new_context = offload.filter(|offsets| self.tx_context != Some(*offsets));
 
-        // Wait for descriptor to be available
-        unsafe {
-            while (*desc).status & TX_STATUS_DD == 0 {
-                // Check if this is an uninitialized descriptor
-                if (*desc).buffer_addr == 0 {
-                    break;
-                }
-                core::hint::spin_loop();
-            }
+        // Check for room for both descriptors before writing either
+        self.reclaim_tx();
+        if self.tx_ring.free() < 1 + new_context.is_some() as usize {
+            return Err("TX ring full");
+        }
+
+        if let Some(offsets) = new_context {
+            let index = self.tx_ring.claim()?;
+            self.tx_ring.prepare_context(index, &offsets);
+            self.tx_context = Some(offsets);
         }
 
         // Copy data to buffer and update descriptor
-        self.tx_ring.prepare_send(tail, data);
+        let index = self.tx_ring.claim()?;
+        self.tx_ring.prepare_send(index, data, offload.as_ref());
 
-        // Update tail pointer
-        let new_tail = (tail + 1) % TX_RING_SIZE;
-        self.write_reg(REG_TDT, new_tail as u32);
+        // Hand everything up to the new tail to the NIC
+        self.write_reg(REG_TDT, self.tx_ring.tail() as u32);
 
         // Update stats
         self.stats.tx_packets += 1;
@@ -274,6 +353,11 @@ impl E1000 {
         Ok(())
     }
 
+    /// Free the TX descriptors the NIC has finished sending; returns how many
+    pub fn reclaim_tx(&mut self) -> usize {
+        self.tx_ring.reclaim()
+    }
+
     /// Receive a packet (returns None if no packet available)
     pub fn receive(&mut self) -> Option<Vec<u8>> {
         let tail = (self.read_reg(REG_RDT) as usize + 1) % RX_RING_SIZE;
@@ -286,7 +370,7 @@ impl E1000 {
             }
 
             let length = (*desc).length as usize;
-            if length == 0 || length > BUFFER_SIZE {
+            if length == 0 || length > BUFFER_SIZE || checksum_failed(self.rx_checksum_offload, &*desc) {
                 // Reset descriptor and move on
                 (*desc).status = 0;
                 self.write_reg(REG_RDT, tail as u32);
@@ -310,6 +394,51 @@ impl E1000 {
         }
     }
 
+    /// Drain up to `out.len()` ready packets in one call (returns count received)
+    /// Copies into caller-owned buffers so the hot path never allocates
+    pub fn receive_batch(&mut self, out: &mut [PacketBuf]) -> usize {
+        let first = (self.read_reg(REG_RDT) as usize + 1) % RX_RING_SIZE;
+        let rx_checksum_offload = self.rx_checksum_offload;
+        let mut count = 0;
+        let mut bytes = 0;
+
+        let consumed = self.rx_ring.drain(first, |desc, frame| {
+            if count == out.len() {
+                return false;
+            }
+
+            // Oversized/empty/corrupt frames are consumed and dropped
+            let length = desc.length as usize;
+            if length != 0 && length <= PACKET_BUF_SIZE && !checksum_failed(rx_checksum_offload, desc) {
+                let buf = &mut out[count];
+                buf.data[..length].copy_from_slice(frame);
+                buf.len = length;
+                count += 1;
+                bytes += length as u64;
+            }
+            true
+        });
+
+        // Hand the consumed descriptors back to the NIC in one tail update
+        if consumed > 0 {
+            self.write_reg(REG_RDT, ((first + consumed - 1) % RX_RING_SIZE) as u32);
+        }
+        self.stats.rx_packets += count as u64;
+        self.stats.rx_bytes += bytes;
+
+        count
+    }
+
+    /// Whether receive checksums are verified by hardware
+    pub fn rx_checksum_offload(&self) -> bool {
+        self.rx_checksum_offload
+    }
+
+    /// Whether transmit checksums are filled in by hardware
+    pub fn tx_checksum_offload(&self) -> bool {
+        self.config.checksum_offload
+    }
+
     /// Check if there's a packet ready to receive
     pub fn has_packet(&self) -> bool {
         let rdt = self.read_reg(REG_RDT) as usize;
@@ -329,15 +458,211 @@ impl E1000 {
     pub fn get_stats(&self) -> DeviceStats {
         self.stats
     }
+
+    /// Read the hardware statistics registers into the running totals
+    ///
+    /// Reading clears the counters, so each call adds what accumulated since
+    /// the previous one.
+    pub fn update_hw_stats(&mut self) {
+        let mmio_base = self.mmio_base;
+        self.hw_stats.accumulate(|reg| unsafe { read_volatile((mmio_base + reg as u64) as *const u32) });
+    }
+
+    /// Hardware counter totals as of the last `update_hw_stats`
+    ///
+    /// Unlike `get_stats` these include what the driver never sees, such as
+    /// frames missed for lack of descriptors (mpc) or dropped with CRC errors.
+    pub fn hw_stats(&self) -> E1000HwStats {
+        self.hw_stats
+    }
+
+    /// Print every hardware counter to serial, one per line
+    pub fn print_stats(&mut self) {
+        self.update_hw_stats();
+        for (name, value) in self.hw_stats().counters() {
+            serial_println!("E1000: {} = {}", name, value);
+        }
+    }
+
+    /// Accept all unicast and multicast frames (true) or only frames for our
+    /// MAC address and broadcasts (false); takes effect immediately
+    pub fn set_promiscuous(&mut self, enabled: bool) {
+        self.config.promiscuous = enabled;
+        self.write_reg(REG_RCTL, rctl_promiscuous(self.read_reg(REG_RCTL), enabled));
+    }
+
+    /// Whether the receiver accepts frames for other addresses
+    pub fn is_promiscuous(&self) -> bool {
+        self.read_reg(REG_RCTL) & (RCTL_UPE | RCTL_MPE) != 0
+    }
+
+    /// Receive frames sent to a multicast group address
+    /// Sets the address's bit in the Multicast Table Array; other addresses
+    /// sharing the hash get through too, so the stack still filters.
+    pub fn add_multicast(&mut self, mac: [u8; 6]) {
+        let (index, bit) = multicast_hash(mac);
+        let reg = REG_MTA + index as u32 * 4;
+        self.write_reg(reg, self.read_reg(reg) | bit);
+    }
+
+    /// Leave every multicast group added with add_multicast
+    pub fn clear_multicast(&mut self) {
+        for index in 0..MTA_ENTRIES {
+            self.write_reg(REG_MTA + index as u32 * 4, 0);
+        }
+    }
+
+    /// Loop transmitted frames straight back to the receiver (for benchmarks)
+    /// Sets MAC loopback in RCTL and PHY loopback, which is what QEMU's e1000 honours
+    pub fn enable_loopback_mode(&mut self) {
+        self.set_loopback(true);
+    }
+
+    /// Return to normal operation after `enable_loopback_mode`
+    pub fn disable_loopback_mode(&mut self) {
+        self.set_loopback(false);
+    }
+
+    fn set_loopback(&mut self, enabled: bool) {
+        let rctl = self.read_reg(REG_RCTL) & !RCTL_LBM_MASK;
+        self.write_reg(REG_RCTL, if enabled { rctl | RCTL_LBM_MAC } else { rctl });
+
+        match self.read_phy(PHY_CTRL) {
+            Some(ctrl) => {
+                let ctrl = if enabled { ctrl | PHY_CTRL_LOOPBACK } else { ctrl & !PHY_CTRL_LOOPBACK };
+                if !self.write_phy(PHY_CTRL, ctrl) {
+                    serial_println!("E1000: PHY loopback write failed");
+                }
+            }
+            None => serial_println!("E1000: PHY control read failed"),
+        }
+        serial_println!("E1000: loopback {}", if enabled { "enabled" } else { "disabled" });
+    }
+
+    /// Wait for an MDI transaction to finish (None on error or timeout)
+    fn wait_mdic(&self) -> Option<u32> {
+        for _ in 0..10_000 {
+            let mdic = self.read_reg(REG_MDIC);
+            if mdic & MDIC_READY != 0 {
+                return (mdic & MDIC_ERROR == 0).then_some(mdic);
+            }
+            core::hint::spin_loop();
+        }
+        None
+    }
+
+    /// Read a PHY register through MDIC
+    fn read_phy(&self, reg: u32) -> Option<u16> {
+        self.write_reg(REG_MDIC, (reg << MDIC_REG_SHIFT) | (PHY_ADDR << MDIC_PHY_SHIFT) | MDIC_OP_READ);
+        self.wait_mdic().map(|mdic| mdic as u16)
+    }
+
+    /// Write a PHY register through MDIC
+    fn write_phy(&self, reg: u32, value: u16) -> bool {
+        self.write_reg(
+            REG_MDIC,
+            value as u32 | (reg << MDIC_REG_SHIFT) | (PHY_ADDR << MDIC_PHY_SHIFT) | MDIC_OP_WRITE,
+        );
+        self.wait_mdic().is_some()
+    }
+}
+
+/// MTA register index and bit for a multicast address
+///
+/// With RCTL.MO = 00 (as init_rx leaves it) the 12-bit hash is address bits
+/// 47:36: the high nibble of byte 4 and all of byte 5. Its upper 7 bits pick
+/// the register and the lower 5 the bit.
+fn multicast_hash(mac: [u8; 6]) -> (usize, u32) {
+    let hash = ((mac[4] >> 4) as u16 | ((mac[5] as u16) << 4)) & 0xFFF;
+    ((hash >> 5) as usize, 1 << (hash & 0x1F))
+}
+
+/// RCTL with unicast and multicast promiscuous set or cleared, other bits kept
+fn rctl_promiscuous(rctl: u32, enabled: bool) -> u32 {
+    if enabled {
+        rctl | RCTL_UPE | RCTL_MPE
+    } else {
+        rctl & !(RCTL_UPE | RCTL_MPE)
+    }
+}
+
+/// Check whether hardware flagged a checksum error on a received frame
+fn checksum_failed(rx_checksum_offload: bool, desc: &RxDescriptor) -> bool {
+    rx_checksum_offload
+        && desc.status & RX_STATUS_IXSM == 0
+        && desc.errors & (RX_ERR_IPE | RX_ERR_TCPE) != 0
 }
 
 /// Global E1000 instance
 pub static E1000_DEVICE: Mutex<Option<E1000>> = Mutex::new(None);
 
 /// Initialize the E1000 driver with the given MMIO base address
-pub fn init(mmio_base: u64) -> Result<(), &'static str> {
-    let mut device = E1000::new(mmio_base);
+pub fn init(mmio_base: u64, config: E1000Config) -> Result<(), &'static str> {
+    let mut device = E1000::new(mmio_base, config);
     device.init()?;
     *E1000_DEVICE.lock() = Some(device);
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Device whose registers are plain memory
+    fn device(regs: &mut [u32]) -> E1000 {
+        E1000::new(regs.as_mut_ptr() as u64, E1000Config::default())
+    }
+
+    #[test]
+    fn test_set_promiscuous_toggles_only_upe_mpe() {
+        let mut regs = alloc::vec![0u32; 0x1000 / 4];
+        let mut nic = device(&mut regs);
+        let base = RCTL_EN | RCTL_SBP | RCTL_BAM | RCTL_SECRC | RCTL_LBM_MAC;
+        nic.write_reg(REG_RCTL, base);
+
+        nic.set_promiscuous(true);
+        assert_eq!(nic.read_reg(REG_RCTL), base | RCTL_UPE | RCTL_MPE);
+        assert!(nic.is_promiscuous());
+
+        nic.set_promiscuous(false);
+        assert_eq!(nic.read_reg(REG_RCTL), base);
+        assert!(!nic.is_promiscuous());
+        // Clearing again leaves the receiver enabled and everything else alone
+        nic.set_promiscuous(false);
+        assert_eq!(nic.read_reg(REG_RCTL), base);
+    }
+
+    #[test]
+    fn test_multicast_hash_known_addresses() {
+        // IPv4 all-hosts 224.0.0.1: hash 0x010
+        assert_eq!(multicast_hash([0x01, 0x00, 0x5E, 0x00, 0x00, 0x01]), (0, 1 << 16));
+        // mDNS 224.0.0.251: hash 0xFB0
+        assert_eq!(multicast_hash([0x01, 0x00, 0x5E, 0x00, 0x00, 0xFB]), (0x7D, 1 << 16));
+        // SSDP 239.255.255.250: hash 0xFAF (low nibble from byte 4)
+        assert_eq!(multicast_hash([0x01, 0x00, 0x5E, 0x7F, 0xFF, 0xFA]), (0x7D, 1 << 15));
+        // Only bits 47:36 count
+        assert_eq!(multicast_hash([0xFF, 0xFF, 0xFF, 0xFF, 0x0F, 0x00]), (0, 1));
+    }
+
+    #[test]
+    fn test_add_and_clear_multicast() {
+        let mut regs = alloc::vec![0u32; 0x6000 / 4];
+        let mut nic = device(&mut regs);
+        let mta = |nic: &E1000, index: u32| nic.read_reg(REG_MTA + index * 4);
+
+        nic.add_multicast([0x01, 0x00, 0x5E, 0x00, 0x00, 0xFB]);
+        nic.add_multicast([0x01, 0x00, 0x5E, 0x7F, 0xFF, 0xFA]);
+        assert_eq!(mta(&nic, 0x7D), (1 << 16) | (1 << 15));
+        assert_eq!((0..MTA_ENTRIES as u32).filter(|&i| mta(&nic, i) != 0).count(), 1);
+
+        nic.clear_multicast();
+        assert!((0..MTA_ENTRIES as u32).all(|i| mta(&nic, i) == 0));
+    }
+
+    #[test]
+    fn test_rctl_promiscuous_bits() {
+        assert_eq!(rctl_promiscuous(RCTL_EN, true), RCTL_EN | RCTL_UPE | RCTL_MPE);
+        assert_eq!(rctl_promiscuous(u32::MAX, false), !(RCTL_UPE | RCTL_MPE));
+        assert_eq!(rctl_promiscuous(RCTL_EN | RCTL_UPE, false), RCTL_EN);
+    }
+}
@@ -0,0 +1,160 @@
+//! TX checksum offload: locating the checksums in an outgoing frame
+//!
+//! The E1000 fills in checksums described by a context descriptor
+//! (`TxContextDescriptor`). It sums from the start offset to the end of the
+//! packet and stores the complement, so the TCP/UDP checksum field must first
+//! hold the pseudo-header sum, and the IPv4 header checksum field must be zero.
+
+/// Ethernet header length (no VLAN tag)
+const ETH_HEADER: usize = 14;
+
+const ETHERTYPE_IPV4: u16 = 0x0800;
+const ETHERTYPE_IPV6: u16 = 0x86DD;
+const IPV6_HEADER: usize = 40;
+const PROTO_TCP: u8 = 6;
+const PROTO_UDP: u8 = 17;
+
+/// Where the checksums of one frame live (offsets from the frame start)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChecksumOffsets {
+    /// IPv4 header (start, checksum field, last byte); None for IPv6
+    pub ip: Option<(u8, u8, u16)>,
+    /// Start of the TCP/UDP header
+    pub tucss: u8,
+    /// TCP/UDP checksum field
+    pub tucso: u8,
+    /// TCP rather than UDP
+    pub tcp: bool,
+}
+
+impl ChecksumOffsets {
+    /// Offsets of an unfragmented IPv4 or IPv6 TCP/UDP frame (None for anything else)
+    pub fn parse(frame: &[u8]) -> Option<Self> {
+        let ethertype = u16::from_be_bytes([*frame.get(12)?, *frame.get(13)?]);
+        let (ip, proto, l4) = match ethertype {
+            ETHERTYPE_IPV4 => {
+                let ihl = (*frame.get(ETH_HEADER)? & 0x0F) as usize * 4;
+                // More-fragments set or a fragment offset: only the first piece has the L4 header
+                let frag = u16::from_be_bytes([*frame.get(ETH_HEADER + 6)?, *frame.get(ETH_HEADER + 7)?]);
+                if ihl < 20 || frag & 0x3FFF != 0 {
+                    return None;
+                }
+                let ip = (ETH_HEADER as u8, (ETH_HEADER + 10) as u8, (ETH_HEADER + ihl - 1) as u16);
+                (Some(ip), *frame.get(ETH_HEADER + 9)?, ETH_HEADER + ihl)
+            }
+            // Next header must be TCP/UDP directly (no extension headers)
+            ETHERTYPE_IPV6 => (None, *frame.get(ETH_HEADER + 6)?, ETH_HEADER + IPV6_HEADER),
+            _ => return None,
+        };
+
+        let field = match proto {
+            PROTO_TCP => 16,
+            PROTO_UDP => 6,
+            _ => return None,
+        };
+        if frame.len() < l4 + field + 2 {
+            return None;
+        }
+        Some(Self { ip, tucss: l4 as u8, tucso: (l4 + field) as u8, tcp: proto == PROTO_TCP })
+    }
+
+    /// Prepare `frame` for the hardware: zero the IPv4 header checksum and seed
+    /// the TCP/UDP checksum with the pseudo-header sum
+    pub fn seed(&self, frame: &mut [u8]) {
+        let l4_len = frame.len() - self.tucss as usize;
+        let proto = if self.tcp { PROTO_TCP } else { PROTO_UDP };
+        let mut sum = proto as u32 + l4_len as u32;
+        let addresses = match self.ip {
+            Some((start, cso, _)) => {
+                frame[cso as usize..cso as usize + 2].fill(0);
+                start as usize + 12..start as usize + 20
+            }
+            None => ETH_HEADER + 8..ETH_HEADER + IPV6_HEADER,
+        };
+        sum += frame[addresses].chunks(2).map(|w| u16::from_be_bytes([w[0], w[1]]) as u32).sum::<u32>();
+        while sum > 0xFFFF {
+            sum = (sum & 0xFFFF) + (sum >> 16);
+        }
+        let tucso = self.tucso as usize;
+        frame[tucso..tucso + 2].copy_from_slice(&(sum as u16).to_be_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Internet checksum of `data` (complemented ones' complement sum)
+    fn checksum(data: &[u8]) -> u16 {
+        let mut sum: u32 = data.chunks(2).map(|w| u16::from_be_bytes([w[0], *w.get(1).unwrap_or(&0)]) as u32).sum();
+        while sum > 0xFFFF {
+            sum = (sum & 0xFFFF) + (sum >> 16);
+        }
+        !(sum as u16)
+    }
+
+    /// What the E1000 does with a context: sum from the start and store the complement
+    fn insert(frame: &mut [u8], start: usize, field: usize, end: usize) {
+        let sum = checksum(&frame[start..=end]);
+        frame[field..field + 2].copy_from_slice(&sum.to_be_bytes());
+    }
+
+    #[test]
+    fn test_seeded_udp_checksum_matches_software() {
+        let mut frame = [0u8; 14 + 20 + 8 + 5];
+        frame[12..14].copy_from_slice(&ETHERTYPE_IPV4.to_be_bytes());
+        frame[14] = 0x45;
+        frame[16..18].copy_from_slice(&33u16.to_be_bytes());
+        frame[22] = 64;
+        frame[23] = PROTO_UDP;
+        frame[26..30].copy_from_slice(&[10, 0, 2, 15]);
+        frame[30..34].copy_from_slice(&[10, 0, 2, 2]);
+        frame[34..36].copy_from_slice(&5000u16.to_be_bytes());
+        frame[36..38].copy_from_slice(&5001u16.to_be_bytes());
+        frame[38..40].copy_from_slice(&13u16.to_be_bytes());
+        frame[42..].copy_from_slice(b"hello");
+
+        // Reference: IPv4 header checksum and UDP checksum over pseudo-header + datagram
+        let mut expected = frame;
+        insert(&mut expected, 14, 24, 33);
+        let mut pseudo = [0u8; 12 + 13];
+        pseudo[..8].copy_from_slice(&frame[26..34]);
+        pseudo[9] = PROTO_UDP;
+        pseudo[10..12].copy_from_slice(&13u16.to_be_bytes());
+        pseudo[12..].copy_from_slice(&frame[34..]);
+        expected[40..42].copy_from_slice(&checksum(&pseudo).to_be_bytes());
+
+        let offsets = ChecksumOffsets::parse(&frame).unwrap();
+        assert_eq!(offsets, ChecksumOffsets { ip: Some((14, 24, 33)), tucss: 34, tucso: 40, tcp: false });
+        frame[24] = 0xAB; // stale header checksum is cleared
+        offsets.seed(&mut frame);
+        insert(&mut frame, 14, 24, 33);
+        let end = frame.len() - 1;
+        insert(&mut frame, 34, 40, end);
+        assert_eq!(frame, expected);
+    }
+
+    #[test]
+    fn test_only_tcp_udp_frames_offloaded() {
+        let mut frame = [0u8; 80];
+        frame[12..14].copy_from_slice(&ETHERTYPE_IPV6.to_be_bytes());
+        frame[20] = PROTO_TCP;
+        assert_eq!(
+            ChecksumOffsets::parse(&frame),
+            Some(ChecksumOffsets { ip: None, tucss: 54, tucso: 70, tcp: true })
+        );
+
+        // ICMPv6, ARP and a non-first IPv4 fragment
+        frame[20] = 58;
+        assert_eq!(ChecksumOffsets::parse(&frame), None);
+        frame[12..14].copy_from_slice(&0x0806u16.to_be_bytes());
+        assert_eq!(ChecksumOffsets::parse(&frame), None);
+        frame[12..14].copy_from_slice(&ETHERTYPE_IPV4.to_be_bytes());
+        frame[14] = 0x45;
+        frame[20] = 0;
+        frame[23] = PROTO_UDP;
+        assert!(ChecksumOffsets::parse(&frame).is_some());
+        frame[21] = 0x10;
+        assert_eq!(ChecksumOffsets::parse(&frame), None);
+    }
+}
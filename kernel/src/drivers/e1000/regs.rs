@@ -5,6 +5,7 @@ pub const REG_CTRL: u32 = 0x0000;
 pub const REG_STATUS: u32 = 0x0008;
 pub const REG_EECD: u32 = 0x0010;
 pub const REG_EERD: u32 = 0x0014;
+pub const REG_MDIC: u32 = 0x0020;
 
 // Interrupt registers
 pub const REG_ICR: u32 = 0x00C0;
@@ -12,6 +13,13 @@ pub const REG_ICS: u32 = 0x00C8;
 pub const REG_IMS: u32 = 0x00D0;
 pub const REG_IMC: u32 = 0x00D8;
 
+// Interrupt cause bits (shared by ICR, IMS and IMC)
+pub const ICR_RXDMT0: u32 = 1 << 4; // RX descriptors below minimum threshold
+pub const ICR_RXO: u32 = 1 << 6; // Receiver overrun
+pub const ICR_RXT0: u32 = 1 << 7; // Receiver timer (frame received)
+/// Causes that mean frames are waiting in the RX ring
+pub const ICR_RX: u32 = ICR_RXDMT0 | ICR_RXO | ICR_RXT0;
+
 // Receive registers
 pub const REG_RCTL: u32 = 0x0100;
 pub const REG_RDBAL: u32 = 0x2800;
@@ -29,6 +37,13 @@ pub const REG_TDLEN: u32 = 0x3808;
 pub const REG_TDH: u32 = 0x3810;
 pub const REG_TDT: u32 = 0x3818;
 
+// Receive checksum control
+pub const REG_RXCSUM: u32 = 0x5000;
+
+// Multicast Table Array: 128 x 32 bits, one bit per 12-bit address hash
+pub const REG_MTA: u32 = 0x5200;
+pub const MTA_ENTRIES: usize = 128;
+
 // MAC address registers
 pub const REG_RAL: u32 = 0x5400;
 pub const REG_RAH: u32 = 0x5404;
@@ -40,12 +55,27 @@ pub const CTRL_RST: u32 = 1 << 26; // Device Reset
 // Status register bits
 pub const STATUS_LU: u32 = 1 << 1; // Link Up
 
+// MDI control bits (PHY register access)
+pub const MDIC_REG_SHIFT: u32 = 16; // PHY register address
+pub const MDIC_PHY_SHIFT: u32 = 21; // PHY address
+pub const MDIC_OP_WRITE: u32 = 1 << 26;
+pub const MDIC_OP_READ: u32 = 2 << 26;
+pub const MDIC_READY: u32 = 1 << 28;
+pub const MDIC_ERROR: u32 = 1 << 30;
+
+// PHY registers and bits
+pub const PHY_ADDR: u32 = 1; // Internal PHY
+pub const PHY_CTRL: u32 = 0;
+pub const PHY_CTRL_LOOPBACK: u16 = 1 << 14;
+
 // Receive control bits
 pub const RCTL_EN: u32 = 1 << 1; // Receiver Enable
 pub const RCTL_SBP: u32 = 1 << 2; // Store Bad Packets
 pub const RCTL_UPE: u32 = 1 << 3; // Unicast Promiscuous Enable
 pub const RCTL_MPE: u32 = 1 << 4; // Multicast Promiscuous Enable
 pub const RCTL_LPE: u32 = 1 << 5; // Long Packet Enable
+pub const RCTL_LBM_MAC: u32 = 1 << 6; // Loopback Mode: MAC
+pub const RCTL_LBM_MASK: u32 = 3 << 6;
 pub const RCTL_BAM: u32 = 1 << 15; // Broadcast Accept Mode
 pub const RCTL_BSIZE_2048: u32 = 0 << 16; // Buffer Size 2048
 pub const RCTL_BSIZE_1024: u32 = 1 << 16; // Buffer Size 1024
@@ -53,6 +83,10 @@ pub const RCTL_BSIZE_512: u32 = 2 << 16; // Buffer Size 512
 pub const RCTL_BSIZE_256: u32 = 3 << 16; // Buffer Size 256
 pub const RCTL_SECRC: u32 = 1 << 26; // Strip Ethernet CRC
 
+// Receive checksum control bits
+pub const RXCSUM_IPOFLD: u32 = 1 << 8; // IP checksum offload
+pub const RXCSUM_TUOFLD: u32 = 1 << 9; // TCP/UDP checksum offload
+
 // Transmit control bits
 pub const TCTL_EN: u32 = 1 << 1; // Transmitter Enable
 pub const TCTL_PSP: u32 = 1 << 3; // Pad Short Packets
@@ -63,6 +97,19 @@ pub const TCTL_COLD_SHIFT: u32 = 12; // Collision Distance
 pub const TX_CMD_EOP: u8 = 1 << 0; // End Of Packet
 pub const TX_CMD_IFCS: u8 = 1 << 1; // Insert FCS
 pub const TX_CMD_RS: u8 = 1 << 3; // Report Status
+pub const TX_CMD_DEXT: u8 = 1 << 5; // Extended descriptor (context or data)
+
+// Extended TX descriptor type (upper nibble of the byte after the length)
+pub const TX_DTYP_CONTEXT: u8 = 0 << 4;
+pub const TX_DTYP_DATA: u8 = 1 << 4;
+
+// TX data descriptor packet options
+pub const TX_POPTS_IXSM: u8 = 1 << 0; // Insert IP checksum
+pub const TX_POPTS_TXSM: u8 = 1 << 1; // Insert TCP/UDP checksum
+
+// TX context descriptor TCP/UDP command bits
+pub const TX_TUCMD_TCP: u8 = 1 << 0; // Packet is TCP (else UDP)
+pub const TX_TUCMD_IP: u8 = 1 << 1; // Packet is IPv4 (else IPv6)
 
 // TX descriptor status bits
 pub const TX_STATUS_DD: u8 = 1 << 0; // Descriptor Done
@@ -70,3 +117,67 @@ pub const TX_STATUS_DD: u8 = 1 << 0; // Descriptor Done
 // RX descriptor status bits
 pub const RX_STATUS_DD: u8 = 1 << 0; // Descriptor Done
 pub const RX_STATUS_EOP: u8 = 1 << 1; // End Of Packet
+pub const RX_STATUS_IXSM: u8 = 1 << 2; // Ignore Checksum Indication
+
+// RX descriptor error bits
+pub const RX_ERR_TCPE: u8 = 1 << 5; // TCP/UDP checksum error
+pub const RX_ERR_IPE: u8 = 1 << 6; // IP checksum error
+
+// Statistics registers (82540EM SDM section 13.7). Every counter clears when
+// read and saturates rather than wrapping. The 64-bit octet counters must be
+// read low then high; reading the high half clears both.
+pub const REG_CRCERRS: u32 = 0x4000; // CRC Error Count
+pub const REG_ALGNERRC: u32 = 0x4004; // Alignment Error Count
+pub const REG_SYMERRS: u32 = 0x4008; // Symbol Error Count
+pub const REG_RXERRC: u32 = 0x400C; // RX Error Count
+pub const REG_MPC: u32 = 0x4010; // Missed Packets Count (RX FIFO full)
+pub const REG_SCC: u32 = 0x4014; // Single Collision Count
+pub const REG_ECOL: u32 = 0x4018; // Excessive Collisions Count
+pub const REG_MCC: u32 = 0x401C; // Multiple Collision Count
+pub const REG_LATECOL: u32 = 0x4020; // Late Collisions Count
+pub const REG_COLC: u32 = 0x4028; // Collision Count
+pub const REG_DC: u32 = 0x4030; // Defer Count
+pub const REG_TNCRS: u32 = 0x4034; // Transmit with No CRS
+pub const REG_SEC: u32 = 0x4038; // Sequence Error Count
+pub const REG_CEXTERR: u32 = 0x403C; // Carrier Extension Error Count
+pub const REG_RLEC: u32 = 0x4040; // Receive Length Error Count
+pub const REG_XONRXC: u32 = 0x4048; // XON Received Count
+pub const REG_XONTXC: u32 = 0x404C; // XON Transmitted Count
+pub const REG_XOFFRXC: u32 = 0x4050; // XOFF Received Count
+pub const REG_XOFFTXC: u32 = 0x4054; // XOFF Transmitted Count
+pub const REG_FCRUC: u32 = 0x4058; // Flow Control Receive Unsupported Count
+pub const REG_PRC64: u32 = 0x405C; // Packets Received (64 bytes)
+pub const REG_PRC127: u32 = 0x4060; // Packets Received (65-127 bytes)
+pub const REG_PRC255: u32 = 0x4064; // Packets Received (128-255 bytes)
+pub const REG_PRC511: u32 = 0x4068; // Packets Received (256-511 bytes)
+pub const REG_PRC1023: u32 = 0x406C; // Packets Received (512-1023 bytes)
+pub const REG_PRC1522: u32 = 0x4070; // Packets Received (1024-Max bytes)
+pub const REG_GPRC: u32 = 0x4074; // Good Packets Received Count
+pub const REG_BPRC: u32 = 0x4078; // Broadcast Packets Received Count
+pub const REG_MPRC: u32 = 0x407C; // Multicast Packets Received Count
+pub const REG_GPTC: u32 = 0x4080; // Good Packets Transmitted Count
+pub const REG_GORCL: u32 = 0x4088; // Good Octets Received Count (low)
+pub const REG_GORCH: u32 = 0x408C; // Good Octets Received Count (high)
+pub const REG_GOTCL: u32 = 0x4090; // Good Octets Transmitted Count (low)
+pub const REG_GOTCH: u32 = 0x4094; // Good Octets Transmitted Count (high)
+pub const REG_RNBC: u32 = 0x40A0; // Receive No Buffers Count
+pub const REG_RUC: u32 = 0x40A4; // Receive Undersize Count
+pub const REG_RFC: u32 = 0x40A8; // Receive Fragment Count
+pub const REG_ROC: u32 = 0x40AC; // Receive Oversize Count
+pub const REG_RJC: u32 = 0x40B0; // Receive Jabber Count
+pub const REG_TORL: u32 = 0x40C0; // Total Octets Received (low)
+pub const REG_TORH: u32 = 0x40C4; // Total Octets Received (high)
+pub const REG_TOTL: u32 = 0x40C8; // Total Octets Transmitted (low)
+pub const REG_TOTH: u32 = 0x40CC; // Total Octets Transmitted (high)
+pub const REG_TPR: u32 = 0x40D0; // Total Packets Received
+pub const REG_TPT: u32 = 0x40D4; // Total Packets Transmitted
+pub const REG_PTC64: u32 = 0x40D8; // Packets Transmitted (64 bytes)
+pub const REG_PTC127: u32 = 0x40DC; // Packets Transmitted (65-127 bytes)
+pub const REG_PTC255: u32 = 0x40E0; // Packets Transmitted (128-255 bytes)
+pub const REG_PTC511: u32 = 0x40E4; // Packets Transmitted (256-511 bytes)
+pub const REG_PTC1023: u32 = 0x40E8; // Packets Transmitted (512-1023 bytes)
+pub const REG_PTC1522: u32 = 0x40EC; // Packets Transmitted (1024-Max bytes)
+pub const REG_MPTC: u32 = 0x40F0; // Multicast Packets Transmitted Count
+pub const REG_BPTC: u32 = 0x40F4; // Broadcast Packets Transmitted Count
+pub const REG_TSCTC: u32 = 0x40F8; // TCP Segmentation Context Transmitted Count
+pub const REG_TSCTFC: u32 = 0x40FC; // TCP Segmentation Context Tx Fail Count
@@ -2,7 +2,8 @@
 //!
 //! Uses the DMA allocator to get physical pages for descriptor rings and buffers.
 
-use super::descriptors::{RxDescriptor, TxDescriptor};
+use super::descriptors::{RxDescriptor, TxContextDescriptor, TxDescriptor};
+use super::offload::ChecksumOffsets;
 use super::regs::*;
 use super::{BUFFER_SIZE, RX_RING_SIZE, TX_RING_SIZE};
 use crate::memory::dma::{alloc_dma_page, virt_to_phys};
@@ -19,8 +20,18 @@ pub struct TxRing {
     buffers: Vec<*mut u8>,
     /// Physical addresses of packet buffers
     buffer_phys: Vec<u64>,
+    /// Oldest descriptor handed to the NIC and not yet reclaimed
+    head: usize,
+    /// Next descriptor to fill (what TDT points at)
+    tail: usize,
+    /// Descriptors from head up to tail that the NIC may still own
+    in_flight: usize,
 }
 
+/// Descriptors that can be in flight at once: one slot always stays empty,
+/// because TDH == TDT means an empty ring to the NIC
+pub const TX_RING_CAPACITY: usize = TX_RING_SIZE - 1;
+
 /// Receive ring buffer
 pub struct RxRing {
     /// Physical address of descriptor ring
@@ -40,6 +51,9 @@ impl TxRing {
             descriptors: core::ptr::null_mut(),
             buffers: Vec::new(),
             buffer_phys: Vec::new(),
+            head: 0,
+            tail: 0,
+            in_flight: 0,
         }
     }
 
@@ -96,17 +110,76 @@ impl TxRing {
         unsafe { self.descriptors.add(index) }
     }
 
-    pub fn prepare_send(&mut self, index: usize, data: &[u8]) {
+    /// Next descriptor to fill, i.e. the value for TDT
+    pub fn tail(&self) -> usize {
+        self.tail
+    }
+
+    /// Descriptors that can be claimed right now
+    pub fn free(&self) -> usize {
+        TX_RING_CAPACITY - self.in_flight
+    }
+
+    /// Give back every descriptor the NIC has finished with (DD set), oldest
+    /// first, stopping at the first one still pending; returns how many
+    ///
+    /// Context descriptors are sent with RS too, so they complete the same way.
+    pub fn reclaim(&mut self) -> usize {
+        let mut reclaimed = 0;
+        while self.in_flight > 0 {
+            let desc = unsafe { &mut *self.descriptors.add(self.head) };
+            if desc.status & TX_STATUS_DD == 0 {
+                break;
+            }
+            self.head = (self.head + 1) % TX_RING_SIZE;
+            self.in_flight -= 1;
+            reclaimed += 1;
+        }
+        reclaimed
+    }
+
+    /// Take the descriptor at the tail for the next send
+    ///
+    /// Fails rather than waits when every descriptor is in flight; call
+    /// `reclaim` first to free the ones the NIC has finished.
+    pub fn claim(&mut self) -> Result<usize, &'static str> {
+        if self.in_flight == TX_RING_CAPACITY {
+            return Err("TX ring full");
+        }
+        let index = self.tail;
+        // Cleared here, set again by the NIC when it's done with the slot
+        unsafe { (*self.descriptors.add(index)).status = 0 };
+        self.tail = (self.tail + 1) % TX_RING_SIZE;
+        self.in_flight += 1;
+        Ok(index)
+    }
+
+    /// Copy a frame into slot `index`, asking the NIC to fill in its checksums
+    /// when `offload` locates them (a matching context must already be loaded)
+    pub fn prepare_send(&mut self, index: usize, data: &[u8], offload: Option<&ChecksumOffsets>) {
         unsafe {
             // Copy data to buffer
             let buf = self.buffers[index];
             core::ptr::copy_nonoverlapping(data.as_ptr(), buf, data.len());
 
-            // Update descriptor
+            // Update descriptor (the slot may have held a context descriptor)
             let desc = &mut *self.descriptors.add(index);
+            *desc = TxDescriptor::new();
+            desc.buffer_addr = self.buffer_phys[index];
             desc.length = data.len() as u16;
             desc.cmd = TX_CMD_EOP | TX_CMD_IFCS | TX_CMD_RS;
-            desc.status = 0;
+
+            if let Some(offsets) = offload {
+                offsets.seed(core::slice::from_raw_parts_mut(buf, data.len()));
+                desc.enable_checksum_offload(offsets.ip.is_some());
+            }
+        }
+    }
+
+    /// Load checksum offsets into slot `index` as a context descriptor
+    pub fn prepare_context(&mut self, index: usize, offsets: &ChecksumOffsets) {
+        unsafe {
+            *(self.descriptors.add(index) as *mut TxContextDescriptor) = TxContextDescriptor::checksum(offsets);
         }
     }
 }
@@ -180,8 +253,154 @@ impl RxRing {
         }
         data
     }
+
+    /// Consume completed descriptors from `first`, passing each descriptor and
+    /// its frame bytes to `deliver` (see `drain_completed`)
+    pub fn drain(&mut self, first: usize, mut deliver: impl FnMut(&RxDescriptor, &[u8]) -> bool) -> usize {
+        let descriptors = unsafe { core::slice::from_raw_parts_mut(self.descriptors, RX_RING_SIZE) };
+        let buffers = &self.buffers;
+        drain_completed(descriptors, first, |index, desc| {
+            let length = (desc.length as usize).min(BUFFER_SIZE);
+            let frame = unsafe { core::slice::from_raw_parts(buffers[index], length) };
+            deliver(desc, frame)
+        })
+    }
+}
+
+/// Consume completed receive descriptors in ring order, starting at `first`
+///
+/// `deliver(index, desc)` is called for each descriptor the NIC has marked
+/// done, and returns false to leave it (and everything after it) for later,
+/// e.g. when the destination is full. Consumed descriptors get their status
+/// cleared for reuse. Returns how many were consumed; the last one consumed
+/// is the new RDT.
+pub fn drain_completed(
+    descriptors: &mut [RxDescriptor],
+    first: usize,
+    mut deliver: impl FnMut(usize, &RxDescriptor) -> bool,
+) -> usize {
+    let len = descriptors.len();
+    let mut consumed = 0;
+    while consumed < len {
+        let index = (first + consumed) % len;
+        let desc = &mut descriptors[index];
+        if desc.status & RX_STATUS_DD == 0 || !deliver(index, desc) {
+            break;
+        }
+        desc.status = 0;
+        consumed += 1;
+    }
+    consumed
 }
 
 // Safety: The rings are protected by the E1000 mutex
 unsafe impl Send for TxRing {}
 unsafe impl Send for RxRing {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// TX ring over a plain descriptor array (no buffers, so nothing is sent)
+    fn tx_ring(descriptors: &mut [TxDescriptor; TX_RING_SIZE]) -> TxRing {
+        TxRing { descriptors: descriptors.as_mut_ptr(), ..TxRing::new() }
+    }
+
+    /// What the NIC does when it has sent the frame in `index`
+    fn complete(descriptors: *mut TxDescriptor, index: usize) {
+        unsafe { (*descriptors.add(index)).status |= TX_STATUS_DD };
+    }
+
+    #[test]
+    fn test_tx_reclaim_stops_at_pending_descriptor() {
+        let mut descriptors = [TxDescriptor::new(); TX_RING_SIZE];
+        let mut ring = tx_ring(&mut descriptors);
+        for expected in 0..5 {
+            assert_eq!(ring.claim(), Ok(expected));
+        }
+        assert_eq!(ring.free(), TX_RING_CAPACITY - 5);
+
+        // Slots 0, 1 and 3 are done, 2 is still being sent
+        complete(ring.descriptors, 0);
+        complete(ring.descriptors, 1);
+        complete(ring.descriptors, 3);
+        assert_eq!(ring.reclaim(), 2);
+        assert_eq!(ring.free(), TX_RING_CAPACITY - 3);
+
+        // Once 2 finishes the rest of the completed run follows; 4 stays out
+        complete(ring.descriptors, 2);
+        assert_eq!(ring.reclaim(), 2);
+        assert_eq!(ring.reclaim(), 0);
+        assert_eq!(ring.free(), TX_RING_CAPACITY - 1);
+        assert_eq!(ring.tail(), 5);
+    }
+
+    #[test]
+    fn test_tx_full_ring_errors_until_reclaimed() {
+        let mut descriptors = [TxDescriptor::new(); TX_RING_SIZE];
+        let mut ring = tx_ring(&mut descriptors);
+        for _ in 0..TX_RING_CAPACITY {
+            ring.claim().unwrap();
+        }
+        assert_eq!(ring.free(), 0);
+        assert_eq!(ring.claim(), Err("TX ring full"));
+
+        // Nothing finished yet, so reclaiming doesn't help
+        assert_eq!(ring.reclaim(), 0);
+        assert_eq!(ring.claim(), Err("TX ring full"));
+
+        // The oldest completes and its slot is reused after the tail wraps
+        complete(ring.descriptors, 0);
+        assert_eq!(ring.reclaim(), 1);
+        assert_eq!(ring.claim(), Ok(TX_RING_SIZE - 1));
+        assert_eq!(ring.claim(), Err("TX ring full"));
+        complete(ring.descriptors, 1);
+        ring.reclaim();
+        assert_eq!(ring.claim(), Ok(0));
+    }
+
+    fn done(length: u16) -> RxDescriptor {
+        RxDescriptor { length, status: RX_STATUS_DD | RX_STATUS_EOP, ..RxDescriptor::new() }
+    }
+
+    #[test]
+    fn test_drain_completed_wraps_and_stops_at_pending() {
+        // Three finished frames wrapping past the end of the ring, then one the NIC still owns
+        let mut ring = [RxDescriptor::new(); 8];
+        ring[6] = done(60);
+        ring[7] = done(61);
+        ring[0] = done(62);
+
+        let mut seen = Vec::new();
+        let consumed = drain_completed(&mut ring, 6, |index, desc| {
+            seen.push((index, desc.length));
+            true
+        });
+        assert_eq!(consumed, 3);
+        assert_eq!(seen, [(6, 60), (7, 61), (0, 62)]);
+        assert!(ring.iter().all(|d| d.status == 0));
+
+        // Nothing left until the NIC finishes another descriptor
+        assert_eq!(drain_completed(&mut ring, 1, |_, _| true), 0);
+    }
+
+    #[test]
+    fn test_drain_completed_leaves_refused_frames() {
+        let mut ring = [RxDescriptor::new(); 4];
+        for desc in &mut ring {
+            *desc = done(64);
+        }
+
+        // The destination takes two; the rest stay done for the next drain
+        let mut room = 2;
+        let consumed = drain_completed(&mut ring, 1, |_, _| {
+            room -= 1;
+            room >= 0
+        });
+        assert_eq!(consumed, 2);
+        assert_eq!(ring[1].status, 0);
+        assert_eq!(ring[2].status, 0);
+        assert_ne!(ring[3].status & RX_STATUS_DD, 0);
+        assert_eq!(drain_completed(&mut ring, 3, |_, _| true), 2);
+    }
+}
@@ -0,0 +1,157 @@
+//! Hardware statistics counters
+//!
+//! The MAC keeps IEEE 802.3 / RMON style counters at 0x4000-0x40FC. They clear
+//! on read, so the driver folds every readout into running totals; reading
+//! them anywhere else would silently lose counts.
+
+use super::regs::*;
+
+/// Read one counter; 64-bit counters are read low half first, since reading
+/// the high half clears both
+fn read_counter(read: &mut impl FnMut(u32) -> u32, low: u32, high: Option<u32>) -> u64 {
+    let lo = read(low) as u64;
+    match high {
+        Some(high) => lo | (read(high) as u64) << 32,
+        None => lo,
+    }
+}
+
+macro_rules! hw_stats {
+    (@high) => { None };
+    (@high $high:ident) => { Some($high) };
+    ($($field:ident: $reg:ident $(+ $high:ident)?,)*) => {
+        /// E1000 MIB counters, accumulated since the driver was initialised
+        ///
+        /// Field names are the 82540EM register mnemonics.
+        #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+        pub struct E1000HwStats {
+            $(pub $field: u64,)*
+        }
+
+        impl E1000HwStats {
+            /// Number of counters
+            pub const COUNT: usize = [$(stringify!($field)),*].len();
+
+            /// Read every counter once (clearing it) and add it to the totals
+            pub(super) fn accumulate(&mut self, mut read: impl FnMut(u32) -> u32) {
+                $(
+                    let value = read_counter(&mut read, $reg, None $(.or(Some($high)))?);
+                    self.$field = self.$field.saturating_add(value);
+                )*
+            }
+
+            /// Counter registers as (low, high) offsets, in field order
+            #[cfg(test)]
+            const REGISTERS: [(u32, Option<u32>); Self::COUNT] = [$(($reg, hw_stats!(@high $($high)?))),*];
+
+            /// (mnemonic, total) for every counter, in register order
+            pub fn counters(&self) -> [(&'static str, u64); Self::COUNT] {
+                [$((stringify!($field), self.$field)),*]
+            }
+        }
+    };
+}
+
+hw_stats! {
+    crcerrs: REG_CRCERRS,
+    algnerrc: REG_ALGNERRC,
+    symerrs: REG_SYMERRS,
+    rxerrc: REG_RXERRC,
+    mpc: REG_MPC,
+    scc: REG_SCC,
+    ecol: REG_ECOL,
+    mcc: REG_MCC,
+    latecol: REG_LATECOL,
+    colc: REG_COLC,
+    dc: REG_DC,
+    tncrs: REG_TNCRS,
+    sec: REG_SEC,
+    cexterr: REG_CEXTERR,
+    rlec: REG_RLEC,
+    xonrxc: REG_XONRXC,
+    xontxc: REG_XONTXC,
+    xoffrxc: REG_XOFFRXC,
+    xofftxc: REG_XOFFTXC,
+    fcruc: REG_FCRUC,
+    prc64: REG_PRC64,
+    prc127: REG_PRC127,
+    prc255: REG_PRC255,
+    prc511: REG_PRC511,
+    prc1023: REG_PRC1023,
+    prc1522: REG_PRC1522,
+    gprc: REG_GPRC,
+    bprc: REG_BPRC,
+    mprc: REG_MPRC,
+    gptc: REG_GPTC,
+    gorc: REG_GORCL + REG_GORCH,
+    gotc: REG_GOTCL + REG_GOTCH,
+    rnbc: REG_RNBC,
+    ruc: REG_RUC,
+    rfc: REG_RFC,
+    roc: REG_ROC,
+    rjc: REG_RJC,
+    tor: REG_TORL + REG_TORH,
+    tot: REG_TOTL + REG_TOTH,
+    tpr: REG_TPR,
+    tpt: REG_TPT,
+    ptc64: REG_PTC64,
+    ptc127: REG_PTC127,
+    ptc255: REG_PTC255,
+    ptc511: REG_PTC511,
+    ptc1023: REG_PTC1023,
+    ptc1522: REG_PTC1522,
+    mptc: REG_MPTC,
+    bptc: REG_BPTC,
+    tsctc: REG_TSCTC,
+    tsctfc: REG_TSCTFC,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accumulate_folds_clear_on_read_counters() {
+        // Fake register file that clears on read, as the hardware does
+        let mut regs = [0u32; 64];
+        let index = |reg: u32| ((reg - REG_CRCERRS) / 4) as usize;
+        let mut stats = E1000HwStats::default();
+
+        regs[index(REG_GPRC)] = 10;
+        regs[index(REG_GORCL)] = 0xFFFF_FFFF;
+        regs[index(REG_GORCH)] = 1;
+        stats.accumulate(|reg| core::mem::take(&mut regs[index(reg)]));
+        assert_eq!(stats.gprc, 10);
+        assert_eq!(stats.gorc, 0x1_FFFF_FFFF);
+        assert!(regs.iter().all(|&r| r == 0));
+
+        regs[index(REG_GPRC)] = 5;
+        stats.accumulate(|reg| core::mem::take(&mut regs[index(reg)]));
+        assert_eq!(stats.gprc, 15);
+        assert_eq!(stats.gorc, 0x1_FFFF_FFFF);
+
+        let counters = stats.counters();
+        assert_eq!(counters.len(), E1000HwStats::COUNT);
+        assert_eq!(counters[0], ("crcerrs", 0));
+        assert!(counters.contains(&("gprc", 15)));
+    }
+
+    #[test]
+    fn test_register_table_covers_statistics_block() {
+        // Ascending, word aligned and inside 0x4000-0x40FC, with 64-bit
+        // counters taking two consecutive registers
+        let mut next = REG_CRCERRS;
+        for (low, high) in E1000HwStats::REGISTERS {
+            assert!(low >= next && low % 4 == 0 && low <= 0x40FC, "register {:#x}", low);
+            next = low + 4;
+            if let Some(high) = high {
+                assert_eq!(high, low + 4);
+                next = high + 4;
+            }
+        }
+
+        // One u64 total per counter, nothing else in the struct
+        assert_eq!(core::mem::size_of::<E1000HwStats>(), E1000HwStats::COUNT * 8);
+        assert_eq!(E1000HwStats::REGISTERS[0], (REG_CRCERRS, None));
+    }
+}
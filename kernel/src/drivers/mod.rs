@@ -1,6 +1,11 @@
 //! Hardware drivers
 
+pub mod apic;
+pub mod bochs;
 pub mod e1000;
 pub mod pci;
+pub mod pic;
+pub mod pit;
 pub mod serial;
+pub mod usb;
 pub mod vmsvga;
@@ -1,5 +1,11 @@
 //! PCI bus enumeration
+//!
+//! The bus is scanned once at boot into `PCI_DEVICE_TABLE`; drivers look
+//! their devices up there instead of probing configuration space again.
 
+use crate::serial_println;
+use alloc::vec::Vec;
+use spin::Mutex;
 use x86_64::instructions::port::Port;
 
 const PCI_CONFIG_ADDRESS: u16 = 0xCF8;
@@ -15,6 +21,9 @@ pub struct PciDevice {
     pub device_id: u16,
     pub class_code: u8,
     pub subclass: u8,
+    pub prog_if: u8,
+    /// Header layout (0 = device, 1 = PCI-to-PCI bridge), multifunction bit masked off
+    pub header_type: u8,
     pub bar0: u32,
     pub bar1: u32,
     pub interrupt_line: u8,
@@ -44,8 +53,147 @@ impl PciDevice {
     }
 
     /// Get BAR0 as memory address (mask off type bits)
+    /// A 64-bit BAR0 takes its upper half from BAR1
     pub fn bar0_address(&self) -> u64 {
-        (self.bar0 & 0xFFFFFFF0) as u64
+        match decode_bar(self.bar0, self.bar1) {
+            Bar::Memory(address) => address,
+            Bar::Io(port) => port as u64,
+        }
+    }
+
+    /// Size in bytes of the region BAR `index` (0-5) decodes, 0 if unimplemented
+    ///
+    /// Writes all ones to the BAR, reads back which address bits stick and
+    /// restores it, with memory and I/O decoding off meanwhile so the device
+    /// never answers at the probe address. A 64-bit BAR also probes its upper
+    /// half in BAR `index + 1`.
+    pub fn bar_size(&self, index: u8) -> u64 {
+        if index > 5 {
+            return 0;
+        }
+        let offset = 0x10 + index * 4;
+        let command = self.read_config(0x04);
+        self.write_config(0x04, command & !0x3);
+
+        let original = self.read_config(offset);
+        self.write_config(offset, 0xFFFF_FFFF);
+        let probe = self.read_config(offset);
+        self.write_config(offset, original);
+
+        let probe_high = if is_64bit_bar(original) && index < 5 {
+            let original_high = self.read_config(offset + 4);
+            self.write_config(offset + 4, 0xFFFF_FFFF);
+            let probe_high = self.read_config(offset + 4);
+            self.write_config(offset + 4, original_high);
+            probe_high
+        } else {
+            0
+        };
+
+        self.write_config(0x04, command);
+        decode_bar_size(original, probe, probe_high)
+    }
+
+    /// Human-readable device class for logs
+    pub fn class_name(&self) -> &'static str {
+        class_name(self.class_code, self.subclass)
+    }
+}
+
+/// A decoded base address register
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bar {
+    /// Memory-mapped region at this physical address
+    Memory(u64),
+    /// I/O port range starting at this port
+    Io(u16),
+}
+
+/// Decode a BAR from its config-space word and the following one
+/// (`next` is only used as the upper half of 64-bit memory BARs)
+pub fn decode_bar(bar: u32, next: u32) -> Bar {
+    if bar & 0x1 != 0 {
+        return Bar::Io((bar & 0xFFFC) as u16);
+    }
+    let low = (bar & 0xFFFFFFF0) as u64;
+    if is_64bit_bar(bar) {
+        Bar::Memory(low | ((next as u64) << 32))
+    } else {
+        Bar::Memory(low)
+    }
+}
+
+/// Whether a BAR is the low half of a 64-bit memory BAR (type bits 2:1 = 0b10)
+pub fn is_64bit_bar(bar: u32) -> bool {
+    bar & 0x1 == 0 && (bar >> 1) & 0x3 == 0x2
+}
+
+/// Region size from a BAR's original value and what it read back after all
+/// ones were written (`probe_high` is the upper half's read-back for 64-bit
+/// BARs). The size is the lowest writable address bit; 0 if none stuck.
+pub fn decode_bar_size(original: u32, probe: u32, probe_high: u32) -> u64 {
+    if original & 0x1 != 0 {
+        // I/O BARs decode at most 16 address bits
+        let mask = probe & 0xFFFC;
+        return if mask == 0 { 0 } else { (!mask & 0xFFFF) as u64 + 1 };
+    }
+    let mask = if is_64bit_bar(original) {
+        ((probe_high as u64) << 32) | (probe & 0xFFFF_FFF0) as u64
+    } else {
+        // Ones above bit 31 so the complement covers only the 32-bit range
+        0xFFFF_FFFF_0000_0000 | (probe & 0xFFFF_FFF0) as u64
+    };
+    if mask & 0xFFFF_FFFF_FFFF_FFF0 == 0xFFFF_FFFF_0000_0000 || mask == 0 {
+        return 0;
+    }
+    (!mask).wrapping_add(1)
+}
+
+/// Vendor and device ID from config word 0x00, None if no function is present
+pub fn decode_ids(word: u32) -> Option<(u16, u16)> {
+    let vendor_id = (word & 0xFFFF) as u16;
+    if vendor_id == 0xFFFF {
+        return None;
+    }
+    Some((vendor_id, (word >> 16) as u16))
+}
+
+/// Class code, subclass and programming interface from config word 0x08
+pub fn decode_class(word: u32) -> (u8, u8, u8) {
+    ((word >> 24) as u8, (word >> 16) as u8, (word >> 8) as u8)
+}
+
+/// Header type from config word 0x0C, with the multifunction bit masked off
+pub fn decode_header_type(word: u32) -> u8 {
+    ((word >> 16) & 0x7F) as u8
+}
+
+/// Whether config word 0x0C of function 0 marks a multifunction device
+pub fn is_multifunction(word: u32) -> bool {
+    (word >> 16) & 0x80 != 0
+}
+
+/// Human-readable name of a PCI class/subclass pair
+pub fn class_name(class_code: u8, subclass: u8) -> &'static str {
+    match (class_code, subclass) {
+        (0x01, 0x01) => "IDE controller",
+        (0x01, 0x06) => "SATA controller",
+        (0x01, _) => "storage controller",
+        (0x02, 0x00) => "ethernet controller",
+        (0x02, _) => "network controller",
+        (0x03, 0x00) => "VGA controller",
+        (0x03, _) => "display controller",
+        (0x04, _) => "multimedia controller",
+        (0x05, _) => "memory controller",
+        (0x06, 0x00) => "host bridge",
+        (0x06, 0x01) => "ISA bridge",
+        (0x06, 0x04) => "PCI bridge",
+        (0x06, _) => "bridge",
+        (0x08, _) => "system peripheral",
+        (0x0C, 0x03) => "USB controller",
+        (0x0C, 0x05) => "SMBus controller",
+        (0x0C, _) => "serial bus controller",
+        _ => "unknown device",
     }
 }
 
@@ -81,24 +229,51 @@ fn pci_write(bus: u8, slot: u8, function: u8, offset: u8, value: u32) {
     }
 }
 
+/// Every device found at boot, in bus/slot/function order
+pub static PCI_DEVICE_TABLE: Mutex<Vec<PciDevice>> = Mutex::new(Vec::new());
+
+/// Scan the bus into `PCI_DEVICE_TABLE` and print it
+/// Needs the heap; call before initializing any PCI driver.
+pub fn init() {
+    serial_println!("Scanning PCI bus...");
+    let devices = enumerate_all();
+    for dev in devices.iter() {
+        serial_println!(
+            "PCI {:02x}:{:02x}.{} {:04x}:{:04x} class {:02x}.{:02x}.{:02x} {}",
+            dev.bus,
+            dev.slot,
+            dev.function,
+            dev.vendor_id,
+            dev.device_id,
+            dev.class_code,
+            dev.subclass,
+            dev.prog_if,
+            dev.class_name()
+        );
+    }
+    serial_println!("PCI: {} devices", devices.len());
+    *PCI_DEVICE_TABLE.lock() = devices;
+}
+
 /// Enumerate all PCI devices
-pub fn enumerate() -> alloc::vec::Vec<PciDevice> {
-    let mut devices = alloc::vec::Vec::new();
+/// Walks every bus and slot; functions 1-7 are only probed when function 0
+/// is present and its header marks a multifunction device
+pub fn enumerate_all() -> Vec<PciDevice> {
+    let mut devices = Vec::new();
 
     for bus in 0..=255u8 {
         for slot in 0..32u8 {
             for function in 0..8u8 {
-                let vendor_device = pci_read(bus, slot, function, 0x00);
-                let vendor_id = (vendor_device & 0xFFFF) as u16;
-
-                if vendor_id == 0xFFFF {
+                let Some((vendor_id, device_id)) = decode_ids(pci_read(bus, slot, function, 0x00)) else {
+                    // No function 0 means no device in this slot
+                    if function == 0 {
+                        break;
+                    }
                     continue;
-                }
+                };
 
-                let device_id = ((vendor_device >> 16) & 0xFFFF) as u16;
-                let class_info = pci_read(bus, slot, function, 0x08);
-                let class_code = ((class_info >> 24) & 0xFF) as u8;
-                let subclass = ((class_info >> 16) & 0xFF) as u8;
+                let (class_code, subclass, prog_if) = decode_class(pci_read(bus, slot, function, 0x08));
+                let header = pci_read(bus, slot, function, 0x0C);
                 let bar0 = pci_read(bus, slot, function, 0x10);
                 let bar1 = pci_read(bus, slot, function, 0x14);
                 let interrupt_info = pci_read(bus, slot, function, 0x3C);
@@ -112,17 +287,16 @@ pub fn enumerate() -> alloc::vec::Vec<PciDevice> {
                     device_id,
                     class_code,
                     subclass,
+                    prog_if,
+                    header_type: decode_header_type(header),
                     bar0,
                     bar1,
                     interrupt_line,
                 });
 
                 // If not multi-function device, skip remaining functions
-                if function == 0 {
-                    let header_type = pci_read(bus, slot, 0, 0x0C);
-                    if (header_type >> 16) & 0x80 == 0 {
-                        break;
-                    }
+                if function == 0 && !is_multifunction(header) {
+                    break;
                 }
             }
         }
@@ -131,13 +305,119 @@ pub fn enumerate() -> alloc::vec::Vec<PciDevice> {
     devices
 }
 
-/// Find a specific device by vendor and device ID
+/// Devices in `devices` with the given vendor and device ID
+fn matching(devices: &[PciDevice], vendor_id: u16, device_id: u16) -> impl Iterator<Item = &PciDevice> {
+    devices
+        .iter()
+        .filter(move |d| d.vendor_id == vendor_id && d.device_id == device_id)
+}
+
+/// Every device in the table with the given vendor and device ID
+/// (e.g. each of several NICs); copies, since the table stays locked otherwise
+pub fn find_all_matching(vendor_id: u16, device_id: u16) -> Vec<PciDevice> {
+    matching(&PCI_DEVICE_TABLE.lock(), vendor_id, device_id).copied().collect()
+}
+
+/// Find a specific device by vendor and device ID (the first, if several)
 pub fn find_device(vendor_id: u16, device_id: u16) -> Option<PciDevice> {
-    enumerate()
-        .into_iter()
-        .find(|d| d.vendor_id == vendor_id && d.device_id == device_id)
+    matching(&PCI_DEVICE_TABLE.lock(), vendor_id, device_id).next().copied()
+}
+
+/// Devices in `devices` with the given class, subclass and programming interface
+fn of_class(devices: &[PciDevice], class_code: u8, subclass: u8, prog_if: u8) -> impl Iterator<Item = &PciDevice> {
+    devices
+        .iter()
+        .filter(move |d| d.class_code == class_code && d.subclass == subclass && d.prog_if == prog_if)
+}
+
+/// Every device in the table of a class (e.g. each xHCI controller), for
+/// drivers that bind by class rather than by vendor and device ID
+pub fn find_by_class(class_code: u8, subclass: u8, prog_if: u8) -> Vec<PciDevice> {
+    of_class(&PCI_DEVICE_TABLE.lock(), class_code, subclass, prog_if).copied().collect()
 }
 
 /// Intel E1000 vendor and device IDs
 pub const INTEL_VENDOR_ID: u16 = 0x8086;
 pub const E1000_DEVICE_ID: u16 = 0x100E;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_config_words() {
+        // Intel 82540EM: vendor 0x8086, device 0x100E
+        assert_eq!(decode_ids(0x100E_8086), Some((0x8086, 0x100E)));
+        assert_eq!(decode_ids(0xFFFF_FFFF), None);
+
+        // Ethernet controller, revision 3
+        assert_eq!(decode_class(0x0200_0003), (0x02, 0x00, 0x00));
+        // USB xHCI (prog-if 0x30)
+        assert_eq!(decode_class(0x0C03_3001), (0x0C, 0x03, 0x30));
+        assert_eq!(class_name(0x0C, 0x03), "USB controller");
+
+        // Header type 0x80: multifunction device with a type 0 header
+        assert!(is_multifunction(0x0080_0000));
+        assert_eq!(decode_header_type(0x0080_0000), 0);
+        assert!(!is_multifunction(0x0001_0000));
+        assert_eq!(decode_header_type(0x0001_0000), 1);
+    }
+
+    #[test]
+    fn test_matching_finds_every_instance() {
+        let device = |slot, vendor_id, device_id| PciDevice {
+            bus: 0,
+            slot,
+            function: 0,
+            vendor_id,
+            device_id,
+            class_code: 0x02,
+            subclass: 0x00,
+            prog_if: 0,
+            header_type: 0,
+            bar0: 0,
+            bar1: 0,
+            interrupt_line: 0,
+        };
+        let devices = [
+            device(2, 0x1234, 0x1111),
+            device(3, INTEL_VENDOR_ID, E1000_DEVICE_ID),
+            device(4, INTEL_VENDOR_ID, E1000_DEVICE_ID),
+        ];
+
+        let nics: Vec<u8> = matching(&devices, INTEL_VENDOR_ID, E1000_DEVICE_ID).map(|d| d.slot).collect();
+        assert_eq!(nics, [3, 4]);
+        assert_eq!(matching(&devices, INTEL_VENDOR_ID, 0x10D3).count(), 0);
+
+        // Serial bus controller, USB, xHCI
+        let xhci = PciDevice { class_code: 0x0C, subclass: 0x03, prog_if: 0x30, ..device(5, 0x1b36, 0x000d) };
+        let devices = [devices[0], xhci, PciDevice { prog_if: 0x20, ..xhci }];
+        let found: Vec<u8> = of_class(&devices, 0x0C, 0x03, 0x30).map(|d| d.slot).collect();
+        assert_eq!(found, [5]);
+    }
+
+    #[test]
+    fn test_decode_bar() {
+        // 32-bit memory BAR, type bits masked off
+        assert_eq!(decode_bar(0xFEBC_0008, 0x1234), Bar::Memory(0xFEBC_0000));
+        // 64-bit memory BAR takes its upper half from the next BAR
+        assert_eq!(decode_bar(0xE000_000C, 0x0000_0001), Bar::Memory(0x1_E000_0000));
+        // I/O BAR
+        assert_eq!(decode_bar(0x0000_C041, 0), Bar::Io(0xC040));
+    }
+
+    #[test]
+    fn test_decode_bar_size() {
+        // E1000 BAR0: 32-bit memory, 128 KB
+        assert_eq!(decode_bar_size(0xFEBC_0000, 0xFFFE_0000, 0), 0x20000);
+        // Prefetchable 64-bit BAR, 256 MB (upper half fully writable)
+        assert_eq!(decode_bar_size(0xE000_000C, 0xF000_000C, 0xFFFF_FFFF), 0x1000_0000);
+        // 64-bit BAR larger than 4 GB
+        assert_eq!(decode_bar_size(0x0000_000C, 0x0000_000C, 0xFFFF_FFFE), 0x2_0000_0000);
+        // I/O BAR of 16 ports (upper bits may read back as zero)
+        assert_eq!(decode_bar_size(0x0000_C041, 0x0000_FFF1, 0), 16);
+        // Unimplemented BARs read back zero
+        assert_eq!(decode_bar_size(0, 0, 0), 0);
+        assert_eq!(decode_bar_size(0x1, 0x1, 0), 0);
+    }
+}
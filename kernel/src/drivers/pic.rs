@@ -0,0 +1,79 @@
+//! Legacy 8259 PIC pair
+//!
+//! Only used to deliver the PCI interrupt lines the firmware assigned. Every
+//! line starts masked and is opened individually by `unmask`.
+
+use x86_64::instructions::port::Port;
+
+/// Vector of IRQ 0 (the power-on vectors overlap the CPU exceptions)
+pub const PIC_OFFSET: u8 = 32;
+
+/// Master line the slave PIC is chained to
+pub const CASCADE_IRQ: u8 = 2;
+
+const MASTER_COMMAND: u16 = 0x20;
+const MASTER_DATA: u16 = 0x21;
+const SLAVE_COMMAND: u16 = 0xA0;
+const SLAVE_DATA: u16 = 0xA1;
+
+/// ICW1: start initialization, ICW4 follows
+const ICW1_INIT: u8 = 0x11;
+/// ICW4: 8086 mode
+const ICW4_8086: u8 = 0x01;
+/// OCW2: non-specific end of interrupt
+const EOI: u8 = 0x20;
+
+/// Give the PIC time to settle between initialization words
+fn io_wait() {
+    unsafe { Port::<u8>::new(0x80).write(0) };
+}
+
+/// IDT vector for an IRQ line
+pub const fn vector(irq: u8) -> u8 {
+    PIC_OFFSET + irq
+}
+
+/// Remap both PICs above the exception vectors with every line masked
+pub fn init() {
+    let words = [
+        (ICW1_INIT, ICW1_INIT),
+        (PIC_OFFSET, PIC_OFFSET + 8),
+        (1 << CASCADE_IRQ, CASCADE_IRQ),
+        (ICW4_8086, ICW4_8086),
+    ];
+    unsafe {
+        for (i, (master, slave)) in words.into_iter().enumerate() {
+            // ICW1 goes to the command port, the rest to the data port
+            let (master_port, slave_port) = if i == 0 { (MASTER_COMMAND, SLAVE_COMMAND) } else { (MASTER_DATA, SLAVE_DATA) };
+            Port::<u8>::new(master_port).write(master);
+            io_wait();
+            Port::<u8>::new(slave_port).write(slave);
+            io_wait();
+        }
+        Port::<u8>::new(MASTER_DATA).write(0xFF);
+        Port::<u8>::new(SLAVE_DATA).write(0xFF);
+    }
+}
+
+/// Let an IRQ line through (slave lines also open the cascade)
+pub fn unmask(irq: u8) {
+    let (port, bit) = if irq < 8 { (MASTER_DATA, irq) } else { (SLAVE_DATA, irq - 8) };
+    unsafe {
+        let mut data = Port::<u8>::new(port);
+        let mask = data.read();
+        data.write(mask & !(1 << bit));
+    }
+    if irq >= 8 {
+        unmask(CASCADE_IRQ);
+    }
+}
+
+/// Acknowledge an IRQ so the PIC delivers the next one
+pub fn end_of_interrupt(irq: u8) {
+    unsafe {
+        if irq >= 8 {
+            Port::<u8>::new(SLAVE_COMMAND).write(EOI);
+        }
+        Port::<u8>::new(MASTER_COMMAND).write(EOI);
+    }
+}
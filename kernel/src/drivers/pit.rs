@@ -0,0 +1,157 @@
+//! 8253/8254 programmable interval timer
+//!
+//! Channel 2 runs one countdown at boot to measure the TSC frequency, which
+//! everything else times itself with. Channel 0 then provides a periodic
+//! interrupt so idle loops can HLT instead of spinning. After calibration
+//! channel 2 drives the PC speaker (see game::audio).
+
+use core::sync::atomic::{AtomicU64, Ordering};
+use x86_64::instructions::port::Port;
+
+/// Input clock of every PIT channel (Hz)
+const PIT_FREQUENCY: u64 = 1_193_182;
+
+/// PIC line channel 0 interrupts on
+pub const IRQ: u8 = 0;
+
+const CHANNEL0_DATA: u16 = 0x40;
+const CHANNEL2_DATA: u16 = 0x42;
+const COMMAND: u16 = 0x43;
+/// Keyboard controller port B: channel 2 gate (bit 0), speaker (bit 1) and
+/// channel 2 output (bit 5)
+const PORT_B: u16 = 0x61;
+
+const PORT_B_GATE2: u8 = 0x01;
+const PORT_B_SPEAKER: u8 = 0x02;
+const PORT_B_OUT2: u8 = 0x20;
+
+/// Channel 0, low then high byte, mode 3 (square wave)
+const CMD_CHANNEL0_PERIODIC: u8 = 0b0011_0110;
+/// Channel 2, low then high byte, mode 0 (interrupt on terminal count)
+const CMD_CHANNEL2_ONESHOT: u8 = 0b1011_0000;
+/// Channel 2, low then high byte, mode 3 (square wave)
+const CMD_CHANNEL2_SQUARE: u8 = 0b1011_0110;
+
+/// Length of the calibration countdown
+const CALIBRATION_MS: u64 = 10;
+
+/// Port reads before giving up on the countdown (no PIT behind the ports)
+const CALIBRATION_MAX_POLLS: u32 = 10_000_000;
+
+/// Measured frequencies outside this range are treated as a failed calibration
+const TSC_MIN_HZ: u64 = 100_000_000;
+const TSC_MAX_HZ: u64 = 10_000_000_000;
+
+/// TSC frequency used until (or if never) calibrated, ~2 GHz as on QEMU
+const DEFAULT_TSC_PER_SECOND: u64 = 2_000_000_000;
+
+/// TSC frequency (Hz) that every TSC-based timing converts with
+static TSC_HZ: AtomicU64 = AtomicU64::new(DEFAULT_TSC_PER_SECOND);
+
+/// TSC cycles per second, measured by `calibrate_tsc` at boot
+pub fn tsc_per_second() -> u64 {
+    TSC_HZ.load(Ordering::Relaxed)
+}
+
+/// TSC frequency from `tsc_delta` cycles counted over `pit_ticks` PIT input
+/// clocks. None if the result is implausible (or nothing was measured).
+fn ticks_per_second(tsc_delta: u64, pit_ticks: u64) -> Option<u64> {
+    if pit_ticks == 0 {
+        return None;
+    }
+    let hz = tsc_delta as u128 * PIT_FREQUENCY as u128 / pit_ticks as u128;
+    let hz = u64::try_from(hz).ok()?;
+    (TSC_MIN_HZ..=TSC_MAX_HZ).contains(&hz).then_some(hz)
+}
+
+/// Measure the TSC against a channel 2 countdown and store the result
+/// Returns the frequency, or None (keeping the default) if the PIT didn't
+/// count down or the result is implausible
+pub fn calibrate_tsc() -> Option<u64> {
+    let count = PIT_FREQUENCY * CALIBRATION_MS / 1000;
+    let (start, end) = unsafe {
+        let mut port_b = Port::<u8>::new(PORT_B);
+        let mut channel2 = Port::<u8>::new(CHANNEL2_DATA);
+
+        // Hold channel 2 with its gate low and keep the speaker off while loading
+        let idle = port_b.read() & !(PORT_B_GATE2 | PORT_B_SPEAKER);
+        port_b.write(idle);
+        Port::<u8>::new(COMMAND).write(CMD_CHANNEL2_ONESHOT);
+        channel2.write(count as u8);
+        channel2.write((count >> 8) as u8);
+
+        // Raising the gate starts the countdown; OUT2 goes high when it ends
+        port_b.write(idle | PORT_B_GATE2);
+        let start = crate::read_tsc();
+        let mut polls = 0;
+        while port_b.read() & PORT_B_OUT2 == 0 {
+            polls += 1;
+            if polls == CALIBRATION_MAX_POLLS {
+                port_b.write(idle);
+                return None;
+            }
+        }
+        let end = crate::read_tsc();
+        port_b.write(idle);
+        (start, end)
+    };
+
+    let hz = ticks_per_second(end.wrapping_sub(start), count)?;
+    TSC_HZ.store(hz, Ordering::Relaxed);
+    Some(hz)
+}
+
+/// Program channel 0 to interrupt `hz` times per second
+pub fn start_periodic(hz: u32) {
+    let divisor = (PIT_FREQUENCY / hz as u64).clamp(1, u16::MAX as u64);
+    unsafe {
+        let mut channel0 = Port::<u8>::new(CHANNEL0_DATA);
+        Port::<u8>::new(COMMAND).write(CMD_CHANNEL0_PERIODIC);
+        channel0.write(divisor as u8);
+        channel0.write((divisor >> 8) as u8);
+    }
+}
+
+/// Sound a `hz` square wave on the PC speaker until `stop_tone`
+pub fn start_tone(hz: u32) {
+    let divisor = (PIT_FREQUENCY / hz.max(1) as u64).clamp(1, u16::MAX as u64);
+    unsafe {
+        let mut channel2 = Port::<u8>::new(CHANNEL2_DATA);
+        Port::<u8>::new(COMMAND).write(CMD_CHANNEL2_SQUARE);
+        channel2.write(divisor as u8);
+        channel2.write((divisor >> 8) as u8);
+
+        // Gate the channel on and connect its output to the speaker
+        let mut port_b = Port::<u8>::new(PORT_B);
+        let value = port_b.read();
+        port_b.write(value | PORT_B_GATE2 | PORT_B_SPEAKER);
+    }
+}
+
+/// Silence the PC speaker
+pub fn stop_tone() {
+    unsafe {
+        let mut port_b = Port::<u8>::new(PORT_B);
+        let value = port_b.read();
+        port_b.write(value & !(PORT_B_GATE2 | PORT_B_SPEAKER));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ticks_per_second() {
+        let count = PIT_FREQUENCY * CALIBRATION_MS / 1000;
+        // 10 ms of a 3 GHz TSC
+        let hz = ticks_per_second(30_000_000, count).unwrap();
+        assert!(hz.abs_diff(3_000_000_000) < 3_000_000);
+        // One full second of PIT input clocks is exact
+        assert_eq!(ticks_per_second(2_400_000_000, PIT_FREQUENCY), Some(2_400_000_000));
+        // Implausible measurements keep the default
+        assert_eq!(ticks_per_second(100, count), None);
+        assert_eq!(ticks_per_second(u64::MAX, count), None);
+        assert_eq!(ticks_per_second(30_000_000, 0), None);
+    }
+}
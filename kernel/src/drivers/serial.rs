@@ -6,6 +6,9 @@ use x86_64::instructions::port::Port;
 
 const COM1_PORT: u16 = 0x3F8;
 
+/// Longest line try_read_line assembles (further characters are dropped)
+pub const MAX_LINE: usize = 80;
+
 /// Global serial port instance
 pub static SERIAL1: Mutex<SerialPort> = Mutex::new(SerialPort::new(COM1_PORT));
 
@@ -47,6 +50,28 @@ impl SerialPort {
         unsafe { Port::<u8>::new(COM1_PORT + 5).read() & 0x20 != 0 }
     }
 
+    /// Read a byte if one is waiting (non-blocking)
+    pub fn try_read_byte(&mut self) -> Option<u8> {
+        unsafe {
+            if self.line_status.read() & 0x01 != 0 {
+                Some(self.data.read())
+            } else {
+                None
+            }
+        }
+    }
+
+    /// Feed waiting input into `line` until it completes a line (non-blocking)
+    /// Returns the finished line; a partial one stays buffered for the next call.
+    pub fn try_read_line<'a>(&mut self, line: &'a mut LineBuffer) -> Option<&'a str> {
+        while let Some(byte) = self.try_read_byte() {
+            if line.push(byte) {
+                return Some(line.take());
+            }
+        }
+        None
+    }
+
     /// Write a single byte to the serial port
     pub fn write_byte(&mut self, byte: u8) {
         while !self.is_transmit_empty() {
@@ -58,6 +83,46 @@ impl SerialPort {
     }
 }
 
+/// A line being typed on the serial port, with backspace editing
+pub struct LineBuffer {
+    buf: [u8; MAX_LINE],
+    len: usize,
+}
+
+impl LineBuffer {
+    pub const fn new() -> Self {
+        Self { buf: [0; MAX_LINE], len: 0 }
+    }
+
+    /// Add a received byte; returns true when it ends a non-empty line
+    /// Printable ASCII is kept, backspace/DEL erase, everything else is ignored.
+    pub fn push(&mut self, byte: u8) -> bool {
+        match byte {
+            b'\r' | b'\n' => return self.len > 0,
+            0x08 | 0x7F => self.len = self.len.saturating_sub(1),
+            b if (b.is_ascii_graphic() || b == b' ') && self.len < MAX_LINE => {
+                self.buf[self.len] = b;
+                self.len += 1;
+            }
+            _ => {}
+        }
+        false
+    }
+
+    /// The line typed so far, emptying the buffer for the next one
+    pub fn take(&mut self) -> &str {
+        let len = core::mem::take(&mut self.len);
+        // Only ASCII is ever stored
+        core::str::from_utf8(&self.buf[..len]).unwrap_or_default()
+    }
+}
+
+impl Default for LineBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Write for SerialPort {
     fn write_str(&mut self, s: &str) -> fmt::Result {
         for byte in s.bytes() {
@@ -0,0 +1,111 @@
+//! USB descriptor parsing
+
+/// Descriptor types (high byte of GET_DESCRIPTOR's wValue)
+pub const DESC_DEVICE: u8 = 1;
+pub const DESC_CONFIGURATION: u8 = 2;
+const DESC_INTERFACE: u8 = 4;
+const DESC_ENDPOINT: u8 = 5;
+
+/// Length of the configuration descriptor header (before its interfaces)
+pub const CONFIGURATION_HEADER_LEN: u16 = 9;
+
+/// Offset of bMaxPacketSize0 in the device descriptor
+pub const DEVICE_MAX_PACKET_OFFSET: usize = 7;
+
+/// HID interface class, boot subclass and keyboard protocol
+const CLASS_HID: u8 = 3;
+const SUBCLASS_BOOT: u8 = 1;
+const PROTOCOL_KEYBOARD: u8 = 1;
+
+/// wTotalLength of a configuration descriptor header
+pub fn total_length(header: &[u8]) -> Option<u16> {
+    (header.len() >= 4 && header[1] == DESC_CONFIGURATION).then(|| u16::from_le_bytes([header[2], header[3]]))
+}
+
+/// A boot keyboard interface and its interrupt-IN endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyboardInterface {
+    /// bConfigurationValue to select
+    pub configuration: u8,
+    pub interface: u8,
+    /// Endpoint address (direction bit set)
+    pub endpoint: u8,
+    pub max_packet: u16,
+    /// bInterval (frames for low/full speed, 2^(n-1) microframes above)
+    pub interval: u8,
+}
+
+/// Find the first boot keyboard in a full configuration descriptor
+pub fn find_boot_keyboard(config: &[u8]) -> Option<KeyboardInterface> {
+    if config.len() < CONFIGURATION_HEADER_LEN as usize || config[1] != DESC_CONFIGURATION {
+        return None;
+    }
+    let configuration = config[5];
+    let mut keyboard_interface = None;
+
+    let mut offset = 0;
+    while offset + 2 <= config.len() {
+        let len = config[offset] as usize;
+        if len < 2 || offset + len > config.len() {
+            break;
+        }
+        let desc = &config[offset..offset + len];
+        match desc[1] {
+            DESC_INTERFACE if len >= 9 => {
+                let boot_keyboard = desc[5] == CLASS_HID && desc[6] == SUBCLASS_BOOT && desc[7] == PROTOCOL_KEYBOARD;
+                keyboard_interface = boot_keyboard.then_some(desc[2]);
+            }
+            DESC_ENDPOINT if len >= 7 => {
+                let (address, attributes) = (desc[2], desc[3]);
+                let interrupt_in = address & 0x80 != 0 && attributes & 0x3 == 0x3;
+                if let (Some(interface), true) = (keyboard_interface, interrupt_in) {
+                    return Some(KeyboardInterface {
+                        configuration,
+                        interface,
+                        endpoint: address,
+                        max_packet: u16::from_le_bytes([desc[4], desc[5]]) & 0x7FF,
+                        interval: desc[6],
+                    });
+                }
+            }
+            _ => {}
+        }
+        offset += len;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Configuration descriptor of QEMU's usb-kbd: configuration, interface,
+    /// HID class descriptor and interrupt-IN endpoint 1
+    const USB_KBD_CONFIG: [u8; 34] = [
+        0x09, 0x02, 0x22, 0x00, 0x01, 0x01, 0x06, 0xA0, 0x32,
+        0x09, 0x04, 0x00, 0x00, 0x01, 0x03, 0x01, 0x01, 0x00,
+        0x09, 0x21, 0x11, 0x01, 0x00, 0x01, 0x22, 0x3F, 0x00,
+        0x07, 0x05, 0x81, 0x03, 0x08, 0x00, 0x0A,
+    ];
+
+    #[test]
+    fn test_find_boot_keyboard() {
+        assert_eq!(total_length(&USB_KBD_CONFIG[..9]), Some(34));
+        assert_eq!(
+            find_boot_keyboard(&USB_KBD_CONFIG),
+            Some(KeyboardInterface { configuration: 1, interface: 0, endpoint: 0x81, max_packet: 8, interval: 10 })
+        );
+    }
+
+    #[test]
+    fn test_non_keyboard_interfaces_skipped() {
+        // Same layout as a boot mouse (protocol 2)
+        let mut mouse = USB_KBD_CONFIG;
+        mouse[16] = 0x02;
+        assert_eq!(find_boot_keyboard(&mouse), None);
+
+        // Truncated descriptors end the walk instead of reading past the end
+        assert_eq!(find_boot_keyboard(&USB_KBD_CONFIG[..30]), None);
+        assert_eq!(find_boot_keyboard(&[0x09, 0x02]), None);
+    }
+}
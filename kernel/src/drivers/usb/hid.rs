@@ -0,0 +1,307 @@
+//! USB HID boot keyboard
+//!
+//! The keyboard is switched to the boot protocol, whose 8-byte reports are
+//! the same on every keyboard: a modifier bitmap, a reserved byte and up to
+//! six pressed key usages. An interrupt-IN transfer is kept queued on its
+//! endpoint; `poll` decodes the reports that have come in and applies the
+//! keys that changed to `KEY_STATE`, next to the PS/2 keyboard.
+
+use super::descriptor::{self, DESC_CONFIGURATION, CONFIGURATION_HEADER_LEN};
+use super::xhci::{Device, Ring, Xhci, TRB_TRANSFER_EVENT};
+use super::SetupPacket;
+use crate::game::input::{KeyState, KEY_STATE};
+use crate::memory::dma::{alloc_dma_page, PAGE_SIZE};
+use spin::Mutex;
+
+/// Boot protocol report length
+pub const BOOT_REPORT_LEN: usize = 8;
+
+/// HID SET_PROTOCOL value for the boot protocol
+const PROTOCOL_BOOT: u8 = 0;
+
+/// Keyboard usage IDs (HID usage page 0x07)
+pub mod usage {
+    /// Every key slot reads this when too many keys are held
+    pub const ERROR_ROLLOVER: u8 = 0x01;
+    pub const A: u8 = 0x04;
+    pub const B: u8 = 0x05;
+    pub const D: u8 = 0x07;
+    pub const E: u8 = 0x08;
+    pub const F: u8 = 0x09;
+    pub const M: u8 = 0x10;
+    pub const Q: u8 = 0x14;
+    pub const R: u8 = 0x15;
+    pub const S: u8 = 0x16;
+    pub const T: u8 = 0x17;
+    pub const W: u8 = 0x1A;
+    pub const ONE: u8 = 0x1E;
+    pub const TWO: u8 = 0x1F;
+    pub const THREE: u8 = 0x20;
+    pub const FOUR: u8 = 0x21;
+    pub const FIVE: u8 = 0x22;
+    pub const ENTER: u8 = 0x28;
+    pub const ESC: u8 = 0x29;
+    pub const TAB: u8 = 0x2B;
+    pub const SPACE: u8 = 0x2C;
+    pub const F3: u8 = 0x3C;
+    pub const F4: u8 = 0x3D;
+    pub const F5: u8 = 0x3E;
+    pub const F6: u8 = 0x3F;
+    pub const F12: u8 = 0x45;
+    pub const RIGHT: u8 = 0x4F;
+    pub const LEFT: u8 = 0x50;
+    pub const DOWN: u8 = 0x51;
+    pub const UP: u8 = 0x52;
+}
+
+/// Modifier bits of a boot report
+pub mod modifier {
+    pub const LEFT_CTRL: u8 = 1 << 0;
+    pub const LEFT_SHIFT: u8 = 1 << 1;
+    pub const RIGHT_CTRL: u8 = 1 << 4;
+    pub const RIGHT_SHIFT: u8 = 1 << 5;
+}
+
+/// The KeyState flag a key drives
+type KeyField = fn(&mut KeyState) -> &mut bool;
+
+/// Key usages and the KeyState flags they drive
+const KEY_TABLE: [(u8, KeyField); 29] = [
+    (usage::W, |k| &mut k.w),
+    (usage::A, |k| &mut k.a),
+    (usage::S, |k| &mut k.s),
+    (usage::D, |k| &mut k.d),
+    (usage::SPACE, |k| &mut k.space),
+    (usage::B, |k| &mut k.b),
+    (usage::ESC, |k| &mut k.escape),
+    (usage::ENTER, |k| &mut k.enter),
+    (usage::TAB, |k| &mut k.tab),
+    (usage::UP, |k| &mut k.up),
+    (usage::DOWN, |k| &mut k.down),
+    (usage::LEFT, |k| &mut k.left),
+    (usage::RIGHT, |k| &mut k.right),
+    (usage::ONE, |k| &mut k.one),
+    (usage::TWO, |k| &mut k.two),
+    (usage::THREE, |k| &mut k.three),
+    (usage::FOUR, |k| &mut k.four),
+    (usage::FIVE, |k| &mut k.five),
+    (usage::Q, |k| &mut k.q),
+    (usage::E, |k| &mut k.e),
+    (usage::R, |k| &mut k.r),
+    (usage::F, |k| &mut k.f),
+    (usage::T, |k| &mut k.t),
+    (usage::M, |k| &mut k.m),
+    (usage::F3, |k| &mut k.f3),
+    (usage::F4, |k| &mut k.f4),
+    (usage::F5, |k| &mut k.f5),
+    (usage::F6, |k| &mut k.f6),
+    (usage::F12, |k| &mut k.f12),
+];
+
+/// Modifier bits (either side) and the KeyState flags they drive
+const MODIFIER_TABLE: [(u8, KeyField); 2] = [
+    (modifier::LEFT_CTRL | modifier::RIGHT_CTRL, |k| &mut k.ctrl),
+    (modifier::LEFT_SHIFT | modifier::RIGHT_SHIFT, |k| &mut k.shift),
+];
+
+/// A decoded boot protocol report
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BootReport {
+    pub modifiers: u8,
+    /// Pressed key usages, 0 in unused slots
+    pub keys: [u8; 6],
+}
+
+impl BootReport {
+    /// Decode a report; None if it is too short or a rollover error (too
+    /// many keys held, so which ones is unknown)
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        if data.len() < BOOT_REPORT_LEN {
+            return None;
+        }
+        let mut keys = [0; 6];
+        keys.copy_from_slice(&data[2..8]);
+        if keys.iter().all(|&k| k == usage::ERROR_ROLLOVER) {
+            return None;
+        }
+        Some(Self { modifiers: data[0], keys })
+    }
+
+    fn is_pressed(&self, usage: u8) -> bool {
+        self.keys.contains(&usage)
+    }
+
+    /// Set the KeyState flags of every key that changed since `previous`
+    /// Keys that didn't change are left alone, so a PS/2 keyboard can drive
+    /// them meanwhile.
+    pub fn apply(&self, previous: &BootReport, state: &mut KeyState) {
+        for (usage, field) in KEY_TABLE {
+            let pressed = self.is_pressed(usage);
+            if pressed != previous.is_pressed(usage) {
+                *field(state) = pressed;
+            }
+        }
+        for (mask, field) in MODIFIER_TABLE {
+            let pressed = self.modifiers & mask != 0;
+            if pressed != (previous.modifiers & mask != 0) {
+                *field(state) = pressed;
+            }
+        }
+    }
+}
+
+/// A boot keyboard with its report transfer running
+pub struct UsbKeyboard {
+    controller: Xhci,
+    device: Device,
+    ring: Ring,
+    /// Device context index of the interrupt-IN endpoint
+    dci: u8,
+    report: *const u8,
+    report_phys: u64,
+    previous: BootReport,
+}
+
+// Safety: the report buffer is only read under the USB_KEYBOARD mutex
+unsafe impl Send for UsbKeyboard {}
+
+/// The USB keyboard, once `usb::init` found one
+pub static USB_KEYBOARD: Mutex<Option<UsbKeyboard>> = Mutex::new(None);
+
+impl UsbKeyboard {
+    /// Find a boot keyboard on a running controller's root hub ports
+    /// Sets it up for boot protocol reports and queues the first transfer.
+    pub fn probe(mut controller: Xhci) -> Result<Self, &'static str> {
+        for port in 1..=controller.port_count() {
+            let Some(speed) = controller.reset_port(port) else {
+                continue;
+            };
+            match Self::start(&mut controller, port, speed) {
+                Ok(Some((device, ring, dci))) => {
+                    let (report_phys, report) = alloc_dma_page().ok_or("out of DMA pages for HID reports")?;
+                    let mut keyboard = Self {
+                        controller,
+                        device,
+                        ring,
+                        dci,
+                        report,
+                        report_phys,
+                        previous: BootReport::default(),
+                    };
+                    keyboard.queue_report();
+                    return Ok(keyboard);
+                }
+                Ok(None) => crate::serial_println!("USB: port {}: not a boot keyboard", port),
+                Err(e) => crate::serial_println!("USB: port {}: {}", port, e),
+            }
+        }
+        Err("no USB keyboard found")
+    }
+
+    /// Address the device on a port and, if it is a boot keyboard, configure
+    /// it and its report endpoint
+    fn start(controller: &mut Xhci, port: u8, speed: u8) -> Result<Option<(Device, Ring, u8)>, &'static str> {
+        let mut device = controller.address_device(port, speed)?;
+
+        let header = controller.control_transfer(&mut device, SetupPacket::get_descriptor(DESC_CONFIGURATION, CONFIGURATION_HEADER_LEN))?;
+        let total = descriptor::total_length(header).ok_or("bad configuration descriptor")?;
+        let length = total.min(PAGE_SIZE as u16);
+        let config = controller.control_transfer(&mut device, SetupPacket::get_descriptor(DESC_CONFIGURATION, length))?;
+        let Some(keyboard) = descriptor::find_boot_keyboard(config) else {
+            return Ok(None);
+        };
+
+        controller.control_transfer(&mut device, SetupPacket::set_configuration(keyboard.configuration))?;
+        controller.control_transfer(&mut device, SetupPacket::set_protocol(keyboard.interface, PROTOCOL_BOOT))?;
+        // Some keyboards stall SET_IDLE; they just repeat reports
+        let _ = controller.control_transfer(&mut device, SetupPacket::set_idle(keyboard.interface));
+
+        let (ring, dci) =
+            controller.configure_interrupt_in(&device, keyboard.endpoint, keyboard.max_packet, keyboard.interval)?;
+        Ok(Some((device, ring, dci)))
+    }
+
+    /// Root hub port the keyboard is on
+    pub fn port(&self) -> u8 {
+        self.device.port
+    }
+
+    fn queue_report(&mut self) {
+        let (slot, dci) = (self.device.slot, self.dci);
+        self.controller.queue_in(&mut self.ring, slot, dci, self.report_phys, BOOT_REPORT_LEN as u32);
+    }
+
+    /// Apply the reports that arrived since the last poll, requeueing the transfer
+    fn poll(&mut self) {
+        while let Some(event) = self.controller.next_event() {
+            let ours = event.trb_type() == TRB_TRANSFER_EVENT
+                && event.slot_id() == self.device.slot
+                && event.endpoint_id() == self.dci;
+            if !ours {
+                continue;
+            }
+            if event.succeeded() {
+                let data = unsafe { core::slice::from_raw_parts(self.report, BOOT_REPORT_LEN) };
+                if let Some(report) = BootReport::parse(data) {
+                    report.apply(&self.previous, &mut KEY_STATE.lock());
+                    self.previous = report;
+                }
+            }
+            self.queue_report();
+        }
+    }
+}
+
+/// Process keyboard reports from the USB keyboard, if there is one
+pub fn poll() {
+    if let Some(keyboard) = USB_KEYBOARD.lock().as_mut() {
+        keyboard.poll();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(modifiers: u8, keys: &[u8]) -> [u8; 8] {
+        let mut data = [0; 8];
+        data[0] = modifiers;
+        data[2..2 + keys.len()].copy_from_slice(keys);
+        data
+    }
+
+    #[test]
+    fn test_report_press_and_release() {
+        let mut state = KeyState::default();
+        let idle = BootReport::default();
+
+        let held = BootReport::parse(&report(modifier::RIGHT_SHIFT, &[usage::W, usage::SPACE])).unwrap();
+        held.apply(&idle, &mut state);
+        assert!(state.w && state.space && state.shift);
+        assert!(!state.a && !state.ctrl);
+
+        // W released, D pressed; the shift key stays down
+        let next = BootReport::parse(&report(modifier::RIGHT_SHIFT, &[usage::SPACE, usage::D])).unwrap();
+        next.apply(&held, &mut state);
+        assert!(!state.w && state.d && state.space && state.shift);
+
+        idle.apply(&next, &mut state);
+        assert!(!state.d && !state.space && !state.shift);
+    }
+
+    #[test]
+    fn test_unchanged_keys_left_to_ps2() {
+        // A key held on the PS/2 keyboard survives USB reports that don't mention it
+        let mut state = KeyState { a: true, ..KeyState::default() };
+        let w = BootReport::parse(&report(0, &[usage::W])).unwrap();
+        w.apply(&BootReport::default(), &mut state);
+        BootReport::default().apply(&w, &mut state);
+        assert!(state.a && !state.w);
+    }
+
+    #[test]
+    fn test_rollover_and_short_reports_ignored() {
+        assert_eq!(BootReport::parse(&report(0, &[usage::ERROR_ROLLOVER; 6])), None);
+        assert_eq!(BootReport::parse(&[0, 0, usage::W]), None);
+    }
+}
@@ -0,0 +1,185 @@
+//! USB host support
+//!
+//! Enough USB to drive a boot-protocol keyboard on real hardware, where
+//! keyboards sit behind a USB host controller instead of the PS/2 port. Only
+//! xHCI controllers are driven: they take devices of every speed on their
+//! root hub ports, while an EHCI controller hands low- and full-speed
+//! devices (keyboards) to companion UHCI/OHCI controllers. No hubs; the
+//! keyboard has to be plugged into a root port.
+
+mod descriptor;
+pub mod hid;
+mod xhci;
+
+use crate::drivers::pci::{self, PciDevice};
+use crate::memory;
+use crate::serial_println;
+use hid::{UsbKeyboard, USB_KEYBOARD};
+use xhci::Xhci;
+
+/// PCI class, subclass and programming interfaces of USB host controllers
+const PCI_CLASS_SERIAL_BUS: u8 = 0x0C;
+const PCI_SUBCLASS_USB: u8 = 0x03;
+const PCI_PROG_IF_EHCI: u8 = 0x20;
+const PCI_PROG_IF_XHCI: u8 = 0x30;
+
+// Standard requests
+const REQ_GET_DESCRIPTOR: u8 = 0x06;
+const REQ_SET_CONFIGURATION: u8 = 0x09;
+// HID class requests
+const REQ_HID_SET_IDLE: u8 = 0x0A;
+const REQ_HID_SET_PROTOCOL: u8 = 0x0B;
+
+/// bmRequestType: device-to-host, standard, device
+const REQUEST_TYPE_IN: u8 = 0x80;
+/// bmRequestType: host-to-device, standard, device
+const REQUEST_TYPE_OUT: u8 = 0x00;
+/// bmRequestType: host-to-device, class, interface
+const REQUEST_TYPE_CLASS_INTERFACE: u8 = 0x21;
+
+/// A control transfer's setup stage
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SetupPacket {
+    pub request_type: u8,
+    pub request: u8,
+    pub value: u16,
+    pub index: u16,
+    pub length: u16,
+}
+
+impl SetupPacket {
+    /// GET_DESCRIPTOR for the first `length` bytes of descriptor `kind` (index 0)
+    pub fn get_descriptor(kind: u8, length: u16) -> Self {
+        Self { request_type: REQUEST_TYPE_IN, request: REQ_GET_DESCRIPTOR, value: (kind as u16) << 8, index: 0, length }
+    }
+
+    /// SET_CONFIGURATION with a bConfigurationValue
+    pub fn set_configuration(configuration: u8) -> Self {
+        Self { request_type: REQUEST_TYPE_OUT, request: REQ_SET_CONFIGURATION, value: configuration as u16, index: 0, length: 0 }
+    }
+
+    /// HID SET_PROTOCOL (0 = boot protocol) for an interface
+    pub fn set_protocol(interface: u8, protocol: u8) -> Self {
+        Self {
+            request_type: REQUEST_TYPE_CLASS_INTERFACE,
+            request: REQ_HID_SET_PROTOCOL,
+            value: protocol as u16,
+            index: interface as u16,
+            length: 0,
+        }
+    }
+
+    /// HID SET_IDLE with an infinite idle rate: reports only when keys change
+    pub fn set_idle(interface: u8) -> Self {
+        Self { request_type: REQUEST_TYPE_CLASS_INTERFACE, request: REQ_HID_SET_IDLE, value: 0, index: interface as u16, length: 0 }
+    }
+
+    /// Data stage runs device-to-host
+    pub fn is_in(&self) -> bool {
+        self.request_type & 0x80 != 0
+    }
+
+    /// The 8 bytes on the wire, little-endian, as one value
+    pub fn to_u64(&self) -> u64 {
+        self.request_type as u64
+            | (self.request as u64) << 8
+            | (self.value as u64) << 16
+            | (self.index as u64) << 32
+            | (self.length as u64) << 48
+    }
+}
+
+/// Busy-wait for about `us` microseconds
+fn delay_us(us: u64) {
+    let cycles = us * (crate::drivers::pit::tsc_per_second() / 1_000_000);
+    let start = crate::read_tsc();
+    while crate::read_tsc().wrapping_sub(start) < cycles {
+        core::hint::spin_loop();
+    }
+}
+
+/// Poll `done` until it returns true or `timeout_us` microseconds pass
+/// Returns whether it finished in time.
+fn wait_until(timeout_us: u64, mut done: impl FnMut() -> bool) -> bool {
+    let cycles = timeout_us * (crate::drivers::pit::tsc_per_second() / 1_000_000);
+    let start = crate::read_tsc();
+    loop {
+        if done() {
+            return true;
+        }
+        if crate::read_tsc().wrapping_sub(start) >= cycles {
+            return false;
+        }
+        core::hint::spin_loop();
+    }
+}
+
+/// Bring up the USB keyboard, if there is one (after PCI enumeration)
+///
+/// Tries every xHCI controller in the PCI device table until one has a boot
+/// keyboard on a root hub port. Returns whether a keyboard was found; without
+/// one, the PS/2 keyboard is the only input.
+pub fn init() -> bool {
+    let controllers = pci::find_by_class(PCI_CLASS_SERIAL_BUS, PCI_SUBCLASS_USB, PCI_PROG_IF_XHCI);
+    if controllers.is_empty() {
+        let ehci = pci::find_by_class(PCI_CLASS_SERIAL_BUS, PCI_SUBCLASS_USB, PCI_PROG_IF_EHCI);
+        if !ehci.is_empty() {
+            serial_println!("USB: {} EHCI controller(s), not supported (xHCI only)", ehci.len());
+        }
+        return false;
+    }
+
+    for dev in controllers {
+        serial_println!(
+            "USB: xHCI at {:02x}:{:02x}.{} BAR0={:#x}",
+            dev.bus,
+            dev.slot,
+            dev.function,
+            dev.bar0_address()
+        );
+        let keyboard = start_controller(&dev).and_then(UsbKeyboard::probe);
+        match keyboard {
+            Ok(keyboard) => {
+                serial_println!("USB: keyboard on port {}", keyboard.port());
+                *USB_KEYBOARD.lock() = Some(keyboard);
+                return true;
+            }
+            Err(e) => serial_println!("USB: {}", e),
+        }
+    }
+    false
+}
+
+/// Map and reset an xHCI controller, leaving it running
+fn start_controller(dev: &PciDevice) -> Result<Xhci, &'static str> {
+    dev.enable_bus_master();
+    dev.enable_memory_space();
+
+    let size = dev.bar_size(0) as usize;
+    if size == 0 {
+        return Err("xHCI BAR0 not implemented");
+    }
+    let base = memory::paging::map_mmio(dev.bar0_address(), size).ok_or("failed to map xHCI registers")?;
+
+    let mut controller = Xhci::new(base);
+    controller.init()?;
+    Ok(controller)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_setup_packet_wire_layout() {
+        // GET_DESCRIPTOR(configuration, 9 bytes): 80 06 00 02 00 00 09 00
+        let setup = SetupPacket::get_descriptor(descriptor::DESC_CONFIGURATION, 9);
+        assert!(setup.is_in());
+        assert_eq!(setup.to_u64().to_le_bytes(), [0x80, 0x06, 0x00, 0x02, 0x00, 0x00, 0x09, 0x00]);
+
+        // SET_PROTOCOL(boot) on interface 1: 21 0B 00 00 01 00 00 00
+        let setup = SetupPacket::set_protocol(1, 0);
+        assert!(!setup.is_in());
+        assert_eq!(setup.to_u64().to_le_bytes(), [0x21, 0x0B, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00]);
+    }
+}
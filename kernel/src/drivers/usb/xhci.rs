@@ -0,0 +1,650 @@
+//! xHCI host controller
+//!
+//! Just enough of the xHCI spec to run a keyboard: one command ring and one
+//! event ring, polled rather than interrupt-driven, control transfers on
+//! endpoint 0 and interrupt-IN endpoints. Every ring is a single DMA page
+//! closed by a link TRB back to its start.
+
+use super::descriptor::{DESC_DEVICE, DEVICE_MAX_PACKET_OFFSET};
+use super::{delay_us, wait_until, SetupPacket};
+use crate::memory::dma::{alloc_dma_page, PAGE_SIZE};
+use core::ptr::{read_volatile, write_volatile};
+use core::sync::atomic::{fence, Ordering};
+
+// Capability registers
+const CAP_CAPLENGTH: u64 = 0x00;
+const CAP_HCSPARAMS1: u64 = 0x04;
+const CAP_HCSPARAMS2: u64 = 0x08;
+const CAP_HCCPARAMS1: u64 = 0x10;
+const CAP_DBOFF: u64 = 0x14;
+const CAP_RTSOFF: u64 = 0x18;
+
+// Operational registers (CAPLENGTH bytes in)
+const OP_USBCMD: u64 = 0x00;
+const OP_USBSTS: u64 = 0x04;
+const OP_CRCR: u64 = 0x18;
+const OP_DCBAAP: u64 = 0x30;
+const OP_CONFIG: u64 = 0x38;
+/// First port's PORTSC; ports are 0x10 apart
+const OP_PORTSC: u64 = 0x400;
+
+// Interrupter 0 registers (RTSOFF bytes in)
+const IR0_ERSTSZ: u64 = 0x28;
+const IR0_ERSTBA: u64 = 0x30;
+const IR0_ERDP: u64 = 0x38;
+
+const USBCMD_RUN: u32 = 1 << 0;
+const USBCMD_HCRST: u32 = 1 << 1;
+const USBSTS_HCH: u32 = 1 << 0;
+const USBSTS_CNR: u32 = 1 << 11;
+
+const PORTSC_CCS: u32 = 1 << 0;
+const PORTSC_PED: u32 = 1 << 1;
+const PORTSC_PR: u32 = 1 << 4;
+const PORTSC_PRC: u32 = 1 << 21;
+/// Bits written back as read: port power, indicator and wake enables
+/// (everything else is read-only, or set/cleared by writing 1)
+const PORTSC_PRESERVE: u32 = 0x0E00_C200;
+/// Status change bits, cleared by writing 1
+const PORTSC_CHANGES: u32 = 0x00FE_0000;
+
+/// Event handler busy: written to ERDP to acknowledge events
+const ERDP_EHB: u64 = 1 << 3;
+
+/// USB legacy support extended capability (BIOS/OS ownership handoff)
+const XECP_LEGACY_SUPPORT: u32 = 1;
+const LEGACY_BIOS_OWNED: u32 = 1 << 16;
+const LEGACY_OS_OWNED: u32 = 1 << 24;
+
+// TRB types
+const TRB_NORMAL: u32 = 1;
+const TRB_SETUP: u32 = 2;
+const TRB_DATA: u32 = 3;
+const TRB_STATUS: u32 = 4;
+const TRB_LINK: u32 = 6;
+const TRB_ENABLE_SLOT: u32 = 9;
+const TRB_ADDRESS_DEVICE: u32 = 11;
+const TRB_CONFIGURE_ENDPOINT: u32 = 12;
+const TRB_EVALUATE_CONTEXT: u32 = 13;
+pub const TRB_TRANSFER_EVENT: u32 = 32;
+const TRB_COMMAND_COMPLETION: u32 = 33;
+
+// TRB control bits
+const TRB_CYCLE: u32 = 1 << 0;
+const TRB_TOGGLE_CYCLE: u32 = 1 << 1;
+const TRB_ISP: u32 = 1 << 2;
+const TRB_IOC: u32 = 1 << 5;
+const TRB_IDT: u32 = 1 << 6;
+const TRB_DIR_IN: u32 = 1 << 16;
+const TRB_TYPE_SHIFT: u32 = 10;
+
+// Setup stage transfer types
+const TRT_NO_DATA: u32 = 0;
+const TRT_OUT: u32 = 2;
+const TRT_IN: u32 = 3;
+
+// Completion codes
+const CC_SUCCESS: u8 = 1;
+const CC_SHORT_PACKET: u8 = 13;
+
+// Endpoint context types
+const EP_TYPE_CONTROL: u32 = 4;
+const EP_TYPE_INTERRUPT_IN: u32 = 7;
+/// Retries before the controller reports a transaction error
+const EP_ERROR_COUNT: u32 = 3;
+
+// PORTSC / slot context speeds
+const SPEED_FULL: u8 = 1;
+const SPEED_LOW: u8 = 2;
+const SPEED_HIGH: u8 = 3;
+
+/// TRBs per ring: one page of 16-byte TRBs
+const RING_TRBS: usize = PAGE_SIZE / 16;
+/// Device slots enabled
+const MAX_SLOTS: u8 = 16;
+/// Scratchpad pages we're willing to give the controller from the DMA pool
+const MAX_SCRATCHPADS: usize = 32;
+/// Time allowed for a command, transfer or controller state change
+const TIMEOUT_US: u64 = 500_000;
+/// Time allowed for a port reset
+const PORT_RESET_TIMEOUT_US: u64 = 100_000;
+/// Reset recovery before the first request to a device
+const RESET_RECOVERY_US: u64 = 10_000;
+
+/// Transfer request block
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Trb {
+    pub parameter: u64,
+    pub status: u32,
+    pub control: u32,
+}
+
+impl Trb {
+    fn new(trb_type: u32, parameter: u64, status: u32, control: u32) -> Self {
+        Self { parameter, status, control: control | trb_type << TRB_TYPE_SHIFT }
+    }
+
+    pub fn trb_type(&self) -> u32 {
+        (self.control >> TRB_TYPE_SHIFT) & 0x3F
+    }
+
+    pub fn completion_code(&self) -> u8 {
+        (self.status >> 24) as u8
+    }
+
+    /// Completed without error (a short packet is fine)
+    pub fn succeeded(&self) -> bool {
+        matches!(self.completion_code(), CC_SUCCESS | CC_SHORT_PACKET)
+    }
+
+    pub fn slot_id(&self) -> u8 {
+        (self.control >> 24) as u8
+    }
+
+    /// Device context index of a transfer event's endpoint
+    pub fn endpoint_id(&self) -> u8 {
+        ((self.control >> 16) & 0x1F) as u8
+    }
+}
+
+/// Producer ring (command or transfer)
+pub struct Ring {
+    trbs: *mut Trb,
+    phys: u64,
+    enqueue: usize,
+    cycle: bool,
+}
+
+impl Ring {
+    fn new() -> Result<Self, &'static str> {
+        let (phys, virt) = alloc_dma_page().ok_or("out of DMA pages for an xHCI ring")?;
+        let trbs = virt as *mut Trb;
+        // The last TRB links back to the start and flips the cycle state
+        unsafe {
+            write_volatile(trbs.add(RING_TRBS - 1), Trb::new(TRB_LINK, phys, 0, TRB_TOGGLE_CYCLE));
+        }
+        Ok(Self { trbs, phys, enqueue: 0, cycle: true })
+    }
+
+    /// Dequeue pointer for a context, with the initial cycle state
+    fn dequeue_pointer(&self) -> u64 {
+        self.phys | 1
+    }
+
+    /// Queue a TRB, handing it to the controller with the ring's cycle bit
+    /// Returns its physical address (what completion events point at).
+    fn push(&mut self, trb: Trb) -> u64 {
+        let address = self.phys + (self.enqueue * core::mem::size_of::<Trb>()) as u64;
+        let cycle = if self.cycle { TRB_CYCLE } else { 0 };
+        unsafe {
+            write_volatile(self.trbs.add(self.enqueue), Trb { control: (trb.control & !TRB_CYCLE) | cycle, ..trb });
+        }
+        self.enqueue += 1;
+        if self.enqueue == RING_TRBS - 1 {
+            // Pass the link TRB over too and wrap
+            unsafe {
+                let link = self.trbs.add(RING_TRBS - 1);
+                let trb = read_volatile(link);
+                write_volatile(link, Trb { control: (trb.control & !TRB_CYCLE) | cycle, ..trb });
+            }
+            self.enqueue = 0;
+            self.cycle = !self.cycle;
+        }
+        address
+    }
+}
+
+/// Consumer ring the controller posts events to
+struct EventRing {
+    trbs: *mut Trb,
+    phys: u64,
+    dequeue: usize,
+    cycle: bool,
+}
+
+impl EventRing {
+    fn new() -> Result<Self, &'static str> {
+        let (phys, virt) = alloc_dma_page().ok_or("out of DMA pages for the xHCI event ring")?;
+        Ok(Self { trbs: virt as *mut Trb, phys, dequeue: 0, cycle: true })
+    }
+
+    /// Next event, if the controller has posted one
+    fn pop(&mut self) -> Option<Trb> {
+        let trb = unsafe { read_volatile(self.trbs.add(self.dequeue)) };
+        if (trb.control & TRB_CYCLE != 0) != self.cycle {
+            return None;
+        }
+        self.dequeue += 1;
+        if self.dequeue == RING_TRBS {
+            self.dequeue = 0;
+            self.cycle = !self.cycle;
+        }
+        Some(trb)
+    }
+
+    fn dequeue_address(&self) -> u64 {
+        self.phys + (self.dequeue * core::mem::size_of::<Trb>()) as u64
+    }
+}
+
+/// An addressed device and its default control endpoint
+pub struct Device {
+    pub slot: u8,
+    /// Root hub port (1-based)
+    pub port: u8,
+    speed: u8,
+    ep0: Ring,
+    input_context: *mut u32,
+    input_context_phys: u64,
+    /// DMA page for control transfer data
+    buffer: *mut u8,
+    buffer_phys: u64,
+}
+
+impl Device {
+    /// Data of the last control transfer
+    pub fn buffer(&self, len: usize) -> &[u8] {
+        unsafe { core::slice::from_raw_parts(self.buffer, len.min(PAGE_SIZE)) }
+    }
+}
+
+/// xHCI controller
+pub struct Xhci {
+    base: u64,
+    op: u64,
+    runtime: u64,
+    doorbells: u64,
+    max_ports: u8,
+    /// Bytes per context structure (32, or 64 with HCCPARAMS1.CSZ)
+    context_size: usize,
+    dcbaa: *mut u64,
+    commands: Option<Ring>,
+    events: Option<EventRing>,
+}
+
+// Safety: the controller is only used through the USB keyboard's mutex
+unsafe impl Send for Xhci {}
+unsafe impl Send for Device {}
+unsafe impl Send for Ring {}
+
+impl Xhci {
+    /// Controller whose registers are mapped at `base`
+    pub fn new(base: u64) -> Self {
+        let read = |offset: u64| unsafe { read_volatile((base + offset) as *const u32) };
+        let caplength = read(CAP_CAPLENGTH) & 0xFF;
+        let csz = read(CAP_HCCPARAMS1) & (1 << 2) != 0;
+        Self {
+            base,
+            op: base + caplength as u64,
+            runtime: base + (read(CAP_RTSOFF) & !0x1F) as u64,
+            doorbells: base + (read(CAP_DBOFF) & !0x3) as u64,
+            max_ports: (read(CAP_HCSPARAMS1) >> 24) as u8,
+            context_size: if csz { 64 } else { 32 },
+            dcbaa: core::ptr::null_mut(),
+            commands: None,
+            events: None,
+        }
+    }
+
+    fn read32(&self, address: u64) -> u32 {
+        unsafe { read_volatile(address as *const u32) }
+    }
+
+    fn write32(&self, address: u64, value: u32) {
+        unsafe { write_volatile(address as *mut u32, value) }
+    }
+
+    fn write64(&self, address: u64, value: u64) {
+        unsafe { write_volatile(address as *mut u64, value) }
+    }
+
+    /// Root hub port count
+    pub fn port_count(&self) -> u8 {
+        self.max_ports
+    }
+
+    /// Reset the controller and start it with empty command and event rings
+    pub fn init(&mut self) -> Result<(), &'static str> {
+        self.take_ownership();
+
+        // Halt, then reset
+        let cmd = self.read32(self.op + OP_USBCMD);
+        self.write32(self.op + OP_USBCMD, cmd & !USBCMD_RUN);
+        if !wait_until(TIMEOUT_US, || self.read32(self.op + OP_USBSTS) & USBSTS_HCH != 0) {
+            return Err("xHCI did not halt");
+        }
+        self.write32(self.op + OP_USBCMD, USBCMD_HCRST);
+        let reset_done = wait_until(TIMEOUT_US, || {
+            self.read32(self.op + OP_USBCMD) & USBCMD_HCRST == 0 && self.read32(self.op + OP_USBSTS) & USBSTS_CNR == 0
+        });
+        if !reset_done {
+            return Err("xHCI reset timed out");
+        }
+
+        let max_slots = (self.read32(self.base + CAP_HCSPARAMS1) & 0xFF) as u8;
+        self.write32(self.op + OP_CONFIG, max_slots.min(MAX_SLOTS) as u32);
+
+        // Device context base address array, with the scratchpad array in slot 0
+        let (dcbaa_phys, dcbaa) = alloc_dma_page().ok_or("out of DMA pages for the xHCI DCBAA")?;
+        self.dcbaa = dcbaa as *mut u64;
+        let scratchpads = self.scratchpad_count();
+        if scratchpads > MAX_SCRATCHPADS {
+            return Err("xHCI wants too many scratchpad pages");
+        }
+        if scratchpads > 0 {
+            let (array_phys, array) = alloc_dma_page().ok_or("out of DMA pages for xHCI scratchpads")?;
+            for i in 0..scratchpads {
+                let (page, _) = alloc_dma_page().ok_or("out of DMA pages for xHCI scratchpads")?;
+                unsafe { write_volatile((array as *mut u64).add(i), page) };
+            }
+            unsafe { write_volatile(self.dcbaa, array_phys) };
+        }
+        self.write64(self.op + OP_DCBAAP, dcbaa_phys);
+
+        let commands = Ring::new()?;
+        self.write64(self.op + OP_CRCR, commands.dequeue_pointer());
+        self.commands = Some(commands);
+
+        // One-segment event ring for interrupter 0 (polled, interrupts stay off)
+        let events = EventRing::new()?;
+        let (erst_phys, erst) = alloc_dma_page().ok_or("out of DMA pages for the xHCI ERST")?;
+        unsafe {
+            write_volatile(erst as *mut u64, events.phys);
+            write_volatile((erst as *mut u32).add(2), RING_TRBS as u32);
+        }
+        self.write32(self.runtime + IR0_ERSTSZ, 1);
+        self.write64(self.runtime + IR0_ERDP, events.phys);
+        self.write64(self.runtime + IR0_ERSTBA, erst_phys);
+        self.events = Some(events);
+
+        self.write32(self.op + OP_USBCMD, USBCMD_RUN);
+        if !wait_until(TIMEOUT_US, || self.read32(self.op + OP_USBSTS) & USBSTS_HCH == 0) {
+            return Err("xHCI did not start");
+        }
+        Ok(())
+    }
+
+    /// Scratchpad pages the controller needs (HCSPARAMS2 max scratchpad buffers)
+    fn scratchpad_count(&self) -> usize {
+        let params = self.read32(self.base + CAP_HCSPARAMS2);
+        ((((params >> 21) & 0x1F) << 5) | ((params >> 27) & 0x1F)) as usize
+    }
+
+    /// Claim the controller from the BIOS's legacy USB emulation
+    fn take_ownership(&self) {
+        let mut offset = ((self.read32(self.base + CAP_HCCPARAMS1) >> 16) << 2) as u64;
+        while offset != 0 {
+            let cap = self.base + offset;
+            let header = self.read32(cap);
+            if header & 0xFF == XECP_LEGACY_SUPPORT {
+                self.write32(cap, header | LEGACY_OS_OWNED);
+                if !wait_until(TIMEOUT_US, || self.read32(cap) & LEGACY_BIOS_OWNED == 0) {
+                    crate::serial_println!("USB: BIOS kept the xHCI controller, continuing");
+                }
+                // Legacy SMIs off
+                self.write32(cap + 4, self.read32(cap + 4) & 0xFFFF_0000);
+                return;
+            }
+            offset = match (header >> 8) & 0xFF {
+                0 => 0,
+                next => offset + (next << 2) as u64,
+            };
+        }
+    }
+
+    /// Next event from the event ring, acknowledged
+    pub fn next_event(&mut self) -> Option<Trb> {
+        let events = self.events.as_mut()?;
+        let event = events.pop()?;
+        let dequeue = events.dequeue_address();
+        self.write64(self.runtime + IR0_ERDP, dequeue | ERDP_EHB);
+        Some(event)
+    }
+
+    /// Poll events until one matches (others are dropped)
+    fn wait_event(&mut self, want: impl Fn(&Trb) -> bool) -> Result<Trb, &'static str> {
+        let mut found = None;
+        wait_until(TIMEOUT_US, || {
+            while let Some(event) = self.next_event() {
+                if want(&event) {
+                    found = Some(event);
+                    return true;
+                }
+            }
+            false
+        });
+        found.ok_or("xHCI event timed out")
+    }
+
+    fn ring_doorbell(&self, slot: u8, target: u8) {
+        fence(Ordering::SeqCst);
+        self.write32(self.doorbells + slot as u64 * 4, target as u32);
+    }
+
+    /// Run a command and wait for its completion event
+    fn command(&mut self, trb: Trb) -> Result<Trb, &'static str> {
+        let address = self.commands.as_mut().ok_or("xHCI not running")?.push(trb);
+        self.ring_doorbell(0, 0);
+        let event = self.wait_event(|e| e.trb_type() == TRB_COMMAND_COMPLETION && e.parameter == address)?;
+        if event.completion_code() != CC_SUCCESS {
+            return Err("xHCI command failed");
+        }
+        Ok(event)
+    }
+
+    /// Reset a root hub port with a device attached
+    /// Returns the port's speed once enabled, None if nothing is connected.
+    pub fn reset_port(&mut self, port: u8) -> Option<u8> {
+        let portsc = self.op + OP_PORTSC + 0x10 * (port as u64 - 1);
+        let status = self.read32(portsc);
+        if status & PORTSC_CCS == 0 {
+            return None;
+        }
+        // USB3 ports enable themselves after link training; USB2 ports need a reset
+        if status & PORTSC_PED == 0 {
+            self.write32(portsc, (status & PORTSC_PRESERVE) | PORTSC_PR);
+            wait_until(PORT_RESET_TIMEOUT_US, || self.read32(portsc) & PORTSC_PRC != 0);
+        }
+        let status = self.read32(portsc);
+        self.write32(portsc, (status & PORTSC_PRESERVE) | (status & PORTSC_CHANGES));
+        if status & PORTSC_PED == 0 {
+            return None;
+        }
+        delay_us(RESET_RECOVERY_US);
+        Some(((status >> 10) & 0xF) as u8)
+    }
+
+    /// Pointer to dword `dword` of context `index` (0 = input control, 1 =
+    /// slot, 1 + DCI = endpoint) in an input context
+    fn context(&self, input: *mut u32, index: usize, dword: usize) -> *mut u32 {
+        unsafe { input.add(index * self.context_size / 4 + dword) }
+    }
+
+    fn clear_input_context(&self, device: &Device) {
+        unsafe { core::ptr::write_bytes(device.input_context as *mut u8, 0, 33 * self.context_size) };
+    }
+
+    /// Slot context dword 0: speed and the last valid endpoint context
+    fn slot_dword0(speed: u8, last_dci: u8) -> u32 {
+        (speed as u32) << 20 | (last_dci as u32) << 27
+    }
+
+    /// Enable a slot for the device on `port` and give it an address
+    pub fn address_device(&mut self, port: u8, speed: u8) -> Result<Device, &'static str> {
+        let slot = self.command(Trb::new(TRB_ENABLE_SLOT, 0, 0, 0))?.slot_id();
+
+        let (output_phys, _) = alloc_dma_page().ok_or("out of DMA pages for a device context")?;
+        unsafe { write_volatile(self.dcbaa.add(slot as usize), output_phys) };
+        let (input_phys, input) = alloc_dma_page().ok_or("out of DMA pages for an input context")?;
+        let (buffer_phys, buffer) = alloc_dma_page().ok_or("out of DMA pages for a control buffer")?;
+        let mut device = Device {
+            slot,
+            port,
+            speed,
+            ep0: Ring::new()?,
+            input_context: input as *mut u32,
+            input_context_phys: input_phys,
+            buffer,
+            buffer_phys,
+        };
+
+        // Add the slot and endpoint 0 contexts
+        let max_packet = default_max_packet(speed);
+        unsafe {
+            write_volatile(self.context(device.input_context, 0, 1), 0b11);
+            write_volatile(self.context(device.input_context, 1, 0), Self::slot_dword0(speed, 1));
+            write_volatile(self.context(device.input_context, 1, 1), (port as u32) << 16);
+            self.write_endpoint_context(&device, 1, EP_TYPE_CONTROL, max_packet, 0, device.ep0.dequeue_pointer(), 8);
+        }
+        self.command(Trb::new(TRB_ADDRESS_DEVICE, input_phys, 0, (slot as u32) << 24))?;
+
+        // Full-speed devices may use a larger endpoint 0 packet than assumed
+        let header = self.control_transfer(&mut device, SetupPacket::get_descriptor(DESC_DEVICE, 8))?;
+        let actual = header.get(DEVICE_MAX_PACKET_OFFSET).copied().unwrap_or(0) as u16;
+        if speed == SPEED_FULL && actual != 0 && actual != max_packet {
+            self.clear_input_context(&device);
+            unsafe {
+                write_volatile(self.context(device.input_context, 0, 1), 0b10);
+                self.write_endpoint_context(&device, 1, EP_TYPE_CONTROL, actual, 0, device.ep0.dequeue_pointer(), 8);
+            }
+            self.command(Trb::new(TRB_EVALUATE_CONTEXT, input_phys, 0, (slot as u32) << 24))?;
+        }
+        Ok(device)
+    }
+
+    /// Fill in endpoint context `dci` of a device's input context
+    fn write_endpoint_context(
+        &self,
+        device: &Device,
+        dci: u8,
+        ep_type: u32,
+        max_packet: u16,
+        interval: u32,
+        dequeue: u64,
+        average_trb: u32,
+    ) {
+        let ep = dci as usize + 1;
+        unsafe {
+            write_volatile(self.context(device.input_context, ep, 0), interval << 16);
+            write_volatile(
+                self.context(device.input_context, ep, 1),
+                EP_ERROR_COUNT << 1 | ep_type << 3 | (max_packet as u32) << 16,
+            );
+            write_volatile(self.context(device.input_context, ep, 2), dequeue as u32);
+            write_volatile(self.context(device.input_context, ep, 3), (dequeue >> 32) as u32);
+            write_volatile(self.context(device.input_context, ep, 4), average_trb);
+        }
+    }
+
+    /// Run a control transfer on endpoint 0
+    /// Returns the data stage's buffer (empty for requests without data).
+    pub fn control_transfer<'a>(&mut self, device: &'a mut Device, setup: SetupPacket) -> Result<&'a [u8], &'static str> {
+        let length = setup.length as usize;
+        let transfer_type = match (length, setup.is_in()) {
+            (0, _) => TRT_NO_DATA,
+            (_, true) => TRT_IN,
+            (_, false) => TRT_OUT,
+        };
+        device.ep0.push(Trb::new(TRB_SETUP, setup.to_u64(), 8, TRB_IDT | transfer_type << 16));
+        if length > 0 {
+            let direction = if setup.is_in() { TRB_DIR_IN } else { 0 };
+            device.ep0.push(Trb::new(TRB_DATA, device.buffer_phys, length as u32, direction));
+        }
+        // The status stage runs opposite to the data (IN when there is none)
+        let status_direction = if length > 0 && setup.is_in() { 0 } else { TRB_DIR_IN };
+        let status = device.ep0.push(Trb::new(TRB_STATUS, 0, 0, TRB_IOC | status_direction));
+        self.ring_doorbell(device.slot, 1);
+
+        let slot = device.slot;
+        let event = self.wait_event(|e| e.trb_type() == TRB_TRANSFER_EVENT && e.slot_id() == slot && e.parameter == status)?;
+        if !event.succeeded() {
+            return Err("USB control transfer failed");
+        }
+        Ok(device.buffer(length))
+    }
+
+    /// Configure an interrupt-IN endpoint (e.g. 0x81) and give it a ring
+    /// Returns the ring and the endpoint's device context index.
+    pub fn configure_interrupt_in(
+        &mut self,
+        device: &Device,
+        endpoint: u8,
+        max_packet: u16,
+        interval: u8,
+    ) -> Result<(Ring, u8), &'static str> {
+        let dci = (endpoint & 0xF) * 2 + 1;
+        let ring = Ring::new()?;
+        self.clear_input_context(device);
+        unsafe {
+            write_volatile(self.context(device.input_context, 0, 1), 1 | 1 << dci);
+            write_volatile(self.context(device.input_context, 1, 0), Self::slot_dword0(device.speed, dci));
+            write_volatile(self.context(device.input_context, 1, 1), (device.port as u32) << 16);
+            // Average TRB length and max ESIT payload are both one packet
+            self.write_endpoint_context(
+                device,
+                dci,
+                EP_TYPE_INTERRUPT_IN,
+                max_packet,
+                interval_exponent(device.speed, interval),
+                ring.dequeue_pointer(),
+                max_packet as u32 | (max_packet as u32) << 16,
+            );
+        }
+        self.command(Trb::new(TRB_CONFIGURE_ENDPOINT, device.input_context_phys, 0, (device.slot as u32) << 24))?;
+        Ok((ring, dci))
+    }
+
+    /// Queue an IN transfer of `length` bytes into `buffer` on an endpoint ring
+    pub fn queue_in(&mut self, ring: &mut Ring, slot: u8, dci: u8, buffer: u64, length: u32) {
+        ring.push(Trb::new(TRB_NORMAL, buffer, length, TRB_IOC | TRB_ISP));
+        self.ring_doorbell(slot, dci);
+    }
+}
+
+/// Endpoint 0 packet size to start with for a port speed
+fn default_max_packet(speed: u8) -> u16 {
+    match speed {
+        SPEED_FULL | SPEED_LOW => 8,
+        SPEED_HIGH => 64,
+        _ => 512,
+    }
+}
+
+/// Endpoint context interval (2^n x 125 us) for an endpoint's bInterval
+/// Low/full-speed intervals are in 1 ms frames, faster ones already an
+/// exponent of microframes (plus one).
+fn interval_exponent(speed: u8, b_interval: u8) -> u32 {
+    match speed {
+        SPEED_FULL | SPEED_LOW => {
+            let microframes = b_interval.max(1) as u32 * 8;
+            (31 - microframes.leading_zeros()).clamp(3, 10)
+        }
+        _ => b_interval.clamp(1, 16) as u32 - 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interval_exponent() {
+        // 10 ms at full speed: 80 microframes rounds down to 2^6 (8 ms)
+        assert_eq!(interval_exponent(SPEED_FULL, 10), 6);
+        assert_eq!(interval_exponent(SPEED_LOW, 1), 3);
+        assert_eq!(interval_exponent(SPEED_LOW, 255), 10);
+        // High speed bInterval 4 = 2^3 microframes
+        assert_eq!(interval_exponent(SPEED_HIGH, 4), 3);
+        assert_eq!(interval_exponent(SPEED_HIGH, 0), 0);
+    }
+
+    #[test]
+    fn test_event_fields() {
+        let event = Trb { parameter: 0x1000, status: 13 << 24 | 3, control: TRB_TRANSFER_EVENT << 10 | 3 << 16 | 2 << 24 | 1 };
+        assert_eq!(event.trb_type(), TRB_TRANSFER_EVENT);
+        assert_eq!((event.slot_id(), event.endpoint_id()), (2, 3));
+        assert!(event.succeeded());
+        assert!(!Trb { status: 6 << 24, ..event }.succeeded());
+    }
+}
@@ -0,0 +1,125 @@
+//! Cursor images for the host-drawn mouse cursor
+//!
+//! SVGA_CMD_DEFINE_CURSOR takes an AND mask and an XOR image: the host
+//! computes `(screen & and) ^ xor` for every cursor pixel. Transparent pixels
+//! set the 1-bit AND mask and leave XOR black; opaque ones clear the mask and
+//! put their color in the 32-bit XOR image. Mask rows are padded to 32 bits,
+//! with the leftmost pixel in the high bit of each byte.
+//!
+//! Devices with SVGA_CAP_ALPHA_CURSOR take SVGA_CMD_DEFINE_ALPHA_CURSOR
+//! instead: a single premultiplied ARGB image, transparent where alpha is 0.
+
+use super::regs::{self, cap, cmd};
+use alloc::vec::Vec;
+
+/// The only cursor we define
+pub const CURSOR_ID: u32 = 0;
+
+/// Whether devices with these capabilities draw the cursor in hardware
+pub fn hardware_supported(capabilities: u32) -> bool {
+    regs::has_capability(capabilities, cap::CURSOR) || alpha_supported(capabilities)
+}
+
+/// Whether the device takes alpha cursor images (define_alpha_cursor_cmd)
+pub fn alpha_supported(capabilities: u32) -> bool {
+    regs::has_capability(capabilities, cap::ALPHA_CURSOR)
+}
+
+/// Build the DEFINE_CURSOR command for a width x height image (at most 256
+/// wide, the host's limit) with the given hotspot; `pixel(x, y)` is the color
+/// there, or None where the screen shows through
+pub fn define_cursor_cmd(
+    hotspot: (u32, u32),
+    width: usize,
+    height: usize,
+    pixel: impl Fn(usize, usize) -> Option<u32>,
+) -> Vec<u32> {
+    let mask_words = width.div_ceil(32);
+    let mut words = Vec::with_capacity(8 + (mask_words + width) * height);
+    // AND mask depth 1, XOR image depth 32
+    words.extend([cmd::DEFINE_CURSOR, CURSOR_ID, hotspot.0, hotspot.1, width as u32, height as u32, 1, 32]);
+
+    for y in 0..height {
+        let mut row = [0u32; 8];
+        for x in 0..width {
+            if pixel(x, y).is_none() {
+                row[x / 32] |= mask_bit(x);
+            }
+        }
+        words.extend_from_slice(&row[..mask_words]);
+    }
+    for y in 0..height {
+        words.extend((0..width).map(|x| pixel(x, y).unwrap_or(0)));
+    }
+    words
+}
+
+/// Build the DEFINE_ALPHA_CURSOR command for a width x height image with the
+/// given hotspot; `pixel(x, y)` is the opaque color there, or None where the
+/// screen shows through
+pub fn define_alpha_cursor_cmd(
+    hotspot: (u32, u32),
+    width: usize,
+    height: usize,
+    pixel: impl Fn(usize, usize) -> Option<u32>,
+) -> Vec<u32> {
+    let mut words = Vec::with_capacity(6 + width * height);
+    words.extend([cmd::DEFINE_ALPHA_CURSOR, CURSOR_ID, hotspot.0, hotspot.1, width as u32, height as u32]);
+    for y in 0..height {
+        // Opaque pixels need no premultiplying; transparent ones are all zero
+        words.extend((0..width).map(|x| pixel(x, y).map_or(0, |color| 0xFF00_0000 | color)));
+    }
+    words
+}
+
+/// Bit of pixel `x` within its 32-bit mask word (bytes are little-endian,
+/// pixels MSB-first within a byte)
+fn mask_bit(x: usize) -> u32 {
+    let byte = (x / 8) % 4;
+    1 << (byte * 8 + 7 - x % 8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_define_cursor_packs_masks() {
+        // 10x2 image: an opaque red pixel at (0, 0) and white at (9, 1)
+        let pixel = |x, y| match (x, y) {
+            (0, 0) => Some(0x00FF_0000),
+            (9, 1) => Some(0x00FF_FFFF),
+            _ => None,
+        };
+        let words = define_cursor_cmd((1, 0), 10, 2, pixel);
+        assert_eq!(words[..8], [cmd::DEFINE_CURSOR, CURSOR_ID, 1, 0, 10, 2, 1, 32]);
+
+        // One padded mask word per row; bytes hold pixels 0-7 and 8-15
+        let all = 0x0000_C0FF;
+        assert_eq!(words[8], all & !0x80);
+        assert_eq!(words[9], all & !0x4000);
+        assert_eq!(words.len(), 8 + 2 + 20);
+
+        let image = &words[10..];
+        assert_eq!(image[0], 0x00FF_0000);
+        assert_eq!(image[10 + 9], 0x00FF_FFFF);
+        assert_eq!(image.iter().filter(|&&p| p != 0).count(), 2);
+    }
+
+    #[test]
+    fn test_hardware_cursor_needs_capability() {
+        assert!(hardware_supported(cap::CURSOR | cap::RECT_COPY));
+        assert!(hardware_supported(cap::RECT_COPY | cap::ALPHA_CURSOR));
+        assert!(!alpha_supported(cap::CURSOR));
+        assert!(!hardware_supported(cap::RECT_COPY));
+        assert!(!hardware_supported(cap::NONE));
+    }
+
+    #[test]
+    fn test_define_alpha_cursor_premultiplied() {
+        let pixel = |x, y| ((x, y) == (1, 0)).then_some(0x0012_3456);
+        let words = define_alpha_cursor_cmd((0, 2), 2, 2, pixel);
+        assert_eq!(words[..6], [cmd::DEFINE_ALPHA_CURSOR, CURSOR_ID, 0, 2, 2, 2]);
+        assert_eq!(words[6..], [0, 0xFF12_3456, 0, 0]);
+    }
+}
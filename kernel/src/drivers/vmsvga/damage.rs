@@ -0,0 +1,101 @@
+//! Bounding box of the pixels drawn through the device since the last present
+//!
+//! The device's drawing methods take `&self` (it is shared behind a mutex
+//! with the rest of the graphics code), so the box lives in a `Cell`.
+
+use core::cell::Cell;
+use crate::graphics::framebuffer::{DirtyRect, DirtyRects};
+
+/// Damage covering at least this many eighths of the screen is sent as one
+/// full update; per-rectangle copies stop paying off near full coverage
+const FULL_UPDATE_EIGHTHS: usize = 6;
+
+/// Accumulated changes as inclusive bounds (min_x, min_y, max_x, max_y)
+#[derive(Debug, Default)]
+pub struct DamageBox {
+    bounds: Cell<Option<(usize, usize, usize, usize)>>,
+}
+
+impl DamageBox {
+    pub const fn new() -> Self {
+        Self { bounds: Cell::new(None) }
+    }
+
+    /// Record a single changed pixel
+    #[inline]
+    pub fn add_pixel(&self, x: usize, y: usize) {
+        self.add_rect(x, y, 1, 1);
+    }
+
+    /// Record a changed rectangle
+    pub fn add_rect(&self, x: usize, y: usize, width: usize, height: usize) {
+        if width == 0 || height == 0 {
+            return;
+        }
+        let (max_x, max_y) = (x + width - 1, y + height - 1);
+        let bounds = match self.bounds.get() {
+            Some((x0, y0, x1, y1)) => (x0.min(x), y0.min(y), x1.max(max_x), y1.max(max_y)),
+            None => (x, y, max_x, max_y),
+        };
+        self.bounds.set(Some(bounds));
+    }
+
+    /// The changed area so far, if anything changed
+    pub fn bounds(&self) -> Option<DirtyRect> {
+        self.bounds
+            .get()
+            .map(|(x0, y0, x1, y1)| DirtyRect::new(x0, y0, x1 + 1 - x0, y1 + 1 - y0))
+    }
+
+    /// Take the changes as a present region for a width x height screen,
+    /// resetting the box; None when nothing changed
+    pub fn take_region(&self, width: usize, height: usize) -> Option<DirtyRects> {
+        let rect = self.bounds()?.clipped(width, height);
+        self.bounds.set(None);
+
+        let mut region = DirtyRects::new();
+        if rect.width * rect.height * 8 >= width * height * FULL_UPDATE_EIGHTHS {
+            region.set_full();
+        } else {
+            region.add(rect);
+        }
+        Some(region)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_damage_box_accumulates_bounds() {
+        let damage = DamageBox::new();
+        assert_eq!(damage.bounds(), None);
+
+        damage.add_pixel(10, 20);
+        assert_eq!(damage.bounds(), Some(DirtyRect::new(10, 20, 1, 1)));
+
+        damage.add_pixel(300, 5);
+        assert_eq!(damage.bounds(), Some(DirtyRect::new(10, 5, 291, 16)));
+
+        // Two pixels still make a small update, not a full one
+        let region = damage.take_region(640, 480).unwrap();
+        assert!(!region.is_full());
+        assert_eq!(region.rects(), [DirtyRect::new(10, 5, 291, 16)]);
+        assert!(damage.take_region(640, 480).is_none());
+    }
+
+    #[test]
+    fn test_damage_box_full_screen() {
+        // Clearing marks the whole screen, which presents as a full update
+        let damage = DamageBox::new();
+        damage.add_rect(0, 0, 640, 480);
+        assert_eq!(damage.bounds(), Some(DirtyRect::new(0, 0, 640, 480)));
+        assert!(damage.take_region(640, 480).unwrap().is_full());
+
+        // Most of the screen also falls back to a full update
+        damage.add_pixel(0, 0);
+        damage.add_pixel(600, 400);
+        assert!(damage.take_region(640, 480).unwrap().is_full());
+    }
+}
@@ -5,10 +5,15 @@
 
 extern crate alloc;
 
-use super::regs::{self, SvgaReg};
+use super::regs::{self, Svga3dTransferType, SvgaReg};
+use crate::drivers::pit;
+use crate::read_tsc;
 use alloc::vec;
 use core::sync::atomic::{fence, Ordering};
 
+/// Longest sync() waits for the device to go idle before giving up
+const SYNC_TIMEOUT_MS: u64 = 1000;
+
 /// FIFO register offsets (indices into FIFO memory)
 pub mod fifo_reg {
     /// Minimum valid offset in FIFO (start of command area)
@@ -274,22 +279,57 @@ impl VmsvgaFifo {
     }
 
     /// Synchronize - wait for all commands to complete
-    pub fn sync(&self) {
+    /// Returns false if the device was still busy after SYNC_TIMEOUT_MS
+    pub fn sync(&self) -> bool {
         if !self.is_initialized() {
-            return;
+            return true;
         }
 
         // Write to SYNC register
         regs::write_reg(self.io_base, SvgaReg::Sync, 1);
 
         // Wait for BUSY to clear
-        loop {
-            let busy = regs::read_reg(self.io_base, SvgaReg::Busy);
-            if busy == 0 {
-                break;
+        let deadline = read_tsc() + pit::tsc_per_second() / 1000 * SYNC_TIMEOUT_MS;
+        while regs::read_reg(self.io_base, SvgaReg::Busy) != 0 {
+            if read_tsc() >= deadline {
+                return false;
             }
             core::hint::spin_loop();
         }
+        true
+    }
+
+    /// Whether the FIFO FENCE register lies outside the command area
+    ///
+    /// The header we set up in init() is only NUM_REGS long, so on most
+    /// hosts the fence register overlaps commands and cannot be trusted.
+    pub fn has_fence_reg(&self) -> bool {
+        self.has_cap(fifo_cap::FENCE) && self.read_reg(fifo_reg::MIN) as usize > fifo_reg::FENCE * 4
+    }
+
+    /// Queue a FENCE command; the host writes `id` to the FENCE register once
+    /// every command before it has been processed
+    pub fn cmd_fence(&self, id: u32) -> bool {
+        if !self.has_fence_reg() {
+            return false;
+        }
+        self.write_cmd(&[regs::cmd::FENCE, id])
+    }
+
+    /// Check without blocking whether the host has processed fence `id`
+    ///
+    /// Without a usable fence register this asks the device to start
+    /// processing and reports whether it is already idle.
+    pub fn fence_passed(&self, id: u32) -> bool {
+        if !self.is_initialized() {
+            return true;
+        }
+        if self.has_fence_reg() {
+            // Wrapping comparison: fence ids count up forever
+            return self.read_reg(fifo_reg::FENCE).wrapping_sub(id) as i32 >= 0;
+        }
+        regs::write_reg(self.io_base, SvgaReg::Sync, 1);
+        regs::read_reg(self.io_base, SvgaReg::Busy) == 0
     }
 
     /// Send UPDATE command to refresh a screen region
@@ -333,6 +373,12 @@ impl VmsvgaFifo {
         self.write_cmd(&cmd)
     }
 
+    /// Send RECT_FILL command to fill a screen rectangle with a solid color
+    /// (needs cap::RECT_FILL); color is in the display's pixel format
+    pub fn cmd_fill(&self, color: u32, x: u32, y: u32, width: u32, height: u32) -> bool {
+        self.write_cmd(&[regs::cmd::RECT_FILL, color, x, y, width, height])
+    }
+
     /// Send UPDATE command to refresh the entire screen
     pub fn cmd_update_full(&self, width: u32, height: u32) -> bool {
         self.cmd_update(0, 0, width, height)
@@ -456,13 +502,13 @@ impl VmsvgaFifo {
     /// Set transform matrix
     pub fn cmd_3d_set_transform(&self, cid: u32, transform_type: u32, matrix: &[f32; 16]) -> bool {
         use super::svga3d::cmd;
-        let mut data = [0u32; 17];
+        let mut data = [0u32; 18];
         data[0] = cid;
         data[1] = transform_type;
         for (i, &val) in matrix.iter().enumerate() {
             data[2 + i] = val.to_bits();
         }
-        self.write_3d_cmd(cmd::SETTRANSFORM, &data[..18])
+        self.write_3d_cmd(cmd::SETTRANSFORM, &data)
     }
 
     /// Set render state
@@ -592,37 +638,50 @@ impl VmsvgaFifo {
         size: u32,
         transfer_to_surface: bool,
     ) -> bool {
-        use super::svga3d::cmd;
-
-        // SVGA3dCmdSurfaceDMA structure:
-        // guest: SVGA3dGuestImage (ptr: SVGAGuestPtr, pitch)
-        // host: SVGA3dSurfaceImageId (sid, face, mipmap)
-        // transfer: SVGA3dTransferType
-        // then array of SVGA3dCopyBox
+        let transfer = if transfer_to_surface {
+            Svga3dTransferType::WriteHostVram
+        } else {
+            Svga3dTransferType::ReadHostVram
+        };
+        // Buffers are one row of `size` bytes; x is a byte offset
+        self.surface_dma(gmr_id, gmr_offset, size, surface_id, transfer, [surface_offset, 0, 0, size, 1, 1, 0, 0, 0])
+    }
 
-        // SVGAGuestPtr: gmr_id, offset
-        // We use a single copy box that covers the entire transfer
+    /// Read back a `width` x `height` 32bpp surface into the start of a GMR,
+    /// one tightly packed row after another
+    pub fn cmd_3d_read_surface(&self, gmr_id: u32, surface_id: u32, width: u32, height: u32) -> bool {
+        self.surface_dma(gmr_id, 0, width * 4, surface_id, Svga3dTransferType::ReadHostVram, [0, 0, 0, width, height, 1, 0, 0, 0])
+    }
 
-        let transfer_type = if transfer_to_surface { 0u32 } else { 1u32 };
+    /// SURFACE_DMA between a GMR and mip 0 of a surface with a single copy box
+    /// `copy_box` is SVGA3dCopyBox: destination x, y, z, size w, h, d, then
+    /// source x, y, z (host surface coordinates, guest at the same offsets)
+    fn surface_dma(
+        &self,
+        gmr_id: u32,
+        gmr_offset: u32,
+        guest_pitch: u32,
+        surface_id: u32,
+        transfer: Svga3dTransferType,
+        copy_box: [u32; 9],
+    ) -> bool {
+        use super::svga3d::cmd;
 
-        let data = [
+        let mut data = [0u32; 17];
+        data[..8].copy_from_slice(&[
             // Guest image (SVGAGuestPtr + pitch)
-            gmr_id,       // guest.ptr.gmrId
-            gmr_offset,   // guest.ptr.offset
-            size,         // guest.pitch (bytes per row, for 1D it's the total size)
-            // Host image (SVGA3dSurfaceImageId)
-            surface_id,   // host.sid
-            0,            // host.face
-            0,            // host.mipmap
-            // Transfer direction
-            transfer_type,
+            gmr_id,
+            gmr_offset,
+            guest_pitch,
+            // Host image (SVGA3dSurfaceImageId: sid, face, mipmap)
+            surface_id,
+            0,
+            0,
+            transfer as u32,
             // Number of copy boxes
             1,
-            // Copy box (srcx, srcy, srcz, x, y, z, w, h, d)
-            0, 0, 0,              // src xyz
-            surface_offset, 0, 0, // dst xyz (we use x as byte offset for buffers)
-            size, 1, 1,           // width, height, depth
-        ];
+        ]);
+        data[8..].copy_from_slice(&copy_box);
 
         self.write_3d_cmd(cmd::SURFACE_DMA, &data)
     }
@@ -637,6 +696,18 @@ impl VmsvgaFifo {
         self.cmd_3d_surface_dma(gmr_id, 0, surface_id, 0, size, true)
     }
 
+    /// Upload `size` bytes from the start of a GMR to `surface_offset` in a buffer surface
+    /// Lets buffers larger than the GMR be filled in chunks
+    pub fn cmd_3d_upload_buffer(
+        &self,
+        gmr_id: u32,
+        surface_id: u32,
+        surface_offset: u32,
+        size: u32,
+    ) -> bool {
+        self.cmd_3d_surface_dma(gmr_id, 0, surface_id, surface_offset, size, true)
+    }
+
     /// Set vertex stream source for drawing
     /// stride is bytes per vertex
     pub fn cmd_3d_set_stream_source(
@@ -671,42 +742,11 @@ impl VmsvgaFifo {
         num_vertices: u32,
         vertex_stride: u32,
     ) -> bool {
-        use super::svga3d::cmd;
-
-        // First set the vertex stream
-        if !self.cmd_3d_set_stream_source(cid, 0, vertex_surface_id, 0, vertex_stride) {
-            return false;
-        }
-
-        // Vertex declaration format:
-        // identity (stream, offset, type, method, usage, usage_index)
-        // We use: FLOAT3 for position (0-11), D3DCOLOR for color (12-15) = 16 bytes total
-
-        // Vertex declarations (each is 6 u32s):
-        // Position: stream 0, offset 0, type FLOAT3 (2), method DEFAULT (0), usage POSITION (0), index 0
-        // Color: stream 0, offset 12, type D3DCOLOR (4), method DEFAULT (0), usage COLOR (10), index 0
-        let vertex_decls: [u32; 12] = [
-            // Position declaration
-            0,   // stream
-            0,   // offset
-            2,   // type: FLOAT3
-            0,   // method: DEFAULT
-            0,   // usage: POSITION
-            0,   // usageIndex
-            // Color declaration
-            0,   // stream
-            12,  // offset (after xyz floats)
-            4,   // type: D3DCOLOR
-            0,   // method: DEFAULT
-            10,  // usage: COLOR
-            0,   // usageIndex
-        ];
-
         // Primitive range:
         // primitiveType, primitiveCount, indexArray.surfaceId, indexArray.offset, indexWidth,
         // indexBias, minIndex, maxIndex
         let num_triangles = num_vertices / 3;
-        let ranges: [u32; 8] = [
+        let range: [u32; 8] = [
             5,             // primitiveType: TRIANGLELIST (5)
             num_triangles, // primitiveCount
             0xFFFFFFFF,    // indexArray.surfaceId (no index buffer, use 0xFFFFFFFF)
@@ -716,6 +756,42 @@ impl VmsvgaFifo {
             0,             // minIndex
             num_vertices - 1, // maxIndex
         ];
+        self.draw_position_color(cid, vertex_surface_id, vertex_stride, &range)
+    }
+
+    /// Draw an indexed triangle list of position+color vertices
+    /// `index_width` is the size of one index in bytes (2 or 4)
+    pub fn cmd_3d_draw_indexed(
+        &self,
+        cid: u32,
+        vertex_surface_id: u32,
+        vertex_stride: u32,
+        num_vertices: u32,
+        index_surface_id: u32,
+        index_width: u32,
+        num_triangles: u32,
+    ) -> bool {
+        let range: [u32; 8] = [
+            5,                // primitiveType: TRIANGLELIST (5)
+            num_triangles,    // primitiveCount
+            index_surface_id, // indexArray.surfaceId
+            0,                // indexArray.offset
+            index_width,      // indexWidth
+            0,                // indexBias
+            0,                // minIndex
+            num_vertices - 1, // maxIndex
+        ];
+        self.draw_position_color(cid, vertex_surface_id, vertex_stride, &range)
+    }
+
+    /// Bind a position+color vertex stream and draw one primitive range from it
+    fn draw_position_color(&self, cid: u32, vertex_surface_id: u32, vertex_stride: u32, range: &[u32; 8]) -> bool {
+        use super::svga3d::cmd;
+
+        // First set the vertex stream
+        if !self.cmd_3d_set_stream_source(cid, 0, vertex_surface_id, 0, vertex_stride) {
+            return false;
+        }
 
         // Build the full DRAW_PRIMITIVES command
         let mut data = vec![
@@ -723,9 +799,29 @@ impl VmsvgaFifo {
             2,  // numVertexDecls
             1,  // numRanges
         ];
-        data.extend_from_slice(&vertex_decls);
-        data.extend_from_slice(&ranges);
+        data.extend_from_slice(&POSITION_COLOR_DECLS);
+        data.extend_from_slice(range);
 
         self.write_3d_cmd(cmd::DRAW_PRIMITIVES, &data)
     }
 }
+
+/// Vertex declarations for position+color vertices (each is 6 u32s):
+/// identity (stream, offset, type, method, usage, usage_index)
+/// We use: FLOAT3 for position (0-11), D3DCOLOR for color (12-15) = 16 bytes total
+const POSITION_COLOR_DECLS: [u32; 12] = [
+    // Position: stream 0, offset 0, type FLOAT3 (2), method DEFAULT (0), usage POSITION (0), index 0
+    0,   // stream
+    0,   // offset
+    2,   // type: FLOAT3
+    0,   // method: DEFAULT
+    0,   // usage: POSITION
+    0,   // usageIndex
+    // Color: stream 0, offset 12, type D3DCOLOR (4), method DEFAULT (0), usage COLOR (10), index 0
+    0,   // stream
+    12,  // offset (after xyz floats)
+    4,   // type: D3DCOLOR
+    0,   // method: DEFAULT
+    10,  // usage: COLOR
+    0,   // usageIndex
+];
@@ -4,25 +4,34 @@
 //! virtual machines, including QEMU (-vga vmware) and VirtualBox (VMSVGA adapter).
 //!
 //! Features:
-//! - Hardware framebuffer with configurable resolution
+//! - Hardware framebuffer with configurable resolution, switchable at runtime
 //! - FIFO command buffer for accelerated operations
 //! - Screen update commands for efficient display refresh
+//! - Presents limited to the bounding box of pixels drawn since the last one
+//! - Triple-buffered present that never waits on the host
 //! - Rectangle fill and copy acceleration (when supported)
+//! - Hardware mouse cursor (when supported)
 //! - SVGA3D support for hardware-accelerated 3D rendering
 //! - GMR (Guest Memory Region) for DMA transfers
 
+pub mod cursor;
+pub mod damage;
 pub mod fifo;
 pub mod gmr;
 pub mod regs;
 pub mod svga3d;
+pub mod swapchain;
 
 use crate::drivers::pci::{self, PciDevice};
+use crate::graphics::framebuffer::{DirtyRect, DirtyRects};
 use crate::memory::paging;
 use crate::serial_println;
 use alloc::vec::Vec;
+use damage::DamageBox;
 use fifo::VmsvgaFifo;
 use regs::{SvgaReg, VMSVGA_DEVICE_ID, VMWARE_VENDOR_ID};
 use spin::Mutex;
+use swapchain::{BufferRing, BUFFER_COUNT};
 
 /// VMSVGA device state
 pub struct VmsvgaDevice {
@@ -32,6 +41,8 @@ pub struct VmsvgaDevice {
     fb_virt: u64,
     /// Framebuffer size in bytes
     fb_size: usize,
+    /// Total VRAM the device reports, in bytes
+    vram_size: usize,
     /// Display width in pixels
     width: u32,
     /// Display height in pixels
@@ -44,8 +55,18 @@ pub struct VmsvgaDevice {
     capabilities: u32,
     /// FIFO command buffer
     fifo: VmsvgaFifo,
-    /// Back buffer for double buffering
-    back_buffer: Vec<u32>,
+    /// Draw buffers, rotated by `ring` on every present
+    buffers: [Vec<u32>; BUFFER_COUNT],
+    /// Which buffer is being drawn, queued, or on screen
+    ring: BufferRing,
+    /// Regions each buffer is missing relative to the latest finished frame
+    stale: [DirtyRects; BUFFER_COUNT],
+    /// Regions changed since the last copy to VRAM
+    pending: DirtyRects,
+    /// Fence sent after the last copy to VRAM
+    fence: u32,
+    /// Pixels drawn through this device since the last `present_damage`
+    damage: DamageBox,
     /// Whether the device is initialized
     initialized: bool,
 }
@@ -61,13 +82,19 @@ impl VmsvgaDevice {
             io_base: 0,
             fb_virt: 0,
             fb_size: 0,
+            vram_size: 0,
             width: 0,
             height: 0,
             bpp: 0,
             pitch: 0,
             capabilities: 0,
             fifo: VmsvgaFifo::new(),
-            back_buffer: Vec::new(),
+            buffers: [Vec::new(), Vec::new(), Vec::new()],
+            ring: BufferRing::new(),
+            stale: [const { DirtyRects::new() }; BUFFER_COUNT],
+            pending: DirtyRects::new(),
+            fence: 0,
+            damage: DamageBox::new(),
             initialized: false,
         }
     }
@@ -102,19 +129,46 @@ impl VmsvgaDevice {
         &self.fifo
     }
 
+    /// Get total VRAM size in bytes
+    pub fn vram_size(&self) -> usize {
+        self.vram_size
+    }
+
+    /// Read an SVGA3D device capability (see regs::devcap)
+    /// None when the device has no DEV_CAP register.
+    pub fn devcap(&self, index: u32) -> Option<u32> {
+        if !self.initialized || !regs::has_capability(self.capabilities, regs::cap::GBOBJECTS) {
+            return None;
+        }
+        regs::write_reg(self.io_base, SvgaReg::DevCap, index);
+        Some(regs::read_reg(self.io_base, SvgaReg::DevCap))
+    }
+
+    /// Largest texture side the 3D device accepts, if it reports one
+    pub fn max_texture_size(&self) -> Option<u32> {
+        let width = self.devcap(regs::devcap::MAX_TEXTURE_WIDTH)?;
+        let height = self.devcap(regs::devcap::MAX_TEXTURE_HEIGHT)?;
+        Some(width.min(height)).filter(|&size| size > 0)
+    }
+
     /// Get pointer to the front buffer (hardware framebuffer)
     pub fn front_buffer(&self) -> *mut u32 {
         self.fb_virt as *mut u32
     }
 
-    /// Get pointer to the back buffer
+    /// The buffer currently being drawn into
+    fn draw_buffer(&self) -> &Vec<u32> {
+        &self.buffers[self.ring.draw()]
+    }
+
+    /// Get pointer to the back buffer (the current draw buffer)
     pub fn back_buffer(&self) -> *mut u32 {
-        self.back_buffer.as_ptr() as *mut u32
+        self.draw_buffer().as_ptr() as *mut u32
     }
 
     /// Get back buffer as slice
     pub fn back_buffer_slice(&self) -> &[u32] {
-        &self.back_buffer
+        self.draw_buffer()
     }
 
     /// Put a pixel in the back buffer
@@ -123,9 +177,10 @@ impl VmsvgaDevice {
         if x < self.width as usize && y < self.height as usize {
             let offset = y * (self.pitch as usize / 4) + x;
             unsafe {
-                let ptr = self.back_buffer.as_ptr() as *mut u32;
+                let ptr = self.draw_buffer().as_ptr() as *mut u32;
                 *ptr.add(offset) = color;
             }
+            self.damage.add_pixel(x, y);
         }
     }
 
@@ -134,7 +189,7 @@ impl VmsvgaDevice {
     pub fn get_pixel(&self, x: usize, y: usize) -> u32 {
         if x < self.width as usize && y < self.height as usize {
             let offset = y * (self.pitch as usize / 4) + x;
-            self.back_buffer[offset]
+            self.draw_buffer()[offset]
         } else {
             0
         }
@@ -142,9 +197,10 @@ impl VmsvgaDevice {
 
     /// Clear the back buffer with a color
     pub fn clear(&self, color: u32) {
+        self.damage.add_rect(0, 0, self.width as usize, self.height as usize);
         let row_pixels = self.pitch as usize / 4;
         let total = row_pixels * self.height as usize;
-        let ptr = self.back_buffer.as_ptr() as *mut u64;
+        let ptr = self.draw_buffer().as_ptr() as *mut u64;
         let color64 = ((color as u64) << 32) | (color as u64);
 
         unsafe {
@@ -152,34 +208,166 @@ impl VmsvgaDevice {
                 *ptr.add(i) = color64;
             }
             if total % 2 == 1 {
-                let ptr32 = self.back_buffer.as_ptr() as *mut u32;
+                let ptr32 = self.draw_buffer().as_ptr() as *mut u32;
                 *ptr32.add(total - 1) = color;
             }
         }
     }
 
-    /// Present: copy back buffer to front buffer and trigger screen update
-    pub fn present(&self) {
-        let row_pixels = self.pitch as usize / 4;
-        let total = row_pixels * self.height as usize;
+    /// Present: queue the finished draw buffer and switch drawing to the next
+    ///
+    /// The queued frame is copied to the front buffer once the host has
+    /// processed the previous screen update; until then presents only rotate
+    /// buffers, so the renderer never waits on the copy. Only the dirty
+    /// regions are copied (everything when `region` is full).
+    pub fn present(&mut self, region: &DirtyRects) {
+        let finished = self.ring.draw();
+        self.pending.extend(region);
+        for (i, stale) in self.stale.iter_mut().enumerate() {
+            if i != finished {
+                stale.extend(region);
+            }
+        }
 
-        // Copy back buffer to front buffer
-        unsafe {
-            let src = self.back_buffer.as_ptr() as *const u64;
-            let dst = self.fb_virt as *mut u64;
+        // Bring the next draw buffer up to date with the frame just finished
+        let next = self.ring.submit();
+        let stale = core::mem::take(&mut self.stale[next]);
+        self.copy_region(self.buffers[finished].as_ptr(), self.buffers[next].as_ptr() as *mut u32, &stale);
 
-            for i in 0..(total / 2) {
-                *dst.add(i) = *src.add(i);
-            }
-            if total % 2 == 1 {
-                let src32 = self.back_buffer.as_ptr() as *const u32;
-                let dst32 = self.fb_virt as *mut u32;
-                *dst32.add(total - 1) = *src32.add(total - 1);
+        if self.fifo.fence_passed(self.fence) {
+            self.flip();
+        }
+    }
+
+    /// Present a single rectangle of the draw buffer (clipped to the screen)
+    ///
+    /// For callers that know exactly what changed; the copy to the front
+    /// buffer and the screen update cover just this rectangle.
+    pub fn present_region(&mut self, x: u32, y: u32, width: u32, height: u32) {
+        let rect = DirtyRect::new(x as usize, y as usize, width as usize, height as usize)
+            .clipped(self.width as usize, self.height as usize);
+        if rect.is_empty() {
+            return;
+        }
+        let mut region = DirtyRects::new();
+        region.add(rect);
+        self.present(&region);
+    }
+
+    /// Present what was drawn through this device since the last call
+    ///
+    /// Updates only the bounding box of the changes, or the full screen when
+    /// the box covers most of it; does nothing if nothing was drawn.
+    pub fn present_damage(&mut self) {
+        if let Some(region) = self.damage.take_region(self.width as usize, self.height as usize) {
+            self.present(&region);
+        }
+    }
+
+    /// Whether presents can be paced by fences (the FIFO has a usable fence
+    /// register to read them back from)
+    pub fn has_fence_sync(&self) -> bool {
+        self.initialized && self.fifo.has_fence_reg()
+    }
+
+    /// Check without blocking whether the last presented frame is on screen
+    ///
+    /// A frame still queued behind the previous update is flipped as soon as
+    /// that update's fence passes, so polling this keeps the queue moving.
+    pub fn frame_shown(&mut self) -> bool {
+        if !self.fifo.fence_passed(self.fence) {
+            return false;
+        }
+        if self.ring.queued().is_some() {
+            self.flip();
+            return false;
+        }
+        true
+    }
+
+    /// Copy the queued frame to the front buffer and update the screen
+    fn flip(&mut self) {
+        let Some(index) = self.ring.flip() else {
+            return;
+        };
+        let region = core::mem::take(&mut self.pending);
+        self.copy_region(self.buffers[index].as_ptr(), self.fb_virt as *mut u32, &region);
+
+        if region.is_full() {
+            self.fifo.cmd_update_full(self.width, self.height);
+        } else {
+            self.update_rects(region.rects());
+        }
+        self.fence = self.fence.wrapping_add(1);
+        self.fifo.cmd_fence(self.fence);
+    }
+
+    /// Copy `region` between two screen-sized buffers
+    fn copy_region(&self, src: *const u32, dst: *mut u32, region: &DirtyRects) {
+        let row_pixels = self.pitch as usize / 4;
+        if region.is_full() {
+            // Safety: both buffers hold pitch * height bytes
+            unsafe { core::ptr::copy_nonoverlapping(src, dst, row_pixels * self.height as usize) };
+            return;
+        }
+
+        for rect in region.rects() {
+            let rect = rect.clipped(self.width as usize, self.height as usize);
+            for y in rect.y..rect.y + rect.height {
+                let start = y * row_pixels + rect.x;
+                // Safety: the clipped rectangle lies inside both buffers
+                unsafe { core::ptr::copy_nonoverlapping(src.add(start), dst.add(start), rect.width) };
             }
         }
+    }
 
-        // Trigger screen update via FIFO
-        self.fifo.cmd_update_full(self.width, self.height);
+    /// Largest mode the host accepts (SVGA_REG_MAX_WIDTH/MAX_HEIGHT)
+    pub fn max_resolution(&self) -> (u32, u32) {
+        (
+            regs::read_reg(self.io_base, SvgaReg::MaxWidth),
+            regs::read_reg(self.io_base, SvgaReg::MaxHeight),
+        )
+    }
+
+    /// Switch the display to `width` x `height`
+    ///
+    /// The mode must fit within the host's maximum and the framebuffer
+    /// mapping; otherwise nothing changes and None is returned. On success
+    /// the draw buffers are reallocated for the new pitch and the next
+    /// present covers the whole screen. Returns the mode the host set.
+    pub fn set_mode(&mut self, width: u32, height: u32) -> Option<(usize, usize)> {
+        if !self.initialized {
+            return None;
+        }
+        let (max_width, max_height) = self.max_resolution();
+        if !mode_fits(width, height, max_width, max_height, self.fb_size) {
+            serial_println!(
+                "VMSVGA: Mode {}x{} rejected (max {}x{}, {} KiB VRAM mapped)",
+                width, height, max_width, max_height, self.fb_size / 1024
+            );
+            return None;
+        }
+
+        // Let the host finish drawing the old mode before it goes away
+        self.fifo.sync();
+        regs::write_reg(self.io_base, SvgaReg::Width, width);
+        regs::write_reg(self.io_base, SvgaReg::Height, height);
+        regs::write_reg(self.io_base, SvgaReg::BitsPerPixel, 32);
+
+        self.width = regs::read_reg(self.io_base, SvgaReg::Width);
+        self.height = regs::read_reg(self.io_base, SvgaReg::Height);
+        self.bpp = regs::read_reg(self.io_base, SvgaReg::BitsPerPixel);
+        self.pitch = regs::read_reg(self.io_base, SvgaReg::BytesPerLine);
+
+        let row_pixels = self.pitch as usize / 4;
+        self.buffers = core::array::from_fn(|_| alloc::vec![0u32; row_pixels * self.height as usize]);
+        self.ring = BufferRing::new();
+        self.stale = core::array::from_fn(|_| DirtyRects::new());
+        self.pending.set_full();
+        self.damage = DamageBox::new();
+
+        serial_println!("VMSVGA: Mode set to {}x{}x{}", self.width, self.height, self.bpp);
+        Some((self.width as usize, self.height as usize))
     }
 
     /// Trigger a screen update (call after writing to front buffer directly)
@@ -187,8 +375,19 @@ impl VmsvgaDevice {
         self.fifo.cmd_update_full(self.width, self.height);
     }
 
+    /// Trigger screen updates for just the given regions of the front buffer
+    pub fn update_rects(&self, rects: &[DirtyRect]) {
+        for rect in rects {
+            let rect = rect.clipped(self.width as usize, self.height as usize);
+            if !rect.is_empty() {
+                self.fifo.cmd_update(rect.x as u32, rect.y as u32, rect.width as u32, rect.height as u32);
+            }
+        }
+    }
+
     /// Fill a rectangle in the back buffer
     pub fn fill_rect(&self, x: usize, y: usize, w: usize, h: usize, color: u32) {
+        // put_pixel records the clipped area pixel by pixel
         for dy in 0..h {
             for dx in 0..w {
                 self.put_pixel(x + dx, y + dy, color);
@@ -196,17 +395,60 @@ impl VmsvgaDevice {
         }
     }
 
+    /// Whether the host can fill screen rectangles itself (cap::RECT_FILL)
+    pub fn has_rect_fill(&self) -> bool {
+        regs::has_capability(self.capabilities, regs::cap::RECT_FILL) && self.fifo.is_initialized()
+    }
+
+    /// Fill a rectangle, on the host when the device has RECT_FILL
+    ///
+    /// The accelerated path queues a FIFO fill of the screen itself and
+    /// returns true; the host runs it asynchronously, so sync the FIFO before
+    /// the CPU writes the same screen pixels. Otherwise (or if the FIFO is
+    /// full) the back buffer is filled in software and false is returned.
+    pub fn fill_rect_accelerated(&self, x: usize, y: usize, w: usize, h: usize, color: u32) -> bool {
+        let rect = DirtyRect::new(x, y, w, h).clipped(self.width as usize, self.height as usize);
+        if rect.is_empty() {
+            return self.has_rect_fill();
+        }
+        if self.has_rect_fill()
+            && self.fifo.cmd_fill(color, rect.x as u32, rect.y as u32, rect.width as u32, rect.height as u32)
+        {
+            return true;
+        }
+        self.fill_rect(rect.x, rect.y, rect.width, rect.height, color);
+        false
+    }
+
+    /// Whether the host can draw the mouse cursor itself (cap::CURSOR)
+    pub fn has_hw_cursor(&self) -> bool {
+        cursor::hardware_supported(self.capabilities) && self.fifo.is_initialized()
+    }
+
+    /// Upload a cursor image built by cursor::define_cursor_cmd
+    pub fn define_cursor(&self, cmd: &[u32]) -> bool {
+        self.fifo.write_cmd(cmd)
+    }
+
+    /// Show the hardware cursor with its hotspot at (x, y), or hide it
+    pub fn set_cursor(&self, visible: bool, x: i32, y: i32) {
+        regs::write_reg(self.io_base, SvgaReg::CursorId, cursor::CURSOR_ID);
+        regs::write_reg(self.io_base, SvgaReg::CursorX, x.max(0) as u32);
+        regs::write_reg(self.io_base, SvgaReg::CursorY, y.max(0) as u32);
+        regs::write_reg(self.io_base, SvgaReg::CursorOn, visible as u32);
+    }
+
     /// Get scanline pointer in the back buffer
     #[inline]
     pub unsafe fn scanline_ptr(&self, y: usize) -> *mut u32 {
-        (self.back_buffer.as_ptr() as *mut u32).add(y * (self.pitch as usize / 4))
+        (self.draw_buffer().as_ptr() as *mut u32).add(y * (self.pitch as usize / 4))
     }
 
     /// Get pixel at linear index from back buffer
     #[inline]
     pub fn pixel_at(&self, idx: usize) -> u32 {
-        if idx < self.back_buffer.len() {
-            self.back_buffer[idx]
+        if idx < self.draw_buffer().len() {
+            self.draw_buffer()[idx]
         } else {
             0
         }
@@ -215,11 +457,13 @@ impl VmsvgaDevice {
     /// Set pixel at linear index in back buffer
     #[inline]
     pub fn set_pixel_at(&self, idx: usize, color: u32) {
-        if idx < self.back_buffer.len() {
+        if idx < self.draw_buffer().len() {
             unsafe {
-                let ptr = self.back_buffer.as_ptr() as *mut u32;
+                let ptr = self.draw_buffer().as_ptr() as *mut u32;
                 *ptr.add(idx) = color;
             }
+            let row_pixels = self.pitch as usize / 4;
+            self.damage.add_pixel(idx % row_pixels, idx / row_pixels);
         }
     }
 
@@ -250,6 +494,22 @@ fn find_device() -> Option<PciDevice> {
     pci::find_device(VMWARE_VENDOR_ID, VMSVGA_DEVICE_ID)
 }
 
+/// Limit a register-reported region size to the size its BAR decodes
+/// (a probe result of 0 means the BAR could not be sized, so keep the register)
+fn clamp_to_bar(size: usize, bar_size: u64) -> usize {
+    if bar_size == 0 { size } else { size.min(bar_size as usize) }
+}
+
+/// Whether a 32-bit `width` x `height` mode is within the host's maximum
+/// and fits in `fb_size` bytes of framebuffer
+fn mode_fits(width: u32, height: u32, max_width: u32, max_height: u32, fb_size: usize) -> bool {
+    width > 0
+        && height > 0
+        && width <= max_width
+        && height <= max_height
+        && width as usize * height as usize * 4 <= fb_size
+}
+
 /// Initialize the VMSVGA driver with specified resolution
 /// Returns (width, height) on success
 pub fn init_with_resolution(target_width: u32, target_height: u32) -> Option<(usize, usize)> {
@@ -292,6 +552,10 @@ pub fn init_with_resolution(target_width: u32, target_height: u32) -> Option<(us
     let fifo_phys = regs::read_reg(io_base, SvgaReg::MemStart) as u64;
     let fifo_size = regs::read_reg(io_base, SvgaReg::MemSize) as usize;
 
+    // Never map past what the framebuffer (BAR1) and FIFO (BAR2) BARs decode
+    let fb_size = clamp_to_bar(fb_size, pci_dev.bar_size(1));
+    let fifo_size = clamp_to_bar(fifo_size, pci_dev.bar_size(2));
+
     // Verify the target resolution is within device limits
     let max_width = regs::read_reg(io_base, SvgaReg::MaxWidth);
     let max_height = regs::read_reg(io_base, SvgaReg::MaxHeight);
@@ -332,21 +596,24 @@ pub fn init_with_resolution(target_width: u32, target_height: u32) -> Option<(us
     let pitch = regs::read_reg(io_base, SvgaReg::BytesPerLine);
 
 
-    // Allocate back buffer
+    // Allocate the draw buffers
     let row_pixels = pitch as usize / 4;
-    let back_buffer = alloc::vec![0u32; row_pixels * height as usize];
+    let buffers = core::array::from_fn(|_| alloc::vec![0u32; row_pixels * height as usize]);
 
     // Initialize device state
     let mut device = VMSVGA_DEVICE.lock();
     device.io_base = io_base;
     device.fb_virt = fb_virt;
     device.fb_size = fb_size;
+    device.vram_size = vram_size;
     device.width = width;
     device.height = height;
     device.bpp = bpp;
     device.pitch = pitch;
     device.capabilities = capabilities;
-    device.back_buffer = back_buffer;
+    device.buffers = buffers;
+    device.ring = BufferRing::new();
+    device.pending.set_full();
 
     // Initialize FIFO
     device.fifo.init(fifo_virt, fifo_size, io_base, capabilities);
@@ -611,3 +878,69 @@ pub fn sync_3d() {
     let device = VMSVGA_DEVICE.lock();
     device.fifo.sync();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use fifo::fifo_reg;
+
+    /// 8x4 device drawing into memory, with its FIFO in `fifo_mem`
+    fn memory_device(capabilities: u32, fifo_mem: &mut [u32]) -> VmsvgaDevice {
+        let mut device = VmsvgaDevice::new();
+        device.width = 8;
+        device.height = 4;
+        device.bpp = 32;
+        device.pitch = 8 * 4;
+        device.capabilities = capabilities;
+        device.buffers = core::array::from_fn(|_| vec![0; 8 * 4]);
+        device.fifo.init(fifo_mem.as_mut_ptr() as u64, fifo_mem.len() * 4, 0, capabilities);
+        device
+    }
+
+    #[test]
+    fn test_mode_fits() {
+        let vram = 1920 * 1080 * 4;
+        assert!(mode_fits(1280, 720, 2560, 1600, vram));
+        assert!(mode_fits(1920, 1080, 1920, 1080, vram));
+        assert!(!mode_fits(2048, 768, 1920, 1200, 16 << 20));
+        assert!(!mode_fits(1280, 1600, 2560, 1200, 16 << 20));
+        assert!(!mode_fits(1920, 1200, 2560, 1600, vram));
+        assert!(!mode_fits(0, 720, 2560, 1600, vram));
+    }
+
+    #[test]
+    fn test_fill_rect_accelerated_emits_fifo_fill() {
+        let mut fifo_mem = [0u32; 64];
+        let device = memory_device(regs::cap::RECT_FILL, &mut fifo_mem);
+
+        // Clipped to the screen before it reaches the host
+        assert!(device.fill_rect_accelerated(2, 1, 10, 2, 0x00FF_8000));
+        assert!(device.back_buffer_slice().iter().all(|&p| p == 0));
+        assert_eq!(device.damage.bounds(), None);
+        drop(device);
+
+        let start = fifo_reg::NUM_REGS;
+        assert_eq!(fifo_mem[start..start + 6], [regs::cmd::RECT_FILL, 0x00FF_8000, 2, 1, 6, 2]);
+        assert_eq!(fifo_mem[fifo_reg::NEXT_CMD], (start as u32 + 6) * 4);
+    }
+
+    #[test]
+    fn test_fill_rect_accelerated_falls_back_to_software() {
+        let mut fifo_mem = [0u32; 64];
+        let device = memory_device(regs::cap::RECT_COPY, &mut fifo_mem);
+
+        assert!(!device.fill_rect_accelerated(2, 1, 10, 2, 0x00FF_8000));
+        for y in 0..4 {
+            for x in 0..8 {
+                let inside = x >= 2 && (1..3).contains(&y);
+                assert_eq!(device.get_pixel(x, y), if inside { 0x00FF_8000 } else { 0 });
+            }
+        }
+        assert_eq!(device.damage.bounds(), Some(DirtyRect::new(2, 1, 6, 2)));
+        drop(device);
+
+        // Nothing was queued for the host
+        assert_eq!(fifo_mem[fifo_reg::NEXT_CMD], fifo_reg::NUM_REGS as u32 * 4);
+    }
+}
@@ -68,6 +68,14 @@ pub enum SvgaReg {
     Busy = 22,
     /// SVGA_REG_GUEST_ID - Guest OS identification
     GuestId = 23,
+    /// SVGA_REG_CURSOR_ID - ID of the cursor the position registers move
+    CursorId = 24,
+    /// SVGA_REG_CURSOR_X - Cursor hotspot X position
+    CursorX = 25,
+    /// SVGA_REG_CURSOR_Y - Cursor hotspot Y position
+    CursorY = 26,
+    /// SVGA_REG_CURSOR_ON - Show (1) or hide (0) the cursor
+    CursorOn = 27,
     /// SVGA_REG_SCRATCH_SIZE - Size of scratch registers
     ScratchSize = 29,
     /// SVGA_REG_MEM_REGS - Number of FIFO registers
@@ -80,6 +88,8 @@ pub enum SvgaReg {
     GmrMaxIds = 34,
     /// SVGA_REG_GMR_MAX_DESCRIPTOR_LENGTH - Maximum GMR descriptor length
     GmrMaxDescriptorLength = 35,
+    /// SVGA_REG_DEV_CAP - Write an SVGA3D devcap index, read back its value
+    DevCap = 52,
 }
 
 /// GMR (Guest Memory Region) related constants
@@ -103,6 +113,8 @@ pub const SVGA_MAGIC: u32 = 0x90000000;
 pub mod cap {
     /// No capabilities
     pub const NONE: u32 = 0x00000000;
+    /// SVGA_CAP_RECT_FILL - Rectangle fill acceleration
+    pub const RECT_FILL: u32 = 0x00000001;
     /// SVGA_CAP_RECT_COPY - Rectangle copy acceleration
     pub const RECT_COPY: u32 = 0x00000002;
     /// SVGA_CAP_CURSOR - Hardware cursor
@@ -135,6 +147,16 @@ pub mod cap {
     pub const GMR2: u32 = 0x00400000;
     /// SVGA_CAP_SCREEN_OBJECT_2 - Screen object 2
     pub const SCREEN_OBJECT_2: u32 = 0x00800000;
+    /// SVGA_CAP_GBOBJECTS - Guest-backed objects (also gates SVGA_REG_DEV_CAP)
+    pub const GBOBJECTS: u32 = 0x08000000;
+}
+
+/// SVGA3D device capability indices (read through SvgaReg::DevCap)
+pub mod devcap {
+    /// SVGA3D_DEVCAP_MAX_TEXTURE_WIDTH - Widest texture in texels
+    pub const MAX_TEXTURE_WIDTH: u32 = 19;
+    /// SVGA3D_DEVCAP_MAX_TEXTURE_HEIGHT - Tallest texture in texels
+    pub const MAX_TEXTURE_HEIGHT: u32 = 20;
 }
 
 /// FIFO commands
@@ -143,6 +165,8 @@ pub mod cmd {
     pub const INVALID: u32 = 0;
     /// SVGA_CMD_UPDATE - Update screen region
     pub const UPDATE: u32 = 1;
+    /// SVGA_CMD_RECT_FILL - Fill a screen rectangle with a solid color
+    pub const RECT_FILL: u32 = 2;
     /// SVGA_CMD_RECT_COPY - Copy rectangle
     pub const RECT_COPY: u32 = 3;
     /// SVGA_CMD_DEFINE_CURSOR - Define cursor
@@ -182,9 +206,9 @@ pub mod cmd {
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Svga3dTransferType {
     /// Transfer from guest memory to surface (upload)
-    SurfaceDma = 0,
+    WriteHostVram = 1,
     /// Transfer from surface to guest memory (download)
-    HostToGuest = 1,
+    ReadHostVram = 2,
 }
 
 /// Read a SVGA register
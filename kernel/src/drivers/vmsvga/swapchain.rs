@@ -0,0 +1,128 @@
+//! Triple-buffer rotation for the VMSVGA present path
+//!
+//! Three buffers take turns in three roles: the one being drawn, a finished
+//! frame waiting to be copied to VRAM, and the frame last copied while the
+//! host is still scanning it out. The renderer always has a buffer of its own,
+//! so presenting never waits for the host; if it finishes a frame while an
+//! older one is still queued, the older one is dropped and its buffer reused.
+
+/// Number of buffers in the ring
+pub const BUFFER_COUNT: usize = 3;
+
+/// Which buffer holds which role
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BufferRing {
+    draw: usize,
+    queued: Option<usize>,
+    scanout: Option<usize>,
+}
+
+impl BufferRing {
+    pub const fn new() -> Self {
+        Self {
+            draw: 0,
+            queued: None,
+            scanout: None,
+        }
+    }
+
+    /// Buffer the renderer draws into
+    pub fn draw(&self) -> usize {
+        self.draw
+    }
+
+    /// Finished frame waiting to be presented
+    pub fn queued(&self) -> Option<usize> {
+        self.queued
+    }
+
+    /// Frame most recently handed to the display
+    pub fn scanout(&self) -> Option<usize> {
+        self.scanout
+    }
+
+    /// Queue the finished draw buffer and return the buffer to draw next
+    ///
+    /// A frame that was still queued is superseded and its buffer reused.
+    pub fn submit(&mut self) -> usize {
+        let finished = self.draw;
+        self.draw = match self.queued {
+            Some(dropped) => dropped,
+            None => (0..BUFFER_COUNT)
+                .find(|&i| i != finished && Some(i) != self.scanout)
+                .unwrap_or(finished),
+        };
+        self.queued = Some(finished);
+        self.draw
+    }
+
+    /// Move the queued frame to scanout, returning its buffer
+    pub fn flip(&mut self) -> Option<usize> {
+        let next = self.queued.take()?;
+        self.scanout = Some(next);
+        Some(next)
+    }
+}
+
+impl Default for BufferRing {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_roles_distinct(ring: &BufferRing) {
+        assert_ne!(Some(ring.draw()), ring.queued());
+        assert_ne!(Some(ring.draw()), ring.scanout());
+        if ring.queued().is_some() {
+            assert_ne!(ring.queued(), ring.scanout());
+        }
+    }
+
+    #[test]
+    fn test_rotation_never_draws_into_presented_buffer() {
+        let mut ring = BufferRing::new();
+        // Host keeps up: every frame flips straight away and all three buffers get used
+        let mut seen = [false; BUFFER_COUNT];
+        for _ in 0..6 {
+            seen[ring.draw()] = true;
+            ring.submit();
+            assert_roles_distinct(&ring);
+            ring.flip();
+            assert_roles_distinct(&ring);
+        }
+        assert!(seen.iter().all(|&s| s));
+
+        // Host busy: the pattern of flips decides which frames are dropped
+        for step in 0..64u32 {
+            let before = ring;
+            let next = ring.submit();
+            assert_eq!(next, ring.draw());
+            assert_eq!(ring.queued(), Some(before.draw()));
+            assert_roles_distinct(&ring);
+            if step % 3 == 0 {
+                assert_eq!(ring.flip(), Some(before.draw()));
+                assert_roles_distinct(&ring);
+            }
+        }
+    }
+
+    #[test]
+    fn test_superseded_frame_is_reused() {
+        let mut ring = BufferRing::new();
+        ring.submit();
+        ring.flip();
+        let dropped = ring.draw();
+        ring.submit();
+        let latest = ring.draw();
+
+        // Host still busy: the next submit drops the waiting frame
+        ring.submit();
+        assert_eq!(ring.draw(), dropped);
+        assert_eq!(ring.flip(), Some(latest));
+        assert_eq!(ring.flip(), None);
+    }
+}
@@ -0,0 +1,179 @@
+//! Text-safe encodings for dumping binary data over the serial port
+//!
+//! Serial captures are line-oriented text, so replays and screenshots are
+//! streamed as fixed-width base64 lines, with a CRC32 the host side can use
+//! to check the capture arrived intact.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+/// Base64 characters per serial line
+pub const BASE64_LINE_LEN: usize = 76;
+
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Base64 encoder that emits fixed-width lines
+pub struct Base64Lines<F: FnMut(&str)> {
+    emit: F,
+    pending: [u8; 3],
+    pending_len: usize,
+    line: [u8; BASE64_LINE_LEN],
+    line_len: usize,
+}
+
+impl<F: FnMut(&str)> Base64Lines<F> {
+    pub fn new(emit: F) -> Self {
+        Self {
+            emit,
+            pending: [0; 3],
+            pending_len: 0,
+            line: [0; BASE64_LINE_LEN],
+            line_len: 0,
+        }
+    }
+
+    pub fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.pending[self.pending_len] = b;
+            self.pending_len += 1;
+            if self.pending_len == 3 {
+                self.flush_group();
+            }
+        }
+    }
+
+    /// Encode the buffered 1-3 bytes as four characters (with '=' padding)
+    fn flush_group(&mut self) {
+        let [a, b, c] = self.pending;
+        let n = self.pending_len;
+        let chars = [
+            BASE64_ALPHABET[(a >> 2) as usize],
+            BASE64_ALPHABET[(((a & 0x03) << 4) | (b >> 4)) as usize],
+            if n > 1 { BASE64_ALPHABET[(((b & 0x0F) << 2) | (c >> 6)) as usize] } else { b'=' },
+            if n > 2 { BASE64_ALPHABET[(c & 0x3F) as usize] } else { b'=' },
+        ];
+        self.pending = [0; 3];
+        self.pending_len = 0;
+
+        for ch in chars {
+            self.line[self.line_len] = ch;
+            self.line_len += 1;
+            if self.line_len == BASE64_LINE_LEN {
+                self.flush_line();
+            }
+        }
+    }
+
+    fn flush_line(&mut self) {
+        if self.line_len > 0 {
+            // The alphabet is ASCII
+            (self.emit)(core::str::from_utf8(&self.line[..self.line_len]).unwrap_or(""));
+            self.line_len = 0;
+        }
+    }
+
+    /// Pad out the last group and emit the final partial line
+    pub fn finish(mut self) {
+        if self.pending_len > 0 {
+            self.flush_group();
+        }
+        self.flush_line();
+    }
+}
+
+/// Decode base64, skipping whitespace; None on any other invalid character
+pub fn base64_decode(text: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(text.len() / 4 * 3);
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for &ch in text {
+        let value = match ch {
+            b'A'..=b'Z' => ch - b'A',
+            b'a'..=b'z' => ch - b'a' + 26,
+            b'0'..=b'9' => ch - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' | b' ' | b'\t' | b'\r' | b'\n' => continue,
+            _ => return None,
+        };
+        acc = (acc << 6) | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    Some(out)
+}
+
+/// Incremental CRC-32 (IEEE 802.3, as used by zlib and Python's `zlib.crc32`)
+///
+/// Bitwise rather than table-driven: it only runs over debug dumps, and this
+/// keeps a 1 KiB table out of the kernel image.
+#[derive(Debug, Clone, Copy)]
+pub struct Crc32(u32);
+
+impl Crc32 {
+    /// Reflected form of the 0x04C11DB7 polynomial
+    const POLY: u32 = 0xEDB8_8320;
+
+    pub const fn new() -> Self {
+        Self(0xFFFF_FFFF)
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 ^= b as u32;
+            for _ in 0..8 {
+                let mask = (self.0 & 1).wrapping_neg();
+                self.0 = (self.0 >> 1) ^ (Self::POLY & mask);
+            }
+        }
+    }
+
+    pub const fn finish(self) -> u32 {
+        !self.0
+    }
+}
+
+impl Default for Crc32 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::String;
+
+    #[test]
+    fn test_base64_roundtrip() {
+        for len in 0..10usize {
+            let data: Vec<u8> = (0..len as u8).map(|i| i.wrapping_mul(37)).collect();
+            let mut text = String::new();
+            let mut encoder = Base64Lines::new(|line: &str| text.push_str(line));
+            encoder.write(&data);
+            encoder.finish();
+            assert_eq!(text.len(), len.div_ceil(3) * 4);
+            assert_eq!(base64_decode(text.as_bytes()), Some(data));
+        }
+        assert_eq!(base64_decode(b"TWFu"), Some(b"Man".to_vec()));
+        assert_eq!(base64_decode(b"TW*u"), None);
+    }
+
+    #[test]
+    fn test_crc32_check_value() {
+        let mut crc = Crc32::new();
+        crc.update(b"123456789");
+        assert_eq!(crc.finish(), 0xCBF4_3926);
+        assert_eq!(Crc32::new().finish(), 0);
+
+        // Feeding in pieces gives the same result
+        let mut split = Crc32::new();
+        split.update(b"1234");
+        split.update(b"56789");
+        assert_eq!(split.finish(), 0xCBF4_3926);
+    }
+}
@@ -0,0 +1,243 @@
+//! Positional sound cues on the PC speaker
+//!
+//! The speaker plays one square wave at a time, so gameplay queues short
+//! `SoundEvent`s and `update` (once per frame) plays the most important
+//! one, ending it after its duration without ever waiting on it. Where a
+//! sound came from is folded into its pitch, Doppler style: up to 10%
+//! higher straight ahead of the player and 10% lower straight behind.
+
+use crate::drivers::pit;
+use core::f32::consts::{PI, TAU};
+use glam::Vec3;
+use spin::Mutex;
+
+/// Sounds waiting to be played
+pub const SOUND_QUEUE_LEN: usize = 8;
+
+/// Pitch change of a sound straight ahead (raised) or behind (lowered)
+const DIRECTION_SHIFT: f32 = 0.1;
+
+/// Farthest away another player's gunshot is heard
+pub const HEARING_RANGE: f32 = 80.0;
+
+/// A short tone to play
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SoundEvent {
+    pub frequency_hz: u16,
+    /// Frames the tone lasts
+    pub duration_ticks: u8,
+    /// Radians from where the listener faces to the sound (0 ahead, +-PI behind)
+    pub direction_angle: f32,
+    /// Higher plays first and cuts off a lower one already playing
+    pub priority: u8,
+}
+
+impl SoundEvent {
+    pub const fn gunshot(direction_angle: f32) -> Self {
+        Self { frequency_hz: 800, duration_ticks: 2, direction_angle, priority: 1 }
+    }
+
+    pub const fn bullet_hit(direction_angle: f32) -> Self {
+        Self { frequency_hz: 1200, duration_ticks: 1, direction_angle, priority: 2 }
+    }
+
+    pub const fn chest_opened(direction_angle: f32) -> Self {
+        Self { frequency_hz: 440, duration_ticks: 4, direction_angle, priority: 0 }
+    }
+
+    /// Always the listener's own damage, so always straight ahead
+    pub const fn storm_damage() -> Self {
+        Self { frequency_hz: 200, duration_ticks: 3, direction_angle: 0.0, priority: 3 }
+    }
+
+    /// Frequency the speaker plays, shifted by the sound's direction
+    pub fn played_frequency(&self) -> u32 {
+        let shift = 1.0 + DIRECTION_SHIFT * libm::cosf(self.direction_angle);
+        libm::roundf(self.frequency_hz as f32 * shift) as u32
+    }
+}
+
+/// Angle from a listener at `listener` facing `yaw` to a sound at `source`
+/// (0 ahead, positive towards +X at yaw 0, wrapped to -PI..=PI)
+pub fn direction_to(listener: Vec3, yaw: f32, source: Vec3) -> f32 {
+    let offset = source - listener;
+    if offset.x == 0.0 && offset.z == 0.0 {
+        return 0.0;
+    }
+    // Facing is (sin yaw, cos yaw) in XZ, see Player::look_direction
+    let angle = libm::atan2f(offset.x, offset.z) - yaw;
+    let wrapped = libm::fmodf(angle + PI, TAU);
+    if wrapped < 0.0 { wrapped + PI } else { wrapped - PI }
+}
+
+/// Ring of queued sounds
+pub struct SoundQueue {
+    slots: [Option<SoundEvent>; SOUND_QUEUE_LEN],
+    /// Slot the next sound goes in
+    next: usize,
+}
+
+impl SoundQueue {
+    pub const fn new() -> Self {
+        Self { slots: [None; SOUND_QUEUE_LEN], next: 0 }
+    }
+
+    /// Queue a sound in the next free slot, or over the oldest one when full
+    pub fn push(&mut self, event: SoundEvent) {
+        let slot = (0..SOUND_QUEUE_LEN)
+            .map(|i| (self.next + i) % SOUND_QUEUE_LEN)
+            .find(|&i| self.slots[i].is_none())
+            .unwrap_or(self.next);
+        self.slots[slot] = Some(event);
+        self.next = (slot + 1) % SOUND_QUEUE_LEN;
+    }
+
+    /// Remove the highest-priority sound that beats `playing` (oldest first
+    /// among equals)
+    fn take_above(&mut self, playing: Option<u8>) -> Option<SoundEvent> {
+        let mut best: Option<usize> = None;
+        for i in (0..SOUND_QUEUE_LEN).map(|i| (self.next + i) % SOUND_QUEUE_LEN) {
+            let Some(event) = self.slots[i] else {
+                continue;
+            };
+            let beats_best = best.and_then(|b| self.slots[b]).is_none_or(|b| event.priority > b.priority);
+            if beats_best && playing.is_none_or(|p| event.priority > p) {
+                best = Some(i);
+            }
+        }
+        best.and_then(|i| self.slots[i].take())
+    }
+}
+
+impl Default for SoundQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// What the speaker has to do this frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SpeakerChange {
+    Start(u32),
+    Stop,
+}
+
+/// The tone playing and the frames it has left
+struct Speaker {
+    playing: Option<SoundEvent>,
+    remaining: u8,
+}
+
+impl Speaker {
+    const fn new() -> Self {
+        Self { playing: None, remaining: 0 }
+    }
+
+    /// Advance one frame: end the tone when its time is up, then start the
+    /// most important queued sound if it beats whatever is still playing
+    fn step(&mut self, queue: &mut SoundQueue) -> Option<SpeakerChange> {
+        let mut stopped = false;
+        if self.playing.is_some() {
+            self.remaining = self.remaining.saturating_sub(1);
+            if self.remaining == 0 {
+                self.playing = None;
+                stopped = true;
+            }
+        }
+
+        if let Some(next) = queue.take_above(self.playing.map(|s| s.priority)) {
+            self.playing = Some(next);
+            self.remaining = next.duration_ticks.max(1);
+            return Some(SpeakerChange::Start(next.played_frequency()));
+        }
+        stopped.then_some(SpeakerChange::Stop)
+    }
+}
+
+/// Sounds waiting for the speaker
+pub static SOUND_QUEUE: Mutex<SoundQueue> = Mutex::new(SoundQueue::new());
+
+static SPEAKER: Mutex<Speaker> = Mutex::new(Speaker::new());
+
+/// Queue a sound
+pub fn play(event: SoundEvent) {
+    SOUND_QUEUE.lock().push(event);
+}
+
+/// Run the speaker for one frame (call once per frame, in every state, so
+/// tones also end after leaving a match)
+pub fn update() {
+    let change = SPEAKER.lock().step(&mut SOUND_QUEUE.lock());
+    match change {
+        Some(SpeakerChange::Start(hz)) => pit::start_tone(hz),
+        Some(SpeakerChange::Stop) => pit::stop_tone(),
+        None => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queued(queue: &SoundQueue) -> usize {
+        queue.slots.iter().flatten().count()
+    }
+
+    #[test]
+    fn test_direction_shifts_pitch() {
+        assert_eq!(SoundEvent::gunshot(0.0).played_frequency(), 880);
+        assert_eq!(SoundEvent::gunshot(PI).played_frequency(), 720);
+        assert_eq!(SoundEvent::gunshot(PI / 2.0).played_frequency(), 800);
+        assert_eq!(SoundEvent::gunshot(-PI / 2.0).played_frequency(), 800);
+    }
+
+    #[test]
+    fn test_direction_to() {
+        let close = |a: f32, b: f32| (a - b).abs() < 1e-4;
+        // Facing +Z at yaw 0
+        assert!(close(direction_to(Vec3::ZERO, 0.0, Vec3::new(0.0, 5.0, 10.0)), 0.0));
+        assert!(close(direction_to(Vec3::ZERO, 0.0, Vec3::new(10.0, 0.0, 0.0)), PI / 2.0));
+        assert!(close(direction_to(Vec3::ZERO, 0.0, Vec3::new(0.0, 0.0, -10.0)).abs(), PI));
+        // Turned to face +X, the same sound is ahead; wraps past -PI
+        assert!(close(direction_to(Vec3::ZERO, PI / 2.0, Vec3::new(10.0, 0.0, 0.0)), 0.0));
+        assert!(close(direction_to(Vec3::ZERO, PI * 0.75, Vec3::new(-10.0, 0.0, 0.0)), PI * 0.75));
+        assert_eq!(direction_to(Vec3::ONE, 1.0, Vec3::ONE), 0.0);
+    }
+
+    #[test]
+    fn test_speaker_plays_by_priority_and_ends_sounds() {
+        let mut queue = SoundQueue::new();
+        let mut speaker = Speaker::new();
+        assert_eq!(speaker.step(&mut queue), None);
+
+        queue.push(SoundEvent::chest_opened(0.0));
+        queue.push(SoundEvent::gunshot(PI / 2.0));
+        // Gunshot first, for its 2 frames
+        assert_eq!(speaker.step(&mut queue), Some(SpeakerChange::Start(800)));
+        // A hit cuts it off
+        queue.push(SoundEvent::bullet_hit(PI / 2.0));
+        assert_eq!(speaker.step(&mut queue), Some(SpeakerChange::Start(1200)));
+        // Then the chest once the hit's frame is over, for 4 frames
+        assert_eq!(speaker.step(&mut queue), Some(SpeakerChange::Start(484)));
+        assert_eq!(queued(&queue), 0);
+        assert_eq!(speaker.step(&mut queue), None);
+        assert_eq!(speaker.step(&mut queue), None);
+        assert_eq!(speaker.step(&mut queue), None);
+        assert_eq!(speaker.step(&mut queue), Some(SpeakerChange::Stop));
+        assert_eq!(speaker.step(&mut queue), None);
+    }
+
+    #[test]
+    fn test_queue_overwrites_oldest_when_full() {
+        let mut queue = SoundQueue::new();
+        for _ in 0..SOUND_QUEUE_LEN {
+            queue.push(SoundEvent::chest_opened(0.0));
+        }
+        queue.push(SoundEvent::storm_damage());
+        assert_eq!(queued(&queue), SOUND_QUEUE_LEN);
+        assert_eq!(queue.take_above(None), Some(SoundEvent::storm_damage()));
+        // Nothing left beats the storm
+        assert_eq!(queue.take_above(Some(3)), None);
+        assert_eq!(queued(&queue), SOUND_QUEUE_LEN - 1);
+    }
+}
@@ -87,6 +87,16 @@ impl BuildPiece {
         }
     }
 
+    /// Whether the piece overlaps an axis-aligned box, using the axis-aligned
+    /// bounds of the rotated piece (its position is the centre)
+    pub fn overlaps_aabb(&self, min: Vec3, max: Vec3) -> bool {
+        let half = self.dimensions() * 0.5;
+        let (cos_r, sin_r) = (libm::cosf(self.rotation).abs(), libm::sinf(self.rotation).abs());
+        let extent = Vec3::new(cos_r * half.x + sin_r * half.z, half.y, sin_r * half.x + cos_r * half.z);
+        let (piece_min, piece_max) = (self.position - extent, self.position + extent);
+        piece_min.cmplt(max).all() && piece_max.cmpgt(min).all()
+    }
+
     /// Get material cost for this piece type
     pub fn material_cost(&self) -> u32 {
         match self.build_type {
@@ -0,0 +1,90 @@
+//! Client-side chat log
+//!
+//! Holds the most recent chat/announcement lines received from the server.
+//! Lines expire after a fixed TTL and fade out during their last second.
+
+use alloc::string::String;
+use protocol::packets::SERVER_SENDER_ID;
+use spin::Mutex;
+
+/// Lines kept on screen at once
+pub const MAX_CHAT_LINES: usize = 5;
+
+/// Seconds a line stays visible
+pub const CHAT_TTL: f32 = 8.0;
+
+/// Seconds of fade-out at the end of a line's TTL
+pub const CHAT_FADE_TIME: f32 = 1.0;
+
+/// One received chat line
+#[derive(Debug, Clone)]
+pub struct ChatLine {
+    pub sender_id: u8,
+    pub text: String,
+    /// Seconds left before the line disappears
+    pub ttl: f32,
+}
+
+impl ChatLine {
+    /// Server announcement rather than a player message
+    pub fn is_server(&self) -> bool {
+        self.sender_id == SERVER_SENDER_ID
+    }
+
+    /// Opacity: 1.0 until the last CHAT_FADE_TIME seconds, then fades to 0.0
+    pub fn alpha(&self) -> f32 {
+        (self.ttl / CHAT_FADE_TIME).clamp(0.0, 1.0)
+    }
+}
+
+/// Ring buffer of recent lines (newest overwrites oldest)
+pub struct ChatLog {
+    lines: [Option<ChatLine>; MAX_CHAT_LINES],
+    /// Slot the next line is written to
+    head: usize,
+}
+
+impl ChatLog {
+    pub const fn new() -> Self {
+        Self {
+            lines: [const { None }; MAX_CHAT_LINES],
+            head: 0,
+        }
+    }
+
+    /// Add a received line
+    pub fn push(&mut self, sender_id: u8, text: &str) {
+        self.lines[self.head] = Some(ChatLine {
+            sender_id,
+            text: String::from(text),
+            ttl: CHAT_TTL,
+        });
+        self.head = (self.head + 1) % MAX_CHAT_LINES;
+    }
+
+    /// Count down TTLs and drop expired lines
+    pub fn update(&mut self, dt: f32) {
+        for slot in self.lines.iter_mut() {
+            if let Some(line) = slot {
+                line.ttl -= dt;
+                if line.ttl <= 0.0 {
+                    *slot = None;
+                }
+            }
+        }
+    }
+
+    /// Live lines, oldest first
+    pub fn lines(&self) -> impl Iterator<Item = &ChatLine> {
+        (0..MAX_CHAT_LINES).filter_map(move |i| self.lines[(self.head + i) % MAX_CHAT_LINES].as_ref())
+    }
+}
+
+impl Default for ChatLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Global chat log (filled by the network handler, drawn by the HUD)
+pub static CHAT_LOG: Mutex<ChatLog> = Mutex::new(ChatLog::new());
@@ -1,6 +1,8 @@
 //! Combat system with hitscan and damage calculation
 
+use core::sync::atomic::{AtomicUsize, Ordering};
 use glam::Vec3;
+use spin::Mutex;
 use super::weapon::{Weapon, WeaponType};
 use super::player::Player;
 
@@ -40,8 +42,6 @@ pub const FALLOFF_MIN_MULT: f32 = 0.7;
 pub struct CombatManager {
     /// Recent hits for visual feedback
     pub hit_markers: [Option<HitMarker>; 8],
-    /// Recent damage numbers
-    pub damage_numbers: [Option<DamageNumber>; 16],
     /// Kill feed entries
     pub kill_feed: [Option<KillFeedEntry>; 6],
 }
@@ -53,14 +53,120 @@ pub struct HitMarker {
     pub headshot: bool,
 }
 
-/// Floating damage number
+/// Floating damage numbers
+pub const MAX_DAMAGE_NUMBERS: usize = 16;
+/// Seconds a damage number stays on screen
+pub const DAMAGE_NUMBER_LIFETIME: f32 = 1.5;
+/// Upward drift in world units per second
+pub const DAMAGE_NUMBER_RISE_SPEED: f32 = 0.5;
+/// Body hit color (white)
+pub const DAMAGE_COLOR_NORMAL: u32 = 0x00FFFFFF;
+/// Headshot color (yellow)
+pub const DAMAGE_COLOR_CRITICAL: u32 = 0x00FFFF00;
+
+/// Floating damage number above a hit target
 #[derive(Debug, Clone, Copy)]
 pub struct DamageNumber {
     pub position: Vec3,
-    pub damage: u8,
-    pub headshot: bool,
-    pub timer: f32,
-    pub velocity_y: f32,
+    pub value: u16,
+    /// Seconds since the hit
+    pub age: f32,
+    pub color: u32,
+    /// Headshot (drawn larger)
+    pub critical: bool,
+}
+
+impl DamageNumber {
+    /// Opacity from 1.0 (new) to 0.0 (expired)
+    pub fn alpha(&self) -> f32 {
+        (1.0 - self.age / DAMAGE_NUMBER_LIFETIME).clamp(0.0, 1.0)
+    }
+}
+
+/// Active damage numbers (ring buffer, newest overwrites oldest)
+pub static DAMAGE_NUMBERS: Mutex<[Option<DamageNumber>; MAX_DAMAGE_NUMBERS]> =
+    Mutex::new([None; MAX_DAMAGE_NUMBERS]);
+
+/// Next ring buffer slot to write
+static DAMAGE_NUMBER_HEAD: AtomicUsize = AtomicUsize::new(0);
+
+/// Show a damage number at a world position
+pub fn push_damage_number(position: Vec3, value: u16, critical: bool) {
+    let color = if critical { DAMAGE_COLOR_CRITICAL } else { DAMAGE_COLOR_NORMAL };
+    let mut numbers = DAMAGE_NUMBERS.lock();
+    let slot = DAMAGE_NUMBER_HEAD.fetch_add(1, Ordering::Relaxed) % MAX_DAMAGE_NUMBERS;
+    numbers[slot] = Some(DamageNumber {
+        position,
+        value,
+        age: 0.0,
+        color,
+        critical,
+    });
+}
+
+/// Age damage numbers, drifting them upward and expiring old ones
+pub fn update_damage_numbers(dt: f32) {
+    let mut numbers = DAMAGE_NUMBERS.lock();
+    for number in numbers.iter_mut() {
+        if let Some(n) = number {
+            n.age += dt;
+            n.position.y += DAMAGE_NUMBER_RISE_SPEED * dt;
+            if n.age >= DAMAGE_NUMBER_LIFETIME {
+                *number = None;
+            }
+        }
+    }
+}
+
+/// Damage direction indicators tracked at once
+pub const MAX_HIT_INDICATORS: usize = 4;
+/// Seconds a hit indicator takes to fade out
+pub const HIT_INDICATOR_LIFETIME: f32 = 2.0;
+
+/// Direction of incoming damage, shown as an arc around the crosshair
+#[derive(Debug, Clone, Copy)]
+pub struct HitIndicator {
+    /// Horizontal world-space direction from the local player to the attacker
+    /// (same convention as Player::yaw: 0 = +Z, PI/2 = +X)
+    pub angle_radians: f32,
+    /// 1.0 when hit, decays to 0.0 over HIT_INDICATOR_LIFETIME
+    pub intensity: f32,
+    /// Seconds since the hit
+    pub age: f32,
+}
+
+/// Active hit indicators (ring buffer, newest overwrites oldest)
+pub static HIT_INDICATORS: Mutex<[Option<HitIndicator>; MAX_HIT_INDICATORS]> =
+    Mutex::new([None; MAX_HIT_INDICATORS]);
+
+/// Next ring buffer slot to write
+static HIT_INDICATOR_HEAD: AtomicUsize = AtomicUsize::new(0);
+
+/// Show where damage to the local player came from
+pub fn push_hit_indicator(victim_position: Vec3, attacker_position: Vec3) {
+    let to_attacker = attacker_position - victim_position;
+    let angle_radians = libm::atan2f(to_attacker.x, to_attacker.z);
+    let mut indicators = HIT_INDICATORS.lock();
+    let slot = HIT_INDICATOR_HEAD.fetch_add(1, Ordering::Relaxed) % MAX_HIT_INDICATORS;
+    indicators[slot] = Some(HitIndicator {
+        angle_radians,
+        intensity: 1.0,
+        age: 0.0,
+    });
+}
+
+/// Age hit indicators, fading and expiring old ones
+pub fn update_hit_indicators(dt: f32) {
+    let mut indicators = HIT_INDICATORS.lock();
+    for indicator in indicators.iter_mut() {
+        if let Some(h) = indicator {
+            h.age += dt;
+            h.intensity = (1.0 - h.age / HIT_INDICATOR_LIFETIME).max(0.0);
+            if h.age >= HIT_INDICATOR_LIFETIME {
+                *indicator = None;
+            }
+        }
+    }
 }
 
 /// Kill feed entry
@@ -83,7 +189,6 @@ impl CombatManager {
     pub fn new() -> Self {
         Self {
             hit_markers: [None; 8],
-            damage_numbers: [None; 16],
             kill_feed: [None; 6],
         }
     }
@@ -100,18 +205,6 @@ impl CombatManager {
             }
         }
 
-        // Update damage numbers
-        for number in &mut self.damage_numbers {
-            if let Some(n) = number {
-                n.timer -= dt;
-                n.position.y += n.velocity_y * dt;
-                n.velocity_y -= 9.8 * dt; // Gravity
-                if n.timer <= 0.0 {
-                    *number = None;
-                }
-            }
-        }
-
         // Update kill feed
         for entry in &mut self.kill_feed {
             if let Some(e) = entry {
@@ -141,30 +234,6 @@ impl CombatManager {
         });
     }
 
-    /// Add a damage number
-    pub fn add_damage_number(&mut self, position: Vec3, damage: u8, headshot: bool) {
-        for number in &mut self.damage_numbers {
-            if number.is_none() {
-                *number = Some(DamageNumber {
-                    position,
-                    damage,
-                    headshot,
-                    timer: 1.5,
-                    velocity_y: 3.0,
-                });
-                return;
-            }
-        }
-        // Replace oldest if full
-        self.damage_numbers[0] = Some(DamageNumber {
-            position,
-            damage,
-            headshot,
-            timer: 1.5,
-            velocity_y: 3.0,
-        });
-    }
-
     /// Add a kill feed entry
     pub fn add_kill(&mut self, killer_id: u8, victim_id: u8, weapon_type: WeaponType, headshot: bool) {
         // Shift entries down
@@ -243,16 +312,18 @@ pub fn hitscan(
 /// Returns distance and whether it was a headshot
 fn ray_player_intersection(origin: Vec3, direction: Vec3, player: &Player) -> Option<(f32, bool)> {
     let player_pos = player.position;
+    // Crouching lowers the head and shortens the body
+    let crouch = PLAYER_HEIGHT - player.height;
 
     // Check head first (sphere test)
-    let head_center = player_pos + Vec3::new(0.0, HEAD_HEIGHT, 0.0);
+    let head_center = player_pos + Vec3::new(0.0, HEAD_HEIGHT - crouch, 0.0);
     if let Some(dist) = ray_sphere_intersection(origin, direction, head_center, HEAD_RADIUS) {
         return Some((dist, true));
     }
 
     // Check body (capsule approximated as box)
     let body_min = player_pos + Vec3::new(-PLAYER_WIDTH / 2.0, 0.0, -PLAYER_DEPTH / 2.0);
-    let body_max = player_pos + Vec3::new(PLAYER_WIDTH / 2.0, PLAYER_HEIGHT - 0.3, PLAYER_DEPTH / 2.0);
+    let body_max = player_pos + Vec3::new(PLAYER_WIDTH / 2.0, player.height - 0.3, PLAYER_DEPTH / 2.0);
 
     if let Some(dist) = ray_aabb_intersection(origin, direction, body_min, body_max) {
         return Some((dist, false));
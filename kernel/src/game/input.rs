@@ -1,5 +1,9 @@
 //! Input handling with PS/2 keyboard and mouse support
+//!
+//! A USB boot keyboard (`drivers::usb::hid`), when one was found at boot,
+//! feeds the same `KEY_STATE` alongside the PS/2 keyboard.
 
+use core::sync::atomic::{AtomicI32, Ordering};
 use protocol::packets::ClientInput;
 use spin::Mutex;
 use x86_64::instructions::port::Port;
@@ -33,8 +37,14 @@ pub mod ScanCode {
     pub const LSHIFT: u8 = 0x2A;
     pub const B: u8 = 0x30;
     pub const T: u8 = 0x14;
+    pub const M: u8 = 0x32;
     pub const ENTER: u8 = 0x1C;
     pub const BACKSPACE: u8 = 0x0E;
+    pub const F3: u8 = 0x3D;
+    pub const F4: u8 = 0x3E;
+    pub const F5: u8 = 0x3F;
+    pub const F6: u8 = 0x40;
+    pub const F12: u8 = 0x58;
 
     // Extended scan codes (prefixed with 0xE0)
     pub const EXTENDED: u8 = 0xE0;
@@ -85,6 +95,18 @@ pub struct KeyState {
     pub r: bool,
     pub f: bool,
     pub t: bool,
+    /// Toggle the fullscreen map
+    pub m: bool,
+    /// Debug: toggle wireframe rendering
+    pub f3: bool,
+    /// Debug: cycle render modes
+    pub f4: bool,
+    /// Debug: toggle GPU backface culling
+    pub f5: bool,
+    /// Debug: toggle GPU depth testing
+    pub f6: bool,
+    /// Debug: dump a screenshot over serial
+    pub f12: bool,
 }
 
 impl KeyState {
@@ -153,6 +175,12 @@ pub static KEY_STATE: Mutex<KeyState> = Mutex::new(KeyState {
     r: false,
     f: false,
     t: false,
+    m: false,
+    f3: false,
+    f4: false,
+    f5: false,
+    f6: false,
+    f12: false,
 });
 
 /// Global mouse state
@@ -194,8 +222,28 @@ pub static PREV_KEY_STATE: Mutex<KeyState> = Mutex::new(KeyState {
     r: false,
     f: false,
     t: false,
+    m: false,
+    f3: false,
+    f4: false,
+    f5: false,
+    f6: false,
+    f12: false,
 });
 
+/// Screen size the mouse cursor is clamped to (see set_mouse_bounds)
+static MOUSE_MAX_X: AtomicI32 = AtomicI32::new(1024);
+static MOUSE_MAX_Y: AtomicI32 = AtomicI32::new(768);
+
+/// Clamp the mouse cursor to a `width` x `height` screen
+/// Called at startup and after every display mode change.
+pub fn set_mouse_bounds(width: usize, height: usize) {
+    MOUSE_MAX_X.store(width as i32, Ordering::Relaxed);
+    MOUSE_MAX_Y.store(height as i32, Ordering::Relaxed);
+    let mut mouse = MOUSE_STATE.lock();
+    mouse.x = mouse.x.min(width as i32);
+    mouse.y = mouse.y.min(height as i32);
+}
+
 /// Track if we're in an extended key sequence
 static EXTENDED_KEY: Mutex<bool> = Mutex::new(false);
 
@@ -320,6 +368,10 @@ pub fn init_mouse() {
 /// Poll keyboard and mouse (non-blocking)
 /// Call this multiple times per frame to process all pending input
 pub fn poll_keyboard() {
+    // USB keyboard reports first; PS/2 still carries the mouse (and the
+    // keyboard on machines without a USB one)
+    crate::drivers::usb::hid::poll();
+
     // Process up to 32 bytes of input per call to handle accumulated data
     for _ in 0..32 {
         unsafe {
@@ -388,6 +440,12 @@ pub fn poll_keyboard() {
                     ScanCode::R => state.r = !released,
                     ScanCode::F => state.f = !released,
                     ScanCode::T => state.t = !released,
+                    ScanCode::M => state.m = !released,
+                    ScanCode::F3 => state.f3 = !released,
+                    ScanCode::F4 => state.f4 = !released,
+                    ScanCode::F5 => state.f5 = !released,
+                    ScanCode::F6 => state.f6 = !released,
+                    ScanCode::F12 => state.f12 = !released,
                     _ => {}
                 }
             }
@@ -446,13 +504,18 @@ fn handle_mouse_data(data: u8) {
         mouse.delta_y += -delta_y;  // Invert Y for screen coordinates
 
         // Update absolute position for cursor (clamped to screen bounds)
-        mouse.x = (mouse.x + delta_x).clamp(0, 1024);
-        mouse.y = (mouse.y - delta_y).clamp(0, 768);
+        mouse.x = (mouse.x + delta_x).clamp(0, MOUSE_MAX_X.load(Ordering::Relaxed));
+        mouse.y = (mouse.y - delta_y).clamp(0, MOUSE_MAX_Y.load(Ordering::Relaxed));
 
         // Update button states
         mouse.left_button = status & 0x01 != 0;
         mouse.right_button = status & 0x02 != 0;
         mouse.middle_button = status & 0x04 != 0;
+
+        // The cursor follows the mouse; a host-drawn one without waiting for a frame
+        let (x, y) = (mouse.x, mouse.y);
+        drop(mouse);
+        crate::graphics::cursor::set_position(x, y);
     }
 }
 
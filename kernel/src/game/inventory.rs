@@ -5,11 +5,101 @@ use super::weapon::{Weapon, WeaponType, Rarity, AmmoType};
 /// Number of weapon slots
 pub const INVENTORY_SLOTS: usize = 5;
 
+/// Kinds of stackable item that share a slot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsumableKind {
+    Bandage,
+    MedKit,
+    ShieldSmall,
+    ShieldLarge,
+    AmmoLight,
+    AmmoHeavy,
+    AmmoShells,
+}
+
+impl ConsumableKind {
+    /// Most items of this kind one slot can hold
+    pub fn max_stack(self) -> u8 {
+        match self {
+            ConsumableKind::Bandage => 15,
+            ConsumableKind::MedKit => 3,
+            ConsumableKind::ShieldSmall => 6,
+            ConsumableKind::ShieldLarge => 3,
+            ConsumableKind::AmmoLight
+            | ConsumableKind::AmmoHeavy
+            | ConsumableKind::AmmoShells => 10,
+        }
+    }
+
+    /// Seconds before the next item can be used
+    pub fn use_time(self) -> f32 {
+        match self {
+            ConsumableKind::Bandage => 4.0,
+            ConsumableKind::MedKit => 10.0,
+            ConsumableKind::ShieldSmall => 2.0,
+            ConsumableKind::ShieldLarge => 5.0,
+            ConsumableKind::AmmoLight
+            | ConsumableKind::AmmoHeavy
+            | ConsumableKind::AmmoShells => 1.0,
+        }
+    }
+
+    /// Two-letter hotbar label
+    pub fn short_name(self) -> &'static str {
+        match self {
+            ConsumableKind::Bandage => "BD",
+            ConsumableKind::MedKit => "MK",
+            ConsumableKind::ShieldSmall => "SS",
+            ConsumableKind::ShieldLarge => "SP",
+            ConsumableKind::AmmoLight => "AL",
+            ConsumableKind::AmmoHeavy => "AH",
+            ConsumableKind::AmmoShells => "AS",
+        }
+    }
+}
+
+/// Contents of an inventory slot
+#[derive(Debug, Clone)]
+pub enum InventoryItem {
+    Weapon(Weapon),
+    /// A stack of `count` items of one kind
+    Consumable { kind: ConsumableKind, count: u8, max_stack: u8 },
+}
+
+impl InventoryItem {
+    /// A stack of consumables, using the kind's stack limit
+    pub fn consumable(kind: ConsumableKind, count: u8) -> Self {
+        InventoryItem::Consumable { kind, count, max_stack: kind.max_stack() }
+    }
+
+    pub fn as_weapon(&self) -> Option<&Weapon> {
+        match self {
+            InventoryItem::Weapon(weapon) => Some(weapon),
+            InventoryItem::Consumable { .. } => None,
+        }
+    }
+
+    pub fn as_weapon_mut(&mut self) -> Option<&mut Weapon> {
+        match self {
+            InventoryItem::Weapon(weapon) => Some(weapon),
+            InventoryItem::Consumable { .. } => None,
+        }
+    }
+
+    /// The consumable kind, if this is a stack
+    pub fn consumable_kind(&self) -> Option<ConsumableKind> {
+        match self {
+            InventoryItem::Weapon(_) => None,
+            InventoryItem::Consumable { kind, .. } => Some(*kind),
+        }
+    }
+}
+
 /// Player inventory
 #[derive(Debug, Clone)]
 pub struct Inventory {
-    /// Weapon slots (0-4)
-    pub slots: [Option<Weapon>; INVENTORY_SLOTS],
+    /// Weapon and consumable slots (0-4)
+    pub slots: [Option<InventoryItem>; INVENTORY_SLOTS],
     /// Currently selected slot index
     pub selected_slot: usize,
     /// The pickaxe (always available, not in slots)
@@ -20,6 +110,8 @@ pub struct Inventory {
     pub ammo: AmmoReserves,
     /// Building materials
     pub materials: Materials,
+    /// Time until another consumable can be used
+    pub use_cooldown: f32,
 }
 
 /// Ammo reserves
@@ -45,10 +137,10 @@ impl AmmoReserves {
     /// Add ammo
     pub fn add(&mut self, ammo_type: AmmoType, amount: u16) {
         match ammo_type {
-            AmmoType::Light => self.light = (self.light + amount).min(999),
-            AmmoType::Medium => self.medium = (self.medium + amount).min(999),
-            AmmoType::Heavy => self.heavy = (self.heavy + amount).min(999),
-            AmmoType::Shells => self.shells = (self.shells + amount).min(999),
+            AmmoType::Light => self.light = self.light.saturating_add(amount).min(999),
+            AmmoType::Medium => self.medium = self.medium.saturating_add(amount).min(999),
+            AmmoType::Heavy => self.heavy = self.heavy.saturating_add(amount).min(999),
+            AmmoType::Shells => self.shells = self.shells.saturating_add(amount).min(999),
         }
     }
 
@@ -103,15 +195,15 @@ impl Materials {
     }
 
     pub fn add_wood(&mut self, amount: u32) {
-        self.wood = (self.wood + amount).min(999);
+        self.wood = self.wood.saturating_add(amount).min(999);
     }
 
     pub fn add_brick(&mut self, amount: u32) {
-        self.brick = (self.brick + amount).min(999);
+        self.brick = self.brick.saturating_add(amount).min(999);
     }
 
     pub fn add_metal(&mut self, amount: u32) {
-        self.metal = (self.metal + amount).min(999);
+        self.metal = self.metal.saturating_add(amount).min(999);
     }
 }
 
@@ -131,6 +223,7 @@ impl Inventory {
             pickaxe_selected: true,
             ammo: AmmoReserves::default(),
             materials: Materials::default(),
+            use_cooldown: 0.0,
         }
     }
 
@@ -139,7 +232,10 @@ impl Inventory {
         if self.pickaxe_selected {
             &self.pickaxe
         } else {
-            self.slots[self.selected_slot].as_ref().unwrap_or(&self.pickaxe)
+            self.slots[self.selected_slot]
+                .as_ref()
+                .and_then(InventoryItem::as_weapon)
+                .unwrap_or(&self.pickaxe)
         }
     }
 
@@ -148,14 +244,22 @@ impl Inventory {
         if self.pickaxe_selected {
             &mut self.pickaxe
         } else {
-            if self.slots[self.selected_slot].is_some() {
-                self.slots[self.selected_slot].as_mut().unwrap()
-            } else {
-                &mut self.pickaxe
+            match &mut self.slots[self.selected_slot] {
+                Some(InventoryItem::Weapon(weapon)) => weapon,
+                _ => &mut self.pickaxe,
             }
         }
     }
 
+    /// The item in the selected slot (None with the pickaxe out)
+    pub fn selected_item(&self) -> Option<&InventoryItem> {
+        if self.pickaxe_selected {
+            None
+        } else {
+            self.slots[self.selected_slot].as_ref()
+        }
+    }
+
     /// Select pickaxe
     pub fn select_pickaxe(&mut self) {
         self.pickaxe_selected = true;
@@ -197,31 +301,114 @@ impl Inventory {
         }
     }
 
-    /// Add a weapon to inventory (returns weapon that was dropped if slot full)
-    pub fn add_weapon(&mut self, weapon: Weapon) -> Option<Weapon> {
+    /// Add a weapon to inventory (returns the item that was dropped if slots full)
+    pub fn add_weapon(&mut self, weapon: Weapon) -> Option<InventoryItem> {
         // Try to find empty slot
-        for i in 0..INVENTORY_SLOTS {
-            if self.slots[i].is_none() {
-                self.slots[i] = Some(weapon);
-                return None;
+        let weapon = self.try_add_item(InventoryItem::Weapon(weapon))?;
+
+        // All slots full, swap with current slot
+        self.slots[self.selected_slot].replace(weapon)
+    }
+
+    /// Add an item without displacing anything (returns what did not fit)
+    ///
+    /// Consumables top up existing stacks of the same kind before taking
+    /// empty slots.
+    pub fn try_add_item(&mut self, mut item: InventoryItem) -> Option<InventoryItem> {
+        if let InventoryItem::Consumable { kind, count, max_stack } = &mut item {
+            for slot in self.slots.iter_mut().flatten() {
+                match slot {
+                    InventoryItem::Consumable { kind: k, count: c, max_stack: m } if *k == *kind => {
+                        let moved = (*m).saturating_sub(*c).min(*count);
+                        *c += moved;
+                        *count -= moved;
+                    }
+                    _ => {}
+                }
             }
+
+            // Anything left starts new stacks
+            while *count > 0 {
+                let Some(i) = self.first_empty_slot() else { break };
+                let moved = (*count).min(*max_stack);
+                self.slots[i] = Some(InventoryItem::consumable(*kind, moved));
+                *count -= moved;
+            }
+            return if *count > 0 { Some(item) } else { None };
         }
 
-        // All slots full, swap with current slot
-        let old = self.slots[self.selected_slot].take();
-        self.slots[self.selected_slot] = Some(weapon);
-        old
+        match self.first_empty_slot() {
+            Some(i) => {
+                self.slots[i] = Some(item);
+                None
+            }
+            None => Some(item),
+        }
     }
 
-    /// Drop the currently selected weapon
-    pub fn drop_selected(&mut self) -> Option<Weapon> {
+    /// Use one consumable from the selected slot, if it holds any and the
+    /// previous use has finished. Empty stacks fall back to the pickaxe.
+    pub fn use_selected_consumable(&mut self) -> Option<ConsumableKind> {
+        if self.pickaxe_selected || self.use_cooldown > 0.0 {
+            return None;
+        }
+
+        let slot = &mut self.slots[self.selected_slot];
+        let (kind, remaining) = match slot {
+            Some(InventoryItem::Consumable { kind, count, .. }) if *count > 0 => {
+                *count -= 1;
+                (*kind, *count)
+            }
+            _ => return None,
+        };
+        if remaining == 0 {
+            *slot = None;
+            self.pickaxe_selected = true;
+        }
+        self.use_cooldown = kind.use_time();
+        Some(kind)
+    }
+
+    /// Drop the currently selected item
+    pub fn drop_selected(&mut self) -> Option<InventoryItem> {
         if self.pickaxe_selected {
             None // Can't drop pickaxe
         } else {
-            let weapon = self.slots[self.selected_slot].take();
+            self.drop_slot(self.selected_slot)
+        }
+    }
+
+    /// Drop the weapon in a slot (0-4), or one item from a stack
+    /// Falls back to the pickaxe if the emptied slot was selected
+    pub fn drop_slot(&mut self, slot: usize) -> Option<InventoryItem> {
+        let contents = self.slots.get_mut(slot)?;
+        let item = match contents {
+            Some(InventoryItem::Consumable { kind, count, .. }) if *count > 1 => {
+                *count -= 1;
+                return Some(InventoryItem::consumable(*kind, 1));
+            }
+            _ => contents.take(),
+        };
+        if item.is_some() && !self.pickaxe_selected && self.selected_slot == slot {
             self.pickaxe_selected = true;
-            weapon
         }
+        item
+    }
+
+    /// Swap the contents of two slots (returns false if either is out of range)
+    /// The selection follows the selected weapon to its new slot
+    pub fn swap_slots(&mut self, a: usize, b: usize) -> bool {
+        if a >= INVENTORY_SLOTS || b >= INVENTORY_SLOTS {
+            return false;
+        }
+
+        self.slots.swap(a, b);
+        if self.selected_slot == a {
+            self.selected_slot = b;
+        } else if self.selected_slot == b {
+            self.selected_slot = a;
+        }
+        true
     }
 
     /// Find first empty slot index
@@ -236,36 +423,102 @@ impl Inventory {
 
     /// Get weapon count
     pub fn weapon_count(&self) -> usize {
-        self.slots.iter().filter(|s| s.is_some()).count()
+        self.slots.iter().flatten().filter(|s| s.as_weapon().is_some()).count()
     }
 
     /// Update all weapons (timers)
     pub fn update(&mut self, dt: f32) {
         self.pickaxe.update(dt);
-        for slot in &mut self.slots {
-            if let Some(weapon) = slot {
-                weapon.update(dt);
-            }
+        self.use_cooldown = (self.use_cooldown - dt).max(0.0);
+        for weapon in self.slots.iter_mut().flatten().filter_map(InventoryItem::as_weapon_mut) {
+            weapon.update(dt);
         }
     }
 
     /// Reload current weapon from ammo reserves
-    pub fn reload_current(&mut self) {
+    ///
+    /// Moves rounds from the matching reserve into the magazine (up to capacity)
+    /// and starts the reload timer. Returns the number of rounds loaded.
+    pub fn reload_current(&mut self) -> u16 {
         if self.pickaxe_selected {
-            return;
+            return 0;
         }
 
-        if let Some(weapon) = &mut self.slots[self.selected_slot] {
+        if let Some(InventoryItem::Weapon(weapon)) = &mut self.slots[self.selected_slot] {
             if let Some(ammo_type) = AmmoType::for_weapon(weapon.weapon_type) {
                 if weapon.ammo < weapon.max_ammo && !weapon.is_reloading() {
                     let needed = weapon.max_ammo - weapon.ammo;
-                    let available = self.ammo.get(ammo_type);
-                    if available > 0 {
+                    let loaded = self.ammo.take(ammo_type, needed);
+                    if loaded > 0 {
                         weapon.start_reload();
-                        // Ammo will be deducted when reload completes
+                        weapon.add_ammo(loaded);
                     }
+                    return loaded;
                 }
             }
         }
+
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stack_count(inv: &Inventory, slot: usize) -> Option<u8> {
+        match inv.slots[slot] {
+            Some(InventoryItem::Consumable { count, .. }) => Some(count),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_consumables_stack_before_taking_slots() {
+        let mut inv = Inventory::new();
+        inv.add_weapon(Weapon::new(WeaponType::Pistol, Rarity::Common));
+
+        let max = ConsumableKind::Bandage.max_stack();
+        for _ in 0..max {
+            assert!(inv.try_add_item(InventoryItem::consumable(ConsumableKind::Bandage, 1)).is_none());
+        }
+        assert_eq!(stack_count(&inv, 1), Some(max));
+        assert!(inv.slots[2].is_none());
+
+        // A full stack spills into the next empty slot; other kinds never merge
+        inv.try_add_item(InventoryItem::consumable(ConsumableKind::Bandage, 2));
+        inv.try_add_item(InventoryItem::consumable(ConsumableKind::MedKit, 1));
+        assert_eq!(stack_count(&inv, 2), Some(2));
+        assert_eq!(inv.slots[3].as_ref().and_then(InventoryItem::consumable_kind), Some(ConsumableKind::MedKit));
+
+        // Stacks still top up with every slot taken; only the excess is returned
+        inv.try_add_item(InventoryItem::consumable(ConsumableKind::ShieldSmall, 1));
+        assert!(inv.is_full());
+        let left = inv.try_add_item(InventoryItem::consumable(ConsumableKind::Bandage, max));
+        assert_eq!(stack_count(&inv, 2), Some(max));
+        assert!(matches!(left, Some(InventoryItem::Consumable { count: 2, .. })));
+        assert_eq!(inv.weapon_count(), 1);
+    }
+
+    #[test]
+    fn test_use_and_drop_consumables() {
+        let mut inv = Inventory::new();
+        inv.try_add_item(InventoryItem::consumable(ConsumableKind::ShieldSmall, 2));
+        inv.select_slot(0);
+        assert_eq!(inv.selected_weapon().weapon_type, WeaponType::Pickaxe);
+
+        assert_eq!(inv.use_selected_consumable(), Some(ConsumableKind::ShieldSmall));
+        assert_eq!(inv.use_selected_consumable(), None); // still in use
+        inv.update(ConsumableKind::ShieldSmall.use_time());
+        assert_eq!(inv.use_selected_consumable(), Some(ConsumableKind::ShieldSmall));
+        assert!(inv.slots[0].is_none());
+        assert!(inv.pickaxe_selected);
+
+        // Dropping takes one item at a time
+        inv.try_add_item(InventoryItem::consumable(ConsumableKind::AmmoShells, 2));
+        assert!(inv.drop_slot(0).is_some());
+        assert_eq!(stack_count(&inv, 0), Some(1));
+        assert!(inv.drop_slot(0).is_some());
+        assert!(inv.slots[0].is_none());
     }
 }
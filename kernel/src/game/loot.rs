@@ -1,6 +1,7 @@
 //! Loot system - drops, spawns, and pickups
 
 use glam::Vec3;
+use super::inventory::{ConsumableKind, InventoryItem};
 use super::weapon::{Weapon, WeaponType, Rarity, AmmoType};
 
 /// Maximum loot drops in world
@@ -12,6 +13,9 @@ pub const PICKUP_RANGE: f32 = 2.5;
 /// Loot drop glow pulse speed
 pub const GLOW_PULSE_SPEED: f32 = 3.0;
 
+/// Rounds in one carried ammo pack
+pub const AMMO_PACK_ROUNDS: u16 = 30;
+
 /// Loot item types
 #[derive(Debug, Clone)]
 pub enum LootItem {
@@ -42,6 +46,43 @@ pub enum LootItem {
 }
 
 impl LootItem {
+    /// The loot for a single consumable item
+    pub fn from_consumable(kind: ConsumableKind) -> Self {
+        let use_time = kind.use_time();
+        match kind {
+            ConsumableKind::Bandage => LootItem::Health { amount: 15, use_time, max_health: 75 },
+            ConsumableKind::MedKit => LootItem::Health { amount: 100, use_time, max_health: 100 },
+            ConsumableKind::ShieldSmall => LootItem::Shield { amount: 25, use_time },
+            ConsumableKind::ShieldLarge => LootItem::Shield { amount: 50, use_time },
+            ConsumableKind::AmmoLight => LootItem::Ammo { ammo_type: AmmoType::Light, amount: AMMO_PACK_ROUNDS },
+            ConsumableKind::AmmoHeavy => LootItem::Ammo { ammo_type: AmmoType::Heavy, amount: AMMO_PACK_ROUNDS },
+            ConsumableKind::AmmoShells => LootItem::Ammo { ammo_type: AmmoType::Shells, amount: AMMO_PACK_ROUNDS },
+        }
+    }
+
+    /// The kind this item stacks as when carried in an inventory slot
+    pub fn consumable_kind(&self) -> Option<ConsumableKind> {
+        match self {
+            LootItem::Health { amount, .. } => Some(if *amount >= 100 {
+                ConsumableKind::MedKit
+            } else {
+                ConsumableKind::Bandage
+            }),
+            LootItem::Shield { amount, .. } => Some(if *amount >= 50 {
+                ConsumableKind::ShieldLarge
+            } else {
+                ConsumableKind::ShieldSmall
+            }),
+            LootItem::Ammo { ammo_type, .. } => match ammo_type {
+                AmmoType::Light => Some(ConsumableKind::AmmoLight),
+                AmmoType::Heavy => Some(ConsumableKind::AmmoHeavy),
+                AmmoType::Shells => Some(ConsumableKind::AmmoShells),
+                AmmoType::Medium => None,
+            },
+            LootItem::Weapon(_) | LootItem::Materials { .. } => None,
+        }
+    }
+
     /// Get the rarity color for this item
     pub fn rarity_color(&self) -> u32 {
         match self {
@@ -208,6 +249,20 @@ impl LootManager {
         None
     }
 
+    /// Drop an inventory slot's contents, one loot drop per stacked item
+    pub fn spawn_inventory_drop(&mut self, position: Vec3, item: InventoryItem) {
+        match item {
+            InventoryItem::Weapon(weapon) => {
+                self.spawn_drop(position, LootItem::Weapon(weapon), true);
+            }
+            InventoryItem::Consumable { kind, count, .. } => {
+                for _ in 0..count {
+                    self.spawn_drop(position, LootItem::from_consumable(kind), true);
+                }
+            }
+        }
+    }
+
     /// Spawn loot from a chest
     pub fn spawn_chest_loot(&mut self, position: Vec3, tier: ChestTier) {
         let weapon = self.generate_weapon(tier);
@@ -282,16 +337,17 @@ impl LootManager {
         }
     }
 
-    /// Get nearest loot drop within pickup range
-    pub fn get_nearest_pickup(&self, position: Vec3) -> Option<&LootDrop> {
+    /// Get nearest loot drop within pickup range of the picker,
+    /// given its distance to a drop position
+    pub fn get_nearest_pickup(&self, distance: impl Fn(Vec3) -> f32) -> Option<&LootDrop> {
         let mut nearest: Option<&LootDrop> = None;
-        let mut nearest_dist_sq = PICKUP_RANGE * PICKUP_RANGE;
+        let mut nearest_dist = PICKUP_RANGE;
 
         for drop in &self.drops {
             if let Some(d) = drop {
-                let dist_sq = (d.position - position).length_squared();
-                if dist_sq < nearest_dist_sq {
-                    nearest_dist_sq = dist_sq;
+                let dist = distance(d.position);
+                if dist < nearest_dist {
+                    nearest_dist = dist;
                     nearest = Some(d);
                 }
             }
@@ -389,26 +445,13 @@ impl LootManager {
     /// Generate random healing item
     fn generate_healing(&mut self) -> LootItem {
         self.seed = self.next_random();
-        match self.seed % 4 {
-            0 => LootItem::Health {
-                amount: 15,
-                use_time: 4.0,
-                max_health: 75,
-            }, // Bandages
-            1 => LootItem::Health {
-                amount: 100,
-                use_time: 10.0,
-                max_health: 100,
-            }, // Medkit
-            2 => LootItem::Shield {
-                amount: 25,
-                use_time: 2.0,
-            }, // Small shield
-            _ => LootItem::Shield {
-                amount: 50,
-                use_time: 5.0,
-            }, // Big shield
-        }
+        let kind = match self.seed % 4 {
+            0 => ConsumableKind::Bandage,
+            1 => ConsumableKind::MedKit,
+            2 => ConsumableKind::ShieldSmall,
+            _ => ConsumableKind::ShieldLarge,
+        };
+        LootItem::from_consumable(kind)
     }
 
     /// Simple LCG random
@@ -424,6 +467,8 @@ pub struct LootSpawn {
     pub position: Vec3,
     pub spawn_type: LootSpawnType,
     pub spawned: bool,
+    /// A chest whose loot pile has been picked from
+    pub opened: bool,
 }
 
 #[derive(Debug, Clone, Copy)]
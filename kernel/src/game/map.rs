@@ -105,6 +105,26 @@ pub enum VegetationType {
     Rock,
 }
 
+impl VegetationType {
+    /// Trees leave a stump when harvested
+    pub fn is_tree(&self) -> bool {
+        matches!(self, Self::TreePine | Self::TreeOak | Self::TreeBirch)
+    }
+
+    /// (wood, brick) awarded for harvesting one completely
+    pub fn harvest_materials(&self) -> (u32, u32) {
+        match self {
+            Self::TreePine => (30, 0),
+            Self::TreeOak | Self::TreeBirch => (25, 0),
+            Self::Bush => (10, 0),
+            Self::Rock => (0, 25),
+        }
+    }
+}
+
+/// Health of a tree, bush or rock before any pickaxe swings
+pub const VEGETATION_HEALTH: u8 = 100;
+
 /// A vegetation instance
 #[derive(Debug, Clone, Copy)]
 pub struct Vegetation {
@@ -112,6 +132,44 @@ pub struct Vegetation {
     pub position: Vec3,
     pub scale: f32,
     pub variant: u8,
+    /// Remaining health; harvested when it reaches zero
+    pub health: u8,
+}
+
+/// Stumps kept at once; the oldest is replaced once full
+pub const MAX_STUMPS: usize = 256;
+
+/// A lake or river pool: a flat water surface over a terrain valley
+#[derive(Debug, Clone, Copy)]
+pub struct WaterBody {
+    /// Center of the surface (its y is the water level)
+    pub center: Vec3,
+    /// Horizontal radius of the area players wade through
+    pub radius: f32,
+}
+
+impl WaterBody {
+    /// Whether a point is inside the water area (ignoring height)
+    pub fn contains(&self, x: f32, z: f32) -> bool {
+        let (dx, dz) = (x - self.center.x, z - self.center.z);
+        dx * dx + dz * dz < self.radius * self.radius
+    }
+}
+
+/// Water surfaces, placed over the deepest valleys of the rendered terrain
+pub static WATER_BODIES: [WaterBody; 6] = [
+    // Loot Lake
+    WaterBody { center: Vec3::new(-160.0, 0.0, -620.0), radius: 100.0 },
+    WaterBody { center: Vec3::new(470.0, 0.0, 10.0), radius: 80.0 },
+    WaterBody { center: Vec3::new(-790.0, 0.0, 10.0), radius: 80.0 },
+    WaterBody { center: Vec3::new(470.0, 0.0, -620.0), radius: 80.0 },
+    WaterBody { center: Vec3::new(-160.0, 0.0, 640.0), radius: 80.0 },
+    WaterBody { center: Vec3::new(-790.0, 0.0, 640.0), radius: 80.0 },
+];
+
+/// The water body covering a position, if any
+pub fn water_body_at(x: f32, z: f32) -> Option<&'static WaterBody> {
+    WATER_BODIES.iter().find(|water| water.contains(x, z))
 }
 
 /// Game map containing all world data
@@ -126,6 +184,10 @@ pub struct GameMap {
     pub vegetation: [Option<Vegetation>; 512],
     /// Vegetation count
     pub vegetation_count: usize,
+    /// Stumps of harvested trees: position and the world tick it was cut
+    pub stumps: [(Vec3, u32); MAX_STUMPS],
+    /// Trees harvested so far (the stump ring wraps past MAX_STUMPS)
+    pub stump_count: usize,
     /// Loot spawns
     pub loot_spawns: [Option<LootSpawn>; 256],
     /// Loot spawn count
@@ -232,6 +294,8 @@ impl GameMap {
             building_count: 0,
             vegetation: [const { None }; 512],
             vegetation_count: 0,
+            stumps: [(Vec3::ZERO, 0); MAX_STUMPS],
+            stump_count: 0,
             loot_spawns: [const { None }; 256],
             loot_spawn_count: 0,
             seed,
@@ -323,6 +387,22 @@ impl GameMap {
         })
     }
 
+    /// Remove a harvested tree, bush or rock, leaving a stump if it was a tree
+    pub fn remove_vegetation(&mut self, index: usize, tick: u32) {
+        let Some(veg) = self.vegetation.get_mut(index).and_then(Option::take) else {
+            return;
+        };
+        if veg.veg_type.is_tree() {
+            self.stumps[self.stump_count % MAX_STUMPS] = (veg.position, tick);
+            self.stump_count += 1;
+        }
+    }
+
+    /// Stumps of harvested trees
+    pub fn stumps(&self) -> &[(Vec3, u32)] {
+        &self.stumps[..self.stump_count.min(MAX_STUMPS)]
+    }
+
     /// Generate buildings for all POIs
     fn generate_buildings(&mut self) {
         for poi in &self.pois.clone() {
@@ -433,6 +513,7 @@ impl GameMap {
                     position: Vec3::new(px, py, pz),
                     scale: 0.8 + self.next_random_f32() * 0.4,
                     variant: (self.next_random() % 4) as u8,
+                    health: VEGETATION_HEALTH,
                 });
                 self.vegetation_count += 1;
 
@@ -472,6 +553,7 @@ impl GameMap {
                 position: Vec3::new(x, y, z),
                 scale: 0.8 + self.next_random_f32() * 0.6,
                 variant: (self.next_random() % 4) as u8,
+                health: VEGETATION_HEALTH,
             });
             self.vegetation_count += 1;
         }
@@ -515,6 +597,7 @@ impl GameMap {
                         position: Vec3::new(world_x, b.position.y + 0.5, world_z),
                         spawn_type,
                         spawned: false,
+                        opened: false,
                     });
                     self.loot_spawn_count += 1;
                 }
@@ -1,9 +1,11 @@
 //! Game logic
 
+pub mod audio;
 pub mod bot;
 pub mod building;
 pub mod bus;
 pub mod camera;
+pub mod chat;
 pub mod combat;
 pub mod input;
 pub mod inventory;
@@ -12,6 +14,7 @@ pub mod loot;
 pub mod map;
 pub mod party;
 pub mod player;
+pub mod replay;
 pub mod state;
 pub mod storm;
 pub mod weapon;
@@ -270,6 +270,72 @@ fn generate_party_code(seed: u64) -> [u8; 6] {
     code
 }
 
+/// Respawn tokens each squad starts a match with
+pub const SQUAD_RESPAWN_TOKENS: u8 = 2;
+
+/// Seconds after elimination during which a teammate can still revive
+pub const REVIVE_WINDOW: f32 = 90.0;
+
+/// How close a teammate has to stand to revive
+pub const REVIVE_RANGE: f32 = 3.0;
+
+/// Health a revived player comes back with
+pub const REVIVE_HEALTH: u8 = 30;
+
+/// An in-match squad of up to four players sharing a respawn token pool
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Squad {
+    /// Player IDs, filled from the front
+    pub members: [Option<u8>; MAX_PARTY_SIZE],
+    /// Revives left for the whole squad
+    pub respawn_tokens: u8,
+}
+
+impl Squad {
+    pub const fn new() -> Self {
+        Self {
+            members: [None; MAX_PARTY_SIZE],
+            respawn_tokens: SQUAD_RESPAWN_TOKENS,
+        }
+    }
+
+    /// Add a player (returns false if the squad is full)
+    pub fn add_member(&mut self, player_id: u8) -> bool {
+        match self.members.iter_mut().find(|m| m.is_none()) {
+            Some(slot) => {
+                *slot = Some(player_id);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Check if every slot is taken
+    pub fn is_full(&self) -> bool {
+        self.members.iter().all(|m| m.is_some())
+    }
+
+    /// Player IDs of the members
+    pub fn member_ids(&self) -> impl Iterator<Item = u8> + '_ {
+        self.members.iter().flatten().copied()
+    }
+
+    /// Spend a respawn token (returns false if none are left)
+    pub fn take_token(&mut self) -> bool {
+        if self.respawn_tokens == 0 {
+            return false;
+        }
+        self.respawn_tokens -= 1;
+        true
+    }
+}
+
+impl Default for Squad {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Global party state
 pub static PARTY: Mutex<Option<Party>> = Mutex::new(None);
 
@@ -311,3 +377,26 @@ pub fn set_game_mode(mode: GameMode) {
 pub fn get_game_mode() -> GameMode {
     PARTY.lock().as_ref().map(|p| p.game_mode).unwrap_or_default()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_squad_fills_and_spends_tokens() {
+        let mut squad = Squad::new();
+        for id in 0..MAX_PARTY_SIZE as u8 {
+            assert!(!squad.is_full());
+            assert!(squad.add_member(id * 2));
+        }
+        assert!(squad.is_full());
+        assert!(!squad.add_member(99));
+        assert_eq!(squad.member_ids().collect::<Vec<_>>(), [0, 2, 4, 6]);
+
+        for _ in 0..SQUAD_RESPAWN_TOKENS {
+            assert!(squad.take_token());
+        }
+        assert!(!squad.take_token());
+        assert_eq!(squad.respawn_tokens, 0);
+    }
+}
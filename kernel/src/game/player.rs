@@ -1,6 +1,7 @@
 //! Player entity
 
 use alloc::string::String;
+use alloc::vec::Vec;
 use glam::Vec3;
 use protocol::packets::{ClientInput, PlayerState, PlayerStateFlags};
 use smoltcp::wire::Ipv4Address;
@@ -19,6 +20,24 @@ pub const SPRINT_MULTIPLIER: f32 = 1.5;
 /// Crouch speed multiplier
 pub const CROUCH_MULTIPLIER: f32 = 0.5;
 
+/// Speed multiplier while wading through water
+pub const WATER_SPEED_MULTIPLIER: f32 = 0.5;
+
+/// Collision box height when standing
+pub const STANDING_HEIGHT: f32 = 1.8;
+
+/// Collision box height when crouched
+pub const CROUCH_HEIGHT: f32 = 1.2;
+
+/// How far the eyes (and the camera) drop when fully crouched
+pub const CROUCH_CAMERA_DROP: f32 = 0.4;
+
+/// How fast the collision box grows back when standing up (units per second)
+pub const STAND_UP_SPEED: f32 = 4.0;
+
+/// Horizontal half-size of the collision box
+pub const PLAYER_RADIUS: f32 = 0.5;
+
 /// Jump velocity
 pub const JUMP_VELOCITY: f32 = 15.0;
 
@@ -37,6 +56,10 @@ pub const GLIDER_DIVE_SPEED: f32 = 45.0;           // Diving descent (hold forwa
 pub const GLIDER_HORIZONTAL_SPEED: f32 = 20.0;     // Normal horizontal (was 15)
 pub const GLIDER_BOOST_SPEED: f32 = 35.0;          // Diving horizontal (was 25)
 
+/// How far the server may end up from a predicted position before the
+/// client corrects its prediction
+pub const PREDICTION_EPSILON: f32 = 0.05;
+
 /// Glider deploy heights
 pub const AUTO_DEPLOY_HEIGHT: f32 = 50.0;          // Deploy closer to ground (was 100)
 pub const MANUAL_DEPLOY_MIN_HEIGHT: f32 = 100.0;   // Can deploy earlier (was 200)
@@ -59,6 +82,17 @@ pub struct Player {
     // Player phase (bus, freefall, gliding, grounded, etc.)
     pub phase: PlayerPhase,
 
+    // Crouch state: crouched (or still standing up), and the current collision box height
+    pub is_crouching: bool,
+    pub height: f32,
+    crouch_held: bool,
+
+    // Wading through a water body (set by the world each tick)
+    pub in_water: bool,
+
+    // Fire held on the previous input, for semi-automatic trigger pulls
+    fire_held: bool,
+
     // Health and shield
     pub health: u8,
     pub shield: u8,
@@ -79,6 +113,10 @@ pub struct Player {
     pub spectate_target: Option<u8>,
     pub eliminator_id: Option<u8>,
 
+    // Squad (squad mode only) and seconds since elimination, for revives
+    pub squad_id: Option<u8>,
+    pub eliminated_timer: f32,
+
     // Stats
     pub eliminations: u16,
     pub damage_dealt: u32,
@@ -103,6 +141,11 @@ impl Player {
             yaw: 0.0,
             pitch: 0.0,
             phase: PlayerPhase::OnBus,
+            is_crouching: false,
+            height: STANDING_HEIGHT,
+            crouch_held: false,
+            in_water: false,
+            fire_held: false,
             health: 100,
             shield: 0,
             max_health: 100,
@@ -113,6 +156,8 @@ impl Player {
             dive_angle: 0.0,
             spectate_target: None,
             eliminator_id: None,
+            squad_id: None,
+            eliminated_timer: 0.0,
             eliminations: 0,
             damage_dealt: 0,
             customization: PlayerCustomization::default(),
@@ -160,6 +205,13 @@ impl Player {
         }
     }
 
+    /// Track the fire button; true on the input where it goes down
+    pub fn pull_trigger(&mut self, fire: bool) -> bool {
+        let pulled = fire && !self.fire_held;
+        self.fire_held = fire;
+        pulled
+    }
+
     /// Apply input during freefall
     fn apply_freefall_input(&mut self, input: &ClientInput, _dt: f32) {
         // Calculate movement direction for steering
@@ -232,13 +284,20 @@ impl Player {
             move_dir = move_dir.normalize();
         }
 
+        // Crouching is immediate; standing back up waits for headroom (see update_stance).
+        // Wading players can neither crouch nor jump.
+        self.crouch_held = input.crouch && !self.in_water;
+        if self.crouch_held {
+            self.is_crouching = true;
+        }
+
         // Apply movement with speed modifiers
         let mut speed = MOVE_SPEED;
-        if input.crouch {
+        if self.is_crouching {
             speed *= CROUCH_MULTIPLIER;
-            self.flags |= PlayerStateFlags::CROUCHING;
-        } else {
-            self.flags &= !PlayerStateFlags::CROUCHING;
+        }
+        if self.in_water {
+            speed *= WATER_SPEED_MULTIPLIER;
         }
 
         if self.is_grounded() {
@@ -246,7 +305,7 @@ impl Player {
             self.velocity.z = move_dir.z * speed;
 
             // Jump
-            if input.jump {
+            if input.jump && !self.in_water {
                 self.velocity.y = JUMP_VELOCITY;
                 self.flags |= PlayerStateFlags::JUMPING;
             }
@@ -257,7 +316,11 @@ impl Player {
     pub fn update(&mut self, dt: f32, buildings: &[crate::game::building::BuildPiece], terrain_height: f32) {
         // Update inventory (weapon timers)
         self.inventory.update(dt);
+        self.update_movement(dt, buildings, terrain_height);
+    }
 
+    /// Movement part of `update` (what client-side prediction replays)
+    pub fn update_movement(&mut self, dt: f32, buildings: &[crate::game::building::BuildPiece], terrain_height: f32) {
         match self.phase {
             PlayerPhase::OnBus => {
                 // Position controlled by bus, no physics
@@ -272,7 +335,8 @@ impl Player {
                 self.update_grounded(dt, buildings, terrain_height);
             }
             PlayerPhase::Eliminated | PlayerPhase::Spectating => {
-                // No physics when dead/spectating
+                // No physics when dead/spectating, but the revive window runs out
+                self.eliminated_timer += dt;
             }
         }
     }
@@ -330,8 +394,35 @@ impl Player {
         }
     }
 
+    /// Crouch or stand up, growing the collision box back over a few frames
+    ///
+    /// Each step sweeps the top of the box up to its next height; anything in
+    /// the way keeps the player crouched until there is room.
+    fn update_stance(&mut self, dt: f32, buildings: &[crate::game::building::BuildPiece]) {
+        if self.crouch_held {
+            self.height = CROUCH_HEIGHT;
+            self.is_crouching = true;
+        } else if self.height < STANDING_HEIGHT {
+            let next = (self.height + STAND_UP_SPEED * dt).min(STANDING_HEIGHT);
+            if !self.collides_in_span(self.position, self.height, next, buildings) {
+                self.height = next;
+            }
+            self.is_crouching = self.height < STANDING_HEIGHT;
+        } else {
+            self.is_crouching = false;
+        }
+
+        if self.is_crouching {
+            self.flags |= PlayerStateFlags::CROUCHING;
+        } else {
+            self.flags &= !PlayerStateFlags::CROUCHING;
+        }
+    }
+
     /// Update grounded physics
     fn update_grounded(&mut self, dt: f32, buildings: &[crate::game::building::BuildPiece], terrain_height: f32) {
+        self.update_stance(dt, buildings);
+
         // Check if we're on the ground
         let on_ground = self.position.y <= terrain_height + 0.1;
 
@@ -441,9 +532,22 @@ impl Player {
         self.health = 0;
         self.phase = PlayerPhase::Eliminated;
         self.eliminator_id = killer_id;
+        self.eliminated_timer = 0.0;
         self.flags &= !PlayerStateFlags::ALIVE;
     }
 
+    /// Bring an eliminated player back where they fell
+    pub fn revive(&mut self, health: u8) {
+        self.health = health.min(self.max_health);
+        self.shield = 0;
+        self.phase = PlayerPhase::Grounded;
+        self.velocity = Vec3::ZERO;
+        self.eliminator_id = None;
+        self.spectate_target = None;
+        self.eliminated_timer = 0.0;
+        self.flags |= PlayerStateFlags::ALIVE;
+    }
+
     /// Start spectating another player
     pub fn start_spectating(&mut self, target_id: u8) {
         self.phase = PlayerPhase::Spectating;
@@ -491,9 +595,28 @@ impl Player {
         )
     }
 
+    /// How far the eyes sit below their standing height (0 standing, CROUCH_CAMERA_DROP crouched)
+    pub fn crouch_drop(&self) -> f32 {
+        CROUCH_CAMERA_DROP * (STANDING_HEIGHT - self.height) / (STANDING_HEIGHT - CROUCH_HEIGHT)
+    }
+
     /// Get eye position for shooting
     pub fn eye_position(&self) -> Vec3 {
-        self.position + Vec3::new(0.0, 1.7, 0.0)
+        self.position + Vec3::new(0.0, 1.7 - self.crouch_drop(), 0.0)
+    }
+
+    /// Collision box as (min, max) corners, using the current (crouched or standing) height
+    pub fn aabb(&self) -> (Vec3, Vec3) {
+        (
+            self.position - Vec3::new(PLAYER_RADIUS, 0.0, PLAYER_RADIUS),
+            self.position + Vec3::new(PLAYER_RADIUS, self.height, PLAYER_RADIUS),
+        )
+    }
+
+    /// Distance from a point to the nearest point of the collision box
+    pub fn distance_to_aabb(&self, point: Vec3) -> f32 {
+        let (min, max) = self.aabb();
+        point.distance(point.clamp(min, max))
     }
 
     /// Record an elimination
@@ -515,12 +638,65 @@ impl Player {
         state.health = self.health;
         state.weapon_id = self.inventory.selected_weapon().weapon_type as u8;
         state.state = self.flags;
+        state.input_seq = self.last_input_seq;
         state
     }
 
+    /// Take on a player's state from a server delta
+    pub fn apply_state(&mut self, state: &PlayerState) {
+        self.position = Vec3::new(state.world_x(), state.world_y(), state.world_z());
+        self.yaw = state.yaw_radians();
+        self.pitch = state.pitch_radians();
+        self.health = state.health;
+        self.set_network_weapon(state.weapon_id);
+        self.flags = state.state;
+
+        // Remote players only send the crouch flag, so snap their box to it
+        self.is_crouching = state.state & PlayerStateFlags::CROUCHING != 0;
+        self.height = if self.is_crouching { CROUCH_HEIGHT } else { STANDING_HEIGHT };
+    }
+
+    /// Correct the local player's prediction with its authoritative server state
+    ///
+    /// The server reports the last input it applied. If it ended up where
+    /// that input was predicted to leave the player, the prediction stands.
+    /// Otherwise the player goes back to the server's position (with the
+    /// velocity and phase predicted there) and the inputs the server hasn't
+    /// applied yet are replayed on top, `physics` running each step after
+    /// its input. Acks of inputs no longer buffered (stale or reordered
+    /// deltas) leave the position alone. Returns whether it was corrected.
+    pub fn reconcile(&mut self, server_state: &PlayerState, inputs: &mut InputHistory, mut physics: impl FnMut(&mut Player, f32)) -> bool {
+        // Health isn't predicted
+        self.health = server_state.health;
+
+        if !inputs.acknowledge(server_state.input_seq) {
+            return false;
+        }
+        let server_position = Vec3::new(server_state.world_x(), server_state.world_y(), server_state.world_z());
+        let (base, pending) = inputs.steps.split_first_mut().expect("acknowledged step is buffered");
+        if base.position.distance(server_position) <= PREDICTION_EPSILON {
+            return false;
+        }
+
+        self.position = server_position;
+        self.velocity = base.velocity;
+        self.phase = base.phase;
+        self.last_input_seq = base.input.sequence;
+        base.position = server_position;
+        for step in pending {
+            self.apply_input(&step.input, step.dt);
+            physics(self, step.dt);
+            step.position = self.position;
+            step.velocity = self.velocity;
+            step.phase = self.phase;
+        }
+        true
+    }
+
     /// Set weapon from network sync (for remote players)
     /// This sets a weapon in the first slot based on the weapon_id received
     pub fn set_network_weapon(&mut self, weapon_id: u8) {
+        use super::inventory::InventoryItem;
         use super::weapon::{Weapon, WeaponType, Rarity};
 
         if weapon_id == 0 {
@@ -529,7 +705,7 @@ impl Player {
         } else if let Some(weapon_type) = WeaponType::from_u8(weapon_id) {
             // Create a weapon of this type in first slot for rendering
             let weapon = Weapon::new(weapon_type, Rarity::Common);
-            self.inventory.slots[0] = Some(weapon);
+            self.inventory.slots[0] = Some(InventoryItem::Weapon(weapon));
             self.inventory.selected_slot = 0;
             self.inventory.pickaxe_selected = false;
         }
@@ -537,8 +713,13 @@ impl Player {
 
     /// Check collision with buildings
     fn check_building_collision(&self, pos: Vec3, buildings: &[crate::game::building::BuildPiece]) -> bool {
-        let player_radius = 0.5; // Approximate radius
-        let player_height = 1.8;
+        self.collides_in_span(pos, 0.0, self.height, buildings)
+    }
+
+    /// Check collision with buildings for the part of the collision box at `pos`
+    /// between `bottom` and `top` above the feet
+    fn collides_in_span(&self, pos: Vec3, bottom: f32, top: f32, buildings: &[crate::game::building::BuildPiece]) -> bool {
+        let player_radius = PLAYER_RADIUS;
 
         for building in buildings {
             if building.is_destroyed() {
@@ -577,7 +758,7 @@ impl Player {
                 let building_min_y = building.position.y - half_h;
                 let building_max_y = building.position.y + half_h;
                 
-                if pos.y + player_height > building_min_y && pos.y < building_max_y {
+                if pos.y + top > building_min_y && pos.y + bottom < building_max_y {
                     return true;
                 }
             }
@@ -585,3 +766,214 @@ impl Player {
         false
     }
 }
+
+/// A local input and where it left the player (client-side prediction)
+#[derive(Debug, Clone)]
+pub struct PredictedStep {
+    pub input: ClientInput,
+    /// Physics step run after the input
+    pub dt: f32,
+    pub position: Vec3,
+    pub velocity: Vec3,
+    pub phase: PlayerPhase,
+}
+
+/// Inputs the local player has applied ahead of the server, oldest first
+///
+/// After the first acknowledgement the oldest step is the last input the
+/// server applied, kept as the base to compare its next state against.
+#[derive(Debug, Clone, Default)]
+pub struct InputHistory {
+    steps: Vec<PredictedStep>,
+}
+
+impl InputHistory {
+    /// Steps kept (two seconds at 60 FPS); older ones are dropped unacked
+    pub const CAPACITY: usize = 120;
+
+    pub const fn new() -> Self {
+        Self { steps: Vec::new() }
+    }
+
+    /// Remember an input the local player applied and the physics step after it
+    pub fn record(&mut self, input: ClientInput, dt: f32, player: &Player) {
+        if self.steps.len() >= Self::CAPACITY {
+            self.steps.remove(0);
+        }
+        self.steps.push(PredictedStep {
+            input,
+            dt,
+            position: player.position,
+            velocity: player.velocity,
+            phase: player.phase,
+        });
+    }
+
+    /// Drop the steps before input `sequence`, keeping its own as the base
+    /// Returns false if that input isn't buffered.
+    fn acknowledge(&mut self, sequence: u32) -> bool {
+        let Some(acked) = self.steps.iter().position(|s| s.input.sequence == sequence) else {
+            return false;
+        };
+        self.steps.drain(..acked);
+        true
+    }
+
+    /// Buffered steps, oldest first
+    pub fn steps(&self) -> &[PredictedStep] {
+        &self.steps
+    }
+
+    pub fn clear(&mut self) {
+        self.steps.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::building::BuildPiece;
+
+    fn grounded_player() -> Player {
+        let mut player = Player::new(0, "Test", Ipv4Address::new(127, 0, 0, 1), 5000);
+        player.phase = PlayerPhase::Grounded;
+        player.flags = PlayerStateFlags::ALIVE;
+        player
+    }
+
+    fn input(sequence: u32, crouch: bool) -> ClientInput {
+        ClientInput { sequence, crouch, forward: 1, ..Default::default() }
+    }
+
+    #[test]
+    fn test_crouch_shrinks_box_and_slows() {
+        let mut player = grounded_player();
+        player.apply_input(&input(1, true), 0.05);
+        player.update(0.05, &[], 0.0);
+        assert!(player.is_crouching);
+        assert_eq!(player.height, CROUCH_HEIGHT);
+        assert!((player.velocity.length() - MOVE_SPEED * CROUCH_MULTIPLIER).abs() < 0.01);
+        assert!((player.crouch_drop() - CROUCH_CAMERA_DROP).abs() < 0.001);
+
+        // Releasing in the open grows the box back over a few ticks
+        player.apply_input(&input(2, false), 0.05);
+        for _ in 0..20 {
+            player.update(0.05, &[], 0.0);
+        }
+        assert!(!player.is_crouching);
+        assert_eq!(player.height, STANDING_HEIGHT);
+        assert_eq!(player.crouch_drop(), 0.0);
+    }
+
+    #[test]
+    fn test_stand_up_blocked_by_ceiling() {
+        let mut player = grounded_player();
+        player.apply_input(&input(1, true), 0.05);
+        player.update(0.05, &[], 0.0);
+
+        // A floor piece just above the crouched head
+        let ceiling = [BuildPiece::floor(player.position + Vec3::new(0.0, 1.4, 0.0), 0.0)];
+        player.apply_input(&ClientInput { sequence: 2, ..Default::default() }, 0.05);
+        for _ in 0..20 {
+            player.update(0.05, &ceiling, 0.0);
+        }
+        assert!(player.is_crouching);
+        assert_eq!(player.height, CROUCH_HEIGHT);
+        assert!(player.flags & PlayerStateFlags::CROUCHING != 0);
+    }
+
+    #[test]
+    fn test_water_slows_and_blocks_crouch_and_jump() {
+        let mut player = grounded_player();
+        player.in_water = true;
+        player.apply_input(&ClientInput { sequence: 1, forward: 1, crouch: true, jump: true, ..Default::default() }, 0.05);
+        assert!(!player.is_crouching);
+        assert_eq!(player.velocity.y, 0.0);
+        assert!((player.velocity.length() - MOVE_SPEED * WATER_SPEED_MULTIPLIER).abs() < 0.01);
+
+        // Back on land the same input jumps
+        player.in_water = false;
+        player.apply_input(&ClientInput { sequence: 2, forward: 1, jump: true, ..Default::default() }, 0.05);
+        assert_eq!(player.velocity.y, JUMP_VELOCITY);
+    }
+
+    /// Walk a grounded player through `count` inputs, turning as it goes,
+    /// recording each step; returns the player and its state after each one
+    fn predict(count: u32) -> (Player, InputHistory, Vec<PlayerState>) {
+        let mut player = grounded_player();
+        let mut history = InputHistory::new();
+        let mut states = Vec::new();
+        for sequence in 1..=count {
+            let input = ClientInput {
+                sequence,
+                forward: 1,
+                strafe: if sequence % 3 == 0 { 1 } else { 0 },
+                yaw: (sequence * 500) as i16,
+                ..Default::default()
+            };
+            player.apply_input(&input, 1.0 / 60.0);
+            player.update_movement(1.0 / 60.0, &[], 0.0);
+            history.record(input, 1.0 / 60.0, &player);
+            states.push(player.to_state());
+        }
+        (player, history, states)
+    }
+
+    fn flat_ground(player: &mut Player, dt: f32) {
+        player.update_movement(dt, &[], 0.0);
+    }
+
+    #[test]
+    fn test_reconcile_keeps_matching_prediction() {
+        let (mut player, mut history, states) = predict(10);
+        let predicted = player.position;
+
+        // The server agrees with input 4: nothing replayed, older inputs dropped
+        assert!(!player.reconcile(&states[3], &mut history, flat_ground));
+        assert_eq!(player.position, predicted);
+        assert_eq!(history.steps().len(), 7);
+        assert_eq!(history.steps()[0].input.sequence, 4);
+
+        // A late delta acking input 2 is no longer buffered and changes nothing
+        let mut moved = states[1];
+        moved.set_position(100.0, 0.0, 100.0);
+        assert!(!player.reconcile(&moved, &mut history, flat_ground));
+        assert_eq!(player.position, predicted);
+    }
+
+    #[test]
+    fn test_reconcile_replays_from_corrected_position() {
+        let (mut player, mut history, states) = predict(10);
+        let predicted = player.position;
+
+        // The server pushed the player 2 units along X by input 6; replaying
+        // inputs 7-10 from there lands the same offset from the prediction
+        let offset = Vec3::new(2.0, 0.0, 0.0);
+        let mut corrected = states[5];
+        let acked = Vec3::new(corrected.world_x(), corrected.world_y(), corrected.world_z()) + offset;
+        corrected.set_position(acked.x, acked.y, acked.z);
+        assert!(player.reconcile(&corrected, &mut history, flat_ground));
+        assert!(player.position.distance(predicted + offset) <= PREDICTION_EPSILON);
+        assert_eq!(player.last_input_seq, 10);
+
+        // The replayed steps now hold the corrected prediction, so the
+        // server confirming it later is not a mismatch
+        let mut confirmed = states[7];
+        let step = history.steps()[2].position;
+        assert_eq!(history.steps()[2].input.sequence, 8);
+        confirmed.set_position(step.x, step.y, step.z);
+        assert!(!player.reconcile(&confirmed, &mut history, flat_ground));
+        assert!(player.position.distance(predicted + offset) <= PREDICTION_EPSILON);
+    }
+
+    #[test]
+    fn test_input_history_capacity() {
+        let player = grounded_player();
+        let mut history = InputHistory::new();
+        for sequence in 1..=InputHistory::CAPACITY as u32 + 5 {
+            history.record(ClientInput { sequence, ..Default::default() }, 1.0 / 60.0, &player);
+        }
+        assert_eq!(history.steps().len(), InputHistory::CAPACITY);
+        assert_eq!(history.steps()[0].input.sequence, 6);
+    }
+}
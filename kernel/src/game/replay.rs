@@ -0,0 +1,440 @@
+//! Match recording and playback
+//!
+//! The recorder samples the whole world at 10 Hz into a pre-allocated ring
+//! buffer (oldest snapshots are dropped once it is full) and dumps it over
+//! the serial port as base64 when the match ends:
+//!
+//! ```text
+//! REPLAY BEGIN <snapshots> <bytes>
+//! <base64, 76 characters per line>
+//! REPLAY END
+//! ```
+//!
+//! The decoded stream is `REPLAY_MAGIC`, a format version byte, then one
+//! record per snapshot: a little-endian u16 length followed by an encoded
+//! `WorldStateDelta` holding every player. Booting with `replay` and the
+//! captured text as a Limine module plays it back instead of simulating.
+
+extern crate alloc;
+
+use super::world::GameWorld;
+use crate::encoding::{base64_decode, Base64Lines};
+use crate::serial_println;
+use alloc::vec;
+use alloc::vec::Vec;
+use protocol::packets::WorldStateDelta;
+use spin::Mutex;
+
+/// Snapshots recorded per second
+pub const REPLAY_SNAPSHOT_HZ: u32 = 10;
+
+/// World updates per second (the world steps at dt = 1/60)
+const WORLD_TICK_RATE: u32 = 60;
+
+/// Ring buffer size: ~30 minutes at 10 Hz with a full lobby
+pub const REPLAY_BUFFER_SIZE: usize = 32 * 1024 * 1024;
+
+/// First bytes of a decoded recording
+const REPLAY_MAGIC: [u8; 4] = *b"BRRP";
+
+/// Recording layout version (bump when the record format changes)
+const REPLAY_FORMAT_VERSION: u8 = 2;
+
+/// Full world state at one instant of the match
+#[derive(Debug, Clone)]
+pub struct WorldSnapshot {
+    pub state: WorldStateDelta,
+}
+
+impl WorldSnapshot {
+    /// Capture every player and the storm
+    pub fn capture(world: &GameWorld) -> Self {
+        Self { state: world.snapshot() }
+    }
+
+    /// Overwrite the world with this snapshot
+    pub fn apply(&self, world: &mut GameWorld) {
+        world.apply_delta(&self.state);
+    }
+}
+
+/// Records snapshots into a fixed-size ring buffer
+pub struct ReplayRecorder {
+    buf: Vec<u8>,
+    /// Offset of the oldest record
+    head: usize,
+    /// Bytes in use, starting at `head` (wrapping)
+    len: usize,
+    /// Records in the buffer
+    count: usize,
+    /// World updates seen since recording started
+    ticks: u32,
+}
+
+impl ReplayRecorder {
+    /// Recorder with the full REPLAY_BUFFER_SIZE buffer
+    pub fn new() -> Self {
+        Self::with_capacity(REPLAY_BUFFER_SIZE)
+    }
+
+    /// Recorder with a buffer of `bytes` bytes (allocated up front)
+    pub fn with_capacity(bytes: usize) -> Self {
+        Self {
+            buf: vec![0u8; bytes],
+            head: 0,
+            len: 0,
+            count: 0,
+            ticks: 0,
+        }
+    }
+
+    /// Call once per world update; samples the world at REPLAY_SNAPSHOT_HZ
+    pub fn tick(&mut self, world: &GameWorld) {
+        if self.ticks.is_multiple_of(WORLD_TICK_RATE / REPLAY_SNAPSHOT_HZ) {
+            self.push(&WorldSnapshot::capture(world));
+        }
+        self.ticks = self.ticks.wrapping_add(1);
+    }
+
+    /// Append a snapshot, dropping the oldest ones if the buffer is full
+    pub fn push(&mut self, snapshot: &WorldSnapshot) {
+        let payload = snapshot.state.encode();
+        let record_len = 2 + payload.len();
+        if payload.len() > u16::MAX as usize || record_len > self.buf.len() {
+            return;
+        }
+
+        while self.buf.len() - self.len < record_len {
+            self.drop_oldest();
+        }
+
+        let tail = (self.head + self.len) % self.buf.len();
+        self.write_at(tail, &(payload.len() as u16).to_le_bytes());
+        self.write_at((tail + 2) % self.buf.len(), &payload);
+        self.len += record_len;
+        self.count += 1;
+    }
+
+    /// Number of snapshots currently held
+    pub fn snapshot_count(&self) -> usize {
+        self.count
+    }
+
+    /// Bytes of the recording stream (magic, version and records)
+    pub fn stream_len(&self) -> usize {
+        REPLAY_MAGIC.len() + 1 + self.len
+    }
+
+    /// Encode the recording as base64, passing each line to `emit`
+    pub fn write_base64(&self, emit: impl FnMut(&str)) {
+        let mut encoder = Base64Lines::new(emit);
+        encoder.write(&REPLAY_MAGIC);
+        encoder.write(&[REPLAY_FORMAT_VERSION]);
+
+        // Records are contiguous in the ring, possibly wrapping once
+        let first = (self.buf.len() - self.head).min(self.len);
+        encoder.write(&self.buf[self.head..self.head + first]);
+        encoder.write(&self.buf[..self.len - first]);
+        encoder.finish();
+    }
+
+    /// Stream the recording over the serial port for capture on the host
+    pub fn save_to_serial(&self) {
+        serial_println!("REPLAY BEGIN {} {}", self.count, self.stream_len());
+        self.write_base64(|line| serial_println!("{}", line));
+        serial_println!("REPLAY END");
+    }
+
+    /// Forget the oldest record
+    fn drop_oldest(&mut self) {
+        let mut len_bytes = [0u8; 2];
+        for (i, b) in len_bytes.iter_mut().enumerate() {
+            *b = self.buf[(self.head + i) % self.buf.len()];
+        }
+        let record_len = 2 + u16::from_le_bytes(len_bytes) as usize;
+        self.head = (self.head + record_len) % self.buf.len();
+        self.len -= record_len;
+        self.count -= 1;
+    }
+
+    /// Copy `bytes` into the ring at `offset`, wrapping at the end
+    fn write_at(&mut self, offset: usize, bytes: &[u8]) {
+        let first = (self.buf.len() - offset).min(bytes.len());
+        self.buf[offset..offset + first].copy_from_slice(&bytes[..first]);
+        self.buf[..bytes.len() - first].copy_from_slice(&bytes[first..]);
+    }
+}
+
+impl Default for ReplayRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Why captured serial output isn't a recording
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayError {
+    /// The text between the markers isn't base64
+    InvalidBase64,
+    /// Wrong magic or a different `REPLAY_FORMAT_VERSION`
+    UnsupportedFormat,
+}
+
+/// Plays back a recording at the speed it was captured
+pub struct ReplayPlayer {
+    snapshots: Vec<WorldSnapshot>,
+    /// The stream ended inside a record; the snapshots before it are kept
+    truncated: bool,
+    /// Next snapshot to apply
+    next: usize,
+    /// Seconds since the last snapshot was applied
+    elapsed: f32,
+}
+
+impl ReplayPlayer {
+    /// Reconstruct a recording from captured serial output
+    ///
+    /// Accepts the text between (and optionally including) the REPLAY BEGIN
+    /// and REPLAY END lines. A stream cut off mid-record still loads the
+    /// snapshots before the cut (see `is_truncated`).
+    pub fn from_serial_bytes(bytes: &[u8]) -> Result<Self, ReplayError> {
+        let stream = base64_decode(replay_body(bytes)).ok_or(ReplayError::InvalidBase64)?;
+        let records = stream
+            .strip_prefix(&REPLAY_MAGIC)
+            .and_then(|rest| rest.split_first())
+            .filter(|(version, _)| **version == REPLAY_FORMAT_VERSION)
+            .map(|(_, records)| records)
+            .ok_or(ReplayError::UnsupportedFormat)?;
+
+        let mut player = Self::empty();
+        let mut rest = records;
+        while let [lo, hi, tail @ ..] = rest {
+            let len = u16::from_le_bytes([*lo, *hi]) as usize;
+            let Some(state) = tail.get(..len).and_then(WorldStateDelta::decode) else {
+                player.truncated = true;
+                break;
+            };
+            player.snapshots.push(WorldSnapshot { state });
+            rest = &tail[len..];
+        }
+        Ok(player)
+    }
+
+    /// A recording with no snapshots (finishes straight away)
+    fn empty() -> Self {
+        Self {
+            snapshots: Vec::new(),
+            truncated: false,
+            next: 0,
+            elapsed: 0.0,
+        }
+    }
+
+    /// Whether the stream ended partway through a snapshot
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Number of snapshots in the recording
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    /// Whether the recording has no snapshots
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    /// Whether every snapshot has been applied
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.snapshots.len()
+    }
+
+    /// Advance playback by `dt` seconds, applying any snapshots that are due
+    /// The first snapshot is applied immediately. Returns false once finished.
+    pub fn advance(&mut self, world: &mut GameWorld, dt: f32) -> bool {
+        let interval = 1.0 / REPLAY_SNAPSHOT_HZ as f32;
+        if self.next == 0 {
+            self.elapsed = interval;
+        } else {
+            self.elapsed += dt;
+        }
+
+        while self.elapsed >= interval && !self.is_finished() {
+            self.snapshots[self.next].apply(world);
+            self.next += 1;
+            self.elapsed -= interval;
+        }
+        !self.is_finished()
+    }
+}
+
+/// Active recorder (only when booted with `record`)
+pub static REPLAY_RECORDER: Mutex<Option<ReplayRecorder>> = Mutex::new(None);
+
+/// Active playback (only in `replay` mode)
+pub static REPLAY_PLAYER: Mutex<Option<ReplayPlayer>> = Mutex::new(None);
+
+/// Allocate the ring buffer and start recording
+pub fn start_recording() {
+    *REPLAY_RECORDER.lock() = Some(ReplayRecorder::new());
+    serial_println!("REPLAY: recording at {} Hz ({} MB buffer)", REPLAY_SNAPSHOT_HZ, REPLAY_BUFFER_SIZE / (1024 * 1024));
+}
+
+/// Feed one world update to the recorder, if recording
+pub fn record(world: &GameWorld) {
+    if let Some(recorder) = REPLAY_RECORDER.lock().as_mut() {
+        recorder.tick(world);
+    }
+}
+
+/// Stop recording and dump the match over serial (once)
+pub fn finish_recording() {
+    let recorder = REPLAY_RECORDER.lock().take();
+    if let Some(recorder) = recorder {
+        recorder.save_to_serial();
+    }
+}
+
+/// Load a captured recording for playback
+pub fn start_playback(bytes: &[u8]) {
+    let player = match ReplayPlayer::from_serial_bytes(bytes) {
+        Ok(player) => player,
+        Err(ReplayError::InvalidBase64) => {
+            serial_println!("REPLAY: invalid base64");
+            ReplayPlayer::empty()
+        }
+        Err(ReplayError::UnsupportedFormat) => {
+            serial_println!("REPLAY: not a v{} recording", REPLAY_FORMAT_VERSION);
+            ReplayPlayer::empty()
+        }
+    };
+    if player.is_truncated() {
+        serial_println!("REPLAY: truncated after {} snapshots", player.len());
+    }
+    serial_println!("REPLAY: loaded {} snapshots", player.len());
+    *REPLAY_PLAYER.lock() = Some(player);
+}
+
+/// Whether a recording is being played back
+pub fn is_playing() -> bool {
+    REPLAY_PLAYER.lock().is_some()
+}
+
+/// Step playback; None if not replaying, Some(false) once the recording ended
+pub fn advance_playback(world: &mut GameWorld, dt: f32) -> Option<bool> {
+    REPLAY_PLAYER.lock().as_mut().map(|player| player.advance(world, dt))
+}
+
+/// The base64 part of captured serial output: the lines between REPLAY BEGIN
+/// and REPLAY END if present, otherwise everything
+fn replay_body(bytes: &[u8]) -> &[u8] {
+    let body = match find(bytes, b"REPLAY BEGIN") {
+        Some(begin) => {
+            let after = &bytes[begin..];
+            after.iter().position(|&b| b == b'\n').map_or(&after[after.len()..], |eol| &after[eol + 1..])
+        }
+        None => bytes,
+    };
+    match find(body, b"REPLAY END") {
+        Some(end) => &body[..end],
+        None => body,
+    }
+}
+
+/// Position of `needle` in `haystack`
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::BASE64_LINE_LEN;
+    use alloc::string::String;
+    use protocol::packets::PlayerState;
+
+    fn snapshot(tick: u32, players: u8) -> WorldSnapshot {
+        WorldSnapshot {
+            state: WorldStateDelta {
+                tick,
+                player_count: players,
+                players: (0..players).map(PlayerState::new).collect(),
+                storm_x: 0,
+                storm_z: 0,
+                storm_radius: 100_000,
+            },
+        }
+    }
+
+    fn captured(recorder: &ReplayRecorder) -> String {
+        let mut text = String::from("noise\nREPLAY BEGIN\n");
+        recorder.write_base64(|line| {
+            assert!(line.len() <= BASE64_LINE_LEN);
+            text.push_str(line);
+            text.push('\n');
+        });
+        text.push_str("REPLAY END\n");
+        text
+    }
+
+    #[test]
+    fn test_recording_roundtrip() {
+        let mut recorder = ReplayRecorder::with_capacity(4096);
+        for tick in 0..5 {
+            recorder.push(&snapshot(tick, 3));
+        }
+        assert_eq!(recorder.snapshot_count(), 5);
+
+        let player = ReplayPlayer::from_serial_bytes(captured(&recorder).as_bytes()).unwrap();
+        assert_eq!(player.len(), 5);
+        assert!(!player.is_truncated());
+        for (i, snap) in player.snapshots.iter().enumerate() {
+            assert_eq!(snap.state.tick, i as u32);
+            assert_eq!(snap.state.players.len(), 3);
+        }
+    }
+
+    #[test]
+    fn test_ring_drops_oldest_when_full() {
+        // Each record is 2 + 17 + 2 * 24 = 67 bytes: room for 4 of them
+        let mut recorder = ReplayRecorder::with_capacity(280);
+        for tick in 0..11 {
+            recorder.push(&snapshot(tick, 2));
+        }
+        assert_eq!(recorder.snapshot_count(), 4);
+
+        // The survivors wrap around the end of the buffer and still decode in order
+        let player = ReplayPlayer::from_serial_bytes(captured(&recorder).as_bytes()).unwrap();
+        let ticks: Vec<u32> = player.snapshots.iter().map(|s| s.state.tick).collect();
+        assert_eq!(ticks, [7, 8, 9, 10]);
+    }
+
+    #[test]
+    fn test_invalid_recording_is_rejected() {
+        let error = |bytes: &[u8]| ReplayPlayer::from_serial_bytes(bytes).err();
+        assert_eq!(error(b""), Some(ReplayError::UnsupportedFormat));
+        assert_eq!(error(b"not base64!"), Some(ReplayError::InvalidBase64));
+        // Valid base64, wrong magic
+        assert_eq!(error(b"AAAAAAE="), Some(ReplayError::UnsupportedFormat));
+    }
+
+    #[test]
+    fn test_truncated_recording_keeps_whole_snapshots() {
+        let mut recorder = ReplayRecorder::with_capacity(4096);
+        for tick in 0..3 {
+            recorder.push(&snapshot(tick, 2));
+        }
+        let text = captured(&recorder);
+        let mut stream = base64_decode(replay_body(text.as_bytes())).unwrap();
+        stream.truncate(stream.len() - 10);
+        let mut cut = String::new();
+        let mut encoder = Base64Lines::new(|line: &str| cut.push_str(line));
+        encoder.write(&stream);
+        encoder.finish();
+
+        let player = ReplayPlayer::from_serial_bytes(cut.as_bytes()).unwrap();
+        assert!(player.is_truncated());
+        assert_eq!(player.len(), 2);
+    }
+}
@@ -2,6 +2,7 @@
 //!
 //! Manages the overall game state transitions from menu to gameplay to victory.
 
+use super::weapon::{Rarity, WeaponType};
 use spin::Mutex;
 
 /// Main game state
@@ -133,11 +134,16 @@ pub enum SettingsOption {
     Sensitivity,
     RenderDistance,
     Volume,
+    FontScale,
+    Fog,
+    Lighting,
+    ForceSoftware,
+    Resolution,
     Back,
 }
 
 impl SettingsOption {
-    pub const COUNT: usize = 6;
+    pub const COUNT: usize = 11;
 
     pub fn from_index(index: usize) -> Self {
         match index % Self::COUNT {
@@ -146,6 +152,11 @@ impl SettingsOption {
             2 => Self::Sensitivity,
             3 => Self::RenderDistance,
             4 => Self::Volume,
+            5 => Self::FontScale,
+            6 => Self::Fog,
+            7 => Self::Lighting,
+            8 => Self::ForceSoftware,
+            9 => Self::Resolution,
             _ => Self::Back,
         }
     }
@@ -157,7 +168,12 @@ impl SettingsOption {
             Self::Sensitivity => 2,
             Self::RenderDistance => 3,
             Self::Volume => 4,
-            Self::Back => 5,
+            Self::FontScale => 5,
+            Self::Fog => 6,
+            Self::Lighting => 7,
+            Self::ForceSoftware => 8,
+            Self::Resolution => 9,
+            Self::Back => 10,
         }
     }
 
@@ -168,16 +184,57 @@ impl SettingsOption {
             Self::Sensitivity => "SENSITIVITY",
             Self::RenderDistance => "RENDER DIST",
             Self::Volume => "VOLUME",
+            Self::FontScale => "FONT SCALE",
+            Self::Fog => "FOG",
+            Self::Lighting => "LIGHTING",
+            Self::ForceSoftware => "FORCE SOFTWARE",
+            Self::Resolution => "RESOLUTION",
             Self::Back => "BACK",
         }
     }
 
     pub fn is_toggle(self) -> bool {
-        matches!(self, Self::ShowFps | Self::InvertY)
+        matches!(self, Self::ShowFps | Self::InvertY | Self::Fog | Self::Lighting | Self::ForceSoftware)
     }
 
     pub fn is_range(self) -> bool {
-        matches!(self, Self::Sensitivity | Self::RenderDistance | Self::Volume)
+        matches!(self, Self::Sensitivity | Self::RenderDistance | Self::Volume | Self::FontScale)
+    }
+}
+
+/// Distance fog setting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FogMode {
+    Off,
+    Near,
+    Far,
+}
+
+impl FogMode {
+    /// Next mode when the option is toggled (OFF -> NEAR -> FAR -> OFF)
+    pub fn next(self) -> Self {
+        match self {
+            Self::Off => Self::Near,
+            Self::Near => Self::Far,
+            Self::Far => Self::Off,
+        }
+    }
+
+    /// View-space (start, end) fog distances, or None when fog is off
+    pub fn range(self) -> Option<(f32, f32)> {
+        match self {
+            Self::Off => None,
+            Self::Near => Some((20.0, 45.0)),
+            Self::Far => Some((40.0, 80.0)),
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Off => "OFF",
+            Self::Near => "NEAR",
+            Self::Far => "FAR",
+        }
     }
 }
 
@@ -189,6 +246,10 @@ pub struct Settings {
     pub sensitivity: u8,      // 1-10
     pub render_distance: u8,  // 1-3
     pub volume: u8,           // 0-100
+    pub font_scale: u8,       // 1-4 (HUD glyph pixel size)
+    pub fog: FogMode,
+    pub lighting_enabled: bool, // per-pixel sunlight in the software renderer
+    pub force_software: bool, // render on the CPU even if the GPU path works
 }
 
 impl Default for Settings {
@@ -199,6 +260,10 @@ impl Default for Settings {
             sensitivity: 5,
             render_distance: 3,
             volume: 80,
+            font_scale: 2,
+            fog: FogMode::Far,
+            lighting_enabled: true,
+            force_software: false,
         }
     }
 }
@@ -212,7 +277,11 @@ impl Settings {
             SettingsOption::Sensitivity => self.sensitivity as i32,
             SettingsOption::RenderDistance => self.render_distance as i32,
             SettingsOption::Volume => self.volume as i32,
-            SettingsOption::Back => 0,
+            SettingsOption::FontScale => self.font_scale as i32,
+            SettingsOption::Fog => self.fog as i32,
+            SettingsOption::Lighting => self.lighting_enabled as i32,
+            SettingsOption::ForceSoftware => self.force_software as i32,
+            SettingsOption::Resolution | SettingsOption::Back => 0,
         }
     }
 
@@ -221,6 +290,9 @@ impl Settings {
         match option {
             SettingsOption::ShowFps => if self.show_fps { "ON" } else { "OFF" },
             SettingsOption::InvertY => if self.invert_y { "ON" } else { "OFF" },
+            SettingsOption::Fog => self.fog.label(),
+            SettingsOption::Lighting => if self.lighting_enabled { "ON" } else { "OFF" },
+            SettingsOption::ForceSoftware => if self.force_software { "ON" } else { "OFF" },
             _ => "", // Numeric values handled differently
         }
     }
@@ -230,6 +302,9 @@ impl Settings {
         match option {
             SettingsOption::ShowFps => self.show_fps = !self.show_fps,
             SettingsOption::InvertY => self.invert_y = !self.invert_y,
+            SettingsOption::Fog => self.fog = self.fog.next(),
+            SettingsOption::Lighting => self.lighting_enabled = !self.lighting_enabled,
+            SettingsOption::ForceSoftware => self.force_software = !self.force_software,
             _ => {}
         }
     }
@@ -249,6 +324,10 @@ impl Settings {
                 let new_val = (self.volume as i16 + delta as i16 * 10).clamp(0, 100);
                 self.volume = new_val as u8;
             }
+            SettingsOption::FontScale => {
+                let new_val = (self.font_scale as i16 + delta as i16).clamp(1, 4);
+                self.font_scale = new_val as u8;
+            }
             _ => {}
         }
     }
@@ -430,6 +509,133 @@ impl LobbyPlayer {
     }
 }
 
+/// Match events kept in the event log
+pub const MAX_MATCH_EVENTS: usize = 16;
+
+/// Most recent events shown in the HUD feed
+pub const KILL_FEED_LINES: usize = 5;
+
+/// Event log clock rate (one tick per GameWorld::update, which steps 1/60 s)
+pub const KILL_FEED_TICK_RATE: u64 = 60;
+
+/// Ticks an event stays in the HUD feed (6 seconds)
+pub const KILL_FEED_TTL_TICKS: u64 = 6 * KILL_FEED_TICK_RATE;
+
+/// Ticks of fade-out at the end of an entry's lifetime
+pub const KILL_FEED_FADE_TICKS: u64 = KILL_FEED_TICK_RATE;
+
+/// One player eliminating another
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Elimination {
+    pub killer_id: u8,
+    pub victim_id: u8,
+    pub weapon_type: WeaponType,
+    /// Rarity of the weapon used (sets the text color)
+    pub rarity: Rarity,
+}
+
+/// Something worth telling every player about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchEvent {
+    Elimination(Elimination),
+    /// The storm started shrinking towards its next circle (1-based)
+    StormPhaseStarted { phase: u8 },
+    /// A player looted a chest near the local player
+    ChestOpened { player_id: u8 },
+}
+
+/// An event and when it happened
+#[derive(Debug, Clone, Copy)]
+pub struct MatchEventEntry {
+    pub event: MatchEvent,
+    /// Event log clock when the event was recorded
+    pub tick: u64,
+}
+
+impl MatchEventEntry {
+    /// Opacity in the HUD feed: 1.0 until the last KILL_FEED_FADE_TICKS,
+    /// then fades to 0.0
+    pub fn alpha(&self, now: u64) -> f32 {
+        let remaining = KILL_FEED_TTL_TICKS.saturating_sub(now.saturating_sub(self.tick));
+        (remaining as f32 / KILL_FEED_FADE_TICKS as f32).clamp(0.0, 1.0)
+    }
+}
+
+/// Ring buffer of recent match events (newest overwrites oldest), plus a
+/// running elimination count per player for the end-of-match stats
+pub struct MatchEventLog {
+    entries: [Option<MatchEventEntry>; MAX_MATCH_EVENTS],
+    /// Slot the next entry is written to
+    head: usize,
+    /// Current event log clock
+    now: u64,
+    eliminations: [u16; 256],
+}
+
+impl MatchEventLog {
+    pub const fn new() -> Self {
+        Self {
+            entries: [None; MAX_MATCH_EVENTS],
+            head: 0,
+            now: 0,
+            eliminations: [0; 256],
+        }
+    }
+
+    /// Forget the previous match
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Current event log clock
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+
+    /// Record an event at the current tick
+    pub fn push(&mut self, event: MatchEvent) {
+        if let MatchEvent::Elimination(kill) = event {
+            let count = &mut self.eliminations[kill.killer_id as usize];
+            *count = count.saturating_add(1);
+        }
+        self.entries[self.head] = Some(MatchEventEntry { event, tick: self.now });
+        self.head = (self.head + 1) % MAX_MATCH_EVENTS;
+    }
+
+    /// Advance the clock one tick
+    pub fn tick(&mut self) {
+        self.now += 1;
+    }
+
+    /// Logged events, oldest first
+    pub fn entries(&self) -> impl Iterator<Item = &MatchEventEntry> {
+        (0..MAX_MATCH_EVENTS).filter_map(move |i| self.entries[(self.head + i) % MAX_MATCH_EVENTS].as_ref())
+    }
+
+    /// The HUD feed: up to KILL_FEED_LINES events that haven't faded out yet,
+    /// oldest first
+    pub fn feed(&self) -> impl Iterator<Item = &MatchEventEntry> {
+        let now = self.now;
+        let live = move |e: &&MatchEventEntry| e.alpha(now) > 0.0;
+        let skip = self.entries().filter(live).count().saturating_sub(KILL_FEED_LINES);
+        self.entries().filter(live).skip(skip)
+    }
+
+    /// Eliminations logged for a player this match
+    pub fn eliminations_by(&self, player_id: u8) -> u16 {
+        self.eliminations[player_id as usize]
+    }
+}
+
+impl Default for MatchEventLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Global match event log
+pub static MATCH_EVENTS: Mutex<MatchEventLog> = Mutex::new(MatchEventLog::new());
+
 /// Global game state
 pub static GAME_STATE: Mutex<GameState> = Mutex::new(GameState::PartyLobby);
 
@@ -443,6 +649,10 @@ pub static SETTINGS: Mutex<Settings> = Mutex::new(Settings {
     sensitivity: 5,
     render_distance: 3,
     volume: 80,
+    font_scale: 2,
+    fog: FogMode::Far,
+    lighting_enabled: true,
+    force_software: false,
 });
 
 /// Local player customization
@@ -492,3 +702,69 @@ pub fn is_gameplay_state() -> bool {
         GameState::BusPhase | GameState::InGame
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kill(killer_id: u8, victim_id: u8) -> MatchEvent {
+        MatchEvent::Elimination(Elimination {
+            killer_id,
+            victim_id,
+            weapon_type: WeaponType::AssaultRifle,
+            rarity: Rarity::Rare,
+        })
+    }
+
+    #[test]
+    fn test_feed_shows_last_five_oldest_first() {
+        let mut log = MatchEventLog::new();
+        for victim in 1..=6 {
+            log.push(kill(0, victim));
+        }
+        log.push(MatchEvent::StormPhaseStarted { phase: 1 });
+        let feed: [MatchEvent; 5] = core::array::from_fn(|i| log.feed().nth(i).unwrap().event);
+        assert_eq!(feed[0], kill(0, 3));
+        assert_eq!(feed[3], kill(0, 6));
+        assert_eq!(feed[4], MatchEvent::StormPhaseStarted { phase: 1 });
+        assert_eq!(log.entries().count(), 7);
+        assert_eq!(log.eliminations_by(0), 6);
+
+        // The log is bounded; the elimination tally is not
+        for victim in 0..MAX_MATCH_EVENTS as u8 {
+            log.push(kill(1, victim));
+        }
+        assert_eq!(log.entries().count(), MAX_MATCH_EVENTS);
+        assert_eq!(log.eliminations_by(0), 6);
+        assert_eq!(log.eliminations_by(1), MAX_MATCH_EVENTS as u16);
+
+        log.clear();
+        assert_eq!(log.entries().count(), 0);
+        assert_eq!(log.eliminations_by(0), 0);
+    }
+
+    #[test]
+    fn test_feed_entries_fade_after_six_seconds() {
+        let mut log = MatchEventLog::new();
+        log.push(kill(1, 2));
+
+        for _ in 0..KILL_FEED_TTL_TICKS - KILL_FEED_FADE_TICKS {
+            log.tick();
+        }
+        let entry = *log.feed().next().unwrap();
+        assert_eq!(entry.alpha(log.now()), 1.0);
+
+        for _ in 0..KILL_FEED_FADE_TICKS / 2 {
+            log.tick();
+        }
+        assert!((entry.alpha(log.now()) - 0.5).abs() < 1e-6);
+
+        for _ in 0..KILL_FEED_FADE_TICKS / 2 {
+            log.tick();
+        }
+        assert_eq!(log.now(), 6 * KILL_FEED_TICK_RATE);
+        assert_eq!(log.feed().count(), 0);
+        // Still in the log, just no longer on screen
+        assert_eq!(log.entries().count(), 1);
+    }
+}
@@ -36,6 +36,18 @@ impl WeaponType {
         }
     }
 
+    /// Abbreviation for compact HUD text (kill feed)
+    pub fn short_name(&self) -> &'static str {
+        match self {
+            Self::Pickaxe => "PICK",
+            Self::Pistol => "PISTOL",
+            Self::Shotgun => "SG",
+            Self::AssaultRifle => "AR",
+            Self::Sniper => "SNIPER",
+            Self::Smg => "SMG",
+        }
+    }
+
     /// Base damage for this weapon type
     pub fn base_damage(&self) -> u8 {
         match self {
@@ -48,18 +60,23 @@ impl WeaponType {
         }
     }
 
-    /// Rounds per second (fire rate)
-    pub fn fire_rate(&self) -> f32 {
+    /// Game ticks (60 Hz) between shots
+    pub fn fire_rate_ticks(&self) -> u8 {
         match self {
-            Self::Pickaxe => 1.0,
-            Self::Pistol => 6.75,
-            Self::Shotgun => 0.7,
-            Self::AssaultRifle => 5.5,
-            Self::Sniper => 0.33,
-            Self::Smg => 12.0,
+            Self::Pickaxe => 30, // 120 swings/min
+            Self::Pistol => 9, // 400 RPM
+            Self::Shotgun => 12, // 300 RPM
+            Self::AssaultRifle => 4, // 900 RPM
+            Self::Sniper => 36, // 100 RPM
+            Self::Smg => 5, // 720 RPM
         }
     }
 
+    /// Semi-automatic: one shot per trigger pull, however long it is held
+    pub fn is_semi_auto(&self) -> bool {
+        matches!(self, Self::Pistol | Self::Sniper)
+    }
+
     /// Magazine size
     pub fn magazine_size(&self) -> u16 {
         match self {
@@ -144,6 +161,18 @@ pub enum Rarity {
 }
 
 impl Rarity {
+    /// Convert from u8 (network protocol)
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::Common),
+            1 => Some(Self::Uncommon),
+            2 => Some(Self::Rare),
+            3 => Some(Self::Epic),
+            4 => Some(Self::Legendary),
+            _ => None,
+        }
+    }
+
     /// Color for this rarity (RGB)
     pub fn color(&self) -> u32 {
         match self {
@@ -185,7 +214,8 @@ pub struct Weapon {
     pub ammo: u16,
     pub max_ammo: u16,
     pub reload_timer: f32,
-    pub fire_cooldown: f32,
+    /// Game ticks until the weapon can fire again
+    pub ticks_until_next_shot: u8,
 }
 
 impl Weapon {
@@ -198,7 +228,7 @@ impl Weapon {
             ammo: max_ammo,
             max_ammo,
             reload_timer: 0.0,
-            fire_cooldown: 0.0,
+            ticks_until_next_shot: 0,
         }
     }
 
@@ -226,9 +256,15 @@ impl Weapon {
         modified as u8
     }
 
-    /// Check if weapon can fire
+    /// Game ticks (60 Hz) between shots
+    pub fn fire_rate_ticks(&self) -> u8 {
+        self.weapon_type.fire_rate_ticks()
+    }
+
+    /// Check if weapon can fire (the pickaxe needs no ammo)
     pub fn can_fire(&self) -> bool {
-        self.fire_cooldown <= 0.0 && self.ammo > 0 && self.reload_timer <= 0.0
+        let loaded = self.ammo > 0 || self.weapon_type == WeaponType::Pickaxe;
+        self.ticks_until_next_shot == 0 && loaded && self.reload_timer <= 0.0
     }
 
     /// Check if weapon is reloading
@@ -236,16 +272,17 @@ impl Weapon {
         self.reload_timer > 0.0
     }
 
-    /// Fire the weapon
-    pub fn fire(&mut self) -> bool {
-        if !self.can_fire() {
+    /// Fire the weapon while the trigger is held; `pulled` is true on the
+    /// tick the trigger went down, which semi-automatic weapons need
+    pub fn fire(&mut self, pulled: bool) -> bool {
+        if !self.can_fire() || (self.weapon_type.is_semi_auto() && !pulled) {
             return false;
         }
 
         if self.weapon_type != WeaponType::Pickaxe {
             self.ammo -= 1;
         }
-        self.fire_cooldown = 1.0 / self.weapon_type.fire_rate();
+        self.ticks_until_next_shot = self.fire_rate_ticks();
         true
     }
 
@@ -256,17 +293,13 @@ impl Weapon {
         }
     }
 
-    /// Update timers
+    /// Update timers (once per game tick)
     pub fn update(&mut self, dt: f32) {
-        if self.fire_cooldown > 0.0 {
-            self.fire_cooldown -= dt;
-        }
+        self.ticks_until_next_shot = self.ticks_until_next_shot.saturating_sub(1);
 
+        // Rounds are loaded when the reload starts; the timer only blocks firing
         if self.reload_timer > 0.0 {
             self.reload_timer -= dt;
-            if self.reload_timer <= 0.0 {
-                self.ammo = self.max_ammo;
-            }
         }
     }
 
@@ -1,29 +1,40 @@
 //! Game world state
 
+use super::audio::{self, SoundEvent, HEARING_RANGE};
 use super::bot::{BotController, BotInput, create_bot_player};
 use super::building::BuildPiece;
 use super::bus::BattleBus;
+use super::chat;
 use super::combat::{self, CombatManager, HitResult};
-use super::loot::{LootManager, LootItem, ChestTier};
-use super::map::{GameMap, VegetationType};
-use super::player::{Player, MAX_PLAYERS};
-use super::state::PlayerPhase;
+use super::inventory::InventoryItem;
+use super::loot::{LootManager, LootItem, LootSpawnType, ChestTier};
+use super::map::{water_body_at, GameMap};
+use super::party::{Squad, REVIVE_HEALTH, REVIVE_RANGE, REVIVE_WINDOW};
+use super::player::{InputHistory, Player, MAX_PLAYERS};
+use super::state::{get_network_mode, Elimination, MatchEvent, NetworkMode, PlayerPhase, MATCH_EVENTS};
 use super::storm::Storm;
 use super::weapon::{AmmoType, WeaponType};
 use alloc::vec::Vec;
 use glam::Vec3;
 use protocol::packets::{ClientInput, PlayerState, WorldStateDelta};
 use smoltcp::wire::Ipv4Address;
-use spin::Mutex;
+use spin::RwLock;
 use alloc::string::String;
-use alloc::format;
 
-/// Kill feed entry
-#[derive(Clone)]
-pub struct KillFeedEntry {
-    pub message: String,
-    pub timer: f32,
-}
+/// Pickaxe reach for harvesting, from the player to a tree or rock's base
+const HARVEST_RANGE: f32 = 5.0;
+
+/// Health a pickaxe swing takes off a tree, bush or rock
+const HARVEST_DAMAGE: u8 = 25;
+
+/// How far from a chest its loot pile spreads
+const CHEST_PILE_RADIUS: f32 = 1.0;
+
+/// Chests opened within this distance of the local player show in the feed
+const CHEST_EVENT_RADIUS: f32 = 40.0;
+
+/// Ticks between storm damage beeps (the damage itself lands every tick)
+const STORM_SOUND_INTERVAL: u32 = 30;
 
 /// Game world
 pub struct GameWorld {
@@ -41,8 +52,13 @@ pub struct GameWorld {
     // Local player ID (for client)
     pub local_player_id: Option<u8>,
 
-    // Kill feed
-    pub kill_feed: Vec<KillFeedEntry>,
+    // Local player inputs not yet acknowledged by the server (client only)
+    pub prediction: InputHistory,
+
+    // Eliminations not yet broadcast to clients (server only)
+    pending_kills: Vec<Elimination>,
+    // Harvest hits not yet sent to clients (vegetation index, remaining health)
+    pending_harvests: Vec<(u16, u8)>,
 
     // Combat manager for hit markers, damage numbers
     pub combat: CombatManager,
@@ -58,6 +74,19 @@ pub struct GameWorld {
 
     // Whether bots have been spawned
     bots_spawned: bool,
+
+    // Squads in squad mode (indexed by Player::squad_id)
+    pub squads: Vec<Squad>,
+
+    // Whether new players are grouped into squads
+    squads_enabled: bool,
+}
+
+/// Set whether a player is wading and return the terrain height under it
+fn ground_player(player: &mut Player, map: &GameMap) -> f32 {
+    player.in_water = player.phase == PlayerPhase::Grounded
+        && water_body_at(player.position.x, player.position.z).is_some();
+    map.get_height_at(player.position.x, player.position.z)
 }
 
 impl GameWorld {
@@ -72,12 +101,91 @@ impl GameWorld {
             is_server,
             changed_players: Vec::new(),
             local_player_id: None,
-            kill_feed: Vec::new(),
+            prediction: InputHistory::new(),
+            pending_kills: Vec::new(),
+            pending_harvests: Vec::new(),
             combat: CombatManager::new(),
             loot: LootManager::new(12345),
             loot_spawned: false,
             bot_controllers: Vec::new(),
             bots_spawned: false,
+            squads: Vec::new(),
+            squads_enabled: false,
+        }
+    }
+
+    /// Group players into squads of four, in join order (existing players too)
+    pub fn enable_squads(&mut self) {
+        self.squads_enabled = true;
+        for id in 0..self.players.len() as u8 {
+            if self.players[id as usize].squad_id.is_none() {
+                self.join_squad(id);
+            }
+        }
+    }
+
+    /// Put a player in the newest squad, starting a new one when it is full
+    fn join_squad(&mut self, player_id: u8) {
+        if !self.squads_enabled {
+            return;
+        }
+        if self.squads.last().is_none_or(|s| s.is_full()) {
+            self.squads.push(Squad::new());
+        }
+        let squad_id = self.squads.len() - 1;
+        self.squads[squad_id].add_member(player_id);
+        if let Some(player) = self.players.get_mut(player_id as usize) {
+            player.squad_id = Some(squad_id as u8);
+        }
+    }
+
+    /// Check if two players are on the same squad
+    pub fn same_squad(&self, a: u8, b: u8) -> bool {
+        let squad = |id: u8| self.players.get(id as usize).and_then(|p| p.squad_id);
+        squad(a).is_some() && squad(a) == squad(b)
+    }
+
+    /// A player's squadmates (not including the player)
+    pub fn teammates(&self, player_id: u8) -> impl Iterator<Item = &Player> + '_ {
+        let squad = self
+            .get_player(player_id)
+            .and_then(|p| p.squad_id)
+            .and_then(|s| self.squads.get(s as usize));
+        squad
+            .into_iter()
+            .flat_map(|s| s.member_ids())
+            .filter(move |&id| id != player_id)
+            .filter_map(|id| self.get_player(id))
+    }
+
+    /// Revive eliminated squad members that a living teammate is standing next to
+    /// Each revive spends one of the squad's shared respawn tokens
+    fn update_revives(&mut self) {
+        for i in 0..self.players.len() {
+            let player = &self.players[i];
+            let Some(squad_id) = player.squad_id else {
+                continue;
+            };
+            if player.is_alive() || player.eliminated_timer > REVIVE_WINDOW {
+                continue;
+            }
+            let Some(squad) = self.squads.get(squad_id as usize) else {
+                continue;
+            };
+            if squad.respawn_tokens == 0 {
+                continue;
+            }
+
+            let position = player.position;
+            let rescuer_nearby = squad.member_ids().any(|id| {
+                self.players.get(id as usize).is_some_and(|t| {
+                    t.is_alive() && t.phase == PlayerPhase::Grounded && t.position.distance(position) <= REVIVE_RANGE
+                })
+            });
+            if rescuer_nearby && self.squads[squad_id as usize].take_token() {
+                self.players[i].revive(REVIVE_HEALTH);
+                self.changed_players.push(i as u8);
+            }
         }
     }
 
@@ -96,6 +204,7 @@ impl GameWorld {
 
         self.players.push(player);
         self.changed_players.push(id);
+        self.join_squad(id);
 
         Some(id)
     }
@@ -109,9 +218,7 @@ impl GameWorld {
         }
 
         // Handle fire input separately (needs immutable borrow of players for hitscan)
-        if input.fire {
-            self.process_fire(player_id);
-        }
+        self.hold_trigger(player_id, input.fire);
 
         // Check for building
         if let Some(player) = self.players.get(player_id as usize) {
@@ -121,8 +228,26 @@ impl GameWorld {
         }
     }
 
+    /// Apply one tick of the fire button; the weapon's own tick counter
+    /// limits how often held fire actually shoots
+    fn hold_trigger(&mut self, player_id: u8, fire: bool) {
+        let pulled = match self.players.get_mut(player_id as usize) {
+            Some(player) => player.pull_trigger(fire),
+            None => return,
+        };
+        if fire {
+            self.process_fire(player_id, pulled);
+        }
+    }
+
     /// Process fire input and perform hitscan
-    fn process_fire(&mut self, player_id: u8) {
+    /// `pulled` is true on the tick the trigger went down
+    fn process_fire(&mut self, player_id: u8, pulled: bool) {
+        // With a consumable selected, firing uses it instead
+        if self.use_consumable(player_id) {
+            return;
+        }
+
         // Get shooter info
         let (origin, direction, weapon_clone, can_fire, is_pickaxe) = {
             let player = match self.players.get(player_id as usize) {
@@ -150,23 +275,37 @@ impl GameWorld {
         // Fire the weapon (consume ammo, set cooldown)
         if let Some(player) = self.players.get_mut(player_id as usize) {
             let weapon = player.inventory.selected_weapon_mut();
-            if !weapon.fire() {
+            if !weapon.fire(pulled) {
                 return;
             }
         }
 
-        // Handle pickaxe harvesting separately
+        // Handle pickaxe harvesting separately; with nothing to harvest in
+        // reach, the swing can still hit a built piece
         if is_pickaxe {
-            self.process_harvest(player_id, origin, direction);
+            if !self.try_harvest(player_id) {
+                self.process_building_hit(player_id, origin, direction);
+            }
             return;
         }
 
+        if self.local_player_id == Some(player_id) {
+            audio::play(SoundEvent::gunshot(0.0));
+        } else if let Some(angle) = self.sound_direction(origin, HEARING_RANGE) {
+            audio::play(SoundEvent::gunshot(angle));
+        }
+
         // Perform hitscan
         let hit_result = combat::hitscan(origin, direction, &weapon_clone, player_id, &self.players);
 
         // Process hit result
         match hit_result {
-            HitResult::PlayerHit { player_id: victim_id, damage, headshot, distance: _ } => {
+            HitResult::PlayerHit { player_id: victim_id, damage, headshot, distance } => {
+                // Squadmates can't hurt each other
+                if self.same_squad(player_id, victim_id) {
+                    return;
+                }
+
                 // Apply damage to victim
                 if let Some(victim) = self.players.get_mut(victim_id as usize) {
                     victim.take_damage(damage, Some(player_id));
@@ -174,9 +313,23 @@ impl GameWorld {
                     // Add hit marker
                     self.combat.add_hit_marker(headshot);
 
-                    // Add damage number at victim position
-                    let victim_pos = victim.position + Vec3::new(0.0, 1.5, 0.0);
-                    self.combat.add_damage_number(victim_pos, damage, headshot);
+                    // Hit confirmation for the shooter, impact for the victim
+                    if self.local_player_id == Some(player_id) {
+                        audio::play(SoundEvent::bullet_hit(0.0));
+                    } else if self.local_player_id == Some(victim_id) {
+                        let (position, yaw) = (victim.position, victim.yaw);
+                        audio::play(SoundEvent::bullet_hit(audio::direction_to(position, yaw, origin)));
+                    }
+
+                    // Floating damage number at the hit point (local player's shots only)
+                    if self.local_player_id == Some(player_id) {
+                        combat::push_damage_number(origin + direction * distance, damage as u16, headshot);
+                    }
+
+                    // Arc pointing back at the shooter when the local player is hit
+                    if self.local_player_id == Some(victim_id) {
+                        combat::push_hit_indicator(victim.position, origin);
+                    }
 
                     // Check for elimination
                     if victim.health == 0 {
@@ -185,19 +338,19 @@ impl GameWorld {
                             killer.record_elimination();
                         }
 
-                        // Get names for kill feed
-                        let killer_name = self.players.get(player_id as usize)
-                            .map(|p| p.name.clone())
-                            .unwrap_or_else(|| String::from("???"));
-                        let victim_name = self.players.get(victim_id as usize)
-                            .map(|p| p.name.clone())
-                            .unwrap_or_else(|| String::from("???"));
-
-                        // Add to world kill feed
-                        self.kill_feed.push(KillFeedEntry {
-                            message: format!("{} eliminated {}", killer_name, victim_name),
-                            timer: 5.0,
-                        });
+                        // Clients get eliminations from the server instead
+                        let kill = Elimination {
+                            killer_id: player_id,
+                            victim_id,
+                            weapon_type: weapon_clone.weapon_type,
+                            rarity: weapon_clone.rarity,
+                        };
+                        if !matches!(get_network_mode(), NetworkMode::Client { .. }) {
+                            MATCH_EVENTS.lock().push(MatchEvent::Elimination(kill));
+                        }
+                        if self.is_server {
+                            self.pending_kills.push(kill);
+                        }
 
                         // Add to combat manager kill feed
                         self.combat.add_kill(player_id, victim_id, weapon_clone.weapon_type, headshot);
@@ -218,80 +371,86 @@ impl GameWorld {
         }
     }
 
-    /// Process pickaxe harvesting
-    fn process_harvest(&mut self, player_id: u8, origin: Vec3, direction: Vec3) {
-        let harvest_range = 3.0; // Pickaxe range
+    /// Swing the pickaxe at the closest tree, bush or rock within HARVEST_RANGE
+    /// Each swing takes HARVEST_DAMAGE off its health; the last one awards its
+    /// materials and removes it (trees leave a stump). Returns false if
+    /// nothing was in reach.
+    pub fn try_harvest(&mut self, player_id: u8) -> bool {
         let player_pos = match self.players.get(player_id as usize) {
             Some(p) => p.position,
-            None => return,
+            None => return false,
         };
 
-        // Check for harvestable vegetation (trees, rocks)
-        let mut best_hit: Option<(usize, f32, VegetationType)> = None;
-
-        for i in 0..self.map.vegetation_count {
-            if let Some(veg) = &self.map.vegetation[i] {
-                let to_veg = veg.position - player_pos;
-                let dist = to_veg.length();
+        let range_sq = HARVEST_RANGE * HARVEST_RANGE;
+        let closest = self.map.vegetation[..self.map.vegetation_count]
+            .iter()
+            .enumerate()
+            .filter_map(|(i, veg)| {
+                let veg = veg.as_ref()?;
+                let dx = veg.position.x - player_pos.x;
+                let dz = veg.position.z - player_pos.z;
+                Some((i, dx * dx + dz * dz))
+            })
+            .filter(|&(_, dist_sq)| dist_sq <= range_sq)
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+        let Some((index, _)) = closest else {
+            return false;
+        };
+        let Some(veg) = self.map.vegetation[index].as_mut() else {
+            return false;
+        };
 
-                if dist > harvest_range + 2.0 {
-                    continue; // Too far
-                }
+        veg.health = veg.health.saturating_sub(HARVEST_DAMAGE);
+        let (veg_type, position, health) = (veg.veg_type, veg.position, veg.health);
+        if self.is_server {
+            self.pending_harvests.push((index as u16, health));
+        }
+        if health > 0 {
+            return true;
+        }
 
-                // Simple raycast check - does ray pass near vegetation?
-                let t = direction.dot(to_veg);
-                if t < 0.0 || t > harvest_range {
-                    continue; // Behind player or too far
-                }
+        let (wood, brick) = veg_type.harvest_materials();
+        if let Some(player) = self.players.get_mut(player_id as usize) {
+            player.inventory.materials.add_wood(wood);
+            player.inventory.materials.add_brick(brick);
+        }
 
-                let closest_point = origin + direction * t;
-                let dist_to_veg = (closest_point - veg.position).length();
-
-                // Vegetation hitbox size depends on type
-                let hitbox_radius = match veg.veg_type {
-                    VegetationType::TreePine | VegetationType::TreeOak | VegetationType::TreeBirch => 1.5,
-                    VegetationType::Bush => 1.0,
-                    VegetationType::Rock => 1.2,
-                };
-
-                if dist_to_veg < hitbox_radius {
-                    // Hit! Check if closest
-                    match &best_hit {
-                        Some((_, best_dist, _)) if *best_dist <= t => {}
-                        _ => best_hit = Some((i, t, veg.veg_type)),
-                    }
-                }
-            }
+        // Visual feedback (damage number showing materials gained)
+        if self.local_player_id == Some(player_id) {
+            combat::push_damage_number(position + Vec3::new(0.0, 1.5, 0.0), (wood + brick) as u16, false);
         }
 
-        // Apply harvest reward
-        if let Some((veg_idx, _, veg_type)) = best_hit {
-            // Give materials based on vegetation type
-            let (wood, brick, metal) = match veg_type {
-                VegetationType::TreePine | VegetationType::TreeOak | VegetationType::TreeBirch => (15, 0, 0),
-                VegetationType::Bush => (5, 0, 0),
-                VegetationType::Rock => (0, 10, 5),
-            };
+        self.map.remove_vegetation(index, self.tick);
+        true
+    }
 
-            if let Some(player) = self.players.get_mut(player_id as usize) {
-                player.inventory.materials.add_wood(wood);
-                player.inventory.materials.add_brick(brick);
-                player.inventory.materials.add_metal(metal);
-            }
+    /// Apply a harvest reported by the server: vegetation `index` is down to
+    /// `health`. Reports may repeat or arrive out of order, so health only
+    /// ever goes down.
+    pub fn apply_harvest(&mut self, index: u16, health: u8) {
+        let Some(veg) = self.map.vegetation.get_mut(index as usize).and_then(Option::as_mut) else {
+            return;
+        };
+        veg.health = veg.health.min(health);
+        if veg.health == 0 {
+            self.map.remove_vegetation(index as usize, self.tick);
+        }
+    }
 
-            // Add visual feedback (damage number showing materials gained)
-            if let Some(veg) = &self.map.vegetation[veg_idx] {
-                let hit_pos = veg.position + Vec3::new(0.0, 1.5, 0.0);
-                self.combat.add_damage_number(hit_pos, (wood + brick + metal) as u8, false);
-            }
+    /// Take harvests recorded since the last call as (vegetation index,
+    /// remaining health), for broadcast to clients
+    pub fn take_pending_harvests(&mut self) -> Vec<(u16, u8)> {
+        core::mem::take(&mut self.pending_harvests)
+    }
 
-            // Remove vegetation after enough hits (simple: remove immediately for now)
-            // In a full implementation, vegetation would have health
-            self.map.vegetation[veg_idx] = None;
-            self.map.vegetation_count = self.map.vegetation_count.saturating_sub(1);
-        }
+    /// Pickaxe swing at player-built pieces (for material recovery)
+    fn process_building_hit(&mut self, player_id: u8, origin: Vec3, direction: Vec3) {
+        let harvest_range = 3.0; // Pickaxe range
+        let player_pos = match self.players.get(player_id as usize) {
+            Some(p) => p.position,
+            None => return,
+        };
 
-        // Also check for hitting player buildings (for material recovery)
         let mut building_hit_idx: Option<usize> = None;
         for (i, building) in self.buildings.iter().enumerate() {
             if building.is_destroyed() {
@@ -330,8 +489,10 @@ impl GameWorld {
             }
 
             // Visual feedback
-            let hit_pos = building.position + Vec3::new(0.0, 1.0, 0.0);
-            self.combat.add_damage_number(hit_pos, 50, false);
+            if self.local_player_id == Some(player_id) {
+                let hit_pos = building.position + Vec3::new(0.0, 1.0, 0.0);
+                combat::push_damage_number(hit_pos, 50, false);
+            }
         }
     }
 
@@ -346,11 +507,21 @@ impl GameWorld {
         let forward = player.forward();
         let build_pos = player.position + forward * 4.0;
 
+        // Never wall the builder in (the box shrinks while crouched)
         let piece = BuildPiece::wall(build_pos, player.yaw);
+        let (min, max) = player.aabb();
+        if piece.overlaps_aabb(min, max) {
+            return;
+        }
         self.buildings.push(piece);
         player.inventory.materials.wood -= 10;
     }
 
+    /// Take eliminations recorded since the last call (for broadcast to clients)
+    pub fn take_pending_kills(&mut self) -> Vec<Elimination> {
+        core::mem::take(&mut self.pending_kills)
+    }
+
     /// Update the world (server tick)
     pub fn update(&mut self, dt: f32) {
         self.tick += 1;
@@ -368,24 +539,28 @@ impl GameWorld {
         }
 
         // Update players with terrain height
+        let storm_beep = self.tick.is_multiple_of(STORM_SOUND_INTERVAL);
         for player in &mut self.players {
-            let terrain_height = self.map.get_height_at(player.position.x, player.position.z);
+            let terrain_height = ground_player(player, &self.map);
             player.update(dt, &self.buildings, terrain_height);
 
             // Storm damage (no attacker)
             if player.is_alive() && !self.storm.contains(player.position) {
                 player.take_damage(self.storm.damage_per_tick(), None);
+                if storm_beep && self.local_player_id == Some(player.id) {
+                    audio::play(SoundEvent::storm_damage());
+                }
             }
         }
 
-        // Update kill feed timers
-        self.kill_feed.retain_mut(|entry| {
-            entry.timer -= dt;
-            entry.timer > 0.0
-        });
+        // Advance the event log clock (fades out old feed entries)
+        MATCH_EVENTS.lock().tick();
 
         // Update combat effects (hit markers, damage numbers)
         self.combat.update(dt);
+        combat::update_damage_numbers(dt);
+        combat::update_hit_indicators(dt);
+        chat::CHAT_LOG.lock().update(dt);
 
         // Update loot drops
         self.loot.update(dt);
@@ -395,12 +570,20 @@ impl GameWorld {
             self.spawn_world_loot();
         }
 
-        // Update storm
+        // Update storm, announcing each new shrink
+        let was_shrinking = self.storm.shrinking;
         self.storm.update(dt);
+        if self.storm.shrinking && !was_shrinking {
+            let phase = (self.storm.phase + 1) as u8;
+            MATCH_EVENTS.lock().push(MatchEvent::StormPhaseStarted { phase });
+        }
 
         // Update bot AI and apply their inputs
         self.update_bots(dt);
 
+        // Teammates pick up eliminated squad members
+        self.update_revives();
+
         // Track all players as changed for simplicity
         // A more optimized version would only track actually changed players
         for player in &self.players {
@@ -428,6 +611,20 @@ impl GameWorld {
         }
     }
 
+    /// Full world state (every player, not just changed ones) for replay recording
+    pub fn snapshot(&self) -> WorldStateDelta {
+        let players: Vec<PlayerState> = self.players.iter().map(|p| p.to_state()).collect();
+
+        WorldStateDelta {
+            tick: self.tick,
+            player_count: players.len() as u8,
+            players,
+            storm_x: (self.storm.center.x * 65536.0) as i32,
+            storm_z: (self.storm.center.z * 65536.0) as i32,
+            storm_radius: (self.storm.radius * 100.0) as u32,
+        }
+    }
+
     /// Clear the changed players list after sending delta
     pub fn clear_delta(&mut self) {
         self.changed_players.clear();
@@ -443,6 +640,7 @@ impl GameWorld {
         self.storm.radius = delta.storm_radius as f32 / 100.0;
 
         // Update players
+        let predicting = matches!(get_network_mode(), NetworkMode::Client { .. });
         for state in &delta.players {
             let id = state.player_id as usize;
 
@@ -457,12 +655,27 @@ impl GameWorld {
             }
 
             let player = &mut self.players[id];
-            player.position = Vec3::new(state.world_x(), state.world_y(), state.world_z());
-            player.yaw = state.yaw_radians();
-            player.pitch = state.pitch_radians();
-            player.health = state.health;
-            player.set_network_weapon(state.weapon_id);
-            player.flags = state.state;
+            if predicting && self.local_player_id == Some(state.player_id) {
+                // Our own player runs ahead of the server; only correct it
+                let (map, buildings) = (&self.map, &self.buildings);
+                player.reconcile(state, &mut self.prediction, |p, dt| {
+                    let terrain_height = ground_player(p, map);
+                    p.update_movement(dt, buildings, terrain_height);
+                });
+            } else {
+                player.apply_state(state);
+            }
+        }
+    }
+
+    /// Remember the local player's input and the physics step after it
+    /// The input must already have been applied and the world updated.
+    pub fn record_prediction(&mut self, input: ClientInput, dt: f32) {
+        let Some(id) = self.local_player_id else {
+            return;
+        };
+        if let Some(player) = self.players.get(id as usize) {
+            self.prediction.record(input, dt, player);
         }
     }
 
@@ -524,15 +737,15 @@ impl GameWorld {
 
     /// Try to pick up loot for a player
     pub fn try_pickup(&mut self, player_id: u8) -> bool {
-        let player_pos = match self.players.get(player_id as usize) {
-            Some(p) => p.position,
+        let player = match self.players.get(player_id as usize) {
+            Some(p) => p,
             None => return false,
         };
 
-        // Find nearest loot
-        let pickup = self.loot.get_nearest_pickup(player_pos);
-        let pickup_id = match pickup {
-            Some(drop) => drop.id,
+        // Find nearest loot, measured from the player's collision box
+        let pickup = self.loot.get_nearest_pickup(|position| player.distance_to_aabb(position));
+        let (pickup_id, pickup_position) = match pickup {
+            Some(drop) => (drop.id, drop.position),
             None => return false,
         };
 
@@ -541,14 +754,15 @@ impl GameWorld {
             Some(item) => item,
             None => return false,
         };
+        self.open_chest_at(pickup_position, player_id);
 
         // Add to player inventory
         if let Some(player) = self.players.get_mut(player_id as usize) {
             match item {
                 LootItem::Weapon(weapon) => {
-                    // If inventory full, drop current weapon
+                    // If inventory full, drop current slot's contents
                     if let Some(dropped) = player.inventory.add_weapon(weapon) {
-                        self.loot.spawn_drop(player.position, LootItem::Weapon(dropped), true);
+                        self.loot.spawn_inventory_drop(player.position, dropped);
                     }
                 }
                 LootItem::Ammo { ammo_type, amount } => {
@@ -559,11 +773,15 @@ impl GameWorld {
                     player.inventory.materials.add_brick(brick);
                     player.inventory.materials.add_metal(metal);
                 }
-                LootItem::Health { amount, max_health, .. } => {
-                    player.heal(amount, max_health);
-                }
-                LootItem::Shield { amount, .. } => {
-                    player.add_shield(amount);
+                LootItem::Health { .. } | LootItem::Shield { .. } => {
+                    // Carried in a slot until used; left on the ground if there's no room
+                    let no_room = item.consumable_kind().is_some_and(|kind| {
+                        player.inventory.try_add_item(InventoryItem::consumable(kind, 1)).is_some()
+                    });
+                    if no_room {
+                        self.loot.spawn_drop(player.position, item, true);
+                        return false;
+                    }
                 }
             }
             return true;
@@ -572,15 +790,104 @@ impl GameWorld {
         false
     }
 
-    /// Check for victory condition (last player standing)
+    /// Direction of a sound at `position` from the local player, or None if
+    /// there's no local player within `range` to hear it
+    fn sound_direction(&self, position: Vec3, range: f32) -> Option<f32> {
+        let listener = self.players.get(self.local_player_id? as usize)?;
+        (listener.position.distance(position) <= range)
+            .then(|| audio::direction_to(listener.position, listener.yaw, position))
+    }
+
+    /// Mark the chest whose loot pile `position` belongs to as opened, logging
+    /// it when the local player is close enough to notice
+    fn open_chest_at(&mut self, position: Vec3, player_id: u8) {
+        let local_position = self
+            .local_player_id
+            .and_then(|id| self.players.get(id as usize))
+            .map(|p| p.position);
+
+        let spawns = self.map.loot_spawns[..self.map.loot_spawn_count].iter_mut().flatten();
+        for spawn in spawns {
+            if spawn.opened
+                || !matches!(spawn.spawn_type, LootSpawnType::Chest(_))
+                || spawn.position.distance(position) > CHEST_PILE_RADIUS
+            {
+                continue;
+            }
+            spawn.opened = true;
+            if local_position.is_some_and(|p| p.distance(spawn.position) <= CHEST_EVENT_RADIUS) {
+                MATCH_EVENTS.lock().push(MatchEvent::ChestOpened { player_id });
+            }
+            let chest_position = spawn.position;
+            if let Some(angle) = self.sound_direction(chest_position, CHEST_EVENT_RADIUS) {
+                audio::play(SoundEvent::chest_opened(angle));
+            }
+            return;
+        }
+    }
+
+    /// Drop the weapon (or one stacked item) in one of a player's slots as
+    /// loot at their feet
+    pub fn drop_weapon(&mut self, player_id: u8, slot: usize) -> bool {
+        let player = match self.players.get_mut(player_id as usize) {
+            Some(p) => p,
+            None => return false,
+        };
+
+        match player.inventory.drop_slot(slot) {
+            Some(item) => {
+                self.loot.spawn_inventory_drop(player.position, item);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Use one item from a grounded player's selected consumable stack
+    /// Returns true when the selected slot holds consumables, used or not,
+    /// so firing doesn't fall through to the pickaxe
+    fn use_consumable(&mut self, player_id: u8) -> bool {
+        let player = match self.players.get_mut(player_id as usize) {
+            Some(p) => p,
+            None => return false,
+        };
+        if player.inventory.selected_item().and_then(InventoryItem::consumable_kind).is_none() {
+            return false;
+        }
+
+        if player.phase != PlayerPhase::Grounded {
+            return true;
+        }
+        if let Some(kind) = player.inventory.use_selected_consumable() {
+            match LootItem::from_consumable(kind) {
+                LootItem::Health { amount, max_health, .. } => player.heal(amount, max_health),
+                LootItem::Shield { amount, .. } => player.add_shield(amount),
+                LootItem::Ammo { ammo_type, amount } => player.inventory.ammo.add(ammo_type, amount),
+                LootItem::Weapon(_) | LootItem::Materials { .. } => {}
+            }
+        }
+        true
+    }
+
+    /// Drop a player's currently selected weapon (no-op for the pickaxe)
+    pub fn drop_selected_weapon(&mut self, player_id: u8) -> bool {
+        let slot = match self.players.get(player_id as usize) {
+            Some(p) if !p.inventory.pickaxe_selected => p.inventory.selected_slot,
+            _ => return false,
+        };
+        self.drop_weapon(player_id, slot)
+    }
+
+    /// Check for the last player (or in squad mode, the last squad) standing
+    /// Returns a surviving player's ID
     pub fn check_victory(&self) -> Option<u8> {
-        let alive: Vec<u8> = self.players.iter()
-            .filter(|p| p.is_alive())
-            .map(|p| p.id)
-            .collect();
+        // Solo players are their own team
+        let team = |p: &Player| p.squad_id.map_or(p.id as u16, |s| MAX_PLAYERS as u16 + s as u16);
+        let mut alive = self.players.iter().filter(|p| p.is_alive());
+        let first = alive.next()?;
 
-        if alive.len() == 1 {
-            Some(alive[0])
+        if alive.all(|p| team(p) == team(first)) {
+            Some(first.id)
         } else {
             None
         }
@@ -621,6 +928,7 @@ impl GameWorld {
             bot.phase = PlayerPhase::Grounded;
 
             self.players.push(bot);
+            self.join_squad(id);
 
             // Ensure bot_controllers vec is large enough
             while self.bot_controllers.len() <= id as usize {
@@ -689,16 +997,55 @@ impl GameWorld {
         }
 
         // Handle firing
-        if input.fire {
-            self.process_fire(bot_id);
-        }
+        self.hold_trigger(bot_id, input.fire);
     }
 }
 
+/// Lock around the game world: any number of readers (render cores binning
+/// meshes, the HUD, the minimap) or one writer (input, physics, network)
+pub type WorldLock = RwLock<Option<GameWorld>>;
+
 /// Global game world
-pub static GAME_WORLD: Mutex<Option<GameWorld>> = Mutex::new(None);
+/// Take `read()` for anything that only looks at it, so render cores don't
+/// serialize on it; `write()` for apply_input, update and other changes.
+pub static GAME_WORLD: WorldLock = RwLock::new(None);
+
+/// Time of day matches are played at (hours), which places the sun
+const MATCH_HOUR: f32 = 14.5;
 
 /// Initialize the game world
 pub fn init(is_server: bool) {
-    *GAME_WORLD.lock() = Some(GameWorld::new(is_server));
+    crate::graphics::pipeline::set_light(crate::graphics::pipeline::LightState::at_hour(MATCH_HOUR));
+    let mut world = GameWorld::new(is_server);
+    MATCH_EVENTS.lock().clear();
+    if crate::boot::config().squads {
+        world.enable_squads();
+    }
+    *GAME_WORLD.write() = Some(world);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_world_lock_readers_and_writer() {
+        let lock = WorldLock::new(None);
+
+        // Readers share the lock and shut out writers
+        let first = lock.read();
+        let second = lock.try_read().expect("second reader");
+        assert!(first.is_none() && second.is_none());
+        assert!(lock.try_write().is_none());
+        drop(first);
+        assert!(lock.try_write().is_none());
+        drop(second);
+
+        // A writer shuts out readers and other writers
+        let writer = lock.try_write().expect("writer once readers are gone");
+        assert!(lock.try_read().is_none());
+        assert!(lock.try_write().is_none());
+        drop(writer);
+        assert!(lock.try_read().is_some());
+    }
 }
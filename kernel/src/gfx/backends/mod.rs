@@ -1,6 +1,57 @@
 //! Graphics Backends
 //!
-//! Backend implementations for software and hardware rendering.
+//! Backend implementations for software and hardware rendering. A Device
+//! replays command buffers through one `GfxBackend`, picked once when the
+//! device is created, so app code recorded against gfx runs on either.
 
 pub mod software;
 pub mod svga3d;
+
+use super::device::{Backend, GpuTriangle};
+use super::pipeline::BufferDesc;
+use glam::Mat4;
+use renderer::vertex::Vertex;
+
+/// Operations a Device dispatches to its backend
+pub trait GfxBackend {
+    /// Which backend this is
+    fn kind(&self) -> Backend;
+
+    /// Human-readable backend name
+    fn name(&self) -> &'static str;
+
+    /// Video memory in bytes (0 when drawing into system memory)
+    fn vram_size(&self) -> usize;
+
+    /// Largest texture side in texels (0 if the device doesn't say)
+    fn max_texture_size(&self) -> u32;
+
+    /// Back a new buffer with device memory
+    /// Returns the backend's id for it, None if the buffer stays in RAM.
+    fn create_buffer(&self, desc: &BufferDesc) -> Option<u32>;
+
+    /// Release device memory returned by create_buffer
+    fn destroy_buffer(&self, id: u32);
+
+    /// Start drawing into the display's color and depth targets
+    fn begin_render_pass(&self);
+
+    /// Finish the render pass and hand the frame to the display
+    fn end_render_pass(&self);
+
+    /// Clear the color and/or depth target (ARGB color)
+    fn clear(&self, color: Option<u32>, depth: Option<f32>);
+
+    /// Draw screen-space triangles with packed colors
+    fn draw_triangles(&self, triangles: &[GpuTriangle]);
+
+    /// Draw projected, clipped mesh triangles
+    /// `projection` maps their positions' 1/w back to depth.
+    fn draw_projected(&self, triangles: &[[Vertex; 3]], projection: &Mat4);
+
+    /// Fill a screen rectangle (2D)
+    fn fill_rect(&self, x: i32, y: i32, width: u32, height: u32, color: u32);
+
+    /// Show the finished frame
+    fn present(&self);
+}
@@ -1,39 +1,71 @@
 //! Software Rendering Backend
 //!
-//! Uses the existing tile-based parallel software rasterizer.
+//! Draws into the framebuffer's back buffer with the software rasterizer.
 
+use super::GfxBackend;
 use crate::api::types::Color;
-use crate::gfx::device::{GpuTriangle, GpuVertex};
-use crate::graphics::rasterizer::RenderContext;
+use crate::gfx::device::{Backend, GpuTriangle, GpuVertex};
+use crate::gfx::pipeline::BufferDesc;
+use crate::graphics::rasterizer::{rasterize_triangle_with_context, RenderContext};
+use glam::{Mat4, Vec3};
 use renderer::vertex::Vertex;
-use glam::Vec3;
+
+/// Largest texture side; textures live in the heap, this keeps one under 256 MiB
+const MAX_TEXTURE_SIZE: u32 = 8192;
 
 /// Software renderer backend
-pub struct SoftwareBackend {
-    width: u32,
-    height: u32,
-}
+#[derive(Default)]
+pub struct SoftwareBackend;
 
 impl SoftwareBackend {
-    pub fn new(width: u32, height: u32) -> Self {
-        Self { width, height }
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl GfxBackend for SoftwareBackend {
+    fn kind(&self) -> Backend {
+        Backend::Software
+    }
+
+    fn name(&self) -> &'static str {
+        "Software"
+    }
+
+    fn vram_size(&self) -> usize {
+        0
+    }
+
+    fn max_texture_size(&self) -> u32 {
+        MAX_TEXTURE_SIZE
     }
 
-    /// Clear the framebuffer
-    pub fn clear(&self, color: Color) {
-        crate::graphics::gpu::clear(color.to_u32());
+    fn create_buffer(&self, _desc: &BufferDesc) -> Option<u32> {
+        // The device's RAM copy is what the rasterizer reads
+        None
     }
 
-    /// Clear the depth buffer
-    pub fn clear_depth(&self) {
-        crate::graphics::zbuffer::clear();
+    fn destroy_buffer(&self, _id: u32) {}
+
+    fn begin_render_pass(&self) {}
+
+    fn end_render_pass(&self) {}
+
+    fn clear(&self, color: Option<u32>, depth: Option<f32>) {
+        let Some(ctx) = RenderContext::acquire() else {
+            return;
+        };
+        if let Some(color) = color {
+            ctx.clear(color);
+        }
+        if depth.is_some() {
+            ctx.clear_zbuffer();
+        }
     }
 
-    /// Draw triangles
-    pub fn draw_triangles(&self, triangles: &[GpuTriangle]) {
-        let ctx = match RenderContext::acquire() {
-            Some(ctx) => ctx,
-            None => return,
+    fn draw_triangles(&self, triangles: &[GpuTriangle]) {
+        let Some(ctx) = RenderContext::acquire() else {
+            return;
         };
 
         for tri in triangles {
@@ -41,37 +73,24 @@ impl SoftwareBackend {
             let v1 = gpu_vertex_to_renderer(&tri.v1);
             let v2 = gpu_vertex_to_renderer(&tri.v2);
 
-            crate::graphics::rasterizer::rasterize_triangle_with_context(&ctx, &v0, &v1, &v2);
+            rasterize_triangle_with_context(&ctx, &v0, &v1, &v2);
         }
     }
 
-    /// Draw a single triangle
-    pub fn draw_triangle(&self, v0: &GpuVertex, v1: &GpuVertex, v2: &GpuVertex) {
-        let ctx = match RenderContext::acquire() {
-            Some(ctx) => ctx,
-            None => return,
+    fn draw_projected(&self, triangles: &[[Vertex; 3]], _projection: &Mat4) {
+        let Some(ctx) = RenderContext::acquire() else {
+            return;
         };
-
-        let rv0 = gpu_vertex_to_renderer(v0);
-        let rv1 = gpu_vertex_to_renderer(v1);
-        let rv2 = gpu_vertex_to_renderer(v2);
-
-        crate::graphics::rasterizer::rasterize_triangle_with_context(&ctx, &rv0, &rv1, &rv2);
+        for [v0, v1, v2] in triangles {
+            rasterize_triangle_with_context(&ctx, v0, v1, v2);
+        }
     }
 
-    /// Fill a rectangle
-    pub fn fill_rect(&self, x: i32, y: i32, width: u32, height: u32, color: Color) {
-        crate::graphics::gpu::fill_rect(
-            x as usize,
-            y as usize,
-            width as usize,
-            height as usize,
-            color.to_u32(),
-        );
+    fn fill_rect(&self, x: i32, y: i32, width: u32, height: u32, color: u32) {
+        crate::graphics::gpu::fill_rect(x as usize, y as usize, width as usize, height as usize, color);
     }
 
-    /// Present the frame
-    pub fn present(&self) {
+    fn present(&self) {
         crate::graphics::gpu::present();
     }
 }
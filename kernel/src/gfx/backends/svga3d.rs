@@ -1,92 +1,145 @@
 //! SVGA3D Rendering Backend
 //!
-//! Uses the GPU batch renderer for hardware-accelerated 3D rendering.
+//! Maps gfx onto the VMSVGA 3D driver: buffers become SVGA3D surfaces, a
+//! render pass binds the GPU batch's render targets and clears them, and
+//! draws feed the GPU batch, which issues FIFO draw-primitive commands.
 
-use crate::api::types::Color;
-use crate::gfx::device::{GpuTriangle, GpuVertex};
+use super::GfxBackend;
+use crate::drivers::vmsvga::{self, svga3d};
+use crate::gfx::device::{Backend, GpuTriangle};
+use crate::gfx::pipeline::{BufferDesc, BufferUsage};
 use crate::graphics::gpu_batch;
+use glam::Mat4;
+use renderer::vertex::Vertex;
 
 /// SVGA3D renderer backend
-pub struct Svga3DBackend {
-    width: u32,
-    height: u32,
-    initialized: bool,
-}
+#[derive(Default)]
+pub struct Svga3DBackend;
 
 impl Svga3DBackend {
-    pub fn new(width: u32, height: u32) -> Self {
-        let initialized = crate::graphics::gpu::has_3d();
-        Self {
-            width,
-            height,
-            initialized,
-        }
+    pub fn new() -> Self {
+        Self
     }
 
-    /// Check if the backend is available
-    pub fn is_available(&self) -> bool {
-        self.initialized
+    /// Whether the 3D device and the GPU batch it draws through are up
+    pub fn is_available() -> bool {
+        vmsvga::is_3d_available() && gpu_batch::is_enabled() && !gpu_batch::has_failed()
     }
 
-    /// Begin a new batch
-    pub fn begin_batch(&self) {
-        if self.initialized {
-            gpu_batch::begin_batch();
+    /// Queue one triangle, flushing the batch once if it is full
+    fn queue(add: impl Fn() -> bool) {
+        if !add() {
+            gpu_batch::flush_batch();
+            add();
         }
     }
+}
+
+/// Surface hints for a buffer of `usage`
+fn surface_flags(usage: BufferUsage) -> u32 {
+    match usage {
+        BufferUsage::Vertex => svga3d::surface_flags::HINT_VERTEXBUFFER,
+        BufferUsage::Index => svga3d::surface_flags::HINT_INDEXBUFFER,
+        BufferUsage::Uniform | BufferUsage::Storage => svga3d::surface_flags::HINT_DYNAMIC,
+    }
+}
+
+impl GfxBackend for Svga3DBackend {
+    fn kind(&self) -> Backend {
+        Backend::Svga3D
+    }
+
+    fn name(&self) -> &'static str {
+        "SVGA3D"
+    }
+
+    fn vram_size(&self) -> usize {
+        vmsvga::VMSVGA_DEVICE.lock().vram_size()
+    }
+
+    fn max_texture_size(&self) -> u32 {
+        vmsvga::VMSVGA_DEVICE.lock().max_texture_size().unwrap_or(0)
+    }
 
-    /// End the current batch
-    pub fn end_batch(&self) {
-        if self.initialized {
-            gpu_batch::end_batch();
+    fn create_buffer(&self, desc: &BufferDesc) -> Option<u32> {
+        vmsvga::create_3d_surface(
+            svga3d::SurfaceFormat::Buffer,
+            desc.size as u32,
+            1,
+            1,
+            surface_flags(desc.usage),
+            1,
+        )
+    }
+
+    fn destroy_buffer(&self, id: u32) {
+        vmsvga::destroy_3d_surface(id);
+    }
+
+    fn begin_render_pass(&self) {
+        gpu_batch::begin_batch();
+        if let Some((cid, color, depth)) = gpu_batch::render_targets() {
+            vmsvga::set_3d_render_target(cid, color, depth);
         }
     }
 
-    /// Clear the framebuffer
-    pub fn clear(&self, color: Color) {
-        crate::graphics::gpu::clear(color.to_u32());
+    fn end_render_pass(&self) {
+        // Flushes the last triangles and presents the color target
+        gpu_batch::end_batch();
     }
 
-    /// Clear the depth buffer
-    pub fn clear_depth(&self) {
-        crate::graphics::zbuffer::clear();
+    fn clear(&self, color: Option<u32>, depth: Option<f32>) {
+        match (color, depth) {
+            (Some(color), Some(depth)) => {
+                gpu_batch::flush_batch();
+                if let Some((cid, _, _)) = gpu_batch::render_targets() {
+                    vmsvga::clear_3d(cid, color, depth);
+                }
+            }
+            _ => gpu_batch::clear(color, depth),
+        }
     }
 
-    /// Add a triangle to the batch
-    pub fn add_triangle(&self, v0: GpuVertex, v1: GpuVertex, v2: GpuVertex) {
-        if self.initialized {
-            gpu_batch::add_triangle_verts(
+    fn draw_triangles(&self, triangles: &[GpuTriangle]) {
+        for tri in triangles {
+            let (v0, v1, v2) = (tri.v0, tri.v1, tri.v2);
+            Self::queue(|| gpu_batch::add_triangle_verts(
                 v0.x, v0.y, v0.z, v0.color,
                 v1.x, v1.y, v1.z, v1.color,
                 v2.x, v2.y, v2.z, v2.color,
-            );
+            ));
         }
     }
 
-    /// Draw triangles
-    pub fn draw_triangles(&self, triangles: &[GpuTriangle]) {
-        if !self.initialized {
-            return;
-        }
-
-        for tri in triangles {
-            self.add_triangle(tri.v0, tri.v1, tri.v2);
+    fn draw_projected(&self, triangles: &[[Vertex; 3]], projection: &Mat4) {
+        let depth = |v: &Vertex| gpu_batch::screen_depth(projection, v.position.z);
+        for [t0, t1, t2] in triangles {
+            Self::queue(|| gpu_batch::add_screen_triangle(
+                t0.position.x, t0.position.y, depth(t0), t0.color.x, t0.color.y, t0.color.z,
+                t1.position.x, t1.position.y, depth(t1), t1.color.x, t1.color.y, t1.color.z,
+                t2.position.x, t2.position.y, depth(t2), t2.color.x, t2.color.y, t2.color.z,
+            ));
         }
     }
 
-    /// Fill a rectangle (uses software path for 2D)
-    pub fn fill_rect(&self, x: i32, y: i32, width: u32, height: u32, color: Color) {
-        crate::graphics::gpu::fill_rect(
-            x as usize,
-            y as usize,
-            width as usize,
-            height as usize,
-            color.to_u32(),
-        );
+    fn fill_rect(&self, x: i32, y: i32, width: u32, height: u32, color: u32) {
+        // 2D goes to the software framebuffer, as for the rest of the UI
+        crate::graphics::gpu::fill_rect(x as usize, y as usize, width as usize, height as usize, color);
     }
 
-    /// Present the frame
-    pub fn present(&self) {
+    fn present(&self) {
         crate::graphics::gpu::present();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buffer_surface_hints() {
+        assert_eq!(surface_flags(BufferUsage::Vertex), svga3d::surface_flags::HINT_VERTEXBUFFER);
+        assert_eq!(surface_flags(BufferUsage::Index), svga3d::surface_flags::HINT_INDEXBUFFER);
+        assert_eq!(surface_flags(BufferUsage::Uniform), svga3d::surface_flags::HINT_DYNAMIC);
+    }
+}
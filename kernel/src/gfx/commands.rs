@@ -1,11 +1,16 @@
 //! Command Recording
 //!
 //! Provides command buffers and encoders for recording GPU commands.
+//!
+//! A command buffer holds no references into the device, so a frame's draw
+//! calls can be recorded on one core and submitted on another; submission
+//! replays them on whichever backend the device uses.
 
-use super::device::{Backend, GpuTriangle, GpuVertex};
-use super::pipeline::{Buffer, Pipeline, RenderPass};
-use crate::api::types::{Color, Handle};
+use super::device::{Backend, Device, GpuTriangle, GpuVertex};
+use super::pipeline::{Buffer, Pipeline, RenderPassDesc};
+use crate::api::types::{Color, Handle, KernelResult};
 use alloc::vec::Vec;
+use glam::Mat4;
 
 /// GPU command
 #[derive(Debug, Clone)]
@@ -24,14 +29,24 @@ pub enum Command {
         min_depth: f32,
         max_depth: f32,
     },
-    /// Begin a render pass
-    BeginRenderPass {
-        pass: Handle,
-        clear_color: Option<Color>,
-        clear_depth: Option<f32>,
+    /// Clear the color buffer to an ARGB color
+    ClearColor(u32),
+    /// Clear the depth buffer to the far plane
+    ClearDepth,
+    /// Set the camera that following DrawMesh commands are seen through
+    SetCamera {
+        view: Mat4,
+        projection: Mat4,
     },
+    /// Begin a render pass
+    BeginRenderPass,
     /// End the current render pass
     EndRenderPass,
+    /// Draw a mesh created with Device::create_mesh at a world transform
+    DrawMesh {
+        mesh_id: u32,
+        transform: Mat4,
+    },
     /// Bind a pipeline
     BindPipeline(Handle),
     /// Bind a vertex buffer
@@ -89,6 +104,11 @@ impl CommandBuffer {
     pub fn len(&self) -> usize {
         self.commands.len()
     }
+
+    /// Replay the recorded commands on `device`
+    pub fn submit(&self, device: &mut Device) -> KernelResult<()> {
+        device.submit(core::slice::from_ref(self))
+    }
 }
 
 impl Default for CommandBuffer {
@@ -155,15 +175,20 @@ impl CommandEncoder {
         self.set_viewport(0.0, 0.0, self.width as f32, self.height as f32, 0.0, 1.0);
     }
 
-    /// Begin a render pass
-    pub fn begin_render_pass(&mut self, pass: &RenderPass) {
-        self.flush_triangles();
+    /// Begin a render pass, clearing as `desc` asks
+    /// Draws are recorded through the returned encoder; the pass ends when
+    /// it is dropped.
+    pub fn begin_render_pass(&mut self, desc: &RenderPassDesc) -> RenderPassEncoder<'_> {
+        self.end_render_pass();
         self.in_render_pass = true;
-        self.commands.push(Command::BeginRenderPass {
-            pass: pass.handle(),
-            clear_color: pass.clear_color(),
-            clear_depth: pass.clear_depth(),
-        });
+        self.commands.push(Command::BeginRenderPass);
+        if let Some(color) = desc.clear_color {
+            self.commands.push(Command::ClearColor(color.to_u32()));
+        }
+        if desc.clear_depth.is_some() {
+            self.commands.push(Command::ClearDepth);
+        }
+        RenderPassEncoder { encoder: self }
     }
 
     /// Bind a pipeline
@@ -270,3 +295,74 @@ impl CommandEncoder {
         CommandBuffer::new(self.commands)
     }
 }
+
+/// Records the draws of one render pass (see CommandEncoder::begin_render_pass)
+pub struct RenderPassEncoder<'a> {
+    encoder: &'a mut CommandEncoder,
+}
+
+impl RenderPassEncoder<'_> {
+    /// Render into the `width` x `height` area at (x, y)
+    pub fn set_viewport(&mut self, x: f32, y: f32, width: f32, height: f32) {
+        self.encoder.flush_triangles();
+        self.encoder.set_viewport(x, y, width, height, 0.0, 1.0);
+    }
+
+    /// Set the camera for the following draw_mesh calls
+    pub fn set_camera(&mut self, view: Mat4, projection: Mat4) {
+        self.encoder.commands.push(Command::SetCamera { view, projection });
+    }
+
+    /// Draw a device mesh with `transform` as its model matrix
+    pub fn draw_mesh(&mut self, mesh_id: u32, transform: Mat4) {
+        self.encoder.flush_triangles();
+        self.encoder.commands.push(Command::DrawMesh { mesh_id, transform });
+    }
+
+    /// Add a screen-space triangle
+    pub fn add_triangle(&mut self, v0: GpuVertex, v1: GpuVertex, v2: GpuVertex) {
+        self.encoder.add_triangle(v0, v1, v2);
+    }
+
+    /// End the render pass (same as dropping the encoder)
+    pub fn end(self) {}
+}
+
+impl Drop for RenderPassEncoder<'_> {
+    fn drop(&mut self) {
+        self.encoder.end_render_pass();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_pass_recording() {
+        let mut encoder = CommandEncoder::new(640, 480, Backend::Software);
+        {
+            let mut pass = encoder.begin_render_pass(&RenderPassDesc::clear(Color::rgb(10, 20, 30)));
+            pass.set_camera(Mat4::IDENTITY, Mat4::IDENTITY);
+            pass.add_triangle(
+                GpuVertex::new(0.0, 0.0, 0.5, 0),
+                GpuVertex::new(1.0, 0.0, 0.5, 0),
+                GpuVertex::new(0.0, 1.0, 0.5, 0),
+            );
+            pass.draw_mesh(7, Mat4::from_translation(glam::Vec3::X));
+        }
+        let commands = encoder.finish();
+        let commands = commands.commands();
+
+        assert!(matches!(commands[0], Command::BeginRenderPass));
+        assert!(matches!(commands[1], Command::ClearColor(c) if c == Color::rgb(10, 20, 30).to_u32()));
+        assert!(matches!(commands[2], Command::ClearDepth));
+        assert!(matches!(commands[3], Command::SetCamera { .. }));
+        // Triangles queued before a mesh draw are recorded ahead of it
+        assert!(matches!(&commands[4], Command::DrawTriangles { triangles } if triangles.len() == 1));
+        assert!(matches!(commands[5], Command::DrawMesh { mesh_id: 7, transform } if transform.w_axis.x == 1.0));
+        // Dropping the pass ends it
+        assert!(matches!(commands[6], Command::EndRenderPass));
+        assert_eq!(commands.len(), 7);
+    }
+}
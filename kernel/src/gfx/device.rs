@@ -2,22 +2,37 @@
 //!
 //! Main entry point for graphics operations. The Device manages all GPU resources
 //! and provides methods for creating buffers, pipelines, and submitting commands.
+//! Commands are replayed through the backend picked when the device is created.
 
+use super::backends::software::SoftwareBackend;
+use super::backends::svga3d::Svga3DBackend;
+use super::backends::GfxBackend;
 use super::commands::{CommandBuffer, CommandEncoder};
 use super::pipeline::{
     BlendMode, Buffer, BufferDesc, BufferUsage, CullMode, Image, ImageDesc, ImageFormat,
     Pipeline, PipelineDesc, RenderPass, RenderPassDesc, Sampler, SamplerDesc,
 };
-use crate::api::types::{Color, Dimensions, Handle, KernelError, KernelResult};
+use crate::api::types::{Dimensions, Handle, KernelError, KernelResult};
+use crate::graphics::pipeline::{normal_matrix, shade_vertex, transform_triangle_clipped, CullMode as MeshCull};
+use alloc::boxed::Box;
 use alloc::vec::Vec;
 use core::sync::atomic::{AtomicU32, Ordering};
+use glam::{Mat4, Vec3};
+use renderer::mesh::Mesh;
+use renderer::vertex::Vertex;
 
 /// Device information
 #[derive(Debug, Clone)]
 pub struct DeviceInfo {
+    /// Backend name
     pub name: &'static str,
+    pub backend: Backend,
     pub width: u32,
     pub height: u32,
+    /// Video memory in bytes (0 for the software backend)
+    pub vram_size: usize,
+    /// Largest texture side in texels (0 if the device doesn't report it)
+    pub max_texture_size: u32,
     pub has_hardware_acceleration: bool,
     pub has_3d: bool,
 }
@@ -35,21 +50,25 @@ pub enum Backend {
 pub struct Device {
     width: u32,
     height: u32,
-    backend: Backend,
+    renderer: Box<dyn GfxBackend>,
     next_buffer_id: AtomicU32,
     next_pipeline_id: AtomicU32,
     next_image_id: AtomicU32,
     next_render_pass_id: AtomicU32,
+    next_mesh_id: AtomicU32,
     buffers: Vec<BufferState>,
     pipelines: Vec<PipelineState>,
     images: Vec<ImageState>,
     render_passes: Vec<RenderPassState>,
+    meshes: Vec<MeshState>,
 }
 
 struct BufferState {
     id: u32,
     desc: BufferDesc,
     data: Vec<u8>,
+    /// Device memory behind the buffer (an SVGA3D surface), if any
+    backing: Option<u32>,
 }
 
 struct PipelineState {
@@ -68,43 +87,72 @@ struct RenderPassState {
     desc: RenderPassDesc,
 }
 
+struct MeshState {
+    id: u32,
+    mesh: Mesh,
+}
+
+/// State a command buffer sets up while it is replayed
+struct Playback {
+    view: Mat4,
+    projection: Mat4,
+    /// Viewport origin and size in pixels
+    viewport: (f32, f32, f32, f32),
+}
+
 impl Device {
     /// Create a new graphics device
+    /// Uses SVGA3D when the 3D device is up, the software rasterizer otherwise.
     pub fn new() -> KernelResult<Self> {
+        let backend = if Svga3DBackend::is_available() {
+            Backend::Svga3D
+        } else {
+            Backend::Software
+        };
+        Self::with_backend(backend)
+    }
+
+    /// Create a graphics device on a specific backend
+    pub fn with_backend(backend: Backend) -> KernelResult<Self> {
         let (w, h) = crate::graphics::gpu::dimensions();
         if w == 0 || h == 0 {
             return Err(KernelError::DeviceNotAvailable);
         }
 
-        let backend = if crate::graphics::gpu::has_3d() {
-            Backend::Svga3D
-        } else {
-            Backend::Software
+        let renderer: Box<dyn GfxBackend> = match backend {
+            Backend::Software => Box::new(SoftwareBackend::new()),
+            Backend::Svga3D if Svga3DBackend::is_available() => Box::new(Svga3DBackend::new()),
+            Backend::Svga3D => return Err(KernelError::DeviceNotAvailable),
         };
 
         Ok(Self {
             width: w as u32,
             height: h as u32,
-            backend,
+            renderer,
             next_buffer_id: AtomicU32::new(1),
             next_pipeline_id: AtomicU32::new(1),
             next_image_id: AtomicU32::new(1),
             next_render_pass_id: AtomicU32::new(1),
+            next_mesh_id: AtomicU32::new(1),
             buffers: Vec::new(),
             pipelines: Vec::new(),
             images: Vec::new(),
             render_passes: Vec::new(),
+            meshes: Vec::new(),
         })
     }
 
     /// Get device information
     pub fn info(&self) -> DeviceInfo {
         DeviceInfo {
-            name: crate::graphics::gpu::backend_name(),
+            name: self.renderer.name(),
+            backend: self.backend(),
             width: self.width,
             height: self.height,
+            vram_size: self.renderer.vram_size(),
+            max_texture_size: self.renderer.max_texture_size(),
             has_hardware_acceleration: crate::graphics::gpu::has_hw_accel(),
-            has_3d: crate::graphics::gpu::has_3d(),
+            has_3d: self.backend() == Backend::Svga3D,
         }
     }
 
@@ -115,18 +163,20 @@ impl Device {
 
     /// Get the active backend
     pub fn backend(&self) -> Backend {
-        self.backend
+        self.renderer.kind()
     }
 
     /// Create a buffer
     pub fn create_buffer(&mut self, desc: &BufferDesc) -> KernelResult<Buffer> {
         let id = self.next_buffer_id.fetch_add(1, Ordering::Relaxed);
         let data = alloc::vec![0u8; desc.size];
+        let backing = self.renderer.create_buffer(desc);
 
         self.buffers.push(BufferState {
             id,
             desc: desc.clone(),
             data,
+            backing,
         });
 
         Ok(Buffer::new(Handle::new(id), desc.size, desc.usage))
@@ -191,9 +241,16 @@ impl Device {
         Ok(RenderPass::new(Handle::new(id), desc.clear_color, desc.clear_depth))
     }
 
+    /// Hand a mesh to the device; returns the id DrawMesh commands refer to it by
+    pub fn create_mesh(&mut self, mesh: Mesh) -> u32 {
+        let id = self.next_mesh_id.fetch_add(1, Ordering::Relaxed);
+        self.meshes.push(MeshState { id, mesh });
+        id
+    }
+
     /// Begin recording commands
     pub fn begin_commands(&mut self) -> CommandEncoder {
-        CommandEncoder::new(self.width, self.height, self.backend)
+        CommandEncoder::new(self.width, self.height, self.backend())
     }
 
     /// Submit command buffers for execution
@@ -206,7 +263,7 @@ impl Device {
 
     /// Present the current frame to the display
     pub fn present(&mut self) -> KernelResult<()> {
-        crate::graphics::gpu::present();
+        self.renderer.present();
         Ok(())
     }
 
@@ -214,32 +271,28 @@ impl Device {
     fn execute_commands(&self, cmd_buf: &CommandBuffer) -> KernelResult<()> {
         use super::commands::Command;
 
+        let mut playback = Playback {
+            view: Mat4::IDENTITY,
+            projection: Mat4::IDENTITY,
+            viewport: (0.0, 0.0, self.width as f32, self.height as f32),
+        };
+
         for cmd in cmd_buf.commands() {
             match cmd {
                 Command::Clear { color, depth } => {
-                    if let Some(c) = color {
-                        crate::graphics::gpu::clear(c.to_u32());
-                    }
-                    if depth.is_some() {
-                        crate::graphics::zbuffer::clear();
-                    }
+                    self.renderer.clear(color.map(|c| c.to_u32()), *depth);
                 }
+                Command::ClearColor(color) => self.renderer.clear(Some(*color), None),
+                Command::ClearDepth => self.renderer.clear(None, Some(1.0)),
                 Command::SetViewport { x, y, width, height, .. } => {
-                    // Viewport is primarily used for software rendering bounds
-                    // Hardware handles this differently
-                    let _ = (x, y, width, height);
-                }
-                Command::BeginRenderPass { clear_color, clear_depth, .. } => {
-                    if let Some(c) = clear_color {
-                        crate::graphics::gpu::clear(c.to_u32());
-                    }
-                    if clear_depth.is_some() {
-                        crate::graphics::zbuffer::clear();
-                    }
+                    playback.viewport = (*x, *y, *width, *height);
                 }
-                Command::EndRenderPass => {
-                    // No-op for now
+                Command::SetCamera { view, projection } => {
+                    playback.view = *view;
+                    playback.projection = *projection;
                 }
+                Command::BeginRenderPass => self.renderer.begin_render_pass(),
+                Command::EndRenderPass => self.renderer.end_render_pass(),
                 Command::BindPipeline(_) => {
                     // Pipeline state is tracked in command encoder
                 }
@@ -253,72 +306,52 @@ impl Device {
                     // Draw calls would dispatch to appropriate backend
                     // For now, these are handled by the existing rendering path
                 }
-                Command::FillRect { x, y, width, height, color } => {
-                    crate::graphics::gpu::fill_rect(
-                        *x as usize,
-                        *y as usize,
-                        *width as usize,
-                        *height as usize,
-                        color.to_u32(),
-                    );
+                Command::DrawMesh { mesh_id, transform } => {
+                    let mesh = &self.meshes
+                        .iter()
+                        .find(|m| m.id == *mesh_id)
+                        .ok_or(KernelError::InvalidHandle)?
+                        .mesh;
+                    self.draw_mesh(mesh, transform, &playback);
                 }
-                Command::DrawTriangles { triangles } => {
-                    // Dispatch to appropriate backend
-                    match self.backend {
-                        Backend::Software => {
-                            // Use existing software rasterizer
-                            self.draw_triangles_software(triangles);
-                        }
-                        Backend::Svga3D => {
-                            // Use GPU batch renderer
-                            self.draw_triangles_gpu(triangles);
-                        }
-                    }
+                Command::FillRect { x, y, width, height, color } => {
+                    self.renderer.fill_rect(*x, *y, *width, *height, color.to_u32());
                 }
+                Command::DrawTriangles { triangles } => self.renderer.draw_triangles(triangles),
             }
         }
         Ok(())
     }
 
-    /// Draw triangles using software rasterization
-    fn draw_triangles_software(&self, triangles: &[GpuTriangle]) {
-        let ctx = match crate::graphics::rasterizer::RenderContext::acquire() {
-            Some(ctx) => ctx,
-            None => return,
-        };
-
-        for tri in triangles {
-            let c0 = Color::from_u32(tri.v0.color);
-            let c1 = Color::from_u32(tri.v1.color);
-            let c2 = Color::from_u32(tri.v2.color);
-
-            let v0 = renderer::vertex::Vertex::pos_color(
-                glam::Vec3::new(tri.v0.x, tri.v0.y, tri.v0.z),
-                glam::Vec3::new(c0.r as f32 / 255.0, c0.g as f32 / 255.0, c0.b as f32 / 255.0),
-            );
-            let v1 = renderer::vertex::Vertex::pos_color(
-                glam::Vec3::new(tri.v1.x, tri.v1.y, tri.v1.z),
-                glam::Vec3::new(c1.r as f32 / 255.0, c1.g as f32 / 255.0, c1.b as f32 / 255.0),
-            );
-            let v2 = renderer::vertex::Vertex::pos_color(
-                glam::Vec3::new(tri.v2.x, tri.v2.y, tri.v2.z),
-                glam::Vec3::new(c2.r as f32 / 255.0, c2.g as f32 / 255.0, c2.b as f32 / 255.0),
-            );
-
-            crate::graphics::rasterizer::rasterize_triangle_with_context(&ctx, &v0, &v1, &v2);
+    /// Light, transform and clip a mesh through the playback camera, then
+    /// hand the screen triangles to the backend
+    fn draw_mesh(&self, mesh: &Mesh, model: &Mat4, playback: &Playback) {
+        let (x, y, width, height) = playback.viewport;
+        let mvp = playback.projection * playback.view * *model;
+        let nm = normal_matrix(model);
+        let offset = |v: Vertex| Vertex { position: v.position + Vec3::new(x, y, 0.0), ..v };
+
+        let mut projected = Vec::with_capacity(mesh.triangle_count());
+        for i in 0..mesh.triangle_count() {
+            let Some((v0, v1, v2)) = mesh.get_triangle(i) else {
+                continue;
+            };
+            let (v0, v1, v2) = (shade_vertex(v0, &nm), shade_vertex(v1, &nm), shade_vertex(v2, &nm));
+            for (t0, t1, t2) in transform_triangle_clipped(&v0, &v1, &v2, &mvp, width, height, MeshCull::Back)
+                .into_iter()
+                .flatten()
+            {
+                projected.push([offset(t0), offset(t1), offset(t2)]);
+            }
         }
+        self.renderer.draw_projected(&projected, &playback.projection);
     }
+}
 
-    /// Draw triangles using GPU acceleration
-    fn draw_triangles_gpu(&self, triangles: &[GpuTriangle]) {
-        use crate::graphics::gpu_batch;
-
-        for tri in triangles {
-            gpu_batch::add_triangle_verts(
-                tri.v0.x, tri.v0.y, tri.v0.z, tri.v0.color,
-                tri.v1.x, tri.v1.y, tri.v1.z, tri.v1.color,
-                tri.v2.x, tri.v2.y, tri.v2.z, tri.v2.color,
-            );
+impl Drop for Device {
+    fn drop(&mut self) {
+        for id in self.buffers.iter().filter_map(|b| b.backing) {
+            self.renderer.destroy_buffer(id);
         }
     }
 }
@@ -5,7 +5,7 @@
 //! and rasterized.
 
 use glam::{Mat4, Vec3, Vec4};
-use libm::{ceilf, sqrtf};
+use libm::{ceilf, floorf, sqrtf};
 
 /// Axis-Aligned Bounding Box for fast culling tests
 #[derive(Clone, Copy, Debug)]
@@ -27,6 +27,16 @@ impl AABB {
         }
     }
 
+    /// Smallest box containing all the points (a degenerate box at the
+    /// origin if there are none)
+    pub fn from_points(points: impl IntoIterator<Item = Vec3>) -> Self {
+        let mut points = points.into_iter();
+        let Some(first) = points.next() else {
+            return Self::new(Vec3::ZERO, Vec3::ZERO);
+        };
+        points.fold(Self::new(first, first), |b, p| Self::new(b.min.min(p), b.max.max(p)))
+    }
+
     /// Get the center of the AABB
     pub fn center(&self) -> Vec3 {
         (self.min + self.max) * 0.5
@@ -56,6 +66,36 @@ impl AABB {
 
         Self::from_center_extents(new_center, new_extents)
     }
+
+    /// Screen rectangle (min_x, min_y, max_x, max_y) covering the box and the
+    /// depth (1/w) of its nearest corner
+    /// Returns None if any corner is behind the camera, where the projection
+    /// no longer bounds the box
+    pub fn screen_rect(&self, view_proj: &Mat4, fb_width: f32, fb_height: f32) -> Option<((i32, i32, i32, i32), f32)> {
+        let (mut min_x, mut min_y) = (f32::INFINITY, f32::INFINITY);
+        let (mut max_x, mut max_y) = (f32::NEG_INFINITY, f32::NEG_INFINITY);
+        let mut nearest = 0.0f32;
+        for i in 0..8 {
+            let corner = Vec3::new(
+                if i & 1 == 0 { self.min.x } else { self.max.x },
+                if i & 2 == 0 { self.min.y } else { self.max.y },
+                if i & 4 == 0 { self.min.z } else { self.max.z },
+            );
+            let clip = *view_proj * corner.extend(1.0);
+            if clip.w <= 0.0001 {
+                return None;
+            }
+            let inv_w = 1.0 / clip.w;
+            let x = (clip.x * inv_w + 1.0) * 0.5 * fb_width;
+            let y = (1.0 - clip.y * inv_w) * 0.5 * fb_height;
+            min_x = min_x.min(x);
+            max_x = max_x.max(x);
+            min_y = min_y.min(y);
+            max_y = max_y.max(y);
+            nearest = nearest.max(inv_w);
+        }
+        Some(((floorf(min_x) as i32, floorf(min_y) as i32, ceilf(max_x) as i32, ceilf(max_y) as i32), nearest))
+    }
 }
 
 /// View frustum for culling
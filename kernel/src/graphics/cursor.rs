@@ -1,8 +1,16 @@
 //! Mouse cursor rendering
 //!
-//! Provides a simple arrow cursor for UI interaction.
+//! Provides a simple arrow cursor for UI interaction. Screens say whether
+//! they want it (set_visible) and the mouse moves it (set_position).
+//!
+//! On VMSVGA devices with the cursor capability the arrow is uploaded once
+//! and the host draws it; moving it only writes the cursor registers, so the
+//! framebuffer is never touched. Elsewhere gpu::present composites it into
+//! the back buffer on top of each frame.
 
-use crate::graphics::framebuffer::Framebuffer;
+use crate::drivers::vmsvga::{self, cursor as hw, VmsvgaDevice};
+use crate::graphics::framebuffer::{mark_dirty, DirtyRect, Framebuffer};
+use core::sync::atomic::{AtomicBool, AtomicI32, Ordering};
 
 /// Simple arrow cursor bitmap (12x16 pixels)
 /// 0 = transparent, 1 = black outline, 2 = white fill
@@ -31,15 +39,119 @@ pub const CURSOR_WIDTH: usize = 12;
 /// Cursor height in pixels
 pub const CURSOR_HEIGHT: usize = 16;
 
+/// How the cursor reaches the screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CursorPath {
+    /// Composited into the back buffer by draw_cursor
+    Software,
+    /// Drawn by the VMSVGA host from an uploaded image
+    Hardware,
+}
+
+impl CursorPath {
+    /// Path for the active display: the capabilities of the VMSVGA device, or
+    /// None when drawing through another backend
+    pub fn select(vmsvga_capabilities: Option<u32>) -> Self {
+        match vmsvga_capabilities {
+            Some(caps) if hw::hardware_supported(caps) => Self::Hardware,
+            _ => Self::Software,
+        }
+    }
+}
+
+/// The arrow was uploaded to the device, which draws it from now on
+static HW_CURSOR: AtomicBool = AtomicBool::new(false);
+/// The current screen wants a cursor (set_visible)
+static CURSOR_VISIBLE: AtomicBool = AtomicBool::new(false);
+/// The hardware cursor is on screen
+static HW_CURSOR_VISIBLE: AtomicBool = AtomicBool::new(false);
+/// Hotspot position (set_position)
+static CURSOR_X: AtomicI32 = AtomicI32::new(0);
+static CURSOR_Y: AtomicI32 = AtomicI32::new(0);
+
+/// Arrow color at (x, y), None where transparent
+fn arrow_pixel(x: usize, y: usize) -> Option<u32> {
+    match CURSOR_DATA[y][x] {
+        0 => None,
+        1 => Some(0x000000), // Black outline
+        _ => Some(0xFFFFFF), // White fill
+    }
+}
+
+/// Upload the arrow to a device that can draw it (call once, at init)
+/// Returns whether the hardware cursor is used from now on
+pub fn init_hardware(device: &VmsvgaDevice) -> bool {
+    if CursorPath::select(Some(device.capabilities())) != CursorPath::Hardware || !device.has_hw_cursor() {
+        return false;
+    }
+    let cmd = if hw::alpha_supported(device.capabilities()) {
+        hw::define_alpha_cursor_cmd((0, 0), CURSOR_WIDTH, CURSOR_HEIGHT, arrow_pixel)
+    } else {
+        hw::define_cursor_cmd((0, 0), CURSOR_WIDTH, CURSOR_HEIGHT, arrow_pixel)
+    };
+    let defined = device.define_cursor(&cmd);
+    if defined {
+        device.set_cursor(false, 0, 0);
+    }
+    HW_CURSOR.store(defined, Ordering::Relaxed);
+    defined
+}
+
+/// Move the cursor hotspot (called from the mouse interrupt)
+///
+/// A host-drawn cursor follows at once through the cursor registers; when
+/// the device is busy the next present moves it instead.
+pub fn set_position(x: i32, y: i32) {
+    CURSOR_X.store(x, Ordering::Relaxed);
+    CURSOR_Y.store(y, Ordering::Relaxed);
+    if !HW_CURSOR_VISIBLE.load(Ordering::Relaxed) {
+        return;
+    }
+    if let Some(device) = vmsvga::VMSVGA_DEVICE.try_lock() {
+        device.set_cursor(true, x, y);
+    }
+}
+
+/// Show or hide the cursor from the next present on
+/// Screens with pointer input show it; gameplay hides it.
+pub fn set_visible(visible: bool) {
+    CURSOR_VISIBLE.store(visible, Ordering::Relaxed);
+}
+
+/// Show or hide the hardware cursor to match set_visible
+/// Called by gpu::present once per frame
+pub fn present_hardware(device: &VmsvgaDevice) {
+    if !HW_CURSOR.load(Ordering::Relaxed) {
+        return;
+    }
+    let visible = CURSOR_VISIBLE.load(Ordering::Relaxed);
+    if visible {
+        device.set_cursor(true, CURSOR_X.load(Ordering::Relaxed), CURSOR_Y.load(Ordering::Relaxed));
+    } else if HW_CURSOR_VISIBLE.load(Ordering::Relaxed) {
+        device.set_cursor(false, 0, 0);
+    }
+    HW_CURSOR_VISIBLE.store(visible, Ordering::Relaxed);
+}
+
+/// Draw a visible cursor into the finished frame when the host doesn't draw it
+/// Called by gpu::present before it collects the frame's dirty regions
+pub fn composite_software(fb: &Framebuffer) {
+    if HW_CURSOR.load(Ordering::Relaxed) || !CURSOR_VISIBLE.load(Ordering::Relaxed) {
+        return;
+    }
+    draw_cursor(fb, CURSOR_X.load(Ordering::Relaxed), CURSOR_Y.load(Ordering::Relaxed));
+}
+
 /// Draw the mouse cursor at the given position
 ///
 /// The cursor hotspot is at (0, 0) - the top-left corner.
 pub fn draw_cursor(fb: &Framebuffer, x: i32, y: i32) {
-    for (dy, row) in CURSOR_DATA.iter().enumerate() {
-        for (dx, &pixel) in row.iter().enumerate() {
-            if pixel == 0 {
+    mark_cursor_dirty(x, y);
+    for dy in 0..CURSOR_HEIGHT {
+        for dx in 0..CURSOR_WIDTH {
+            let Some(color) = arrow_pixel(dx, dy) else {
                 continue; // Transparent
-            }
+            };
 
             let px = x + dx as i32;
             let py = y + dy as i32;
@@ -49,12 +161,6 @@ pub fn draw_cursor(fb: &Framebuffer, x: i32, y: i32) {
                 continue;
             }
 
-            let color = if pixel == 1 {
-                0x000000 // Black outline
-            } else {
-                0xFFFFFF // White fill
-            };
-
             fb.put_pixel(px as usize, py as usize, color);
         }
     }
@@ -62,6 +168,7 @@ pub fn draw_cursor(fb: &Framebuffer, x: i32, y: i32) {
 
 /// Draw the mouse cursor with a custom outline and fill color
 pub fn draw_cursor_colored(fb: &Framebuffer, x: i32, y: i32, outline: u32, fill: u32) {
+    mark_cursor_dirty(x, y);
     for (dy, row) in CURSOR_DATA.iter().enumerate() {
         for (dx, &pixel) in row.iter().enumerate() {
             if pixel == 0 {
@@ -81,6 +188,12 @@ pub fn draw_cursor_colored(fb: &Framebuffer, x: i32, y: i32, outline: u32, fill:
     }
 }
 
+/// Record the cursor sprite's screen area as dirty
+fn mark_cursor_dirty(x: i32, y: i32) {
+    let (w, h) = (CURSOR_WIDTH as i32, CURSOR_HEIGHT as i32);
+    mark_dirty(DirtyRect::from_bounds(x, y, x + w - 1, y + h - 1));
+}
+
 /// Check if a point is within a rectangular area
 pub fn point_in_rect(x: i32, y: i32, rect_x: usize, rect_y: usize, width: usize, height: usize) -> bool {
     x >= rect_x as i32
@@ -88,3 +201,27 @@ pub fn point_in_rect(x: i32, y: i32, rect_x: usize, rect_y: usize, width: usize,
         && y >= rect_y as i32
         && y < (rect_y + height) as i32
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::drivers::vmsvga::regs::cap;
+
+    #[test]
+    fn test_hardware_path_only_with_capability() {
+        assert_eq!(CursorPath::select(None), CursorPath::Software);
+        assert_eq!(CursorPath::select(Some(cap::RECT_COPY | cap::RECT_FILL)), CursorPath::Software);
+        assert_eq!(CursorPath::select(Some(cap::CURSOR | cap::CURSOR_BYPASS_2)), CursorPath::Hardware);
+        assert_eq!(CursorPath::select(Some(cap::ALPHA_CURSOR)), CursorPath::Hardware);
+    }
+
+    #[test]
+    fn test_arrow_pixels_match_bitmap() {
+        assert_eq!(arrow_pixel(0, 0), Some(0x000000));
+        assert_eq!(arrow_pixel(1, 2), Some(0xFFFFFF));
+        assert_eq!(arrow_pixel(11, 0), None);
+        let words = hw::define_cursor_cmd((0, 0), CURSOR_WIDTH, CURSOR_HEIGHT, arrow_pixel);
+        // Header, one mask word per row, then the 32-bit image
+        assert_eq!(words.len(), 8 + CURSOR_HEIGHT + CURSOR_WIDTH * CURSOR_HEIGHT);
+    }
+}
@@ -1,8 +1,13 @@
 //! Extended 8x8 bitmap font for text rendering
 //!
 //! Supports full alphabet (A-Z), digits (0-9), and common punctuation.
+//! Glyphs advance by the width of their lit columns (digits share one width),
+//! and integer-scaled text can have its diagonal steps smoothed.
 
-use super::framebuffer::FRAMEBUFFER;
+use core::sync::atomic::{AtomicBool, Ordering};
+use super::framebuffer::{lerp_color, mark_dirty, DirtyRect, Framebuffer, FRAMEBUFFER};
+use super::ui::{colors, Rect};
+use super::ui::panel::{FillDirection, ProgressBar};
 
 /// 8x8 bitmap font data for digits, letters, and punctuation
 /// Each character is 8 bytes, one byte per row
@@ -152,30 +157,95 @@ fn char_to_glyph(c: char) -> usize {
     }
 }
 
-/// Draw a character at position (x, y) with given color
-/// Scale multiplies the character size
-pub fn draw_char(x: usize, y: usize, c: char, color: u32, scale: usize) {
-    let fb_guard = FRAMEBUFFER.lock();
-    let fb = match fb_guard.as_ref() {
-        Some(f) => f,
-        None => return,
-    };
+/// Width of the space glyph in font pixels (its bitmap is empty)
+const SPACE_WIDTH: usize = 3;
+
+/// Blank font pixels between glyphs
+const GLYPH_GAP: usize = 1;
+
+/// Blank font pixels between wrapped lines
+const LINE_GAP: usize = 2;
+
+/// Round off the corners of scaled glyphs (see `set_smooth_scaling`)
+static SMOOTH_SCALING: AtomicBool = AtomicBool::new(true);
+
+/// Enable or disable corner smoothing for text drawn at scale 2 and up
+pub fn set_smooth_scaling(enabled: bool) {
+    SMOOTH_SCALING.store(enabled, Ordering::Relaxed);
+}
+
+/// Horizontal text alignment within a rectangle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+/// Bitmap columns a glyph occupies: (first column, width)
+/// Digits share one span so numbers don't shift as their value changes
+fn glyph_span(glyph: usize) -> (usize, usize) {
+    if glyph < 10 {
+        return (1, 7);
+    }
+    let mask = FONT_DATA[glyph].iter().fold(0u8, |acc, row| acc | row);
+    if mask == 0 {
+        return (0, SPACE_WIDTH);
+    }
+    let first = mask.leading_zeros() as usize;
+    (first, 8 - first - mask.trailing_zeros() as usize)
+}
+
+/// Horizontal advance of a character in pixels, including the gap after it
+pub fn char_advance(c: char, scale: usize) -> usize {
+    (glyph_span(char_to_glyph(c)).1 + GLYPH_GAP) * scale
+}
+
+/// Whether bitmap cell (row, col) of a glyph is lit (false outside the 8x8 cell)
+fn glyph_bit(data: &[u8; 8], row: isize, col: isize) -> bool {
+    (0..8).contains(&row) && (0..8).contains(&col) && data[row as usize] & (0x80 >> col) != 0
+}
 
+/// Call `plot` for every pixel of a scaled glyph whose drawn span starts at x
+///
+/// With smoothing, an empty cell between two lit neighbours (above and beside)
+/// gets the matching corner filled with a triangle, so diagonals look less
+/// stepped at large scales.
+fn for_each_glyph_pixel(x: usize, y: usize, c: char, scale: usize, mut plot: impl FnMut(usize, usize)) {
     let glyph = char_to_glyph(c);
     let data = &FONT_DATA[glyph];
+    let (first, width) = glyph_span(glyph);
+    let smooth = scale >= 2 && SMOOTH_SCALING.load(Ordering::Relaxed);
+    let n = scale as isize - 1;
+
+    for row in 0..8isize {
+        for col in first as isize..(first + width) as isize {
+            let lit = glyph_bit(data, row, col);
+            // Corners to fill in an empty cell: top-left, top-right, bottom-left, bottom-right
+            let corners = if lit || !smooth {
+                [false; 4]
+            } else {
+                let up = glyph_bit(data, row - 1, col);
+                let down = glyph_bit(data, row + 1, col);
+                let left = glyph_bit(data, row, col - 1);
+                let right = glyph_bit(data, row, col + 1);
+                [up && left, up && right, down && left, down && right]
+            };
+            if !lit && !corners.contains(&true) {
+                continue;
+            }
 
-    for row in 0..8 {
-        let bits = data[row];
-        for col in 0..8 {
-            if bits & (0x80 >> col) != 0 {
-                // Draw scaled pixel
-                for sy in 0..scale {
-                    for sx in 0..scale {
-                        let px = x + col * scale + sx;
-                        let py = y + row * scale + sy;
-                        if px < fb.width && py < fb.height {
-                            fb.put_pixel(px, py, color);
-                        }
+            let cell_x = x + (col as usize - first) * scale;
+            let cell_y = y + row as usize * scale;
+            for sy in 0..scale as isize {
+                for sx in 0..scale as isize {
+                    let inside = lit
+                        || (corners[0] && sx + sy < n)
+                        || (corners[1] && (n - sx) + sy < n)
+                        || (corners[2] && sx + (n - sy) < n)
+                        || (corners[3] && (n - sx) + (n - sy) < n);
+                    if inside {
+                        plot(cell_x + sx as usize, cell_y + sy as usize);
                     }
                 }
             }
@@ -183,56 +253,189 @@ pub fn draw_char(x: usize, y: usize, c: char, color: u32, scale: usize) {
     }
 }
 
+/// Draw a glyph, keeping only pixels inside `clip` and the framebuffer
+fn draw_glyph_clipped(fb: &Framebuffer, x: usize, y: usize, c: char, color: u32, scale: usize, clip: &Rect) {
+    let right = clip.right().min(fb.width);
+    let bottom = clip.bottom().min(fb.height);
+    for_each_glyph_pixel(x, y, c, scale, |px, py| {
+        if px >= clip.x && px < right && py >= clip.y && py < bottom {
+            fb.put_pixel(px, py, color);
+        }
+    });
+}
+
+/// The whole framebuffer as a clip rectangle
+fn screen_rect(fb: &Framebuffer) -> Rect {
+    Rect::new(0, 0, fb.width, fb.height)
+}
+
+/// Draw a character at position (x, y) with given color
+/// Scale multiplies the character size
+pub fn draw_char(x: usize, y: usize, c: char, color: u32, scale: usize) {
+    let fb_guard = FRAMEBUFFER.lock();
+    let fb = match fb_guard.as_ref() {
+        Some(f) => f,
+        None => return,
+    };
+    draw_char_raw(fb, x, y, c, color, scale);
+}
+
 /// Draw a character without holding the framebuffer lock (for batch drawing)
 /// Caller must ensure fb is valid
 pub fn draw_char_raw(fb: &super::framebuffer::Framebuffer, x: usize, y: usize, c: char, color: u32, scale: usize) {
-    let glyph = char_to_glyph(c);
-    let data = &FONT_DATA[glyph];
-
-    for row in 0..8 {
-        let bits = data[row];
-        for col in 0..8 {
-            if bits & (0x80 >> col) != 0 {
-                // Draw scaled pixel
-                for sy in 0..scale {
-                    for sx in 0..scale {
-                        let px = x + col * scale + sx;
-                        let py = y + row * scale + sy;
-                        if px < fb.width && py < fb.height {
-                            fb.put_pixel(px, py, color);
-                        }
-                    }
-                }
-            }
-        }
-    }
+    mark_dirty(DirtyRect::new(x, y, char_advance(c, scale), char_height(scale)));
+    draw_glyph_clipped(fb, x, y, c, color, scale, &screen_rect(fb));
 }
 
 /// Draw a string at position (x, y)
 pub fn draw_string(x: usize, y: usize, s: &str, color: u32, scale: usize) {
-    let mut cx = x;
-    for c in s.chars() {
-        draw_char(cx, y, c, color, scale);
-        cx += 8 * scale + scale; // Character width + spacing
+    let fb_guard = FRAMEBUFFER.lock();
+    if let Some(fb) = fb_guard.as_ref() {
+        draw_string_raw(fb, x, y, s, color, scale);
     }
 }
 
 /// Draw a string without holding the framebuffer lock (for batch drawing)
 pub fn draw_string_raw(fb: &super::framebuffer::Framebuffer, x: usize, y: usize, s: &str, color: u32, scale: usize) {
+    draw_string_clipped(fb, x, y, s, color, scale, &screen_rect(fb));
+}
+
+/// Draw a string keeping only the pixels inside `clip`
+fn draw_string_clipped(fb: &Framebuffer, x: usize, y: usize, s: &str, color: u32, scale: usize, clip: &Rect) {
+    mark_dirty(DirtyRect::new(x, y, string_width(s, scale), char_height(scale)));
+    let mut cx = x;
+    for c in s.chars() {
+        if cx >= clip.right().min(fb.width) {
+            break;
+        }
+        draw_glyph_clipped(fb, cx, y, c, color, scale, clip);
+        cx += char_advance(c, scale);
+    }
+}
+
+/// Draw a string blended over the framebuffer contents
+/// `alpha` ranges from 0.0 (invisible) to 1.0 (opaque)
+pub fn draw_string_blended_raw(fb: &Framebuffer, x: usize, y: usize, s: &str, color: u32, scale: usize, alpha: f32) {
+    mark_dirty(DirtyRect::new(x, y, string_width(s, scale), char_height(scale)));
     let mut cx = x;
     for c in s.chars() {
-        draw_char_raw(fb, cx, y, c, color, scale);
-        cx += 8 * scale + scale; // Character width + spacing
+        for_each_glyph_pixel(cx, y, c, scale, |px, py| {
+            if px < fb.width && py < fb.height {
+                fb.put_pixel(px, py, lerp_color(fb.get_pixel(px, py), color, alpha));
+            }
+        });
+        cx += char_advance(c, scale);
     }
 }
 
+/// Draw a string inside `rect`: aligned horizontally, centered vertically,
+/// and clipped to the rectangle
+///
+/// Text wider than the rectangle starts at its left edge whatever the alignment,
+/// so the beginning stays readable
+pub fn draw_string_aligned(fb: &Framebuffer, rect: Rect, text: &str, align: Align, color: u32, scale: usize) {
+    let (width, height) = measure_string(text, scale);
+    let slack = rect.width.saturating_sub(width);
+    let x = rect.x
+        + match align {
+            Align::Left => 0,
+            Align::Center => slack / 2,
+            Align::Right => slack,
+        };
+    let y = rect.y + rect.height.saturating_sub(height) / 2;
+    draw_string_clipped(fb, x, y, text, color, scale, &rect);
+}
+
+/// Draw text broken into lines no wider than `max_width`
+///
+/// Lines break at spaces; a word wider than a whole line is broken between
+/// characters, and `\n` always starts a new line. Returns the height used
+/// (see measure_string_height).
+pub fn draw_string_wrapped(fb: &Framebuffer, x: usize, y: usize, max_width: usize, text: &str, color: u32, scale: u8) -> usize {
+    let scale = scale as usize;
+    let mut line_y = y;
+    wrap_lines(text, max_width, scale, |line| {
+        draw_string_raw(fb, x, line_y, line, color, scale);
+        line_y += line_height(scale);
+    });
+    line_y - y
+}
+
+/// Height draw_string_wrapped uses for `text`: one line_height per line,
+/// so the next element can be placed directly below
+pub fn measure_string_height(text: &str, max_width: usize, scale: u8) -> usize {
+    let mut lines = 0;
+    wrap_lines(text, max_width, scale as usize, |_| lines += 1);
+    lines * line_height(scale as usize)
+}
+
+/// Vertical distance between wrapped lines
+pub fn line_height(scale: usize) -> usize {
+    char_height(scale) + LINE_GAP * scale
+}
+
+/// Call `line` with each line of `text` wrapped to `max_width` (see
+/// draw_string_wrapped), without the spaces it was broken at
+fn wrap_lines<'a>(text: &'a str, max_width: usize, scale: usize, mut line: impl FnMut(&'a str)) {
+    for paragraph in text.split('\n') {
+        // Byte range of the line being filled
+        let mut current: Option<(usize, usize)> = None;
+        let mut offset = 0;
+        for word in paragraph.split(' ') {
+            let (mut start, end) = (offset, offset + word.len());
+            offset = end + 1;
+            if word.is_empty() {
+                continue;
+            }
+            if let Some((line_start, line_end)) = current {
+                if string_width(&paragraph[line_start..end], scale) <= max_width {
+                    current = Some((line_start, end));
+                    continue;
+                }
+                line(&paragraph[line_start..line_end]);
+            }
+            // The word starts a new line, in pieces if it doesn't fit on one
+            while string_width(&paragraph[start..end], scale) > max_width {
+                let piece = fitting_prefix(&paragraph[start..end], max_width, scale);
+                line(&paragraph[start..start + piece]);
+                start += piece;
+            }
+            current = (start < end).then_some((start, end));
+        }
+        match current {
+            Some((start, end)) => line(&paragraph[start..end]),
+            None if paragraph.trim_matches(' ').is_empty() => line(""),
+            None => {}
+        }
+    }
+}
+
+/// Byte length of the longest prefix of `word` no wider than `max_width`
+/// (at least one character, so wrapping always makes progress)
+fn fitting_prefix(word: &str, max_width: usize, scale: usize) -> usize {
+    let mut width = 0;
+    let mut fit = 0;
+    for (i, c) in word.char_indices() {
+        width += char_advance(c, scale);
+        // The last glyph of a line has no gap after it
+        if fit > 0 && width - GLYPH_GAP * scale > max_width {
+            break;
+        }
+        fit = i + c.len_utf8();
+    }
+    fit
+}
+
 /// Get the pixel width of a string at a given scale
 pub fn string_width(s: &str, scale: usize) -> usize {
-    if s.is_empty() {
-        return 0;
-    }
-    let char_count = s.chars().count();
-    char_count * (8 * scale) + (char_count - 1) * scale
+    let total: usize = s.chars().map(|c| char_advance(c, scale)).sum();
+    // No gap after the last glyph
+    total.saturating_sub(GLYPH_GAP * scale)
+}
+
+/// Measure a string's pixel size as (width, height) (for aligning several text elements)
+pub fn measure_string(text: &str, scale: usize) -> (usize, usize) {
+    (string_width(text, scale), char_height(scale))
 }
 
 /// Get the pixel height at a given scale
@@ -240,31 +443,20 @@ pub fn char_height(scale: usize) -> usize {
     8 * scale
 }
 
-/// Draw a centered string
-pub fn draw_string_centered(y: usize, s: &str, color: u32, scale: usize, fb_width: usize) {
-    let text_width = string_width(s, scale);
-    let x = if text_width >= fb_width {
-        0
-    } else {
-        (fb_width - text_width) / 2
-    };
-    draw_string(x, y, s, color, scale);
+/// Draw a string horizontally centered on the framebuffer
+pub fn draw_string_centered(fb: &Framebuffer, y: usize, text: &str, color: u32, scale: u8) {
+    draw_string_centered_raw(fb, y, text, color, scale as usize);
 }
 
 /// Draw a centered string without holding the framebuffer lock
 pub fn draw_string_centered_raw(fb: &super::framebuffer::Framebuffer, y: usize, s: &str, color: u32, scale: usize) {
-    let text_width = string_width(s, scale);
-    let x = if text_width >= fb.width {
-        0
-    } else {
-        (fb.width - text_width) / 2
-    };
-    draw_string_raw(fb, x, y, s, color, scale);
+    let row = Rect::new(0, y, fb.width, char_height(scale));
+    draw_string_aligned(fb, row, s, Align::Center, color, scale);
 }
 
 /// Draw FPS counter in top-left corner with solid background
-/// Uses a larger, more visible format
-pub fn draw_fps(fps: u32, _fb_width: usize) {
+/// Uses a larger, more visible format; `scale` is the glyph pixel size
+pub fn draw_fps(fps: u32, fb_width: usize, scale: u8) {
     // Get triangle count for this frame
     let tri_count = super::tiles::triangle_count();
 
@@ -275,11 +467,10 @@ pub fn draw_fps(fps: u32, _fb_width: usize) {
     let mut buf = [0u8; 48];
     let s = format_fps_extended(fps, tri_count, gpu_name, &mut buf);
 
-    let scale = 2; // Slightly smaller for more info
-    let char_width = 8 * scale + scale;
-    let text_width = s.len() * char_width;
-    let x = 10; // Top-left corner for visibility
-    let y = 10;
+    let scale = (scale as usize).max(1);
+    let (text_width, text_height) = measure_string(s, scale);
+    let x = fb_width / 100; // Top-left corner for visibility
+    let y = x;
 
     // Draw solid background rectangle first
     let bg_color = 0x00202040u32; // Dark blue-gray, matches clear color
@@ -287,19 +478,19 @@ pub fn draw_fps(fps: u32, _fb_width: usize) {
     if let Some(fb) = fb_guard.as_ref() {
         let padding = 4;
         let y_start = if y >= padding { y - padding } else { 0 };
-        let y_end = (y + 8 * scale + padding).min(fb.height);
+        let y_end = (y + text_height + padding).min(fb.height);
         let x_start = if x >= padding { x - padding } else { 0 };
         let x_end = (x + text_width + padding).min(fb.width);
+        mark_dirty(DirtyRect::new(x_start, y_start, x_end.saturating_sub(x_start), y_end.saturating_sub(y_start)));
         for py in y_start..y_end {
             for px in x_start..x_end {
                 fb.put_pixel(px, py, bg_color);
             }
         }
-    }
-    drop(fb_guard);
 
-    let color = 0x00FFFF00; // Yellow for maximum visibility
-    draw_string(x, y, s, color, scale);
+        let color = 0x00FFFF00; // Yellow for maximum visibility
+        draw_string_raw(fb, x, y, s, color, scale);
+    }
 }
 
 /// Format FPS with triangle count and GPU info
@@ -358,90 +549,76 @@ fn format_fps_extended<'a>(fps: u32, tri_count: usize, gpu: &str, buf: &'a mut [
     core::str::from_utf8(&buf[..pos]).unwrap_or("FPS:?")
 }
 
-/// Draw game HUD (health, materials, alive count)
-pub fn draw_hud(health: u8, materials: u32, alive: usize, total: usize, _fb_width: usize, fb_height: usize) {
-    let scale = 2;
-    let char_width = 8 * scale + scale;
-    let line_height = 8 * scale + 8;
-    let padding = 10;
+/// Empty-shield end of the HUD shield bar gradient (dark blue)
+const SHIELD_LOW: u32 = 0x00183870;
+
+/// Draw game HUD (health, shield, alive count)
+/// Layout is relative to the framebuffer size so it holds up across resolutions
+pub fn draw_hud(health: u8, shield: u8, alive: usize, total: usize, fb_width: usize, fb_height: usize, scale: u8) {
+    let scale = (scale as usize).max(1);
+    let line_height = 8 * scale + 4 * scale;
+    let padding = fb_width / 100;
 
     // Bottom-left corner for HUD
-    let base_y = fb_height - padding - line_height * 3;
+    let base_y = fb_height.saturating_sub(padding + line_height * 3);
+
+    let mut buf = [0u8; 16];
+    let alive_str = format_alive(alive, total, &mut buf);
+
+    // Bars span eight digits, with the values right-aligned in a column after them
+    let (bar_width, text_height) = measure_string("00000000", scale);
+    let gap = char_advance(' ', scale);
+    let value_width = measure_string("100", scale).0;
+    let value_x = padding + bar_width + gap;
+    let health_bar = ProgressBar {
+        x: padding,
+        y: base_y,
+        width: bar_width,
+        height: text_height,
+        color_low: colors::HEALTH_LOW,
+        color_high: colors::HEALTH_HIGH,
+        direction: FillDirection::LeftToRight,
+    };
+    let shield_bar = ProgressBar {
+        y: base_y + line_height,
+        color_low: SHIELD_LOW,
+        color_high: colors::FN_BLUE,
+        ..health_bar
+    };
 
-    // Draw background
+    // Draw background and bars
     let bg_color = 0x00202040u32;
     let fb_guard = FRAMEBUFFER.lock();
-    if let Some(fb) = fb_guard.as_ref() {
-        let bg_width = char_width * 12;
-        let bg_height = line_height * 3 + padding;
-        for py in base_y.saturating_sub(padding)..(base_y + bg_height).min(fb.height) {
-            for px in 0..(bg_width + padding * 2).min(fb.width) {
-                fb.put_pixel(px, py, bg_color);
-            }
+    let fb = match fb_guard.as_ref() {
+        Some(f) => f,
+        None => return,
+    };
+    let bg_width = (bar_width + gap + value_width).max(measure_string(alive_str, scale).0);
+    let bg_height = line_height * 3 + padding;
+    let bg_y = base_y.saturating_sub(padding);
+    mark_dirty(DirtyRect::new(0, bg_y, bg_width + padding * 2, base_y + bg_height - bg_y));
+    for py in bg_y..(base_y + bg_height).min(fb.height) {
+        for px in 0..(bg_width + padding * 2).min(fb.width) {
+            fb.put_pixel(px, py, bg_color);
         }
     }
-    drop(fb_guard);
-
-    // Health (red/green based on value)
-    let health_color = if health > 50 {
-        0x0000FF00 // Green
-    } else if health > 25 {
-        0x00FFFF00 // Yellow
-    } else {
-        0x00FF0000 // Red
-    };
-    let mut buf = [0u8; 16];
-    let health_str = format_stat("HP", health as u32, &mut buf);
-    draw_string(padding, base_y, health_str, health_color, scale);
 
-    // Materials (orange)
-    let mut buf2 = [0u8; 16];
-    let mat_str = format_stat("MAT", materials, &mut buf2);
-    draw_string(padding, base_y + line_height, mat_str, 0x00FFA500, scale);
+    health_bar.draw(fb, health as f32 / 100.0);
+    shield_bar.draw(fb, shield as f32 / 100.0);
 
-    // Alive count (white)
-    let mut buf3 = [0u8; 16];
-    let alive_str = format_alive(alive, total, &mut buf3);
-    draw_string(padding, base_y + line_height * 2, alive_str, 0x00FFFFFF, scale);
-}
+    // Bar values
+    let mut buf = [0u8; 8];
+    let health_str = format_number(health as u32, &mut buf);
+    let health_rect = Rect::new(value_x, base_y, value_width, text_height);
+    draw_string_aligned(fb, health_rect, health_str, Align::Right, health_bar.fill_color(health as f32 / 100.0), scale);
 
-/// Format a stat line like "HP: 100"
-fn format_stat<'a>(label: &str, value: u32, buf: &'a mut [u8; 16]) -> &'a str {
-    let mut pos = 0;
-    for c in label.bytes() {
-        if pos < 16 {
-            buf[pos] = c;
-            pos += 1;
-        }
-    }
-    if pos < 16 {
-        buf[pos] = b':';
-        pos += 1;
-    }
-    if pos < 16 {
-        buf[pos] = b' ';
-        pos += 1;
-    }
+    let mut buf = [0u8; 8];
+    let shield_str = format_number(shield as u32, &mut buf);
+    let shield_rect = Rect::new(value_x, base_y + line_height, value_width, text_height);
+    draw_string_aligned(fb, shield_rect, shield_str, Align::Right, colors::FN_BLUE, scale);
 
-    // Write number
-    if value == 0 {
-        if pos < 16 {
-            buf[pos] = b'0';
-            pos += 1;
-        }
-    } else {
-        let mut n = value;
-        let start = pos;
-        while n > 0 && pos < 16 {
-            buf[pos] = b'0' + (n % 10) as u8;
-            n /= 10;
-            pos += 1;
-        }
-        // Reverse the digits
-        buf[start..pos].reverse();
-    }
-
-    unsafe { core::str::from_utf8_unchecked(&buf[..pos]) }
+    // Alive count (white)
+    draw_string_raw(fb, padding, base_y + line_height * 2, alive_str, 0x00FFFFFF, scale);
 }
 
 /// Format alive count like "50/100"
@@ -543,3 +720,136 @@ pub fn format_number(value: u32, buf: &mut [u8]) -> &str {
 
     unsafe { core::str::from_utf8_unchecked(&buf[..pos]) }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::framebuffer::PixelFormat;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    /// Framebuffer backed only by its back buffer (never presented)
+    fn mock_framebuffer(width: usize, height: usize) -> Framebuffer {
+        Framebuffer {
+            address: core::ptr::null_mut(),
+            back_buffer: vec![0; width * height],
+            width,
+            height,
+            pitch: width * 4,
+            bpp: 32,
+            format: PixelFormat::RGB,
+        }
+    }
+
+    /// Bounding box of every lit pixel as (min_x, min_y, max_x, max_y)
+    fn lit_bounds(fb: &Framebuffer) -> Option<(usize, usize, usize, usize)> {
+        let mut bounds: Option<(usize, usize, usize, usize)> = None;
+        for y in 0..fb.height {
+            for x in 0..fb.width {
+                if fb.get_pixel(x, y) != 0 {
+                    let (x0, y0, x1, y1) = bounds.unwrap_or((x, y, x, y));
+                    bounds = Some((x0.min(x), y0.min(y), x1.max(x), y1.max(y)));
+                }
+            }
+        }
+        bounds
+    }
+
+    #[test]
+    fn test_draw_string_centered_offset() {
+        let fb = mock_framebuffer(200, 40);
+        let text = "VICTORY!";
+        let scale = 2;
+
+        draw_string_centered(&fb, 10, text, 0x00FFFFFF, scale);
+
+        let (width, height) = measure_string(text, scale as usize);
+        assert_eq!(height, 16);
+        let start_x = (200 - width) / 2;
+
+        // Glyphs start at their first lit column, so the ink starts exactly at start_x
+        let (first_x, _, last_x, _) = lit_bounds(&fb).unwrap();
+        assert_eq!(first_x, start_x);
+        assert_eq!(last_x, start_x + width - 1);
+    }
+
+    #[test]
+    fn test_measure_string_proportional() {
+        assert_eq!(measure_string("", 3), (0, 24));
+        assert!(string_width("I", 1) < string_width("W", 1));
+        assert_eq!(string_width("I", 2), 2 * string_width("I", 1));
+
+        // Digits are tabular so counters don't jitter as they change
+        let digit = string_width("0", 2);
+        assert!("123456789".chars().all(|c| string_width(c.encode_utf8(&mut [0; 4]), 2) == digit));
+        assert_eq!(string_width("100", 2), string_width("888", 2));
+
+        // Unknown characters and spaces still advance
+        assert!(string_width("A B", 1) > string_width("AB", 1));
+    }
+
+    #[test]
+    fn test_drawing_clips_at_framebuffer_edges() {
+        let fb = mock_framebuffer(40, 20);
+        // Runs off the right and bottom edges without panicking
+        draw_string_raw(&fb, 30, 15, "WWWW", 0x00FFFFFF, 3);
+        draw_string_raw(&fb, 100, 100, "OFFSCREEN", 0x00FFFFFF, 2);
+        draw_string_blended_raw(&fb, 35, 18, "HI", 0x00FFFFFF, 2, 0.5);
+        let (x0, y0, _, _) = lit_bounds(&fb).unwrap();
+        assert!(x0 >= 30 && y0 >= 15);
+    }
+
+    #[test]
+    fn test_draw_string_aligned_stays_inside_rect() {
+        let rect = Rect::new(10, 5, 30, 12);
+
+        // Wider than the rectangle: clipped, starting at its left edge
+        let fb = mock_framebuffer(80, 30);
+        draw_string_aligned(&fb, rect, "WWWWWW", Align::Center, 0x00FFFFFF, 1);
+        let (x0, y0, x1, y1) = lit_bounds(&fb).unwrap();
+        assert_eq!(x0, rect.x);
+        assert!(x1 < rect.right() && y0 >= rect.y && y1 < rect.bottom());
+
+        // Right alignment ends exactly at the right edge
+        let fb = mock_framebuffer(80, 30);
+        draw_string_aligned(&fb, rect, "24", Align::Right, 0x00FFFFFF, 1);
+        let (x0, _, x1, _) = lit_bounds(&fb).unwrap();
+        assert_eq!(x1, rect.right() - 1);
+        assert_eq!(x0, rect.right() - string_width("24", 1));
+    }
+
+    /// Lines wrap_lines produces
+    fn wrapped(text: &str, max_width: usize) -> Vec<&str> {
+        let mut lines = Vec::new();
+        wrap_lines(text, max_width, 1, |line| lines.push(line));
+        lines
+    }
+
+    #[test]
+    fn test_wrap_breaks_at_spaces_and_inside_long_words() {
+        let two_words = string_width("AB CD", 1);
+        assert_eq!(wrapped("AB CD EF", two_words), ["AB CD", "EF"]);
+        assert_eq!(wrapped("AB  CD", string_width("AB", 1)), ["AB", "CD"]);
+        assert_eq!(wrapped("AB\n\nCD", 100), ["AB", "", "CD"]);
+
+        // Too long for any line: broken between characters, every piece fits
+        let narrow = string_width("WWW", 1);
+        let lines = wrapped("WWWWWWW X", narrow);
+        assert_eq!(lines, ["WWW", "WWW", "W X"]);
+        assert!(lines.iter().all(|line| string_width(line, 1) <= narrow));
+    }
+
+    #[test]
+    fn test_wrapped_height_matches_drawing() {
+        let text = "THE STORM THE STORM THE";
+        let max_width = string_width("THE STORM", 2);
+        assert_eq!(measure_string_height(text, max_width, 2), 3 * line_height(2));
+
+        let fb = mock_framebuffer(200, 100);
+        let height = draw_string_wrapped(&fb, 4, 2, max_width, text, 0x00FFFFFF, 2);
+        assert_eq!(height, measure_string_height(text, max_width, 2));
+        let (x0, y0, x1, y1) = lit_bounds(&fb).unwrap();
+        assert!(x0 >= 4 && x1 < 4 + max_width);
+        assert!(y0 >= 2 && y1 < 2 + height);
+    }
+}
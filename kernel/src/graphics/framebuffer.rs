@@ -1,10 +1,58 @@
 //! Framebuffer wrapper for Limine with double buffering
 
 use crate::boot::FRAMEBUFFER_REQUEST;
+use crate::encoding::{Base64Lines, Crc32};
+use crate::serial_println;
+use alloc::string::String;
 use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, Ordering};
 use spin::Mutex;
 
+/// Output pixels downscaled and encoded per step when dumping a screenshot
+const PPM_CHUNK_PIXELS: usize = 256;
+
+/// Bit position of each 8-bit channel in a display pixel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PixelFormat {
+    pub red_shift: u8,
+    pub green_shift: u8,
+    pub blue_shift: u8,
+}
+
+impl PixelFormat {
+    /// 0x00RRGGBB, the layout every color in the kernel uses
+    pub const RGB: Self = Self { red_shift: 16, green_shift: 8, blue_shift: 0 };
+    /// 0x00BBGGRR, reported by some firmware framebuffers
+    pub const BGR: Self = Self { red_shift: 0, green_shift: 8, blue_shift: 16 };
+
+    /// Format from the firmware's (mask size, mask shift) per channel
+    /// None unless each channel is a byte at a byte boundary of a 32-bit pixel
+    pub fn from_masks(red: (u8, u8), green: (u8, u8), blue: (u8, u8)) -> Option<Self> {
+        let byte_channel = |(size, shift): (u8, u8)| size == 8 && shift % 8 == 0 && shift <= 24;
+        if !(byte_channel(red) && byte_channel(green) && byte_channel(blue)) {
+            return None;
+        }
+        Some(Self { red_shift: red.1, green_shift: green.1, blue_shift: blue.1 })
+    }
+
+    /// Whether back buffer colors can be copied to the display unchanged
+    #[inline]
+    pub fn is_rgb(&self) -> bool {
+        *self == Self::RGB
+    }
+
+    /// Convert a 0x00RRGGBB color to this format
+    #[inline]
+    pub fn encode(&self, color: u32) -> u32 {
+        let (r, g, b) = ((color >> 16) & 0xFF, (color >> 8) & 0xFF, color & 0xFF);
+        (r << self.red_shift) | (g << self.green_shift) | (b << self.blue_shift)
+    }
+}
+
 /// Framebuffer information with double buffering support
+///
+/// The back buffer always holds 0x00RRGGBB colors (see `rgb`); `format`
+/// describes the display, and presenting converts when the two differ.
 pub struct Framebuffer {
     pub address: *mut u32,      // Front buffer (display)
     pub back_buffer: Vec<u32>,  // Back buffer (render target)
@@ -12,6 +60,7 @@ pub struct Framebuffer {
     pub height: usize,
     pub pitch: usize, // Bytes per row
     pub bpp: u16,
+    pub format: PixelFormat,
 }
 
 impl Framebuffer {
@@ -28,6 +77,22 @@ impl Framebuffer {
         let row_pixels = pitch / 4;
         let back_buffer = alloc::vec![0u32; row_pixels * height];
 
+        let masks = (
+            (fb.red_mask_size(), fb.red_mask_shift()),
+            (fb.green_mask_size(), fb.green_mask_shift()),
+            (fb.blue_mask_size(), fb.blue_mask_shift()),
+        );
+        let format = match PixelFormat::from_masks(masks.0, masks.1, masks.2) {
+            Some(format) if fb.bpp() == 32 => format,
+            _ => {
+                serial_println!("FB: unsupported pixel format (bpp {}, masks {:?}), assuming RGB", fb.bpp(), masks);
+                PixelFormat::RGB
+            }
+        };
+        if !format.is_rgb() {
+            serial_println!("FB: pixel format {:?}, converting on present", format);
+        }
+
         Some(Self {
             address: fb.addr() as *mut u32,
             back_buffer,
@@ -35,9 +100,20 @@ impl Framebuffer {
             height,
             pitch,
             bpp: fb.bpp(),
+            format,
         })
     }
 
+    /// Switch to a new display mode whose front buffer is at `address`
+    /// The back buffer is reallocated (black) for the new size and pitch.
+    pub fn set_mode(&mut self, address: *mut u32, width: usize, height: usize, pitch: usize) {
+        self.address = address;
+        self.width = width;
+        self.height = height;
+        self.pitch = pitch;
+        self.back_buffer = alloc::vec![0u32; pitch / 4 * height];
+    }
+
     /// Put a pixel at (x, y) with color - writes to BACK buffer
     #[inline]
     pub fn put_pixel(&self, x: usize, y: usize, color: u32) {
@@ -91,6 +167,7 @@ impl Framebuffer {
 
     /// Clear the back buffer with a color (optimized with unrolled 128-bit writes)
     pub fn clear(&self, color: u32) {
+        mark_background(color);
         let row_pixels = self.pitch / 4;
         let total = row_pixels * self.height;
         let ptr64 = self.back_buffer.as_ptr() as *mut u64;
@@ -125,6 +202,10 @@ impl Framebuffer {
     pub fn present(&self) {
         let row_pixels = self.pitch / 4;
         let total = row_pixels * self.height;
+        if !self.format.is_rgb() {
+            self.copy_converted(&self.back_buffer, 0, total);
+            return;
+        }
 
         unsafe {
             let src = self.back_buffer.as_ptr() as *const u64;
@@ -154,8 +235,41 @@ impl Framebuffer {
         }
     }
 
+    /// Copy only the given regions of the back buffer to the front buffer
+    pub fn present_rects(&self, rects: &[DirtyRect]) {
+        let row_pixels = self.pitch / 4;
+        for rect in rects {
+            let rect = rect.clipped(self.width, self.height);
+            for y in rect.y..rect.y + rect.height {
+                self.copy_span(&self.back_buffer, y * row_pixels + rect.x, rect.width);
+            }
+        }
+    }
+
+    /// Copy `len` pixels from `start` of a back buffer sized `src` to the
+    /// same place on the display
+    fn copy_span(&self, src: &[u32], start: usize, len: usize) {
+        if !self.format.is_rgb() {
+            self.copy_converted(src, start, len);
+            return;
+        }
+        let src = &src[start..start + len];
+        // Safety: the front buffer is as large as the back buffer
+        unsafe { core::ptr::copy_nonoverlapping(src.as_ptr(), self.address.add(start), len) };
+    }
+
+    /// Copy `len` pixels of `src` from `start` to the display, converting
+    /// each to the display's pixel format
+    fn copy_converted(&self, src: &[u32], start: usize, len: usize) {
+        for (i, &color) in src[start..start + len].iter().enumerate() {
+            // Safety: the front buffer is as large as the back buffer
+            unsafe { *self.address.add(start + i) = self.format.encode(color) };
+        }
+    }
+
     /// Fill a rectangle
     pub fn fill_rect(&self, x: usize, y: usize, w: usize, h: usize, color: u32) {
+        mark_dirty(DirtyRect::new(x, y, w, h));
         for dy in 0..h {
             for dx in 0..w {
                 self.put_pixel(x + dx, y + dy, color);
@@ -163,6 +277,21 @@ impl Framebuffer {
         }
     }
 
+    /// Fill a rectangle that is presented by the display device filling the
+    /// screen itself, when it can (see set_host_fill)
+    ///
+    /// The back buffer is written either way; a host fill just skips copying
+    /// those pixels, which makes large opaque panels nearly free to present.
+    pub fn fill_rect_accelerated(&self, x: usize, y: usize, w: usize, h: usize, color: u32) {
+        let rect = DirtyRect::new(x, y, w, h).clipped(self.width, self.height);
+        for py in rect.y..rect.bottom() {
+            self.blend_span(rect.x, rect.right(), py, color, 256);
+        }
+        if !HOST_FILL.load(Ordering::Relaxed) || !DIRTY.lock().fills.push(rect, self.format.encode(color)) {
+            mark_dirty(rect);
+        }
+    }
+
     /// Draw a horizontal line
     #[inline]
     pub fn hline(&self, x1: usize, x2: usize, y: usize, color: u32) {
@@ -171,11 +300,31 @@ impl Framebuffer {
         }
         let start = x1.min(x2).min(self.width);
         let end = x1.max(x2).min(self.width);
+        mark_dirty(DirtyRect::new(start, y, end - start, 1));
         for x in start..end {
             self.put_pixel(x, y, color);
         }
     }
 
+    /// Blend `color` over columns [x1, x2) of row y; `alpha` is out of 256 (256 is opaque)
+    ///
+    /// Does not mark anything dirty; callers mark the area they cover as a whole.
+    pub fn blend_span(&self, x1: usize, x2: usize, y: usize, color: u32, alpha: u32) {
+        let end = x2.min(self.width);
+        if y >= self.height || x1 >= end {
+            return;
+        }
+        // Safety: the span lies within row y of our own back buffer
+        let span = unsafe { core::slice::from_raw_parts_mut(self.scanline_ptr(y).add(x1), end - x1) };
+        if alpha >= 256 {
+            span.fill(color);
+        } else {
+            for pixel in span {
+                *pixel = blend_rgb(*pixel, color, alpha);
+            }
+        }
+    }
+
     /// Get raw pointer to a scanline in the BACK buffer
     #[inline]
     pub unsafe fn scanline_ptr(&self, y: usize) -> *mut u32 {
@@ -203,6 +352,493 @@ pub fn init() -> Option<(usize, usize)> {
     Some((w, h))
 }
 
+/// Third buffer between the back buffer and the display (triple buffering)
+///
+/// Presenting snapshots the frame's dirty region into `pending`, a RAM-to-RAM
+/// copy, and hands the back buffer straight back to the renderer. The slow
+/// copy to the display then happens a few rows at a time from `pending`,
+/// which nothing renders into, while the next frame is drawn.
+pub struct PresentQueue {
+    pending: Vec<u32>,
+    /// Region reported back once copied (full when the whole screen was)
+    region: DirtyRects,
+    /// Rectangles still being copied; a full region is one screen rectangle
+    copy: DirtyRects,
+    /// Rectangle and row within it the copy resumes at
+    rect: usize,
+    row: usize,
+    queued: bool,
+}
+
+impl PresentQueue {
+    /// Queue with a third buffer the size of the framebuffer's back buffer
+    pub fn new(fb: &Framebuffer) -> Self {
+        Self {
+            pending: alloc::vec![0u32; fb.back_buffer.len()],
+            region: DirtyRects::new(),
+            copy: DirtyRects::new(),
+            rect: 0,
+            row: 0,
+            queued: false,
+        }
+    }
+
+    /// Whether a frame is waiting to be copied to the display
+    pub fn is_queued(&self) -> bool {
+        self.queued
+    }
+
+    /// Snapshot `region` of the back buffer for copying to the display
+    ///
+    /// A frame still queued is copied out first; its region is returned so
+    /// the caller can update the screen for it.
+    pub fn queue(&mut self, fb: &Framebuffer, region: &DirtyRects) -> Option<DirtyRects> {
+        let previous = self.finish(fb);
+        self.copy = DirtyRects::new();
+        if region.is_full() {
+            self.copy.add(DirtyRect::new(0, 0, fb.width, fb.height));
+        } else {
+            self.copy.extend(region);
+        }
+
+        let row_pixels = fb.pitch / 4;
+        for rect in self.copy.rects() {
+            let rect = rect.clipped(fb.width, fb.height);
+            for y in rect.y..rect.bottom() {
+                let start = y * row_pixels + rect.x;
+                let span = start..start + rect.width;
+                self.pending[span.clone()].copy_from_slice(&fb.back_buffer[span]);
+            }
+        }
+
+        self.region = region.clone();
+        self.rect = 0;
+        self.row = 0;
+        self.queued = true;
+        previous
+    }
+
+    /// Copy up to `max_rows` rows of the queued frame to the display
+    /// Returns the frame's region once the last row is on the display.
+    pub fn step(&mut self, fb: &Framebuffer, max_rows: usize) -> Option<DirtyRects> {
+        if !self.queued {
+            return None;
+        }
+        let row_pixels = fb.pitch / 4;
+        let mut rows = 0;
+        while rows < max_rows {
+            let Some(rect) = self.copy.rects().get(self.rect) else {
+                self.queued = false;
+                return Some(self.region.clone());
+            };
+            let rect = rect.clipped(fb.width, fb.height);
+            if self.row >= rect.height {
+                self.rect += 1;
+                self.row = 0;
+                continue;
+            }
+            fb.copy_span(&self.pending, (rect.y + self.row) * row_pixels + rect.x, rect.width);
+            self.row += 1;
+            rows += 1;
+        }
+        None
+    }
+
+    /// Copy whatever is left of the queued frame
+    pub fn finish(&mut self, fb: &Framebuffer) -> Option<DirtyRects> {
+        self.step(fb, usize::MAX)
+    }
+}
+
+/// Triple buffering state, None when presents copy straight to the display
+/// Lock before FRAMEBUFFER when holding both.
+pub static PRESENT_QUEUE: Mutex<Option<PresentQueue>> = Mutex::new(None);
+
+/// Most separate regions tracked per frame before falling back to a full update
+pub const MAX_DIRTY_RECTS: usize = 16;
+
+/// Back buffer region written during a frame, in pixels
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DirtyRect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl DirtyRect {
+    pub const fn new(x: usize, y: usize, width: usize, height: usize) -> Self {
+        Self { x, y, width, height }
+    }
+
+    /// Rectangle covering the inclusive pixel bounds; parts left of or above
+    /// the screen are dropped
+    pub fn from_bounds(min_x: i32, min_y: i32, max_x: i32, max_y: i32) -> Self {
+        if max_x < 0 || max_y < 0 || max_x < min_x || max_y < min_y {
+            return Self::default();
+        }
+        let (x, y) = (min_x.max(0) as usize, min_y.max(0) as usize);
+        Self::new(x, y, max_x as usize + 1 - x, max_y as usize + 1 - y)
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    #[inline]
+    fn right(&self) -> usize {
+        self.x + self.width
+    }
+
+    #[inline]
+    fn bottom(&self) -> usize {
+        self.y + self.height
+    }
+
+    /// Smallest rectangle covering both
+    pub fn union(&self, other: &DirtyRect) -> DirtyRect {
+        let (x, y) = (self.x.min(other.x), self.y.min(other.y));
+        DirtyRect::new(x, y, self.right().max(other.right()) - x, self.bottom().max(other.bottom()) - y)
+    }
+
+    /// Overlapping or sharing an edge (merging them wastes no area between them)
+    fn touches(&self, other: &DirtyRect) -> bool {
+        self.x <= other.right() && other.x <= self.right() && self.y <= other.bottom() && other.y <= self.bottom()
+    }
+
+    /// The part of the rectangle inside a width x height screen
+    pub fn clipped(&self, width: usize, height: usize) -> DirtyRect {
+        let (x, y) = (self.x.min(width), self.y.min(height));
+        DirtyRect::new(x, y, self.right().min(width) - x, self.bottom().min(height) - y)
+    }
+
+    /// Whether `other` lies entirely inside this rectangle
+    pub fn contains(&self, other: &DirtyRect) -> bool {
+        self.x <= other.x && self.y <= other.y && other.right() <= self.right() && other.bottom() <= self.bottom()
+    }
+}
+
+/// Fixed list of dirty rectangles that merges touching entries
+#[derive(Debug, Clone)]
+pub struct DirtyRects {
+    rects: [DirtyRect; MAX_DIRTY_RECTS],
+    len: usize,
+    /// Overflowed or explicitly invalidated: the whole screen is dirty
+    full: bool,
+}
+
+impl Default for DirtyRects {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DirtyRects {
+    pub const fn new() -> Self {
+        Self {
+            rects: [DirtyRect::new(0, 0, 0, 0); MAX_DIRTY_RECTS],
+            len: 0,
+            full: false,
+        }
+    }
+
+    /// Add a region, merging it with every entry it touches
+    pub fn add(&mut self, rect: DirtyRect) {
+        if self.full || rect.is_empty() {
+            return;
+        }
+        let mut rect = rect;
+        // A merge can make the grown rectangle touch entries it missed before
+        let mut i = 0;
+        while i < self.len {
+            if self.rects[i].touches(&rect) {
+                rect = rect.union(&self.rects[i]);
+                self.len -= 1;
+                self.rects[i] = self.rects[self.len];
+                i = 0;
+            } else {
+                i += 1;
+            }
+        }
+        if self.len == MAX_DIRTY_RECTS {
+            self.set_full();
+        } else {
+            self.rects[self.len] = rect;
+            self.len += 1;
+        }
+    }
+
+    /// Add every region of another list
+    pub fn extend(&mut self, other: &DirtyRects) {
+        if other.full {
+            self.set_full();
+        }
+        for &rect in other.rects() {
+            self.add(rect);
+        }
+    }
+
+    /// Mark the whole screen dirty
+    pub fn set_full(&mut self) {
+        self.full = true;
+        self.len = 0;
+    }
+
+    /// Whether the whole screen must be updated
+    pub fn is_full(&self) -> bool {
+        self.full
+    }
+
+    /// The tracked regions (empty when full)
+    pub fn rects(&self) -> &[DirtyRect] {
+        &self.rects[..self.len]
+    }
+
+    /// Pixels covered by the tracked regions
+    pub fn area(&self) -> usize {
+        self.rects().iter().map(|r| r.width * r.height).sum()
+    }
+}
+
+/// Most solid fills handed to the display device per frame; fills beyond
+/// this are copied like any other drawing
+pub const MAX_HOST_FILLS: usize = 16;
+
+/// Solid rectangles the display device fills on screen by itself
+///
+/// Colors are in the display's pixel format.
+#[derive(Debug, Clone, Copy)]
+pub struct HostFills {
+    fills: [(DirtyRect, u32); MAX_HOST_FILLS],
+    len: usize,
+}
+
+impl HostFills {
+    pub const fn new() -> Self {
+        Self {
+            fills: [(DirtyRect::new(0, 0, 0, 0), 0); MAX_HOST_FILLS],
+            len: 0,
+        }
+    }
+
+    /// Queue a fill; false when the list is full
+    pub fn push(&mut self, rect: DirtyRect, color: u32) -> bool {
+        if rect.is_empty() {
+            return true;
+        }
+        if self.len == MAX_HOST_FILLS {
+            return false;
+        }
+        self.fills[self.len] = (rect, color);
+        self.len += 1;
+        true
+    }
+
+    /// The queued fills, in drawing order
+    pub fn fills(&self) -> &[(DirtyRect, u32)] {
+        &self.fills[..self.len]
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Whether a single fill covers all of `rect`
+    pub fn covers(&self, rect: &DirtyRect) -> bool {
+        self.fills().iter().any(|(fill, _)| fill.contains(rect))
+    }
+}
+
+impl Default for HostFills {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Dirty regions of the current and previous frame
+///
+/// A full-screen background (a clear, or a gradient drawn every frame) is not
+/// tracked as dirty: when this frame used the same background as the last one,
+/// a pixel outside both frames' drawn regions holds that background in the
+/// back buffer and still on the display. Presenting the union of the two
+/// frames' regions therefore refreshes everything that changed.
+struct DirtyTracker {
+    current: DirtyRects,
+    previous: DirtyRects,
+    /// Fills left to the display device this frame (not in `current`)
+    fills: HostFills,
+    /// Key of the last full-screen background drawn this frame
+    background: Option<u32>,
+    previous_background: Option<u32>,
+}
+
+impl DirtyTracker {
+    const fn new() -> Self {
+        Self {
+            current: DirtyRects::new(),
+            previous: DirtyRects::new(),
+            fills: HostFills::new(),
+            background: None,
+            previous_background: None,
+        }
+    }
+
+    /// Finish the frame and return the regions to copy, plus the fills the
+    /// display device makes itself; `untracked` means the back buffer was
+    /// also written without marking
+    fn end_frame(&mut self, untracked: bool) -> (DirtyRects, HostFills) {
+        let fills = core::mem::take(&mut self.fills);
+        let mut region = self.current.clone();
+        if self.previous.is_full() {
+            region.set_full();
+        }
+        // Last frame's drawing under one of this frame's fills is replaced on
+        // screen by the fill itself
+        for rect in self.previous.rects() {
+            if !fills.covers(rect) {
+                region.add(*rect);
+            }
+        }
+        if untracked || self.background != self.previous_background {
+            region.set_full();
+        }
+
+        // Untracked writes may have touched any pixel, so the next frame must
+        // refresh everything as well
+        if untracked {
+            self.current.set_full();
+        }
+        // The fills are drawing like any other when the next frame erases them
+        for (rect, _) in fills.fills() {
+            self.current.add(*rect);
+        }
+        self.previous = core::mem::take(&mut self.current);
+        self.previous_background = self.background.take();
+        (region, fills)
+    }
+}
+
+static DIRTY: Mutex<DirtyTracker> = Mutex::new(DirtyTracker::new());
+
+/// Set when something wrote the back buffer without tracking it (3D frames);
+/// the first frame is always full
+static FULL_REDRAW: AtomicBool = AtomicBool::new(true);
+
+/// Whether fill_rect_accelerated may leave fills to the display device
+static HOST_FILL: AtomicBool = AtomicBool::new(false);
+
+/// Key for background fills that are not a solid clear (colors are 24-bit)
+pub const GRADIENT_BACKGROUND: u32 = 0x0100_0000;
+
+/// Record a back buffer region drawn this frame
+pub fn mark_dirty(rect: DirtyRect) {
+    DIRTY.lock().current.add(rect);
+}
+
+/// Force a full update this frame (the back buffer changed in untracked ways)
+pub fn mark_full_dirty() {
+    FULL_REDRAW.store(true, Ordering::Relaxed);
+}
+
+/// Record a full-screen background fill; `key` identifies its contents (the
+/// clear color, or GRADIENT_BACKGROUND)
+pub fn mark_background(key: u32) {
+    let mut dirty = DIRTY.lock();
+    dirty.background = Some(key);
+    // Whatever was drawn before the fill is gone
+    dirty.current = DirtyRects::new();
+    dirty.fills = HostFills::new();
+}
+
+/// Let fill_rect_accelerated leave solid fills to the display device (set
+/// when the VMSVGA backend can fill rectangles on the host)
+pub fn set_host_fill(enabled: bool) {
+    HOST_FILL.store(enabled, Ordering::Relaxed);
+}
+
+/// End the frame: the regions the display must be refreshed from, and the
+/// fills it should make itself before those copies
+pub fn take_present_region() -> (DirtyRects, HostFills) {
+    let untracked = FULL_REDRAW.swap(false, Ordering::Relaxed);
+    DIRTY.lock().end_frame(untracked)
+}
+
+/// Binary PPM (P6) header for a width x height image with 8-bit channels
+pub fn ppm_header(width: usize, height: usize) -> String {
+    alloc::format!("P6\n{} {}\n255\n", width, height)
+}
+
+/// Pack 0x00RRGGBB pixels into PPM byte order (R, G, B per pixel)
+/// `out` must hold 3 bytes per pixel
+pub fn pack_rgb(pixels: &[u32], out: &mut [u8]) {
+    for (&pixel, rgb) in pixels.iter().zip(out.as_chunks_mut::<3>().0) {
+        *rgb = [(pixel >> 16) as u8, (pixel >> 8) as u8, pixel as u8];
+    }
+}
+
+/// Average 2x2 pixel blocks of two source rows into one output row
+///
+/// `out[i]` covers source columns 2i and 2i+1; `top` and `bottom` must hold
+/// at least `2 * out.len()` pixels.
+pub fn downscale_2x(top: &[u32], bottom: &[u32], out: &mut [u32]) {
+    let channel = |p: u32, shift: u32| (p >> shift) & 0xFF;
+    for (i, dst) in out.iter_mut().enumerate() {
+        let block = [top[2 * i], top[2 * i + 1], bottom[2 * i], bottom[2 * i + 1]];
+        let avg = |shift: u32| (block.iter().map(|&p| channel(p, shift)).sum::<u32>() + 2) / 4;
+        *dst = (avg(16) << 16) | (avg(8) << 8) | avg(0);
+    }
+}
+
+/// Stream the back buffer out the serial port as a half-size base64 PPM
+///
+/// ```text
+/// SCREENSHOT_BEGIN <width> <height> <ppm bytes>
+/// <base64, 76 characters per line>
+/// SCREENSHOT_CRC32 <crc32 of the decoded ppm, 8 hex digits>
+/// SCREENSHOT_END
+/// ```
+///
+/// The image is downscaled 2x to keep the dump to a few seconds of serial
+/// time. Pixels are averaged, packed and encoded PPM_CHUNK_PIXELS at a time,
+/// so no image-sized buffer is ever allocated.
+pub fn dump_ppm_serial(fb: &Framebuffer) {
+    let (width, height) = (fb.width / 2, fb.height / 2);
+    let header = ppm_header(width, height);
+    serial_println!("SCREENSHOT_BEGIN {} {} {}", width, height, header.len() + width * height * 3);
+
+    let mut crc = Crc32::new();
+    let mut encoder = Base64Lines::new(|line: &str| serial_println!("{}", line));
+    crc.update(header.as_bytes());
+    encoder.write(header.as_bytes());
+
+    let stride = fb.pitch / 4;
+    let mut pixels = [0u32; PPM_CHUNK_PIXELS];
+    let mut bytes = [0u8; PPM_CHUNK_PIXELS * 3];
+    for y in 0..height {
+        let top = &fb.back_buffer[2 * y * stride..][..2 * width];
+        let bottom = &fb.back_buffer[(2 * y + 1) * stride..][..2 * width];
+        for x in (0..width).step_by(PPM_CHUNK_PIXELS) {
+            let len = PPM_CHUNK_PIXELS.min(width - x);
+            downscale_2x(&top[2 * x..], &bottom[2 * x..], &mut pixels[..len]);
+            pack_rgb(&pixels[..len], &mut bytes[..len * 3]);
+            crc.update(&bytes[..len * 3]);
+            encoder.write(&bytes[..len * 3]);
+        }
+    }
+    encoder.finish();
+
+    serial_println!("SCREENSHOT_CRC32 {:08x}", crc.finish());
+    serial_println!("SCREENSHOT_END");
+}
+
+/// Dump the current back buffer (the last rendered frame) over serial
+pub fn dump_screenshot() {
+    if let Some(fb) = FRAMEBUFFER.lock().as_ref() {
+        dump_ppm_serial(fb);
+    }
+}
+
 /// Pack RGB values into a 32-bit color
 #[inline]
 pub const fn rgb(r: u8, g: u8, b: u8) -> u32 {
@@ -226,3 +862,242 @@ pub fn lerp_color(c1: u32, c2: u32, t: f32) -> u32 {
 
     (r << 16) | (g << 8) | b
 }
+
+/// Blend `overlay` over `base` with integer alpha out of 256
+///
+/// Red and blue are blended together in one multiply; the fixed-point form
+/// of `lerp_color` for per-pixel loops.
+#[inline]
+pub fn blend_rgb(base: u32, overlay: u32, alpha: u32) -> u32 {
+    let alpha = alpha.min(256);
+    let inv = 256 - alpha;
+    let rb = ((overlay & 0xFF00FF) * alpha + (base & 0xFF00FF) * inv) >> 8;
+    let g = ((overlay & 0x00FF00) * alpha + (base & 0x00FF00) * inv) >> 8;
+    (rb & 0xFF00FF) | (g & 0x00FF00)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ppm_header() {
+        assert_eq!(ppm_header(1024, 768), "P6\n1024 768\n255\n");
+        assert_eq!(ppm_header(3, 2).len(), 11);
+    }
+
+    #[test]
+    fn test_blend_rgb_matches_lerp() {
+        let (base, overlay) = (rgb(200, 100, 0), rgb(0, 50, 255));
+        assert_eq!(blend_rgb(base, overlay, 0), base);
+        assert_eq!(blend_rgb(base, overlay, 256), overlay);
+        assert_eq!(blend_rgb(base, overlay, 128), rgb(100, 75, 127));
+        // The alpha byte of the base is dropped, as lerp_color does
+        assert_eq!(blend_rgb(0xFF00_0000 | base, overlay, 64), lerp_color(base, overlay, 0.25));
+    }
+
+    #[test]
+    fn test_pack_rgb_byte_order() {
+        let pixels = [rgb(255, 0, 0), rgb(0, 128, 0), rgb(1, 2, 3), 0xFF00_0000 | rgb(4, 5, 6)];
+        let mut out = [0u8; 12];
+        pack_rgb(&pixels, &mut out);
+        // The unused top byte never reaches the image
+        assert_eq!(out, [255, 0, 0, 0, 128, 0, 1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn test_downscale_2x_averages_blocks() {
+        let top = [rgb(0, 0, 0), rgb(255, 255, 255), rgb(10, 20, 30), rgb(10, 20, 30)];
+        let bottom = [rgb(0, 0, 0), rgb(255, 255, 255), rgb(10, 20, 30), 0xFF00_0000 | rgb(10, 20, 30)];
+        let mut out = [0u32; 2];
+        downscale_2x(&top, &bottom, &mut out);
+        // Rounded to nearest: (0 + 255 + 0 + 255) / 4 = 127.5
+        assert_eq!(out, [rgb(128, 128, 128), rgb(10, 20, 30)]);
+    }
+
+    #[test]
+    fn test_dirty_rects_merge_touching() {
+        let mut dirty = DirtyRects::new();
+        dirty.add(DirtyRect::new(0, 0, 10, 10));
+        dirty.add(DirtyRect::new(100, 100, 5, 5));
+        // Shares an edge with the first rectangle
+        dirty.add(DirtyRect::new(10, 0, 10, 5));
+        dirty.add(DirtyRect::new(0, 0, 0, 50));
+        assert_eq!(dirty.rects().len(), 2);
+        assert!(dirty.rects().contains(&DirtyRect::new(0, 0, 20, 10)));
+
+        // Bridging both entries collapses the list to one rectangle
+        dirty.add(DirtyRect::new(5, 5, 100, 100));
+        assert_eq!(dirty.rects(), &[DirtyRect::new(0, 0, 105, 105)]);
+        assert_eq!(dirty.area(), 105 * 105);
+    }
+
+    #[test]
+    fn test_dirty_rects_overflow_to_full() {
+        let mut dirty = DirtyRects::new();
+        for i in 0..MAX_DIRTY_RECTS {
+            dirty.add(DirtyRect::new(i * 10, 0, 5, 5));
+        }
+        assert!(!dirty.is_full());
+        dirty.add(DirtyRect::new(0, 100, 5, 5));
+        assert!(dirty.is_full());
+        assert!(dirty.rects().is_empty());
+    }
+
+    #[test]
+    fn test_dirty_rect_bounds_and_clipping() {
+        assert_eq!(DirtyRect::from_bounds(-4, 2, 5, 3), DirtyRect::new(0, 2, 6, 2));
+        assert!(DirtyRect::from_bounds(-10, -10, -1, 5).is_empty());
+        assert_eq!(DirtyRect::new(90, 40, 20, 20).clipped(100, 50), DirtyRect::new(90, 40, 10, 10));
+        assert!(DirtyRect::new(120, 0, 5, 5).clipped(100, 50).is_empty());
+    }
+
+    #[test]
+    fn test_dirty_tracker_presents_both_frames() {
+        let mut tracker = DirtyTracker::new();
+        let button = DirtyRect::new(10, 10, 20, 20);
+        let cursor = DirtyRect::new(200, 200, 12, 16);
+
+        // The first frame with a background is always full
+        tracker.background = Some(GRADIENT_BACKGROUND);
+        tracker.current.add(button);
+        assert!(tracker.end_frame(false).0.is_full());
+
+        // Same background: this frame's and last frame's drawing only
+        tracker.background = Some(GRADIENT_BACKGROUND);
+        tracker.current.add(cursor);
+        let region = tracker.end_frame(false).0;
+        assert!(!region.is_full());
+        assert_eq!(region.area(), button.width * button.height + cursor.width * cursor.height);
+
+        // A different background replaces every pixel
+        tracker.background = Some(rgb(20, 25, 40));
+        assert!(tracker.end_frame(false).0.is_full());
+    }
+
+    #[test]
+    fn test_dirty_tracker_untracked_frame_forces_next_full() {
+        let mut tracker = DirtyTracker::new();
+        tracker.background = Some(0);
+        tracker.end_frame(false);
+
+        // A 3D frame, then a menu frame on the same background
+        tracker.background = Some(0);
+        assert!(tracker.end_frame(true).0.is_full());
+        tracker.background = Some(0);
+        assert!(tracker.end_frame(false).0.is_full());
+        tracker.background = Some(0);
+        assert!(tracker.end_frame(false).0.rects().is_empty());
+    }
+
+    #[test]
+    fn test_dirty_tracker_host_fills() {
+        let mut tracker = DirtyTracker::new();
+        let panel = DirtyRect::new(100, 100, 200, 80);
+        let label = DirtyRect::new(120, 110, 40, 10);
+        tracker.background = Some(0);
+        tracker.end_frame(false);
+
+        // The panel is filled by the device; only the label on it is copied
+        tracker.background = Some(0);
+        assert!(tracker.fills.push(panel, 0x203040));
+        tracker.current.add(label);
+        let (region, fills) = tracker.end_frame(false);
+        assert_eq!(region.rects(), [label]);
+        assert_eq!(fills.fills(), [(panel, 0x203040)]);
+
+        // Filled again: last frame's panel and label are under the new fill
+        tracker.background = Some(0);
+        tracker.fills.push(panel, 0x203040);
+        let (region, _) = tracker.end_frame(false);
+        assert!(region.rects().is_empty());
+
+        // Gone: its area is copied from the back buffer to erase it
+        tracker.background = Some(0);
+        let (region, fills) = tracker.end_frame(false);
+        assert_eq!(region.rects(), [panel]);
+        assert!(fills.is_empty());
+    }
+
+    /// Framebuffer presenting into `front` (width x height, no row padding)
+    fn presenting_framebuffer(front: &mut [u32], width: usize, height: usize, format: PixelFormat) -> Framebuffer {
+        Framebuffer {
+            address: front.as_mut_ptr(),
+            back_buffer: alloc::vec![0; width * height],
+            width,
+            height,
+            pitch: width * 4,
+            bpp: 32,
+            format,
+        }
+    }
+
+    #[test]
+    fn test_pixel_format_from_masks() {
+        assert_eq!(PixelFormat::from_masks((8, 16), (8, 8), (8, 0)), Some(PixelFormat::RGB));
+        assert_eq!(PixelFormat::from_masks((8, 0), (8, 8), (8, 16)), Some(PixelFormat::BGR));
+        // 5:6:5 and unaligned channels are not supported
+        assert_eq!(PixelFormat::from_masks((5, 11), (6, 5), (5, 0)), None);
+        assert_eq!(PixelFormat::from_masks((8, 4), (8, 12), (8, 20)), None);
+    }
+
+    #[test]
+    fn test_bgr_framebuffer_presents_red_in_low_byte() {
+        let mut front = [0u32; 4];
+        let fb = presenting_framebuffer(&mut front, 2, 2, PixelFormat::BGR);
+        fb.put_pixel(0, 0, rgb(255, 0, 0));
+        fb.put_pixel(1, 1, rgb(1, 2, 3));
+        // The back buffer keeps the kernel's 0x00RRGGBB layout
+        assert_eq!(fb.get_pixel(0, 0), 0x00FF_0000);
+
+        fb.present();
+        drop(fb);
+        // Little-endian memory: red is the first byte of a BGR pixel
+        assert_eq!(front[0].to_le_bytes(), [255, 0, 0, 0]);
+        assert_eq!(front[3].to_le_bytes(), [1, 2, 3, 0]);
+
+        let mut front = [0u32; 4];
+        let fb = presenting_framebuffer(&mut front, 2, 2, PixelFormat::BGR);
+        fb.put_pixel(1, 0, rgb(255, 0, 0));
+        fb.present_rects(&[DirtyRect::new(1, 0, 1, 1)]);
+        drop(fb);
+        assert_eq!(front[1].to_le_bytes(), [255, 0, 0, 0]);
+
+        // RGB displays get the color unchanged: red is the third byte
+        let mut front = [0u32; 4];
+        let fb = presenting_framebuffer(&mut front, 2, 2, PixelFormat::RGB);
+        fb.put_pixel(0, 0, rgb(255, 0, 0));
+        fb.present();
+        drop(fb);
+        assert_eq!(front[0].to_le_bytes(), [0, 0, 255, 0]);
+    }
+
+    #[test]
+    fn test_present_queue_copies_snapshot_in_steps() {
+        let mut front = [0u32; 16];
+        let fb = presenting_framebuffer(&mut front, 4, 4, PixelFormat::RGB);
+        let mut queue = PresentQueue::new(&fb);
+        let mut region = DirtyRects::new();
+        region.add(DirtyRect::new(0, 0, 4, 3));
+
+        fb.fill_rect(0, 0, 4, 4, 0x11);
+        assert!(queue.queue(&fb, &region).is_none());
+        // The next frame renders while the queued one is still being copied
+        fb.fill_rect(0, 0, 4, 4, 0x22);
+
+        assert!(queue.step(&fb, 2).is_none());
+        let shown = |i: usize| unsafe { *fb.address.add(i) };
+        assert_eq!((shown(4), shown(8)), (0x11, 0));
+        let done = queue.step(&fb, 2).expect("third row finishes the frame");
+        assert_eq!(done.rects(), region.rects());
+        assert!(!queue.is_queued());
+        assert_eq!((shown(8), shown(12)), (0x11, 0));
+
+        // Queueing over an unfinished frame copies it out first
+        let mut full = DirtyRects::new();
+        full.set_full();
+        queue.queue(&fb, &full);
+        assert!(queue.queue(&fb, &region).is_some_and(|r| r.is_full()));
+        assert_eq!(shown(12), 0x22);
+    }
+}
@@ -4,14 +4,21 @@
 //! multiple backends:
 //! - SVGA3D for true GPU 3D hardware acceleration
 //! - VMSVGA (VMware SVGA II) for 2D hardware-accelerated display
+//! - Bochs DISPI (QEMU `-vga std`, VirtualBox VBoxVGA) for mode setting
+//!   without acceleration
 //! - Software framebuffer (Limine) as a fallback
 //!
 //! The init() function automatically selects the best available backend.
 
-use crate::drivers::vmsvga;
-use crate::graphics::framebuffer::{self, Framebuffer, FRAMEBUFFER};
+use crate::drivers::{bochs, vmsvga};
+use crate::graphics::cursor;
+use crate::graphics::framebuffer::{self, DirtyRects, Framebuffer, PresentQueue, FRAMEBUFFER};
 use crate::graphics::gpu3d;
+use crate::graphics::gpu_batch;
+use crate::graphics::tiles;
+use crate::graphics::zbuffer;
 use crate::serial_println;
+use core::sync::atomic::{AtomicU32, Ordering};
 use spin::Mutex;
 
 /// GPU backend type
@@ -23,11 +30,28 @@ pub enum GpuBackend {
     Vmsvga,
     /// SVGA3D (true GPU 3D rasterization)
     Svga3D,
+    /// Bochs DISPI linear framebuffer (mode setting, software rendering)
+    BochsDispi,
 }
 
 /// Currently active GPU backend
 static ACTIVE_BACKEND: Mutex<GpuBackend> = Mutex::new(GpuBackend::Software);
 
+/// Display modes offered by the settings screen, smallest first
+/// (all tall enough for the menu layouts)
+pub const COMMON_MODES: [(usize, usize); 6] = [
+    (1024, 768),
+    (1280, 720),
+    (1280, 800),
+    (1366, 768),
+    (1600, 900),
+    (1920, 1080),
+];
+
+/// Bumped on every successful set_mode so code that caches the screen size
+/// (UI screens, the projection matrix) can tell it changed
+static MODE_GENERATION: AtomicU32 = AtomicU32::new(0);
+
 /// Get the currently active backend
 pub fn active_backend() -> GpuBackend {
     *ACTIVE_BACKEND.lock()
@@ -35,7 +59,8 @@ pub fn active_backend() -> GpuBackend {
 
 /// Initialize the GPU subsystem
 ///
-/// Attempts to initialize SVGA3D first, then VMSVGA, falls back to Limine framebuffer.
+/// Attempts to initialize SVGA3D first, then VMSVGA, then Bochs DISPI, falls
+/// back to Limine framebuffer.
 /// Returns (width, height) on success.
 pub fn init() -> (usize, usize) {
     // ALWAYS initialize Limine framebuffer first to get the configured resolution
@@ -57,6 +82,9 @@ pub fn init() -> (usize, usize) {
                 serial_println!("GPU: WARNING - VMSVGA resolution {}x{} differs from Limine {}x{}", w, h, limine_w, limine_h);
             }
 
+            *ACTIVE_BACKEND.lock() = GpuBackend::Vmsvga;
+            let (w, h) = apply_boot_resolution(w, h);
+
             // VMSVGA 2D is now active, try to enable SVGA3D
             if vmsvga::is_3d_available() {
                 // Try to initialize GPU3D rendering
@@ -72,7 +100,20 @@ pub fn init() -> (usize, usize) {
                 serial_println!("GPU: SVGA3D not available, using VMSVGA 2D backend {}x{}", w, h);
             }
 
+            // Opaque 2D fills and the mouse cursor can be left to the host
+            if *ACTIVE_BACKEND.lock() == GpuBackend::Vmsvga {
+                let device = vmsvga::VMSVGA_DEVICE.lock();
+                if device.has_rect_fill() {
+                    framebuffer::set_host_fill(true);
+                    serial_println!("GPU: Using host rectangle fills for UI panels");
+                }
+                if cursor::init_hardware(&device) {
+                    serial_println!("GPU: Using hardware cursor");
+                }
+            }
+
             // Framebuffer already initialized above
+            init_buffering();
             return (w, h);
         }
         serial_println!("GPU: VMSVGA initialization failed, falling back to software");
@@ -80,13 +121,138 @@ pub fn init() -> (usize, usize) {
         serial_println!("GPU: VMSVGA device not available");
     }
 
+    // Try Bochs DISPI (QEMU -vga std) with Limine's resolution
+    if bochs::is_available() {
+        serial_println!("GPU: Bochs DISPI device detected, attempting initialization...");
+        if let Some((w, h)) = bochs::init_with_resolution(limine_w as u32, limine_h as u32) {
+            // Present to the DISPI framebuffer, which is always 0x00RRGGBB
+            {
+                let device = bochs::BOCHS_DEVICE.lock();
+                let mut fb = FRAMEBUFFER.lock();
+                if let Some(ref mut f) = *fb {
+                    f.set_mode(device.front_buffer(), w, h, device.pitch());
+                    f.format = framebuffer::PixelFormat::RGB;
+                }
+            }
+            *ACTIVE_BACKEND.lock() = GpuBackend::BochsDispi;
+            let (w, h) = apply_boot_resolution(w, h);
+            serial_println!("GPU: Using Bochs DISPI backend (software rendering) {}x{}", w, h);
+            init_buffering();
+            return (w, h);
+        }
+        serial_println!("GPU: Bochs DISPI initialization failed, falling back to software");
+    }
+
     // Fall back to Limine framebuffer (already initialized)
     serial_println!("GPU: Using software rendering (Limine framebuffer)");
     *ACTIVE_BACKEND.lock() = GpuBackend::Software;
     serial_println!("GPU: Software framebuffer {}x{}", limine_w, limine_h);
+    init_buffering();
     (limine_w, limine_h)
 }
 
+/// Switch to the mode asked for on the command line (`res=WxH`), if any
+/// Returns the resulting mode; the current one if there is none or it fails.
+fn apply_boot_resolution(width: usize, height: usize) -> (usize, usize) {
+    match crate::boot::config().resolution {
+        Some((rw, rh)) if (rw as usize, rh as usize) != (width, height) => {
+            set_display_mode(rw as usize, rh as usize).unwrap_or_else(|| {
+                serial_println!("GPU: Keeping {}x{}, res={}x{} is not available", width, height, rw, rh);
+                (width, height)
+            })
+        }
+        _ => (width, height),
+    }
+}
+
+/// Turn on triple buffering when the command line asks for it (`buffers=3`)
+/// SVGA3D presents its own render target, so it always double buffers.
+fn init_buffering() {
+    let requested = crate::boot::config().triple_buffering;
+    let triple = requested && *ACTIVE_BACKEND.lock() != GpuBackend::Svga3D && set_triple_buffering(true);
+    if triple {
+        serial_println!("GPU: Triple buffering (display copy overlaps rendering)");
+    } else if requested {
+        serial_println!("GPU: Double buffering (triple buffering unavailable on this backend)");
+    } else {
+        serial_println!("GPU: Double buffering");
+    }
+}
+
+/// Switch the display to `width` x `height` while running
+///
+/// Reprograms the VMSVGA or Bochs DISPI mode, then reallocates everything sized to the
+/// screen: the back buffer, the third buffer, the SVGA3D render targets, the
+/// GPU batch targets, the z-buffer and the tile grid. Callers that cache the
+/// size notice through mode_generation. Returns the new size, or None (and
+/// the old mode stays) if the backend can't change modes or the device
+/// rejects this one. Call between frames, never while tiles are rendering.
+pub fn set_mode(width: usize, height: usize) -> Option<(usize, usize)> {
+    let (w, h) = set_display_mode(width, height)?;
+    if *ACTIVE_BACKEND.lock() == GpuBackend::Svga3D && !gpu3d::resize(w as u32, h as u32) {
+        serial_println!("GPU: SVGA3D targets lost, continuing with VMSVGA 2D");
+        *ACTIVE_BACKEND.lock() = GpuBackend::Vmsvga;
+    }
+    // Failure turns the batch path off; frames go through the software path
+    gpu_batch::resize(w as u32, h as u32);
+    zbuffer::init(w, h);
+    tiles::init(w, h);
+    if let Some(queue) = tiles::TILE_QUEUE.lock().as_ref() {
+        tiles::init_bins(queue.tile_count());
+    }
+
+    MODE_GENERATION.fetch_add(1, Ordering::Release);
+    serial_println!("GPU: Display mode {}x{}", w, h);
+    Some((w, h))
+}
+
+/// Number of mode switches so far (see set_mode)
+pub fn mode_generation() -> u32 {
+    MODE_GENERATION.load(Ordering::Acquire)
+}
+
+/// Switch the display device's mode and resize the framebuffer (and third
+/// buffer) to it. The software backend is stuck with the bootloader's mode.
+fn set_display_mode(width: usize, height: usize) -> Option<(usize, usize)> {
+    let backend = *ACTIVE_BACKEND.lock();
+    let ready = match backend {
+        GpuBackend::Svga3D | GpuBackend::Vmsvga => vmsvga::VMSVGA_DEVICE.lock().is_initialized(),
+        GpuBackend::BochsDispi => bochs::BOCHS_DEVICE.lock().is_initialized(),
+        GpuBackend::Software => false,
+    };
+    if !ready {
+        return None;
+    }
+
+    // A frame still queued for the display belongs to the old mode
+    let mut queue = framebuffer::PRESENT_QUEUE.lock();
+    let mut fb = FRAMEBUFFER.lock();
+    let f = fb.as_mut()?;
+    if let Some(ref mut pending) = *queue {
+        pending.finish(f);
+    }
+
+    // Both mappings cover all of VRAM; Limine's only its own mode
+    if backend == GpuBackend::BochsDispi {
+        let mut device = bochs::BOCHS_DEVICE.lock();
+        let (w, h) = device.set_mode(width as u32, height as u32)?;
+        f.set_mode(device.front_buffer(), w, h, device.pitch());
+    } else {
+        let mut device = vmsvga::VMSVGA_DEVICE.lock();
+        let (w, h) = device.set_mode(width as u32, height as u32)?;
+        f.set_mode(device.front_buffer(), w, h, device.pitch());
+    }
+    let (w, h) = (f.width, f.height);
+
+    if queue.is_some() {
+        *queue = Some(PresentQueue::new(f));
+    }
+    drop(fb);
+    drop(queue);
+    framebuffer::mark_full_dirty();
+    Some((w, h))
+}
+
 /// Initialize a compatibility software framebuffer for VMSVGA mode
 ///
 /// The existing codebase uses FRAMEBUFFER directly. When VMSVGA is active,
@@ -104,7 +270,7 @@ pub fn dimensions() -> (usize, usize) {
             let device = vmsvga::VMSVGA_DEVICE.lock();
             device.dimensions()
         }
-        GpuBackend::Software => {
+        GpuBackend::Software | GpuBackend::BochsDispi => {
             let fb = FRAMEBUFFER.lock();
             if let Some(ref f) = *fb {
                 (f.width, f.height)
@@ -122,7 +288,7 @@ pub fn pitch() -> usize {
             let device = vmsvga::VMSVGA_DEVICE.lock();
             device.pitch()
         }
-        GpuBackend::Software => {
+        GpuBackend::Software | GpuBackend::BochsDispi => {
             let fb = FRAMEBUFFER.lock();
             if let Some(ref f) = *fb {
                 f.pitch
@@ -133,12 +299,25 @@ pub fn pitch() -> usize {
     }
 }
 
+/// Rows of a queued frame copied to the display per present_step call
+const PRESENT_STEP_ROWS: usize = 32;
+
 /// Present the back buffer to the display
 ///
-/// This copies the back buffer to the front buffer and triggers
-/// a screen update (for VMSVGA).
+/// This copies the regions of the back buffer drawn this frame (see
+/// framebuffer::mark_dirty) to the front buffer and triggers a screen update
+/// for them (for VMSVGA). 3D frames and overflowing dirty lists fall back to
+/// a full copy.
+///
+/// With triple buffering the regions are only snapshotted here; the copy to
+/// the display is spread over present_step calls during the next frame.
 pub fn present() {
     let backend = *ACTIVE_BACKEND.lock();
+    // A software cursor goes on top of the finished frame
+    if let Some(ref f) = *FRAMEBUFFER.lock() {
+        cursor::composite_software(f);
+    }
+    let (mut region, fills) = framebuffer::take_present_region();
 
     // For SVGA3D, use the GPU 3D end_frame which presents the render target
     if backend == GpuBackend::Svga3D && gpu3d::is_ready() {
@@ -146,24 +325,121 @@ pub fn present() {
         return;
     }
 
-    // For VMSVGA and Software, use Limine's present() to copy back buffer to front buffer.
+    // The previous frame must be on the display before the host fills below
+    // draw this one's panels over it
+    let mut queue = framebuffer::PRESENT_QUEUE.lock();
+    if let Some(ref mut queue) = *queue {
+        let fb = FRAMEBUFFER.lock();
+        if let Some(previous) = fb.as_ref().and_then(|f| queue.finish(f)) {
+            drop(fb);
+            update_display(backend, &previous);
+        }
+    }
+
+    // Solid panels go to the host first (a full copy covers them anyway).
+    // The copies below may draw over them, so wait until the host is done.
+    if backend == GpuBackend::Vmsvga && !region.is_full() && !fills.is_empty() {
+        let device = vmsvga::VMSVGA_DEVICE.lock();
+        for &(rect, color) in fills.fills() {
+            if !device.fill_rect_accelerated(rect.x, rect.y, rect.width, rect.height, color) {
+                region.add(rect);
+            }
+        }
+        device.fifo().sync();
+    }
+
+    // For VMSVGA and Software, use Limine's framebuffer to copy back buffer to front buffer.
     // Limine's front buffer is mapped with proper caching by the bootloader.
     {
         let fb = FRAMEBUFFER.lock();
         if let Some(ref f) = *fb {
-            f.present();
+            if let Some(ref mut queue) = *queue {
+                queue.queue(f, &region);
+                // The screen is updated once present_step has copied it
+                region = DirtyRects::new();
+            } else if region.is_full() {
+                f.present();
+            } else {
+                f.present_rects(region.rects());
+            }
         }
     }
+    drop(queue);
 
-    // If VMSVGA is active (but not SVGA3D), send UPDATE command to refresh the display.
-    // This tells VMSVGA that the framebuffer contents have changed.
+    // If VMSVGA is active (but not SVGA3D), send UPDATE commands to refresh the display.
+    // This tells VMSVGA which parts of the framebuffer contents have changed.
     // Limine's framebuffer should be the same as VMSVGA's when -vga vmware is used.
     if backend == GpuBackend::Vmsvga {
-        let device = vmsvga::VMSVGA_DEVICE.lock();
+        let mut device = vmsvga::VMSVGA_DEVICE.lock();
         if device.is_initialized() {
-            device.update_screen();
+            // Anything drawn through the device itself (gpu::clear, gpu::fill_rect)
+            device.present_damage();
+            cursor::present_hardware(&device);
         }
     }
+    update_display(backend, &region);
+}
+
+/// Copy the next few rows of a frame queued by present (triple buffering)
+///
+/// Called between tiles while the next frame renders; a no-op when nothing
+/// is queued. The screen is updated once the whole frame has been copied.
+pub fn present_step() {
+    // Never wait on the lock: another core may be presenting right now
+    let Some(mut queue) = framebuffer::PRESENT_QUEUE.try_lock() else {
+        return;
+    };
+    let Some(ref mut queue) = *queue else {
+        return;
+    };
+    if !queue.is_queued() {
+        return;
+    }
+    let done = match *FRAMEBUFFER.lock() {
+        Some(ref f) => queue.step(f, PRESENT_STEP_ROWS),
+        None => None,
+    };
+    if let Some(region) = done {
+        update_display(*ACTIVE_BACKEND.lock(), &region);
+    }
+}
+
+/// Switch between double and triple buffering
+/// Any queued frame is copied out first. Returns whether triple buffering is on.
+pub fn set_triple_buffering(enabled: bool) -> bool {
+    let mut queue = framebuffer::PRESENT_QUEUE.lock();
+    let fb = FRAMEBUFFER.lock();
+    let Some(ref f) = *fb else {
+        return false;
+    };
+    let previous = queue.as_mut().and_then(|pending| pending.finish(f));
+    *queue = if enabled { Some(PresentQueue::new(f)) } else { None };
+    drop(fb);
+    if let Some(region) = previous {
+        update_display(*ACTIVE_BACKEND.lock(), &region);
+    }
+    enabled
+}
+
+/// Whether presents go through the third buffer
+pub fn is_triple_buffered() -> bool {
+    framebuffer::PRESENT_QUEUE.lock().is_some()
+}
+
+/// Tell the VMSVGA host which parts of the front buffer changed
+fn update_display(backend: GpuBackend, region: &DirtyRects) {
+    if backend != GpuBackend::Vmsvga {
+        return;
+    }
+    let device = vmsvga::VMSVGA_DEVICE.lock();
+    if !device.is_initialized() {
+        return;
+    }
+    if region.is_full() {
+        device.update_screen();
+    } else {
+        device.update_rects(region.rects());
+    }
 }
 
 /// Clear the back buffer with a color
@@ -181,7 +457,7 @@ pub fn clear(color: u32) {
                 device.clear(color);
             }
         }
-        GpuBackend::Software => {
+        GpuBackend::Software | GpuBackend::BochsDispi => {
             let fb = FRAMEBUFFER.lock();
             if let Some(ref f) = *fb {
                 f.clear(color);
@@ -201,7 +477,7 @@ pub fn put_pixel(x: usize, y: usize, color: u32) {
                 f.put_pixel(x, y, color);
             }
         }
-        GpuBackend::Software => {
+        GpuBackend::Software | GpuBackend::BochsDispi => {
             let fb = FRAMEBUFFER.lock();
             if let Some(ref f) = *fb {
                 f.put_pixel(x, y, color);
@@ -222,7 +498,7 @@ pub fn get_pixel(x: usize, y: usize) -> u32 {
                 0
             }
         }
-        GpuBackend::Software => {
+        GpuBackend::Software | GpuBackend::BochsDispi => {
             let fb = FRAMEBUFFER.lock();
             if let Some(ref f) = *fb {
                 f.get_pixel(x, y)
@@ -244,7 +520,7 @@ pub fn fill_rect(x: usize, y: usize, w: usize, h: usize, color: u32) {
                 f.fill_rect(x, y, w, h, color);
             }
         }
-        GpuBackend::Software => {
+        GpuBackend::Software | GpuBackend::BochsDispi => {
             let fb = FRAMEBUFFER.lock();
             if let Some(ref f) = *fb {
                 f.fill_rect(x, y, w, h, color);
@@ -263,7 +539,7 @@ pub fn is_initialized() -> bool {
             let device = vmsvga::VMSVGA_DEVICE.lock();
             device.is_initialized()
         }
-        GpuBackend::Software => {
+        GpuBackend::Software | GpuBackend::BochsDispi => {
             let fb = FRAMEBUFFER.lock();
             fb.is_some()
         }
@@ -276,6 +552,7 @@ pub fn backend_name() -> &'static str {
         GpuBackend::Svga3D => "SVGA3D (GPU 3D)",
         GpuBackend::Vmsvga => "VMSVGA (2D accel)",
         GpuBackend::Software => "Software (Limine)",
+        GpuBackend::BochsDispi => "bochs-dispi",
     }
 }
 
@@ -144,6 +144,62 @@ pub fn init(width: u32, height: u32) -> bool {
     true
 }
 
+/// Recreate the color and depth targets for a new screen size
+/// Returns false (and marks GPU 3D not ready) if they can't be created.
+pub fn resize(width: u32, height: u32) -> bool {
+    let mut state = GPU3D_STATE.lock();
+    let Some(cid) = state.context_id else {
+        return false;
+    };
+    if (state.width, state.height) == (width, height) {
+        return state.ready;
+    }
+
+    if let Some(sid) = state.color_target.take() {
+        vmsvga::destroy_3d_surface(sid);
+    }
+    if let Some(sid) = state.depth_target.take() {
+        vmsvga::destroy_3d_surface(sid);
+    }
+    state.width = width;
+    state.height = height;
+    state.ready = false;
+
+    let color_sid = vmsvga::create_3d_surface(
+        SurfaceFormat::A8R8G8B8,
+        width,
+        height,
+        1,
+        svga3d::surface_flags::HINT_RENDERTARGET,
+        1,
+    );
+    let depth_sid = vmsvga::create_3d_surface(
+        SurfaceFormat::ZD24S8,
+        width,
+        height,
+        1,
+        svga3d::surface_flags::HINT_DEPTHSTENCIL,
+        1,
+    );
+    state.color_target = color_sid;
+    state.depth_target = depth_sid;
+    let (Some(color_sid), Some(depth_sid)) = (color_sid, depth_sid) else {
+        serial_println!("GPU3D: Failed to recreate render targets at {}x{}", width, height);
+        return false;
+    };
+
+    if !vmsvga::set_3d_render_target(cid, color_sid, Some(depth_sid))
+        || !vmsvga::set_3d_viewport(cid, 0.0, 0.0, width as f32, height as f32)
+    {
+        serial_println!("GPU3D: Failed to bind render targets at {}x{}", width, height);
+        return false;
+    }
+
+    state.ready = true;
+    serial_println!("GPU3D: Render targets resized to {}x{}", width, height);
+    true
+}
+
 /// Set default render states for 3D rendering
 fn set_default_render_states(cid: u32) {
     let device = vmsvga::VMSVGA_DEVICE.lock();
@@ -10,15 +10,30 @@
 //! 3. flush_batch() - Upload to GPU and draw
 //! 4. end_batch() - Present to screen
 //!
+//! Static meshes skip the per-frame CPU transform: draw_cached_mesh() uploads
+//! a mesh once into indexed vertex/index buffer surfaces and then draws it
+//! with the model as the SVGA3D world transform. The cache keeps to a VRAM
+//! budget by evicting the least recently drawn meshes; meshes it can't hold
+//! (and dynamic geometry) go through add_triangle() as before.
+//!
 //! The renderer uses a hybrid approach:
 //! - When GPU 3D is available: triangles are batched and drawn with DRAW_PRIMITIVES
 //! - When GPU 3D is unavailable: falls back to software rasterization
+//!
+//! Some hosts advertise 3D and then render nothing or garbage, so init()
+//! only enables the GPU path after a self-test triangle reads back with the
+//! right pixels. Rejected commands and sync timeouts later in the session
+//! disable it again (see has_failed()).
 
 use crate::drivers::vmsvga::{self, gmr, svga3d};
 use crate::graphics::gpu;
+use crate::graphics::pipeline::{shade_vertex, CullMode};
 use crate::serial_println;
 use alloc::vec::Vec;
+use core::f32::consts::TAU;
 use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use glam::{Mat3, Mat4};
+use renderer::mesh::Mesh;
 use spin::Mutex;
 
 /// Maximum triangles per batch (limited by GMR size)
@@ -35,6 +50,31 @@ pub const TRIANGLE_SIZE: usize = VERTEX_SIZE * 3;
 /// Batch buffer size
 pub const BATCH_BUFFER_SIZE: usize = MAX_TRIANGLES_PER_BATCH * TRIANGLE_SIZE;
 
+/// Key of a static mesh in the mesh cache (chosen by the caller, one per mesh)
+pub type MeshId = u32;
+
+/// VRAM budget for cached vertex and index buffers
+pub const MESH_CACHE_BUDGET: usize = 8 * 1024 * 1024;
+
+/// Maximum cached meshes (each lighting variant counts)
+pub const MESH_CACHE_ENTRIES: usize = 64;
+
+/// Staging GMR size for mesh uploads; larger buffers are uploaded in chunks
+pub const MESH_STAGING_SIZE: usize = 64 * 1024;
+
+/// Yaw steps per turn that baked lighting is quantized to
+const LIGHTING_YAW_STEPS: u32 = 1024;
+
+/// Width and height of the init self-test render target
+const SELF_TEST_SIZE: u32 = 16;
+
+/// Self-test clear and triangle colors (ARGB)
+const SELF_TEST_CLEAR: u32 = 0xFF2040C0;
+const SELF_TEST_COLOR: u32 = 0xFFE0C020;
+
+/// Largest per-channel difference a read-back self-test pixel may have
+const SELF_TEST_TOLERANCE: u32 = 8;
+
 /// GPU vertex format (matches SVGA3D expectations)
 /// Layout: float3 position, DWORD color (D3DCOLOR format)
 #[repr(C, packed)]
@@ -69,6 +109,56 @@ impl GpuTriangle {
     }
 }
 
+/// SVGA3D render states the batch path draws with, re-issued at every begin_batch
+/// Debug modes flip these at runtime (set_render_state)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RenderState {
+    /// Test against the depth surface (LESSEQUAL)
+    pub depth_test: bool,
+    /// Write depth for drawn pixels
+    pub depth_write: bool,
+    /// Cull back faces of meshes drawn with CullMode::Back
+    pub backface_cull: bool,
+}
+
+impl RenderState {
+    pub const DEFAULT: Self = Self { depth_test: true, depth_write: true, backface_cull: true };
+}
+
+impl Default for RenderState {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// A mesh uploaded to vertex and index buffer surfaces
+struct CachedMesh {
+    id: MeshId,
+    /// Yaw step the vertex colors were lit at
+    yaw_step: u32,
+    vertex_surface_id: u32,
+    index_surface_id: u32,
+    /// Bytes per index (2 or 4)
+    index_width: u32,
+    vertex_count: u32,
+    triangle_count: u32,
+    /// VRAM held by both surfaces
+    bytes: usize,
+    /// Frame this mesh was last drawn in
+    last_used: u64,
+}
+
+/// Which transforms the 3D context currently has set
+#[derive(Clone, Copy, PartialEq)]
+enum TransformState {
+    /// Unknown, or the scene camera changed since it was set
+    Unset,
+    /// Identity world/view and the screen-space ortho projection (batched triangles)
+    Screen,
+    /// The frame's camera view and projection (cached meshes)
+    Scene,
+}
+
 /// GPU batch state
 pub struct GpuBatch {
     /// Whether GPU batching is enabled
@@ -101,6 +191,27 @@ pub struct GpuBatch {
     cpu_triangles: Vec<GpuTriangle>,
     /// Whether 3D resources are initialized
     resources_initialized: bool,
+    /// Static meshes resident in VRAM
+    mesh_cache: Vec<CachedMesh>,
+    /// VRAM used by the mesh cache
+    mesh_cache_bytes: usize,
+    /// GMR that mesh uploads are staged through
+    staging_gmr_id: Option<u32>,
+    /// Pointer to write staged upload data
+    staging_ptr: Option<*mut u8>,
+    /// Camera for cached meshes this frame (view, projection)
+    scene: Option<(svga3d::Matrix4x4, svga3d::Matrix4x4)>,
+    /// Transforms currently set on the context
+    transforms: TransformState,
+    /// Cull mode render state currently set on the context
+    cull_mode: u32,
+    /// Render states applied at the start of each batch
+    render_state: RenderState,
+    /// Cached meshes drawn this frame
+    cached_draws: usize,
+    /// Mesh uploads and evictions since init
+    mesh_uploads: u64,
+    mesh_evictions: u64,
 }
 
 impl GpuBatch {
@@ -121,6 +232,17 @@ impl GpuBatch {
             depth_target_id: None,
             cpu_triangles: Vec::new(),
             resources_initialized: false,
+            mesh_cache: Vec::new(),
+            mesh_cache_bytes: 0,
+            staging_gmr_id: None,
+            staging_ptr: None,
+            scene: None,
+            transforms: TransformState::Unset,
+            cull_mode: svga3d::CullMode::None as u32,
+            render_state: RenderState::DEFAULT,
+            cached_draws: 0,
+            mesh_uploads: 0,
+            mesh_evictions: 0,
         }
     }
 }
@@ -139,6 +261,12 @@ static BATCH_ACTIVE: AtomicBool = AtomicBool::new(false);
 /// Triangle count for current batch (lock-free for hot path)
 static BATCH_TRI_COUNT: AtomicUsize = AtomicUsize::new(0);
 
+/// Whether draw_cached_mesh may use the mesh cache (off = everything is batched)
+static MESH_CACHE_ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Set when the GPU path was disabled after a runtime failure
+static GPU_FAILED: AtomicBool = AtomicBool::new(false);
+
 /// Initialize GPU batch renderer
 pub fn init(width: u32, height: u32) -> bool {
     let mut batch = GPU_BATCH.lock();
@@ -161,6 +289,14 @@ pub fn init(width: u32, height: u32) -> bool {
         return false;
     }
 
+    if !self_test(&mut batch) {
+        serial_println!("GPU Batch: Self-test failed, using software fallback");
+        batch.enabled = false;
+        batch.cpu_triangles = Vec::with_capacity(MAX_TRIANGLES_PER_BATCH);
+        return false;
+    }
+    serial_println!("GPU Batch: Self-test passed");
+
     batch.enabled = true;
     batch.resources_initialized = true;
     serial_println!(
@@ -185,6 +321,167 @@ fn init_3d_resources(batch: &mut GpuBatch, width: u32, height: u32) -> bool {
     };
     batch.context_id = Some(cid);
 
+    if !init_render_targets(batch, cid, width, height) {
+        return false;
+    }
+
+    // Set up orthographic projection for screen-space triangles
+    if !vmsvga::set_3d_transform(cid, svga3d::TransformType::Projection, &screen_projection(width, height)) {
+        serial_println!("GPU Batch: Failed to set projection");
+        return false;
+    }
+
+    // Set identity transforms for view and world
+    let identity = svga3d::Matrix4x4::identity();
+    vmsvga::set_3d_transform(cid, svga3d::TransformType::View, &identity);
+    vmsvga::set_3d_transform(cid, svga3d::TransformType::World, &identity);
+    batch.transforms = TransformState::Screen;
+
+    // Allocate GMR for vertex buffer
+    if gmr::is_supported() {
+        if let Some(gmr_id) = gmr::alloc(BATCH_BUFFER_SIZE) {
+            batch.gmr_id = Some(gmr_id);
+            batch.vertex_ptr = gmr::get_write_ptr(gmr_id);
+            serial_println!("GPU Batch: Allocated GMR {} for vertex buffer", gmr_id);
+        } else {
+            serial_println!("GPU Batch: Failed to allocate GMR");
+            return false;
+        }
+    } else {
+        serial_println!("GPU Batch: GMR not supported");
+        return false;
+    }
+
+    // Mesh uploads get their own staging GMR so they never wait on the batch
+    // (without one the mesh cache stays empty and everything is batched)
+    if let Some(gmr_id) = gmr::alloc(MESH_STAGING_SIZE) {
+        batch.staging_gmr_id = Some(gmr_id);
+        batch.staging_ptr = gmr::get_write_ptr(gmr_id);
+        serial_println!("GPU Batch: Allocated GMR {} for mesh uploads", gmr_id);
+    } else {
+        serial_println!("GPU Batch: No staging GMR, mesh cache disabled");
+    }
+
+    // Create vertex buffer surface
+    if let Some(sid) = vmsvga::create_3d_surface(
+        svga3d::SurfaceFormat::Buffer,
+        BATCH_BUFFER_SIZE as u32,
+        1,
+        1,
+        svga3d::surface_flags::HINT_VERTEXBUFFER | svga3d::surface_flags::HINT_DYNAMIC,
+        1,
+    ) {
+        batch.vertex_surface_id = Some(sid);
+        serial_println!("GPU Batch: Created vertex surface {}", sid);
+    } else {
+        serial_println!("GPU Batch: Failed to create vertex surface");
+        return false;
+    }
+
+    // Set up render states
+    setup_render_states(cid, &batch.render_state);
+
+    true
+}
+
+/// Draw a triangle into a small offscreen target, read it back and check a
+/// few pixels on both sides of its edge
+/// The context's render targets and viewport are restored afterwards.
+fn self_test(batch: &mut GpuBatch) -> bool {
+    let (Some(cid), Some(gmr_id), Some(ptr), Some(vertex_sid)) =
+        (batch.context_id, batch.gmr_id, batch.vertex_ptr, batch.vertex_surface_id)
+    else {
+        return false;
+    };
+    let (color_sid, depth_sid) = (batch.color_target_id, batch.depth_target_id);
+    let (width, height) = (batch.width, batch.height);
+
+    // Render into throwaway targets so a failure leaves nothing behind
+    let mut test = GpuBatch::new();
+    let passed = init_render_targets(&mut test, cid, SELF_TEST_SIZE, SELF_TEST_SIZE)
+        && draw_self_test(batch, cid, gmr_id, ptr, vertex_sid)
+        && self_test_passed(&read_self_test(gmr_id, ptr, test.color_target_id));
+    destroy_render_targets(&mut test);
+
+    if let Some(color_sid) = color_sid {
+        vmsvga::set_3d_render_target(cid, color_sid, depth_sid);
+    }
+    vmsvga::set_3d_viewport(cid, 0.0, 0.0, width as f32, height as f32);
+    batch.transforms = TransformState::Unset;
+    passed
+}
+
+/// Clear the bound self-test target and draw the upper-left half of it
+fn draw_self_test(batch: &mut GpuBatch, cid: u32, gmr_id: u32, ptr: *mut u8, vertex_sid: u32) -> bool {
+    let size = SELF_TEST_SIZE as f32;
+    let projection = screen_projection(SELF_TEST_SIZE, SELF_TEST_SIZE);
+    if !vmsvga::set_3d_transform(cid, svga3d::TransformType::Projection, &projection)
+        || !vmsvga::clear_3d(cid, SELF_TEST_CLEAR, 1.0)
+    {
+        return false;
+    }
+    setup_render_states(cid, &batch.render_state);
+
+    let tri = GpuTriangle::new(
+        GpuVertex::new(0.0, 0.0, 0.5, SELF_TEST_COLOR),
+        GpuVertex::new(size, 0.0, 0.5, SELF_TEST_COLOR),
+        GpuVertex::new(0.0, size, 0.5, SELF_TEST_COLOR),
+    );
+    // Safety: the batch GMR holds MAX_TRIANGLES_PER_BATCH triangles
+    unsafe { core::ptr::write_volatile(ptr as *mut GpuTriangle, tri) };
+    core::sync::atomic::fence(Ordering::SeqCst);
+
+    let device = vmsvga::VMSVGA_DEVICE.lock();
+    let fifo = device.fifo();
+    fifo.cmd_3d_upload_vertex_buffer(gmr_id, vertex_sid, TRIANGLE_SIZE as u32)
+        && fifo.cmd_3d_draw_primitives_simple(cid, vertex_sid, 3, VERTEX_SIZE as u32)
+        && fifo.sync()
+}
+
+/// Read the self-test target back through the batch GMR
+/// Returns no pixels if the readback fails
+fn read_self_test(gmr_id: u32, ptr: *mut u8, color_sid: Option<u32>) -> Vec<u32> {
+    let pixels = (SELF_TEST_SIZE * SELF_TEST_SIZE) as usize;
+    let Some(color_sid) = color_sid else {
+        return Vec::new();
+    };
+    // Zero first so a DMA that silently does nothing can't pass
+    // Safety: the GMR is far larger than the test image, and the host is
+    // done with the vertex data (draw_self_test synced)
+    unsafe { core::ptr::write_bytes(ptr as *mut u32, 0, pixels) };
+    core::sync::atomic::fence(Ordering::SeqCst);
+
+    let device = vmsvga::VMSVGA_DEVICE.lock();
+    let fifo = device.fifo();
+    if !fifo.cmd_3d_read_surface(gmr_id, color_sid, SELF_TEST_SIZE, SELF_TEST_SIZE) || !fifo.sync() {
+        return Vec::new();
+    }
+    (0..pixels)
+        .map(|i| unsafe { core::ptr::read_volatile((ptr as *const u32).add(i)) })
+        .collect()
+}
+
+/// Whether a read-back self-test image shows the triangle over the clear color
+/// The triangle covers the upper-left half (x + y < SELF_TEST_SIZE)
+fn self_test_passed(pixels: &[u32]) -> bool {
+    let size = SELF_TEST_SIZE as usize;
+    if pixels.len() != size * size {
+        return false;
+    }
+    let inside = [(2, 2), (size - 4, 1), (1, size - 4)];
+    let outside = [(size - 2, size - 2), (size - 3, size / 2 + 2), (size / 2 + 2, size - 3)];
+    inside.iter().all(|&(x, y)| pixel_matches(pixels[y * size + x], SELF_TEST_COLOR))
+        && outside.iter().all(|&(x, y)| pixel_matches(pixels[y * size + x], SELF_TEST_CLEAR))
+}
+
+/// Whether the RGB channels of `pixel` are within SELF_TEST_TOLERANCE of `expected`
+fn pixel_matches(pixel: u32, expected: u32) -> bool {
+    [0, 8, 16].iter().all(|&shift| ((pixel >> shift) & 0xFF).abs_diff((expected >> shift) & 0xFF) <= SELF_TEST_TOLERANCE)
+}
+
+/// Create the color and Z_D24S8 depth surfaces at `width` x `height`, bind
+/// them as the context's render targets and cover them with the viewport
+fn init_render_targets(batch: &mut GpuBatch, cid: u32, width: u32, height: u32) -> bool {
     // Create color render target surface
     let color_sid = match vmsvga::create_3d_surface(
         svga3d::SurfaceFormat::A8R8G8B8,
@@ -231,73 +528,56 @@ fn init_3d_resources(batch: &mut GpuBatch, width: u32, height: u32) -> bool {
         return false;
     }
 
-    // Set up orthographic projection for screen-space triangles
-    // Screen coordinates: (0,0) top-left to (width, height) bottom-right
-    // Depth: 0.0 (near) to 1.0 (far)
-    let ortho = svga3d::Matrix4x4 {
-        m: [
-            [2.0 / width as f32, 0.0, 0.0, 0.0],
-            [0.0, -2.0 / height as f32, 0.0, 0.0],
-            [0.0, 0.0, 1.0, 0.0],
-            [-1.0, 1.0, 0.0, 1.0],
-        ],
-    };
+    true
+}
 
-    if !vmsvga::set_3d_transform(cid, svga3d::TransformType::Projection, &ortho) {
-        serial_println!("GPU Batch: Failed to set projection");
-        return false;
+/// Destroy the color and depth surfaces
+fn destroy_render_targets(batch: &mut GpuBatch) {
+    if let Some(sid) = batch.color_target_id.take() {
+        vmsvga::destroy_3d_surface(sid);
     }
-
-    // Set identity transforms for view and world
-    let identity = svga3d::Matrix4x4::identity();
-    vmsvga::set_3d_transform(cid, svga3d::TransformType::View, &identity);
-    vmsvga::set_3d_transform(cid, svga3d::TransformType::World, &identity);
-
-    // Allocate GMR for vertex buffer
-    if gmr::is_supported() {
-        if let Some(gmr_id) = gmr::alloc(BATCH_BUFFER_SIZE) {
-            batch.gmr_id = Some(gmr_id);
-            batch.vertex_ptr = gmr::get_write_ptr(gmr_id);
-            serial_println!("GPU Batch: Allocated GMR {} for vertex buffer", gmr_id);
-        } else {
-            serial_println!("GPU Batch: Failed to allocate GMR");
-            return false;
-        }
-    } else {
-        serial_println!("GPU Batch: GMR not supported");
-        return false;
+    if let Some(sid) = batch.depth_target_id.take() {
+        vmsvga::destroy_3d_surface(sid);
     }
+}
 
-    // Create vertex buffer surface
-    if let Some(sid) = vmsvga::create_3d_surface(
-        svga3d::SurfaceFormat::Buffer,
-        BATCH_BUFFER_SIZE as u32,
-        1,
-        1,
-        svga3d::surface_flags::HINT_VERTEXBUFFER | svga3d::surface_flags::HINT_DYNAMIC,
-        1,
-    ) {
-        batch.vertex_surface_id = Some(sid);
-        serial_println!("GPU Batch: Created vertex surface {}", sid);
-    } else {
-        serial_println!("GPU Batch: Failed to create vertex surface");
+/// Recreate the render targets after a resolution change
+/// The old color and depth surfaces are destroyed first. Returns false (and
+/// disables the GPU path) if the new ones can't be created.
+pub fn resize(width: u32, height: u32) -> bool {
+    let mut batch = GPU_BATCH.lock();
+    let Some(cid) = batch.context_id else {
         return false;
+    };
+    if (batch.width, batch.height) == (width, height) && batch.color_target_id.is_some() {
+        return true;
     }
 
-    // Set up render states
-    setup_render_states(cid);
+    destroy_render_targets(&mut batch);
+    batch.width = width;
+    batch.height = height;
+    // The screen projection depends on the size
+    batch.transforms = TransformState::Unset;
 
+    if !init_render_targets(&mut batch, cid, width, height) {
+        serial_println!("GPU Batch: Failed to resize to {}x{}, using software fallback", width, height);
+        destroy_render_targets(&mut batch);
+        batch.enabled = false;
+        return false;
+    }
+    serial_println!("GPU Batch: Render targets resized to {}x{}", width, height);
     true
 }
 
-/// Set up default render states for batch rendering
-fn setup_render_states(cid: u32) {
+/// Issue the batch's render states (depth test/write, culling, fill)
+/// Culling starts off: batched triangles are culled on the CPU, and cached
+/// meshes switch it per draw
+fn setup_render_states(cid: u32, state: &RenderState) {
     let device = vmsvga::VMSVGA_DEVICE.lock();
 
-    // Enable depth testing
     device.fifo().cmd_3d_set_render_state(cid, &[
-        (svga3d::RenderStateId::ZEnable as u32, 1),
-        (svga3d::RenderStateId::ZWriteEnable as u32, 1),
+        (svga3d::RenderStateId::ZEnable as u32, state.depth_test as u32),
+        (svga3d::RenderStateId::ZWriteEnable as u32, state.depth_write as u32),
         (svga3d::RenderStateId::ZFunc as u32, 4), // LESSEQUAL
         (svga3d::RenderStateId::CullMode as u32, svga3d::CullMode::None as u32),
         (svga3d::RenderStateId::FillMode as u32, svga3d::FillMode::Solid as u32),
@@ -306,26 +586,48 @@ fn setup_render_states(cid: u32) {
     ]);
 }
 
+/// Render states the batch path uses
+pub fn render_state() -> RenderState {
+    GPU_BATCH.lock().render_state
+}
+
+/// Change the batch path's render states (applied from the next begin_batch)
+pub fn set_render_state(state: RenderState) {
+    GPU_BATCH.lock().render_state = state;
+}
+
 /// Check if GPU batch rendering is enabled
 pub fn is_enabled() -> bool {
     GPU_BATCH.lock().enabled
 }
 
+/// Whether the GPU path was disabled by a runtime failure this session
+/// The renderer checks this each frame and switches to the software path.
+pub fn has_failed() -> bool {
+    GPU_FAILED.load(Ordering::Acquire)
+}
+
 /// Begin a new batch (call at start of frame)
 pub fn begin_batch() {
     let mut batch = GPU_BATCH.lock();
     batch.triangle_count = 0;
     batch.frame_triangle_count = 0;
     batch.batch_count = 0;
+    batch.cached_draws = 0;
     batch.frame_count += 1;
 
     if !batch.enabled {
         batch.cpu_triangles.clear();
     } else {
-        // Clear GPU render targets
+        // Clear GPU render targets (color and depth) and re-issue the render states
         if let Some(cid) = batch.context_id {
             // Clear to sky blue
-            vmsvga::clear_3d(cid, 0xFF87CEEB, 1.0);
+            if vmsvga::clear_3d(cid, 0xFF87CEEB, 1.0) {
+                setup_render_states(cid, &batch.render_state);
+                batch.cull_mode = svga3d::CullMode::None as u32;
+            } else {
+                batch.fail("clear command rejected");
+            }
         }
     }
 
@@ -333,6 +635,31 @@ pub fn begin_batch() {
     BATCH_ACTIVE.store(true, Ordering::Release);
 }
 
+/// The batch's 3D context with its color and depth render targets
+/// None while the GPU path is disabled.
+pub fn render_targets() -> Option<(u32, u32, Option<u32>)> {
+    let batch = GPU_BATCH.lock();
+    match (batch.enabled, batch.context_id, batch.color_target_id) {
+        (true, Some(cid), Some(color)) => Some((cid, color, batch.depth_target_id)),
+        _ => None,
+    }
+}
+
+/// Clear the render targets mid-frame, flushing queued triangles first
+/// Either part can be left alone; begin_batch already clears both.
+pub fn clear(color: Option<u32>, depth: Option<f32>) {
+    flush_batch();
+    let batch = GPU_BATCH.lock();
+    let (true, Some(cid)) = (batch.enabled, batch.context_id) else {
+        return;
+    };
+    let flags = color.map_or(0, |_| svga3d::clear_flags::COLOR) | depth.map_or(0, |_| svga3d::clear_flags::DEPTH);
+    if flags != 0 {
+        let device = vmsvga::VMSVGA_DEVICE.lock();
+        device.fifo().cmd_3d_clear(cid, flags, color.unwrap_or(0), depth.unwrap_or(1.0), 0);
+    }
+}
+
 /// Add a triangle to the current batch
 /// Returns true if added, false if batch is full (caller should flush)
 #[inline]
@@ -393,7 +720,7 @@ pub fn flush_batch() -> usize {
 
     if batch.enabled {
         // GPU path: DMA upload and draw
-        flush_gpu_batch(&batch, count);
+        flush_gpu_batch(&mut batch, count);
     }
     // CPU fallback: triangles are already in cpu_triangles, caller handles rasterization
 
@@ -406,7 +733,7 @@ pub fn flush_batch() -> usize {
 }
 
 /// Flush batch using GPU
-fn flush_gpu_batch(batch: &GpuBatch, count: usize) {
+fn flush_gpu_batch(batch: &mut GpuBatch, count: usize) {
     let gmr_id = match batch.gmr_id {
         Some(id) => id,
         None => return,
@@ -422,6 +749,10 @@ fn flush_gpu_batch(batch: &GpuBatch, count: usize) {
         None => return,
     };
 
+    // Batched triangles are already in screen space and culled
+    batch.use_transforms(cid, TransformState::Screen);
+    batch.set_cull_mode(cid, svga3d::CullMode::None);
+
     // Memory barrier to ensure all writes are visible
     core::sync::atomic::fence(Ordering::SeqCst);
 
@@ -431,7 +762,8 @@ fn flush_gpu_batch(batch: &GpuBatch, count: usize) {
     // Upload vertex data from GMR to vertex surface
     let data_size = count * TRIANGLE_SIZE;
     if !fifo.cmd_3d_upload_vertex_buffer(gmr_id, vertex_surface_id, data_size as u32) {
-        serial_println!("GPU Batch: Failed to upload vertex buffer");
+        drop(device);
+        batch.fail("vertex buffer upload rejected");
         return;
     }
 
@@ -443,14 +775,16 @@ fn flush_gpu_batch(batch: &GpuBatch, count: usize) {
         num_vertices,
         VERTEX_SIZE as u32,
     ) {
-        serial_println!("GPU Batch: Failed to draw primitives");
+        drop(device);
+        batch.fail("draw command rejected");
         return;
     }
 
     // Sync to ensure drawing is complete before next batch
     // (Only needed if we're doing multiple batches per frame)
-    if batch.batch_count > 0 {
-        fifo.sync();
+    if batch.batch_count > 0 && !fifo.sync() {
+        drop(device);
+        batch.fail("sync timed out");
     }
 }
 
@@ -461,24 +795,34 @@ pub fn end_batch() {
 
     BATCH_ACTIVE.store(false, Ordering::Release);
 
-    let batch = GPU_BATCH.lock();
+    let mut batch = GPU_BATCH.lock();
 
     if batch.enabled {
         // Present GPU render target to screen
-        if let Some(color_sid) = batch.color_target_id {
-            vmsvga::present_3d(color_sid, batch.width, batch.height);
+        if let Some(color_sid) = batch.color_target_id
+            && !vmsvga::present_3d(color_sid, batch.width, batch.height)
+        {
+            batch.fail("present command rejected");
         }
     }
 
     // Log stats periodically
     if batch.frame_count % 300 == 0 && batch.frame_triangle_count > 0 {
         serial_println!(
-            "GPU Batch: frame {} - {} triangles in {} batches (gpu={})",
+            "GPU Batch: frame {} - {} triangles in {} batches + {} cached draws (gpu={})",
             batch.frame_count,
             batch.frame_triangle_count,
             batch.batch_count,
+            batch.cached_draws,
             batch.enabled
         );
+        serial_println!(
+            "GPU Batch: mesh cache {} meshes, {} KB, {} uploads, {} evictions",
+            batch.mesh_cache.len(),
+            batch.mesh_cache_bytes / 1024,
+            batch.mesh_uploads,
+            batch.mesh_evictions
+        );
     }
 }
 
@@ -509,6 +853,306 @@ pub fn needs_flush() -> bool {
     BATCH_TRI_COUNT.load(Ordering::Acquire) >= MAX_TRIANGLES_PER_BATCH
 }
 
+/// Turn the mesh cache on or off (off = every mesh is batched by the caller)
+pub fn set_mesh_cache_enabled(enabled: bool) {
+    MESH_CACHE_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether draw_cached_mesh may draw from the mesh cache
+pub fn is_mesh_cache_enabled() -> bool {
+    MESH_CACHE_ENABLED.load(Ordering::Relaxed)
+}
+
+/// Set the camera that cached meshes are drawn with this frame
+pub fn set_scene_transforms(view: &Mat4, projection: &Mat4) {
+    let mut batch = GPU_BATCH.lock();
+    batch.scene = Some((to_svga_matrix(view), to_svga_matrix(projection)));
+    if batch.transforms == TransformState::Scene {
+        batch.transforms = TransformState::Unset;
+    }
+}
+
+/// Draw a static mesh from the mesh cache with `model` as its world transform,
+/// uploading it on first use
+/// Returns the triangles drawn, or None when the caller should batch the mesh
+/// through add_triangle instead: no GPU 3D or scene camera, cache disabled or
+/// full, or a model tilted or unevenly scaled so its lighting can't be baked
+pub fn draw_cached_mesh(id: MeshId, mesh: &Mesh, model: &Mat4, cull: CullMode) -> Option<usize> {
+    if !is_mesh_cache_enabled() || !BATCH_ACTIVE.load(Ordering::Acquire) {
+        return None;
+    }
+    let yaw_step = lighting_yaw_step(model)?;
+
+    let mut batch = GPU_BATCH.lock();
+    if !batch.enabled || batch.scene.is_none() {
+        return None;
+    }
+    let cid = batch.context_id?;
+    let index = batch.cached_mesh(id, yaw_step, mesh)?;
+
+    batch.use_transforms(cid, TransformState::Scene);
+    vmsvga::set_3d_transform(cid, svga3d::TransformType::World, &to_svga_matrix(model));
+    // Screen Y points down, so back faces are the clockwise ones
+    let cull_back = cull == CullMode::Back && batch.render_state.backface_cull;
+    batch.set_cull_mode(cid, if cull_back { svga3d::CullMode::Cw } else { svga3d::CullMode::None });
+
+    let entry = &batch.mesh_cache[index];
+    let triangles = entry.triangle_count as usize;
+    let drawn = vmsvga::VMSVGA_DEVICE.lock().fifo().cmd_3d_draw_indexed(
+        cid,
+        entry.vertex_surface_id,
+        VERTEX_SIZE as u32,
+        entry.vertex_count,
+        entry.index_surface_id,
+        entry.index_width,
+        entry.triangle_count,
+    );
+    if !drawn {
+        return None;
+    }
+
+    batch.frame_triangle_count += triangles;
+    batch.cached_draws += 1;
+    Some(triangles)
+}
+
+impl GpuBatch {
+    /// Stop using the GPU after a runtime failure and log why
+    /// The renderer sees has_failed() and takes the software path from the
+    /// next frame on.
+    fn fail(&mut self, reason: &str) {
+        if !self.enabled {
+            return;
+        }
+        self.enabled = false;
+        GPU_FAILED.store(true, Ordering::Release);
+        serial_println!("GPU Batch: {}, switching to software rendering", reason);
+    }
+
+    /// Set the transforms `state` needs unless they're already set
+    fn use_transforms(&mut self, cid: u32, state: TransformState) {
+        if self.transforms == state {
+            return;
+        }
+        let identity = svga3d::Matrix4x4::identity();
+        let (view, projection) = match (state, self.scene) {
+            (TransformState::Screen, _) => (identity, screen_projection(self.width, self.height)),
+            (TransformState::Scene, Some(scene)) => scene,
+            _ => return,
+        };
+        // Cached draws leave their model in the world transform
+        if state == TransformState::Screen {
+            vmsvga::set_3d_transform(cid, svga3d::TransformType::World, &identity);
+        }
+        vmsvga::set_3d_transform(cid, svga3d::TransformType::View, &view);
+        vmsvga::set_3d_transform(cid, svga3d::TransformType::Projection, &projection);
+        self.transforms = state;
+    }
+
+    /// Set the cull mode render state if it changed
+    fn set_cull_mode(&mut self, cid: u32, mode: svga3d::CullMode) {
+        let mode = mode as u32;
+        if self.cull_mode == mode {
+            return;
+        }
+        let device = vmsvga::VMSVGA_DEVICE.lock();
+        device.fifo().cmd_3d_set_render_state(cid, &[(svga3d::RenderStateId::CullMode as u32, mode)]);
+        self.cull_mode = mode;
+    }
+
+    /// Index of a mesh's lighting variant in the cache, uploading it if needed
+    fn cached_mesh(&mut self, id: MeshId, yaw_step: u32, mesh: &Mesh) -> Option<usize> {
+        if let Some(index) = self.mesh_cache.iter().position(|c| c.id == id && c.yaw_step == yaw_step) {
+            let entry = &mut self.mesh_cache[index];
+            if entry.vertex_count as usize == mesh.vertices.len()
+                && entry.triangle_count as usize == mesh.triangle_count()
+            {
+                entry.last_used = self.frame_count;
+                return Some(index);
+            }
+            // The id now names other geometry
+            self.evict(index);
+        }
+        self.upload_mesh(id, yaw_step, mesh)
+    }
+
+    /// Upload a mesh lit at `yaw_step` into new vertex and index buffer surfaces
+    /// Evicts the least recently drawn meshes to stay within the budget, but never
+    /// ones drawn this frame or the last: a working set bigger than the budget
+    /// then settles (the overflow is batched) instead of re-uploading every frame
+    fn upload_mesh(&mut self, id: MeshId, yaw_step: u32, mesh: &Mesh) -> Option<usize> {
+        let (gmr_id, staging) = (self.staging_gmr_id?, self.staging_ptr?);
+        let vertex_count = mesh.vertices.len();
+        let triangle_count = mesh.triangle_count();
+        let index_width = index_width(vertex_count);
+        let bytes = vertex_count * VERTEX_SIZE + triangle_count * 3 * index_width as usize;
+        if triangle_count == 0 || bytes > MESH_CACHE_BUDGET {
+            return None;
+        }
+
+        while self.mesh_cache_bytes + bytes > MESH_CACHE_BUDGET || self.mesh_cache.len() >= MESH_CACHE_ENTRIES {
+            let frame = self.frame_count;
+            let (lru, _) = self
+                .mesh_cache
+                .iter()
+                .enumerate()
+                .filter(|(_, c)| c.last_used + 1 < frame)
+                .min_by_key(|(_, c)| c.last_used)?;
+            self.evict(lru);
+        }
+
+        let vertices = baked_vertices(mesh, yaw_step);
+        let indices = packed_indices(&mesh.indices[..triangle_count * 3], index_width);
+        let vertex_surface_id = create_buffer_surface(vertices.len(), svga3d::surface_flags::HINT_VERTEXBUFFER)?;
+        let Some(index_surface_id) = create_buffer_surface(indices.len(), svga3d::surface_flags::HINT_INDEXBUFFER) else {
+            vmsvga::destroy_3d_surface(vertex_surface_id);
+            return None;
+        };
+        if !upload_buffer(gmr_id, staging, vertex_surface_id, &vertices)
+            || !upload_buffer(gmr_id, staging, index_surface_id, &indices)
+        {
+            serial_println!("GPU Batch: Failed to upload mesh {}", id);
+            vmsvga::destroy_3d_surface(vertex_surface_id);
+            vmsvga::destroy_3d_surface(index_surface_id);
+            return None;
+        }
+
+        self.mesh_cache.push(CachedMesh {
+            id,
+            yaw_step,
+            vertex_surface_id,
+            index_surface_id,
+            index_width,
+            vertex_count: vertex_count as u32,
+            triangle_count: triangle_count as u32,
+            bytes,
+            last_used: self.frame_count,
+        });
+        self.mesh_cache_bytes += bytes;
+        self.mesh_uploads += 1;
+        Some(self.mesh_cache.len() - 1)
+    }
+
+    /// Drop a cached mesh and free its surfaces
+    fn evict(&mut self, index: usize) {
+        let entry = self.mesh_cache.swap_remove(index);
+        vmsvga::destroy_3d_surface(entry.vertex_surface_id);
+        vmsvga::destroy_3d_surface(entry.index_surface_id);
+        self.mesh_cache_bytes -= entry.bytes;
+        self.mesh_evictions += 1;
+    }
+}
+
+/// Create a static buffer surface of `bytes` bytes
+fn create_buffer_surface(bytes: usize, hint: u32) -> Option<u32> {
+    vmsvga::create_3d_surface(
+        svga3d::SurfaceFormat::Buffer,
+        bytes as u32,
+        1,
+        1,
+        hint | svga3d::surface_flags::HINT_STATIC,
+        1,
+    )
+}
+
+/// Copy `data` into a buffer surface through the staging GMR, one GMR-sized chunk at a time
+fn upload_buffer(gmr_id: u32, staging: *mut u8, surface_id: u32, data: &[u8]) -> bool {
+    let device = vmsvga::VMSVGA_DEVICE.lock();
+    let fifo = device.fifo();
+    for (i, chunk) in data.chunks(MESH_STAGING_SIZE).enumerate() {
+        // Safety: the staging GMR is MESH_STAGING_SIZE bytes and the host is
+        // done with the previous chunk (synced below)
+        unsafe { core::ptr::copy_nonoverlapping(chunk.as_ptr(), staging, chunk.len()) };
+        core::sync::atomic::fence(Ordering::SeqCst);
+        let offset = (i * MESH_STAGING_SIZE) as u32;
+        if !fifo.cmd_3d_upload_buffer(gmr_id, surface_id, offset, chunk.len() as u32) || !fifo.sync() {
+            return false;
+        }
+    }
+    true
+}
+
+/// Vertex buffer contents of a mesh lit at `yaw_step`, in GpuVertex layout
+fn baked_vertices(mesh: &Mesh, yaw_step: u32) -> Vec<u8> {
+    let normal_matrix = Mat3::from_rotation_y(yaw_step as f32 * TAU / LIGHTING_YAW_STEPS as f32);
+    let mut bytes = Vec::with_capacity(mesh.vertices.len() * VERTEX_SIZE);
+    for vertex in &mesh.vertices {
+        let lit = shade_vertex(vertex, &normal_matrix);
+        let color = color_to_argb(lit.color.x, lit.color.y, lit.color.z);
+        for word in [lit.position.x.to_bits(), lit.position.y.to_bits(), lit.position.z.to_bits(), color] {
+            bytes.extend_from_slice(&word.to_le_bytes());
+        }
+    }
+    bytes
+}
+
+/// Bytes per index for a mesh: 16-bit when every vertex is addressable
+fn index_width(vertex_count: usize) -> u32 {
+    if vertex_count <= u16::MAX as usize + 1 { 2 } else { 4 }
+}
+
+/// Index buffer contents at `width` bytes per index
+fn packed_indices(indices: &[u32], width: u32) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(indices.len() * width as usize);
+    for &index in indices {
+        bytes.extend_from_slice(&index.to_le_bytes()[..width as usize]);
+    }
+    bytes
+}
+
+/// Yaw step to bake a model's lighting at, if it only rotates about Y
+/// (uniform scale and translation are fine, lighting normalizes the normal).
+/// Tilted, sheared or unevenly scaled models light each vertex differently
+/// per instance, so they can't share baked colors
+fn lighting_yaw_step(model: &Mat4) -> Option<u32> {
+    let m = Mat3::from_mat4(*model);
+    let (x, y, z) = (m.x_axis, m.y_axis, m.z_axis);
+    let scale = y.y;
+    let tolerance = scale * 1e-3;
+    // About Y only: x = (cos, 0, -sin) * scale and z = (sin, 0, cos) * scale
+    let upright = scale > 0.0
+        && y.x.abs() <= tolerance
+        && y.z.abs() <= tolerance
+        && x.y.abs() <= tolerance
+        && z.y.abs() <= tolerance
+        && (z.x + x.z).abs() <= tolerance
+        && (z.z - x.x).abs() <= tolerance
+        && (x.length() - scale).abs() <= tolerance;
+    if !upright {
+        return None;
+    }
+    let turns = libm::atan2f(-x.z, x.x) / TAU;
+    let step = libm::roundf(turns * LIGHTING_YAW_STEPS as f32) as i32;
+    Some(step.rem_euclid(LIGHTING_YAW_STEPS as i32) as u32)
+}
+
+/// Depth to submit for a batched screen-space vertex with `inv_w` = 1/w
+/// Matches what SVGA3D computes for cached meshes drawn through `projection`
+/// (0 at the near plane, 1 at the far plane), so the two depth-test together
+#[inline]
+pub fn screen_depth(projection: &Mat4, inv_w: f32) -> f32 {
+    projection.w_axis.z * inv_w - projection.z_axis.z
+}
+
+/// glam matrix as an SVGA3D (row-vector) matrix
+fn to_svga_matrix(m: &Mat4) -> svga3d::Matrix4x4 {
+    svga3d::Matrix4x4 { m: m.to_cols_array_2d() }
+}
+
+/// Orthographic projection for batched screen-space triangles
+/// Screen coordinates: (0,0) top-left to (width, height) bottom-right
+/// Depth: 0.0 (near) to 1.0 (far)
+fn screen_projection(width: u32, height: u32) -> svga3d::Matrix4x4 {
+    svga3d::Matrix4x4 {
+        m: [
+            [2.0 / width as f32, 0.0, 0.0, 0.0],
+            [0.0, -2.0 / height as f32, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [-1.0, 1.0, 0.0, 1.0],
+        ],
+    }
+}
+
 /// Convert screen-space triangle from the tiles system to GPU batch format
 /// The tiles system uses ScreenTriangle with pre-computed edge coefficients,
 /// but we need screen-space vertex positions for GPU rendering
@@ -537,3 +1181,59 @@ fn color_to_argb(r: f32, g: f32, b: f32) -> u32 {
     let bi = (b.clamp(0.0, 1.0) * 255.0) as u32;
     0xFF000000 | (ri << 16) | (gi << 8) | bi
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::Vec3;
+
+    #[test]
+    fn test_lighting_yaw_step() {
+        let translated = Mat4::from_translation(Vec3::new(10.0, 2.0, -5.0)) * Mat4::from_scale(Vec3::splat(1.5));
+        assert_eq!(lighting_yaw_step(&translated), Some(0));
+
+        let quarter = Mat4::from_rotation_y(TAU / 4.0);
+        assert_eq!(lighting_yaw_step(&quarter), Some(LIGHTING_YAW_STEPS / 4));
+        let back = Mat4::from_rotation_y(-TAU / 4.0);
+        assert_eq!(lighting_yaw_step(&back), Some(LIGHTING_YAW_STEPS * 3 / 4));
+
+        // Tilted or unevenly scaled models light per instance
+        assert_eq!(lighting_yaw_step(&Mat4::from_rotation_x(0.3)), None);
+        assert_eq!(lighting_yaw_step(&Mat4::from_scale(Vec3::new(40.0, 1.0, 40.0))), None);
+    }
+
+    #[test]
+    fn test_index_packing() {
+        assert_eq!(index_width(65536), 2);
+        assert_eq!(index_width(65537), 4);
+        assert_eq!(packed_indices(&[1, 0x0203], 2), [1, 0, 3, 2]);
+        assert_eq!(packed_indices(&[0x0001_0000], 4), [0, 0, 1, 0]);
+    }
+
+    #[test]
+    fn test_screen_depth_matches_projection() {
+        let projection = Mat4::perspective_rh(1.2, 4.0 / 3.0, 0.5, 3000.0);
+        for distance in [0.5f32, 10.0, 3000.0] {
+            let clip = projection * glam::Vec4::new(0.0, 0.0, -distance, 1.0);
+            let depth = screen_depth(&projection, 1.0 / clip.w);
+            assert!((depth - clip.z / clip.w).abs() < 1e-4, "{} vs {}", depth, clip.z / clip.w);
+        }
+    }
+
+    #[test]
+    fn test_self_test_image() {
+        let size = SELF_TEST_SIZE as usize;
+        let mut pixels: Vec<u32> = (0..size * size)
+            .map(|i| if i % size + i / size < size { SELF_TEST_COLOR } else { SELF_TEST_CLEAR })
+            .collect();
+        assert!(self_test_passed(&pixels));
+
+        // Small rounding differences are fine, wrong colors or no readback are not
+        assert!(pixel_matches(0x00DEC322, SELF_TEST_COLOR));
+        assert!(!pixel_matches(SELF_TEST_CLEAR, SELF_TEST_COLOR));
+        pixels[2 * size + 2] = SELF_TEST_CLEAR;
+        assert!(!self_test_passed(&pixels));
+        assert!(!self_test_passed(&[0; 256]));
+        assert!(!self_test_passed(&[]));
+    }
+}
@@ -8,7 +8,9 @@ pub mod gpu;
 pub mod gpu3d;
 pub mod gpu_batch;
 pub mod gpu_render;
+pub mod offscreen;
 pub mod pipeline;
+pub mod post;
 pub mod rasterizer;
 pub mod tiles;
 pub mod ui;
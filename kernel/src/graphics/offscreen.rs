@@ -0,0 +1,141 @@
+//! Offscreen render targets
+//!
+//! A color buffer and z-buffer owned by the caller, drawn through the same
+//! lighting, transform and rasterizer code as the screen. Meshes are
+//! rasterized directly instead of through the frame's tile bins, so a target
+//! can be rendered at any time (e.g. the minimap's top-down map).
+
+use alloc::vec;
+use alloc::vec::Vec;
+use glam::Mat4;
+use renderer::mesh::Mesh;
+
+use super::pipeline::{normal_matrix, shade_vertex, transform_and_bin_fast, CullMode};
+use super::rasterizer::{rasterize_screen_triangle_simple, RenderContext};
+
+/// Offscreen color + depth target
+pub struct OffscreenTarget {
+    pub width: usize,
+    pub height: usize,
+    /// Row-major 0x00RRGGBB pixels (pitch = width)
+    pub pixels: Vec<u32>,
+    depth: Vec<f32>,
+}
+
+impl OffscreenTarget {
+    /// Create a target cleared to black
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; width * height],
+            depth: vec![f32::NEG_INFINITY; width * height],
+        }
+    }
+
+    /// Clear color to `color` and depth to the farthest value
+    pub fn clear(&mut self, color: u32) {
+        self.pixels.fill(color);
+        self.depth.fill(f32::NEG_INFINITY);
+    }
+
+    /// Render context drawing into this target
+    /// The context points into the target's buffers: drop it before the target
+    pub fn context(&mut self) -> RenderContext {
+        RenderContext::for_buffers(&mut self.pixels, &mut self.depth, self.width, self.height)
+    }
+
+    /// Light, transform and rasterize a mesh into the target
+    /// Returns the number of screen triangles drawn
+    pub fn draw_mesh(&mut self, mesh: &Mesh, model: &Mat4, view_projection: &Mat4, cull: CullMode) -> usize {
+        let mvp = *view_projection * *model;
+        let nm = normal_matrix(model);
+        let (width, height) = (self.width as f32, self.height as f32);
+        let (max_x, max_y) = (self.width as i32 - 1, self.height as i32 - 1);
+        let ctx = self.context();
+
+        let mut drawn = 0;
+        for i in 0..mesh.triangle_count() {
+            let Some((v0, v1, v2)) = mesh.get_triangle(i) else {
+                continue;
+            };
+            let (v0, v1, v2) = (shade_vertex(v0, &nm), shade_vertex(v1, &nm), shade_vertex(v2, &nm));
+            for tri in transform_and_bin_fast(&v0, &v1, &v2, &mvp, width, height, cull).into_iter().flatten() {
+                rasterize_screen_triangle_simple(&ctx, &tri, 0, max_x, 0, max_y);
+                drawn += 1;
+            }
+        }
+        drawn
+    }
+
+    /// Pixel at (x, y), or 0 outside the target
+    #[inline]
+    pub fn pixel(&self, x: usize, y: usize) -> u32 {
+        if x < self.width && y < self.height { self.pixels[y * self.width + x] } else { 0 }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::framebuffer::rgb;
+    use crate::graphics::pipeline::lambert;
+    use glam::Vec3;
+    use renderer::vertex::Vertex;
+
+    /// One triangle of the given color
+    fn triangle_mesh(points: [(f32, f32); 3], color: Vec3) -> Mesh {
+        let mut mesh = Mesh::new();
+        mesh.vertices = points.iter().map(|&(x, y)| Vertex::pos_color(Vec3::new(x, y, 0.0), color)).collect();
+        mesh.indices = vec![0, 1, 2];
+        mesh
+    }
+
+    /// Orthographic projection mapping world (x, y) in [0, 16] straight to pixels
+    fn pixel_ortho() -> Mat4 {
+        Mat4::orthographic_rh(0.0, 16.0, 16.0, 0.0, -1.0, 1.0)
+    }
+
+    #[test]
+    fn test_single_triangle_writes_expected_pixels() {
+        let background = rgb(1, 2, 3);
+        let mut target = OffscreenTarget::new(16, 16);
+        target.clear(background);
+
+        let mesh = triangle_mesh([(0.0, 0.0), (16.0, 0.0), (0.0, 16.0)], Vec3::ONE);
+        assert_eq!(target.draw_mesh(&mesh, &Mat4::IDENTITY, &pixel_ortho(), CullMode::None), 1);
+
+        // Pixel centers inside the hypotenuse are lit; those past it keep the background
+        let grey = (lambert(mesh.vertices[0].normal) * 255.0) as u8;
+        for y in 0..16 {
+            for x in 0..16 {
+                let pixel = target.pixel(x, y);
+                match x + y {
+                    0..=14 => assert_eq!(pixel, rgb(grey, grey, grey), "({}, {})", x, y),
+                    15 => assert!(pixel == background || pixel == rgb(grey, grey, grey)),
+                    _ => assert_eq!(pixel, background, "({}, {})", x, y),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_clear_resets_color_and_depth() {
+        let mut target = OffscreenTarget::new(16, 16);
+        let white = triangle_mesh([(0.0, 0.0), (16.0, 0.0), (0.0, 16.0)], Vec3::ONE);
+        let red = triangle_mesh([(0.0, 0.0), (8.0, 0.0), (0.0, 8.0)], Vec3::X);
+        target.draw_mesh(&white, &Mat4::IDENTITY, &pixel_ortho(), CullMode::None);
+        let lit_white = target.pixel(1, 1);
+
+        // Same depth: the first triangle drawn keeps the pixel
+        target.draw_mesh(&red, &Mat4::IDENTITY, &pixel_ortho(), CullMode::None);
+        assert_eq!(target.pixel(1, 1), lit_white);
+
+        target.clear(0);
+        assert!(target.pixels.iter().all(|&p| p == 0));
+        target.draw_mesh(&red, &Mat4::IDENTITY, &pixel_ortho(), CullMode::None);
+        let [_, r, g, b] = target.pixel(1, 1).to_be_bytes();
+        assert!(r > 0 && g == 0 && b == 0);
+        assert_eq!(target.pixel(12, 2), 0);
+    }
+}
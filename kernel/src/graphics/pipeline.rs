@@ -1,8 +1,9 @@
 //! Vertex transformation pipeline
 
 use super::tiles::ScreenTriangle;
-use glam::{Mat4, Vec3, Vec4};
+use glam::{Mat3, Mat4, Vec3, Vec4};
 use renderer::vertex::Vertex;
+use spin::Mutex;
 
 /// Transform a vertex from world space to screen space
 pub fn transform_vertex(
@@ -73,45 +74,282 @@ pub fn transform_vertex_fast(
     }
 }
 
-/// Transform a triangle and perform backface culling
-/// Returns None if the triangle should be culled
-pub fn transform_triangle(
-    v0: &Vertex,
-    v1: &Vertex,
-    v2: &Vertex,
-    model: &Mat4,
-    view: &Mat4,
-    projection: &Mat4,
-    viewport_width: f32,
-    viewport_height: f32,
-) -> Option<(Vertex, Vertex, Vertex)> {
-    let tv0 = transform_vertex(v0, model, view, projection, viewport_width, viewport_height);
-    let tv1 = transform_vertex(v1, model, view, projection, viewport_width, viewport_height);
-    let tv2 = transform_vertex(v2, model, view, projection, viewport_width, viewport_height);
+/// Minimum clip-space w kept by near-plane clipping
+/// Anything closer to (or behind) the camera would blow up in the perspective divide
+pub const NEAR_W_EPSILON: f32 = 0.001;
 
-    // Near plane clipping: reject if behind camera (w < 0 means 1/w < 0)
-    if tv0.position.z < 0.0 || tv1.position.z < 0.0 || tv2.position.z < 0.0 {
-        return None;
+/// Vertex after the MVP transform, before perspective division
+#[derive(Debug, Clone, Copy)]
+pub struct ClipVertex {
+    pub clip: Vec4,
+    pub vertex: Vertex,
+}
+
+impl ClipVertex {
+    /// Transform a vertex into clip space
+    #[inline]
+    pub fn new(vertex: &Vertex, mvp: &Mat4) -> Self {
+        Self {
+            clip: *mvp * Vec4::new(vertex.position.x, vertex.position.y, vertex.position.z, 1.0),
+            vertex: *vertex,
+        }
+    }
+
+    /// Point on the edge towards `other` (attributes interpolated linearly in clip space)
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self {
+            clip: self.clip.lerp(other.clip, t),
+            vertex: Vertex {
+                position: self.vertex.position.lerp(other.vertex.position, t),
+                normal: self.vertex.normal.lerp(other.vertex.normal, t),
+                color: self.vertex.color.lerp(other.vertex.color, t),
+                uv: self.vertex.uv.lerp(other.vertex.uv, t),
+            },
+        }
+    }
+
+    /// Perspective divide and viewport transform (z holds 1/w, like transform_vertex)
+    #[inline]
+    pub fn project(&self, viewport_width: f32, viewport_height: f32) -> Vertex {
+        let inv_w = 1.0 / self.clip.w;
+        Vertex {
+            position: Vec3::new(
+                (self.clip.x * inv_w + 1.0) * 0.5 * viewport_width,
+                (1.0 - self.clip.y * inv_w) * 0.5 * viewport_height,
+                inv_w,
+            ),
+            normal: self.vertex.normal,
+            color: self.vertex.color,
+            uv: self.vertex.uv,
+        }
+    }
+}
+
+/// Guard band half-extent, as a multiple of the viewport half-extent
+/// Triangles are only clipped against the sides once they reach this far
+/// off-screen; anything inside the band is left to the rasterizer's
+/// framebuffer-clamped bounding box. Keeps fixed-point screen coordinates
+/// small and huge off-screen triangles out of the binner.
+pub const GUARD_BAND: f32 = 2.0;
+
+/// Clip planes applied by `clip_triangle`: the near plane plus four guard band sides
+const CLIP_PLANES: [ClipPlane; 5] = [
+    ClipPlane::Near,
+    ClipPlane::Left,
+    ClipPlane::Right,
+    ClipPlane::Bottom,
+    ClipPlane::Top,
+];
+
+/// A triangle clipped by all CLIP_PLANES is a polygon of at most 3 + 5 vertices
+const MAX_CLIP_VERTICES: usize = 3 + CLIP_PLANES.len();
+
+/// ... which fans into at most this many triangles
+pub const MAX_CLIPPED_TRIANGLES: usize = MAX_CLIP_VERTICES - 2;
+
+/// A clip-space plane
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ClipPlane {
+    /// w = NEAR_W_EPSILON
+    Near,
+    /// x = -GUARD_BAND * w
+    Left,
+    /// x = GUARD_BAND * w
+    Right,
+    /// y = -GUARD_BAND * w
+    Bottom,
+    /// y = GUARD_BAND * w
+    Top,
+}
+
+impl ClipPlane {
+    /// Signed distance from the plane, positive on the kept side
+    #[inline]
+    fn distance(self, clip: &Vec4) -> f32 {
+        match self {
+            Self::Near => clip.w - NEAR_W_EPSILON,
+            Self::Left => clip.x + GUARD_BAND * clip.w,
+            Self::Right => GUARD_BAND * clip.w - clip.x,
+            Self::Bottom => clip.y + GUARD_BAND * clip.w,
+            Self::Top => GUARD_BAND * clip.w - clip.y,
+        }
     }
 
-    // NOTE: Far plane clipping removed - was incorrectly rejecting close objects
+    /// Whether a distance is on the kept side (the near plane itself is excluded)
+    #[inline]
+    fn keeps(self, distance: f32) -> bool {
+        if self == Self::Near { distance > 0.0 } else { distance >= 0.0 }
+    }
+}
+
+/// Output of clipping: up to MAX_CLIPPED_TRIANGLES triangles
+#[derive(Debug, Clone, Copy)]
+pub struct ClippedTriangles {
+    tris: [[ClipVertex; 3]; MAX_CLIPPED_TRIANGLES],
+    count: usize,
+}
+
+impl ClippedTriangles {
+    /// Triangles that survived clipping
+    #[inline]
+    pub fn as_slice(&self) -> &[[ClipVertex; 3]] {
+        &self.tris[..self.count]
+    }
+
+    /// Keep a clipped triangle unless it collapsed to a zero-area sliver
+    #[inline]
+    fn push(&mut self, tri: [ClipVertex; 3]) {
+        if !is_degenerate(&tri) {
+            self.tris[self.count] = tri;
+            self.count += 1;
+        }
+    }
+}
+
+/// Clip a clip-space triangle against the near plane (w > NEAR_W_EPSILON)
+/// Yields 0, 1 or 2 triangles.
+pub fn clip_triangle_near(tri: [ClipVertex; 3]) -> ClippedTriangles {
+    clip_triangle_against(tri, &[ClipPlane::Near])
+}
+
+/// Clip a clip-space triangle against the near plane and the guard band
+pub fn clip_triangle(tri: [ClipVertex; 3]) -> ClippedTriangles {
+    clip_triangle_against(tri, &CLIP_PLANES)
+}
+
+/// Sutherland-Hodgman against each plane in turn; the resulting convex
+/// polygon is fanned back into triangles with the original winding.
+/// Allocation-free: the polygon lives in a fixed-size array.
+fn clip_triangle_against(tri: [ClipVertex; 3], planes: &[ClipPlane]) -> ClippedTriangles {
+    let mut out = ClippedTriangles { tris: [tri; MAX_CLIPPED_TRIANGLES], count: 0 };
+
+    // Trivial accept (common case) and trivial reject
+    let mut all_inside = true;
+    for &plane in planes {
+        let inside = tri.map(|v| plane.keeps(plane.distance(&v.clip)));
+        if !inside.iter().any(|&i| i) {
+            return out;
+        }
+        all_inside &= inside.iter().all(|&i| i);
+    }
+    if all_inside {
+        out.count = 1;
+        return out;
+    }
+
+    let mut poly = [tri[0]; MAX_CLIP_VERTICES];
+    poly[..3].copy_from_slice(&tri);
+    let mut len = 3;
+    for &plane in planes {
+        let mut next = poly;
+        let mut next_len = 0;
+        for i in 0..len {
+            let a = &poly[i];
+            let b = &poly[(i + 1) % len];
+            let (da, db) = (plane.distance(&a.clip), plane.distance(&b.clip));
+            // A convex polygon gains at most one vertex per plane; the bound
+            // check only guards against rounding on near-coplanar vertices
+            if plane.keeps(da) && next_len < MAX_CLIP_VERTICES {
+                next[next_len] = *a;
+                next_len += 1;
+            }
+            if plane.keeps(da) != plane.keeps(db) && next_len < MAX_CLIP_VERTICES {
+                next[next_len] = a.lerp(b, da / (da - db));
+                next_len += 1;
+            }
+        }
+        poly = next;
+        len = next_len;
+        if len < 3 {
+            return out;
+        }
+    }
+
+    for i in 1..len - 1 {
+        out.push([poly[0], poly[i], poly[i + 1]]);
+    }
+    out
+}
+
+/// Whether a clipped triangle has (near) zero area after the perspective divide
+/// Also catches NaN from vertices that landed exactly on w = 0
+#[inline]
+fn is_degenerate(tri: &[ClipVertex; 3]) -> bool {
+    let ndc = tri.map(|v| (v.clip.x / v.clip.w, v.clip.y / v.clip.w));
+    let area = (ndc[1].0 - ndc[0].0) * (ndc[2].1 - ndc[0].1) - (ndc[1].1 - ndc[0].1) * (ndc[2].0 - ndc[0].0);
+    area.is_nan() || area.abs() <= 1e-12
+}
 
-    // Backface culling using screen-space winding order
+/// Which triangle faces the transform stage discards
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CullMode {
+    /// Drop triangles facing away from the camera (closed meshes)
+    #[default]
+    Back,
+    /// Keep both faces (double-sided meshes: gliders, foliage)
+    None,
+}
+
+/// Screen-space backface test (CCW in screen space = back-facing)
+#[inline]
+fn is_back_facing(tv0: &Vertex, tv1: &Vertex, tv2: &Vertex) -> bool {
     // In screen space with Y pointing down (after viewport transform):
     // - CCW triangles in world space become CW in screen space
     // - CW screen-space triangles have NEGATIVE cross_z
-    // We cull back-facing triangles (positive cross_z in screen space)
-    let edge1 = tv1.position - tv0.position;
-    let edge2 = tv2.position - tv0.position;
-    let cross_z = edge1.x * edge2.y - edge1.y * edge2.x;
-
-    // Cull back-facing triangles (CCW in screen space = positive cross_z)
-    // Keep front-facing triangles (CW in screen space = negative cross_z)
-    if cross_z > 0.0 {
-        return None;
+    let edge1_x = tv1.position.x - tv0.position.x;
+    let edge1_y = tv1.position.y - tv0.position.y;
+    let edge2_x = tv2.position.x - tv0.position.x;
+    let edge2_y = tv2.position.y - tv0.position.y;
+    edge1_x * edge2_y - edge1_y * edge2_x > 0.0
+}
+
+/// Transform, clip (near plane and guard band), project and backface-cull a triangle
+/// Returns the screen-space triangles that survive `cull`
+pub fn transform_triangle_clipped(
+    v0: &Vertex,
+    v1: &Vertex,
+    v2: &Vertex,
+    mvp: &Mat4,
+    viewport_width: f32,
+    viewport_height: f32,
+    cull: CullMode,
+) -> [Option<(Vertex, Vertex, Vertex)>; MAX_CLIPPED_TRIANGLES] {
+    let clipped = clip_triangle([
+        ClipVertex::new(v0, mvp),
+        ClipVertex::new(v1, mvp),
+        ClipVertex::new(v2, mvp),
+    ]);
+
+    let mut out = [None; MAX_CLIPPED_TRIANGLES];
+    for (slot, tri) in out.iter_mut().zip(clipped.as_slice()) {
+        let tv0 = tri[0].project(viewport_width, viewport_height);
+        let tv1 = tri[1].project(viewport_width, viewport_height);
+        let tv2 = tri[2].project(viewport_width, viewport_height);
+
+        // NOTE: Far plane clipping removed - was incorrectly rejecting close objects
+        // Back faces that are kept come out CCW; ScreenTriangle normalizes either winding
+        if cull == CullMode::None || !is_back_facing(&tv0, &tv1, &tv2) {
+            *slot = Some((tv0, tv1, tv2));
+        }
     }
+    out
+}
 
-    Some((tv0, tv1, tv2))
+/// Transform a triangle and perform near-plane/guard-band clipping and backface culling
+/// Returns up to MAX_CLIPPED_TRIANGLES triangles (clipping can turn it into a polygon)
+pub fn transform_triangle(
+    v0: &Vertex,
+    v1: &Vertex,
+    v2: &Vertex,
+    model: &Mat4,
+    view: &Mat4,
+    projection: &Mat4,
+    viewport_width: f32,
+    viewport_height: f32,
+    cull: CullMode,
+) -> [Option<(Vertex, Vertex, Vertex)>; MAX_CLIPPED_TRIANGLES] {
+    let mvp = *projection * *view * *model;
+    transform_triangle_clipped(v0, v1, v2, &mvp, viewport_width, viewport_height, cull)
 }
 
 /// Create a perspective projection matrix
@@ -148,8 +386,112 @@ pub fn apply_lighting(vertex: &mut Vertex, light_dir: Vec3, ambient: f32) {
     vertex.color *= total_light;
 }
 
-/// Transform triangle and create a ScreenTriangle for binning
-/// Returns None if triangle is culled or degenerate
+/// Direction towards the sun (world space, unit length)
+pub const SUN_DIRECTION: Vec3 = Vec3::new(0.4, 0.8, 0.447_213_6);
+
+/// Light every surface receives regardless of orientation
+pub const AMBIENT_LIGHT: f32 = 0.55;
+
+/// Extra light for surfaces facing the sun (ambient + diffuse = 1.0 at full exposure)
+pub const DIFFUSE_LIGHT: f32 = 0.45;
+
+/// Highest the sun climbs at noon (radians above the horizon)
+const SUN_MAX_ELEVATION: f32 = 1.1;
+
+/// Lowest the sun sinks, as a fraction of SUN_MAX_ELEVATION, so dawn, dusk
+/// and night still light the world from somewhere
+const SUN_MIN_HEIGHT: f32 = 0.15;
+
+/// Diffuse light tint with the sun on the horizon (full height is white)
+const SUN_LOW_TINT: Vec3 = Vec3::new(1.0, 0.7, 0.45);
+
+/// Directional sunlight for per-pixel (Phong) shading
+/// See `rasterizer::rasterize_screen_triangle_lit`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LightState {
+    /// Direction towards the sun (unit length)
+    pub direction: Vec3,
+    /// Diffuse light per channel on a surface facing the sun
+    pub color: Vec3,
+    /// Light per channel every surface receives regardless of orientation
+    pub ambient: Vec3,
+}
+
+impl LightState {
+    /// The fixed sun per-vertex lighting uses (see `lambert`)
+    pub const DEFAULT: Self = Self {
+        direction: SUN_DIRECTION,
+        color: Vec3::splat(DIFFUSE_LIGHT),
+        ambient: Vec3::splat(AMBIENT_LIGHT),
+    };
+
+    /// Sunlight at `hour` (0-24): the sun rises in +X at 6:00, is highest
+    /// towards +Z at noon and sets in -X at 18:00, warmer near the horizon
+    pub fn at_hour(hour: f32) -> Self {
+        let t = (hour - 6.0) / 12.0 * core::f32::consts::PI;
+        let height = libm::sinf(t).max(SUN_MIN_HEIGHT);
+        let elevation = height * SUN_MAX_ELEVATION;
+        let (sin_e, cos_e) = (libm::sinf(elevation), libm::cosf(elevation));
+        Self {
+            direction: Vec3::new(cos_e * libm::cosf(t), sin_e, cos_e * libm::sinf(t)).normalize(),
+            color: SUN_LOW_TINT.lerp(Vec3::ONE, height) * DIFFUSE_LIGHT,
+            ambient: Vec3::splat(AMBIENT_LIGHT),
+        }
+    }
+
+    /// This light with its direction rotated into the view space of `view`,
+    /// where lit triangles keep their normals
+    pub fn to_view(&self, view: &Mat4) -> Self {
+        Self {
+            direction: (Mat3::from_mat4(*view) * self.direction).normalize_or_zero(),
+            ..*self
+        }
+    }
+
+    /// Light per channel reaching a surface with unit normal `normal`
+    #[inline]
+    pub fn intensity(&self, normal: Vec3) -> Vec3 {
+        self.ambient + self.color * normal.dot(self.direction).max(0.0)
+    }
+}
+
+/// Sunlight for the current match (world space)
+static LIGHT: Mutex<LightState> = Mutex::new(LightState::DEFAULT);
+
+/// Set the sunlight (at world init, from the match time of day)
+pub fn set_light(light: LightState) {
+    *LIGHT.lock() = light;
+}
+
+/// Current sunlight (world space)
+pub fn light() -> LightState {
+    *LIGHT.lock()
+}
+
+/// Normal matrix for a model transform (inverse transpose of the upper 3x3)
+#[inline]
+pub fn normal_matrix(model: &Mat4) -> Mat3 {
+    Mat3::from_mat4(*model).inverse().transpose()
+}
+
+/// Lambert brightness for a world-space normal
+#[inline]
+pub fn lambert(normal: Vec3) -> f32 {
+    AMBIENT_LIGHT + DIFFUSE_LIGHT * normal.normalize_or_zero().dot(SUN_DIRECTION).max(0.0)
+}
+
+/// Per-vertex directional lighting: scale the color by the Lambert term of
+/// the normal rotated into world space
+#[inline]
+pub fn shade_vertex(vertex: &Vertex, normal_matrix: &Mat3) -> Vertex {
+    Vertex {
+        color: vertex.color * lambert(*normal_matrix * vertex.normal),
+        ..*vertex
+    }
+}
+
+/// Transform triangle and create ScreenTriangles for binning
+/// Clipping may split the triangle into several; culled/degenerate parts are None
 pub fn transform_and_bin(
     v0: &Vertex,
     v1: &Vertex,
@@ -159,29 +501,11 @@ pub fn transform_and_bin(
     projection: &Mat4,
     fb_width: f32,
     fb_height: f32,
-) -> Option<ScreenTriangle> {
-    // Transform all three vertices
-    let tv0 = transform_vertex(v0, model, view, projection, fb_width, fb_height);
-    let tv1 = transform_vertex(v1, model, view, projection, fb_width, fb_height);
-    let tv2 = transform_vertex(v2, model, view, projection, fb_width, fb_height);
-
-    // Near plane clipping: reject if behind camera (w < 0 means 1/w < 0)
-    if tv0.position.z < 0.0 || tv1.position.z < 0.0 || tv2.position.z < 0.0 {
-        return None;
-    }
-
-    // NOTE: Far plane clipping removed - was incorrectly rejecting close objects
-
-    // Backface culling using screen-space winding order
-    let edge1 = tv1.position - tv0.position;
-    let edge2 = tv2.position - tv0.position;
-    let cross_z = edge1.x * edge2.y - edge1.y * edge2.x;
-    if cross_z > 0.0 {
-        return None;
-    }
-
-    // Create ScreenTriangle with pre-computed edge coefficients
-    ScreenTriangle::from_vertices(&tv0, &tv1, &tv2, fb_width as i32, fb_height as i32)
+) -> [Option<ScreenTriangle>; MAX_CLIPPED_TRIANGLES] {
+    let mvp = *projection * *view * *model;
+    let nm = normal_matrix(model);
+    let (v0, v1, v2) = (shade_vertex(v0, &nm), shade_vertex(v1, &nm), shade_vertex(v2, &nm));
+    transform_and_bin_fast(&v0, &v1, &v2, &mvp, fb_width, fb_height, CullMode::Back)
 }
 
 /// FAST: Transform triangle using precomputed MVP matrix
@@ -194,29 +518,36 @@ pub fn transform_and_bin_fast(
     mvp: &Mat4,
     fb_width: f32,
     fb_height: f32,
-) -> Option<ScreenTriangle> {
-    // Transform all three vertices using single MVP matrix (3x faster!)
-    let tv0 = transform_vertex_fast(v0, mvp, fb_width, fb_height);
-    let tv1 = transform_vertex_fast(v1, mvp, fb_width, fb_height);
-    let tv2 = transform_vertex_fast(v2, mvp, fb_width, fb_height);
-
-    // Near plane clipping: reject if behind camera
-    if tv0.position.z < 0.0 || tv1.position.z < 0.0 || tv2.position.z < 0.0 {
-        return None;
-    }
-
-    // Backface culling using screen-space winding order
-    let edge1_x = tv1.position.x - tv0.position.x;
-    let edge1_y = tv1.position.y - tv0.position.y;
-    let edge2_x = tv2.position.x - tv0.position.x;
-    let edge2_y = tv2.position.y - tv0.position.y;
-    let cross_z = edge1_x * edge2_y - edge1_y * edge2_x;
-    if cross_z > 0.0 {
-        return None;
-    }
+    cull: CullMode,
+) -> [Option<ScreenTriangle>; MAX_CLIPPED_TRIANGLES] {
+    // Create ScreenTriangles with pre-computed edge coefficients
+    transform_triangle_clipped(v0, v1, v2, mvp, fb_width, fb_height, cull).map(|tri| {
+        tri.and_then(|(tv0, tv1, tv2)| {
+            ScreenTriangle::from_vertices(&tv0, &tv1, &tv2, fb_width as i32, fb_height as i32)
+        })
+    })
+}
 
-    // Create ScreenTriangle with pre-computed edge coefficients
-    ScreenTriangle::from_vertices(&tv0, &tv1, &tv2, fb_width as i32, fb_height as i32)
+/// transform_and_bin_fast for per-pixel lighting
+/// Vertex colors must be unlit; `normal_matrix` takes the vertex normals to
+/// view space, where the triangles keep them for the rasterizer
+#[inline]
+pub fn transform_and_bin_fast_lit(
+    v0: &Vertex,
+    v1: &Vertex,
+    v2: &Vertex,
+    mvp: &Mat4,
+    normal_matrix: &Mat3,
+    fb_width: f32,
+    fb_height: f32,
+    cull: CullMode,
+) -> [Option<ScreenTriangle>; MAX_CLIPPED_TRIANGLES] {
+    let to_view = |v: &Vertex| Vertex { normal: (*normal_matrix * v.normal).normalize_or_zero(), ..*v };
+    transform_triangle_clipped(&to_view(v0), &to_view(v1), &to_view(v2), mvp, fb_width, fb_height, cull).map(|tri| {
+        tri.and_then(|(tv0, tv1, tv2)| {
+            ScreenTriangle::from_vertices_lit(&tv0, &tv1, &tv2, fb_width as i32, fb_height as i32)
+        })
+    })
 }
 
 /// Project a point from world space to screen space
@@ -262,7 +593,7 @@ pub fn project_point(
 }
 
 /// Transform a triangle and add to GPU batch for hardware rasterization
-/// Returns true if the triangle was added to GPU batch, false if culled or batch full
+/// Returns true if any part of the triangle was added to GPU batch, false if culled or batch full
 pub fn transform_and_gpu_batch(
     v0: &Vertex,
     v1: &Vertex,
@@ -275,41 +606,28 @@ pub fn transform_and_gpu_batch(
 ) -> bool {
     use super::gpu_batch;
 
-    // Transform all three vertices
-    let tv0 = transform_vertex(v0, model, view, projection, fb_width, fb_height);
-    let tv1 = transform_vertex(v1, model, view, projection, fb_width, fb_height);
-    let tv2 = transform_vertex(v2, model, view, projection, fb_width, fb_height);
-
-    // Near plane clipping: reject if behind camera (w < 0 means 1/w < 0)
-    if tv0.position.z < 0.0 || tv1.position.z < 0.0 || tv2.position.z < 0.0 {
-        return false;
-    }
-
-    // NOTE: Far plane clipping removed - was incorrectly rejecting close objects
-
-    // Backface culling using screen-space winding order
-    let edge1 = tv1.position - tv0.position;
-    let edge2 = tv2.position - tv0.position;
-    let cross_z = edge1.x * edge2.y - edge1.y * edge2.x;
-    if cross_z > 0.0 {
-        return false;
+    let mut added = false;
+    for (tv0, tv1, tv2) in transform_triangle(v0, v1, v2, model, view, projection, fb_width, fb_height, CullMode::Back)
+        .into_iter()
+        .flatten()
+    {
+        // Add to GPU batch with screen-space coordinates and colors
+        added |= gpu_batch::add_screen_triangle(
+            tv0.position.x, tv0.position.y, tv0.position.z,
+            tv0.color.x, tv0.color.y, tv0.color.z,
+            tv1.position.x, tv1.position.y, tv1.position.z,
+            tv1.color.x, tv1.color.y, tv1.color.z,
+            tv2.position.x, tv2.position.y, tv2.position.z,
+            tv2.color.x, tv2.color.y, tv2.color.z,
+        );
     }
-
-    // Add to GPU batch with screen-space coordinates and colors
-    gpu_batch::add_screen_triangle(
-        tv0.position.x, tv0.position.y, tv0.position.z,
-        tv0.color.x, tv0.color.y, tv0.color.z,
-        tv1.position.x, tv1.position.y, tv1.position.z,
-        tv1.color.x, tv1.color.y, tv1.color.z,
-        tv2.position.x, tv2.position.y, tv2.position.z,
-        tv2.color.x, tv2.color.y, tv2.color.z,
-    )
+    added
 }
 
-/// Transform a triangle and either add to GPU batch or create ScreenTriangle for software rasterization
-/// Returns (Some(ScreenTriangle), true) if GPU batch was used
-/// Returns (Some(ScreenTriangle), false) if software path should be used
-/// Returns (None, _) if triangle was culled
+/// Transform a triangle and either add to GPU batch or create ScreenTriangles for software rasterization
+/// Returns ([None; MAX_CLIPPED_TRIANGLES], true) if GPU batch was used
+/// Returns (ScreenTriangles, false) if software path should be used
+/// Culled parts (including everything behind the near plane) are None
 pub fn transform_and_bin_hybrid(
     v0: &Vertex,
     v1: &Vertex,
@@ -320,51 +638,323 @@ pub fn transform_and_bin_hybrid(
     fb_width: f32,
     fb_height: f32,
     use_gpu_batch: bool,
-) -> (Option<ScreenTriangle>, bool) {
+) -> ([Option<ScreenTriangle>; MAX_CLIPPED_TRIANGLES], bool) {
     use super::gpu_batch;
 
-    // Transform all three vertices
-    let tv0 = transform_vertex(v0, model, view, projection, fb_width, fb_height);
-    let tv1 = transform_vertex(v1, model, view, projection, fb_width, fb_height);
-    let tv2 = transform_vertex(v2, model, view, projection, fb_width, fb_height);
-
-    // Near plane clipping: reject if behind camera (w < 0 means 1/w < 0)
-    if tv0.position.z < 0.0 || tv1.position.z < 0.0 || tv2.position.z < 0.0 {
-        return (None, false);
+    let tris = transform_triangle(v0, v1, v2, model, view, projection, fb_width, fb_height, CullMode::Back);
+    if tris.iter().all(Option::is_none) {
+        return ([None; MAX_CLIPPED_TRIANGLES], false);
     }
 
-    // NOTE: Far plane clipping removed - was incorrectly rejecting close objects
-
-    // Backface culling using screen-space winding order
-    let edge1 = tv1.position - tv0.position;
-    let edge2 = tv2.position - tv0.position;
-    let cross_z = edge1.x * edge2.y - edge1.y * edge2.x;
-    if cross_z > 0.0 {
-        return (None, false);
-    }
-
-    // If GPU batch is enabled, add triangle to GPU batch
+    // If GPU batch is enabled, add triangles to GPU batch
     if use_gpu_batch && gpu_batch::is_enabled() && gpu_batch::is_active() {
-        let added = gpu_batch::add_screen_triangle(
-            tv0.position.x, tv0.position.y, tv0.position.z,
-            tv0.color.x, tv0.color.y, tv0.color.z,
-            tv1.position.x, tv1.position.y, tv1.position.z,
-            tv1.color.x, tv1.color.y, tv1.color.z,
-            tv2.position.x, tv2.position.y, tv2.position.z,
-            tv2.color.x, tv2.color.y, tv2.color.z,
-        );
+        let mut added = true;
+        for (tv0, tv1, tv2) in tris.iter().flatten() {
+            added &= gpu_batch::add_screen_triangle(
+                tv0.position.x, tv0.position.y, tv0.position.z,
+                tv0.color.x, tv0.color.y, tv0.color.z,
+                tv1.position.x, tv1.position.y, tv1.position.z,
+                tv1.color.x, tv1.color.y, tv1.color.z,
+                tv2.position.x, tv2.position.y, tv2.position.z,
+                tv2.color.x, tv2.color.y, tv2.color.z,
+            );
+        }
 
         if added {
             // Check if batch needs flushing
             if gpu_batch::needs_flush() {
                 gpu_batch::flush_batch();
             }
-            return (None, true); // GPU handled it, no ScreenTriangle needed
+            return ([None; MAX_CLIPPED_TRIANGLES], true); // GPU handled it, no ScreenTriangle needed
         }
         // Batch full, fall through to software path
     }
 
-    // Create ScreenTriangle for software rasterization
-    let screen_tri = ScreenTriangle::from_vertices(&tv0, &tv1, &tv2, fb_width as i32, fb_height as i32);
-    (screen_tri, false)
+    // Create ScreenTriangles for software rasterization
+    let screen_tris = tris.map(|tri| {
+        tri.and_then(|(tv0, tv1, tv2)| {
+            ScreenTriangle::from_vertices(&tv0, &tv1, &tv2, fb_width as i32, fb_height as i32)
+        })
+    });
+    (screen_tris, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clip_vertex(x: f32, y: f32, w: f32) -> ClipVertex {
+        ClipVertex {
+            clip: Vec4::new(x, y, 0.0, w),
+            vertex: Vertex::pos_color(Vec3::ZERO, Vec3::new(x, y, 0.0)),
+        }
+    }
+
+    #[test]
+    fn test_clip_triangle_spanning_near_plane() {
+        // v0 is behind the camera, v1 and v2 are in front: the result is a quad
+        let v0 = clip_vertex(0.0, 0.0, -1.0);
+        let v1 = clip_vertex(1.0, 0.0, 1.0);
+        let v2 = clip_vertex(0.0, 1.0, 1.0);
+
+        let clipped = clip_triangle_near([v0, v1, v2]);
+        let tris = clipped.as_slice();
+        assert_eq!(tris.len(), 2);
+
+        for v in tris.iter().flatten() {
+            assert!(v.clip.w >= NEAR_W_EPSILON - 1e-6);
+        }
+
+        // New vertices sit on the near plane along edges v0->v1 and v2->v0
+        let t = (NEAR_W_EPSILON + 1.0) / 2.0;
+        let on_01 = tris[0][0];
+        assert!((on_01.clip.w - NEAR_W_EPSILON).abs() < 1e-6);
+        assert!((on_01.clip.x - t).abs() < 1e-6);
+        assert!((on_01.vertex.color.x - t).abs() < 1e-6);
+
+        let on_20 = tris[1][2];
+        assert!((on_20.clip.w - NEAR_W_EPSILON).abs() < 1e-6);
+        assert!((on_20.clip.y - t).abs() < 1e-6);
+
+        // Original in-front vertices are kept, winding preserved
+        assert_eq!(tris[0][1].clip, v1.clip);
+        assert_eq!(tris[0][2].clip, v2.clip);
+        assert_eq!(tris[1][1].clip, v2.clip);
+    }
+
+    #[test]
+    fn test_clip_triangle_two_vertices_behind() {
+        let clipped = clip_triangle_near([
+            clip_vertex(0.0, 0.0, 1.0),
+            clip_vertex(1.0, 0.0, -1.0),
+            clip_vertex(0.0, 1.0, -1.0),
+        ]);
+        assert_eq!(clipped.as_slice().len(), 1);
+        for v in clipped.as_slice()[0].iter() {
+            assert!(v.clip.w >= NEAR_W_EPSILON - 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_clip_triangle_fully_behind_camera() {
+        let clipped = clip_triangle_near([
+            clip_vertex(0.0, 0.0, -1.0),
+            clip_vertex(1.0, 0.0, -2.0),
+            clip_vertex(0.0, 1.0, -0.5),
+        ]);
+        assert!(clipped.as_slice().is_empty());
+    }
+
+    #[test]
+    fn test_clip_triangle_every_inside_outside_combination() {
+        let base = [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)];
+        for mask in 0..8u32 {
+            let tri = [0, 1, 2].map(|i| {
+                let w = if mask & (1 << i) != 0 { 1.0 } else { -1.0 };
+                clip_vertex(base[i].0, base[i].1, w)
+            });
+            let expected = match mask.count_ones() {
+                0 => 0,
+                1 => 1,
+                2 => 2,
+                _ => 1,
+            };
+            let clipped = clip_triangle_near(tri);
+            assert_eq!(clipped.as_slice().len(), expected, "mask {:03b}", mask);
+            for v in clipped.as_slice().iter().flatten() {
+                assert!(v.clip.w >= NEAR_W_EPSILON - 1e-6, "mask {:03b}", mask);
+            }
+        }
+    }
+
+    #[test]
+    fn test_clip_interpolates_depth_and_color_at_boundary() {
+        // Edge from w = -1 to w = 3 crosses the near plane a quarter of the way along
+        let mut behind = clip_vertex(0.0, 0.0, -1.0);
+        behind.clip.z = -2.0;
+        behind.vertex.color = Vec3::new(1.0, 0.0, 0.0);
+        let mut front = clip_vertex(2.0, 0.0, 3.0);
+        front.clip.z = 2.0;
+        front.vertex.color = Vec3::new(0.0, 0.0, 1.0);
+        let other = clip_vertex(0.0, 2.0, 3.0);
+
+        let clipped = clip_triangle_near([behind, front, other]);
+        let on_plane = clipped.as_slice()[0][0];
+        let t = (NEAR_W_EPSILON + 1.0) / 4.0;
+        assert!((on_plane.clip.w - NEAR_W_EPSILON).abs() < 1e-6);
+        assert!((on_plane.clip.z - (-2.0 + 4.0 * t)).abs() < 1e-5);
+        assert!((on_plane.vertex.color.x - (1.0 - t)).abs() < 1e-5);
+        assert!((on_plane.vertex.color.z - t).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_clip_rejects_zero_area_sliver() {
+        // Collinear in clip space: whatever survives the near plane has no area
+        let clipped = clip_triangle_near([
+            clip_vertex(0.0, 0.0, -1.0),
+            clip_vertex(1.0, 1.0, 1.0),
+            clip_vertex(2.0, 2.0, 3.0),
+        ]);
+        assert!(clipped.as_slice().is_empty());
+    }
+
+    #[test]
+    fn test_guard_band_keeps_triangle_on_its_edge() {
+        // A vertex exactly on the guard band edge is inside: no split, no new vertices
+        let tri = [
+            clip_vertex(-GUARD_BAND, 0.0, 1.0),
+            clip_vertex(0.0, -GUARD_BAND, 1.0),
+            clip_vertex(GUARD_BAND, GUARD_BAND, 1.0),
+        ];
+        let clipped = clip_triangle(tri);
+        assert_eq!(clipped.as_slice().len(), 1);
+        for (v, original) in clipped.as_slice()[0].iter().zip(tri.iter()) {
+            assert_eq!(v.clip, original.clip);
+        }
+    }
+
+    #[test]
+    fn test_guard_band_clips_huge_triangle() {
+        // Storm-wall sized: reaches 50x the viewport on every side
+        let clipped = clip_triangle([
+            clip_vertex(-50.0, -50.0, 1.0),
+            clip_vertex(50.0, -50.0, 1.0),
+            clip_vertex(0.0, 50.0, 1.0),
+        ]);
+        let tris = clipped.as_slice();
+        assert!(!tris.is_empty() && tris.len() <= MAX_CLIPPED_TRIANGLES);
+        for v in tris.iter().flatten() {
+            assert!(v.clip.x.abs() <= GUARD_BAND * v.clip.w + 1e-4);
+            assert!(v.clip.y.abs() <= GUARD_BAND * v.clip.w + 1e-4);
+        }
+
+        // Every clipped piece keeps the winding of the input
+        for tri in tris {
+            let area = (tri[1].clip.x - tri[0].clip.x) * (tri[2].clip.y - tri[0].clip.y)
+                - (tri[1].clip.y - tri[0].clip.y) * (tri[2].clip.x - tri[0].clip.x);
+            assert!(area > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_guard_band_rejects_offscreen_triangle() {
+        // Entirely past the right guard band edge
+        let clipped = clip_triangle([
+            clip_vertex(2.5, 0.0, 1.0),
+            clip_vertex(4.0, 0.0, 1.0),
+            clip_vertex(3.0, 1.0, 1.0),
+        ]);
+        assert!(clipped.as_slice().is_empty());
+
+        // Off screen but inside the band: left for the rasterizer's scissor
+        let clipped = clip_triangle([
+            clip_vertex(1.2, 0.0, 1.0),
+            clip_vertex(1.9, 0.0, 1.0),
+            clip_vertex(1.5, 1.0, 1.0),
+        ]);
+        assert_eq!(clipped.as_slice().len(), 1);
+    }
+
+    #[test]
+    fn test_viewport_edge_triangle_projects_inside_framebuffer() {
+        // NDC x in [-1, 1] maps exactly onto the 0..=100 pixel range
+        let (a, b, c) = (
+            Vertex::pos_color(Vec3::new(-1.0, -1.0, 0.0), Vec3::ONE),
+            Vertex::pos_color(Vec3::new(1.0, -1.0, 0.0), Vec3::ONE),
+            Vertex::pos_color(Vec3::new(1.0, 1.0, 0.0), Vec3::ONE),
+        );
+        let tris = transform_and_bin_fast(&a, &b, &c, &Mat4::IDENTITY, 100.0, 100.0, CullMode::None);
+        let screen: alloc::vec::Vec<_> = tris.into_iter().flatten().collect();
+        assert_eq!(screen.len(), 1);
+        assert_eq!((screen[0].min_x, screen[0].max_x), (0, 99));
+        assert_eq!((screen[0].min_y, screen[0].max_y), (0, 99));
+    }
+
+    /// Counter-clockwise in NDC (y up), which projects to clockwise on screen
+    fn front_facing_triangle() -> (Vertex, Vertex, Vertex) {
+        (
+            Vertex::pos_color(Vec3::new(-0.5, -0.5, 0.0), Vec3::ONE),
+            Vertex::pos_color(Vec3::new(0.5, -0.5, 0.0), Vec3::ONE),
+            Vertex::pos_color(Vec3::new(0.0, 0.5, 0.0), Vec3::ONE),
+        )
+    }
+
+    #[test]
+    fn test_front_facing_triangle_passes_cull() {
+        let (v0, v1, v2) = front_facing_triangle();
+        let tris = transform_triangle_clipped(&v0, &v1, &v2, &Mat4::IDENTITY, 100.0, 100.0, CullMode::Back);
+        assert!(tris[0].is_some());
+        assert!(tris[1].is_none());
+
+        let (tv0, tv1, tv2) = tris[0].unwrap();
+        assert!(!is_back_facing(&tv0, &tv1, &tv2));
+    }
+
+    #[test]
+    fn test_reversed_winding_is_culled() {
+        let (v0, v1, v2) = front_facing_triangle();
+        let culled = transform_triangle_clipped(&v0, &v2, &v1, &Mat4::IDENTITY, 100.0, 100.0, CullMode::Back);
+        assert!(culled.iter().all(Option::is_none));
+
+        // Double-sided meshes keep the back face
+        let kept = transform_triangle_clipped(&v0, &v2, &v1, &Mat4::IDENTITY, 100.0, 100.0, CullMode::None);
+        assert!(kept[0].is_some());
+    }
+
+    #[test]
+    fn test_lambert_sun_facing_is_brighter() {
+        let lit = Vertex::new(Vec3::ZERO, SUN_DIRECTION, Vec3::ONE, glam::Vec2::ZERO);
+        let unlit = Vertex::new(Vec3::ZERO, -SUN_DIRECTION, Vec3::ONE, glam::Vec2::ZERO);
+        let nm = normal_matrix(&Mat4::IDENTITY);
+
+        let lit = shade_vertex(&lit, &nm).color;
+        let unlit = shade_vertex(&unlit, &nm).color;
+        assert!(lit.x > unlit.x);
+        assert!((lit.x - (AMBIENT_LIGHT + DIFFUSE_LIGHT)).abs() < 1e-5);
+        assert!((unlit.x - AMBIENT_LIGHT).abs() < 1e-5);
+
+        // Rotating the model 180 degrees turns the sun-facing normal away
+        let flipped = normal_matrix(&Mat4::from_rotation_x(core::f32::consts::PI));
+        let v = Vertex::new(Vec3::ZERO, Vec3::Y, Vec3::ONE, glam::Vec2::ZERO);
+        assert!(shade_vertex(&v, &flipped).color.x < shade_vertex(&v, &nm).color.x);
+    }
+
+    #[test]
+    fn test_sun_follows_time_of_day() {
+        let (morning, noon, evening) = (LightState::at_hour(9.0), LightState::at_hour(12.0), LightState::at_hour(15.0));
+        for light in [morning, noon, evening] {
+            assert!((light.direction.length() - 1.0).abs() < 1e-5);
+        }
+        // Highest and whitest at noon; east in the morning, west in the evening
+        assert!(noon.direction.y > morning.direction.y);
+        assert!((morning.direction.y - evening.direction.y).abs() < 1e-5);
+        assert!(morning.direction.x > 0.0 && evening.direction.x < 0.0);
+        assert!(noon.color.z > morning.color.z);
+
+        // The night sun stays above the horizon
+        assert!(LightState::at_hour(0.0).direction.y > 0.0);
+
+        let light = LightState::DEFAULT;
+        assert_eq!(light.intensity(SUN_DIRECTION), Vec3::splat(AMBIENT_LIGHT + DIFFUSE_LIGHT));
+        assert_eq!(light.intensity(-SUN_DIRECTION), Vec3::splat(AMBIENT_LIGHT));
+    }
+
+    #[test]
+    fn test_lit_triangle_keeps_view_space_normals() {
+        let (v0, v1, v2) = front_facing_triangle();
+        let view = Mat4::from_rotation_y(core::f32::consts::FRAC_PI_2);
+        let nm = Mat3::from_mat4(view);
+        let tris = transform_and_bin_fast_lit(&v0, &v1, &v2, &Mat4::IDENTITY, &nm, 100.0, 100.0, CullMode::Back);
+        let tri = tris[0].unwrap();
+        assert!(tri.lit);
+
+        // +Y stays +Y under a rotation about Y
+        let expected = nm * Vec3::Y;
+        for n in tri.normals() {
+            assert!((n - expected).length() < 1e-3);
+        }
+        // The light follows the view the same way
+        let light = LightState { direction: Vec3::Y, ..LightState::DEFAULT }.to_view(&view);
+        assert!((light.direction - expected).length() < 1e-5);
+    }
 }
@@ -0,0 +1,90 @@
+//! Full-screen post-processing passes over the finished back buffer
+//!
+//! These run after rasterization and read the frame's z-buffer, so they cost
+//! the same per pixel however much overdraw the frame had.
+
+use crate::graphics::framebuffer::Framebuffer;
+use crate::graphics::rasterizer::Fog;
+use crate::graphics::zbuffer::{depth_mode, DepthFormat, DepthMode, IntDepth, ZBuffer};
+
+/// Blend every pixel toward `fog_color` by its z-buffer depth
+///
+/// The blend is `(depth - fog_start) / (fog_end - fog_start)` with the same
+/// dithered steps as the rasterizer's own fog (see `Fog`), so the two match.
+/// Pixels with no depth (the sky) and pixels nearer than `fog_start` keep
+/// their color. Pixels are written in pairs as one u64.
+///
+/// The GPU batch path fogs through render state; don't run this over its frames.
+pub fn apply_fog(fb: &Framebuffer, zb: &ZBuffer, fog_start: f32, fog_end: f32, fog_color: u32) {
+    let fog = Fog::new(fog_start, fog_end, fog_color);
+    let mode = depth_mode();
+    let width = fb.width.min(zb.width);
+    let height = fb.height.min(zb.height);
+
+    let shade = |color: u32, stored: f32, x: usize, y: usize| -> u32 {
+        let z = match mode {
+            DepthMode::Float => stored,
+            DepthMode::Integer => IntDepth::stored_to_f32(stored.to_bits()),
+        };
+        // Cleared slots are -inf (float) or 0 (integer)
+        if z <= 0.0 || z * fog_start >= 1.0 {
+            return color;
+        }
+        fog.apply((color >> 16) as u8, (color >> 8) as u8, color as u8, z, x as i32, y as i32)
+    };
+
+    for y in 0..height {
+        let depths = &zb.data[y * zb.width..][..width];
+        // Safety: `width` pixels of row y of our own back buffer
+        let row = unsafe { fb.scanline_ptr(y) };
+        let pairs = width / 2;
+        for i in 0..pairs {
+            let x = i * 2;
+            // Safety: pixels x and x + 1 lie within the row
+            unsafe {
+                let pair = row.add(x) as *mut u64;
+                let colors = pair.read_unaligned();
+                let left = shade(colors as u32, depths[x], x, y);
+                let right = shade((colors >> 32) as u32, depths[x + 1], x + 1, y);
+                pair.write_unaligned((left as u64) | ((right as u64) << 32));
+            }
+        }
+        if width % 2 == 1 {
+            let x = width - 1;
+            // Safety: the last pixel of the row
+            unsafe { *row.add(x) = shade(*row.add(x), depths[x], x, y) };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::framebuffer::{rgb, PixelFormat};
+
+    #[test]
+    fn test_apply_fog_by_depth() {
+        let (width, height) = (3, 2);
+        let mut front = alloc::vec![0u32; width * height];
+        let fb = Framebuffer {
+            address: front.as_mut_ptr(),
+            back_buffer: alloc::vec![rgb(200, 0, 0); width * height],
+            width,
+            height,
+            pitch: width * 4,
+            bpp: 32,
+            format: PixelFormat::RGB,
+        };
+        let mut zb = ZBuffer::new(width, height);
+        // Row 0: sky, nearer than the fog, past its end; row 1: odd tail pixel fogged
+        zb.data.copy_from_slice(&[f32::NEG_INFINITY, 1.0 / 10.0, 1.0 / 100.0, 1.0 / 5.0, 1.0 / 5.0, 1.0 / 60.0]);
+
+        let fog_color = rgb(50, 70, 100);
+        apply_fog(&fb, &zb, 20.0, 40.0, fog_color);
+        assert_eq!(fb.get_pixel(0, 0), rgb(200, 0, 0));
+        assert_eq!(fb.get_pixel(1, 0), rgb(200, 0, 0));
+        assert_eq!(fb.get_pixel(2, 0), fog_color);
+        assert_eq!(fb.get_pixel(1, 1), rgb(200, 0, 0));
+        assert_eq!(fb.get_pixel(2, 1), fog_color);
+    }
+}
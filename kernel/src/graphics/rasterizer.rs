@@ -12,12 +12,37 @@
 //! 5. Hierarchical 8x8 block rasterization with early rejection
 //! 6. Tile-bounded rasterization for parallel rendering
 //! 7. **SIMD 4-wide pixel processing** - processes 4 pixels per iteration
-//! 8. **Integer z-buffer** - faster depth comparisons
-
-use super::framebuffer::{rgb, FRAMEBUFFER};
-use super::tiles::ScreenTriangle;
-use super::zbuffer::ZBUFFER;
+//! 8. **Integer z-buffer** - optional 24-bit fixed-point depth (see
+//!    `zbuffer::DepthMode`); every fill path is generic over `DepthFormat`
+//!
+//! Every fill path applies the same per-pixel distance fog (see `Fog`), so
+//! tiles rasterized by different paths match.
+//!
+//! Triangles binned for per-pixel lighting (`ScreenTriangle::lit`) carry
+//! unlit colors and view-space normals; with a light in the render settings
+//! they go through the Phong path, which interpolates and renormalizes the
+//! normal at every pixel.
+//!
+//! Triangles of meshes with a material (`ScreenTriangle::material`) take the
+//! textured path: UVs are interpolated perspective-correctly and the
+//! material's atlas texture is sampled nearest-neighbor and modulated by the
+//! vertex color. Untextured triangles never touch it, so they keep the fast
+//! paths.
+//!
+//! Debug render modes (`RenderMode`) swap the fill path per tile: wireframe
+//! draws depth-tested edges, overdraw counts depth-passing writes into a
+//! per-pixel counter that replaces the tile with a heatmap, and tiles tints
+//! each finished tile by how many triangles were binned to it.
+
+use super::framebuffer::{self, rgb, DirtyRect, FRAMEBUFFER};
+use super::pipeline::LightState;
+use super::tiles::{ScreenTriangle, MAX_TRIANGLES_PER_TILE};
+use super::zbuffer::{depth_mode, DepthFormat, DepthMode, FloatDepth, IntDepth, ZBUFFER};
+use boot_config::RenderMode;
+use core::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use renderer::texture::{Material, Texture, TextureAtlas};
 use renderer::vertex::Vertex;
+use spin::{Mutex, Once};
 
 /// Fixed-point precision: 4 bits = 16 sub-pixels per pixel
 const FP_BITS: i32 = 4;
@@ -29,6 +54,148 @@ const COLOR_BITS: i32 = 16;
 const COLOR_ONE: i32 = 1 << COLOR_BITS;
 
 
+/// Debug render mode for the next frames (`RenderMode::to_u8`)
+static RENDER_MODE: AtomicU8 = AtomicU8::new(0);
+
+/// Select the debug render mode (picked up by the next frame's contexts)
+pub fn set_render_mode(mode: RenderMode) {
+    RENDER_MODE.store(mode.to_u8(), Ordering::Relaxed);
+}
+
+/// Current debug render mode
+pub fn render_mode() -> RenderMode {
+    RenderMode::from_u8(RENDER_MODE.load(Ordering::Relaxed)).unwrap_or_default()
+}
+
+/// Skip triangles hidden behind a tile's already-drawn depth (Hi-Z)
+pub static HIZ_ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Opaque triangles drawn between refreshes of a tile's Hi-Z depth
+/// (each refresh reads the tile's whole z-buffer region)
+const HIZ_REFRESH_TRIANGLES: usize = 16;
+
+
+/// Wireframe edge color
+const WIREFRAME_COLOR: u32 = rgb(255, 255, 255);
+
+/// Debug heatmap, indexed by overdraw count (5+ = red) or binned-triangle bucket
+const DEBUG_HEAT: [u32; 6] = [
+    rgb(0, 0, 0),
+    rgb(0, 0, 160),
+    rgb(0, 160, 0),
+    rgb(220, 220, 0),
+    rgb(255, 128, 0),
+    rgb(255, 0, 0),
+];
+
+/// Fog blend steps (the fog amount is dithered between adjacent steps)
+const FOG_STEPS: u32 = 16;
+
+/// 4x4 ordered-dither thresholds in [0, 1), indexed [y & 3][x & 3]
+const BAYER_4X4: [[f32; 4]; 4] = [
+    [0.0 / 16.0, 8.0 / 16.0, 2.0 / 16.0, 10.0 / 16.0],
+    [12.0 / 16.0, 4.0 / 16.0, 14.0 / 16.0, 6.0 / 16.0],
+    [3.0 / 16.0, 11.0 / 16.0, 1.0 / 16.0, 9.0 / 16.0],
+    [15.0 / 16.0, 7.0 / 16.0, 13.0 / 16.0, 5.0 / 16.0],
+];
+
+/// Linear distance fog between two view-space depths
+///
+/// Pixel depth comes from the interpolated z (1/w, so view depth is exactly
+/// 1/z). The blend amount is ordered-dithered across FOG_STEPS levels, which
+/// hides banding in the long, shallow gradients fog produces.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fog {
+    /// Depth where fog begins
+    pub start: f32,
+    /// Depth where fog is fully opaque
+    pub end: f32,
+    /// Fog color (0x00RRGGBB)
+    pub color: u32,
+}
+
+impl Fog {
+    pub const fn new(start: f32, end: f32, color: u32) -> Self {
+        Self { start, end, color }
+    }
+
+    /// Fog to apply to a triangle, or None if all of it is nearer than `start`
+    #[inline]
+    fn for_triangle(&self, tri: &ScreenTriangle) -> Option<&Self> {
+        // The farthest vertex has the smallest 1/w
+        let far_z = tri.z0.min(tri.z1).min(tri.z2);
+        (far_z * self.start < 1.0).then_some(self)
+    }
+
+    /// Fogged color of a pixel at depth `z` (1/w) and screen position (x, y)
+    #[inline(always)]
+    pub fn apply(&self, r: u8, g: u8, b: u8, z: f32, x: i32, y: i32) -> u32 {
+        let depth = 1.0 / z;
+        let t = ((depth - self.start) / (self.end - self.start)).clamp(0.0, 1.0);
+        let dither = BAYER_4X4[(y & 3) as usize][(x & 3) as usize];
+        let amount = ((t * FOG_STEPS as f32 + dither) as u32).min(FOG_STEPS);
+        let keep = FOG_STEPS - amount;
+        let mix = |src: u8, shift: u32| {
+            ((src as u32 * keep + ((self.color >> shift) & 0xFF) * amount) / FOG_STEPS) as u8
+        };
+        rgb(mix(r, 16), mix(g, 8), mix(b, 0))
+    }
+}
+
+/// Per-frame render settings, snapshotted into every RenderContext
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct RenderSettings {
+    /// Distance fog (None = off)
+    pub fog: Option<Fog>,
+    /// Debug visualization
+    pub mode: RenderMode,
+    /// Sunlight in view space for lit triangles (None = per-vertex lighting)
+    pub light: Option<LightState>,
+}
+
+/// Settings for the frame being rendered (set before workers acquire contexts)
+static RENDER_SETTINGS: Mutex<RenderSettings> =
+    Mutex::new(RenderSettings { fog: None, mode: RenderMode::Normal, light: None });
+
+/// Whether the current settings have a light, for binning without the lock
+static LIGHTING: AtomicBool = AtomicBool::new(false);
+
+/// Set the render settings for the next contexts acquired
+pub fn set_render_settings(settings: RenderSettings) {
+    *RENDER_SETTINGS.lock() = settings;
+    LIGHTING.store(settings.light.is_some(), Ordering::Relaxed);
+}
+
+/// Material textures, generated once by `init_textures`
+static TEXTURES: Once<TextureAtlas> = Once::new();
+
+/// Generate the material texture atlas (before the first textured frame)
+pub fn init_textures() {
+    TEXTURES.call_once(TextureAtlas::generate);
+}
+
+/// Atlas texture of a triangle's material, if it has one and the atlas is up
+#[inline]
+fn texture_for(tri: &ScreenTriangle) -> Option<&'static Texture> {
+    let material = Material::from_u8(tri.material)?;
+    TEXTURES.get()?.get(material)
+}
+
+/// Whether triangles binned now should be lit per pixel (see
+/// `pipeline::transform_and_bin_fast_lit`)
+pub fn lighting_enabled() -> bool {
+    LIGHTING.load(Ordering::Relaxed)
+}
+
+/// Final color of a pixel: the triangle color, fogged if the triangle has fog
+#[inline(always)]
+fn shade_pixel<D: DepthFormat>(fog: Option<&Fog>, r: u8, g: u8, b: u8, z: D::Z, x: i32, y: i32) -> u32 {
+    match fog {
+        Some(fog) => fog.apply(r, g, b, D::to_f32(z), x, y),
+        None => rgb(r, g, b),
+    }
+}
+
 /// Convert float to fixed-point (4-bit)
 #[inline(always)]
 fn to_fixed(f: f32) -> i32 {
@@ -43,11 +210,16 @@ pub struct RenderContext {
     fb_pitch: usize,  // Framebuffer pixels per row (may be > width due to padding)
     zb_ptr: *mut f32,
     zb_width: usize,  // Z-buffer width (uses width, not pitch)
+    overdraw_ptr: *mut u8,  // Per-pixel write counts (z-buffer stride), null without a counter buffer
+    screen: bool,  // Renders into the display back buffer, so writes are dirty-tracked
+    settings: RenderSettings,
+    depth: DepthMode,  // Z-buffer storage format, fixed for the context's lifetime
 }
 
 impl RenderContext {
     /// Acquire render context with direct buffer access
-    /// Uses the BACK buffer for rendering (double buffering)
+    /// Uses the BACK buffer for rendering; triple buffering presents from a
+    /// snapshot (framebuffer::PresentQueue), so the back buffer is always free
     pub fn acquire() -> Option<Self> {
         let fb_guard = FRAMEBUFFER.lock();
         let zb_guard = ZBUFFER.lock();
@@ -63,6 +235,10 @@ impl RenderContext {
             fb_pitch: fb.pitch / 4,  // Convert bytes to pixels (for framebuffer)
             zb_ptr: zb.data.as_ptr() as *mut f32,
             zb_width: zb.width,  // Z-buffer uses width for stride
+            overdraw_ptr: zb.overdraw.as_ptr() as *mut u8,
+            screen: true,
+            settings: *RENDER_SETTINGS.lock(),
+            depth: depth_mode(),
         };
 
         drop(fb_guard);
@@ -71,13 +247,81 @@ impl RenderContext {
         Some(ctx)
     }
 
+    /// Context over caller-owned color and depth buffers (offscreen targets)
+    /// Uses float depth and default settings; the buffers must outlive the context
+    pub fn for_buffers(fb: &mut [u32], zb: &mut [f32], width: usize, height: usize) -> Self {
+        assert!(fb.len() >= width * height && zb.len() >= width * height);
+        Self {
+            fb_ptr: fb.as_mut_ptr(),
+            fb_width: width,
+            fb_height: height,
+            fb_pitch: width,
+            zb_ptr: zb.as_mut_ptr(),
+            zb_width: width,
+            overdraw_ptr: core::ptr::null_mut(),
+            screen: false,
+            settings: RenderSettings::default(),
+            depth: DepthMode::Float,
+        }
+    }
+
     #[inline]
     pub fn dimensions(&self) -> (usize, usize) {
         (self.fb_width, self.fb_height)
     }
 
+    /// Render settings this context was acquired with
+    #[inline]
+    pub fn settings(&self) -> &RenderSettings {
+        &self.settings
+    }
+
+    /// Whether tile passes use Hi-Z (never in wireframe mode, which shows hidden edges)
+    #[inline]
+    pub fn hiz_enabled(&self) -> bool {
+        HIZ_ENABLED.load(Ordering::Relaxed) && self.settings.mode != RenderMode::Wireframe
+    }
+
+    /// Z-buffer storage format this context was acquired with
+    #[inline]
+    pub fn depth_mode(&self) -> DepthMode {
+        self.depth
+    }
+
+    /// Farthest depth (smallest 1/w) in a screen region of the z-buffer
+    /// A triangle whose nearest depth is below this fails the z-test at every pixel
+    pub fn farthest_depth(&self, min_x: i32, max_x: i32, min_y: i32, max_y: i32) -> f32 {
+        match self.depth {
+            DepthMode::Float => self.farthest_stored::<FloatDepth>(min_x, max_x, min_y, max_y),
+            DepthMode::Integer => self.farthest_stored::<IntDepth>(min_x, max_x, min_y, max_y),
+        }
+    }
+
+    fn farthest_stored<D: DepthFormat>(&self, min_x: i32, max_x: i32, min_y: i32, max_y: i32) -> f32 {
+        let zb = self.zb_ptr as *const D::Stored;
+        let mut far = f32::INFINITY;
+        for y in min_y..=max_y {
+            let row = y as usize * self.zb_width;
+            // Safety: the region lies inside the z-buffer
+            let zs = unsafe {
+                core::slice::from_raw_parts(zb.add(row + min_x as usize), (max_x - min_x + 1) as usize)
+            };
+            far = zs.iter().fold(far, |far, &z| far.min(D::stored_to_f32(z)));
+        }
+        far
+    }
+
+    /// Fog for a triangle under this context's settings
+    #[inline]
+    fn fog_for(&self, tri: &ScreenTriangle) -> Option<&Fog> {
+        self.settings.fog.as_ref()?.for_triangle(tri)
+    }
+
     /// Fast clear using unrolled 128-bit writes
     pub fn clear(&self, color: u32) {
+        if self.screen {
+            framebuffer::mark_background(color);
+        }
         let size = self.fb_pitch * self.fb_height;
         let color64 = (color as u64) | ((color as u64) << 32);
         let ptr64 = self.fb_ptr as *mut u64;
@@ -106,9 +350,15 @@ impl RenderContext {
     }
 
     /// Clear z-buffer to minimum depth (optimized)
+    /// Float mode clears to -inf, integer mode to 0
     pub fn clear_zbuffer(&self) {
+        // A depth clear starts a 3D pass, whose triangles are not dirty-tracked
+        if self.screen {
+            framebuffer::mark_full_dirty();
+        }
         let size = self.zb_width * self.fb_height;
-        let neg_inf_bits: u64 = 0xFF800000_FF800000;
+        let far_bits = self.depth.clear_bits();
+        let neg_inf_bits = (far_bits as u64) << 32 | far_bits as u64;
         let ptr64 = self.zb_ptr as *mut u64;
 
         unsafe {
@@ -129,7 +379,7 @@ impl RenderContext {
                 *ptr64.add(j) = neg_inf_bits;
             }
             if size & 1 != 0 {
-                *self.zb_ptr.add(size - 1) = f32::NEG_INFINITY;
+                *self.zb_ptr.add(size - 1) = f32::from_bits(far_bits);
             }
         }
     }
@@ -137,9 +387,20 @@ impl RenderContext {
 
 /// High-performance triangle rasterizer
 pub fn rasterize_triangle_with_context(ctx: &RenderContext, v0: &Vertex, v1: &Vertex, v2: &Vertex) {
+    if ctx.screen {
+        framebuffer::mark_full_dirty();
+    }
+    match ctx.depth {
+        DepthMode::Float => rasterize_vertices::<FloatDepth>(ctx, v0, v1, v2),
+        DepthMode::Integer => rasterize_vertices::<IntDepth>(ctx, v0, v1, v2),
+    }
+}
+
+fn rasterize_vertices<D: DepthFormat>(ctx: &RenderContext, v0: &Vertex, v1: &Vertex, v2: &Vertex) {
     let (fb_width, fb_height) = ctx.dimensions();
     let fb_pitch = ctx.fb_pitch;  // Framebuffer uses pitch for row stride
     let zb_width = ctx.zb_width;  // Z-buffer uses width for row stride
+    let zb = ctx.zb_ptr as *mut D::Stored;
     let fb_width_i = fb_width as i32;
     let fb_height_i = fb_height as i32;
 
@@ -205,9 +466,9 @@ pub fn rasterize_triangle_with_context(ctx: &RenderContext, v0: &Vertex, v1: &Ve
     let inv_area = 1.0 / (area as f32);
     let fp_scale = FP_ONE as f32;
 
-    // Z gradients (still float for depth precision)
-    let dz_dx = (z0 * a12 as f32 + z1 * a20 as f32 + z2 * a01 as f32) * inv_area * fp_scale;
-    let dz_dy = (z0 * b12 as f32 + z1 * b20 as f32 + z2 * b01 as f32) * inv_area * fp_scale;
+    // Z gradients (computed in float, converted once to the depth format)
+    let dz_dx = D::from_f32((z0 * a12 as f32 + z1 * a20 as f32 + z2 * a01 as f32) * inv_area * fp_scale);
+    let dz_dy = D::from_f32((z0 * b12 as f32 + z1 * b20 as f32 + z2 * b01 as f32) * inv_area * fp_scale);
 
     // Color gradients as fixed-point integers
     let area_i64 = area;
@@ -239,7 +500,7 @@ pub fn rasterize_triangle_with_context(ctx: &RenderContext, v0: &Vertex, v1: &Ve
     let b1_start = w1_row as f32 * inv_area;
     let b2_start = w2_row as f32 * inv_area;
 
-    let mut z_row = b0_start * z0 + b1_start * z1 + b2_start * z2;
+    let mut z_row = D::from_f32(b0_start * z0 + b1_start * z1 + b2_start * z2);
 
     // Color initial values (fixed-point)
     let mut r_row = (w0_row * r0 + w1_row * r1 + w2_row * r2) / area_i64;
@@ -271,9 +532,10 @@ pub fn rasterize_triangle_with_context(ctx: &RenderContext, v0: &Vertex, v1: &Ve
                 let zb_idx = (py as usize) * zb_width + (px as usize);
 
                 unsafe {
-                    let current_z = *ctx.zb_ptr.add(zb_idx);
-                    if z > current_z {
-                        *ctx.zb_ptr.add(zb_idx) = z;
+                    let current_z = *zb.add(zb_idx);
+                    let depth = D::store(z);
+                    if depth > current_z {
+                        *zb.add(zb_idx) = depth;
 
                         // Convert fixed-point color to u8 with clamping
                         let ri = ((r >> COLOR_BITS) as i32).clamp(0, 255) as u8;
@@ -324,12 +586,32 @@ pub fn rasterize_screen_triangle_in_tile(
     tile_max_x: i32,
     tile_min_y: i32,
     tile_max_y: i32,
+) {
+    match ctx.depth {
+        DepthMode::Float => rasterize_in_tile::<FloatDepth>(ctx, tri, tile_min_x, tile_max_x, tile_min_y, tile_max_y),
+        DepthMode::Integer => rasterize_in_tile::<IntDepth>(ctx, tri, tile_min_x, tile_max_x, tile_min_y, tile_max_y),
+    }
+}
+
+fn rasterize_in_tile<D: DepthFormat>(
+    ctx: &RenderContext,
+    tri: &ScreenTriangle,
+    tile_min_x: i32,
+    tile_max_x: i32,
+    tile_min_y: i32,
+    tile_max_y: i32,
 ) {
     const BLOCK_SIZE: i32 = 8;
 
+    if tri.perspective {
+        rasterize_perspective::<false, D>(ctx, tri, tile_min_x, tile_max_x, tile_min_y, tile_max_y);
+        return;
+    }
+
     // Use pitch for framebuffer, width for z-buffer
     let fb_pitch = ctx.fb_pitch;
     let zb_width = ctx.zb_width;
+    let zb = ctx.zb_ptr as *mut D::Stored;
 
     // Clamp triangle bounds to tile bounds
     let min_x = tri.min_x.max(tile_min_x);
@@ -344,14 +626,15 @@ pub fn rasterize_screen_triangle_in_tile(
     // Pre-compute gradients for depth and color interpolation
     let fp_one_i64 = FP_ONE as i64;
     let area_i64 = (1.0 / tri.inv_area) as i64;
+    let fog = ctx.fog_for(tri);
 
-    // Z gradients (still float for depth precision)
-    let dz_dx = (tri.z0 * tri.a12 as f32 + tri.z1 * tri.a20 as f32 + tri.z2 * tri.a01 as f32)
-        * tri.inv_area
-        * FP_ONE as f32;
-    let dz_dy = (tri.z0 * tri.b12 as f32 + tri.z1 * tri.b20 as f32 + tri.z2 * tri.b01 as f32)
-        * tri.inv_area
-        * FP_ONE as f32;
+    // Z gradients (computed in float, converted once to the depth format)
+    let dz_dx = D::from_f32(
+        (tri.z0 * tri.a12 as f32 + tri.z1 * tri.a20 as f32 + tri.z2 * tri.a01 as f32) * tri.inv_area * FP_ONE as f32,
+    );
+    let dz_dy = D::from_f32(
+        (tri.z0 * tri.b12 as f32 + tri.z1 * tri.b20 as f32 + tri.z2 * tri.b01 as f32) * tri.inv_area * FP_ONE as f32,
+    );
 
     // Color gradients as fixed-point
     let dr_dx = ((tri.r0 * tri.a12 as i64 + tri.r1 * tri.a20 as i64 + tri.r2 * tri.a01 as i64) * fp_one_i64) / area_i64;
@@ -405,7 +688,7 @@ pub fn rasterize_screen_triangle_in_tile(
             let b1_start = w1_row as f32 * tri.inv_area;
             let b2_start = w2_row as f32 * tri.inv_area;
 
-            let mut z_row = b0_start * tri.z0 + b1_start * tri.z1 + b2_start * tri.z2;
+            let mut z_row = D::from_f32(b0_start * tri.z0 + b1_start * tri.z1 + b2_start * tri.z2);
             let mut r_row = (w0_row * tri.r0 + w1_row * tri.r1 + w2_row * tri.r2) / area_i64;
             let mut g_row = (w0_row * tri.g0 + w1_row * tri.g1 + w2_row * tri.g2) / area_i64;
             let mut b_row = (w0_row * tri.b0 + w1_row * tri.b1 + w2_row * tri.b2) / area_i64;
@@ -427,15 +710,16 @@ pub fn rasterize_screen_triangle_in_tile(
                         let zb_idx = (py as usize) * zb_width + (px as usize);
 
                         unsafe {
-                            let current_z = *ctx.zb_ptr.add(zb_idx);
-                            if z > current_z {
-                                *ctx.zb_ptr.add(zb_idx) = z;
+                            let current_z = *zb.add(zb_idx);
+                            let depth = D::store(z);
+                            if depth > current_z {
+                                *zb.add(zb_idx) = depth;
 
                                 let ri = ((r >> COLOR_BITS) as i32).clamp(0, 255) as u8;
                                 let gi = ((g >> COLOR_BITS) as i32).clamp(0, 255) as u8;
                                 let bi = ((b_color >> COLOR_BITS) as i32).clamp(0, 255) as u8;
 
-                                *ctx.fb_ptr.add(fb_idx) = rgb(ri, gi, bi);
+                                *ctx.fb_ptr.add(fb_idx) = shade_pixel::<D>(fog, ri, gi, bi, z, px, py);
                             }
                         }
                     }
@@ -528,9 +812,29 @@ pub fn rasterize_screen_triangle_simple(
     tile_min_y: i32,
     tile_max_y: i32,
 ) {
+    match ctx.depth {
+        DepthMode::Float => rasterize_simple::<FloatDepth>(ctx, tri, tile_min_x, tile_max_x, tile_min_y, tile_max_y),
+        DepthMode::Integer => rasterize_simple::<IntDepth>(ctx, tri, tile_min_x, tile_max_x, tile_min_y, tile_max_y),
+    }
+}
+
+fn rasterize_simple<D: DepthFormat>(
+    ctx: &RenderContext,
+    tri: &ScreenTriangle,
+    tile_min_x: i32,
+    tile_max_x: i32,
+    tile_min_y: i32,
+    tile_max_y: i32,
+) {
+    if tri.perspective {
+        rasterize_perspective::<false, D>(ctx, tri, tile_min_x, tile_max_x, tile_min_y, tile_max_y);
+        return;
+    }
+
     // Use pitch for framebuffer, width for z-buffer
     let fb_pitch = ctx.fb_pitch;
     let zb_width = ctx.zb_width;
+    let zb = ctx.zb_ptr as *mut D::Stored;
 
     // Clamp to tile bounds
     let min_x = tri.min_x.max(tile_min_x);
@@ -544,12 +848,15 @@ pub fn rasterize_screen_triangle_simple(
 
     let fp_one_i64 = FP_ONE as i64;
     let area_i64 = (1.0 / tri.inv_area) as i64;
+    let fog = ctx.fog_for(tri);
 
-    // Gradients
-    let dz_dx = (tri.z0 * tri.a12 as f32 + tri.z1 * tri.a20 as f32 + tri.z2 * tri.a01 as f32)
-        * tri.inv_area * FP_ONE as f32;
-    let dz_dy = (tri.z0 * tri.b12 as f32 + tri.z1 * tri.b20 as f32 + tri.z2 * tri.b01 as f32)
-        * tri.inv_area * FP_ONE as f32;
+    // Gradients (depth converted once to the depth format)
+    let dz_dx = D::from_f32(
+        (tri.z0 * tri.a12 as f32 + tri.z1 * tri.a20 as f32 + tri.z2 * tri.a01 as f32) * tri.inv_area * FP_ONE as f32,
+    );
+    let dz_dy = D::from_f32(
+        (tri.z0 * tri.b12 as f32 + tri.z1 * tri.b20 as f32 + tri.z2 * tri.b01 as f32) * tri.inv_area * FP_ONE as f32,
+    );
 
     let dr_dx = ((tri.r0 * tri.a12 as i64 + tri.r1 * tri.a20 as i64 + tri.r2 * tri.a01 as i64) * fp_one_i64) / area_i64;
     let dr_dy = ((tri.r0 * tri.b12 as i64 + tri.r1 * tri.b20 as i64 + tri.r2 * tri.b01 as i64) * fp_one_i64) / area_i64;
@@ -580,7 +887,7 @@ pub fn rasterize_screen_triangle_simple(
     let b1_start = w1_row as f32 * tri.inv_area;
     let b2_start = w2_row as f32 * tri.inv_area;
 
-    let mut z_row = b0_start * tri.z0 + b1_start * tri.z1 + b2_start * tri.z2;
+    let mut z_row = D::from_f32(b0_start * tri.z0 + b1_start * tri.z1 + b2_start * tri.z2);
     let mut r_row = (w0_row * tri.r0 + w1_row * tri.r1 + w2_row * tri.r2) / area_i64;
     let mut g_row = (w0_row * tri.g0 + w1_row * tri.g1 + w2_row * tri.g2) / area_i64;
     let mut b_row = (w0_row * tri.b0 + w1_row * tri.b1 + w2_row * tri.b2) / area_i64;
@@ -601,15 +908,16 @@ pub fn rasterize_screen_triangle_simple(
                 let zb_idx = (py as usize) * zb_width + (px as usize);
 
                 unsafe {
-                    let current_z = *ctx.zb_ptr.add(zb_idx);
-                    if z > current_z {
-                        *ctx.zb_ptr.add(zb_idx) = z;
+                    let current_z = *zb.add(zb_idx);
+                    let depth = D::store(z);
+                    if depth > current_z {
+                        *zb.add(zb_idx) = depth;
 
                         let ri = ((r >> COLOR_BITS) as i32).clamp(0, 255) as u8;
                         let gi = ((g >> COLOR_BITS) as i32).clamp(0, 255) as u8;
                         let bi = ((b_color >> COLOR_BITS) as i32).clamp(0, 255) as u8;
 
-                        *ctx.fb_ptr.add(fb_idx) = rgb(ri, gi, bi);
+                        *ctx.fb_ptr.add(fb_idx) = shade_pixel::<D>(fog, ri, gi, bi, z, px, py);
                     }
                 }
             }
@@ -633,46 +941,67 @@ pub fn rasterize_screen_triangle_simple(
     }
 }
 
-// ============================================================================
-// SIMD 4-WIDE RASTERIZATION
-// Processes 4 horizontal pixels per iteration for ~2-4x speedup
-// ============================================================================
-
-/// SIMD 4-wide pixel processing structure
-#[repr(align(16))]
-struct Simd4i64 {
-    v: [i64; 4],
-}
-
-impl Simd4i64 {
-    #[inline(always)]
-    const fn splat(val: i64) -> Self {
-        Self { v: [val, val, val, val] }
+/// Perspective-correct tile-bounded rasterization
+/// Interpolates color/w and 1/w linearly in screen space, then divides per pixel.
+/// Slower than the affine path, so only used for triangles flagged `perspective`.
+pub fn rasterize_screen_triangle_perspective(
+    ctx: &RenderContext,
+    tri: &ScreenTriangle,
+    tile_min_x: i32,
+    tile_max_x: i32,
+    tile_min_y: i32,
+    tile_max_y: i32,
+) {
+    match ctx.depth {
+        DepthMode::Float => rasterize_perspective::<false, FloatDepth>(ctx, tri, tile_min_x, tile_max_x, tile_min_y, tile_max_y),
+        DepthMode::Integer => rasterize_perspective::<false, IntDepth>(ctx, tri, tile_min_x, tile_max_x, tile_min_y, tile_max_y),
     }
+}
 
-    #[inline(always)]
-    const fn from_array(arr: [i64; 4]) -> Self {
-        Self { v: arr }
+/// Alpha-blended tile-bounded rasterization for translucent triangles
+/// Blends `tri.alpha` of the triangle color over the framebuffer; depth-tested
+/// against the opaque scene but never writes depth, so translucent triangles
+/// must be drawn back-to-front. Always perspective-correct (they are few).
+pub fn rasterize_screen_triangle_blended(
+    ctx: &RenderContext,
+    tri: &ScreenTriangle,
+    tile_min_x: i32,
+    tile_max_x: i32,
+    tile_min_y: i32,
+    tile_max_y: i32,
+) {
+    match ctx.depth {
+        DepthMode::Float => rasterize_perspective::<true, FloatDepth>(ctx, tri, tile_min_x, tile_max_x, tile_min_y, tile_max_y),
+        DepthMode::Integer => rasterize_perspective::<true, IntDepth>(ctx, tri, tile_min_x, tile_max_x, tile_min_y, tile_max_y),
     }
+}
 
-    #[inline(always)]
-    fn add(&self, other: &Self) -> Self {
-        Self {
-            v: [
-                self.v[0].wrapping_add(other.v[0]),
-                self.v[1].wrapping_add(other.v[1]),
-                self.v[2].wrapping_add(other.v[2]),
-                self.v[3].wrapping_add(other.v[3]),
-            ],
-        }
+/// Per-pixel (Phong) lit tile-bounded rasterization
+/// Interpolates the view-space normals perspective-correctly along with the
+/// colors, renormalizes them and scales the color by the context's light.
+/// Triangles that aren't lit, or contexts without a light, take the simple path.
+pub fn rasterize_screen_triangle_lit(
+    ctx: &RenderContext,
+    tri: &ScreenTriangle,
+    tile_min_x: i32,
+    tile_max_x: i32,
+    tile_min_y: i32,
+    tile_max_y: i32,
+) {
+    let Some(light) = ctx.settings.light.filter(|_| tri.lit) else {
+        rasterize_screen_triangle_simple(ctx, tri, tile_min_x, tile_max_x, tile_min_y, tile_max_y);
+        return;
+    };
+    match ctx.depth {
+        DepthMode::Float => rasterize_lit::<FloatDepth>(ctx, tri, &light, tile_min_x, tile_max_x, tile_min_y, tile_max_y),
+        DepthMode::Integer => rasterize_lit::<IntDepth>(ctx, tri, &light, tile_min_x, tile_max_x, tile_min_y, tile_max_y),
     }
 }
 
-/// SIMD 4-wide rasterizer - processes 4 horizontal pixels per iteration
-/// This is the optimized hot path for software rasterization
-pub fn rasterize_screen_triangle_simd4(
+fn rasterize_lit<D: DepthFormat>(
     ctx: &RenderContext,
     tri: &ScreenTriangle,
+    light: &LightState,
     tile_min_x: i32,
     tile_max_x: i32,
     tile_min_y: i32,
@@ -681,7 +1010,9 @@ pub fn rasterize_screen_triangle_simd4(
     // Use pitch for framebuffer, width for z-buffer
     let fb_pitch = ctx.fb_pitch;
     let zb_width = ctx.zb_width;
+    let zb = ctx.zb_ptr as *mut D::Stored;
 
+    // Clamp to tile bounds
     let min_x = tri.min_x.max(tile_min_x);
     let max_x = tri.max_x.min(tile_max_x);
     let min_y = tri.min_y.max(tile_min_y);
@@ -691,131 +1022,976 @@ pub fn rasterize_screen_triangle_simd4(
         return;
     }
 
-    let aligned_min_x = min_x & !3;
     let fp_one_i64 = FP_ONE as i64;
-    let area_i64 = (1.0 / tri.inv_area) as i64;
-
-    // Z gradients as floats (direct f32 comparison with z-buffer - no conversion overhead)
-    let dz_dx = (tri.z0 * tri.a12 as f32 + tri.z1 * tri.a20 as f32 + tri.z2 * tri.a01 as f32)
-        * tri.inv_area * FP_ONE as f32;
-    let dz_dy = (tri.z0 * tri.b12 as f32 + tri.z1 * tri.b20 as f32 + tri.z2 * tri.b01 as f32)
-        * tri.inv_area * FP_ONE as f32;
-
-    let dr_dx = ((tri.r0 * tri.a12 as i64 + tri.r1 * tri.a20 as i64 + tri.r2 * tri.a01 as i64) * fp_one_i64) / area_i64;
-    let dr_dy = ((tri.r0 * tri.b12 as i64 + tri.r1 * tri.b20 as i64 + tri.r2 * tri.b01 as i64) * fp_one_i64) / area_i64;
-    let dg_dx = ((tri.g0 * tri.a12 as i64 + tri.g1 * tri.a20 as i64 + tri.g2 * tri.a01 as i64) * fp_one_i64) / area_i64;
-    let dg_dy = ((tri.g0 * tri.b12 as i64 + tri.g1 * tri.b20 as i64 + tri.g2 * tri.b01 as i64) * fp_one_i64) / area_i64;
-    let db_dx = ((tri.b0 * tri.a12 as i64 + tri.b1 * tri.a20 as i64 + tri.b2 * tri.a01 as i64) * fp_one_i64) / area_i64;
-    let db_dy = ((tri.b0 * tri.b12 as i64 + tri.b1 * tri.b20 as i64 + tri.b2 * tri.b01 as i64) * fp_one_i64) / area_i64;
+    let fog = ctx.fog_for(tri);
+    let color_scale = 1.0 / COLOR_ONE as f32;
+
+    // Attributes pre-divided by w (colors in 0..255)
+    let iw = [tri.inv_w0, tri.inv_w1, tri.inv_w2];
+    let per_w = |a: [f32; 3]| [a[0] * iw[0], a[1] * iw[1], a[2] * iw[2]];
+    let rw = per_w([tri.r0 as f32, tri.r1 as f32, tri.r2 as f32].map(|c| c * color_scale));
+    let gw = per_w([tri.g0 as f32, tri.g1 as f32, tri.g2 as f32].map(|c| c * color_scale));
+    let bw = per_w([tri.b0 as f32, tri.b1 as f32, tri.b2 as f32].map(|c| c * color_scale));
+    let [n0, n1, n2] = tri.normals();
+    let nxw = per_w([n0.x, n1.x, n2.x]);
+    let nyw = per_w([n0.y, n1.y, n2.y]);
+    let nzw = per_w([n0.z, n1.z, n2.z]);
+
+    // Screen-space gradients of a per-vertex attribute
+    let grad_x = |a: [f32; 3]| {
+        (a[0] * tri.a12 as f32 + a[1] * tri.a20 as f32 + a[2] * tri.a01 as f32) * tri.inv_area * FP_ONE as f32
+    };
+    let grad_y = |a: [f32; 3]| {
+        (a[0] * tri.b12 as f32 + a[1] * tri.b20 as f32 + a[2] * tri.b01 as f32) * tri.inv_area * FP_ONE as f32
+    };
+    let zs = [tri.z0, tri.z1, tri.z2];
+    let (dz_dx, dz_dy) = (D::from_f32(grad_x(zs)), D::from_f32(grad_y(zs)));
+    let (diw_dx, diw_dy) = (grad_x(iw), grad_y(iw));
+    let (drw_dx, drw_dy) = (grad_x(rw), grad_y(rw));
+    let (dgw_dx, dgw_dy) = (grad_x(gw), grad_y(gw));
+    let (dbw_dx, dbw_dy) = (grad_x(bw), grad_y(bw));
+    let (dnx_dx, dnx_dy) = (grad_x(nxw), grad_y(nxw));
+    let (dny_dx, dny_dy) = (grad_x(nyw), grad_y(nyw));
+    let (dnz_dx, dnz_dy) = (grad_x(nzw), grad_y(nzw));
 
-    let w0_step_x4 = (tri.a12 as i64) * fp_one_i64 * 4;
-    let w1_step_x4 = (tri.a20 as i64) * fp_one_i64 * 4;
-    let w2_step_x4 = (tri.a01 as i64) * fp_one_i64 * 4;
+    // Edge steps
+    let w0_step_x = (tri.a12 as i64) * fp_one_i64;
+    let w1_step_x = (tri.a20 as i64) * fp_one_i64;
+    let w2_step_x = (tri.a01 as i64) * fp_one_i64;
     let w0_step_y = (tri.b12 as i64) * fp_one_i64;
     let w1_step_y = (tri.b20 as i64) * fp_one_i64;
     let w2_step_y = (tri.b01 as i64) * fp_one_i64;
-    let w0_step_x1 = (tri.a12 as i64) * fp_one_i64;
-    let w1_step_x1 = (tri.a20 as i64) * fp_one_i64;
-    let w2_step_x1 = (tri.a01 as i64) * fp_one_i64;
 
-    let start_x = (aligned_min_x << FP_BITS) + FP_HALF;
+    // Starting point
+    let start_x = (min_x << FP_BITS) + FP_HALF;
     let start_y = (min_y << FP_BITS) + FP_HALF;
 
-    let w0_base = (tri.a12 as i64) * (start_x as i64) + (tri.b12 as i64) * (start_y as i64) + tri.c12;
-    let w1_base = (tri.a20 as i64) * (start_x as i64) + (tri.b20 as i64) * (start_y as i64) + tri.c20;
-    let w2_base = (tri.a01 as i64) * (start_x as i64) + (tri.b01 as i64) * (start_y as i64) + tri.c01;
-
-    let w0_init = Simd4i64::from_array([w0_base, w0_base + w0_step_x1, w0_base + w0_step_x1 * 2, w0_base + w0_step_x1 * 3]);
-    let w1_init = Simd4i64::from_array([w1_base, w1_base + w1_step_x1, w1_base + w1_step_x1 * 2, w1_base + w1_step_x1 * 3]);
-    let w2_init = Simd4i64::from_array([w2_base, w2_base + w2_step_x1, w2_base + w2_step_x1 * 2, w2_base + w2_step_x1 * 3]);
-
-    let w0_step_y_vec = Simd4i64::splat(w0_step_y);
-    let w1_step_y_vec = Simd4i64::splat(w1_step_y);
-    let w2_step_y_vec = Simd4i64::splat(w2_step_y);
-
-    // Initial z value as float (matching z-buffer format)
-    let b0_s = w0_base as f32 * tri.inv_area;
-    let b1_s = w1_base as f32 * tri.inv_area;
-    let b2_s = w2_base as f32 * tri.inv_area;
-    let z_row_init = b0_s * tri.z0 + b1_s * tri.z1 + b2_s * tri.z2;
-    let r_row_init = (w0_base * tri.r0 + w1_base * tri.r1 + w2_base * tri.r2) / area_i64;
-    let g_row_init = (w0_base * tri.g0 + w1_base * tri.g1 + w2_base * tri.g2) / area_i64;
-    let b_row_init = (w0_base * tri.b0 + w1_base * tri.b1 + w2_base * tri.b2) / area_i64;
-
-    let mut w0_row = w0_init;
-    let mut w1_row = w1_init;
-    let mut w2_row = w2_init;
-    let mut z_row = z_row_init;
-    let mut r_row = r_row_init;
-    let mut g_row = g_row_init;
-    let mut b_row = b_row_init;
+    // Initial edge values
+    let mut w0_row = (tri.a12 as i64) * (start_x as i64) + (tri.b12 as i64) * (start_y as i64) + tri.c12;
+    let mut w1_row = (tri.a20 as i64) * (start_x as i64) + (tri.b20 as i64) * (start_y as i64) + tri.c20;
+    let mut w2_row = (tri.a01 as i64) * (start_x as i64) + (tri.b01 as i64) * (start_y as i64) + tri.c01;
 
-    let dz_dx4 = dz_dx * 4.0;
-    let dr_dx4 = dr_dx * 4;
-    let dg_dx4 = dg_dx * 4;
-    let db_dx4 = db_dx * 4;
+    // Initial attributes
+    let bary = [
+        w0_row as f32 * tri.inv_area,
+        w1_row as f32 * tri.inv_area,
+        w2_row as f32 * tri.inv_area,
+    ];
+    let lerp = |a: [f32; 3]| bary[0] * a[0] + bary[1] * a[1] + bary[2] * a[2];
+
+    let mut z_row = D::from_f32(lerp(zs));
+    let mut iw_row = lerp(iw);
+    let mut rw_row = lerp(rw);
+    let mut gw_row = lerp(gw);
+    let mut bw_row = lerp(bw);
+    let mut nx_row = lerp(nxw);
+    let mut ny_row = lerp(nyw);
+    let mut nz_row = lerp(nzw);
 
     for py in min_y..=max_y {
-        let mut w0 = w0_row.v;
-        let mut w1 = w1_row.v;
-        let mut w2 = w2_row.v;
-        // Z as float array for direct comparison
-        let mut z = [z_row, z_row + dz_dx, z_row + dz_dx * 2.0, z_row + dz_dx * 3.0];
-        let mut r = [r_row, r_row + dr_dx, r_row + dr_dx * 2, r_row + dr_dx * 3];
-        let mut g = [g_row, g_row + dg_dx, g_row + dg_dx * 2, g_row + dg_dx * 3];
-        let mut bc = [b_row, b_row + db_dx, b_row + db_dx * 2, b_row + db_dx * 3];
-
-        let mut px = aligned_min_x;
-        while px <= max_x {
-            let m0 = w0[0] | w1[0] | w2[0];
-            let m1 = w0[1] | w1[1] | w2[1];
-            let m2 = w0[2] | w1[2] | w2[2];
-            let m3 = w0[3] | w1[3] | w2[3];
+        let mut w0 = w0_row;
+        let mut w1 = w1_row;
+        let mut w2 = w2_row;
+        let mut z = z_row;
+        let mut inv_w = iw_row;
+        let mut r_w = rw_row;
+        let mut g_w = gw_row;
+        let mut b_w = bw_row;
+        let mut nx_w = nx_row;
+        let mut ny_w = ny_row;
+        let mut nz_w = nz_row;
 
-            if m0 >= 0 || m1 >= 0 || m2 >= 0 || m3 >= 0 {
+        for px in min_x..=max_x {
+            if (w0 | w1 | w2) >= 0 && inv_w > 0.0 {
                 // Separate indices: framebuffer uses pitch, z-buffer uses width
-                let fb_base = (py as usize) * fb_pitch + (px as usize);
-                let zb_base = (py as usize) * zb_width + (px as usize);
+                let fb_idx = (py as usize) * fb_pitch + (px as usize);
+                let zb_idx = (py as usize) * zb_width + (px as usize);
 
-                if px >= min_x && px <= max_x && m0 >= 0 {
-                    unsafe {
-                        let cz = *ctx.zb_ptr.add(zb_base);
-                        if z[0] > cz {
-                            *ctx.zb_ptr.add(zb_base) = z[0];
-                            *ctx.fb_ptr.add(fb_base) = rgb(
-                                ((r[0] >> COLOR_BITS) as i32).clamp(0, 255) as u8,
-                                ((g[0] >> COLOR_BITS) as i32).clamp(0, 255) as u8,
-                                ((bc[0] >> COLOR_BITS) as i32).clamp(0, 255) as u8,
-                            );
-                        }
-                    }
-                }
-                if px + 1 >= min_x && px + 1 <= max_x && m1 >= 0 {
-                    unsafe {
-                        let zb_idx = zb_base + 1;
-                        let fb_idx = fb_base + 1;
-                        let cz = *ctx.zb_ptr.add(zb_idx);
-                        if z[1] > cz {
-                            *ctx.zb_ptr.add(zb_idx) = z[1];
-                            *ctx.fb_ptr.add(fb_idx) = rgb(
-                                ((r[1] >> COLOR_BITS) as i32).clamp(0, 255) as u8,
-                                ((g[1] >> COLOR_BITS) as i32).clamp(0, 255) as u8,
-                                ((bc[1] >> COLOR_BITS) as i32).clamp(0, 255) as u8,
-                            );
-                        }
+                unsafe {
+                    let depth = D::store(z);
+                    if depth > *zb.add(zb_idx) {
+                        *zb.add(zb_idx) = depth;
+
+                        // The 1/w factor cancels when the normal is renormalized
+                        let normal = glam::Vec3::new(nx_w, ny_w, nz_w).normalize_or_zero();
+                        let lit = light.intensity(normal) / inv_w;
+                        let ri = (r_w * lit.x).clamp(0.0, 255.0) as u8;
+                        let gi = (g_w * lit.y).clamp(0.0, 255.0) as u8;
+                        let bi = (b_w * lit.z).clamp(0.0, 255.0) as u8;
+
+                        *ctx.fb_ptr.add(fb_idx) = shade_pixel::<D>(fog, ri, gi, bi, z, px, py);
                     }
                 }
-                if px + 2 >= min_x && px + 2 <= max_x && m2 >= 0 {
-                    unsafe {
-                        let zb_idx = zb_base + 2;
+            }
+
+            w0 += w0_step_x;
+            w1 += w1_step_x;
+            w2 += w2_step_x;
+            z += dz_dx;
+            inv_w += diw_dx;
+            r_w += drw_dx;
+            g_w += dgw_dx;
+            b_w += dbw_dx;
+            nx_w += dnx_dx;
+            ny_w += dny_dx;
+            nz_w += dnz_dx;
+        }
+
+        w0_row += w0_step_y;
+        w1_row += w1_step_y;
+        w2_row += w2_step_y;
+        z_row += dz_dy;
+        iw_row += diw_dy;
+        rw_row += drw_dy;
+        gw_row += dgw_dy;
+        bw_row += dbw_dy;
+        nx_row += dnx_dy;
+        ny_row += dny_dy;
+        nz_row += dnz_dy;
+    }
+}
+
+/// Textured tile-bounded rasterization
+/// Interpolates UVs and colors perspective-correctly, samples the material's
+/// texture (nearest texel, wrapping) and multiplies it by the vertex color.
+/// Lit triangles are also lit per pixel, as in the Phong path. Triangles
+/// without a texture take the lit or simple path.
+pub fn rasterize_screen_triangle_textured(
+    ctx: &RenderContext,
+    tri: &ScreenTriangle,
+    tile_min_x: i32,
+    tile_max_x: i32,
+    tile_min_y: i32,
+    tile_max_y: i32,
+) {
+    let Some(texture) = texture_for(tri) else {
+        rasterize_screen_triangle_lit(ctx, tri, tile_min_x, tile_max_x, tile_min_y, tile_max_y);
+        return;
+    };
+    let light = ctx.settings.light.filter(|_| tri.lit);
+    match ctx.depth {
+        DepthMode::Float => rasterize_textured::<FloatDepth>(ctx, tri, texture, light.as_ref(), tile_min_x, tile_max_x, tile_min_y, tile_max_y),
+        DepthMode::Integer => rasterize_textured::<IntDepth>(ctx, tri, texture, light.as_ref(), tile_min_x, tile_max_x, tile_min_y, tile_max_y),
+    }
+}
+
+fn rasterize_textured<D: DepthFormat>(
+    ctx: &RenderContext,
+    tri: &ScreenTriangle,
+    texture: &Texture,
+    light: Option<&LightState>,
+    tile_min_x: i32,
+    tile_max_x: i32,
+    tile_min_y: i32,
+    tile_max_y: i32,
+) {
+    // Use pitch for framebuffer, width for z-buffer
+    let fb_pitch = ctx.fb_pitch;
+    let zb_width = ctx.zb_width;
+    let zb = ctx.zb_ptr as *mut D::Stored;
+
+    // Clamp to tile bounds
+    let min_x = tri.min_x.max(tile_min_x);
+    let max_x = tri.max_x.min(tile_max_x);
+    let min_y = tri.min_y.max(tile_min_y);
+    let max_y = tri.max_y.min(tile_max_y);
+
+    if min_x > max_x || min_y > max_y {
+        return;
+    }
+
+    let fp_one_i64 = FP_ONE as i64;
+    let fog = ctx.fog_for(tri);
+    // Colors in 0..1 so the texel (0..255) keeps its range after modulation
+    let color_scale = 1.0 / (COLOR_ONE as f32 * 255.0);
+
+    // Attributes pre-divided by w
+    let iw = [tri.inv_w0, tri.inv_w1, tri.inv_w2];
+    let per_w = |a: [f32; 3]| [a[0] * iw[0], a[1] * iw[1], a[2] * iw[2]];
+    let rw = per_w([tri.r0 as f32, tri.r1 as f32, tri.r2 as f32].map(|c| c * color_scale));
+    let gw = per_w([tri.g0 as f32, tri.g1 as f32, tri.g2 as f32].map(|c| c * color_scale));
+    let bw = per_w([tri.b0 as f32, tri.b1 as f32, tri.b2 as f32].map(|c| c * color_scale));
+    let uw = per_w([tri.uv0[0], tri.uv1[0], tri.uv2[0]]);
+    let vw = per_w([tri.uv0[1], tri.uv1[1], tri.uv2[1]]);
+    let [n0, n1, n2] = tri.normals();
+    let nxw = per_w([n0.x, n1.x, n2.x]);
+    let nyw = per_w([n0.y, n1.y, n2.y]);
+    let nzw = per_w([n0.z, n1.z, n2.z]);
+
+    // Screen-space gradients of a per-vertex attribute
+    let grad_x = |a: [f32; 3]| {
+        (a[0] * tri.a12 as f32 + a[1] * tri.a20 as f32 + a[2] * tri.a01 as f32) * tri.inv_area * FP_ONE as f32
+    };
+    let grad_y = |a: [f32; 3]| {
+        (a[0] * tri.b12 as f32 + a[1] * tri.b20 as f32 + a[2] * tri.b01 as f32) * tri.inv_area * FP_ONE as f32
+    };
+    let zs = [tri.z0, tri.z1, tri.z2];
+    let (dz_dx, dz_dy) = (D::from_f32(grad_x(zs)), D::from_f32(grad_y(zs)));
+    let (diw_dx, diw_dy) = (grad_x(iw), grad_y(iw));
+    let (drw_dx, drw_dy) = (grad_x(rw), grad_y(rw));
+    let (dgw_dx, dgw_dy) = (grad_x(gw), grad_y(gw));
+    let (dbw_dx, dbw_dy) = (grad_x(bw), grad_y(bw));
+    let (duw_dx, duw_dy) = (grad_x(uw), grad_y(uw));
+    let (dvw_dx, dvw_dy) = (grad_x(vw), grad_y(vw));
+    let (dnx_dx, dnx_dy) = (grad_x(nxw), grad_y(nxw));
+    let (dny_dx, dny_dy) = (grad_x(nyw), grad_y(nyw));
+    let (dnz_dx, dnz_dy) = (grad_x(nzw), grad_y(nzw));
+
+    // Edge steps
+    let w0_step_x = (tri.a12 as i64) * fp_one_i64;
+    let w1_step_x = (tri.a20 as i64) * fp_one_i64;
+    let w2_step_x = (tri.a01 as i64) * fp_one_i64;
+    let w0_step_y = (tri.b12 as i64) * fp_one_i64;
+    let w1_step_y = (tri.b20 as i64) * fp_one_i64;
+    let w2_step_y = (tri.b01 as i64) * fp_one_i64;
+
+    // Starting point
+    let start_x = (min_x << FP_BITS) + FP_HALF;
+    let start_y = (min_y << FP_BITS) + FP_HALF;
+
+    // Initial edge values
+    let mut w0_row = (tri.a12 as i64) * (start_x as i64) + (tri.b12 as i64) * (start_y as i64) + tri.c12;
+    let mut w1_row = (tri.a20 as i64) * (start_x as i64) + (tri.b20 as i64) * (start_y as i64) + tri.c20;
+    let mut w2_row = (tri.a01 as i64) * (start_x as i64) + (tri.b01 as i64) * (start_y as i64) + tri.c01;
+
+    // Initial attributes
+    let bary = [
+        w0_row as f32 * tri.inv_area,
+        w1_row as f32 * tri.inv_area,
+        w2_row as f32 * tri.inv_area,
+    ];
+    let lerp = |a: [f32; 3]| bary[0] * a[0] + bary[1] * a[1] + bary[2] * a[2];
+
+    let mut z_row = D::from_f32(lerp(zs));
+    let mut iw_row = lerp(iw);
+    let mut rw_row = lerp(rw);
+    let mut gw_row = lerp(gw);
+    let mut bw_row = lerp(bw);
+    let mut uw_row = lerp(uw);
+    let mut vw_row = lerp(vw);
+    let mut nx_row = lerp(nxw);
+    let mut ny_row = lerp(nyw);
+    let mut nz_row = lerp(nzw);
+
+    for py in min_y..=max_y {
+        let mut w0 = w0_row;
+        let mut w1 = w1_row;
+        let mut w2 = w2_row;
+        let mut z = z_row;
+        let mut inv_w = iw_row;
+        let mut r_w = rw_row;
+        let mut g_w = gw_row;
+        let mut b_w = bw_row;
+        let mut u_w = uw_row;
+        let mut v_w = vw_row;
+        let mut nx_w = nx_row;
+        let mut ny_w = ny_row;
+        let mut nz_w = nz_row;
+
+        for px in min_x..=max_x {
+            if (w0 | w1 | w2) >= 0 && inv_w > 0.0 {
+                // Separate indices: framebuffer uses pitch, z-buffer uses width
+                let fb_idx = (py as usize) * fb_pitch + (px as usize);
+                let zb_idx = (py as usize) * zb_width + (px as usize);
+
+                unsafe {
+                    let depth = D::store(z);
+                    if depth > *zb.add(zb_idx) {
+                        *zb.add(zb_idx) = depth;
+
+                        let w = 1.0 / inv_w;
+                        let [tr, tg, tb, _] = texture.sample(u_w * w, v_w * w);
+                        // Light scale, with the w that undoes the color's 1/w
+                        let scale = match light {
+                            Some(light) => light.intensity(glam::Vec3::new(nx_w, ny_w, nz_w).normalize_or_zero()) * w,
+                            None => glam::Vec3::splat(w),
+                        };
+                        let ri = (r_w * scale.x * tr as f32).clamp(0.0, 255.0) as u8;
+                        let gi = (g_w * scale.y * tg as f32).clamp(0.0, 255.0) as u8;
+                        let bi = (b_w * scale.z * tb as f32).clamp(0.0, 255.0) as u8;
+
+                        *ctx.fb_ptr.add(fb_idx) = shade_pixel::<D>(fog, ri, gi, bi, z, px, py);
+                    }
+                }
+            }
+
+            w0 += w0_step_x;
+            w1 += w1_step_x;
+            w2 += w2_step_x;
+            z += dz_dx;
+            inv_w += diw_dx;
+            r_w += drw_dx;
+            g_w += dgw_dx;
+            b_w += dbw_dx;
+            u_w += duw_dx;
+            v_w += dvw_dx;
+            nx_w += dnx_dx;
+            ny_w += dny_dx;
+            nz_w += dnz_dx;
+        }
+
+        w0_row += w0_step_y;
+        w1_row += w1_step_y;
+        w2_row += w2_step_y;
+        z_row += dz_dy;
+        iw_row += diw_dy;
+        rw_row += drw_dy;
+        gw_row += dgw_dy;
+        bw_row += dbw_dy;
+        uw_row += duw_dy;
+        vw_row += dvw_dy;
+        nx_row += dnx_dy;
+        ny_row += dny_dy;
+        nz_row += dnz_dy;
+    }
+}
+
+/// Src-alpha blend of (r, g, b) over `dst`: src * alpha + dst * (1 - alpha)
+#[inline]
+fn blend_rgb(dst: u32, r: u8, g: u8, b: u8, alpha: u8) -> u32 {
+    let a = alpha as u32;
+    let mix = |src: u8, shift: u32| (src as u32 * a + ((dst >> shift) & 0xFF) * (255 - a) + 127) / 255;
+    rgb(mix(r, 16) as u8, mix(g, 8) as u8, mix(b, 0) as u8)
+}
+
+/// Shared perspective-correct rasterizer; BLEND selects the translucent path
+#[inline(always)]
+fn rasterize_perspective<const BLEND: bool, D: DepthFormat>(
+    ctx: &RenderContext,
+    tri: &ScreenTriangle,
+    tile_min_x: i32,
+    tile_max_x: i32,
+    tile_min_y: i32,
+    tile_max_y: i32,
+) {
+    // Use pitch for framebuffer, width for z-buffer
+    let fb_pitch = ctx.fb_pitch;
+    let zb_width = ctx.zb_width;
+    let zb = ctx.zb_ptr as *mut D::Stored;
+
+    // Clamp to tile bounds
+    let min_x = tri.min_x.max(tile_min_x);
+    let max_x = tri.max_x.min(tile_max_x);
+    let min_y = tri.min_y.max(tile_min_y);
+    let max_y = tri.max_y.min(tile_max_y);
+
+    if min_x > max_x || min_y > max_y {
+        return;
+    }
+
+    let fp_one_i64 = FP_ONE as i64;
+    let fog = ctx.fog_for(tri);
+    let color_scale = 1.0 / COLOR_ONE as f32;
+
+    // Attributes pre-divided by w (colors in 0..255)
+    let iw = [tri.inv_w0, tri.inv_w1, tri.inv_w2];
+    let rw = [
+        tri.r0 as f32 * color_scale * iw[0],
+        tri.r1 as f32 * color_scale * iw[1],
+        tri.r2 as f32 * color_scale * iw[2],
+    ];
+    let gw = [
+        tri.g0 as f32 * color_scale * iw[0],
+        tri.g1 as f32 * color_scale * iw[1],
+        tri.g2 as f32 * color_scale * iw[2],
+    ];
+    let bw = [
+        tri.b0 as f32 * color_scale * iw[0],
+        tri.b1 as f32 * color_scale * iw[1],
+        tri.b2 as f32 * color_scale * iw[2],
+    ];
+
+    // Screen-space gradients of a per-vertex attribute
+    let grad_x = |a: [f32; 3]| {
+        (a[0] * tri.a12 as f32 + a[1] * tri.a20 as f32 + a[2] * tri.a01 as f32) * tri.inv_area * FP_ONE as f32
+    };
+    let grad_y = |a: [f32; 3]| {
+        (a[0] * tri.b12 as f32 + a[1] * tri.b20 as f32 + a[2] * tri.b01 as f32) * tri.inv_area * FP_ONE as f32
+    };
+    let zs = [tri.z0, tri.z1, tri.z2];
+    let (dz_dx, dz_dy) = (D::from_f32(grad_x(zs)), D::from_f32(grad_y(zs)));
+    let (diw_dx, diw_dy) = (grad_x(iw), grad_y(iw));
+    let (drw_dx, drw_dy) = (grad_x(rw), grad_y(rw));
+    let (dgw_dx, dgw_dy) = (grad_x(gw), grad_y(gw));
+    let (dbw_dx, dbw_dy) = (grad_x(bw), grad_y(bw));
+
+    // Edge steps
+    let w0_step_x = (tri.a12 as i64) * fp_one_i64;
+    let w1_step_x = (tri.a20 as i64) * fp_one_i64;
+    let w2_step_x = (tri.a01 as i64) * fp_one_i64;
+    let w0_step_y = (tri.b12 as i64) * fp_one_i64;
+    let w1_step_y = (tri.b20 as i64) * fp_one_i64;
+    let w2_step_y = (tri.b01 as i64) * fp_one_i64;
+
+    // Starting point
+    let start_x = (min_x << FP_BITS) + FP_HALF;
+    let start_y = (min_y << FP_BITS) + FP_HALF;
+
+    // Initial edge values
+    let mut w0_row = (tri.a12 as i64) * (start_x as i64) + (tri.b12 as i64) * (start_y as i64) + tri.c12;
+    let mut w1_row = (tri.a20 as i64) * (start_x as i64) + (tri.b20 as i64) * (start_y as i64) + tri.c20;
+    let mut w2_row = (tri.a01 as i64) * (start_x as i64) + (tri.b01 as i64) * (start_y as i64) + tri.c01;
+
+    // Initial attributes
+    let bary = [
+        w0_row as f32 * tri.inv_area,
+        w1_row as f32 * tri.inv_area,
+        w2_row as f32 * tri.inv_area,
+    ];
+    let lerp = |a: [f32; 3]| bary[0] * a[0] + bary[1] * a[1] + bary[2] * a[2];
+
+    let mut z_row = D::from_f32(lerp(zs));
+    let mut iw_row = lerp(iw);
+    let mut rw_row = lerp(rw);
+    let mut gw_row = lerp(gw);
+    let mut bw_row = lerp(bw);
+
+    for py in min_y..=max_y {
+        let mut w0 = w0_row;
+        let mut w1 = w1_row;
+        let mut w2 = w2_row;
+        let mut z = z_row;
+        let mut inv_w = iw_row;
+        let mut r_w = rw_row;
+        let mut g_w = gw_row;
+        let mut b_w = bw_row;
+
+        for px in min_x..=max_x {
+            if (w0 | w1 | w2) >= 0 && inv_w > 0.0 {
+                // Separate indices: framebuffer uses pitch, z-buffer uses width
+                let fb_idx = (py as usize) * fb_pitch + (px as usize);
+                let zb_idx = (py as usize) * zb_width + (px as usize);
+
+                unsafe {
+                    let depth = D::store(z);
+                    if depth > *zb.add(zb_idx) {
+                        let w = 1.0 / inv_w;
+                        let ri = (r_w * w).clamp(0.0, 255.0) as u8;
+                        let gi = (g_w * w).clamp(0.0, 255.0) as u8;
+                        let bi = (b_w * w).clamp(0.0, 255.0) as u8;
+
+                        let pixel = ctx.fb_ptr.add(fb_idx);
+                        if BLEND {
+                            // Fog the source first so glass fades with its surroundings
+                            let src = shade_pixel::<D>(fog, ri, gi, bi, z, px, py);
+                            let [_, sr, sg, sb] = src.to_be_bytes();
+                            *pixel = blend_rgb(*pixel, sr, sg, sb, tri.alpha);
+                        } else {
+                            *zb.add(zb_idx) = depth;
+                            *pixel = shade_pixel::<D>(fog, ri, gi, bi, z, px, py);
+                        }
+                    }
+                }
+            }
+
+            w0 += w0_step_x;
+            w1 += w1_step_x;
+            w2 += w2_step_x;
+            z += dz_dx;
+            inv_w += diw_dx;
+            r_w += drw_dx;
+            g_w += dgw_dx;
+            b_w += dbw_dx;
+        }
+
+        w0_row += w0_step_y;
+        w1_row += w1_step_y;
+        w2_row += w2_step_y;
+        z_row += dz_dy;
+        iw_row += diw_dy;
+        rw_row += drw_dy;
+        gw_row += dgw_dy;
+        bw_row += dbw_dy;
+    }
+}
+
+/// Walk the Bresenham line from (x0, y0) to (x1, y1), calling `plot(x, y, step)`
+/// for every pixel inside the inclusive bounds
+/// Only the steps whose major coordinate lands inside the bounds are visited,
+/// so far off-screen endpoints cost nothing extra. Each pixel depends only on
+/// the endpoints (midpoint rule), so a line split across tiles joins up exactly
+#[inline]
+fn for_each_line_pixel(
+    (x0, y0): (i32, i32),
+    (x1, y1): (i32, i32),
+    (min_x, max_x, min_y, max_y): (i32, i32, i32, i32),
+    mut plot: impl FnMut(usize, usize, i64),
+) {
+    let (dx, dy) = (x1 as i64 - x0 as i64, y1 as i64 - y0 as i64);
+    let x_major = dx.abs() >= dy.abs();
+
+    // Step along the major axis; the minor axis follows the midpoint rule
+    let (major0, minor0, d_major, d_minor, major_min, major_max) = if x_major {
+        (x0 as i64, y0 as i64, dx, dy, min_x as i64, max_x as i64)
+    } else {
+        (y0 as i64, x0 as i64, dy, dx, min_y as i64, max_y as i64)
+    };
+    let steps = d_major.abs();
+    let s_major = if d_major < 0 { -1 } else { 1 };
+    let s_minor = if d_minor < 0 { -1 } else { 1 };
+
+    // Steps whose major coordinate falls inside the bounds
+    let (a, b) = ((major_min - major0) * s_major, (major_max - major0) * s_major);
+    let first = a.min(b).max(0);
+    let last = a.max(b).min(steps);
+
+    for i in first..=last {
+        let major = major0 + s_major * i;
+        let minor = if steps > 0 {
+            // i128: both factors can approach 2^32 for extreme endpoints
+            let rounded = (2 * i as i128 * d_minor.abs() as i128 + steps as i128) / (2 * steps as i128);
+            minor0 + s_minor * rounded as i64
+        } else {
+            minor0
+        };
+        let (x, y) = if x_major { (major, minor) } else { (minor, major) };
+        if x < min_x as i64 || x > max_x as i64 || y < min_y as i64 || y > max_y as i64 {
+            continue;
+        }
+        plot(x as usize, y as usize, i);
+    }
+}
+
+/// Draw a solid line between two pixel centers (no depth test)
+/// Endpoints may lie anywhere; the line is clipped to the framebuffer
+pub fn draw_line(ctx: &RenderContext, x0: i32, y0: i32, x1: i32, y1: i32, color: u32) {
+    if ctx.fb_width == 0 || ctx.fb_height == 0 {
+        return;
+    }
+    let bounds = (0, ctx.fb_width as i32 - 1, 0, ctx.fb_height as i32 - 1);
+    if ctx.screen {
+        framebuffer::mark_dirty(DirtyRect::from_bounds(x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1)));
+    }
+    for_each_line_pixel((x0, y0), (x1, y1), bounds, |x, y, _| unsafe {
+        *ctx.fb_ptr.add(y * ctx.fb_pitch + x) = color;
+    });
+}
+
+/// Draw a depth-tested line (WIREFRAME_COLOR) between two pixel centers,
+/// restricted to the tile bounds
+/// Depth is interpolated linearly in screen space (z holds 1/w, which is linear there)
+pub fn draw_line_in_tile(
+    ctx: &RenderContext,
+    a: (i32, i32, f32),
+    b: (i32, i32, f32),
+    tile_min_x: i32,
+    tile_max_x: i32,
+    tile_min_y: i32,
+    tile_max_y: i32,
+) {
+    let bounds = (tile_min_x, tile_max_x, tile_min_y, tile_max_y);
+    match ctx.depth {
+        DepthMode::Float => draw_depth_line::<FloatDepth>(ctx, a, b, bounds),
+        DepthMode::Integer => draw_depth_line::<IntDepth>(ctx, a, b, bounds),
+    }
+}
+
+fn draw_depth_line<D: DepthFormat>(
+    ctx: &RenderContext,
+    (x0, y0, z0): (i32, i32, f32),
+    (x1, y1, z1): (i32, i32, f32),
+    bounds: (i32, i32, i32, i32),
+) {
+    let steps = (x1 as i64 - x0 as i64).abs().max((y1 as i64 - y0 as i64).abs());
+    let dz = D::from_f32(if steps > 0 { (z1 - z0) / steps as f32 } else { 0.0 });
+    let z0 = D::from_f32(z0);
+    let zb = ctx.zb_ptr as *mut D::Stored;
+
+    for_each_line_pixel((x0, y0), (x1, y1), bounds, |x, y, i| {
+        let z = D::store(z0 + D::steps(dz, i));
+        let fb_idx = y * ctx.fb_pitch + x;
+        let zb_idx = y * ctx.zb_width + x;
+        unsafe {
+            if z >= *zb.add(zb_idx) {
+                *zb.add(zb_idx) = z;
+                *ctx.fb_ptr.add(fb_idx) = WIREFRAME_COLOR;
+            }
+        }
+    });
+}
+
+/// Rasterize a tile's opaque triangles in bin order with Hi-Z rejection
+///
+/// The tile's farthest depth is refreshed every HIZ_REFRESH_TRIANGLES drawn
+/// triangles; a triangle entirely behind it is skipped without touching any
+/// pixel. Front-to-back bin order fills the tile early and skips the most.
+/// Wireframe mode draws every edge instead, without Hi-Z; overdraw mode only
+/// counts writes. Textured triangles take the textured path in the shaded
+/// modes.
+/// Returns (triangles drawn, triangles skipped).
+pub fn rasterize_tile_opaque(
+    ctx: &RenderContext,
+    tris: impl Iterator<Item = ScreenTriangle>,
+    tile_min_x: i32,
+    tile_max_x: i32,
+    tile_min_y: i32,
+    tile_max_y: i32,
+) -> (usize, usize) {
+    let hiz = ctx.hiz_enabled();
+    let raster = match ctx.settings.mode {
+        RenderMode::Wireframe => draw_triangle_wireframe,
+        RenderMode::Overdraw => rasterize_screen_triangle_overdraw,
+        RenderMode::Normal | RenderMode::Tiles if ctx.settings.light.is_some() => rasterize_screen_triangle_lit,
+        RenderMode::Normal | RenderMode::Tiles => rasterize_screen_triangle_simple,
+    };
+    let textures = matches!(ctx.settings.mode, RenderMode::Normal | RenderMode::Tiles);
+    let (mut drawn, mut skipped) = (0, 0);
+    let mut far_depth = f32::NEG_INFINITY;
+    let mut since_refresh = 0;
+
+    for tri in tris {
+        if hiz && tri.nearest_depth() < far_depth {
+            skipped += 1;
+            continue;
+        }
+        if textures && tri.is_textured() {
+            rasterize_screen_triangle_textured(ctx, &tri, tile_min_x, tile_max_x, tile_min_y, tile_max_y);
+        } else {
+            raster(ctx, &tri, tile_min_x, tile_max_x, tile_min_y, tile_max_y);
+        }
+        drawn += 1;
+
+        since_refresh += 1;
+        if hiz && since_refresh == HIZ_REFRESH_TRIANGLES {
+            since_refresh = 0;
+            far_depth = ctx.farthest_depth(tile_min_x, tile_max_x, tile_min_y, tile_max_y);
+        }
+    }
+    (drawn, skipped)
+}
+
+/// Draw the three edges of a triangle (debug wireframe mode)
+pub fn draw_triangle_wireframe(
+    ctx: &RenderContext,
+    tri: &ScreenTriangle,
+    tile_min_x: i32,
+    tile_max_x: i32,
+    tile_min_y: i32,
+    tile_max_y: i32,
+) {
+    let v0 = (tri.x0 >> FP_BITS, tri.y0 >> FP_BITS, tri.z0);
+    let v1 = (tri.x1 >> FP_BITS, tri.y1 >> FP_BITS, tri.z1);
+    let v2 = (tri.x2 >> FP_BITS, tri.y2 >> FP_BITS, tri.z2);
+
+    // Vertices may lie far off screen; only the in-tile part of each edge is walked
+    for (a, b) in [(v0, v1), (v1, v2), (v2, v0)] {
+        draw_line_in_tile(ctx, a, b, tile_min_x, tile_max_x, tile_min_y, tile_max_y);
+    }
+}
+
+/// Depth-test a triangle and count each pixel it writes (debug overdraw mode)
+/// Translucent triangles are counted without writing depth, as when blended.
+pub fn rasterize_screen_triangle_overdraw(
+    ctx: &RenderContext,
+    tri: &ScreenTriangle,
+    tile_min_x: i32,
+    tile_max_x: i32,
+    tile_min_y: i32,
+    tile_max_y: i32,
+) {
+    match ctx.depth {
+        DepthMode::Float => count_overdraw::<FloatDepth>(ctx, tri, tile_min_x, tile_max_x, tile_min_y, tile_max_y),
+        DepthMode::Integer => count_overdraw::<IntDepth>(ctx, tri, tile_min_x, tile_max_x, tile_min_y, tile_max_y),
+    }
+}
+
+fn count_overdraw<D: DepthFormat>(
+    ctx: &RenderContext,
+    tri: &ScreenTriangle,
+    tile_min_x: i32,
+    tile_max_x: i32,
+    tile_min_y: i32,
+    tile_max_y: i32,
+) {
+    let min_x = tri.min_x.max(tile_min_x);
+    let max_x = tri.max_x.min(tile_max_x);
+    let min_y = tri.min_y.max(tile_min_y);
+    let max_y = tri.max_y.min(tile_max_y);
+    if ctx.overdraw_ptr.is_null() || min_x > max_x || min_y > max_y {
+        return;
+    }
+
+    // Debug only: edges and depth evaluated directly per pixel
+    let zb = ctx.zb_ptr as *mut D::Stored;
+    let write_z = !tri.is_translucent();
+    for py in min_y..=max_y {
+        let sy = ((py << FP_BITS) + FP_HALF) as i64;
+        for px in min_x..=max_x {
+            let sx = ((px << FP_BITS) + FP_HALF) as i64;
+            let w0 = tri.a12 as i64 * sx + tri.b12 as i64 * sy + tri.c12;
+            let w1 = tri.a20 as i64 * sx + tri.b20 as i64 * sy + tri.c20;
+            let w2 = tri.a01 as i64 * sx + tri.b01 as i64 * sy + tri.c01;
+            if (w0 | w1 | w2) < 0 {
+                continue;
+            }
+
+            let z = (w0 as f32 * tri.z0 + w1 as f32 * tri.z1 + w2 as f32 * tri.z2) * tri.inv_area;
+            let depth = D::store(D::from_f32(z));
+            let idx = py as usize * ctx.zb_width + px as usize;
+            unsafe {
+                if depth > *zb.add(idx) {
+                    if write_z {
+                        *zb.add(idx) = depth;
+                    }
+                    let count = ctx.overdraw_ptr.add(idx);
+                    *count = (*count).saturating_add(1);
+                }
+            }
+        }
+    }
+}
+
+/// Replace a finished tile with its debug view
+/// Overdraw shows (and resets) the tile's write counts as a heatmap; tiles
+/// tints the shaded tile by `binned` triangles. Other modes leave it alone.
+pub fn present_tile_debug(
+    ctx: &RenderContext,
+    binned: usize,
+    tile_min_x: i32,
+    tile_max_x: i32,
+    tile_min_y: i32,
+    tile_max_y: i32,
+) {
+    let overdraw = match ctx.settings.mode {
+        RenderMode::Overdraw if !ctx.overdraw_ptr.is_null() => true,
+        RenderMode::Tiles => false,
+        _ => return,
+    };
+    let tint = DEBUG_HEAT[(binned * 5).div_ceil(MAX_TRIANGLES_PER_TILE).min(5)];
+
+    for py in tile_min_y as usize..=tile_max_y as usize {
+        for px in tile_min_x as usize..=tile_max_x as usize {
+            unsafe {
+                let pixel = ctx.fb_ptr.add(py * ctx.fb_pitch + px);
+                if overdraw {
+                    let count = ctx.overdraw_ptr.add(py * ctx.zb_width + px);
+                    *pixel = DEBUG_HEAT[(*count as usize).min(5)];
+                    *count = 0;
+                } else {
+                    // 50% blend toward the tint
+                    *pixel = ((*pixel >> 1) & 0x7F7F7F) + ((tint >> 1) & 0x7F7F7F);
+                }
+            }
+        }
+    }
+}
+
+// ============================================================================
+// SIMD 4-WIDE RASTERIZATION
+// Processes 4 horizontal pixels per iteration for ~2-4x speedup
+// ============================================================================
+
+/// SIMD 4-wide pixel processing structure
+#[repr(align(16))]
+struct Simd4i64 {
+    v: [i64; 4],
+}
+
+impl Simd4i64 {
+    #[inline(always)]
+    const fn splat(val: i64) -> Self {
+        Self { v: [val, val, val, val] }
+    }
+
+    #[inline(always)]
+    const fn from_array(arr: [i64; 4]) -> Self {
+        Self { v: arr }
+    }
+
+    #[inline(always)]
+    fn add(&self, other: &Self) -> Self {
+        Self {
+            v: [
+                self.v[0].wrapping_add(other.v[0]),
+                self.v[1].wrapping_add(other.v[1]),
+                self.v[2].wrapping_add(other.v[2]),
+                self.v[3].wrapping_add(other.v[3]),
+            ],
+        }
+    }
+}
+
+/// SIMD 4-wide rasterizer - processes 4 horizontal pixels per iteration
+/// This is the optimized hot path for software rasterization
+pub fn rasterize_screen_triangle_simd4(
+    ctx: &RenderContext,
+    tri: &ScreenTriangle,
+    tile_min_x: i32,
+    tile_max_x: i32,
+    tile_min_y: i32,
+    tile_max_y: i32,
+) {
+    match ctx.depth {
+        DepthMode::Float => rasterize_simd4::<FloatDepth>(ctx, tri, tile_min_x, tile_max_x, tile_min_y, tile_max_y),
+        DepthMode::Integer => rasterize_simd4::<IntDepth>(ctx, tri, tile_min_x, tile_max_x, tile_min_y, tile_max_y),
+    }
+}
+
+fn rasterize_simd4<D: DepthFormat>(
+    ctx: &RenderContext,
+    tri: &ScreenTriangle,
+    tile_min_x: i32,
+    tile_max_x: i32,
+    tile_min_y: i32,
+    tile_max_y: i32,
+) {
+    // Use pitch for framebuffer, width for z-buffer
+    let fb_pitch = ctx.fb_pitch;
+    let zb_width = ctx.zb_width;
+    let zb = ctx.zb_ptr as *mut D::Stored;
+
+    let min_x = tri.min_x.max(tile_min_x);
+    let max_x = tri.max_x.min(tile_max_x);
+    let min_y = tri.min_y.max(tile_min_y);
+    let max_y = tri.max_y.min(tile_max_y);
+
+    if min_x > max_x || min_y > max_y {
+        return;
+    }
+
+    let aligned_min_x = min_x & !3;
+    let fp_one_i64 = FP_ONE as i64;
+    let area_i64 = (1.0 / tri.inv_area) as i64;
+    let fog = ctx.fog_for(tri);
+
+    // Z gradients in the z-buffer's format (direct comparison - no per-pixel conversion)
+    let dz_dx = D::from_f32(
+        (tri.z0 * tri.a12 as f32 + tri.z1 * tri.a20 as f32 + tri.z2 * tri.a01 as f32) * tri.inv_area * FP_ONE as f32,
+    );
+    let dz_dy = D::from_f32(
+        (tri.z0 * tri.b12 as f32 + tri.z1 * tri.b20 as f32 + tri.z2 * tri.b01 as f32) * tri.inv_area * FP_ONE as f32,
+    );
+
+    let dr_dx = ((tri.r0 * tri.a12 as i64 + tri.r1 * tri.a20 as i64 + tri.r2 * tri.a01 as i64) * fp_one_i64) / area_i64;
+    let dr_dy = ((tri.r0 * tri.b12 as i64 + tri.r1 * tri.b20 as i64 + tri.r2 * tri.b01 as i64) * fp_one_i64) / area_i64;
+    let dg_dx = ((tri.g0 * tri.a12 as i64 + tri.g1 * tri.a20 as i64 + tri.g2 * tri.a01 as i64) * fp_one_i64) / area_i64;
+    let dg_dy = ((tri.g0 * tri.b12 as i64 + tri.g1 * tri.b20 as i64 + tri.g2 * tri.b01 as i64) * fp_one_i64) / area_i64;
+    let db_dx = ((tri.b0 * tri.a12 as i64 + tri.b1 * tri.a20 as i64 + tri.b2 * tri.a01 as i64) * fp_one_i64) / area_i64;
+    let db_dy = ((tri.b0 * tri.b12 as i64 + tri.b1 * tri.b20 as i64 + tri.b2 * tri.b01 as i64) * fp_one_i64) / area_i64;
+
+    let w0_step_x4 = (tri.a12 as i64) * fp_one_i64 * 4;
+    let w1_step_x4 = (tri.a20 as i64) * fp_one_i64 * 4;
+    let w2_step_x4 = (tri.a01 as i64) * fp_one_i64 * 4;
+    let w0_step_y = (tri.b12 as i64) * fp_one_i64;
+    let w1_step_y = (tri.b20 as i64) * fp_one_i64;
+    let w2_step_y = (tri.b01 as i64) * fp_one_i64;
+    let w0_step_x1 = (tri.a12 as i64) * fp_one_i64;
+    let w1_step_x1 = (tri.a20 as i64) * fp_one_i64;
+    let w2_step_x1 = (tri.a01 as i64) * fp_one_i64;
+
+    let start_x = (aligned_min_x << FP_BITS) + FP_HALF;
+    let start_y = (min_y << FP_BITS) + FP_HALF;
+
+    let w0_base = (tri.a12 as i64) * (start_x as i64) + (tri.b12 as i64) * (start_y as i64) + tri.c12;
+    let w1_base = (tri.a20 as i64) * (start_x as i64) + (tri.b20 as i64) * (start_y as i64) + tri.c20;
+    let w2_base = (tri.a01 as i64) * (start_x as i64) + (tri.b01 as i64) * (start_y as i64) + tri.c01;
+
+    let w0_init = Simd4i64::from_array([w0_base, w0_base + w0_step_x1, w0_base + w0_step_x1 * 2, w0_base + w0_step_x1 * 3]);
+    let w1_init = Simd4i64::from_array([w1_base, w1_base + w1_step_x1, w1_base + w1_step_x1 * 2, w1_base + w1_step_x1 * 3]);
+    let w2_init = Simd4i64::from_array([w2_base, w2_base + w2_step_x1, w2_base + w2_step_x1 * 2, w2_base + w2_step_x1 * 3]);
+
+    let w0_step_y_vec = Simd4i64::splat(w0_step_y);
+    let w1_step_y_vec = Simd4i64::splat(w1_step_y);
+    let w2_step_y_vec = Simd4i64::splat(w2_step_y);
+
+    // Initial z value (matching z-buffer format)
+    let b0_s = w0_base as f32 * tri.inv_area;
+    let b1_s = w1_base as f32 * tri.inv_area;
+    let b2_s = w2_base as f32 * tri.inv_area;
+    let z_row_init = D::from_f32(b0_s * tri.z0 + b1_s * tri.z1 + b2_s * tri.z2);
+    let r_row_init = (w0_base * tri.r0 + w1_base * tri.r1 + w2_base * tri.r2) / area_i64;
+    let g_row_init = (w0_base * tri.g0 + w1_base * tri.g1 + w2_base * tri.g2) / area_i64;
+    let b_row_init = (w0_base * tri.b0 + w1_base * tri.b1 + w2_base * tri.b2) / area_i64;
+
+    let mut w0_row = w0_init;
+    let mut w1_row = w1_init;
+    let mut w2_row = w2_init;
+    let mut z_row = z_row_init;
+    let mut r_row = r_row_init;
+    let mut g_row = g_row_init;
+    let mut b_row = b_row_init;
+
+    let dz_dx4 = D::steps(dz_dx, 4);
+    let dr_dx4 = dr_dx * 4;
+    let dg_dx4 = dg_dx * 4;
+    let db_dx4 = db_dx * 4;
+
+    for py in min_y..=max_y {
+        let mut w0 = w0_row.v;
+        let mut w1 = w1_row.v;
+        let mut w2 = w2_row.v;
+        // Z lanes in the z-buffer's format for direct comparison
+        let mut z = [z_row, z_row + dz_dx, z_row + D::steps(dz_dx, 2), z_row + D::steps(dz_dx, 3)];
+        let mut r = [r_row, r_row + dr_dx, r_row + dr_dx * 2, r_row + dr_dx * 3];
+        let mut g = [g_row, g_row + dg_dx, g_row + dg_dx * 2, g_row + dg_dx * 3];
+        let mut bc = [b_row, b_row + db_dx, b_row + db_dx * 2, b_row + db_dx * 3];
+
+        let mut px = aligned_min_x;
+        while px <= max_x {
+            let m0 = w0[0] | w1[0] | w2[0];
+            let m1 = w0[1] | w1[1] | w2[1];
+            let m2 = w0[2] | w1[2] | w2[2];
+            let m3 = w0[3] | w1[3] | w2[3];
+
+            if m0 >= 0 || m1 >= 0 || m2 >= 0 || m3 >= 0 {
+                // Separate indices: framebuffer uses pitch, z-buffer uses width
+                let fb_base = (py as usize) * fb_pitch + (px as usize);
+                let zb_base = (py as usize) * zb_width + (px as usize);
+
+                if px >= min_x && px <= max_x && m0 >= 0 {
+                    unsafe {
+                        let cz = *zb.add(zb_base);
+                        let depth = D::store(z[0]);
+                        if depth > cz {
+                            *zb.add(zb_base) = depth;
+                            *ctx.fb_ptr.add(fb_base) = shade_pixel::<D>(
+                                fog,
+                                ((r[0] >> COLOR_BITS) as i32).clamp(0, 255) as u8,
+                                ((g[0] >> COLOR_BITS) as i32).clamp(0, 255) as u8,
+                                ((bc[0] >> COLOR_BITS) as i32).clamp(0, 255) as u8,
+                                z[0],
+                                px,
+                                py,
+                            );
+                        }
+                    }
+                }
+                if px + 1 >= min_x && px + 1 <= max_x && m1 >= 0 {
+                    unsafe {
+                        let zb_idx = zb_base + 1;
+                        let fb_idx = fb_base + 1;
+                        let cz = *zb.add(zb_idx);
+                        let depth = D::store(z[1]);
+                        if depth > cz {
+                            *zb.add(zb_idx) = depth;
+                            *ctx.fb_ptr.add(fb_idx) = shade_pixel::<D>(
+                                fog,
+                                ((r[1] >> COLOR_BITS) as i32).clamp(0, 255) as u8,
+                                ((g[1] >> COLOR_BITS) as i32).clamp(0, 255) as u8,
+                                ((bc[1] >> COLOR_BITS) as i32).clamp(0, 255) as u8,
+                                z[1],
+                                px + 1,
+                                py,
+                            );
+                        }
+                    }
+                }
+                if px + 2 >= min_x && px + 2 <= max_x && m2 >= 0 {
+                    unsafe {
+                        let zb_idx = zb_base + 2;
                         let fb_idx = fb_base + 2;
-                        let cz = *ctx.zb_ptr.add(zb_idx);
-                        if z[2] > cz {
-                            *ctx.zb_ptr.add(zb_idx) = z[2];
-                            *ctx.fb_ptr.add(fb_idx) = rgb(
+                        let cz = *zb.add(zb_idx);
+                        let depth = D::store(z[2]);
+                        if depth > cz {
+                            *zb.add(zb_idx) = depth;
+                            *ctx.fb_ptr.add(fb_idx) = shade_pixel::<D>(
+                                fog,
                                 ((r[2] >> COLOR_BITS) as i32).clamp(0, 255) as u8,
                                 ((g[2] >> COLOR_BITS) as i32).clamp(0, 255) as u8,
                                 ((bc[2] >> COLOR_BITS) as i32).clamp(0, 255) as u8,
+                                z[2],
+                                px + 2,
+                                py,
                             );
                         }
                     }
@@ -824,13 +2000,18 @@ pub fn rasterize_screen_triangle_simd4(
                     unsafe {
                         let zb_idx = zb_base + 3;
                         let fb_idx = fb_base + 3;
-                        let cz = *ctx.zb_ptr.add(zb_idx);
-                        if z[3] > cz {
-                            *ctx.zb_ptr.add(zb_idx) = z[3];
-                            *ctx.fb_ptr.add(fb_idx) = rgb(
+                        let cz = *zb.add(zb_idx);
+                        let depth = D::store(z[3]);
+                        if depth > cz {
+                            *zb.add(zb_idx) = depth;
+                            *ctx.fb_ptr.add(fb_idx) = shade_pixel::<D>(
+                                fog,
                                 ((r[3] >> COLOR_BITS) as i32).clamp(0, 255) as u8,
                                 ((g[3] >> COLOR_BITS) as i32).clamp(0, 255) as u8,
                                 ((bc[3] >> COLOR_BITS) as i32).clamp(0, 255) as u8,
+                                z[3],
+                                px + 3,
+                                py,
                             );
                         }
                     }
@@ -863,3 +2044,496 @@ pub fn rasterize_screen_triangle_simd4(
         b_row = b_row.wrapping_add(db_dy);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use glam::Vec3;
+
+    #[test]
+    fn test_perspective_correct_centroid_color() {
+        const SIZE: usize = 64;
+        let mut fb = vec![0u32; SIZE * SIZE];
+        let mut zb = vec![f32::NEG_INFINITY; SIZE * SIZE];
+        let ctx = RenderContext {
+            fb_ptr: fb.as_mut_ptr(),
+            fb_width: SIZE,
+            fb_height: SIZE,
+            fb_pitch: SIZE,
+            zb_ptr: zb.as_mut_ptr(),
+            zb_width: SIZE,
+            overdraw_ptr: core::ptr::null_mut(),
+            screen: false,
+            settings: RenderSettings::default(),
+            depth: DepthMode::Float,
+        };
+
+        // Steeply angled: the white apex is 10x further away (1/w = 0.1)
+        let near = Vec3::new(0.0, 0.0, 0.0);
+        let v0 = Vertex::pos_color(Vec3::new(4.5, 4.5, 1.0), near);
+        let v1 = Vertex::pos_color(Vec3::new(60.5, 4.5, 1.0), near);
+        let v2 = Vertex::pos_color(Vec3::new(32.5, 61.5, 0.1), Vec3::new(1.0, 1.0, 1.0));
+        let tri = ScreenTriangle::from_vertices(&v0, &v1, &v2, SIZE as i32, SIZE as i32).unwrap();
+        assert!(tri.perspective);
+
+        rasterize_screen_triangle_in_tile(&ctx, &tri, 0, SIZE as i32 - 1, 0, SIZE as i32 - 1);
+
+        // Centroid (32.5, 23.5) is the center of pixel (32, 23)
+        let red = ((fb[23 * SIZE + 32] >> 16) & 0xFF) as f32;
+
+        // Screen barycentrics are 1/3 each; weight by 1/w: 255 * 0.1 / 2.1
+        let expected = 255.0 * 0.1 / (1.0 + 1.0 + 0.1);
+        assert!((red - expected).abs() <= 1.0, "got {}, expected {}", red, expected);
+
+        // The affine result (255 / 3) would be far off
+        assert!((red - 85.0).abs() > 50.0);
+    }
+
+    #[test]
+    fn test_grazing_floor_matches_reference() {
+        use crate::graphics::pipeline::{transform_and_bin_fast, CullMode};
+        use alloc::vec::Vec;
+        use glam::Mat4;
+
+        const SIZE: usize = 128;
+        const BACKGROUND: u32 = 0x0000FF;
+        const FLOOR_Y: f32 = -0.5;
+        const NEAR_D: f32 = 1.0;
+        const FAR_D: f32 = 9.0;
+        const HALF_WIDTH: f32 = 4.0;
+
+        // Camera at the origin looking down -Z with a 90 degree FOV, so a pixel's
+        // view ray is (ndc_x, ndc_y, -1)
+        let mvp = Mat4::perspective_rh(core::f32::consts::FRAC_PI_2, 1.0, 0.1, 100.0);
+
+        // Floor quad shaded black (near) to white (far)
+        let black = Vec3::ZERO;
+        let white = Vec3::ONE;
+        let nl = Vertex::pos_color(Vec3::new(-HALF_WIDTH, FLOOR_Y, -NEAR_D), black);
+        let nr = Vertex::pos_color(Vec3::new(HALF_WIDTH, FLOOR_Y, -NEAR_D), black);
+        let fr = Vertex::pos_color(Vec3::new(HALF_WIDTH, FLOOR_Y, -FAR_D), white);
+        let fl = Vertex::pos_color(Vec3::new(-HALF_WIDTH, FLOOR_Y, -FAR_D), white);
+
+        let mut tris = Vec::new();
+        for (a, b, c) in [(&nl, &nr, &fr), (&nl, &fr, &fl)] {
+            tris.extend(transform_and_bin_fast(a, b, c, &mvp, SIZE as f32, SIZE as f32, CullMode::None).into_iter().flatten());
+        }
+        // The near corners sit at NDC x = +-4, past the guard band, so the quad
+        // comes back in several pieces, including thin slivers spanning most of
+        // the depth range; those must still interpolate perspective-correctly
+        assert!(tris.len() > 2);
+        assert!(tris.iter().all(|t| t.perspective));
+
+        let render = |tris: &[ScreenTriangle]| {
+            let mut fb = vec![BACKGROUND; SIZE * SIZE];
+            let mut zb = vec![f32::NEG_INFINITY; SIZE * SIZE];
+            let ctx = RenderContext {
+                fb_ptr: fb.as_mut_ptr(),
+                fb_width: SIZE,
+                fb_height: SIZE,
+                fb_pitch: SIZE,
+                zb_ptr: zb.as_mut_ptr(),
+                zb_width: SIZE,
+                overdraw_ptr: core::ptr::null_mut(),
+                screen: false,
+                settings: RenderSettings::default(),
+                depth: DepthMode::Float,
+            };
+            for tri in tris {
+                rasterize_screen_triangle_in_tile(&ctx, tri, 0, SIZE as i32 - 1, 0, SIZE as i32 - 1);
+            }
+            fb
+        };
+
+        // Largest deviation from the ray-traced reference over the floor interior
+        let max_error = |fb: &[u32]| {
+            let mut worst = 0.0f32;
+            let mut checked = 0;
+            for py in 0..SIZE {
+                for px in 0..SIZE {
+                    let ndc_x = (px as f32 + 0.5) / SIZE as f32 * 2.0 - 1.0;
+                    let ndc_y = 1.0 - (py as f32 + 0.5) / SIZE as f32 * 2.0;
+                    if ndc_y >= 0.0 {
+                        continue;
+                    }
+                    // Distance along -Z where the pixel's ray meets the floor
+                    let d = FLOOR_Y / ndc_y;
+                    let x = ndc_x * d;
+                    // Stay clear of the edges, where coverage rules decide
+                    if x.abs() > HALF_WIDTH - 0.5 || d < NEAR_D + 0.3 || d > FAR_D - 0.5 {
+                        continue;
+                    }
+                    let pixel = fb[py * SIZE + px];
+                    assert_ne!(pixel, BACKGROUND, "floor pixel ({}, {}) not drawn", px, py);
+                    let expected = 255.0 * (d - NEAR_D) / (FAR_D - NEAR_D);
+                    let red = ((pixel >> 16) & 0xFF) as f32;
+                    worst = worst.max((red - expected).abs());
+                    checked += 1;
+                }
+            }
+            assert!(checked > 500, "only {} pixels checked", checked);
+            worst
+        };
+
+        let perspective_error = max_error(&render(&tris));
+        assert!(perspective_error <= 3.0, "perspective max error {}", perspective_error);
+
+        // The affine path swims badly on the same floor
+        for tri in tris.iter_mut() {
+            tri.perspective = false;
+        }
+        let affine_error = max_error(&render(&tris));
+        assert!(affine_error > 20.0, "affine max error {}", affine_error);
+    }
+
+    /// 16x16 offscreen target for line tests
+    fn line_target() -> (alloc::vec::Vec<u32>, alloc::vec::Vec<f32>) {
+        (vec![0u32; 16 * 16], vec![f32::NEG_INFINITY; 16 * 16])
+    }
+
+    fn line_ctx(fb: &mut [u32], zb: &mut [f32]) -> RenderContext {
+        RenderContext {
+            fb_ptr: fb.as_mut_ptr(),
+            fb_width: 16,
+            fb_height: 16,
+            fb_pitch: 16,
+            zb_ptr: zb.as_mut_ptr(),
+            zb_width: 16,
+            overdraw_ptr: core::ptr::null_mut(),
+            screen: false,
+            settings: RenderSettings::default(),
+            depth: DepthMode::Float,
+        }
+    }
+
+    #[test]
+    fn test_line_shallow() {
+        let (mut fb, mut zb) = line_target();
+        let ctx = line_ctx(&mut fb, &mut zb);
+
+        // Exactly one pixel per column, endpoints included
+        draw_line_in_tile(&ctx, (1, 2, 1.0), (12, 5, 1.0), 0, 15, 0, 15);
+        for x in 1..=12 {
+            assert_eq!((0..16).filter(|&y| fb[y * 16 + x] == WIREFRAME_COLOR).count(), 1, "column {}", x);
+        }
+        assert_eq!(fb[2 * 16 + 1], WIREFRAME_COLOR);
+        assert_eq!(fb[5 * 16 + 12], WIREFRAME_COLOR);
+        assert_eq!(fb.iter().filter(|&&p| p == WIREFRAME_COLOR).count(), 12);
+    }
+
+    #[test]
+    fn test_line_steep() {
+        let (mut fb, mut zb) = line_target();
+        let ctx = line_ctx(&mut fb, &mut zb);
+
+        // Exactly one pixel per row, drawn bottom to top
+        draw_line_in_tile(&ctx, (5, 14, 1.0), (2, 1, 1.0), 0, 15, 0, 15);
+        for y in 1..=14 {
+            assert_eq!((0..16).filter(|&x| fb[y * 16 + x] == WIREFRAME_COLOR).count(), 1, "row {}", y);
+        }
+        assert_eq!(fb[14 * 16 + 5], WIREFRAME_COLOR);
+        assert_eq!(fb[16 + 2], WIREFRAME_COLOR);
+        assert_eq!(fb.iter().filter(|&&p| p == WIREFRAME_COLOR).count(), 14);
+    }
+
+    #[test]
+    fn test_line_clipped_to_tile() {
+        let (mut full, mut zb) = line_target();
+        let ctx = line_ctx(&mut full, &mut zb);
+        draw_line_in_tile(&ctx, (-5, 3, 1.0), (20, 12, 1.0), 0, 15, 0, 15);
+
+        // Same line restricted to the tile x 4..=7, y 0..=15
+        let (mut tile, mut zb) = line_target();
+        let ctx = line_ctx(&mut tile, &mut zb);
+        draw_line_in_tile(&ctx, (-5, 3, 1.0), (20, 12, 1.0), 4, 7, 0, 15);
+
+        for y in 0..16 {
+            for x in 0..16 {
+                let expected = if (4..=7).contains(&x) { full[y * 16 + x] } else { 0 };
+                assert_eq!(tile[y * 16 + x], expected, "pixel ({}, {})", x, y);
+            }
+        }
+        assert!(tile.contains(&WIREFRAME_COLOR));
+    }
+
+    /// Pixels of a 16x16 target that are set to `color`
+    fn lit_pixels(fb: &[u32], color: u32) -> alloc::vec::Vec<(usize, usize)> {
+        (0..16 * 16).filter(|&i| fb[i] == color).map(|i| (i % 16, i / 16)).collect()
+    }
+
+    #[test]
+    fn test_draw_line_axis_aligned_and_diagonal() {
+        const RED: u32 = 0x00FF_0000;
+        let (mut fb, mut zb) = line_target();
+        let ctx = line_ctx(&mut fb, &mut zb);
+
+        draw_line(&ctx, 2, 3, 9, 3, RED);
+        assert_eq!(lit_pixels(&fb, RED), (2..=9).map(|x| (x, 3)).collect::<alloc::vec::Vec<_>>());
+
+        fb.fill(0);
+        draw_line(&ctx, 4, 12, 4, 1, RED);
+        let mut vertical = lit_pixels(&fb, RED);
+        vertical.sort_by_key(|&(_, y)| y);
+        assert_eq!(vertical, (1..=12).map(|y| (4, y)).collect::<alloc::vec::Vec<_>>());
+
+        fb.fill(0);
+        draw_line(&ctx, 10, 10, 3, 3, RED);
+        let mut diagonal = lit_pixels(&fb, RED);
+        diagonal.sort();
+        assert_eq!(diagonal, (3..=10).map(|i| (i, i)).collect::<alloc::vec::Vec<_>>());
+    }
+
+    #[test]
+    fn test_draw_line_known_slope() {
+        const RED: u32 = 0x00FF_0000;
+        let (mut fb, mut zb) = line_target();
+        let ctx = line_ctx(&mut fb, &mut zb);
+
+        // y = 0.4x rounded to the nearest pixel, endpoints included
+        draw_line(&ctx, 0, 0, 5, 2, RED);
+        let mut pixels = lit_pixels(&fb, RED);
+        pixels.sort();
+        assert_eq!(pixels, [(0, 0), (1, 0), (2, 1), (3, 1), (4, 2), (5, 2)]);
+
+        // Same pixels when drawn in the opposite direction
+        fb.fill(0);
+        draw_line(&ctx, 5, 2, 0, 0, RED);
+        let mut reversed = lit_pixels(&fb, RED);
+        reversed.sort();
+        assert_eq!(reversed, pixels);
+    }
+
+    #[test]
+    fn test_draw_line_clips_out_of_bounds() {
+        const RED: u32 = 0x00FF_0000;
+        let (mut fb, mut zb) = line_target();
+        let ctx = line_ctx(&mut fb, &mut zb);
+
+        // Entirely off screen: nothing drawn, no panic
+        draw_line(&ctx, -40, -3, -2, -30, RED);
+        draw_line(&ctx, 20, 0, 90, 15, RED);
+        draw_line(&ctx, i32::MIN, i32::MIN, i32::MIN, i32::MAX, RED);
+        // Passes ~50px above the target; the in-range steps sit 2^31 along it
+        draw_line(&ctx, i32::MIN, i32::MIN, i32::MAX, i32::MAX - 100, RED);
+        assert!(lit_pixels(&fb, RED).is_empty());
+
+        // Crossing the whole target: only the visible part, one pixel per column
+        draw_line(&ctx, -1000, 8, 1000, 8, RED);
+        assert_eq!(lit_pixels(&fb, RED), (0..16).map(|x| (x, 8)).collect::<alloc::vec::Vec<_>>());
+        fb.fill(0);
+        draw_line(&ctx, -8, -8, 40, 40, RED);
+        let mut diagonal = lit_pixels(&fb, RED);
+        diagonal.sort();
+        assert_eq!(diagonal, (0..16).map(|i| (i, i)).collect::<alloc::vec::Vec<_>>());
+    }
+
+    #[test]
+    fn test_line_respects_depth() {
+        let (mut fb, mut zb) = line_target();
+        let ctx = line_ctx(&mut fb, &mut zb);
+
+        // A nearer line (larger 1/w) keeps its depth where a farther one crosses it
+        draw_line_in_tile(&ctx, (0, 8, 0.9), (15, 8, 0.9), 0, 15, 0, 15);
+        draw_line_in_tile(&ctx, (8, 0, 0.1), (8, 15, 0.1), 0, 15, 0, 15);
+        assert_eq!(zb[8 * 16 + 8], 0.9);
+        assert_eq!(zb[7 * 16 + 8], 0.1);
+    }
+
+    #[test]
+    fn test_blended_triangle_depth_tested_without_depth_write() {
+        let (mut fb, mut zb) = line_target();
+        let ctx = line_ctx(&mut fb, &mut zb);
+
+        // Opaque grey background at 1/w = 0.5, with a near occluder in the left column
+        fb.fill(rgb(100, 100, 100));
+        zb.fill(0.5);
+        for y in 0..16 {
+            zb[y * 16] = 2.0;
+        }
+
+        // Half-transparent blue quad covering the whole target at 1/w = 1.0
+        let blue = Vec3::new(0.0, 0.0, 1.0);
+        let corners = [(0.0, 0.0), (16.0, 0.0), (16.0, 16.0), (0.0, 16.0)]
+            .map(|(x, y)| Vertex::pos_color(Vec3::new(x, y, 1.0), blue));
+        for (a, b, c) in [(0, 1, 2), (0, 2, 3)] {
+            let mut tri = ScreenTriangle::from_vertices(&corners[a], &corners[b], &corners[c], 16, 16).unwrap();
+            tri.alpha = 128;
+            rasterize_screen_triangle_blended(&ctx, &tri, 0, 15, 0, 15);
+        }
+
+        // (100 * 127 + 255 * 128) / 255 for blue, 100 * 127 / 255 for red and green
+        assert_eq!(fb[5 * 16 + 10], rgb(50, 50, 178));
+        // Occluded pixels keep the background
+        assert_eq!(fb[8 * 16], rgb(100, 100, 100));
+        // Depth is untouched
+        assert_eq!(zb[5 * 16 + 10], 0.5);
+    }
+
+    #[test]
+    fn test_fog_by_depth_and_same_across_paths() {
+        let fog_color = rgb(50, 70, 100);
+        let fog = Fog::new(20.0, 40.0, fog_color);
+        let red = Vec3::new(1.0, 0.0, 0.0);
+        let quad = |inv_w: [f32; 4]| {
+            let corners = [(0.0, 0.0), (16.0, 0.0), (16.0, 16.0), (0.0, 16.0)];
+            let v: [Vertex; 4] =
+                core::array::from_fn(|i| Vertex::pos_color(Vec3::new(corners[i].0, corners[i].1, inv_w[i]), red));
+            [(0, 1, 2), (0, 2, 3)].map(|(a, b, c)| ScreenTriangle::from_vertices(&v[a], &v[b], &v[c], 16, 16).unwrap())
+        };
+        let render = |tris: &[ScreenTriangle], simd: bool| {
+            let (mut fb, mut zb) = line_target();
+            let mut ctx = line_ctx(&mut fb, &mut zb);
+            ctx.settings.fog = Some(fog);
+            for tri in tris {
+                if simd {
+                    rasterize_screen_triangle_simd4(&ctx, tri, 0, 15, 0, 15);
+                } else {
+                    rasterize_screen_triangle_simple(&ctx, tri, 0, 15, 0, 15);
+                }
+            }
+            fb
+        };
+
+        // Nearer than the fog start: untouched; beyond the end: solid fog
+        assert!(render(&quad([0.1; 4]), false).iter().all(|&p| p == rgb(255, 0, 0)));
+        assert!(render(&quad([0.01; 4]), false).iter().all(|&p| p == fog_color));
+
+        // A quad receding through the fog band renders the same on both paths
+        let receding = quad([0.1, 0.1, 0.02, 0.02]);
+        let scalar = render(&receding, false);
+        assert_eq!(scalar, render(&receding, true));
+        assert_eq!(scalar[0], rgb(255, 0, 0));
+        assert_eq!(scalar[15 * 16], fog_color);
+        assert!(scalar.iter().any(|&p| p != rgb(255, 0, 0) && p != fog_color));
+    }
+
+    #[test]
+    fn test_overdraw_counts_depth_passing_writes() {
+        let red = Vec3::new(1.0, 0.0, 0.0);
+        // Screen-space quad over columns 0..width at constant 1/w
+        let quad = |width: f32, inv_w: f32| {
+            let corners = [(0.0, 0.0), (width, 0.0), (width, 16.0), (0.0, 16.0)];
+            let v: [Vertex; 4] =
+                core::array::from_fn(|i| Vertex::pos_color(Vec3::new(corners[i].0, corners[i].1, inv_w), red));
+            [(0, 1, 2), (0, 2, 3)].map(|(a, b, c)| ScreenTriangle::from_vertices(&v[a], &v[b], &v[c], 16, 16).unwrap())
+        };
+        let (mut fb, mut zb) = line_target();
+        let mut counts = vec![0u8; 16 * 16];
+        let mut ctx = line_ctx(&mut fb, &mut zb);
+        ctx.overdraw_ptr = counts.as_mut_ptr();
+        ctx.settings.mode = RenderMode::Overdraw;
+
+        // Far full quad, then a nearer left half (counted), then a hidden right half (rejected)
+        let tris = [quad(16.0, 0.1), quad(8.0, 0.5), quad(16.0, 0.05)];
+        rasterize_tile_opaque(&ctx, tris.iter().flatten().copied(), 0, 15, 0, 15);
+        assert_eq!(counts[5 * 16 + 2], 2);
+        assert_eq!(counts[5 * 16 + 12], 1);
+
+        // Presenting shows the heatmap and leaves the counters clear for the next frame
+        present_tile_debug(&ctx, 6, 0, 15, 0, 15);
+        assert_eq!(fb[5 * 16 + 2], DEBUG_HEAT[2]);
+        assert_eq!(fb[5 * 16 + 12], DEBUG_HEAT[1]);
+        assert!(counts.iter().all(|&c| c == 0));
+    }
+
+    #[test]
+    fn test_fog_band_edges_ignore_dither() {
+        let fog = Fog::new(20.0, 40.0, rgb(50, 70, 100));
+        // Every dither threshold: untouched at the start, solid fog at the end
+        for (x, y) in (0..4).flat_map(|y| (0..4).map(move |x| (x, y))) {
+            assert_eq!(fog.apply(255, 0, 0, 1.0 / fog.start, x, y), rgb(255, 0, 0));
+            assert_eq!(fog.apply(255, 0, 0, 1.0 / fog.end, x, y), fog.color);
+        }
+    }
+
+    #[test]
+    fn test_hiz_skips_hidden_triangles_without_changing_output() {
+        // Near grey floor as a 4x4 grid of cells (32 triangles), then far triangles behind it
+        // and a few near-most ones in front of it that must still be drawn
+        let tri = |pts: [(f32, f32); 3], inv_w: f32, color: Vec3| {
+            let [a, b, c] = pts.map(|(x, y)| Vertex::pos_color(Vec3::new(x, y, inv_w), color));
+            ScreenTriangle::from_vertices(&a, &b, &c, 16, 16).unwrap()
+        };
+        let mut scene = alloc::vec::Vec::new();
+        for cy in 0..4 {
+            for cx in 0..4 {
+                let (x0, y0) = (cx as f32 * 4.0, cy as f32 * 4.0);
+                let (x1, y1) = (x0 + 4.0, y0 + 4.0);
+                let grey = Vec3::splat(0.5);
+                scene.push(tri([(x0, y0), (x1, y0), (x1, y1)], 0.5, grey));
+                scene.push(tri([(x0, y0), (x1, y1), (x0, y1)], 0.5, grey));
+            }
+        }
+        for i in 0..20 {
+            let o = (i % 10) as f32;
+            scene.push(tri([(o, 0.0), (o + 6.0, 2.0), (o, 12.0)], 0.1, Vec3::new(1.0, 0.0, 0.0)));
+        }
+        scene.push(tri([(2.0, 2.0), (12.0, 3.0), (4.0, 13.0)], 0.9, Vec3::new(0.0, 1.0, 0.0)));
+
+        let render = |hiz: bool| {
+            HIZ_ENABLED.store(hiz, Ordering::Relaxed);
+            let (mut fb, mut zb) = line_target();
+            let ctx = line_ctx(&mut fb, &mut zb);
+            let counts = rasterize_tile_opaque(&ctx, scene.iter().copied(), 0, 15, 0, 15);
+            let checksum = fb.iter().fold(0u32, |h, &p| (h ^ p).wrapping_mul(16_777_619));
+            (checksum, fb, counts)
+        };
+        let (reference, reference_fb, (drawn, skipped)) = render(false);
+        assert_eq!((drawn, skipped), (scene.len(), 0));
+
+        let (checksum, fb, (drawn, skipped)) = render(true);
+        HIZ_ENABLED.store(true, Ordering::Relaxed);
+        assert_eq!(checksum, reference);
+        assert_eq!(fb, reference_fb);
+        assert_eq!(skipped, 20);
+        assert_eq!(drawn, scene.len() - 20);
+        assert!(fb.contains(&rgb(0, 255, 0)));
+    }
+
+    #[test]
+    fn test_integer_depth_ordering_matches_float() {
+        // Overlapping, slightly slanted triangles spread over the whole depth range
+        // (near plane 0.5 to far plane 3000), each a distinct color
+        let depths = [1.9, 0.6, 0.2, 0.05, 0.01, 0.002, 0.0005];
+        let tris: alloc::vec::Vec<ScreenTriangle> = depths
+            .iter()
+            .enumerate()
+            .map(|(i, &z)| {
+                let o = i as f32 * 1.5;
+                let color = Vec3::new(i as f32 / 7.0, 1.0 - i as f32 / 7.0, 0.5);
+                let [a, b, c] = [(o, 0.0, 1.05), (16.0, o, 0.95), (16.0 - o, 16.0, 1.0)]
+                    .map(|(x, y, k)| Vertex::pos_color(Vec3::new(x, y, z * k), color));
+                ScreenTriangle::from_vertices(&a, &b, &c, 16, 16).unwrap()
+            })
+            .collect();
+
+        type Path = fn(&RenderContext, &ScreenTriangle, i32, i32, i32, i32);
+        let paths: [Path; 4] = [
+            rasterize_screen_triangle_simple,
+            rasterize_screen_triangle_in_tile,
+            rasterize_screen_triangle_simd4,
+            rasterize_screen_triangle_perspective,
+        ];
+        let render = |depth: DepthMode, path: Path, far_first: bool| {
+            let (mut fb, mut zb) = (vec![0u32; 16 * 16], vec![1.0f32; 16 * 16]);
+            let mut ctx = line_ctx(&mut fb, &mut zb);
+            ctx.depth = depth;
+            ctx.clear_zbuffer();
+            for i in 0..tris.len() {
+                let i = if far_first { tris.len() - 1 - i } else { i };
+                path(&ctx, &tris[i], 0, 15, 0, 15);
+            }
+            (fb, zb)
+        };
+
+        for path in paths {
+            for far_first in [false, true] {
+                let (float_fb, _) = render(DepthMode::Float, path, far_first);
+                let (int_fb, int_zb) = render(DepthMode::Integer, path, far_first);
+                assert_eq!(int_fb, float_fb);
+                assert!(int_zb.iter().all(|z| z.to_bits() <= super::super::zbuffer::INT_DEPTH_MAX));
+            }
+        }
+    }
+}
@@ -2,13 +2,18 @@
 
 use alloc::vec::Vec;
 use core::cell::UnsafeCell;
-use core::sync::atomic::{AtomicU16, AtomicUsize, Ordering};
+use core::sync::atomic::{AtomicBool, AtomicPtr, AtomicU16, AtomicUsize, Ordering};
+use glam::Vec3;
 use renderer::vertex::Vertex;
 use spin::Mutex;
 
-/// Tile size in pixels (64x64 fits in L1 cache)
+/// Default tile size in pixels (64x64 fits in L1 cache)
 pub const TILE_SIZE: usize = 64;
 
+/// Supported tile sizes for `init_with_size` (powers of two)
+pub const MIN_TILE_SIZE: usize = 8;
+pub const MAX_TILE_SIZE: usize = 256;
+
 /// Maximum triangles per frame
 pub const MAX_TRIANGLES_PER_FRAME: usize = 32768;
 
@@ -24,6 +29,30 @@ const FP_HALF: i32 = FP_ONE >> 1;
 const COLOR_BITS: i32 = 16;
 const COLOR_ONE: i32 = 1 << COLOR_BITS;
 
+/// Packed normal component for 1.0
+const NORMAL_ONE: f32 = i16::MAX as f32;
+
+/// Triangles covering more than this many pixels use perspective-correct
+/// interpolation (affine warping is invisible on smaller ones)
+pub const PERSPECTIVE_MIN_AREA: i64 = 512;
+
+/// ... as do smaller ones whose nearest vertex is this many times closer than
+/// the farthest (long guard-band slivers warp visibly despite their area)
+pub const PERSPECTIVE_MAX_DEPTH_RATIO: f32 = 1.5;
+
+/// Pipeline switch for perspective-correct interpolation (off = always affine)
+static PERSPECTIVE_CORRECT: AtomicBool = AtomicBool::new(true);
+
+/// Enable or disable perspective-correct interpolation for new triangles
+pub fn set_perspective_correct(enabled: bool) {
+    PERSPECTIVE_CORRECT.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether new triangles may use perspective-correct interpolation
+pub fn perspective_correct_enabled() -> bool {
+    PERSPECTIVE_CORRECT.load(Ordering::Relaxed)
+}
+
 /// Pre-computed screen-space triangle with edge coefficients (cache-line aligned)
 #[repr(C, align(64))]
 #[derive(Clone, Copy)]
@@ -67,6 +96,27 @@ pub struct ScreenTriangle {
     pub r2: i64,
     pub g2: i64,
     pub b2: i64,
+    // Per-vertex 1/w for perspective-correct attribute interpolation
+    pub inv_w0: f32,
+    pub inv_w1: f32,
+    pub inv_w2: f32,
+    // Interpolate colors perspective-correctly (large triangles only)
+    pub perspective: bool,
+    // Opacity (255 = opaque; anything less goes to the translucent bins)
+    pub alpha: u8,
+    // View-space vertex normals (components scaled by NORMAL_ONE), only set
+    // when `lit`
+    pub n0: [i16; 3],
+    pub n1: [i16; 3],
+    pub n2: [i16; 3],
+    // Colors are unlit; the rasterizer lights each pixel from the normals
+    pub lit: bool,
+    // Vertex texture coordinates, sampled when `material` is set
+    pub uv0: [f32; 2],
+    pub uv1: [f32; 2],
+    pub uv2: [f32; 2],
+    // Texture atlas material (`renderer::texture::Material` id, 0 = untextured)
+    pub material: u8,
 }
 
 impl ScreenTriangle {
@@ -142,6 +192,18 @@ impl ScreenTriangle {
         let g2 = (v2.color.y * 255.0 * COLOR_ONE as f32) as i64;
         let b2 = (v2.color.z * 255.0 * COLOR_ONE as f32) as i64;
 
+        // Doubled area is in fixed-point units (FP_ONE^2 per pixel)
+        let pixel_area = area / (2 * (FP_ONE as i64) * (FP_ONE as i64));
+
+        // Flat-shaded triangles (UI, solid fills) look the same either way
+        let flat = r0 == r1 && r1 == r2 && g0 == g1 && g1 == g2 && b0 == b1 && b1 == b2;
+        let (z_near, z_far) = (
+            v0.position.z.max(v1.position.z).max(v2.position.z),
+            v0.position.z.min(v1.position.z).min(v2.position.z),
+        );
+        let deep = z_near > z_far * PERSPECTIVE_MAX_DEPTH_RATIO;
+        let perspective = (pixel_area > PERSPECTIVE_MIN_AREA || deep) && !flat && perspective_correct_enabled();
+
         Some(Self {
             x0,
             y0,
@@ -176,9 +238,67 @@ impl ScreenTriangle {
             r2,
             g2,
             b2,
+            // transform_vertex stores 1/w in position.z
+            inv_w0: v0.position.z,
+            inv_w1: v1.position.z,
+            inv_w2: v2.position.z,
+            perspective,
+            alpha: 255,
+            n0: [0; 3],
+            n1: [0; 3],
+            n2: [0; 3],
+            lit: false,
+            uv0: v0.uv.to_array(),
+            uv1: v1.uv.to_array(),
+            uv2: v2.uv.to_array(),
+            material: 0,
+        })
+    }
+
+    /// Create a ScreenTriangle for per-pixel lighting from transformed
+    /// vertices with unlit colors and view-space normals
+    pub fn from_vertices_lit(v0: &Vertex, v1: &Vertex, v2: &Vertex, fb_width: i32, fb_height: i32) -> Option<Self> {
+        let tri = Self::from_vertices(v0, v1, v2, fb_width, fb_height)?;
+        Some(Self {
+            n0: pack_normal(v0.normal),
+            n1: pack_normal(v1.normal),
+            n2: pack_normal(v2.normal),
+            lit: true,
+            ..tri
         })
     }
 
+    /// View-space vertex normals of a lit triangle
+    #[inline]
+    pub fn normals(&self) -> [Vec3; 3] {
+        let unpack = |n: [i16; 3]| Vec3::new(n[0] as f32, n[1] as f32, n[2] as f32) / NORMAL_ONE;
+        [unpack(self.n0), unpack(self.n1), unpack(self.n2)]
+    }
+
+    /// Whether the rasterizer samples a texture for this triangle
+    #[inline]
+    pub fn is_textured(&self) -> bool {
+        self.material != 0
+    }
+
+    /// Whether the triangle is alpha-blended rather than opaque
+    #[inline]
+    pub fn is_translucent(&self) -> bool {
+        self.alpha < 255
+    }
+
+    /// Depth of the centroid as 1/w (larger is nearer)
+    #[inline]
+    pub fn centroid_depth(&self) -> f32 {
+        (self.z0 + self.z1 + self.z2) * (1.0 / 3.0)
+    }
+
+    /// Conservative nearest depth as 1/w: no covered pixel is nearer than this
+    #[inline]
+    pub fn nearest_depth(&self) -> f32 {
+        self.z0.max(self.z1).max(self.z2)
+    }
+
     /// Check if this triangle overlaps a tile
     #[inline]
     pub fn overlaps_tile(&self, tile_x: i32, tile_y: i32, tile_w: i32, tile_h: i32) -> bool {
@@ -188,6 +308,13 @@ impl ScreenTriangle {
     }
 }
 
+/// Pack a unit normal into i16 components
+#[inline]
+fn pack_normal(n: Vec3) -> [i16; 3] {
+    let pack = |c: f32| (c.clamp(-1.0, 1.0) * NORMAL_ONE) as i16;
+    [pack(n.x), pack(n.y), pack(n.z)]
+}
+
 /// Lock-free per-tile bin using atomic counter
 pub struct TileBinLockFree {
     indices: UnsafeCell<[u16; MAX_TRIANGLES_PER_TILE]>,
@@ -248,16 +375,17 @@ impl TileBinLockFree {
 }
 
 /// Lock-free triangle storage for the frame
-/// Uses UnsafeCell for lock-free writes (single producer) and reads (multiple consumers)
+/// Uses UnsafeCell for lock-free writes (every render core bins its share of
+/// the meshes) and reads (multiple consumers, once binning is done)
 pub struct TriangleStorage {
     triangles: UnsafeCell<[ScreenTriangle; MAX_TRIANGLES_PER_FRAME]>,
     count: AtomicUsize,
 }
 
 // Safety: TriangleStorage is safe to share across threads because:
-// - Writes only happen from the main thread (single producer)
+// - Each slot is claimed by exactly one producer (atomic count update)
 // - Each slot is written exactly once per frame before any reads
-// - Reads happen after the write barrier (fetch_add with AcqRel)
+// - Reads happen after the cores meet at the binning barrier
 unsafe impl Sync for TriangleStorage {}
 
 impl TriangleStorage {
@@ -275,6 +403,13 @@ impl TriangleStorage {
             r0: 0, g0: 0, b0: 0,
             r1: 0, g1: 0, b1: 0,
             r2: 0, g2: 0, b2: 0,
+            inv_w0: 0.0, inv_w1: 0.0, inv_w2: 0.0,
+            perspective: false,
+            alpha: 255,
+            n0: [0; 3], n1: [0; 3], n2: [0; 3],
+            lit: false,
+            uv0: [0.0; 2], uv1: [0.0; 2], uv2: [0.0; 2],
+            material: 0,
         };
         Self {
             triangles: UnsafeCell::new([EMPTY; MAX_TRIANGLES_PER_FRAME]),
@@ -282,14 +417,22 @@ impl TriangleStorage {
         }
     }
 
-    /// Add a triangle (lock-free, single producer)
+    /// Add a triangle (lock-free, any number of producers)
+    /// The count never moves past capacity, so concurrent overflowing adds
+    /// all fail instead of racing the count up
     #[inline]
     pub fn add(&self, tri: ScreenTriangle) -> Option<u16> {
-        let idx = self.count.fetch_add(1, Ordering::AcqRel);
-        if idx >= MAX_TRIANGLES_PER_FRAME {
-            return None;
+        let mut idx = self.count.load(Ordering::Acquire);
+        loop {
+            if idx >= MAX_TRIANGLES_PER_FRAME {
+                return None;
+            }
+            match self.count.compare_exchange_weak(idx, idx + 1, Ordering::AcqRel, Ordering::Acquire) {
+                Ok(_) => break,
+                Err(current) => idx = current,
+            }
         }
-        // Safety: idx is unique due to atomic increment, single producer
+        // Safety: idx is unique due to the atomic update
         unsafe {
             (*self.triangles.get())[idx] = tri;
         }
@@ -300,7 +443,7 @@ impl TriangleStorage {
     #[inline]
     pub fn get(&self, idx: u16) -> Option<ScreenTriangle> {
         let idx = idx as usize;
-        if idx < self.count.load(Ordering::Acquire) {
+        if idx < self.len() {
             // Safety: idx is within bounds and data was written before count update
             Some(unsafe { (*self.triangles.get())[idx] })
         } else {
@@ -308,10 +451,10 @@ impl TriangleStorage {
         }
     }
 
-    /// Get current triangle count
+    /// Get current triangle count (rejected adds past capacity are not counted)
     #[inline]
     pub fn len(&self) -> usize {
-        self.count.load(Ordering::Acquire)
+        self.count.load(Ordering::Acquire).min(MAX_TRIANGLES_PER_FRAME)
     }
 
     /// Reset for new frame
@@ -327,20 +470,91 @@ static TRIANGLE_STORAGE: TriangleStorage = TriangleStorage::new();
 /// Atomic count of triangles (for backward compatibility)
 pub static TRIANGLE_COUNT: AtomicUsize = AtomicUsize::new(0);
 
-/// Maximum number of tiles (for static allocation)
-/// 512 tiles supports up to ~1600x1200 with 64x64 tiles (25*20=500)
-const MAX_TILES: usize = 512;
+/// Tile-triangle pairs binned this frame (each triangle counts once per tile it lands in)
+static BINNED_PAIRS: AtomicUsize = AtomicUsize::new(0);
+
+/// Triangles rejected because the frame triangle buffer was full
+pub static DROPPED_TRIANGLES: AtomicUsize = AtomicUsize::new(0);
+
+/// Triangles dropped by the last completed binning pass (latched by `reset`)
+static DROPPED_LAST_FRAME: AtomicUsize = AtomicUsize::new(0);
+
+/// Tile grid for the screen and its lock-free bins (one opaque and one
+/// translucent bin per tile; translucent ones are drawn after the opaque pass)
+pub struct TileGrid {
+    pub tile_size: usize,
+    pub tiles_x: usize,
+    pub tiles_y: usize,
+    opaque: Vec<TileBinLockFree>,
+    translucent: Vec<TileBinLockFree>,
+}
+
+impl TileGrid {
+    /// Grid covering a `screen_width` x `screen_height` screen
+    /// `tile_size` is rounded up to a power of two within MIN/MAX_TILE_SIZE
+    pub fn new(screen_width: usize, screen_height: usize, tile_size: usize) -> Self {
+        let tile_size = tile_size.clamp(MIN_TILE_SIZE, MAX_TILE_SIZE).next_power_of_two();
+        let tiles_x = screen_width.div_ceil(tile_size);
+        let tiles_y = screen_height.div_ceil(tile_size);
+        let bins = || (0..tiles_x * tiles_y).map(|_| TileBinLockFree::new()).collect();
+        Self {
+            tile_size,
+            tiles_x,
+            tiles_y,
+            opaque: bins(),
+            translucent: bins(),
+        }
+    }
+
+    /// Number of tiles (and of bins of each kind)
+    pub fn tile_count(&self) -> usize {
+        self.tiles_x * self.tiles_y
+    }
+
+    /// Add a triangle to the bin of every tile it overlaps (lock-free, any core)
+    /// Returns the number of tile-triangle pairs binned
+    pub fn bin(&self, triangle_idx: u16, tri: &ScreenTriangle) -> usize {
+        let Some((tile_min_x, tile_max_x, tile_min_y, tile_max_y)) = tile_range(tri, self) else {
+            return 0; // Entirely off screen
+        };
 
-/// Lock-free triangle bins (one per tile)
-pub static TILE_BINS_LOCKFREE: [TileBinLockFree; MAX_TILES] = {
-    const INIT: TileBinLockFree = TileBinLockFree::new();
-    [INIT; MAX_TILES]
-};
+        let bins = if tri.is_translucent() { &self.translucent } else { &self.opaque };
+        let mut pairs = 0;
+        for ty in tile_min_y..=tile_max_y {
+            let row_start = ty * self.tiles_x;
+            for tx in tile_min_x..=tile_max_x {
+                if let Some(bin) = bins.get(row_start + tx) {
+                    bin.add(triangle_idx);
+                    pairs += 1;
+                }
+            }
+        }
+        pairs
+    }
+}
+
+/// Current tile grid, published by `init_with_size` and read without locking
+/// A replaced grid is leaked: other cores may still be binning into it
+static TILE_GRID: AtomicPtr<TileGrid> = AtomicPtr::new(core::ptr::null_mut());
+
+/// The current tile grid (None before `init`)
+#[inline]
+pub fn grid() -> Option<&'static TileGrid> {
+    // Safety: a published grid is never freed
+    unsafe { TILE_GRID.load(Ordering::Acquire).as_ref() }
+}
+
+/// Opaque triangle bin of a tile
+#[inline]
+pub fn opaque_bin(tile_idx: usize) -> Option<&'static TileBinLockFree> {
+    grid()?.opaque.get(tile_idx)
+}
 
 /// Initialize the frame triangle buffer (no-op for lock-free storage)
 pub fn init_triangle_buffer() {
     TRIANGLE_STORAGE.reset();
     TRIANGLE_COUNT.store(0, Ordering::Release);
+    BINNED_PAIRS.store(0, Ordering::Relaxed);
 }
 
 /// Reset triangle buffer for new frame (LOCK-FREE)
@@ -348,13 +562,18 @@ pub fn init_triangle_buffer() {
 pub fn reset_triangle_buffer() {
     TRIANGLE_STORAGE.reset();
     TRIANGLE_COUNT.store(0, Ordering::Release);
+    BINNED_PAIRS.store(0, Ordering::Relaxed);
 }
 
 /// Add a screen triangle to the frame buffer (LOCK-FREE)
-/// Returns the triangle index, or None if buffer is full
+/// Returns the triangle index, or None if buffer is full (counted in DROPPED_TRIANGLES)
 #[inline]
 pub fn add_triangle(tri: ScreenTriangle) -> Option<u16> {
-    TRIANGLE_STORAGE.add(tri)
+    let idx = TRIANGLE_STORAGE.add(tri);
+    if idx.is_none() {
+        DROPPED_TRIANGLES.fetch_add(1, Ordering::Relaxed);
+    }
+    idx
 }
 
 /// Get a triangle from the frame buffer (LOCK-FREE)
@@ -369,54 +588,90 @@ pub fn triangle_count() -> usize {
     TRIANGLE_STORAGE.len()
 }
 
-/// Clear all lock-free bins
+/// Tile-triangle pairs binned since the last reset (binning cost of the frame)
+#[inline]
+pub fn binned_pair_count() -> usize {
+    BINNED_PAIRS.load(Ordering::Relaxed)
+}
+
+/// Triangles dropped on buffer overflow in the frame last handed to `reset`
+#[inline]
+pub fn dropped_this_frame() -> usize {
+    DROPPED_LAST_FRAME.load(Ordering::Relaxed)
+}
+
+/// Clear all lock-free bins (opaque and translucent)
 pub fn clear_lockfree_bins() {
-    for bin in TILE_BINS_LOCKFREE.iter() {
-        bin.clear();
+    if let Some(grid) = grid() {
+        for bin in grid.opaque.iter().chain(grid.translucent.iter()) {
+            bin.clear();
+        }
     }
 }
 
-/// Cached tile grid dimensions (set once during init, read without locking)
-static TILE_GRID_WIDTH: AtomicUsize = AtomicUsize::new(0);
-static TILE_GRID_HEIGHT: AtomicUsize = AtomicUsize::new(0);
+/// Translucent triangles binned to a tile in back-to-front draw order
+/// Fills `order` with triangle indices and returns how many are valid
+pub fn translucent_draw_order(tile_idx: usize, order: &mut [u16; MAX_TRIANGLES_PER_TILE]) -> usize {
+    let mut keyed = [(0.0f32, 0u16); MAX_TRIANGLES_PER_TILE];
+    let mut count = 0;
+    if let Some(bin) = grid().and_then(|grid| grid.translucent.get(tile_idx)) {
+        for i in 0..bin.len() {
+            if let Some(tri_idx) = bin.get(i)
+                && let Some(tri) = get_triangle(tri_idx)
+            {
+                keyed[count] = (tri.centroid_depth(), tri_idx);
+                count += 1;
+            }
+        }
+    }
+
+    sort_back_to_front(&mut keyed[..count]);
+    for (slot, &(_, tri_idx)) in order.iter_mut().zip(&keyed[..count]) {
+        *slot = tri_idx;
+    }
+    count
+}
 
-/// Set tile grid dimensions (call once during init)
-pub fn set_tile_grid_dimensions(screen_width: usize, screen_height: usize) {
-    let tiles_x = (screen_width + TILE_SIZE - 1) / TILE_SIZE;
-    let tiles_y = (screen_height + TILE_SIZE - 1) / TILE_SIZE;
-    TILE_GRID_WIDTH.store(tiles_x, Ordering::Release);
-    TILE_GRID_HEIGHT.store(tiles_y, Ordering::Release);
+/// Sort (centroid 1/w, triangle index) pairs farthest first
+/// Ties keep submission order so overlapping tiles blend identically
+fn sort_back_to_front(keyed: &mut [(f32, u16)]) {
+    keyed.sort_unstable_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+}
+
+/// Inclusive tile range (min_x, max_x, min_y, max_y) covered by a triangle
+/// Bounds are clamped to the framebuffer first, so off-screen tiles are never
+/// touched; None if nothing of the triangle is on screen
+#[inline]
+fn tile_range(tri: &ScreenTriangle, grid: &TileGrid) -> Option<(usize, usize, usize, usize)> {
+    let (tiles_x, tiles_y, tile_size) = (grid.tiles_x, grid.tiles_y, grid.tile_size);
+    if tiles_x == 0 || tiles_y == 0 {
+        return None;
+    }
+    let max_px = (tiles_x * tile_size - 1) as i32;
+    let max_py = (tiles_y * tile_size - 1) as i32;
+    let offscreen = tri.max_x < 0 || tri.max_y < 0 || tri.min_x > max_px || tri.min_y > max_py;
+    if offscreen || tri.min_x > tri.max_x || tri.min_y > tri.max_y {
+        return None;
+    }
+
+    Some((
+        tri.min_x.max(0) as usize / tile_size,
+        tri.max_x.min(max_px) as usize / tile_size,
+        tri.min_y.max(0) as usize / tile_size,
+        tri.max_y.min(max_py) as usize / tile_size,
+    ))
 }
 
 /// Bin a triangle to appropriate tiles (TRULY lock-free version)
 /// Computes tile indices directly from triangle bounds - no mutex needed
 #[inline]
 pub fn bin_triangle_lockfree(triangle_idx: u16, tri: &ScreenTriangle) {
-    let tiles_x = TILE_GRID_WIDTH.load(Ordering::Acquire);
-    if tiles_x == 0 {
+    let Some(grid) = grid() else {
         return; // Not initialized
-    }
-
-    // Compute which tiles this triangle overlaps
-    let tile_min_x = (tri.min_x as usize) / TILE_SIZE;
-    let tile_max_x = (tri.max_x as usize) / TILE_SIZE;
-    let tile_min_y = (tri.min_y as usize) / TILE_SIZE;
-    let tile_max_y = (tri.max_y as usize) / TILE_SIZE;
-
-    // Clamp to valid tile range
-    let tile_max_x = tile_max_x.min(tiles_x - 1);
-    let tile_max_y = tile_max_y.min(TILE_GRID_HEIGHT.load(Ordering::Acquire) - 1);
+    };
 
     // Add to each overlapping tile's bin (no locking required)
-    for ty in tile_min_y..=tile_max_y {
-        let row_start = ty * tiles_x;
-        for tx in tile_min_x..=tile_max_x {
-            let tile_idx = row_start + tx;
-            if tile_idx < MAX_TILES {
-                TILE_BINS_LOCKFREE[tile_idx].add(triangle_idx);
-            }
-        }
-    }
+    BINNED_PAIRS.fetch_add(grid.bin(triangle_idx, tri), Ordering::Relaxed);
 }
 
 /// A rendering tile
@@ -472,19 +727,25 @@ pub struct TileWorkQueue {
 }
 
 impl TileWorkQueue {
-    /// Create a new work queue from screen dimensions
+    /// Create a new work queue from screen dimensions (default tile size)
     pub fn new(screen_width: usize, screen_height: usize) -> Self {
+        Self::with_tile_size(screen_width, screen_height, TILE_SIZE)
+    }
+
+    /// Create a work queue of `tile_size` tiles in row-major order
+    /// (matching TileGrid's bin indices); edge tiles are cropped to the screen
+    pub fn with_tile_size(screen_width: usize, screen_height: usize, tile_size: usize) -> Self {
         let mut tiles = Vec::new();
 
-        let tiles_x = (screen_width + TILE_SIZE - 1) / TILE_SIZE;
-        let tiles_y = (screen_height + TILE_SIZE - 1) / TILE_SIZE;
+        let tiles_x = screen_width.div_ceil(tile_size);
+        let tiles_y = screen_height.div_ceil(tile_size);
 
         for ty in 0..tiles_y {
             for tx in 0..tiles_x {
-                let x = tx * TILE_SIZE;
-                let y = ty * TILE_SIZE;
-                let width = TILE_SIZE.min(screen_width - x);
-                let height = TILE_SIZE.min(screen_height - y);
+                let x = tx * tile_size;
+                let y = ty * tile_size;
+                let width = tile_size.min(screen_width - x);
+                let height = tile_size.min(screen_height - y);
 
                 tiles.push(Tile {
                     x,
@@ -536,17 +797,26 @@ impl TileWorkQueue {
 /// Global tile work queue
 pub static TILE_QUEUE: Mutex<Option<TileWorkQueue>> = Mutex::new(None);
 
-/// Initialize the tile system
+/// Initialize the tile system with the default tile size
 pub fn init(width: usize, height: usize) {
-    *TILE_QUEUE.lock() = Some(TileWorkQueue::new(width, height));
-    // Set tile grid dimensions for lock-free binning
-    set_tile_grid_dimensions(width, height);
+    init_with_size(width, height, TILE_SIZE);
+}
+
+/// Initialize the tile system with `tile_size` x `tile_size` tiles
+/// The size is rounded up to a power of two within MIN/MAX_TILE_SIZE
+pub fn init_with_size(width: usize, height: usize, tile_size: usize) {
+    // Allocate the bins for lock-free binning, then publish the grid
+    let grid = alloc::boxed::Box::new(TileGrid::new(width, height, tile_size));
+    *TILE_QUEUE.lock() = Some(TileWorkQueue::with_tile_size(width, height, grid.tile_size));
+    TILE_GRID.store(alloc::boxed::Box::leak(grid), Ordering::Release);
     // Also initialize the triangle buffer for parallel rendering
     init_triangle_buffer();
 }
 
 /// Reset tiles for new frame
+/// Called once binning is done, so it also latches and clears the overflow count
 pub fn reset() {
+    DROPPED_LAST_FRAME.store(DROPPED_TRIANGLES.swap(0, Ordering::Relaxed), Ordering::Relaxed);
     if let Some(queue) = TILE_QUEUE.lock().as_ref() {
         queue.reset();
     }
@@ -619,3 +889,187 @@ pub fn bin_triangle(v0: &Vertex, v1: &Vertex, v2: &Vertex) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::Vec3;
+
+    fn screen_tri(points: [(f32, f32); 3], fb_width: i32, fb_height: i32) -> Option<ScreenTriangle> {
+        let [a, b, c] = points.map(|(x, y)| Vertex::pos_color(Vec3::new(x, y, 1.0), Vec3::ONE));
+        ScreenTriangle::from_vertices(&a, &b, &c, fb_width, fb_height)
+    }
+
+    #[test]
+    fn test_bounds_clamped_at_viewport_edge() {
+        let grid = TileGrid::new(256, 128, TILE_SIZE);
+
+        // Right edge lies exactly on the viewport edge (x = 256 on a 256-wide target)
+        let tri = screen_tri([(192.0, 10.0), (256.0, 10.0), (256.0, 100.0)], 256, 128).unwrap();
+        assert_eq!(tri.max_x, 255);
+        assert_eq!(tile_range(&tri, &grid), Some((3, 3, 0, 1)));
+
+        // Touching the left edge from outside the guard band's on-screen part
+        let tri = screen_tri([(-300.0, 0.0), (0.0, 0.0), (0.0, 60.0)], 256, 128).unwrap();
+        assert_eq!(tri.min_x, 0);
+        assert_eq!(tile_range(&tri, &grid), Some((0, 0, 0, 0)));
+    }
+
+    #[test]
+    fn test_offscreen_parts_never_binned() {
+        let grid = TileGrid::new(256, 128, TILE_SIZE);
+
+        // Spans far beyond every edge: binned to exactly the on-screen tiles
+        let mut tri = screen_tri([(-500.0, -500.0), (900.0, -500.0), (-500.0, 900.0)], 256, 128).unwrap();
+        assert_eq!(tile_range(&tri, &grid), Some((0, 3, 0, 1)));
+
+        // Bounds that were never clamped (e.g. built by hand) are still safe
+        tri.min_x = -1000;
+        tri.max_x = 10_000;
+        assert_eq!(tile_range(&tri, &grid), Some((0, 3, 0, 1)));
+        tri.min_x = 256;
+        assert_eq!(tile_range(&tri, &grid), None);
+        assert_eq!(tile_range(&tri, &TileGrid::new(0, 0, TILE_SIZE)), None);
+    }
+
+    #[test]
+    fn test_translucent_sorted_back_to_front() {
+        // 1/w: smaller is farther, so it must come first
+        let mut keyed = [(0.5, 0), (0.1, 1), (0.9, 2), (0.1, 3), (0.3, 4)];
+        sort_back_to_front(&mut keyed);
+        let order: [u16; 5] = keyed.map(|(_, idx)| idx);
+        assert_eq!(order, [1, 3, 4, 0, 2]);
+
+        let tri = screen_tri([(0.0, 0.0), (10.0, 0.0), (0.0, 10.0)], 64, 64).unwrap();
+        assert!(!tri.is_translucent());
+        assert!((tri.centroid_depth() - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_overflow_counts_dropped_triangles() {
+        let tri = screen_tri([(0.0, 0.0), (10.0, 0.0), (0.0, 10.0)], 64, 64).unwrap();
+        reset_triangle_buffer();
+        reset();
+
+        let accepted = (0..MAX_TRIANGLES_PER_FRAME + 5).filter(|_| add_triangle(tri).is_some()).count();
+        assert_eq!(accepted, MAX_TRIANGLES_PER_FRAME);
+        assert_eq!(triangle_count(), MAX_TRIANGLES_PER_FRAME);
+        assert_eq!(DROPPED_TRIANGLES.load(Ordering::Relaxed), 5);
+
+        // `reset` latches the frame's count and starts the next frame at zero
+        reset();
+        assert_eq!(dropped_this_frame(), 5);
+        reset_triangle_buffer();
+        reset();
+        assert_eq!(dropped_this_frame(), 0);
+    }
+
+    #[test]
+    fn test_concurrent_binning_matches_serial() {
+        extern crate std;
+
+        // Own storage: other tests use the global frame buffer
+        static STORAGE: TriangleStorage = TriangleStorage::new();
+
+        // 24 "meshes" of 40 triangles scattered over a 4x3-tile screen
+        let meshes: Vec<Vec<ScreenTriangle>> = (0..24)
+            .map(|m| {
+                (0..40)
+                    .map(|t| {
+                        let (x, y) = (((m * 7 + t * 13) % 110) as f32, ((m * 5 + t * 3) % 80) as f32);
+                        screen_tri([(x, y), (x + 20.0, y), (x, y + 15.0)], 128, 96).unwrap()
+                    })
+                    .collect()
+            })
+            .collect();
+
+        // Cores claim whole meshes in turn, as the render cores do
+        let bin_all = |cores: usize| {
+            let grid = TileGrid::new(128, 96, 32);
+            STORAGE.reset();
+            let next = AtomicUsize::new(0);
+            let pairs = AtomicUsize::new(0);
+            std::thread::scope(|s| {
+                for _ in 0..cores {
+                    s.spawn(|| {
+                        while let Some(mesh) = meshes.get(next.fetch_add(1, Ordering::Relaxed)) {
+                            for tri in mesh {
+                                let idx = STORAGE.add(*tri).unwrap();
+                                pairs.fetch_add(grid.bin(idx, tri), Ordering::Relaxed);
+                            }
+                        }
+                    });
+                }
+            });
+            let per_tile: Vec<usize> = grid.opaque.iter().map(|bin| bin.len()).collect();
+            (STORAGE.len(), pairs.into_inner(), per_tile)
+        };
+
+        let serial = bin_all(1);
+        assert_eq!(serial.0, 24 * 40);
+        assert!(serial.1 > serial.0);
+        assert_eq!(bin_all(4), serial);
+    }
+
+    #[test]
+    fn test_tile_sizes_cover_screen() {
+        // 100x70 is not a multiple of either size, so edge tiles are cropped
+        for (tile_size, tiles_x, tiles_y) in [(16, 7, 5), (32, 4, 3)] {
+            let grid = TileGrid::new(100, 70, tile_size);
+            assert_eq!((grid.tile_size, grid.tiles_x, grid.tiles_y), (tile_size, tiles_x, tiles_y));
+
+            let queue = TileWorkQueue::with_tile_size(100, 70, tile_size);
+            assert_eq!(queue.tile_count(), grid.tile_count());
+
+            // Every pixel is covered by exactly one tile
+            let mut covered = [0u8; 100 * 70];
+            for tile in queue.tiles.iter() {
+                assert!(tile.width <= tile_size && tile.height <= tile_size);
+                for y in tile.y..tile.y + tile.height {
+                    for x in tile.x..tile.x + tile.width {
+                        covered[y * 100 + x] += 1;
+                    }
+                }
+            }
+            assert!(covered.iter().all(|&c| c == 1));
+
+            // A full-screen triangle bins to every tile; bin indices match the queue
+            let tri = screen_tri([(-10.0, -10.0), (300.0, -10.0), (-10.0, 300.0)], 100, 70).unwrap();
+            assert_eq!(tile_range(&tri, &grid), Some((0, tiles_x - 1, 0, tiles_y - 1)));
+            let last = queue.get_tile(grid.tile_count() - 1).unwrap();
+            assert_eq!((last.x / tile_size, last.y / tile_size), (tiles_x - 1, tiles_y - 1));
+        }
+
+        // Sizes are snapped to supported powers of two
+        assert_eq!(TileGrid::new(100, 70, 24).tile_size, 32);
+        assert_eq!(TileGrid::new(100, 70, 1).tile_size, MIN_TILE_SIZE);
+    }
+
+    #[test]
+    fn test_lit_triangle_normals_fit_cache_lines() {
+        assert_eq!(core::mem::size_of::<ScreenTriangle>(), 256);
+
+        let mut v = [(10.0, 10.0), (60.0, 10.0), (10.0, 60.0)]
+            .map(|(x, y)| Vertex::pos_color(Vec3::new(x, y, 1.0), Vec3::ONE));
+        v[1].normal = Vec3::new(0.6, -0.8, 0.0);
+        v[2].normal = Vec3::NEG_Z;
+        let tri = ScreenTriangle::from_vertices_lit(&v[0], &v[1], &v[2], 100, 100).unwrap();
+        assert!(tri.lit);
+        for (n, expected) in tri.normals().into_iter().zip(v.iter().map(|v| v.normal)) {
+            assert!((n - expected).length() < 1e-4);
+        }
+        assert!(!screen_tri([(10.0, 10.0), (60.0, 10.0), (10.0, 60.0)], 100, 100).unwrap().lit);
+    }
+
+    #[test]
+    fn test_triangle_keeps_uvs_for_texturing() {
+        let mut v = [(10.0, 10.0), (60.0, 10.0), (10.0, 60.0)]
+            .map(|(x, y)| Vertex::pos_color(Vec3::new(x, y, 1.0), Vec3::ONE));
+        v[1].uv = glam::Vec2::new(1.0, 0.0);
+        v[2].uv = glam::Vec2::new(0.0, 2.5);
+        let tri = ScreenTriangle::from_vertices(&v[0], &v[1], &v[2], 100, 100).unwrap();
+        assert_eq!((tri.uv0, tri.uv1, tri.uv2), ([0.0, 0.0], [1.0, 0.0], [0.0, 2.5]));
+        assert!(!tri.is_textured());
+        assert!(ScreenTriangle { material: 2, ..tri }.is_textured());
+    }
+}
@@ -1,8 +1,8 @@
 //! Button UI primitive
 
-use crate::graphics::font;
-use crate::graphics::framebuffer::Framebuffer;
-use super::colors;
+use crate::graphics::font::{self, Align};
+use crate::graphics::framebuffer::{mark_dirty, DirtyRect, Framebuffer};
+use super::{colors, Rect};
 
 /// A clickable button with label
 #[derive(Debug, Clone)]
@@ -36,6 +36,7 @@ impl Button {
 
     /// Draw the button
     pub fn draw(&self, fb: &Framebuffer) {
+        mark_dirty(DirtyRect::new(self.x, self.y, self.width, self.height));
         let bg_color = if self.selected {
             colors::BUTTON_SELECTED
         } else {
@@ -63,13 +64,9 @@ impl Button {
 
         // Draw centered label
         let scale = 2;
-        let text_width = font::string_width(self.label, scale);
         let text_height = font::char_height(scale);
-
-        let text_x = self.x + (self.width.saturating_sub(text_width)) / 2;
-        let text_y = self.y + (self.height.saturating_sub(text_height)) / 2;
-
-        font::draw_string_raw(fb, text_x, text_y, self.label, colors::BUTTON_TEXT, scale);
+        let bounds = Rect::new(self.x, self.y, self.width, self.height);
+        font::draw_string_aligned(fb, bounds, self.label, Align::Center, colors::BUTTON_TEXT, scale);
 
         // Draw selection indicator (arrow) if selected
         if self.selected {
@@ -178,6 +175,7 @@ impl Toggle {
     }
 
     pub fn draw(&self, fb: &Framebuffer) {
+        mark_dirty(DirtyRect::new(self.x, self.y, self.width, self.height));
         let bg_color = if self.selected {
             colors::BUTTON_SELECTED
         } else {
@@ -240,6 +238,7 @@ impl Slider {
     }
 
     pub fn draw(&self, fb: &Framebuffer) {
+        mark_dirty(DirtyRect::new(self.x, self.y, self.width, self.height));
         let bg_color = if self.selected {
             colors::BUTTON_SELECTED
         } else {
@@ -6,7 +6,41 @@ pub mod panel;
 
 pub use button::Button;
 pub use list::PlayerList;
-pub use panel::{draw_gradient_background, draw_panel, draw_panel_raw};
+pub use panel::{draw_gradient_background, draw_panel, draw_panel_raw, FillDirection, ProgressBar};
+
+/// Screen-space rectangle for laying out UI elements
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Rect {
+    pub const fn new(x: usize, y: usize, width: usize, height: usize) -> Self {
+        Self { x, y, width, height }
+    }
+
+    /// One past the rightmost column
+    pub const fn right(&self) -> usize {
+        self.x + self.width
+    }
+
+    /// One past the bottom row
+    pub const fn bottom(&self) -> usize {
+        self.y + self.height
+    }
+
+    /// Overlapping part of two rectangles, if any
+    pub fn intersect(&self, other: &Rect) -> Option<Rect> {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = self.right().min(other.right());
+        let bottom = self.bottom().min(other.bottom());
+        (x < right && y < bottom).then(|| Rect::new(x, y, right - x, bottom - y))
+    }
+}
 
 /// Common UI colors
 pub mod colors {
@@ -1,7 +1,9 @@
 //! Panel UI primitives - backgrounds and containers
 
-use crate::graphics::framebuffer::{Framebuffer, FRAMEBUFFER};
-use super::colors;
+use crate::graphics::framebuffer::{
+    lerp_color, mark_background, mark_dirty, DirtyRect, Framebuffer, FRAMEBUFFER, GRADIENT_BACKGROUND,
+};
+use super::{colors, Rect};
 
 /// Draw a vertical gradient background
 pub fn draw_gradient_background(fb_width: usize, fb_height: usize) {
@@ -24,6 +26,13 @@ pub fn draw_gradient_background_raw(fb: &Framebuffer, fb_width: usize, fb_height
     let bot_g = ((colors::BG_BOTTOM >> 8) & 0xFF) as f32;
     let bot_b = (colors::BG_BOTTOM & 0xFF) as f32;
 
+    // The full-screen gradient is the same every frame, so it counts as a background
+    if fb_width >= fb.width && fb_height >= fb.height {
+        mark_background(GRADIENT_BACKGROUND);
+    } else {
+        mark_dirty(DirtyRect::new(0, 0, fb_width, fb_height));
+    }
+
     for y in 0..fb_height.min(fb.height) {
         let t = y as f32 / fb_height as f32;
 
@@ -51,25 +60,24 @@ pub fn draw_panel(x: usize, y: usize, width: usize, height: usize, bg_color: u32
 
 /// Draw a panel without holding the lock
 pub fn draw_panel_raw(fb: &Framebuffer, x: usize, y: usize, width: usize, height: usize, bg_color: u32) {
-    let border_color = colors::PANEL_BORDER;
     let border_width = 2;
-
-    for py in y..(y + height).min(fb.height) {
-        for px in x..(x + width).min(fb.width) {
-            let is_border = px < x + border_width
-                || px >= x + width - border_width
-                || py < y + border_width
-                || py >= y + height - border_width;
-
-            let color = if is_border { border_color } else { bg_color };
-            fb.put_pixel(px, py, color);
-        }
+    // Border color under the whole panel, then the background inside it
+    fb.fill_rect_accelerated(x, y, width, height, colors::PANEL_BORDER);
+    if width > 2 * border_width && height > 2 * border_width {
+        fb.fill_rect_accelerated(
+            x + border_width,
+            y + border_width,
+            width - 2 * border_width,
+            height - 2 * border_width,
+            bg_color,
+        );
     }
 }
 
 /// Draw a rounded panel (approximated with corner pixels)
 pub fn draw_rounded_panel_raw(fb: &Framebuffer, x: usize, y: usize, width: usize, height: usize, bg_color: u32, radius: usize) {
     let border_color = colors::PANEL_BORDER;
+    mark_dirty(DirtyRect::new(x, y, width, height));
 
     for py in y..(y + height).min(fb.height) {
         for px in x..(x + width).min(fb.width) {
@@ -126,8 +134,131 @@ pub fn draw_rounded_panel_raw(fb: &Framebuffer, x: usize, y: usize, width: usize
     }
 }
 
+/// Opacity of each drop shadow layer, out of 256
+/// Layers overlap, so the shadow is darkest next to the panel
+const SHADOW_ALPHA: u32 = 56;
+
+/// Outline and drop shadow of a blended panel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PanelBorder {
+    pub color: u32,
+    /// Outline width in pixels (0 for none); drawn opaque
+    pub width: usize,
+    /// Soft shadow below and right of the panel in pixels (0 for none, 1-2 look best)
+    pub shadow: usize,
+}
+
+impl PanelBorder {
+    /// No outline and no shadow
+    pub const NONE: PanelBorder = PanelBorder { color: 0, width: 0, shadow: 0 };
+
+    pub const fn new(color: u32, width: usize) -> Self {
+        Self { color, width, shadow: 0 }
+    }
+
+    pub const fn with_shadow(self, shadow: usize) -> Self {
+        Self { shadow, ..self }
+    }
+}
+
+/// Columns [start, end) of row `y` covered by `rect` with its corners rounded to `radius`
+fn rounded_span(rect: Rect, radius: usize, y: usize) -> Option<(usize, usize)> {
+    if y < rect.y || y >= rect.bottom() {
+        return None;
+    }
+    let radius = radius.min(rect.width / 2).min(rect.height / 2);
+    // Rows from the corner circles' centre line, as in draw_rounded_panel_raw
+    let dy = if y < rect.y + radius {
+        rect.y + radius - y
+    } else if y >= rect.bottom() - radius {
+        y + radius + 1 - rect.bottom()
+    } else {
+        0
+    };
+    let inset = radius - libm::sqrtf((radius * radius - dy * dy) as f32) as usize;
+    let (start, end) = (rect.x + inset, rect.right() - inset);
+    (start < end).then_some((start, end))
+}
+
+/// Blend a span of one row, keeping only the columns inside `clip`
+#[inline]
+fn blend_span_clipped(fb: &Framebuffer, x1: usize, x2: usize, y: usize, color: u32, alpha: u32, clip: &Rect) {
+    fb.blend_span(x1.max(clip.x), x2.min(clip.right()), y, color, alpha);
+}
+
+/// Draw a translucent panel blended over what is already in the framebuffer
+///
+/// `alpha` is the background opacity (0.0-1.0). Corners are rounded to
+/// `corner_radius`, and `border` adds an opaque outline and a soft shadow.
+pub fn draw_panel_blended(fb: &Framebuffer, rect: Rect, bg_color: u32, alpha: f32, corner_radius: usize, border: PanelBorder) {
+    let screen = Rect::new(0, 0, fb.width, fb.height);
+    draw_panel_blended_clipped(fb, rect, bg_color, alpha, corner_radius, border, screen);
+}
+
+/// Draw a blended panel, touching only pixels inside the scissor rectangle `clip`
+pub fn draw_panel_blended_clipped(
+    fb: &Framebuffer,
+    rect: Rect,
+    bg_color: u32,
+    alpha: f32,
+    corner_radius: usize,
+    border: PanelBorder,
+    clip: Rect,
+) {
+    let with_shadow = Rect::new(rect.x, rect.y, rect.width + border.shadow, rect.height + border.shadow);
+    let Some(clip) = clip.intersect(&Rect::new(0, 0, fb.width, fb.height)) else {
+        return;
+    };
+    let Some(area) = with_shadow.intersect(&clip) else {
+        return;
+    };
+    mark_dirty(DirtyRect::new(area.x, area.y, area.width, area.height));
+
+    // Shadow layers, skipping the columns the panel itself covers
+    for offset in (1..=border.shadow).rev() {
+        let shadow = Rect::new(rect.x + offset, rect.y + offset, rect.width, rect.height);
+        for y in area.y..area.bottom() {
+            let Some((x1, x2)) = rounded_span(shadow, corner_radius, y) else {
+                continue;
+            };
+            match rounded_span(rect, corner_radius, y) {
+                Some((px1, px2)) => {
+                    blend_span_clipped(fb, x1, x2.min(px1), y, colors::BLACK, SHADOW_ALPHA, &clip);
+                    blend_span_clipped(fb, x1.max(px2), x2, y, colors::BLACK, SHADOW_ALPHA, &clip);
+                }
+                None => blend_span_clipped(fb, x1, x2, y, colors::BLACK, SHADOW_ALPHA, &clip),
+            }
+        }
+    }
+
+    // Background inside the outline, outline between the outer and inner shapes
+    let alpha = (alpha.clamp(0.0, 1.0) * 256.0) as u32;
+    let bw = border.width;
+    let inner = Rect::new(
+        rect.x + bw,
+        rect.y + bw,
+        rect.width.saturating_sub(2 * bw),
+        rect.height.saturating_sub(2 * bw),
+    );
+    let inner_radius = corner_radius.saturating_sub(bw);
+    for y in area.y..area.bottom().min(rect.bottom()) {
+        let Some((x1, x2)) = rounded_span(rect, corner_radius, y) else {
+            continue;
+        };
+        match rounded_span(inner, inner_radius, y) {
+            Some((ix1, ix2)) => {
+                blend_span_clipped(fb, x1, ix1, y, border.color, 256, &clip);
+                blend_span_clipped(fb, ix1, ix2, y, bg_color, alpha, &clip);
+                blend_span_clipped(fb, ix2, x2, y, border.color, 256, &clip);
+            }
+            None => blend_span_clipped(fb, x1, x2, y, border.color, 256, &clip),
+        }
+    }
+}
+
 /// Draw a horizontal divider line
 pub fn draw_divider_raw(fb: &Framebuffer, x: usize, y: usize, width: usize, color: u32) {
+    mark_dirty(DirtyRect::new(x, y, width, 2));
     for px in x..(x + width).min(fb.width) {
         if y < fb.height {
             fb.put_pixel(px, y, color);
@@ -150,6 +281,7 @@ pub fn draw_progress_bar_raw(
     bg_color: u32,
 ) {
     let border_color = colors::PANEL_BORDER;
+    mark_dirty(DirtyRect::new(x, y, width, height));
 
     for py in y..(y + height).min(fb.height) {
         for px in x..(x + width).min(fb.width) {
@@ -174,10 +306,83 @@ pub fn draw_progress_bar_raw(
     }
 }
 
+/// Which end of a progress bar the fill grows from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FillDirection {
+    LeftToRight,
+    RightToLeft,
+}
+
+/// Progress bar whose fill color shifts from `color_low` (empty) to `color_high` (full)
+#[derive(Debug, Clone, Copy)]
+pub struct ProgressBar {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+    pub color_low: u32,
+    pub color_high: u32,
+    pub direction: FillDirection,
+}
+
+impl ProgressBar {
+    /// Draw the bar filled to `value` (0.0 to 1.0)
+    pub fn draw(&self, fb: &Framebuffer, value: f32) {
+        if self.width < 2 || self.height < 2 {
+            return;
+        }
+
+        mark_dirty(DirtyRect::new(self.x, self.y, self.width, self.height));
+        let value = value.clamp(0.0, 1.0);
+        let fill_color = self.fill_color(value);
+        let fill_width = ((self.width - 2) as f32 * value) as usize;
+        let fill_x = match self.direction {
+            FillDirection::LeftToRight => self.x + 1,
+            FillDirection::RightToLeft => self.x + self.width - 1 - fill_width,
+        };
+
+        for py in self.y..(self.y + self.height).min(fb.height) {
+            for px in self.x..(self.x + self.width).min(fb.width) {
+                let is_border = px == self.x
+                    || px == self.x + self.width - 1
+                    || py == self.y
+                    || py == self.y + self.height - 1;
+                let is_filled = px >= fill_x && px < fill_x + fill_width;
+
+                let color = if is_border {
+                    colors::PANEL_BORDER
+                } else if is_filled {
+                    fill_color
+                } else {
+                    colors::PANEL_BG
+                };
+                fb.put_pixel(px, py, color);
+            }
+        }
+    }
+
+    /// Fill color for `value`: each channel passes through the brighter of the
+    /// two endpoints at 0.5, so red -> green goes via yellow instead of brown
+    pub fn fill_color(&self, value: f32) -> u32 {
+        let mid = [16, 8, 0].iter().fold(0u32, |acc, &shift| {
+            let low = (self.color_low >> shift) & 0xFF;
+            let high = (self.color_high >> shift) & 0xFF;
+            acc | (low.max(high) << shift)
+        });
+
+        if value < 0.5 {
+            lerp_color(self.color_low, mid, value * 2.0)
+        } else {
+            lerp_color(mid, self.color_high, (value - 0.5) * 2.0)
+        }
+    }
+}
+
 /// Draw a color swatch
 pub fn draw_swatch_raw(fb: &Framebuffer, x: usize, y: usize, size: usize, color: u32, selected: bool) {
     let border_color = if selected { colors::FN_YELLOW } else { colors::PANEL_BORDER };
     let border_width = if selected { 3 } else { 2 };
+    mark_dirty(DirtyRect::new(x, y, size, size));
 
     for py in y..(y + size).min(fb.height) {
         for px in x..(x + size).min(fb.width) {
@@ -194,11 +399,7 @@ pub fn draw_swatch_raw(fb: &Framebuffer, x: usize, y: usize, size: usize, color:
 
 /// Draw a simple filled rectangle
 pub fn fill_rect_raw(fb: &Framebuffer, x: usize, y: usize, width: usize, height: usize, color: u32) {
-    for py in y..(y + height).min(fb.height) {
-        for px in x..(x + width).min(fb.width) {
-            fb.put_pixel(px, py, color);
-        }
-    }
+    fb.fill_rect_accelerated(x, y, width, height, color);
 }
 
 /// Draw a crosshair at center of screen
@@ -207,6 +408,7 @@ pub fn draw_crosshair_raw(fb: &Framebuffer, fb_width: usize, fb_height: usize, c
     let cy = fb_height / 2;
     let size = 10;
     let gap = 3;
+    mark_dirty(DirtyRect::new(cx - size - gap, cy - size - gap, 2 * (size + gap) + 1, 2 * (size + gap) + 1));
 
     // Horizontal lines
     for x in (cx - size - gap)..(cx - gap) {
@@ -232,3 +434,269 @@ pub fn draw_crosshair_raw(fb: &Framebuffer, fb_width: usize, fb_height: usize, c
         }
     }
 }
+
+/// Put a pixel if it lies inside the clip rectangle (x0, y0, x1, y1), exclusive end
+#[inline]
+fn put_pixel_clipped(fb: &Framebuffer, x: i32, y: i32, color: u32, (x0, y0, x1, y1): (usize, usize, usize, usize)) {
+    if x >= x0 as i32 && y >= y0 as i32 && x < x1 as i32 && y < y1 as i32 {
+        fb.put_pixel(x as usize, y as usize, color);
+    }
+}
+
+/// Walk one octant of a midpoint circle, calling `step(x, y)` with x >= y
+/// The other seven octants are the reflections of each step
+fn midpoint_circle(r: i32, mut step: impl FnMut(i32, i32)) {
+    let (mut x, mut y) = (r, 0);
+    let mut err = 1 - r;
+    while x >= y {
+        step(x, y);
+        y += 1;
+        if err < 0 {
+            err += 2 * y + 1;
+        } else {
+            x -= 1;
+            err += 2 * (y - x) + 1;
+        }
+    }
+}
+
+/// Draw a one-pixel circle outline (midpoint algorithm), clipped to the framebuffer
+pub fn draw_circle_outline(fb: &Framebuffer, cx: i32, cy: i32, r: i32, color: u32) {
+    draw_circle_outline_clipped(fb, cx, cy, r, color, (0, 0, fb.width, fb.height));
+}
+
+/// Draw a circle outline clipped to `clip` (x0, y0, x1, y1), exclusive end
+pub fn draw_circle_outline_clipped(fb: &Framebuffer, cx: i32, cy: i32, r: i32, color: u32, clip: (usize, usize, usize, usize)) {
+    if r < 0 {
+        return;
+    }
+    mark_dirty(DirtyRect::from_bounds(cx - r, cy - r, cx + r, cy + r));
+    midpoint_circle(r, |x, y| {
+        for (dx, dy) in [(x, y), (y, x), (-y, x), (-x, y), (-x, -y), (-y, -x), (y, -x), (x, -y)] {
+            put_pixel_clipped(fb, cx + dx, cy + dy, color, clip);
+        }
+    });
+}
+
+/// Draw a dashed circle outline clipped to `clip`: runs of `dash` pixels
+/// along each octant, alternating drawn and skipped
+pub fn draw_circle_dashed_clipped(fb: &Framebuffer, cx: i32, cy: i32, r: i32, dash: i32, color: u32, clip: (usize, usize, usize, usize)) {
+    if r < 0 {
+        return;
+    }
+    let dash = dash.max(1);
+    mark_dirty(DirtyRect::from_bounds(cx - r, cy - r, cx + r, cy + r));
+    midpoint_circle(r, |x, y| {
+        if (y / dash) % 2 != 0 {
+            return;
+        }
+        for (dx, dy) in [(x, y), (y, x), (-y, x), (-x, y), (-x, -y), (-y, -x), (y, -x), (x, -y)] {
+            put_pixel_clipped(fb, cx + dx, cy + dy, color, clip);
+        }
+    });
+}
+
+/// Draw a filled circle (spans between the midpoint outline), clipped to the framebuffer
+pub fn draw_circle_filled(fb: &Framebuffer, cx: i32, cy: i32, r: i32, color: u32) {
+    if r < 0 {
+        return;
+    }
+    let clip = (0, 0, fb.width, fb.height);
+    mark_dirty(DirtyRect::from_bounds(cx - r, cy - r, cx + r, cy + r));
+    midpoint_circle(r, |x, y| {
+        for (half, row) in [(x, cy + y), (x, cy - y), (y, cy + x), (y, cy - x)] {
+            if row < 0 || row >= fb.height as i32 {
+                continue;
+            }
+            for px in (cx - half).max(0)..=(cx + half).min(fb.width as i32 - 1) {
+                put_pixel_clipped(fb, px, row, color, clip);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::framebuffer::PixelFormat;
+
+    #[test]
+    fn test_progress_bar_health_gradient() {
+        let bar = ProgressBar {
+            x: 0,
+            y: 0,
+            width: 100,
+            height: 10,
+            color_low: 0x00FF0000,
+            color_high: 0x0000FF00,
+            direction: FillDirection::LeftToRight,
+        };
+
+        assert_eq!(bar.fill_color(0.0), 0x00FF0000);
+        assert_eq!(bar.fill_color(0.5), 0x00FFFF00);
+        assert_eq!(bar.fill_color(1.0), 0x0000FF00);
+    }
+
+    /// Framebuffer backed only by its back buffer (never presented)
+    fn mock_framebuffer(width: usize, height: usize) -> Framebuffer {
+        Framebuffer {
+            address: core::ptr::null_mut(),
+            back_buffer: alloc::vec![0; width * height],
+            width,
+            height,
+            pitch: width * 4,
+            bpp: 32,
+            format: PixelFormat::RGB,
+        }
+    }
+
+    /// Lit pixels as offsets from (cx, cy)
+    fn lit_offsets(fb: &Framebuffer, cx: i32, cy: i32) -> alloc::vec::Vec<(i32, i32)> {
+        let mut lit = alloc::vec::Vec::new();
+        for y in 0..fb.height {
+            for x in 0..fb.width {
+                if fb.get_pixel(x, y) != 0 {
+                    lit.push((x as i32 - cx, y as i32 - cy));
+                }
+            }
+        }
+        lit
+    }
+
+    #[test]
+    fn test_circle_small_radii() {
+        let fb = mock_framebuffer(8, 8);
+        draw_circle_outline(&fb, 4, 4, 0, 1);
+        assert_eq!(lit_offsets(&fb, 4, 4), [(0, 0)]);
+
+        let fb = mock_framebuffer(8, 8);
+        draw_circle_outline(&fb, 4, 4, 1, 1);
+        assert_eq!(lit_offsets(&fb, 4, 4), [(0, -1), (-1, 0), (1, 0), (0, 1)]);
+
+        // Radius 2: every outline pixel is within half a pixel of the true circle
+        let fb = mock_framebuffer(8, 8);
+        draw_circle_outline(&fb, 4, 4, 2, 1);
+        let lit = lit_offsets(&fb, 4, 4);
+        assert_eq!(lit.len(), 12);
+        for (x, y) in lit {
+            let d = libm::sqrtf((x * x + y * y) as f32);
+            assert!((d - 2.0).abs() < 0.5, "({}, {})", x, y);
+        }
+
+        // Nothing for a negative radius
+        let fb = mock_framebuffer(8, 8);
+        draw_circle_outline(&fb, 4, 4, -1, 1);
+        draw_circle_filled(&fb, 4, 4, -1, 1);
+        assert!(lit_offsets(&fb, 4, 4).is_empty());
+    }
+
+    #[test]
+    fn test_circle_octant_symmetry() {
+        let fb = mock_framebuffer(32, 32);
+        draw_circle_outline(&fb, 16, 16, 11, 1);
+        let lit = lit_offsets(&fb, 16, 16);
+        assert!(!lit.is_empty());
+        for &(x, y) in &lit {
+            for mirrored in [(y, x), (-y, x), (-x, y), (-x, -y), (-y, -x), (y, -x), (x, -y)] {
+                assert!(lit.contains(&mirrored), "({}, {}) has no mirror {:?}", x, y, mirrored);
+            }
+        }
+
+        // Dashes are a symmetric subset of the outline with gaps in it
+        let dashed = mock_framebuffer(32, 32);
+        draw_circle_dashed_clipped(&dashed, 16, 16, 11, 2, 1, (0, 0, 32, 32));
+        let dashes = lit_offsets(&dashed, 16, 16);
+        assert!(!dashes.is_empty() && dashes.len() < lit.len());
+        assert!(dashes.iter().all(|p| lit.contains(p)));
+        for &(x, y) in &dashes {
+            assert!(dashes.contains(&(-x, -y)));
+        }
+
+        // Filled disc covers its outline with no holes in any row
+        let filled = mock_framebuffer(32, 32);
+        draw_circle_filled(&filled, 16, 16, 11, 1);
+        let disc = lit_offsets(&filled, 16, 16);
+        assert!(lit.iter().all(|p| disc.contains(p)));
+        for row in -11..=11 {
+            let xs: alloc::vec::Vec<i32> = disc.iter().filter(|p| p.1 == row).map(|p| p.0).collect();
+            let (min, max) = (*xs.iter().min().unwrap(), *xs.iter().max().unwrap());
+            assert_eq!(xs.len() as i32, max - min + 1, "row {}", row);
+            assert_eq!(min, -max, "row {}", row);
+        }
+    }
+
+    #[test]
+    fn test_circle_partially_off_screen() {
+        // Reference: the whole circle on a big target
+        let full = mock_framebuffer(40, 40);
+        draw_circle_outline(&full, 20, 20, 9, 1);
+        let reference = lit_offsets(&full, 20, 20);
+
+        // Centered on the corner of a small target: only the visible quadrant is drawn
+        let fb = mock_framebuffer(12, 12);
+        draw_circle_outline(&fb, 0, 0, 9, 1);
+        let lit = lit_offsets(&fb, 0, 0);
+        let expected: alloc::vec::Vec<_> = reference.iter().copied().filter(|&(x, y)| x >= 0 && y >= 0).collect();
+        let mut sorted = lit.clone();
+        sorted.sort_by_key(|&(x, y)| (y, x));
+        assert_eq!(sorted, expected);
+
+        // Far off screen in every direction: no panic, nothing drawn
+        let fb = mock_framebuffer(12, 12);
+        draw_circle_outline(&fb, -100, 50, 9, 1);
+        draw_circle_filled(&fb, 500, -500, 30, 1);
+        draw_circle_filled(&fb, 6, 6, 100, 1);
+        assert_eq!(lit_offsets(&fb, 0, 0).len(), 144);
+
+        // Custom clip rectangle (minimap) keeps the outline inside it
+        let fb = mock_framebuffer(12, 12);
+        draw_circle_outline_clipped(&fb, 6, 6, 5, 1, (2, 2, 8, 8));
+        assert!(!lit_offsets(&fb, 0, 0).is_empty());
+        assert!(lit_offsets(&fb, 0, 0).iter().all(|&(x, y)| (2..8).contains(&x) && (2..8).contains(&y)));
+    }
+
+    #[test]
+    fn test_blended_panel_rounds_corners_and_blends() {
+        let fb = mock_framebuffer(40, 30);
+        fb.clear(0x00FF0000);
+        let rect = Rect::new(5, 5, 20, 14);
+        draw_panel_blended(&fb, rect, 0x000000FF, 0.5, 4, PanelBorder::new(0x0000FF00, 1));
+
+        // Corners outside the radius keep the background; the outline is opaque
+        assert_eq!(fb.get_pixel(5, 5), 0x00FF0000);
+        assert_eq!(fb.get_pixel(24, 18), 0x00FF0000);
+        assert_eq!(fb.get_pixel(5, 12), 0x0000FF00);
+        assert_eq!(fb.get_pixel(15, 18), 0x0000FF00);
+        // The inside is an even mix of panel and background
+        assert_eq!(fb.get_pixel(15, 12), 0x007F007F);
+        // Nothing outside the rectangle without a shadow
+        assert_eq!(fb.get_pixel(25, 12), 0x00FF0000);
+        assert_eq!(fb.get_pixel(15, 19), 0x00FF0000);
+    }
+
+    #[test]
+    fn test_blended_panel_shadow_and_clip() {
+        let fb = mock_framebuffer(40, 30);
+        fb.clear(0x00FFFFFF);
+        let rect = Rect::new(5, 5, 10, 10);
+        draw_panel_blended(&fb, rect, 0x00FFFFFF, 1.0, 0, PanelBorder::NONE.with_shadow(2));
+
+        // Shadow only below and right of the panel, darkest next to it
+        assert_eq!(fb.get_pixel(10, 10), 0x00FFFFFF);
+        assert_eq!(fb.get_pixel(4, 10), 0x00FFFFFF);
+        assert!(fb.get_pixel(15, 10) < fb.get_pixel(16, 10));
+        assert!(fb.get_pixel(16, 10) < 0x00FFFFFF);
+        assert_eq!(fb.get_pixel(17, 10), 0x00FFFFFF);
+
+        // A scissor rectangle keeps everything else untouched, even off-screen panels
+        let fb = mock_framebuffer(40, 30);
+        let clip = Rect::new(10, 10, 5, 5);
+        draw_panel_blended_clipped(&fb, Rect::new(0, 0, 60, 60), 0x00FFFFFF, 1.0, 3, PanelBorder::NONE, clip);
+        for y in 0..fb.height {
+            for x in 0..fb.width {
+                let inside = (10..15).contains(&x) && (10..15).contains(&y);
+                assert_eq!(fb.get_pixel(x, y) != 0, inside, "pixel ({x}, {y})");
+            }
+        }
+    }
+}
@@ -4,20 +4,19 @@
 //!
 //! Best practices implemented:
 //! 1. VGA vertical retrace detection via port 0x3DA (bit 3)
-//! 2. TSC-based frame timing for consistent frame rate
-//! 3. Adaptive sync that falls back to timer-based if vsync unavailable
+//! 2. VMSVGA fence sync: a frame ends once the host has processed its update
+//! 3. TSC-based frame timing (PIT-calibrated) capping the frame rate, and the
+//!    only pacing when neither of the above is available
 //! 4. Frame statistics tracking (dropped frames, FPS)
 //!
 //! Note on CPU power consumption:
-//! Currently uses spin_loop() for waiting since this kernel doesn't have
-//! interrupt handlers configured. To reduce CPU usage with HLT:
-//! 1. Set up an IDT with timer interrupt handlers (PIT or APIC timer)
-//! 2. Enable interrupts with x86_64::instructions::interrupts::enable()
-//! 3. Replace spin_loop() with cpu_halt() in sleep_us()
+//! The fence wait HLTs between polls once the PIT tick is running (see
+//! `interrupts::init_timer`). The TSC waits still use spin_loop() since a
+//! 1 ms tick is too coarse for them.
 //!
 //! Reference: OSDev Wiki - Video Signals And Timing, VGA Hardware
 
-use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use core::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
 use x86_64::instructions::port::Port;
 
 /// VGA Input Status Register 1 (read-only)
@@ -36,12 +35,15 @@ pub const TARGET_FPS: u64 = 60;
 /// Target frame time in microseconds
 pub const TARGET_FRAME_TIME_US: u64 = 1_000_000 / TARGET_FPS;
 
-/// Estimated TSC cycles per microsecond (calibrated at runtime)
-static TSC_PER_US: AtomicU64 = AtomicU64::new(2000); // Default ~2GHz
+/// Frames a fence may take before fence sync is given up for timer pacing
+const FENCE_TIMEOUT_FRAMES: u64 = 3;
 
 /// Whether VGA vsync is available (tested at init)
 static VSYNC_AVAILABLE: AtomicBool = AtomicBool::new(false);
 
+/// Whether frames can be paced by VMSVGA fences (tested at init)
+static FENCE_SYNC_AVAILABLE: AtomicBool = AtomicBool::new(false);
+
 /// Whether to use vsync (can be disabled for benchmarking)
 static VSYNC_ENABLED: AtomicBool = AtomicBool::new(true);
 
@@ -51,23 +53,41 @@ static FRAME_COUNT: AtomicU64 = AtomicU64::new(0);
 /// Dropped frame counter (frames that took too long)
 static DROPPED_FRAMES: AtomicU64 = AtomicU64::new(0);
 
+/// Frames presented over the last full second
+static CURRENT_FPS: AtomicU32 = AtomicU32::new(0);
+
 /// Read the CPU timestamp counter
 #[inline]
 fn read_tsc() -> u64 {
     unsafe { core::arch::x86_64::_rdtsc() }
 }
 
+/// TSC cycles per microsecond (calibrated against the PIT at boot)
+#[inline]
+fn tsc_per_us() -> u64 {
+    crate::drivers::pit::tsc_per_second() / 1_000_000
+}
+
 /// Execute HLT instruction to idle CPU until next interrupt
 /// This reduces power consumption compared to busy-waiting
 /// Note: Requires interrupt handlers (PIT/APIC timer) to wake up
 #[inline]
-#[allow(dead_code)]
 fn cpu_halt() {
     unsafe {
         core::arch::asm!("hlt", options(nomem, nostack, preserves_flags));
     }
 }
 
+/// Wait a little: HLT until the next PIT tick when it is running, else spin
+#[inline]
+fn idle() {
+    if crate::interrupts::timer_running() {
+        cpu_halt();
+    } else {
+        core::hint::spin_loop();
+    }
+}
+
 /// Read VGA Input Status Register 1
 #[inline]
 fn read_vga_status() -> u8 {
@@ -90,7 +110,7 @@ pub fn is_in_blanking() -> bool {
 }
 
 /// Initialize vsync subsystem
-/// Calibrates TSC frequency and tests for VGA vsync availability
+/// Tests for VGA vsync and VMSVGA fence sync availability
 pub fn init() {
     // Test if VGA status port is readable and responsive
     // Read the port multiple times to check for changing values
@@ -98,7 +118,7 @@ pub fn init() {
     let mut found_no_vretrace = false;
 
     let start = read_tsc();
-    let timeout_cycles = 100_000_000; // ~50ms at 2GHz
+    let timeout_cycles = 50_000 * tsc_per_us(); // ~50ms
 
     while read_tsc() - start < timeout_cycles {
         let status = read_vga_status();
@@ -120,10 +140,12 @@ pub fn init() {
         crate::serial_println!("VSync: VGA vertical retrace not available, using timer-based sync");
     }
 
-    // Calibrate TSC frequency using a simple delay
-    // In a real system, you'd use PIT or HPET for accurate calibration
-    // For now, assume ~2GHz which is common for QEMU
-    TSC_PER_US.store(2000, Ordering::Release);
+    // VMSVGA has no retrace to poll, but its FIFO fences report when the
+    // host has processed a screen update
+    if crate::drivers::vmsvga::VMSVGA_DEVICE.lock().has_fence_sync() {
+        FENCE_SYNC_AVAILABLE.store(true, Ordering::Release);
+        crate::serial_println!("VSync: VMSVGA fence sync available");
+    }
 
     crate::serial_println!("VSync: Initialized (target {} FPS, {}us/frame)",
         TARGET_FPS, TARGET_FRAME_TIME_US);
@@ -165,8 +187,7 @@ pub fn wait_for_blanking() {
 /// Note: Uses spin_loop since this kernel doesn't have interrupt handlers configured.
 /// In a full OS with timer interrupts, HLT could be used for better power efficiency.
 pub fn sleep_us(microseconds: u64) {
-    let tsc_per_us = TSC_PER_US.load(Ordering::Acquire);
-    let target_cycles = microseconds * tsc_per_us;
+    let target_cycles = microseconds * tsc_per_us();
     let start = read_tsc();
 
     while read_tsc() - start < target_cycles {
@@ -195,13 +216,14 @@ pub struct FrameTimer {
     tsc_per_frame: u64,
     /// Whether to use vsync (true) or uncapped (false)
     use_vsync: bool,
+    /// Whether frames wait for their VMSVGA fence (cleared on a timeout)
+    fence_sync: bool,
 }
 
 impl FrameTimer {
     /// Create a new frame timer
     pub fn new() -> Self {
-        let tsc_per_us = TSC_PER_US.load(Ordering::Acquire);
-        let tsc_per_frame = TARGET_FRAME_TIME_US * tsc_per_us;
+        let tsc_per_frame = TARGET_FRAME_TIME_US * tsc_per_us();
 
         Self {
             frame_start: read_tsc(),
@@ -210,6 +232,7 @@ impl FrameTimer {
             current_fps: 0,
             tsc_per_frame,
             use_vsync: VSYNC_ENABLED.load(Ordering::Acquire),
+            fence_sync: FENCE_SYNC_AVAILABLE.load(Ordering::Acquire),
         }
     }
 
@@ -229,12 +252,12 @@ impl FrameTimer {
         FRAME_COUNT.fetch_add(1, Ordering::Relaxed);
 
         // Calculate FPS every second
-        let tsc_per_us = TSC_PER_US.load(Ordering::Acquire);
-        let tsc_per_second = tsc_per_us * 1_000_000;
+        let tsc_per_second = crate::drivers::pit::tsc_per_second();
         let elapsed = frame_end.wrapping_sub(self.last_fps_time);
 
         if elapsed >= tsc_per_second {
             self.current_fps = self.fps_frame_count;
+            CURRENT_FPS.store(self.current_fps, Ordering::Relaxed);
             self.fps_frame_count = 0;
             self.last_fps_time = frame_end;
         }
@@ -247,9 +270,13 @@ impl FrameTimer {
             return false;
         }
 
-        // Wait for frame timing using TSC-based timing
-        // VGA vsync detection unreliable in QEMU, so use timer as primary
+        // Wait for the host to show the frame, then for the rest of the frame
+        // time; VGA vsync detection unreliable in QEMU, so the timer caps
+        // the rate on its own when there are no fences
         if self.use_vsync {
+            if self.fence_sync {
+                self.wait_for_fence();
+            }
             while read_tsc().wrapping_sub(self.frame_start) < self.tsc_per_frame {
                 core::hint::spin_loop();
             }
@@ -258,6 +285,24 @@ impl FrameTimer {
         on_time
     }
 
+    /// Wait until the last presented frame is on screen
+    ///
+    /// A host that stops processing fences turns fence sync off for good;
+    /// pacing then falls back to the timer alone.
+    fn wait_for_fence(&mut self) {
+        let start = read_tsc();
+        let timeout = self.tsc_per_frame * FENCE_TIMEOUT_FRAMES;
+        while !crate::drivers::vmsvga::VMSVGA_DEVICE.lock().frame_shown() {
+            if read_tsc().wrapping_sub(start) >= timeout {
+                self.fence_sync = false;
+                FENCE_SYNC_AVAILABLE.store(false, Ordering::Release);
+                crate::serial_println!("VSync: fence timed out, falling back to timer pacing");
+                return;
+            }
+            idle();
+        }
+    }
+
     /// Get current FPS
     pub fn fps(&self) -> u32 {
         self.current_fps
@@ -269,9 +314,10 @@ impl FrameTimer {
         VSYNC_ENABLED.store(enabled, Ordering::Release);
     }
 
-    /// Check if vsync is enabled
+    /// Check if frames are synchronized to the display (vsync enabled and
+    /// fence sync working), as opposed to paced by the timer alone
     pub fn vsync_enabled(&self) -> bool {
-        self.use_vsync
+        self.use_vsync && self.fence_sync
     }
 }
 
@@ -300,6 +346,11 @@ pub fn frame_count() -> u64 {
     FRAME_COUNT.load(Ordering::Relaxed)
 }
 
+/// Frames presented over the last full second (0 before the first second)
+pub fn current_fps() -> u32 {
+    CURRENT_FPS.load(Ordering::Relaxed)
+}
+
 /// Get total dropped frame count since init
 pub fn dropped_frames() -> u64 {
     DROPPED_FRAMES.load(Ordering::Relaxed)
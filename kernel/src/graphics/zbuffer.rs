@@ -1,12 +1,170 @@
 //! Depth buffer for 3D rendering
+//!
+//! Depth is 1/w (larger = nearer) and is stored either as f32 or, in the
+//! integer mode, as 24-bit fixed point in a u32 so the rasterizer's depth
+//! test is an integer compare. Both use the same 4-byte slots.
 
 use alloc::vec;
 use alloc::vec::Vec;
+use core::ops::{Add, AddAssign};
+use core::sync::atomic::{AtomicBool, Ordering};
 use spin::Mutex;
 
+/// Fixed-point bits of a stored integer depth (u24 in a u32)
+pub const INT_DEPTH_BITS: u32 = 24;
+
+/// Largest stored integer depth
+pub const INT_DEPTH_MAX: u32 = (1 << INT_DEPTH_BITS) - 1;
+
+/// Scale of a stored integer depth: 1/w of 2.0 (the near plane at 0.5) maps to INT_DEPTH_MAX
+const INT_DEPTH_SCALE_BITS: u32 = INT_DEPTH_BITS - 1;
+
+/// Extra fraction bits kept while interpolating, so per-pixel steps don't drift
+const INT_DEPTH_STEP_BITS: u32 = 16;
+
+/// Depth buffer storage mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DepthMode {
+    /// f32 1/w
+    Float,
+    /// 24-bit fixed-point 1/w in a u32
+    Integer,
+}
+
+impl DepthMode {
+    pub fn label(self) -> &'static str {
+        match self {
+            DepthMode::Float => "float",
+            DepthMode::Integer => "int24",
+        }
+    }
+
+    /// Bit pattern of a cleared (farthest) slot
+    #[inline]
+    pub fn clear_bits(self) -> u32 {
+        match self {
+            DepthMode::Float => f32::NEG_INFINITY.to_bits(),
+            DepthMode::Integer => 0,
+        }
+    }
+}
+
+/// Whether the z-buffer holds integer depth (see DepthMode)
+static INTEGER_DEPTH: AtomicBool = AtomicBool::new(false);
+
+/// Select the depth mode for subsequently acquired render contexts
+/// The buffer must be cleared before it is depth-tested in the new mode
+/// (every frame clears it, so switching between frames is safe)
+pub fn set_depth_mode(mode: DepthMode) {
+    INTEGER_DEPTH.store(mode == DepthMode::Integer, Ordering::Relaxed);
+}
+
+/// Current depth mode
+pub fn depth_mode() -> DepthMode {
+    if INTEGER_DEPTH.load(Ordering::Relaxed) { DepthMode::Integer } else { DepthMode::Float }
+}
+
+/// Depth representation used by the rasterizer inner loops
+///
+/// Setup converts vertex depths and gradients with `from_f32` once per
+/// triangle; the inner loops only step `Z` and compare `Stored` values.
+pub trait DepthFormat {
+    /// Interpolated depth, stepped per pixel
+    type Z: Copy + Add<Output = Self::Z> + AddAssign;
+    /// Depth as stored in a z-buffer slot (4 bytes in every format)
+    type Stored: Copy + PartialOrd;
+
+    /// Convert a 1/w depth or depth gradient
+    fn from_f32(z: f32) -> Self::Z;
+    /// Gradient times a pixel count
+    fn steps(dz: Self::Z, n: i64) -> Self::Z;
+    /// Value compared against and written to the z-buffer
+    fn store(z: Self::Z) -> Self::Stored;
+    /// Interpolated depth back as 1/w (for fog)
+    fn to_f32(z: Self::Z) -> f32;
+    /// Stored depth back as 1/w; never nearer than the depth that was stored
+    fn stored_to_f32(z: Self::Stored) -> f32;
+}
+
+/// f32 depth (DepthMode::Float)
+pub struct FloatDepth;
+
+impl DepthFormat for FloatDepth {
+    type Z = f32;
+    type Stored = f32;
+
+    #[inline(always)]
+    fn from_f32(z: f32) -> f32 {
+        z
+    }
+
+    #[inline(always)]
+    fn steps(dz: f32, n: i64) -> f32 {
+        dz * n as f32
+    }
+
+    #[inline(always)]
+    fn store(z: f32) -> f32 {
+        z
+    }
+
+    #[inline(always)]
+    fn to_f32(z: f32) -> f32 {
+        z
+    }
+
+    #[inline(always)]
+    fn stored_to_f32(z: f32) -> f32 {
+        z
+    }
+}
+
+/// 24-bit fixed-point depth (DepthMode::Integer)
+/// Interpolated with INT_DEPTH_STEP_BITS extra fraction bits in an i64
+pub struct IntDepth;
+
+impl IntDepth {
+    const Z_ONE: f32 = (1u64 << (INT_DEPTH_SCALE_BITS + INT_DEPTH_STEP_BITS)) as f32;
+    const STORED_ONE: f32 = (1u32 << INT_DEPTH_SCALE_BITS) as f32;
+}
+
+impl DepthFormat for IntDepth {
+    type Z = i64;
+    type Stored = u32;
+
+    #[inline(always)]
+    fn from_f32(z: f32) -> i64 {
+        (z * Self::Z_ONE) as i64
+    }
+
+    #[inline(always)]
+    fn steps(dz: i64, n: i64) -> i64 {
+        dz.wrapping_mul(n)
+    }
+
+    #[inline(always)]
+    fn store(z: i64) -> u32 {
+        (z >> INT_DEPTH_STEP_BITS).clamp(0, INT_DEPTH_MAX as i64) as u32
+    }
+
+    #[inline(always)]
+    fn to_f32(z: i64) -> f32 {
+        z as f32 / Self::Z_ONE
+    }
+
+    #[inline(always)]
+    fn stored_to_f32(z: u32) -> f32 {
+        z as f32 / Self::STORED_ONE
+    }
+}
+
 /// Z-buffer for depth testing
+/// Slots hold f32 depth; in DepthMode::Integer they hold u32 bit patterns
+/// and only the rasterizer's integer paths read them
 pub struct ZBuffer {
     pub data: Vec<f32>,
+    /// Per-pixel write counts for the overdraw debug view (kept zeroed between frames)
+    pub overdraw: Vec<u8>,
     pub width: usize,
     pub height: usize,
 }
@@ -16,15 +174,17 @@ impl ZBuffer {
     pub fn new(width: usize, height: usize) -> Self {
         Self {
             data: vec![f32::NEG_INFINITY; width * height],
+            overdraw: vec![0; width * height],
             width,
             height,
         }
     }
 
-    /// Clear the z-buffer
+    /// Clear the z-buffer to the farthest depth of the current mode
     pub fn clear(&mut self) {
+        let far = f32::from_bits(depth_mode().clear_bits());
         for z in &mut self.data {
-            *z = f32::NEG_INFINITY;
+            *z = far;
         }
     }
 
@@ -63,6 +223,99 @@ impl ZBuffer {
     }
 }
 
+/// Occlusion buffer size in cells; at 1024x768 each cell covers 16x16 pixels
+pub const OCCLUSION_WIDTH: usize = 64;
+pub const OCCLUSION_HEIGHT: usize = 48;
+
+/// Downsampled depth of the occluders drawn so far, for CPU visibility tests
+///
+/// Each cell holds the farthest 1/w in its block of pixels, so anything
+/// farther than a cell is certainly hidden throughout that block.
+pub struct OcclusionBuffer {
+    cells: [f32; OCCLUSION_WIDTH * OCCLUSION_HEIGHT],
+    /// Pixels per cell (0 until the first `build`)
+    cell_width: usize,
+    cell_height: usize,
+}
+
+impl OcclusionBuffer {
+    pub const fn new() -> Self {
+        Self {
+            cells: [f32::NEG_INFINITY; OCCLUSION_WIDTH * OCCLUSION_HEIGHT],
+            cell_width: 0,
+            cell_height: 0,
+        }
+    }
+
+    /// Downsample a z-buffer holding depth in the given mode
+    pub fn build(&mut self, zb: &ZBuffer, mode: DepthMode) {
+        self.cell_width = zb.width.div_ceil(OCCLUSION_WIDTH).max(1);
+        self.cell_height = zb.height.div_ceil(OCCLUSION_HEIGHT).max(1);
+        self.cells.fill(f32::INFINITY);
+
+        for y in 0..zb.height {
+            let row = &zb.data[y * zb.width..(y + 1) * zb.width];
+            let cells = &mut self.cells[(y / self.cell_height) * OCCLUSION_WIDTH..][..OCCLUSION_WIDTH];
+            for (chunk, cell) in row.chunks(self.cell_width).zip(cells.iter_mut()) {
+                let farthest = match mode {
+                    DepthMode::Float => chunk.iter().fold(f32::INFINITY, |a, &z| a.min(z)),
+                    DepthMode::Integer => chunk
+                        .iter()
+                        .map(|z| IntDepth::stored_to_f32(z.to_bits()))
+                        .fold(f32::INFINITY, f32::min),
+                };
+                *cell = cell.min(farthest);
+            }
+        }
+
+        // Cells past the screen edge cover nothing and must not hide anything
+        for (i, cell) in self.cells.iter_mut().enumerate() {
+            if (i % OCCLUSION_WIDTH) * self.cell_width >= zb.width || (i / OCCLUSION_WIDTH) * self.cell_height >= zb.height {
+                *cell = f32::NEG_INFINITY;
+            }
+        }
+    }
+
+    /// Whether anything at depth `z` (1/w) inside the inclusive pixel rectangle
+    /// (min_x, min_y, max_x, max_y) might be visible
+    ///
+    /// False only if every cell overlapping the rectangle is nearer than `z`.
+    pub fn is_visible(&self, screen_aabb: (i32, i32, i32, i32), z: f32) -> bool {
+        if self.cell_width == 0 {
+            return true;
+        }
+        let (min_x, min_y, max_x, max_y) = screen_aabb;
+        let last_x = (OCCLUSION_WIDTH * self.cell_width) as i32 - 1;
+        let last_y = (OCCLUSION_HEIGHT * self.cell_height) as i32 - 1;
+        if max_x < 0 || max_y < 0 || min_x > last_x || min_y > last_y || min_x > max_x || min_y > max_y {
+            return true; // Off screen: nothing here to test against
+        }
+
+        let cell_x = |x: i32| x.clamp(0, last_x) as usize / self.cell_width;
+        let cell_y = |y: i32| y.clamp(0, last_y) as usize / self.cell_height;
+        (cell_y(min_y)..=cell_y(max_y)).any(|cy| {
+            let row = &self.cells[cy * OCCLUSION_WIDTH..][..OCCLUSION_WIDTH];
+            row[cell_x(min_x)..=cell_x(max_x)].iter().any(|&cell| cell <= z)
+        })
+    }
+}
+
+impl Default for OcclusionBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Occluder depth of the frame being rendered (see `build_occlusion`)
+pub static OCCLUSION: Mutex<OcclusionBuffer> = Mutex::new(OcclusionBuffer::new());
+
+/// Downsample the z-buffer into OCCLUSION
+pub fn build_occlusion() {
+    if let Some(zb) = ZBUFFER.lock().as_ref() {
+        OCCLUSION.lock().build(zb, depth_mode());
+    }
+}
+
 /// Global z-buffer
 pub static ZBUFFER: Mutex<Option<ZBuffer>> = Mutex::new(None);
 
@@ -77,3 +330,61 @@ pub fn clear() {
         zb.clear();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 1024x768 z-buffer with a near wall (1/w 0.5) over the left half and
+    /// nothing drawn on the right
+    fn half_wall() -> ZBuffer {
+        let mut zb = ZBuffer::new(1024, 768);
+        for y in 0..768 {
+            for x in 0..512 {
+                zb.set(x, y, 0.5);
+            }
+        }
+        zb
+    }
+
+    #[test]
+    fn test_occlusion_hides_only_behind_occluders() {
+        let mut occlusion = OcclusionBuffer::new();
+        assert!(occlusion.is_visible((0, 0, 10, 10), 0.0), "unbuilt buffer hides nothing");
+        occlusion.build(&half_wall(), DepthMode::Float);
+
+        // Behind the wall, in front of it, and straddling its edge
+        assert!(!occlusion.is_visible((100, 100, 200, 200), 0.1));
+        assert!(occlusion.is_visible((100, 100, 200, 200), 0.9));
+        assert!(occlusion.is_visible((500, 100, 520, 200), 0.1));
+        // Open sky on the right, and a box hanging off the screen
+        assert!(occlusion.is_visible((600, 100, 700, 200), 0.1));
+        assert!(!occlusion.is_visible((-50, -50, 40, 40), 0.1));
+    }
+
+    #[test]
+    fn test_occlusion_cell_keeps_farthest_depth() {
+        // One far pixel in a block means the block doesn't fully occlude
+        let mut zb = half_wall();
+        zb.set(20, 20, 0.05);
+        let mut occlusion = OcclusionBuffer::new();
+        occlusion.build(&zb, DepthMode::Float);
+        assert!(occlusion.is_visible((18, 18, 22, 22), 0.1));
+        assert!(!occlusion.is_visible((40, 40, 44, 44), 0.1));
+    }
+
+    #[test]
+    fn test_occlusion_from_integer_depth() {
+        let mut zb = ZBuffer::new(1024, 768);
+        zb.data.fill(f32::from_bits(0));
+        for y in 0..768 {
+            for x in 0..512 {
+                zb.data[y * 1024 + x] = f32::from_bits(IntDepth::store(IntDepth::from_f32(0.5)));
+            }
+        }
+        let mut occlusion = OcclusionBuffer::new();
+        occlusion.build(&zb, DepthMode::Integer);
+        assert!(!occlusion.is_visible((100, 100, 200, 200), 0.1));
+        assert!(occlusion.is_visible((600, 100, 700, 200), 0.1));
+    }
+}
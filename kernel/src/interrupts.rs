@@ -0,0 +1,127 @@
+//! Interrupt descriptor table and hardware interrupt handlers
+//!
+//! Everything else in the kernel is polled, so only the BSP takes device
+//! interrupts and only lines routed here are unmasked. The render cores
+//! load the table too, but only for the wakeup IPI that ends their HLT
+//! between frames; the other APs run with interrupts off. Neither the PIT
+//! tick nor the wakeup does any work of its own: they only end HLT waits.
+
+use crate::drivers::{apic, e1000, pic, pit};
+use core::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use spin::Once;
+use x86_64::structures::idt::{InterruptDescriptorTable, InterruptStackFrame};
+
+static IDT: Once<InterruptDescriptorTable> = Once::new();
+
+/// PIC line the E1000 interrupts on
+static E1000_IRQ: AtomicU8 = AtomicU8::new(0);
+
+/// Whether the PIT tick is unmasked, so a HLT is sure to return
+static TIMER_RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// Rate of the PIT tick (1 ms granularity for HLT waits)
+const TIMER_HZ: u32 = 1000;
+
+/// Vector of the IPI that wakes parked render cores
+pub const WAKE_VECTOR: u8 = 0xF0;
+
+/// Lines the master and slave PIC report spurious interrupts on
+const SPURIOUS_MASTER_IRQ: u8 = 7;
+const SPURIOUS_SLAVE_IRQ: u8 = 15;
+
+/// Build and load the table and remap the PICs, all masked, on first use
+///
+/// The E1000 vector is only filled in if its line is known by then, so
+/// `init_e1000` has to run before `init_timer`.
+fn load() {
+    let mut first = false;
+    let idt = IDT.call_once(|| {
+        first = true;
+        let mut idt = InterruptDescriptorTable::new();
+        idt[pic::vector(SPURIOUS_MASTER_IRQ)].set_handler_fn(spurious_master_interrupt);
+        idt[pic::vector(SPURIOUS_SLAVE_IRQ)].set_handler_fn(spurious_slave_interrupt);
+        idt[pic::vector(pit::IRQ)].set_handler_fn(timer_interrupt);
+        idt[WAKE_VECTOR].set_handler_fn(wake_interrupt);
+        idt[apic::SPURIOUS_VECTOR].set_handler_fn(spurious_apic_interrupt);
+        let e1000_irq = E1000_IRQ.load(Ordering::Relaxed);
+        if e1000_irq != 0 {
+            idt[pic::vector(e1000_irq)].set_handler_fn(e1000_interrupt);
+        }
+        idt
+    });
+    if first {
+        idt.load();
+        pic::init();
+    }
+}
+
+/// Route the E1000's PCI interrupt line to its handler and enable interrupts
+/// Returns false, leaving the driver polled, when the line isn't a PIC line
+/// (0xFF means the firmware didn't connect one)
+pub fn init_e1000(irq: u8) -> bool {
+    if irq >= 16 || irq == pic::CASCADE_IRQ || irq == pit::IRQ {
+        return false;
+    }
+    E1000_IRQ.store(irq, Ordering::Relaxed);
+    e1000::irq::enable();
+
+    load();
+    pic::unmask(irq);
+    x86_64::instructions::interrupts::enable();
+    true
+}
+
+/// Start the periodic PIT tick and enable interrupts
+pub fn init_timer() {
+    pit::start_periodic(TIMER_HZ);
+    load();
+    pic::unmask(pit::IRQ);
+    x86_64::instructions::interrupts::enable();
+    TIMER_RUNNING.store(true, Ordering::Release);
+}
+
+/// Whether a timer interrupt will end a HLT within a millisecond
+pub fn timer_running() -> bool {
+    TIMER_RUNNING.load(Ordering::Acquire)
+}
+
+/// Let the calling render core take wakeup IPIs (interrupts stay off until
+/// it HLTs)
+/// Returns false, leaving the core to spin, without an APIC or before the
+/// BSP has built the table.
+pub fn init_render_core() -> bool {
+    let Some(idt) = IDT.get() else {
+        return false;
+    };
+    if !apic::is_ready() {
+        return false;
+    }
+    idt.load();
+    apic::enable();
+    true
+}
+
+extern "x86-interrupt" fn timer_interrupt(_frame: InterruptStackFrame) {
+    pic::end_of_interrupt(pit::IRQ);
+}
+
+extern "x86-interrupt" fn e1000_interrupt(_frame: InterruptStackFrame) {
+    e1000::irq::handle_interrupt();
+    pic::end_of_interrupt(E1000_IRQ.load(Ordering::Relaxed));
+}
+
+extern "x86-interrupt" fn wake_interrupt(_frame: InterruptStackFrame) {
+    apic::end_of_interrupt();
+}
+
+/// Spurious APIC interrupts aren't in service, so they take no EOI
+extern "x86-interrupt" fn spurious_apic_interrupt(_frame: InterruptStackFrame) {}
+
+/// A spurious IRQ 7 was never in service on the master, so it takes no EOI
+extern "x86-interrupt" fn spurious_master_interrupt(_frame: InterruptStackFrame) {}
+
+/// A spurious IRQ 15 was never in service on the slave, but the master did
+/// see the cascade line and still needs its EOI
+extern "x86-interrupt" fn spurious_slave_interrupt(_frame: InterruptStackFrame) {
+    pic::end_of_interrupt(pic::CASCADE_IRQ);
+}
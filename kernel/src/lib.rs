@@ -11,10 +11,13 @@ extern crate alloc;
 pub mod api;
 pub mod app;
 pub mod boot;
+pub mod console;
 pub mod drivers;
+pub mod encoding;
 pub mod game;
 pub mod gfx;
 pub mod graphics;
+pub mod interrupts;
 pub mod memory;
 pub mod net;
 pub mod smp;
@@ -12,15 +12,19 @@ extern crate alloc;
 mod api;
 mod app;
 mod boot;
+mod console;
 mod drivers;
+mod encoding;
 mod game;
 mod gfx;
 mod graphics;
+mod interrupts;
 mod memory;
 mod net;
 mod smp;
 mod ui;
 
+use boot_config::AppMode;
 use boot::{BASE_REVISION, HHDM_REQUEST, KERNEL_FILE_REQUEST, MEMORY_MAP_REQUEST};
 use core::panic::PanicInfo;
 
@@ -39,6 +43,12 @@ extern "C" fn _start() -> ! {
     drivers::serial::SERIAL1.lock().init();
     serial_println!("BattleRoyaleOS Kernel Loaded");
 
+    // Measure the TSC before anything times itself with it
+    match drivers::pit::calibrate_tsc() {
+        Some(hz) => serial_println!("TSC: {} MHz (PIT calibrated)", hz / 1_000_000),
+        None => serial_println!("TSC: calibration failed, assuming {} MHz", drivers::pit::tsc_per_second() / 1_000_000),
+    }
+
     // Initialize memory allocator
     memory::allocator::init();
     serial_println!("Heap allocator initialized");
@@ -51,6 +61,9 @@ extern "C" fn _start() -> ! {
         serial_println!("HHDM offset: {:#x}", hhdm_offset);
     }
 
+    // Build the PCI device table before any driver looks for its device
+    drivers::pci::init();
+
     // Print memory map info and initialize DMA pool
     if let Some(memmap) = MEMORY_MAP_REQUEST.get_response() {
         let entries = memmap.entries();
@@ -71,27 +84,25 @@ extern "C" fn _start() -> ! {
 
     // Check kernel arguments for boot mode FIRST (before GPU init)
     // This way we can skip GPU initialization in server mode
-    let mut is_server = false;
-    let mut benchmark_mode = false;
-    let mut test_mode = false;
-    if let Some(file) = KERNEL_FILE_REQUEST.get_response() {
-        let cmdline_bytes = file.file().cmdline();
-        if let Ok(cmdline) = core::str::from_utf8(cmdline_bytes) {
-            serial_println!("Kernel cmdline: {:?}", cmdline);
-            if cmdline.contains("server") {
-                is_server = true;
-                serial_println!("SERVER MODE: Dedicated server (no rendering)");
-            }
-            if cmdline.contains("benchmark") {
-                benchmark_mode = true;
-                serial_println!("BENCHMARK MODE: Performance testing");
-            }
-            if cmdline.contains("test") {
-                test_mode = true;
-                serial_println!("TEST MODE: All items spawned");
-            }
-        }
+    let cmdline = KERNEL_FILE_REQUEST
+        .get_response()
+        .and_then(|file| core::str::from_utf8(file.file().cmdline()).ok())
+        .unwrap_or("");
+    serial_println!("Kernel cmdline: {:?}", cmdline);
+    let config = boot_config::BootConfig::from_cmdline(cmdline);
+    match config.mode {
+        AppMode::GameServer => serial_println!("SERVER MODE: Dedicated server (no rendering)"),
+        AppMode::Benchmark => serial_println!("BENCHMARK MODE: Performance testing"),
+        AppMode::TestHarness => serial_println!("TEST MODE: All items spawned"),
+        AppMode::Replay => serial_println!("REPLAY MODE: Playing back recorded match"),
+        AppMode::GameClient => {}
+    }
+    if config.record {
+        game::replay::start_recording();
     }
+    graphics::rasterizer::set_render_mode(config.render_mode);
+    let is_server = config.mode == AppMode::GameServer;
+    boot::set_config(config);
 
     // Initialize GPU (skip in server mode - dedicated server has no display)
     let (fb_width, fb_height, gpu_batch_available) = if is_server {
@@ -116,6 +127,9 @@ extern "C" fn _start() -> ! {
         graphics::zbuffer::init(w, h);
         serial_println!("Z-buffer initialized");
 
+        // Generate material textures
+        graphics::rasterizer::init_textures();
+
         // Initialize tile system
         graphics::tiles::init(w, h);
         if let Some(queue) = graphics::tiles::TILE_QUEUE.lock().as_ref() {
@@ -133,12 +147,15 @@ extern "C" fn _start() -> ! {
     let cpu_count = smp::scheduler::cpu_count();
     serial_println!("CPU count: {}", cpu_count);
 
-    // Initialize PCI and find E1000
-    serial_println!("Scanning PCI bus...");
-    if let Some(e1000_dev) = drivers::pci::find_device(
+    // Find E1000 in the PCI device table
+    let e1000_devs = drivers::pci::find_all_matching(
         drivers::pci::INTEL_VENDOR_ID,
         drivers::pci::E1000_DEVICE_ID,
-    ) {
+    );
+    if e1000_devs.len() > 1 {
+        serial_println!("PCI: {} E1000 NICs, using the first", e1000_devs.len());
+    }
+    if let Some(&e1000_dev) = e1000_devs.first() {
         serial_println!(
             "Found E1000 at {:02x}:{:02x}.{} BAR0={:#x}",
             e1000_dev.bus,
@@ -154,9 +171,15 @@ extern "C" fn _start() -> ! {
         // Get BAR0 physical address
         let bar0_phys = e1000_dev.bar0_address();
 
+        // Size the register window from the BAR itself (128KB on the 82540EM)
+        let bar0_size = e1000_dev.bar_size(0) as usize;
+        if bar0_size == 0 {
+            serial_println!("E1000: BAR0 not implemented");
+            halt_loop();
+        }
+
         // Map MMIO region into kernel address space with proper caching attributes
-        // E1000 MMIO region is 128KB
-        let mmio_base = match memory::paging::map_mmio(bar0_phys, 0x20000) {
+        let mmio_base = match memory::paging::map_mmio(bar0_phys, bar0_size) {
             Some(virt) => virt,
             None => {
                 serial_println!("E1000: Failed to map MMIO region");
@@ -165,17 +188,53 @@ extern "C" fn _start() -> ! {
         };
 
         // Initialize E1000 driver
-        if let Err(e) = drivers::e1000::init(mmio_base) {
+        // A dedicated server only needs frames addressed to it and broadcasts
+        let e1000_config = drivers::e1000::E1000Config {
+            checksum_offload: boot::config().checksum_offload,
+            promiscuous: !is_server,
+        };
+        if let Err(e) = drivers::e1000::init(mmio_base, e1000_config) {
             serial_println!("E1000 init failed: {}", e);
         } else {
             serial_println!("E1000 initialized successfully");
             // Initialize network stack
             net::stack::init();
+
+            // Deliver received frames by interrupt; the stack still polls the ring as a fallback
+            if interrupts::init_e1000(e1000_dev.interrupt_line) {
+                serial_println!("E1000: RX interrupts on IRQ {}", e1000_dev.interrupt_line);
+            } else {
+                serial_println!("E1000: No usable IRQ line, polling RX");
+            }
+
+            // Dedicated server exposes a plaintext status channel for monitoring
+            if is_server {
+                net::stack::enable_status_server(net::protocol::STATUS_PORT);
+            }
         }
     } else {
         serial_println!("E1000 not found");
     }
 
+    // USB keyboard behind an xHCI controller (after the NIC has its DMA rings)
+    if !is_server {
+        if drivers::usb::init() {
+            serial_println!("USB keyboard initialized");
+        } else {
+            serial_println!("USB: no keyboard found, using PS/2");
+        }
+    }
+
+    // Periodic tick so frame pacing can HLT while it waits
+    interrupts::init_timer();
+    serial_println!("PIT: timer interrupts enabled");
+
+    // DMA pool usage after the drivers have taken their rings (debug boots only)
+    if boot::config().debug {
+        serial_println!("DMA: {}", memory::dma::DMA_ALLOCATOR.stats());
+        memory::dma::DMA_ALLOCATOR.dump_allocations();
+    }
+
     // Initialize game world (uses is_server flag from earlier cmdline parsing)
     serial_println!("Initializing game world...");
     game::world::init(is_server);
@@ -198,11 +257,7 @@ extern "C" fn _start() -> ! {
         // Dedicated server loop (no rendering)
         server_loop();
     } else {
-        // Set mode flags for game client
-        app::set_benchmark_mode(benchmark_mode);
-        app::set_test_mode(test_mode);
-
-        // Run game client
+        // Run game client (reads benchmark/test mode from the boot config)
         app::run(fb_width, fb_height, gpu_batch_available);
     }
 }
@@ -215,16 +270,18 @@ fn server_loop() -> ! {
     serial_println!("Waiting for client connections...");
 
     let mut tick_count = 0u64;
-    let tsc_per_second: u64 = 2_000_000_000;
+    let tsc_per_second = drivers::pit::tsc_per_second();
     let start_tsc = read_tsc();
     let mut last_status_tsc = start_tsc;
+    let mut last_hw_stats_tsc = start_tsc;
 
     // Server tick rate: 60 ticks per second (same as client frame rate)
-    let tsc_per_tick = tsc_per_second / 60;
+    let tick_rate = 60u32;
+    let tsc_per_tick = tsc_per_second / tick_rate as u64;
     let mut next_tick_tsc = start_tsc + tsc_per_tick;
 
     // Initialize the game world in server mode
-    if let Some(world) = game::world::GAME_WORLD.lock().as_mut() {
+    if let Some(world) = game::world::GAME_WORLD.write().as_mut() {
         world.spawn_bots(10); // Spawn 10 bots for the battle
         serial_println!("Spawned 10 bots for battle");
     }
@@ -240,9 +297,18 @@ fn server_loop() -> ! {
             // Process incoming network packets
             net::protocol::process_incoming();
 
+            // Handle admin commands typed on the serial console
+            console::poll();
+
             // Update game world physics
-            if let Some(world) = game::world::GAME_WORLD.lock().as_mut() {
+            if let Some(world) = game::world::GAME_WORLD.write().as_mut() {
                 world.update(1.0 / 60.0);
+                game::replay::record(world);
+
+                // Dump the recording once the match has a winner
+                if world.check_victory().is_some() {
+                    game::replay::finish_recording();
+                }
             }
 
             // Broadcast world state to clients every 6 ticks (~10 Hz)
@@ -250,8 +316,18 @@ fn server_loop() -> ! {
                 net::protocol::broadcast_world_state();
             }
 
-            // Poll network stack
-            net::stack::poll(tick_count as i64);
+            // Send new eliminations and harvests to clients
+            net::protocol::broadcast_events();
+
+            // Refresh the TCP status document once per second
+            if tick_count % tick_rate as u64 == 0 {
+                let uptime_secs = (current_tsc - start_tsc) / tsc_per_second;
+                net::protocol::update_status(uptime_secs, tick_rate);
+            }
+
+            // Poll network stack (timestamps in ms so TCP timers run in real time)
+            let now_ms = (current_tsc - start_tsc) / (tsc_per_second / 1000);
+            net::stack::poll(now_ms as i64);
 
             // Print status every 10 seconds
             if current_tsc - last_status_tsc >= tsc_per_second * 10 {
@@ -259,14 +335,33 @@ fn server_loop() -> ! {
                 let elapsed_secs = (current_tsc - start_tsc) / tsc_per_second;
 
                 // Get player count
-                let player_count = if let Some(world) = game::world::GAME_WORLD.lock().as_ref() {
+                let player_count = if let Some(world) = game::world::GAME_WORLD.read().as_ref() {
                     world.players.len()
                 } else {
                     0
                 };
 
-                serial_println!("[SERVER] Uptime: {}s | Ticks: {} | Players: {}",
-                    elapsed_secs, tick_count, player_count);
+                // NIC counters as the hardware sees them, drops included
+                let nic = drivers::e1000::E1000_DEVICE.lock().as_mut().map(|device| {
+                    device.update_hw_stats();
+                    device.hw_stats()
+                });
+                match nic {
+                    Some(hw) => serial_println!(
+                        "[SERVER] Uptime: {}s | Ticks: {} | Players: {} | NIC rx {} tx {} crc {} missed {}",
+                        elapsed_secs, tick_count, player_count, hw.gprc, hw.gptc, hw.crcerrs, hw.mpc
+                    ),
+                    None => serial_println!("[SERVER] Uptime: {}s | Ticks: {} | Players: {}",
+                        elapsed_secs, tick_count, player_count),
+                }
+
+                // Hardware NIC counters once a minute
+                if current_tsc - last_hw_stats_tsc >= tsc_per_second * 60 {
+                    last_hw_stats_tsc = current_tsc;
+                    if let Some(device) = drivers::e1000::E1000_DEVICE.lock().as_mut() {
+                        device.print_stats();
+                    }
+                }
             }
         } else {
             // Idle CPU while waiting for next tick (saves power)
@@ -30,3 +30,55 @@ pub fn alloc_aligned(size: usize, align: usize) -> Option<NonNull<u8>> {
     let ptr = unsafe { alloc::alloc::alloc(layout) };
     NonNull::new(ptr)
 }
+
+/// Heap usage snapshot
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AllocatorStats {
+    /// Bytes handed to the allocator
+    pub total_bytes: usize,
+    /// Bytes in live allocations
+    pub used_bytes: usize,
+    /// Largest single allocation that would currently succeed
+    pub largest_free: usize,
+    /// Allocations made since boot
+    pub alloc_count: u64,
+}
+
+impl AllocatorStats {
+    /// Bytes still available, whether contiguous or not
+    pub fn free_bytes(&self) -> usize {
+        self.total_bytes.saturating_sub(self.used_bytes)
+    }
+}
+
+/// Snapshot the heap counters
+/// `largest_free` is found by probing with real allocations under the heap lock,
+/// so this costs a few dozen malloc/free pairs; keep it out of per-frame paths.
+pub fn stats() -> AllocatorStats {
+    let mut talc = ALLOCATOR.lock();
+    let counters = talc.get_counters();
+    let mut stats = AllocatorStats {
+        total_bytes: counters.claimed_bytes,
+        used_bytes: counters.allocated_bytes,
+        largest_free: 0,
+        alloc_count: counters.total_allocation_count,
+    };
+
+    // Binary search the largest size that still fits in one hole
+    let (mut low, mut high) = (0, counters.available_bytes);
+    while low < high {
+        let size = low + (high - low).div_ceil(2);
+        let Ok(layout) = Layout::from_size_align(size, 8) else {
+            break;
+        };
+        match unsafe { talc.malloc(layout) } {
+            Ok(ptr) => {
+                unsafe { talc.free(ptr, layout) };
+                low = size;
+            }
+            Err(()) => high = size - 1,
+        }
+    }
+    stats.largest_free = low;
+    stats
+}
@@ -0,0 +1,32 @@
+//! Heap allocator benchmark
+//!
+//! Runs `benchmark::MemoryBenchmark` against the global allocator, timed by the TSC.
+
+use super::allocator;
+use crate::read_tsc;
+use benchmark::{HeapProbe, MemoryBenchResults, MemoryBenchmark};
+
+/// The kernel heap as seen by the benchmark
+struct KernelHeap {
+    tsc_per_second: u64,
+}
+
+impl HeapProbe for KernelHeap {
+    fn now_ticks(&self) -> u64 {
+        read_tsc()
+    }
+
+    fn ticks_per_second(&self) -> u64 {
+        self.tsc_per_second
+    }
+
+    fn free_space(&self) -> (usize, usize) {
+        let stats = allocator::stats();
+        (stats.largest_free, stats.free_bytes())
+    }
+}
+
+/// Stress the allocator for `duration_secs`
+pub fn run(duration_secs: u32, tsc_per_second: u64) -> MemoryBenchResults {
+    MemoryBenchmark::new(KernelHeap { tsc_per_second }).run(duration_secs)
+}
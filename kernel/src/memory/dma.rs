@@ -5,7 +5,7 @@
 //! HHDM-mapped virtual addresses (for CPU access).
 
 use core::ptr::NonNull;
-use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use core::sync::atomic::{AtomicU16, AtomicU64, AtomicUsize, Ordering};
 use spin::Mutex;
 
 /// Page size for DMA allocations
@@ -26,15 +26,48 @@ pub struct DmaBuffer {
 unsafe impl Send for DmaBuffer {}
 unsafe impl Sync for DmaBuffer {}
 
+/// DMA pool usage snapshot
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DmaStats {
+    /// Bytes added to the pool
+    pub total_bytes: usize,
+    /// Bytes handed out to live allocations
+    pub allocated_bytes: usize,
+    /// Successful allocations
+    pub allocation_count: usize,
+    /// Share of the pages consumed so far that back no allocation (lost to
+    /// allocations that ran out of pages part way; the pool never frees)
+    pub fragmentation_percent: u8,
+}
+
+impl core::fmt::Display for DmaStats {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(
+            f,
+            "{} of {} KB allocated in {} allocations, {}% fragmented",
+            self.allocated_bytes / 1024,
+            self.total_bytes / 1024,
+            self.allocation_count,
+            self.fragmentation_percent
+        )
+    }
+}
+
 /// Simple physical page allocator for DMA
 /// Uses a static array of page physical addresses
 pub struct DmaAllocator {
     /// Physical addresses of available DMA pages
     pages: [AtomicU64; MAX_DMA_PAGES],
+    /// Page count of the allocation starting at each page (0 = not the first page of one)
+    runs: [AtomicU16; MAX_DMA_PAGES],
     /// Number of pages available
     count: AtomicUsize,
     /// Next page to allocate
     next: AtomicUsize,
+    /// Pages backing successful allocations
+    allocated: AtomicUsize,
+    /// Successful allocations
+    allocations: AtomicUsize,
 }
 
 impl DmaAllocator {
@@ -43,8 +76,11 @@ impl DmaAllocator {
         const ZERO: AtomicU64 = AtomicU64::new(0);
         Self {
             pages: [ZERO; MAX_DMA_PAGES],
+            runs: [const { AtomicU16::new(0) }; MAX_DMA_PAGES],
             count: AtomicUsize::new(0),
             next: AtomicUsize::new(0),
+            allocated: AtomicUsize::new(0),
+            allocations: AtomicUsize::new(0),
         }
     }
 
@@ -56,51 +92,88 @@ impl DmaAllocator {
         }
     }
 
-    /// Allocate a single DMA page
-    /// Returns (physical_address, virtual_address) or None if out of pages
-    pub fn alloc_page(&self, hhdm_offset: u64) -> Option<(u64, *mut u8)> {
-        let idx = self.next.fetch_add(1, Ordering::SeqCst);
-        let count = self.count.load(Ordering::SeqCst);
+    /// Pages actually tracked (pages added beyond MAX_DMA_PAGES are dropped)
+    fn capacity(&self) -> usize {
+        self.count.load(Ordering::SeqCst).min(MAX_DMA_PAGES)
+    }
 
-        if idx >= count {
+    /// Reserve and zero `n` consecutive pool pages as one allocation
+    /// Returns the pool index of the first page, or None if the pool is exhausted
+    fn reserve(&self, n: usize, hhdm_offset: u64) -> Option<usize> {
+        let first = self.next.fetch_add(n, Ordering::SeqCst);
+        if n == 0 || first + n > self.capacity() {
             return None;
         }
 
-        let phys = self.pages[idx].load(Ordering::SeqCst);
-        if phys == 0 {
-            return None;
+        for page in &self.pages[first..first + n] {
+            let phys = page.load(Ordering::SeqCst);
+            if phys == 0 {
+                return None;
+            }
+            // Zero the page
+            unsafe {
+                core::ptr::write_bytes((phys + hhdm_offset) as *mut u8, 0, PAGE_SIZE);
+            }
         }
 
-        let virt = (phys + hhdm_offset) as *mut u8;
+        self.runs[first].store(n as u16, Ordering::SeqCst);
+        self.allocated.fetch_add(n, Ordering::SeqCst);
+        self.allocations.fetch_add(1, Ordering::SeqCst);
+        Some(first)
+    }
 
-        // Zero the page
-        unsafe {
-            core::ptr::write_bytes(virt, 0, PAGE_SIZE);
-        }
+    /// (physical, virtual) address of pool page `idx`
+    fn page(&self, idx: usize, hhdm_offset: u64) -> (u64, *mut u8) {
+        let phys = self.pages[idx].load(Ordering::SeqCst);
+        (phys, (phys + hhdm_offset) as *mut u8)
+    }
 
-        Some((phys, virt))
+    /// Allocate a single DMA page
+    /// Returns (physical_address, virtual_address) or None if out of pages
+    pub fn alloc_page(&self, hhdm_offset: u64) -> Option<(u64, *mut u8)> {
+        let idx = self.reserve(1, hhdm_offset)?;
+        Some(self.page(idx, hhdm_offset))
     }
 
     /// Allocate multiple contiguous pages (best effort - may not be physically contiguous)
     /// For E1000, we allocate individual descriptor buffers, so contiguous is not required
     pub fn alloc_pages(&self, count: usize, hhdm_offset: u64) -> Option<alloc::vec::Vec<(u64, *mut u8)>> {
-        let mut pages = alloc::vec::Vec::with_capacity(count);
-        for _ in 0..count {
-            if let Some(page) = self.alloc_page(hhdm_offset) {
-                pages.push(page);
-            } else {
-                // Out of pages
-                return None;
-            }
-        }
-        Some(pages)
+        let first = self.reserve(count, hhdm_offset)?;
+        Some((first..first + count).map(|idx| self.page(idx, hhdm_offset)).collect())
     }
 
     /// Get the number of available pages
     pub fn available(&self) -> usize {
-        let count = self.count.load(Ordering::SeqCst);
         let next = self.next.load(Ordering::SeqCst);
-        count.saturating_sub(next)
+        self.capacity().saturating_sub(next)
+    }
+
+    /// Pool usage, from counters kept by the allocator (no heap allocation)
+    pub fn stats(&self) -> DmaStats {
+        let capacity = self.capacity();
+        let consumed = self.next.load(Ordering::SeqCst).min(capacity);
+        let allocated = self.allocated.load(Ordering::SeqCst);
+        let wasted = consumed.saturating_sub(allocated);
+        DmaStats {
+            total_bytes: capacity * PAGE_SIZE,
+            allocated_bytes: allocated * PAGE_SIZE,
+            allocation_count: self.allocations.load(Ordering::SeqCst),
+            fragmentation_percent: (wasted * 100).checked_div(consumed).unwrap_or(0) as u8,
+        }
+    }
+
+    /// Print every allocation's address and size to serial
+    pub fn dump_allocations(&self) {
+        use crate::serial_println;
+
+        let consumed = self.next.load(Ordering::SeqCst).min(self.capacity());
+        for (run, page) in self.runs.iter().zip(&self.pages).take(consumed) {
+            let pages = run.load(Ordering::SeqCst) as usize;
+            if pages != 0 {
+                let phys = page.load(Ordering::SeqCst);
+                serial_println!("DMA: alloc {:#x} {} bytes ({} pages)", phys, pages * PAGE_SIZE, pages);
+            }
+        }
     }
 }
 
@@ -153,6 +226,7 @@ pub fn init_dma_pool(memory_map: &[&limine::memory_map::Entry], hhdm_offset: u64
     }
 
     serial_println!("DMA: Initialized {} pages ({} KB)", pages_added, pages_added * 4);
+    serial_println!("DMA: {}", DMA_ALLOCATOR.stats());
 }
 
 /// Allocate a DMA buffer of the given size
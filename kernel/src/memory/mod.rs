@@ -1,5 +1,6 @@
 //! Memory management
 
 pub mod allocator;
+pub mod bench;
 pub mod dma;
 pub mod paging;
@@ -0,0 +1,163 @@
+//! ARP cache tracking
+//!
+//! smoltcp keeps its neighbor cache private, so the RX path snoops ARP and
+//! IPv4 frames to maintain a read-only mirror for debugging, and to notice
+//! destinations that stopped answering (e.g. a server restarted with a new MAC).
+
+use smoltcp::wire::{ArpPacket, EthernetFrame, EthernetProtocol, Ipv4Packet};
+use spin::Mutex;
+
+/// Number of mirrored ARP entries
+pub const ARP_TABLE_SIZE: usize = 16;
+
+/// Unanswered sends to one destination before its ARP entry is re-resolved
+pub const ARP_RETRY_THRESHOLD: u32 = 120;
+
+/// One IP -> MAC mapping observed on the wire
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArpEntry {
+    pub ip: [u8; 4],
+    pub mac: [u8; 6],
+    /// Poll timestamp of the last ARP packet from this host
+    pub last_seen_ms: i64,
+}
+
+/// Sends to a destination since it was last heard from
+#[derive(Debug, Clone, Copy)]
+struct Unanswered {
+    ip: [u8; 4],
+    count: u32,
+}
+
+/// ARP mirror and per-destination send tracking
+pub struct ArpTable {
+    entries: [Option<ArpEntry>; ARP_TABLE_SIZE],
+    unanswered: [Option<Unanswered>; ARP_TABLE_SIZE],
+}
+
+impl ArpTable {
+    pub const fn new() -> Self {
+        Self {
+            entries: [None; ARP_TABLE_SIZE],
+            unanswered: [None; ARP_TABLE_SIZE],
+        }
+    }
+
+    /// Record an IP -> MAC mapping (replaces the oldest entry when full)
+    pub fn insert(&mut self, ip: [u8; 4], mac: [u8; 6], now_ms: i64) {
+        let entry = ArpEntry { ip, mac, last_seen_ms: now_ms };
+
+        if let Some(slot) = self.entries.iter_mut().find(|e| e.is_some_and(|e| e.ip == ip)) {
+            *slot = Some(entry);
+            return;
+        }
+        if let Some(slot) = self.entries.iter_mut().find(|e| e.is_none()) {
+            *slot = Some(entry);
+            return;
+        }
+
+        let oldest = self
+            .entries
+            .iter_mut()
+            .min_by_key(|e| e.map_or(i64::MIN, |e| e.last_seen_ms));
+        if let Some(slot) = oldest {
+            *slot = Some(entry);
+        }
+    }
+
+    /// Look up a mirrored entry
+    pub fn get(&self, ip: [u8; 4]) -> Option<ArpEntry> {
+        self.entries.iter().flatten().find(|e| e.ip == ip).copied()
+    }
+
+    /// All mirrored entries
+    pub fn entries(&self) -> impl Iterator<Item = &ArpEntry> {
+        self.entries.iter().flatten()
+    }
+
+    /// Forget every entry and send counter
+    pub fn clear(&mut self) {
+        self.entries = [None; ARP_TABLE_SIZE];
+        self.unanswered = [None; ARP_TABLE_SIZE];
+    }
+
+    /// A packet from `ip` arrived, so the path to it works
+    pub fn mark_answered(&mut self, ip: [u8; 4]) {
+        for slot in self.unanswered.iter_mut() {
+            if slot.is_some_and(|u| u.ip == ip) {
+                *slot = None;
+            }
+        }
+    }
+
+    /// Count a send to `ip`; returns true when the destination has stopped
+    /// answering and its link-layer address should be re-resolved
+    pub fn note_send(&mut self, ip: [u8; 4]) -> bool {
+        let slot = match self.unanswered.iter().position(|u| u.is_some_and(|u| u.ip == ip)) {
+            Some(i) => i,
+            None => match self.unanswered.iter().position(|u| u.is_none()) {
+                Some(i) => i,
+                // Tracking table full: recycle the slot with the fewest sends
+                None => self
+                    .unanswered
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|(_, u)| u.map_or(0, |u| u.count))
+                    .map_or(0, |(i, _)| i),
+            },
+        };
+
+        let count = match self.unanswered[slot] {
+            Some(u) if u.ip == ip => u.count + 1,
+            _ => 1,
+        };
+
+        if count >= ARP_RETRY_THRESHOLD {
+            self.unanswered[slot] = None;
+            true
+        } else {
+            self.unanswered[slot] = Some(Unanswered { ip, count });
+            false
+        }
+    }
+
+    /// Update the mirror from a received Ethernet frame
+    pub fn observe_frame(&mut self, frame: &[u8], now_ms: i64) {
+        let eth = match EthernetFrame::new_checked(frame) {
+            Ok(f) => f,
+            Err(_) => return,
+        };
+
+        match eth.ethertype() {
+            EthernetProtocol::Arp => {
+                if let Ok(arp) = ArpPacket::new_checked(eth.payload()) {
+                    let (Ok(ip), Ok(mac)) = (
+                        <[u8; 4]>::try_from(arp.source_protocol_addr()),
+                        <[u8; 6]>::try_from(arp.source_hardware_addr()),
+                    ) else {
+                        return;
+                    };
+                    if ip != [0; 4] {
+                        self.insert(ip, mac, now_ms);
+                        self.mark_answered(ip);
+                    }
+                }
+            }
+            EthernetProtocol::Ipv4 => {
+                if let Ok(ip) = Ipv4Packet::new_checked(eth.payload()) {
+                    self.mark_answered(ip.src_addr().octets());
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Default for ArpTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Global ARP mirror (updated from the RX path)
+pub static ARP_TABLE: Mutex<ArpTable> = Mutex::new(ArpTable::new());
@@ -0,0 +1,57 @@
+//! Network packet-rate benchmark
+//!
+//! Runs `benchmark::NetworkBenchmark` against the E1000 with loopback enabled,
+//! so every transmitted frame comes straight back to our own receive ring.
+
+use crate::drivers::e1000::{PacketBuf, E1000, E1000_DEVICE};
+use crate::read_tsc;
+use benchmark::{LoopbackDevice, NetworkBenchResults, NetworkBenchmark};
+
+/// E1000 (already in loopback) as a benchmark device, timed by the TSC
+struct E1000Loopback<'a> {
+    nic: &'a mut E1000,
+    rx: PacketBuf,
+    tsc_per_second: u64,
+}
+
+impl LoopbackDevice for E1000Loopback<'_> {
+    fn mac_address(&self) -> [u8; 6] {
+        self.nic.mac_address()
+    }
+
+    fn send(&mut self, frame: &[u8]) -> bool {
+        self.nic.transmit(frame).is_ok()
+    }
+
+    fn receive(&mut self, buf: &mut [u8]) -> Option<usize> {
+        if self.nic.receive_batch(core::slice::from_mut(&mut self.rx)) == 0 {
+            return None;
+        }
+        let len = self.rx.len.min(buf.len());
+        buf[..len].copy_from_slice(&self.rx.data[..len]);
+        Some(len)
+    }
+
+    fn now_ticks(&self) -> u64 {
+        read_tsc()
+    }
+
+    fn ticks_per_second(&self) -> u64 {
+        self.tsc_per_second
+    }
+}
+
+/// Benchmark the NIC for `duration_secs` (None without an E1000)
+/// Holds the device for the whole run and restores normal mode afterwards.
+pub fn run(duration_secs: u32, tsc_per_second: u64) -> Option<NetworkBenchResults> {
+    let mut guard = E1000_DEVICE.lock();
+    let nic = guard.as_mut()?;
+
+    nic.enable_loopback_mode();
+    let device = E1000Loopback { nic, rx: PacketBuf::new(), tsc_per_second };
+    let mut bench = NetworkBenchmark::new(device);
+    let results = bench.run(duration_secs);
+    bench.into_device().nic.disable_loopback_mode();
+
+    Some(results)
+}
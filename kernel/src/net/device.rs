@@ -1,31 +1,126 @@
 //! smoltcp Device trait implementation for E1000
 
-use crate::drivers::e1000::{E1000, E1000_DEVICE, BUFFER_SIZE};
-use smoltcp::phy::{self, Device, DeviceCapabilities, Medium};
+use super::arp::ARP_TABLE;
+use crate::drivers::e1000::{irq, PacketBuf, E1000_DEVICE, BUFFER_SIZE};
+use alloc::boxed::Box;
+use alloc::vec;
+use core::sync::atomic::{AtomicU64, Ordering};
+use smoltcp::phy::{self, Checksum, Device, DeviceCapabilities, Medium};
 use smoltcp::time::Instant;
 
+/// Packets drained from the NIC per lock acquisition
+pub const RX_BATCH_SIZE: usize = 32;
+
+/// Receive batching counters (for benchmark reporting)
+static RX_POLLS: AtomicU64 = AtomicU64::new(0);
+static RX_PACKETS: AtomicU64 = AtomicU64::new(0);
+static RX_BATCHES: AtomicU64 = AtomicU64::new(0);
+
+/// Snapshot of receive batching counters
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RxBatchStats {
+    /// Stack polls that ran since the last reset
+    pub polls: u64,
+    /// Packets handed to smoltcp
+    pub packets: u64,
+    /// Non-empty batches pulled from the NIC
+    pub batches: u64,
+}
+
+impl RxBatchStats {
+    /// Average packets received per stack poll
+    pub fn packets_per_poll(&self) -> f32 {
+        if self.polls == 0 {
+            0.0
+        } else {
+            self.packets as f32 / self.polls as f32
+        }
+    }
+
+    /// Average packets per non-empty batch (> 1 means batching engaged)
+    pub fn packets_per_batch(&self) -> f32 {
+        if self.batches == 0 {
+            0.0
+        } else {
+            self.packets as f32 / self.batches as f32
+        }
+    }
+}
+
+/// Read and reset the receive batching counters
+pub fn take_rx_batch_stats() -> RxBatchStats {
+    RxBatchStats {
+        polls: RX_POLLS.swap(0, Ordering::Relaxed),
+        packets: RX_PACKETS.swap(0, Ordering::Relaxed),
+        batches: RX_BATCHES.swap(0, Ordering::Relaxed),
+    }
+}
+
+/// Count one stack poll
+pub fn record_poll() {
+    RX_POLLS.fetch_add(1, Ordering::Relaxed);
+}
+
 /// E1000 device wrapper for smoltcp
-pub struct E1000Device;
+pub struct E1000Device {
+    /// Preallocated receive buffers, refilled a batch at a time
+    rx_pool: Box<[PacketBuf]>,
+    /// Number of valid packets in the pool
+    rx_count: usize,
+    /// Next packet to hand to smoltcp
+    rx_next: usize,
+    /// NIC verifies receive checksums
+    rx_checksum_offload: bool,
+    /// NIC fills in transmit checksums
+    tx_checksum_offload: bool,
+}
 
 impl E1000Device {
     pub fn new() -> Self {
-        Self
+        Self {
+            rx_pool: vec![PacketBuf::new(); RX_BATCH_SIZE].into_boxed_slice(),
+            rx_count: 0,
+            rx_next: 0,
+            rx_checksum_offload: E1000_DEVICE.lock().as_ref().is_some_and(|d| d.rx_checksum_offload()),
+            tx_checksum_offload: E1000_DEVICE.lock().as_ref().is_some_and(|d| d.tx_checksum_offload()),
+        }
+    }
+
+    /// Refill the pool from frames the interrupt handler queued, topped up
+    /// from the NIC's ring
+    fn refill(&mut self, now_ms: i64) {
+        let count = irq::receive(&mut self.rx_pool);
+
+        self.rx_count = count;
+        self.rx_next = 0;
+        if count > 0 {
+            RX_BATCHES.fetch_add(1, Ordering::Relaxed);
+            RX_PACKETS.fetch_add(count as u64, Ordering::Relaxed);
+
+            // Mirror ARP replies and note which hosts are answering
+            let mut arp = ARP_TABLE.lock();
+            for buf in &self.rx_pool[..count] {
+                arp.observe_frame(buf.as_slice(), now_ms);
+            }
+        }
     }
 }
 
 impl Device for E1000Device {
-    type RxToken<'a> = E1000RxToken;
+    type RxToken<'a> = E1000RxToken<'a>;
     type TxToken<'a> = E1000TxToken;
 
-    fn receive(&mut self, _timestamp: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
-        let mut device_guard = E1000_DEVICE.lock();
-        let device = device_guard.as_mut()?;
-
-        if device.has_packet() {
-            Some((E1000RxToken, E1000TxToken))
-        } else {
-            None
+    fn receive(&mut self, timestamp: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+        if self.rx_next >= self.rx_count {
+            self.refill(timestamp.total_millis());
+            if self.rx_count == 0 {
+                return None;
+            }
         }
+
+        let buf = &self.rx_pool[self.rx_next];
+        self.rx_next += 1;
+        Some((E1000RxToken { buf }, E1000TxToken))
     }
 
     fn transmit(&mut self, _timestamp: Instant) -> Option<Self::TxToken<'_>> {
@@ -37,26 +132,31 @@ impl Device for E1000Device {
         caps.medium = Medium::Ethernet;
         caps.max_transmission_unit = 1500;
         caps.max_burst_size = Some(1);
+        // smoltcp only computes the checksums the NIC doesn't handle
+        let checksum = match (self.rx_checksum_offload, self.tx_checksum_offload) {
+            (false, false) => Checksum::Both,
+            (true, false) => Checksum::Tx,
+            (false, true) => Checksum::Rx,
+            (true, true) => Checksum::None,
+        };
+        caps.checksum.ipv4 = checksum;
+        caps.checksum.udp = checksum;
+        caps.checksum.tcp = checksum;
         caps
     }
 }
 
-/// RX token for receiving packets
-pub struct E1000RxToken;
+/// RX token for receiving packets (borrows a pooled buffer)
+pub struct E1000RxToken<'a> {
+    buf: &'a PacketBuf,
+}
 
-impl phy::RxToken for E1000RxToken {
+impl phy::RxToken for E1000RxToken<'_> {
     fn consume<R, F>(self, f: F) -> R
     where
         F: FnOnce(&[u8]) -> R,
     {
-        let mut device_guard = E1000_DEVICE.lock();
-        if let Some(device) = device_guard.as_mut() {
-            if let Some(data) = device.receive() {
-                return f(&data);
-            }
-        }
-        // If no packet, return with empty slice
-        f(&[])
+        f(self.buf.as_slice())
     }
 }
 
@@ -1,5 +1,8 @@
 //! Network stack
 
+pub mod arp;
+pub mod bench;
 pub mod device;
 pub mod protocol;
+pub mod reliable;
 pub mod stack;
@@ -1,12 +1,20 @@
 //! Game network protocol handler
 
+use super::reliable::{ReliableSender, SeenSequences};
 use super::stack::NETWORK_STACK;
+use crate::drivers::e1000::PacketBuf;
+use crate::game::chat::CHAT_LOG;
+use crate::game::state::{set_network_mode, set_state, Elimination, GameState, MatchEvent, NetworkMode, MATCH_EVENTS};
+use crate::game::weapon::{Rarity, WeaponType};
 use crate::game::world::GAME_WORLD;
 use crate::serial_println;
 use alloc::vec::Vec;
 use alloc::string::String;
-use protocol::packets::{ClientInput, Packet, PlayerState, WorldStateDelta};
+use protocol::packets::{
+    ClientInput, Packet, PlayerState, WorldStateDelta, MAX_CHAT_LEN, PROTOCOL_VERSION, SERVER_SENDER_ID,
+};
 use smoltcp::wire::Ipv4Address;
+use spin::Mutex;
 
 /// Game protocol port
 pub const GAME_PORT: u16 = 5000;
@@ -14,14 +22,41 @@ pub const GAME_PORT: u16 = 5000;
 /// Server tick rate (Hz)
 pub const SERVER_TICK_RATE: u32 = 20;
 
+/// TCP status channel port (dedicated server only)
+pub const STATUS_PORT: u16 = 5001;
+
+/// Protocol version the server asked for when it last rejected our join
+static JOIN_REJECTED: Mutex<Option<u8>> = Mutex::new(None);
+
+/// Eliminations and harvests sent to clients, resent until each client
+/// acknowledges them
+static RELIABLE_OUT: Mutex<ReliableSender<(Ipv4Address, u16)>> = Mutex::new(ReliableSender::new());
+
+/// Kill feed sequence numbers already received from the server
+static KILL_FEED_SEEN: Mutex<SeenSequences> = Mutex::new(SeenSequences::new());
+
+/// Take the pending join rejection (server's expected protocol version), if any
+pub fn take_join_rejection() -> Option<u8> {
+    JOIN_REJECTED.lock().take()
+}
+
 /// Handle incoming game packets
+///
+/// Datagrams are received into a reused fixed buffer, so draining a burst of
+/// client inputs doesn't allocate per packet. The stack lock is released
+/// before each packet is handled so handlers can send replies.
 pub fn process_incoming() {
-    let mut stack_guard = NETWORK_STACK.lock();
-    if let Some(stack) = stack_guard.as_mut() {
-        while let Some((src_ip, src_port, data)) = stack.recv_udp() {
-            if let Some(packet) = Packet::decode(&data) {
-                handle_packet(src_ip, src_port, packet);
-            }
+    let mut buf = PacketBuf::new();
+    loop {
+        let received = match NETWORK_STACK.lock().as_mut() {
+            Some(stack) => stack.recv_udp_into(&mut buf),
+            None => return,
+        };
+        let Some((src_ip, src_port)) = received else {
+            return;
+        };
+        if let Some(packet) = Packet::decode(buf.as_slice()) {
+            handle_packet(src_ip, src_port, packet);
         }
     }
 }
@@ -31,60 +66,143 @@ fn handle_packet(src_ip: Ipv4Address, src_port: u16, packet: Packet) {
     match packet {
         Packet::ClientInput(input) => {
             // Update player state based on input
-            if let Some(world) = GAME_WORLD.lock().as_mut() {
+            if let Some(world) = GAME_WORLD.write().as_mut() {
                 world.apply_input(input.player_id, &input);
             }
         }
-        Packet::JoinRequest { name } => {
-            serial_println!("NET: Join request from {}:{} - {}", src_ip, src_port, name);
+        Packet::JoinRequest { version, name } => {
+            serial_println!("NET: Join request from {}:{} - {} (protocol v{})", src_ip, src_port, name, version);
+            if version != PROTOCOL_VERSION {
+                serial_println!("NET: Rejecting {}: server speaks protocol v{}", name, PROTOCOL_VERSION);
+                send_join_reject(src_ip, src_port);
+                return;
+            }
             // Assign player ID and send response
-            if let Some(world) = GAME_WORLD.lock().as_mut() {
+            if let Some(world) = GAME_WORLD.write().as_mut() {
                 if let Some(player_id) = world.add_player(&name, src_ip, src_port) {
                     send_join_response(src_ip, src_port, player_id);
                 }
             }
         }
-        Packet::JoinResponse { player_id } => {
-            serial_println!("NET: Joined game with ID {}", player_id);
-            if let Some(world) = GAME_WORLD.lock().as_mut() {
+        Packet::JoinResponse { player_id, version } => {
+            serial_println!("NET: Joined game with ID {} (protocol v{})", player_id, version);
+            KILL_FEED_SEEN.lock().clear();
+            if let Some(world) = GAME_WORLD.write().as_mut() {
                 world.local_player_id = Some(player_id);
             }
         }
         Packet::WorldStateDelta(delta) => {
             // Client received world update - apply interpolation
-            if let Some(world) = GAME_WORLD.lock().as_mut() {
+            if let Some(world) = GAME_WORLD.write().as_mut() {
                 if !world.is_server {
                     world.apply_delta(&delta);
                 }
             }
         }
         Packet::Discovery => {
-            if let Some(world) = GAME_WORLD.lock().as_ref() {
+            if let Some(world) = GAME_WORLD.read().as_ref() {
                 if world.is_server {
                     let count = world.alive_count() as u8;
                     send_discovery_response(src_ip, src_port, "BattleRoyale Server", count);
                 }
             }
         }
+        Packet::JoinReject { expected_version } => {
+            serial_println!(
+                "NET: Join rejected: server speaks protocol v{}, client v{}",
+                expected_version,
+                PROTOCOL_VERSION
+            );
+            // Back to the server-select screen, which shows the error
+            *JOIN_REJECTED.lock() = Some(expected_version);
+            set_network_mode(NetworkMode::Offline);
+            set_state(GameState::ServerSelect);
+        }
         Packet::DiscoveryResponse {
             server_name,
             player_count,
+            version,
         } => {
             serial_println!(
-                "NET: Found server '{}' with {} players at {}",
+                "NET: Found server '{}' with {} players at {} (protocol v{}{})",
                 server_name,
                 player_count,
-                src_ip
+                src_ip,
+                version,
+                if version == PROTOCOL_VERSION { "" } else { ", incompatible" }
             );
             // Server discovery logged; UI integration handled by server select screen
         }
+        Packet::Chat { sender_id, message } => {
+            // Only servers announce; clients show what they receive
+            let is_server = GAME_WORLD.read().as_ref().is_some_and(|w| w.is_server);
+            if !is_server {
+                serial_println!("CHAT: [{}] {}", sender_id, message);
+                CHAT_LOG.lock().push(sender_id, &message);
+            }
+        }
+        Packet::KillFeed { seq, killer_id, victim_id, weapon_type, rarity } => {
+            let is_server = GAME_WORLD.read().as_ref().is_some_and(|w| w.is_server);
+            if is_server {
+                return;
+            }
+            // Ack every copy, since an earlier ack may have been the one lost
+            send_ack(src_ip, src_port, seq);
+            if KILL_FEED_SEEN.lock().insert(seq)
+                && let (Some(weapon_type), Some(rarity)) = (WeaponType::from_u8(weapon_type), Rarity::from_u8(rarity))
+            {
+                MATCH_EVENTS.lock().push(MatchEvent::Elimination(Elimination {
+                    killer_id,
+                    victim_id,
+                    weapon_type,
+                    rarity,
+                }));
+            }
+        }
+        Packet::Harvest { seq, index, health } => {
+            let mut world_guard = GAME_WORLD.write();
+            let Some(world) = world_guard.as_mut().filter(|w| !w.is_server) else {
+                return;
+            };
+            // Applying a repeat is harmless, so there's no duplicate check
+            world.apply_harvest(index, health);
+            drop(world_guard);
+            send_ack(src_ip, src_port, seq);
+        }
+        Packet::Ack { seq } => {
+            RELIABLE_OUT.lock().ack(seq, (src_ip, src_port));
+        }
         _ => {}
     }
 }
 
 /// Send join response to a new player
 fn send_join_response(dest_ip: Ipv4Address, dest_port: u16, player_id: u8) {
-    let packet = Packet::JoinResponse { player_id };
+    let packet = Packet::JoinResponse {
+        player_id,
+        version: PROTOCOL_VERSION,
+    };
+    let data = packet.encode();
+
+    if let Some(stack) = NETWORK_STACK.lock().as_mut() {
+        stack.send_udp(dest_ip, dest_port, &data);
+    }
+}
+
+/// Acknowledge a resent-until-acked packet from the server
+fn send_ack(dest_ip: Ipv4Address, dest_port: u16, seq: u16) {
+    let data = Packet::Ack { seq }.encode();
+
+    if let Some(stack) = NETWORK_STACK.lock().as_mut() {
+        stack.send_udp(dest_ip, dest_port, &data);
+    }
+}
+
+/// Tell a client built for another protocol version which one we expect
+fn send_join_reject(dest_ip: Ipv4Address, dest_port: u16) {
+    let packet = Packet::JoinReject {
+        expected_version: PROTOCOL_VERSION,
+    };
     let data = packet.encode();
 
     if let Some(stack) = NETWORK_STACK.lock().as_mut() {
@@ -92,11 +210,25 @@ fn send_join_response(dest_ip: Ipv4Address, dest_port: u16, player_id: u8) {
     }
 }
 
+/// Ask a server to let us join
+pub fn send_join_request(server_ip: Ipv4Address, port: u16, name: &str) {
+    let packet = Packet::JoinRequest {
+        version: PROTOCOL_VERSION,
+        name: String::from(name),
+    };
+    let data = packet.encode();
+
+    if let Some(stack) = NETWORK_STACK.lock().as_mut() {
+        stack.send_udp(server_ip, port, &data);
+    }
+}
+
 /// Send discovery response
 fn send_discovery_response(dest_ip: Ipv4Address, dest_port: u16, name: &str, count: u8) {
     let packet = Packet::DiscoveryResponse {
         server_name: String::from(name),
         player_count: count,
+        version: PROTOCOL_VERSION,
     };
     let data = packet.encode();
 
@@ -118,7 +250,7 @@ pub fn broadcast_discovery() {
 
 /// Broadcast world state delta to all connected clients
 pub fn broadcast_world_state() {
-    let world_guard = GAME_WORLD.lock();
+    let world_guard = GAME_WORLD.read();
     if let Some(world) = world_guard.as_ref() {
         let delta = world.get_delta();
         let packet = Packet::WorldStateDelta(delta);
@@ -126,21 +258,7 @@ pub fn broadcast_world_state() {
 
         drop(world_guard);
 
-        // Get list of connected clients
-        let clients: Vec<(Ipv4Address, u16)> = {
-            let world_guard = GAME_WORLD.lock();
-            if let Some(world) = world_guard.as_ref() {
-                world
-                    .players
-                    .iter()
-                    .filter(|p| p.connected)
-                    .map(|p| (p.address, p.port))
-                    .collect()
-            } else {
-                Vec::new()
-            }
-        };
-
+        let clients = connected_clients();
         if let Some(stack) = NETWORK_STACK.lock().as_mut() {
             for (ip, port) in clients {
                 stack.send_udp(ip, port, &data);
@@ -149,6 +267,116 @@ pub fn broadcast_world_state() {
     }
 }
 
+/// Send eliminations and harvests recorded since the last call to all
+/// connected clients, and resend earlier ones that haven't been acknowledged
+pub fn broadcast_events() {
+    let (kills, harvests) = match GAME_WORLD.write().as_mut() {
+        Some(world) => (world.take_pending_kills(), world.take_pending_harvests()),
+        None => return,
+    };
+
+    let mut out = RELIABLE_OUT.lock();
+    if !kills.is_empty() || !harvests.is_empty() {
+        let clients = connected_clients();
+        for kill in &kills {
+            out.queue(clients.clone(), |seq| {
+                Packet::KillFeed {
+                    seq,
+                    killer_id: kill.killer_id,
+                    victim_id: kill.victim_id,
+                    weapon_type: kill.weapon_type as u8,
+                    rarity: kill.rarity as u8,
+                }
+                .encode()
+            });
+        }
+        for &(index, health) in &harvests {
+            out.queue(clients.clone(), |seq| Packet::Harvest { seq, index, health }.encode());
+        }
+    }
+
+    if let Some(stack) = NETWORK_STACK.lock().as_mut() {
+        out.poll(|(ip, port), data| {
+            stack.send_udp(ip, port, data);
+        });
+    }
+}
+
+/// Addresses of all connected clients
+fn connected_clients() -> Vec<(Ipv4Address, u16)> {
+    let world_guard = GAME_WORLD.read();
+    if let Some(world) = world_guard.as_ref() {
+        world
+            .players
+            .iter()
+            .filter(|p| p.connected)
+            .map(|p| (p.address, p.port))
+            .collect()
+    } else {
+        Vec::new()
+    }
+}
+
+/// Send a server announcement to every connected client
+/// Returns the number of clients it was sent to, or None if the message is
+/// empty or longer than MAX_CHAT_LEN bytes
+pub fn broadcast_chat(message: &str) -> Option<usize> {
+    if message.is_empty() || message.len() > MAX_CHAT_LEN {
+        return None;
+    }
+
+    let packet = Packet::Chat {
+        sender_id: SERVER_SENDER_ID,
+        message: String::from(message),
+    };
+    let data = packet.encode();
+
+    let clients = connected_clients();
+    if let Some(stack) = NETWORK_STACK.lock().as_mut() {
+        for &(ip, port) in &clients {
+            stack.send_udp(ip, port, &data);
+        }
+    }
+    Some(clients.len())
+}
+
+/// Format the plaintext status document served on the TCP status channel
+pub fn format_status(out: &mut String, uptime_secs: u64, tick_rate: u32) {
+    use core::fmt::Write;
+
+    let (players, alive, storm_phase, storm_shrinking) = match GAME_WORLD.read().as_ref() {
+        Some(world) => (
+            world.players.len(),
+            world.alive_count(),
+            world.storm.phase,
+            world.storm.shrinking,
+        ),
+        None => (0, 0, 0, false),
+    };
+
+    out.clear();
+    let _ = write!(
+        out,
+        "uptime: {}\nplayers: {}\nalive: {}\ntick_rate: {}\nstorm_phase: {}\nstorm_state: {}\n",
+        uptime_secs,
+        players,
+        alive,
+        tick_rate,
+        storm_phase,
+        if storm_shrinking { "shrinking" } else { "waiting" }
+    );
+}
+
+/// Refresh the status channel document (world lock is released before the stack is locked)
+pub fn update_status(uptime_secs: u64, tick_rate: u32) {
+    let mut document = String::new();
+    format_status(&mut document, uptime_secs, tick_rate);
+
+    if let Some(stack) = NETWORK_STACK.lock().as_mut() {
+        stack.set_status_document(&document);
+    }
+}
+
 /// Send client input to server
 pub fn send_input(input: &ClientInput, server_ip: Ipv4Address) {
     let packet = Packet::ClientInput(input.clone());
@@ -0,0 +1,180 @@
+//! Acknowledged delivery for server messages that must not be lost
+//!
+//! World state goes out as unreliable deltas that the next one supersedes, but
+//! a dropped elimination would never show up in a client's kill feed. Such
+//! messages carry a sequence number: the server resends each one to every
+//! client that hasn't acknowledged it, and clients drop the duplicates.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+/// Polls between resends of an unacknowledged message
+pub const RESEND_INTERVAL: u32 = 4;
+
+/// Sends per client before a message is given up on
+pub const MAX_SENDS: u32 = 8;
+
+/// Sequence numbers a receiver remembers for duplicate detection
+const SEEN_WINDOW: usize = 32;
+
+/// One message and the clients that still owe an acknowledgement
+struct Outgoing<A> {
+    seq: u16,
+    data: Vec<u8>,
+    unacked: Vec<A>,
+    sends: u32,
+    /// Polls until the next send
+    wait: u32,
+}
+
+/// Sender side: queued messages, resent until acked or out of retries
+pub struct ReliableSender<A> {
+    outgoing: Vec<Outgoing<A>>,
+    next_seq: u16,
+}
+
+impl<A: Copy + PartialEq> ReliableSender<A> {
+    pub const fn new() -> Self {
+        Self {
+            outgoing: Vec::new(),
+            next_seq: 0,
+        }
+    }
+
+    /// Queue a message for the given recipients; `encode` builds the
+    /// datagram for the assigned sequence number
+    pub fn queue(&mut self, recipients: Vec<A>, encode: impl FnOnce(u16) -> Vec<u8>) -> u16 {
+        let seq = self.next_seq;
+        self.next_seq = self.next_seq.wrapping_add(1);
+        if !recipients.is_empty() {
+            self.outgoing.push(Outgoing {
+                seq,
+                data: encode(seq),
+                unacked: recipients,
+                sends: 0,
+                wait: 0,
+            });
+        }
+        seq
+    }
+
+    /// Record an acknowledgement from one recipient
+    pub fn ack(&mut self, seq: u16, from: A) {
+        if let Some(message) = self.outgoing.iter_mut().find(|m| m.seq == seq) {
+            message.unacked.retain(|&a| a != from);
+        }
+    }
+
+    /// Send everything that is due: new messages straight away, then a resend
+    /// every RESEND_INTERVAL polls until acked or MAX_SENDS is reached
+    pub fn poll(&mut self, mut send: impl FnMut(A, &[u8])) {
+        for message in &mut self.outgoing {
+            if message.wait == 0 {
+                for &to in &message.unacked {
+                    send(to, &message.data);
+                }
+                message.sends += 1;
+                message.wait = RESEND_INTERVAL;
+            }
+            message.wait -= 1;
+        }
+        self.outgoing.retain(|m| !m.unacked.is_empty() && m.sends < MAX_SENDS);
+    }
+}
+
+impl<A: Copy + PartialEq> Default for ReliableSender<A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Receiver side: recently seen sequence numbers
+pub struct SeenSequences {
+    seen: [Option<u16>; SEEN_WINDOW],
+    head: usize,
+}
+
+impl SeenSequences {
+    pub const fn new() -> Self {
+        Self {
+            seen: [None; SEEN_WINDOW],
+            head: 0,
+        }
+    }
+
+    /// Record a sequence number; false if it was already seen (a resend)
+    pub fn insert(&mut self, seq: u16) -> bool {
+        if self.seen.contains(&Some(seq)) {
+            return false;
+        }
+        self.seen[self.head] = Some(seq);
+        self.head = (self.head + 1) % SEEN_WINDOW;
+        true
+    }
+
+    /// Forget everything (a new server numbers its messages from zero)
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+}
+
+impl Default for SeenSequences {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_resend_until_acked() {
+        let mut sender = ReliableSender::new();
+        let seq = sender.queue(vec![1u8, 2], |seq| vec![seq as u8]);
+        let mut sent = Vec::new();
+
+        // First poll sends to everyone, the next few wait
+        sender.poll(|to, _| sent.push(to));
+        assert_eq!(sent, [1, 2]);
+        for _ in 1..RESEND_INTERVAL {
+            sender.poll(|to, _| sent.push(to));
+        }
+        assert_eq!(sent.len(), 2);
+
+        // Only the client that hasn't acked hears it again
+        sender.ack(seq, 1);
+        sent.clear();
+        sender.poll(|to, _| sent.push(to));
+        assert_eq!(sent, [2]);
+
+        sender.ack(seq, 2);
+        for _ in 0..RESEND_INTERVAL * 2 {
+            sender.poll(|_, _| panic!("acked message resent"));
+        }
+    }
+
+    #[test]
+    fn test_gives_up_after_max_sends() {
+        let mut sender = ReliableSender::new();
+        sender.queue(vec![7u8], |_| vec![0]);
+        let mut sends = 0;
+        for _ in 0..RESEND_INTERVAL * MAX_SENDS * 2 {
+            sender.poll(|_, _| sends += 1);
+        }
+        assert_eq!(sends, MAX_SENDS);
+    }
+
+    #[test]
+    fn test_seen_sequences_drop_duplicates() {
+        let mut seen = SeenSequences::new();
+        assert!(seen.insert(5));
+        assert!(!seen.insert(5));
+        assert!(seen.insert(6));
+
+        seen.clear();
+        assert!(seen.insert(5));
+    }
+}
@@ -1,16 +1,72 @@
 //! Network stack wrapper using smoltcp
 
-use super::device::E1000Device;
-use crate::drivers::e1000::{E1000_DEVICE, DeviceStats};
+use super::arp::{ArpEntry, ARP_TABLE};
+use super::device::{self, E1000Device};
+use crate::drivers::e1000::{E1000_DEVICE, DeviceStats, PacketBuf};
 use crate::serial_println;
+use alloc::string::String;
 use alloc::vec;
+use alloc::vec::Vec;
 use smoltcp::iface::{Config, Interface, SocketHandle, SocketSet};
 use smoltcp::socket::udp::{self, PacketBuffer as UdpPacketBuffer, PacketMetadata as UdpPacketMetadata};
 use smoltcp::socket::icmp::{self, PacketBuffer as IcmpPacketBuffer, PacketMetadata as IcmpPacketMetadata};
+use smoltcp::socket::tcp::{self, SocketBuffer as TcpSocketBuffer};
 use smoltcp::time::Instant;
 use smoltcp::wire::{EthernetAddress, HardwareAddress, IpAddress, IpCidr, Ipv4Address, Icmpv4Repr, Icmpv4Packet};
 use spin::Mutex;
 
+/// Static IPv4 configuration
+#[derive(Debug, Clone, Copy)]
+pub struct NetConfig {
+    pub ip: Ipv4Address,
+    pub prefix_len: u8,
+    /// Default route for off-subnet destinations
+    pub gateway: Option<Ipv4Address>,
+}
+
+impl NetConfig {
+    /// QEMU user networking (slirp) defaults
+    pub const QEMU_USER: Self = Self {
+        ip: Ipv4Address::new(10, 0, 2, 15),
+        prefix_len: 24,
+        gateway: Some(Ipv4Address::new(10, 0, 2, 2)),
+    };
+
+    /// Whether `dest` is on the local subnet (reachable without the gateway)
+    pub fn is_local(&self, dest: Ipv4Address) -> bool {
+        let mask = u32::MAX.checked_shl(32 - self.prefix_len as u32).unwrap_or(0);
+        (u32::from(dest) & mask) == (u32::from(self.ip) & mask)
+    }
+}
+
+impl Default for NetConfig {
+    fn default() -> Self {
+        Self::QEMU_USER
+    }
+}
+
+/// Maximum concurrent status channel connections
+pub const MAX_STATUS_CONNECTIONS: usize = 2;
+
+/// Status connections older than this are aborted (ms of poll time)
+pub const STATUS_IDLE_TIMEOUT_MS: i64 = 5_000;
+
+/// One listening/serving slot of the TCP status channel
+struct StatusConnection {
+    handle: SocketHandle,
+    /// Poll timestamp when the connection was accepted
+    accepted_ms: Option<i64>,
+    /// Whether the status document has been queued
+    served: bool,
+}
+
+/// Plaintext TCP status channel (one document per connection, then close)
+struct StatusServer {
+    port: u16,
+    connections: Vec<StatusConnection>,
+    document: String,
+}
+
 /// Network stack state
 pub struct NetworkStack {
     pub interface: Interface,
@@ -18,28 +74,29 @@ pub struct NetworkStack {
     pub sockets: SocketSet<'static>,
     pub udp_handle: Option<SocketHandle>,
     pub icmp_handle: Option<SocketHandle>,
+    pub config: NetConfig,
+    status: Option<StatusServer>,
 }
 
 impl NetworkStack {
     /// Create a new network stack
-    pub fn new(mac: [u8; 6], ip: Ipv4Address) -> Self {
+    pub fn new(mac: [u8; 6], config: NetConfig) -> Self {
         let device = E1000Device::new();
 
         // Create interface config
-        let config = Config::new(HardwareAddress::Ethernet(EthernetAddress(mac)));
+        let iface_config = Config::new(HardwareAddress::Ethernet(EthernetAddress(mac)));
 
-        let mut interface = Interface::new(config, &mut E1000Device::new(), Instant::from_millis(0));
+        let mut interface = Interface::new(iface_config, &mut E1000Device::new(), Instant::from_millis(0));
 
         // Set IP address
         interface.update_ip_addrs(|addrs| {
-            addrs.push(IpCidr::new(IpAddress::Ipv4(ip), 24)).ok();
+            addrs.push(IpCidr::new(IpAddress::Ipv4(config.ip), config.prefix_len)).ok();
         });
 
-        // Set default gateway (for QEMU user networking)
-        interface
-            .routes_mut()
-            .add_default_ipv4_route(Ipv4Address::new(10, 0, 2, 2))
-            .ok();
+        // Route off-subnet traffic via the gateway
+        if let Some(gateway) = config.gateway {
+            interface.routes_mut().add_default_ipv4_route(gateway).ok();
+        }
 
         // Create socket set
         let mut sockets = SocketSet::new(vec![]);
@@ -62,6 +119,8 @@ impl NetworkStack {
             sockets,
             udp_handle: None,
             icmp_handle,
+            config,
+            status: None,
         }
     }
 
@@ -87,15 +146,93 @@ impl NetworkStack {
         handle
     }
 
+    /// Open the TCP status channel on `port` (bounded to MAX_STATUS_CONNECTIONS)
+    pub fn enable_status_server(&mut self, port: u16) {
+        let mut connections = Vec::with_capacity(MAX_STATUS_CONNECTIONS);
+        for _ in 0..MAX_STATUS_CONNECTIONS {
+            let rx_buffer = TcpSocketBuffer::new(vec![0; 256]);
+            let tx_buffer = TcpSocketBuffer::new(vec![0; 1024]);
+            let mut socket = tcp::Socket::new(rx_buffer, tx_buffer);
+            if socket.listen(port).is_err() {
+                serial_println!("NET: Failed to listen on TCP port {}", port);
+                return;
+            }
+            connections.push(StatusConnection {
+                handle: self.sockets.add(socket),
+                accepted_ms: None,
+                served: false,
+            });
+        }
+
+        self.status = Some(StatusServer {
+            port,
+            connections,
+            document: String::new(),
+        });
+        serial_println!("NET: TCP status channel listening on port {}", port);
+    }
+
+    /// Replace the document served on the status channel
+    pub fn set_status_document(&mut self, document: &str) {
+        if let Some(status) = self.status.as_mut() {
+            status.document.clear();
+            status.document.push_str(document);
+        }
+    }
+
     /// Poll the network stack
     pub fn poll(&mut self, timestamp_ms: i64) {
         let timestamp = Instant::from_millis(timestamp_ms);
+        device::record_poll();
         self.interface
             .poll(timestamp, &mut self.device, &mut self.sockets);
+        self.service_status(timestamp_ms);
+    }
+
+    /// Accept, serve and reap status channel connections
+    fn service_status(&mut self, timestamp_ms: i64) {
+        let status = match self.status.as_mut() {
+            Some(s) => s,
+            None => return,
+        };
+
+        for conn in &mut status.connections {
+            let socket = self.sockets.get_mut::<tcp::Socket>(conn.handle);
+
+            // Closed (served, reset or reaped): go back to listening
+            if !socket.is_open() {
+                conn.accepted_ms = None;
+                conn.served = false;
+                let _ = socket.listen(status.port);
+                continue;
+            }
+
+            if !socket.is_active() {
+                continue;
+            }
+
+            let accepted = *conn.accepted_ms.get_or_insert(timestamp_ms);
+            if timestamp_ms - accepted > STATUS_IDLE_TIMEOUT_MS {
+                socket.abort();
+                continue;
+            }
+
+            if !conn.served && socket.can_send() {
+                let _ = socket.send_slice(status.document.as_bytes());
+                socket.close();
+                conn.served = true;
+            }
+        }
     }
 
     /// Send a UDP packet
     pub fn send_udp(&mut self, dest_ip: Ipv4Address, dest_port: u16, data: &[u8]) -> bool {
+        // A destination that keeps ignoring us may sit behind a stale ARP entry
+        if !dest_ip.is_broadcast() && ARP_TABLE.lock().note_send(dest_ip.octets()) {
+            serial_println!("NET: No reply from {}, re-resolving ARP", dest_ip);
+            self.flush_arp();
+        }
+
         if let Some(handle) = self.udp_handle {
             let socket = self.sockets.get_mut::<udp::Socket>(handle);
             let endpoint = (IpAddress::Ipv4(dest_ip), dest_port);
@@ -122,6 +259,46 @@ impl NetworkStack {
         None
     }
 
+    /// Receive a UDP packet into a caller-owned buffer (no allocation)
+    /// Returns the source address; payloads larger than the buffer are dropped
+    pub fn recv_udp_into(&mut self, buf: &mut PacketBuf) -> Option<(Ipv4Address, u16)> {
+        let handle = self.udp_handle?;
+        let socket = self.sockets.get_mut::<udp::Socket>(handle);
+        while socket.can_recv() {
+            match socket.recv_slice(&mut buf.data) {
+                Ok((size, meta)) => {
+                    buf.len = size;
+                    let IpAddress::Ipv4(ip) = meta.endpoint.addr;
+                    return Some((ip, meta.endpoint.port));
+                }
+                // Oversized datagrams are dropped by smoltcp; try the next one
+                Err(_) => continue,
+            }
+        }
+        None
+    }
+
+    /// Drop all cached link-layer addresses so they are re-resolved on next send
+    pub fn flush_arp(&mut self) {
+        // Updating the address list flushes smoltcp's neighbor cache
+        self.interface.update_ip_addrs(|_| {});
+        ARP_TABLE.lock().clear();
+    }
+
+    /// Snapshot of the ARP entries seen on the wire
+    pub fn arp_table(&self) -> Vec<ArpEntry> {
+        ARP_TABLE.lock().entries().copied().collect()
+    }
+
+    /// Next hop for a destination (itself on-link, otherwise the gateway)
+    pub fn next_hop(&self, dest: Ipv4Address) -> Option<Ipv4Address> {
+        if self.config.is_local(dest) {
+            Some(dest)
+        } else {
+            self.config.gateway
+        }
+    }
+
     /// Check link status
     pub fn link_status(&self) -> bool {
         let device = E1000_DEVICE.lock();
@@ -169,15 +346,16 @@ pub fn init() {
         let mac = device.mac_address();
         drop(device_guard);
 
-        // Use 10.0.2.15 for QEMU user networking
-        let ip = Ipv4Address::new(10, 0, 2, 15);
+        // Static config for QEMU user networking
+        let config = NetConfig::default();
 
-        let mut stack = NetworkStack::new(mac, ip);
+        let mut stack = NetworkStack::new(mac, config);
         stack.add_udp_socket(5000); // Game protocol port
 
         // Send a test packet to trigger ARP resolution for gateway
-        let gateway = Ipv4Address::new(10, 0, 2, 2);
-        stack.send_udp(gateway, 1234, b"test");
+        if let Some(gateway) = config.gateway {
+            stack.send_udp(gateway, 1234, b"test");
+        }
 
         // Poll to process ARP handshake
         for i in 0..1000 {
@@ -190,7 +368,7 @@ pub fn init() {
 
         *NETWORK_STACK.lock() = Some(stack);
 
-        serial_println!("NET: Stack initialized with IP 10.0.2.15");
+        serial_println!("NET: Stack initialized with IP {}/{}", config.ip, config.prefix_len);
     }
 }
 
@@ -201,6 +379,13 @@ pub fn poll(timestamp_ms: i64) {
     }
 }
 
+/// Open the TCP status channel (call once after init)
+pub fn enable_status_server(port: u16) {
+    if let Some(stack) = NETWORK_STACK.lock().as_mut() {
+        stack.enable_status_server(port);
+    }
+}
+
 /// Check if network stack is initialized
 pub fn is_initialized() -> bool {
     NETWORK_STACK.lock().is_some()
@@ -208,10 +393,20 @@ pub fn is_initialized() -> bool {
 
 /// Get local IP address
 pub fn local_ip() -> Option<[u8; 4]> {
-    // Return the fixed IP we use for QEMU user networking
-    if is_initialized() {
-        Some([10, 0, 2, 15])
-    } else {
-        None
+    NETWORK_STACK.lock().as_ref().map(|stack| stack.config.ip.octets())
+}
+
+/// Flush the ARP cache (debug/admin)
+pub fn flush_arp() {
+    if let Some(stack) = NETWORK_STACK.lock().as_mut() {
+        stack.flush_arp();
+    }
+}
+
+/// Snapshot of the ARP cache (debug/admin)
+pub fn arp_table() -> Vec<ArpEntry> {
+    match NETWORK_STACK.lock().as_ref() {
+        Some(stack) => stack.arp_table(),
+        None => Vec::new(),
     }
 }
@@ -1,4 +1,5 @@
 //! Symmetric Multi-Processing (SMP) support
 
 pub mod scheduler;
+pub mod stats;
 pub mod sync;
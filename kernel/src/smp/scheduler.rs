@@ -1,6 +1,9 @@
 //! Core assignment and scheduling
 
+use super::stats::{self, FrameStats};
 use crate::boot::SMP_REQUEST;
+use crate::drivers::apic;
+use crate::interrupts;
 use crate::serial_println;
 use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use limine::mp::Cpu;
@@ -11,9 +14,9 @@ use spin::Mutex;
 pub enum CoreRole {
     /// Main game logic, input, frame orchestration (Core 0)
     GameLogic,
-    /// Triangle rasterization (Cores 1-3)
-    Rasterizer(u8), // Which rasterizer (0, 1, 2)
-    /// Network polling and packet processing (Core 4)
+    /// Triangle rasterization (Cores 1..render_core_count())
+    Rasterizer(u8), // Which rasterizer (0, 1, 2, ...)
+    /// Network polling and packet processing (the core after the render cores)
     Network,
 }
 
@@ -35,6 +38,7 @@ impl CoreData {
 }
 
 /// Global core data array (max 8 cores)
+/// Roles shown are for 5 CPUs; `init` reassigns them for the actual count.
 static CORE_DATA: [Mutex<CoreData>; 8] = [
     Mutex::new(CoreData::new(0, CoreRole::GameLogic)),
     Mutex::new(CoreData::new(1, CoreRole::Rasterizer(0))),
@@ -46,14 +50,24 @@ static CORE_DATA: [Mutex<CoreData>; 8] = [
     Mutex::new(CoreData::new(7, CoreRole::GameLogic)), // Unused
 ];
 
+/// Most cores that take part in tile rasterization (Core 0 + rasterizer cores)
+pub const MAX_RENDER_CORES: usize = 8;
+
+/// Fewest CPUs at which one core is set aside for networking
+const NETWORK_CORE_MIN_CPUS: usize = 5;
+
 /// Number of active cores
 static ACTIVE_CORES: AtomicU32 = AtomicU32::new(1);
 
+/// Number of cores that render (Core 0 + rasterizer cores)
+static RENDER_CORES: AtomicU32 = AtomicU32::new(1);
+
 /// Frame counter for synchronization
 pub static FRAME_COUNTER: AtomicU32 = AtomicU32::new(0);
 
-/// Flag to signal render cores to start
-pub static RENDER_START: AtomicBool = AtomicBool::new(false);
+/// Render passes started so far; rasterizer cores run one pass per increment
+/// (a frame may take several, e.g. occluders first, then the rest)
+static RENDER_PASSES: AtomicU32 = AtomicU32::new(0);
 
 /// Flag to signal all cores to stop
 pub static SHUTDOWN: AtomicBool = AtomicBool::new(false);
@@ -67,22 +81,70 @@ pub fn cpu_count() -> u32 {
     }
 }
 
+/// Get the number of cores that render (1 until `init` runs)
+pub fn render_core_count() -> usize {
+    RENDER_CORES.load(Ordering::Acquire) as usize
+}
+
+/// Render cores for `cpu_count` CPUs
+/// With enough CPUs the core after the render cores polls the network;
+/// below that every core renders. A single CPU renders serially on Core 0.
+fn render_cores_for(cpu_count: usize) -> usize {
+    let render = if cpu_count >= NETWORK_CORE_MIN_CPUS {
+        cpu_count - 1
+    } else {
+        cpu_count
+    };
+    render.clamp(1, MAX_RENDER_CORES)
+}
+
+/// Role of `core_id` when `render_cores` cores render, None for idle cores
+fn role_for(core_id: usize, render_cores: usize, cpu_count: usize) -> Option<CoreRole> {
+    if core_id == 0 {
+        Some(CoreRole::GameLogic)
+    } else if core_id < render_cores {
+        Some(CoreRole::Rasterizer((core_id - 1) as u8))
+    } else if core_id == render_cores && cpu_count >= NETWORK_CORE_MIN_CPUS {
+        Some(CoreRole::Network)
+    } else {
+        None
+    }
+}
+
+/// Size the render barriers for `render_cores` and record the count
+fn configure_render_cores(render_cores: usize) {
+    RENDER_CORES.store(render_cores as u32, Ordering::Release);
+    crate::smp::sync::set_render_cores(render_cores);
+}
+
 /// Initialize SMP and start worker cores
 pub fn init() {
     let response = match SMP_REQUEST.get_response() {
         Some(r) => r,
         None => {
             serial_println!("SMP: No SMP response, running single-core");
+            configure_render_cores(1);
             return;
         }
     };
 
     let cpus = response.cpus();
     let cpu_count = cpus.len();
+    let render_cores = render_cores_for(cpu_count);
 
-    serial_println!("SMP: {} CPUs available", cpu_count);
+    serial_println!("SMP: {} CPUs available, {} render cores", cpu_count, render_cores);
     ACTIVE_CORES.store(cpu_count as u32, Ordering::Release);
 
+    // Barriers must be sized before any worker core can reach them
+    configure_render_cores(render_cores);
+
+    // Render cores HLT between frames if start_render can wake them
+    if apic::init() {
+        serial_println!("SMP: render cores halt between frames");
+    } else {
+        serial_println!("SMP: no local APIC, render cores spin between frames");
+    }
+
     // Start worker cores (skip BSP which is core 0)
     for (i, cpu) in cpus.iter().enumerate() {
         if i == 0 {
@@ -95,13 +157,17 @@ pub fn init() {
         serial_println!("SMP: Starting core {}", core_id);
 
         // Set up the core's entry point based on role
-        match core_id {
-            1..=3 => {
+        let role = role_for(i, render_cores, cpu_count);
+        if let (Some(role), Some(data)) = (role, CORE_DATA.get(i)) {
+            data.lock().role = role;
+        }
+        match role {
+            Some(CoreRole::Rasterizer(_)) => {
                 // Rasterizer cores
                 cpu.goto_address
                     .write(rasterizer_entry);
             }
-            4 => {
+            Some(CoreRole::Network) => {
                 // Network core
                 cpu.goto_address
                     .write(network_entry);
@@ -127,25 +193,78 @@ unsafe extern "C" fn rasterizer_entry(cpu: &Cpu) -> ! {
         data.lock().running.store(true, Ordering::Release);
     }
 
+    let park = interrupts::init_render_core();
+    let mut waiter = PassWaiter::default();
     loop {
         // Wait for render signal
-        while !RENDER_START.load(Ordering::Acquire) {
-            if SHUTDOWN.load(Ordering::Acquire) {
-                halt_loop();
-            }
-            core::hint::spin_loop();
-        }
+        wait_for_pass(&mut waiter, park);
 
-        // Do rendering work
-        crate::app::render_worker(rasterizer_id);
+        // Help transform and bin the frame's meshes, then rasterize tiles
+        // (stats are indexed by physical core id)
+        crate::app::bin_worker();
+        crate::app::render_worker(core_id as u8);
 
         // Signal completion via barrier
         crate::smp::sync::RENDER_BARRIER.wait();
+    }
+}
+
+/// A render core's progress through the passes core 0 signals
+///
+/// Core 0 can't start another pass before every render core has passed
+/// RENDER_BARRIER, so at most one is ever pending.
+#[derive(Debug, Default)]
+struct PassWaiter {
+    /// Passes this core has run
+    passes: u32,
+}
+
+impl PassWaiter {
+    /// Whether a pass this core hasn't run is signaled
+    fn is_pending(&self, signaled: u32) -> bool {
+        signaled != self.passes
+    }
+
+    /// Claim the pending pass, if there is one
+    fn try_start(&mut self, signaled: u32) -> bool {
+        let pending = self.is_pending(signaled);
+        if pending {
+            self.passes = self.passes.wrapping_add(1);
+        }
+        pending
+    }
+}
 
-        // Wait for next frame
-        while RENDER_START.load(Ordering::Acquire) {
+/// Wait until core 0 signals a render pass, then claim it
+///
+/// With `park` the core HLTs until start_render's wakeup IPI instead of
+/// spinning. Interrupts are only enabled by the `sti; hlt` pair, which takes
+/// effect atomically, so an IPI sent between the check and the HLT stays
+/// pending and ends the HLT at once instead of being lost.
+fn wait_for_pass(waiter: &mut PassWaiter, park: bool) {
+    loop {
+        if waiter.try_start(RENDER_PASSES.load(Ordering::Acquire)) {
+            return;
+        }
+        if SHUTDOWN.load(Ordering::Acquire) {
+            halt_loop();
+        }
+        if !park {
             core::hint::spin_loop();
+            continue;
         }
+        x86_64::instructions::interrupts::disable();
+        if !waiter.is_pending(RENDER_PASSES.load(Ordering::Acquire)) && !SHUTDOWN.load(Ordering::Acquire) {
+            x86_64::instructions::interrupts::enable_and_hlt();
+            x86_64::instructions::interrupts::disable();
+        }
+    }
+}
+
+/// Wake render cores parked in `wait_for_pass`
+fn wake_render_cores() {
+    if apic::is_ready() {
+        apic::broadcast_ipi(interrupts::WAKE_VECTOR);
     }
 }
 
@@ -188,14 +307,19 @@ fn halt_loop() -> ! {
     }
 }
 
-/// Signal render cores to start
-pub fn start_render() {
-    RENDER_START.store(true, Ordering::Release);
+/// Take the per-core tile and triangle counts since the last call and
+/// reset them (call once per frame)
+pub fn frame_stats() -> FrameStats {
+    let frame = FrameStats::collect(render_core_count(), stats::get);
+    stats::reset();
+    frame
 }
 
-/// Signal render cores that frame is complete
-pub fn end_render() {
-    RENDER_START.store(false, Ordering::Release);
+/// Signal render cores to run one render pass
+/// Core 0 joins in and then waits at RENDER_BARRIER before starting another
+pub fn start_render() {
+    RENDER_PASSES.fetch_add(1, Ordering::Release);
+    wake_render_cores();
 }
 
 /// Increment frame counter
@@ -211,9 +335,82 @@ pub fn current_frame() -> u32 {
 /// Signal all cores to shutdown
 pub fn shutdown() {
     SHUTDOWN.store(true, Ordering::Release);
+    wake_render_cores();
 }
 
 /// Check if shutdown was requested
 pub fn should_shutdown() -> bool {
     SHUTDOWN.load(Ordering::Acquire)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_cores_for_cpu_count() {
+        assert_eq!(render_cores_for(0), 1);
+        assert_eq!(render_cores_for(1), 1);
+        assert_eq!(render_cores_for(2), 2);
+        assert_eq!(render_cores_for(4), 4);
+        // From 5 CPUs one is kept for the network
+        assert_eq!(render_cores_for(5), 4);
+        assert_eq!(render_cores_for(8), 7);
+        assert_eq!(render_cores_for(64), MAX_RENDER_CORES);
+    }
+
+    #[test]
+    fn test_roles_for_cpu_count() {
+        // 2 CPUs: both render, no network core
+        assert_eq!(role_for(0, 2, 2), Some(CoreRole::GameLogic));
+        assert_eq!(role_for(1, 2, 2), Some(CoreRole::Rasterizer(0)));
+
+        // 8 CPUs: 7 render, the last polls the network
+        assert_eq!(role_for(6, 7, 8), Some(CoreRole::Rasterizer(5)));
+        assert_eq!(role_for(7, 7, 8), Some(CoreRole::Network));
+
+        // More CPUs than render cores + network: the rest idle
+        assert_eq!(role_for(8, 8, 12), Some(CoreRole::Network));
+        assert_eq!(role_for(9, 8, 12), None);
+    }
+
+    #[test]
+    fn test_parked_worker_starts_once_per_pass() {
+        let mut waiter = PassWaiter { passes: RENDER_PASSES.load(Ordering::Acquire) };
+
+        // Nothing signaled: the worker stays parked however often it wakes
+        for _ in 0..3 {
+            assert!(!waiter.try_start(RENDER_PASSES.load(Ordering::Acquire)));
+        }
+
+        for _ in 0..3 {
+            start_render();
+            let signaled = RENDER_PASSES.load(Ordering::Acquire);
+            assert!(waiter.is_pending(signaled));
+            assert!(waiter.try_start(signaled));
+            // A spurious wakeup after the pass doesn't run it again
+            assert!(!waiter.try_start(signaled));
+            assert!(!waiter.is_pending(signaled));
+        }
+    }
+
+    #[test]
+    fn test_barriers_match_configured_cores() {
+        use crate::smp::sync::{BIN_BARRIER, FRAME_BARRIER, RENDER_BARRIER};
+
+        for cpus in [1, 2, 5, 8] {
+            configure_render_cores(render_cores_for(cpus));
+            let cores = render_core_count();
+            assert_eq!(RENDER_BARRIER.participants(), cores);
+            assert_eq!(BIN_BARRIER.participants(), cores);
+            assert_eq!(FRAME_BARRIER.participants(), cores);
+        }
+
+        // One core: Core 0 passes both frame barriers alone (serial rendering)
+        configure_render_cores(1);
+        let mut reset = false;
+        BIN_BARRIER.wait_with(|| reset = true);
+        RENDER_BARRIER.wait();
+        assert!(reset);
+    }
+}
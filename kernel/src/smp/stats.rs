@@ -0,0 +1,194 @@
+//! Per-core rendering statistics
+//!
+//! Each render core accumulates its own counters in a cache-line padded slot,
+//! so the hot path never contends on a shared line.
+
+use crate::serial_println;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+/// Maximum number of cores tracked (matches the scheduler's core table)
+pub const MAX_STAT_CORES: usize = 8;
+
+/// Snapshot of one core's rendering work
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CoreRenderStats {
+    pub tiles_processed: u64,
+    pub triangles_rasterized: u64,
+    /// Triangles rejected by the tile Hi-Z test without rasterizing
+    pub triangles_hiz_skipped: u64,
+    pub cycles_spent: u64,
+}
+
+/// One frame's render stats for each render core
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FrameStats {
+    cores: [CoreRenderStats; MAX_STAT_CORES],
+    core_count: usize,
+}
+
+impl FrameStats {
+    /// Gather `get(core)` for cores `0..core_count` (at most MAX_STAT_CORES)
+    pub fn collect(core_count: usize, get: impl Fn(usize) -> CoreRenderStats) -> Self {
+        let core_count = core_count.min(MAX_STAT_CORES);
+        Self {
+            cores: core::array::from_fn(|core| if core < core_count { get(core) } else { CoreRenderStats::default() }),
+            core_count,
+        }
+    }
+
+    /// Stats of each core, indexed by core id
+    pub fn cores(&self) -> &[CoreRenderStats] {
+        &self.cores[..self.core_count]
+    }
+
+    /// Tiles rasterized by all cores
+    pub fn total_tiles(&self) -> u64 {
+        self.cores().iter().map(|c| c.tiles_processed).sum()
+    }
+
+    /// Triangles rasterized by all cores
+    pub fn total_triangles(&self) -> u64 {
+        self.cores().iter().map(|c| c.triangles_rasterized).sum()
+    }
+
+    /// Fewest and most tiles any core rasterized
+    pub fn tile_spread(&self) -> (u64, u64) {
+        let tiles = self.cores().iter().map(|c| c.tiles_processed);
+        (tiles.clone().min().unwrap_or(0), tiles.max().unwrap_or(0))
+    }
+
+    /// The busiest core's tiles as a percentage of the per-core mean
+    /// 100 is an even split; a straggler-bound frame reads well above it.
+    pub fn imbalance_percent(&self) -> u64 {
+        let total = self.total_tiles();
+        if total == 0 {
+            return 100;
+        }
+        self.tile_spread().1 * self.core_count as u64 * 100 / total
+    }
+}
+
+/// Per-core accumulator padded to a full cache line (avoids false sharing)
+#[repr(align(64))]
+struct CoreStatsSlot {
+    tiles_processed: AtomicU64,
+    triangles_rasterized: AtomicU64,
+    triangles_hiz_skipped: AtomicU64,
+    cycles_spent: AtomicU64,
+}
+
+impl CoreStatsSlot {
+    const fn new() -> Self {
+        Self {
+            tiles_processed: AtomicU64::new(0),
+            triangles_rasterized: AtomicU64::new(0),
+            triangles_hiz_skipped: AtomicU64::new(0),
+            cycles_spent: AtomicU64::new(0),
+        }
+    }
+}
+
+/// One stats slot per core
+static CORE_STATS: [CoreStatsSlot; MAX_STAT_CORES] = [const { CoreStatsSlot::new() }; MAX_STAT_CORES];
+
+/// Add one render_worker call's work to a core's counters
+#[inline]
+pub fn record(core_id: usize, tiles: u64, triangles: u64, hiz_skipped: u64, cycles: u64) {
+    if let Some(slot) = CORE_STATS.get(core_id) {
+        // Only the owning core writes its slot, so relaxed ordering is enough
+        slot.tiles_processed.fetch_add(tiles, Ordering::Relaxed);
+        slot.triangles_rasterized.fetch_add(triangles, Ordering::Relaxed);
+        slot.triangles_hiz_skipped.fetch_add(hiz_skipped, Ordering::Relaxed);
+        slot.cycles_spent.fetch_add(cycles, Ordering::Relaxed);
+    }
+}
+
+/// Read a core's accumulated stats
+pub fn get(core_id: usize) -> CoreRenderStats {
+    match CORE_STATS.get(core_id) {
+        Some(slot) => CoreRenderStats {
+            tiles_processed: slot.tiles_processed.load(Ordering::Relaxed),
+            triangles_rasterized: slot.triangles_rasterized.load(Ordering::Relaxed),
+            triangles_hiz_skipped: slot.triangles_hiz_skipped.load(Ordering::Relaxed),
+            cycles_spent: slot.cycles_spent.load(Ordering::Relaxed),
+        },
+        None => CoreRenderStats::default(),
+    }
+}
+
+/// Reset all counters (call once per frame after reporting)
+pub fn reset() {
+    for slot in CORE_STATS.iter() {
+        slot.tiles_processed.store(0, Ordering::Relaxed);
+        slot.triangles_rasterized.store(0, Ordering::Relaxed);
+        slot.triangles_hiz_skipped.store(0, Ordering::Relaxed);
+        slot.cycles_spent.store(0, Ordering::Relaxed);
+    }
+}
+
+/// Print one line per render core and the tile spread, then reset the
+/// counters for the next frame
+/// Format: `CORE0: 48 tiles, 12300 tris, 4100 hi-z skipped, 2.1Mcycles`
+/// and `SPREAD: 40-52 tiles per core (184 tiles, 12300 tris), busiest 113% of mean`
+pub fn print_render_stats() {
+    let frame = super::scheduler::frame_stats();
+    for (core, stats) in frame.cores().iter().enumerate() {
+        serial_println!(
+            "CORE{}: {} tiles, {} tris, {} hi-z skipped, {:.1}Mcycles",
+            core,
+            stats.tiles_processed,
+            stats.triangles_rasterized,
+            stats.triangles_hiz_skipped,
+            stats.cycles_spent as f64 / 1_000_000.0
+        );
+    }
+    let (min, max) = frame.tile_spread();
+    serial_println!(
+        "SPREAD: {}-{} tiles per core ({} tiles, {} tris), busiest {}% of mean",
+        min,
+        max,
+        frame.total_tiles(),
+        frame.total_triangles(),
+        frame.imbalance_percent()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::smp::sync::WorkCounter;
+
+    #[test]
+    fn test_core_counts_sum_to_tile_total() {
+        // Simulated cores steal tiles from one queue
+        const TILES: usize = 480;
+        const CORES: usize = 4;
+        let queue = WorkCounter::new(TILES);
+        // Core 3 is a straggler: it grabs a tile half as often as the others
+        const TURNS: [usize; 7] = [0, 1, 2, 0, 1, 2, 3];
+        let mut per_core = [CoreRenderStats::default(); CORES];
+        while let Some(tile) = queue.get_next() {
+            let core = TURNS[tile % TURNS.len()];
+            per_core[core].tiles_processed += 1;
+            per_core[core].triangles_rasterized += (tile % 5) as u64;
+        }
+
+        let frame = FrameStats::collect(CORES, |c| per_core[c]);
+        assert_eq!(frame.cores().len(), CORES);
+        assert_eq!(frame.total_tiles(), TILES as u64);
+        assert_eq!(frame.total_triangles(), (0..TILES).map(|t| (t % 5) as u64).sum::<u64>());
+
+        let (min, max) = frame.tile_spread();
+        assert!(min < max);
+        assert_eq!(per_core[3].tiles_processed, min);
+        assert!(frame.imbalance_percent() > 100);
+    }
+
+    #[test]
+    fn test_frame_stats_single_core() {
+        let frame = FrameStats::collect(1, |_| CoreRenderStats { tiles_processed: 120, ..Default::default() });
+        assert_eq!(frame.tile_spread(), (120, 120));
+        assert_eq!(frame.imbalance_percent(), 100);
+        assert_eq!(FrameStats::collect(0, get).imbalance_percent(), 100);
+    }
+}
@@ -44,7 +44,8 @@ impl SpinLock {
 /// A barrier for synchronizing multiple cores
 pub struct CoreBarrier {
     count: AtomicUsize,
-    target: usize,
+    /// Cores that must arrive before the barrier releases
+    target: AtomicUsize,
     generation: AtomicU32,
 }
 
@@ -52,20 +53,27 @@ impl CoreBarrier {
     pub const fn new(num_cores: usize) -> Self {
         Self {
             count: AtomicUsize::new(0),
-            target: num_cores,
+            target: AtomicUsize::new(num_cores),
             generation: AtomicU32::new(0),
         }
     }
 
     /// Wait at the barrier until all cores have arrived
     pub fn wait(&self) {
+        self.wait_with(|| {});
+    }
+
+    /// Wait at the barrier; the last core to arrive runs `last` before
+    /// releasing the others
+    pub fn wait_with(&self, last: impl FnOnce()) {
         let current_gen = self.generation.load(Ordering::Acquire);
 
         // Increment the count
         let arrived = self.count.fetch_add(1, Ordering::AcqRel) + 1;
 
-        if arrived == self.target {
-            // Last core to arrive - reset and advance generation
+        if arrived == self.target.load(Ordering::Acquire) {
+            // Last core to arrive - run its work, reset and advance generation
+            last();
             self.count.store(0, Ordering::Release);
             self.generation.fetch_add(1, Ordering::Release);
         } else {
@@ -77,9 +85,15 @@ impl CoreBarrier {
     }
 
     /// Reset the barrier for a new target
+    /// Only call while no core is waiting at it (e.g. before workers start).
     pub fn reset(&self, num_cores: usize) {
+        self.target.store(num_cores.max(1), Ordering::Release);
         self.count.store(0, Ordering::Release);
-        // Note: Can't change target in const context, would need interior mutability
+    }
+
+    /// Number of cores the barrier waits for
+    pub fn participants(&self) -> usize {
+        self.target.load(Ordering::Acquire)
     }
 }
 
@@ -119,5 +133,44 @@ impl WorkCounter {
 }
 
 /// Global barriers for frame synchronization
-pub static RENDER_BARRIER: CoreBarrier = CoreBarrier::new(4); // 4 render cores (Core 0-3)
-pub static FRAME_BARRIER: CoreBarrier = CoreBarrier::new(4); // All cores except network
+/// Sized for Core 0 alone until `set_render_cores` runs at SMP init.
+pub static RENDER_BARRIER: CoreBarrier = CoreBarrier::new(1); // All render cores (Core 0 + rasterizers)
+pub static BIN_BARRIER: CoreBarrier = CoreBarrier::new(1); // Render cores done binning, before rasterizing
+pub static FRAME_BARRIER: CoreBarrier = CoreBarrier::new(1); // All cores except network
+
+/// Size the frame barriers for `render_cores` participants
+/// With one core every wait returns at once, so rendering runs serially on Core 0.
+pub fn set_render_cores(render_cores: usize) {
+    RENDER_BARRIER.reset(render_cores);
+    BIN_BARRIER.reset(render_cores);
+    FRAME_BARRIER.reset(render_cores);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_barrier_participants_follow_core_count() {
+        let barrier = CoreBarrier::new(1);
+        for cores in [2, 4, 7] {
+            barrier.reset(cores);
+            assert_eq!(barrier.participants(), cores);
+        }
+        barrier.reset(0);
+        assert_eq!(barrier.participants(), 1);
+    }
+
+    #[test]
+    fn test_single_core_barrier_does_not_wait() {
+        // One-core mode: Core 0 is the last arrival every time
+        let barrier = CoreBarrier::new(1);
+        let mut ran = 0;
+        for _ in 0..3 {
+            barrier.wait_with(|| ran += 1);
+        }
+        assert_eq!(ran, 3);
+        assert_eq!(barrier.generation.load(Ordering::Relaxed), 3);
+        assert_eq!(barrier.count.load(Ordering::Relaxed), 0);
+    }
+}
@@ -2,7 +2,7 @@
 
 use crate::game::state::{GameState, MenuAction, PlayerCustomization, CustomizationCategory, PLAYER_CUSTOMIZATION};
 use crate::graphics::font;
-use crate::graphics::framebuffer::FRAMEBUFFER;
+use crate::graphics::framebuffer::{mark_dirty, DirtyRect, FRAMEBUFFER};
 use crate::graphics::rasterizer::RenderContext;
 use crate::graphics::ui::colors;
 use crate::graphics::ui::panel::{draw_gradient_background_raw, draw_panel_raw, fill_rect_raw};
@@ -27,6 +27,12 @@ impl CustomizationScreen {
         }
     }
 
+    /// Adopt a new screen size after a display mode change
+    pub fn resize(&mut self, fb_width: usize, fb_height: usize) {
+        self.fb_width = fb_width;
+        self.fb_height = fb_height;
+    }
+
     /// Reload customization from global state
     pub fn reload(&mut self) {
         self.local_customization = *PLAYER_CUSTOMIZATION.lock();
@@ -310,6 +316,7 @@ fn draw_line(fb: &crate::graphics::framebuffer::Framebuffer, x0: usize, y0: usiz
     let sx: i32 = if x0 < x1 { 1 } else { -1 };
     let sy: i32 = if y0 < y1 { 1 } else { -1 };
     let mut err = dx + dy;
+    mark_dirty(DirtyRect::new(x0.min(x1), y0.min(y1), x0.abs_diff(x1) + 1, y0.abs_diff(y1) + 1));
 
     let mut x = x0 as i32;
     let mut y = y0 as i32;
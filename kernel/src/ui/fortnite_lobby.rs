@@ -8,11 +8,11 @@ use crate::game::state::{
     GameState, LobbyPlayer, MenuAction, NetworkMode,
     PLAYER_CUSTOMIZATION, get_network_mode,
 };
-use crate::graphics::font;
-use crate::graphics::framebuffer::FRAMEBUFFER;
+use crate::graphics::font::{self, Align};
+use crate::graphics::framebuffer::{mark_dirty, DirtyRect, FRAMEBUFFER};
 use crate::graphics::rasterizer::RenderContext;
-use crate::graphics::ui::colors;
-use crate::graphics::ui::panel::{draw_panel_raw, fill_rect_raw};
+use crate::graphics::ui::{colors, Rect};
+use crate::graphics::ui::panel::{draw_panel_blended, fill_rect_raw, PanelBorder};
 
 /// Lobby tabs
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -123,6 +123,12 @@ impl FortniteLobby {
         }
     }
 
+    /// Adopt a new screen size after a display mode change
+    pub fn resize(&mut self, fb_width: usize, fb_height: usize) {
+        self.fb_width = fb_width;
+        self.fb_height = fb_height;
+    }
+
     /// Get player rotation for 3D rendering
     pub fn get_rotation(&self) -> f32 {
         self.player_rotation
@@ -304,6 +310,7 @@ impl FortniteLobby {
         let colors_top = [0xFF, 0x8C, 0x00]; // Orange
         let colors_mid1 = [0xFF, 0x69, 0xB4]; // Pink
         let colors_mid2 = [0x94, 0x00, 0xD3]; // Purple
+        mark_dirty(DirtyRect::new(0, 0, fb_width, fb_height));
         let colors_bot = [0x19, 0x19, 0x70];  // Dark blue
 
         for y in 0..fb_height.min(fb.height) {
@@ -404,7 +411,7 @@ impl FortniteLobby {
 
     fn draw_header(&self, fb: &crate::graphics::framebuffer::Framebuffer, fb_width: usize) {
         // Header background
-        fill_rect_raw(fb, 0, 0, fb_width, 60, 0x20102030);
+        draw_panel_blended(fb, Rect::new(0, 0, fb_width, 60), 0x102030, 0.6, 0, PanelBorder::NONE);
 
         // Game title
         font::draw_string_raw(fb, 20, 15, "BATTLE ROYALE", colors::TITLE, 3);
@@ -419,13 +426,12 @@ impl FortniteLobby {
             let tab_x = tab_start_x + i * (tab_width + tab_spacing);
             let selected = i == self.selected_tab;
 
-            let bg_color = if selected { 0x504080C0 } else { 0x30304060 };
-            fill_rect_raw(fb, tab_x, 10, tab_width, 40, bg_color);
+            let (bg_color, alpha) = if selected { (0x4080C0, 0.85) } else { (0x304060, 0.6) };
+            let tab_rect = Rect::new(tab_x, 10, tab_width, 40);
+            draw_panel_blended(fb, tab_rect, bg_color, alpha, 6, PanelBorder::NONE.with_shadow(1));
 
             let text_color = if selected { colors::WHITE } else { colors::SUBTITLE };
-            let label = tab.label();
-            let text_x = tab_x + (tab_width - font::string_width(label, 2)) / 2;
-            font::draw_string_raw(fb, text_x, 18, label, text_color, 2);
+            font::draw_string_aligned(fb, tab_rect, tab.label(), Align::Center, text_color, 2);
         }
     }
 
@@ -477,7 +483,9 @@ impl FortniteLobby {
         let panel_width = fb_width / 3 - 20;
         let panel_height = 200;
 
-        draw_panel_raw(fb, panel_x, panel_y, panel_width, panel_height, 0x30203040);
+        let panel = Rect::new(panel_x, panel_y, panel_width, panel_height);
+        let border = PanelBorder::new(colors::PANEL_BORDER, 2).with_shadow(2);
+        draw_panel_blended(fb, panel, 0x203040, 0.7, 10, border);
 
         // Player name
         let custom = PLAYER_CUSTOMIZATION.lock();
@@ -499,7 +507,7 @@ impl FortniteLobby {
         let bar_height = 80;
 
         // Semi-transparent background
-        fill_rect_raw(fb, 0, bar_y, fb_width, bar_height, 0x40102030);
+        draw_panel_blended(fb, Rect::new(0, bar_y, fb_width, bar_height), 0x102030, 0.65, 0, PanelBorder::NONE);
 
         // Game mode selector (left side)
         let mode = GameMode::from_index(self.selected_mode);
@@ -523,10 +531,8 @@ impl FortniteLobby {
         fill_rect_raw(fb, play_button_x, play_button_y, play_button_width, play_button_height, button_color);
 
         let play_text = if self.is_ready { "READY!" } else { "PLAY" };
-        let text_width = font::string_width(play_text, 3);
-        let text_x = play_button_x + (play_button_width - text_width) / 2;
-        let text_y = play_button_y + (play_button_height - 24) / 2;
-        font::draw_string_raw(fb, text_x, text_y, play_text, colors::BLACK, 3);
+        let play_rect = Rect::new(play_button_x, play_button_y, play_button_width, play_button_height);
+        font::draw_string_aligned(fb, play_rect, play_text, Align::Center, colors::BLACK, 3);
 
         // Player count
         let mut count_buf = [0u8; 16];
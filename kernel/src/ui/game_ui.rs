@@ -1,11 +1,14 @@
 //! In-game UI elements (HUD, crosshair, weapon display, etc.)
 
+use alloc::format;
+use alloc::string::String;
 use crate::game::state::PlayerPhase;
-use crate::graphics::font;
+use crate::graphics::font::{self, Align};
 use crate::graphics::framebuffer::{Framebuffer, FRAMEBUFFER};
 use crate::graphics::rasterizer::RenderContext;
 use crate::graphics::ui::colors as ui_colors;
-use crate::graphics::ui::panel::{draw_crosshair_raw, draw_gradient_background_raw, draw_panel_raw, draw_progress_bar_raw, fill_rect_raw};
+use crate::graphics::ui::Rect;
+use crate::graphics::ui::panel::{draw_crosshair_raw, draw_gradient_background_raw, draw_panel_raw, fill_rect_raw, FillDirection, ProgressBar};
 
 /// Draw countdown screen
 pub fn draw_countdown(_ctx: &RenderContext, fb_width: usize, fb_height: usize, seconds: u8) {
@@ -114,7 +117,8 @@ fn format_time<'a>(minutes: u8, seconds: u8, buf: &'a mut [u8; 8]) -> &'a str {
 }
 
 /// Draw victory/defeat screen
-pub fn draw_victory(_ctx: &RenderContext, fb_width: usize, fb_height: usize, winner_id: Option<u8>) {
+/// `winner_eliminations` is the winner's elimination count from the match event log
+pub fn draw_victory(_ctx: &RenderContext, fb_width: usize, fb_height: usize, winner_id: Option<u8>, winner_eliminations: u16) {
     let fb_guard = FRAMEBUFFER.lock();
     let fb = match fb_guard.as_ref() {
         Some(f) => f,
@@ -153,9 +157,19 @@ pub fn draw_victory(_ctx: &RenderContext, fb_width: usize, fb_height: usize, win
             Some(_) => "YOU PLACED: #2",
             None => "MATCH ENDED",
         };
-        font::draw_string_centered_raw(fb, fb_height / 2, placement, colors::WHITE, 3);
+        font::draw_string_centered_raw(fb, fb_height / 2 + 20, placement, colors::WHITE, 3);
     }
 
+    // Winner message, wrapped into a centered column below the title
+    let message = match winner_id {
+        Some(0) => String::from("YOU ARE THE LAST PLAYER STANDING ON THE ISLAND"),
+        Some(id) => format!("PLAYER {} WON THE MATCH WITH {} ELIMINATIONS", id, winner_eliminations),
+        None => String::from("THE MATCH ENDED WITHOUT A WINNER"),
+    };
+    let message_width = fb_width.saturating_sub(40).min(560);
+    let message_x = (fb_width - message_width) / 2;
+    font::draw_string_wrapped(fb, message_x, fb_height / 2 - 30, message_width, &message, colors::WHITE, 2);
+
     // Stats panel
     let panel_width = 400;
     let panel_height = 150;
@@ -163,15 +177,16 @@ pub fn draw_victory(_ctx: &RenderContext, fb_width: usize, fb_height: usize, win
     let panel_y = fb_height / 2 + 60;
     draw_panel_raw(fb, panel_x, panel_y, panel_width, panel_height, colors::PANEL_BG);
 
-    // Draw stats (default values, actual stats tracked per player)
-    font::draw_string_raw(fb, panel_x + 20, panel_y + 20, "ELIMINATIONS:", colors::SUBTITLE, 2);
-    font::draw_string_raw(fb, panel_x + 250, panel_y + 20, "0", colors::WHITE, 2);
-
-    font::draw_string_raw(fb, panel_x + 20, panel_y + 60, "DAMAGE DEALT:", colors::SUBTITLE, 2);
-    font::draw_string_raw(fb, panel_x + 250, panel_y + 60, "0", colors::WHITE, 2);
-
-    font::draw_string_raw(fb, panel_x + 20, panel_y + 100, "TIME SURVIVED:", colors::SUBTITLE, 2);
-    font::draw_string_raw(fb, panel_x + 250, panel_y + 100, "0:00", colors::WHITE, 2);
+    // Draw stats (placeholders except eliminations), values right-aligned
+    let mut elim_buf = [0u8; 5];
+    let eliminations = font::format_number(winner_eliminations as u32, &mut elim_buf);
+    let elim_label = if is_winner { "ELIMINATIONS:" } else { "WINNER ELIMS:" };
+    let stats = [(elim_label, eliminations), ("DAMAGE DEALT:", "0"), ("TIME SURVIVED:", "0:00")];
+    for (i, (label, value)) in stats.iter().enumerate() {
+        let row = Rect::new(panel_x + 20, panel_y + 20 + i * 40, panel_width - 40, font::char_height(2));
+        font::draw_string_aligned(fb, row, label, Align::Left, colors::SUBTITLE, 2);
+        font::draw_string_aligned(fb, row, value, Align::Right, colors::WHITE, 2);
+    }
 
     // Return to menu prompt
     font::draw_string_centered_raw(fb, fb_height - 60, "PRESS ENTER TO CONTINUE", colors::SUBTITLE, 2);
@@ -188,6 +203,12 @@ impl GameUI {
         Self { fb_width, fb_height }
     }
 
+    /// Adopt a new screen size after a display mode change
+    pub fn resize(&mut self, fb_width: usize, fb_height: usize) {
+        self.fb_width = fb_width;
+        self.fb_height = fb_height;
+    }
+
     /// Draw the full game HUD
     pub fn draw(&self, fb: &Framebuffer, health: u8, shield: u8, ammo: u16, max_ammo: u16, materials: u32, alive_count: usize, eliminations: u16, phase: PlayerPhase, weapon_name: &str) {
         match phase {
@@ -284,10 +305,17 @@ impl GameUI {
     fn draw_spectating_ui(&self, fb: &Framebuffer, target_name: &str) {
         // Spectating banner at top
         let banner_y = 20;
-        draw_panel_raw(fb, self.fb_width / 2 - 150, banner_y, 300, 50, colors::PANEL_BG);
-
-        font::draw_string_raw(fb, self.fb_width / 2 - 130, banner_y + 10, "SPECTATING:", colors::SUBTITLE, 2);
-        font::draw_string_raw(fb, self.fb_width / 2 - 20, banner_y + 10, target_name, colors::WHITE, 2);
+        let label = "SPECTATING: ";
+        let (label_width, text_height) = font::measure_string(label, 2);
+        let (name_width, _) = font::measure_string(target_name, 2);
+        let banner_width = (label_width + name_width + 40).max(300).min(self.fb_width);
+        let banner_x = (self.fb_width - banner_width) / 2;
+        draw_panel_raw(fb, banner_x, banner_y, banner_width, 50, colors::PANEL_BG);
+
+        let text_x = (self.fb_width - (label_width + name_width).min(self.fb_width)) / 2;
+        let text_y = banner_y + (50 - text_height) / 2;
+        font::draw_string_raw(fb, text_x, text_y, label, colors::SUBTITLE, 2);
+        font::draw_string_raw(fb, text_x + label_width, text_y, target_name, colors::WHITE, 2);
 
         // Navigation hint
         let hint = "A/D: SWITCH PLAYER";
@@ -296,16 +324,16 @@ impl GameUI {
 
     /// Draw health bar
     fn draw_health_bar(&self, fb: &Framebuffer, x: usize, y: usize, width: usize, height: usize, health: u8) {
-        let fill_color = if health > 50 {
-            colors::HEALTH_HIGH
-        } else if health > 25 {
-            colors::HEALTH_MED
-        } else {
-            colors::HEALTH_LOW
+        let bar = ProgressBar {
+            x,
+            y,
+            width,
+            height,
+            color_low: colors::HEALTH_LOW,
+            color_high: colors::HEALTH_HIGH,
+            direction: FillDirection::LeftToRight,
         };
-
-        let progress = health as f32 / 100.0;
-        draw_progress_bar_raw(fb, x, y, width, height, progress, fill_color, colors::PANEL_BG);
+        bar.draw(fb, health as f32 / 100.0);
 
         // Health icon/text
         let mut buf = [0u8; 8];
@@ -319,9 +347,16 @@ impl GameUI {
             return;
         }
 
-        let fill_color = colors::FN_BLUE;
-        let progress = shield as f32 / 100.0;
-        draw_progress_bar_raw(fb, x, y, width, height, progress, fill_color, colors::PANEL_BG);
+        let bar = ProgressBar {
+            x,
+            y,
+            width,
+            height,
+            color_low: colors::FN_BLUE,
+            color_high: colors::FN_BLUE,
+            direction: FillDirection::LeftToRight,
+        };
+        bar.draw(fb, shield as f32 / 100.0);
 
         // Shield text
         let mut buf = [0u8; 8];
@@ -425,16 +460,12 @@ impl GameUI {
         }
 
         // Weapon name below hotbar
-        let name_width = font::string_width(weapon_name, 2);
-        let name_x = (self.fb_width - name_width) / 2;
-        font::draw_string_raw(fb, name_x, y + slot_size + 5, weapon_name, colors::WHITE, 2);
+        font::draw_string_centered(fb, y + slot_size + 5, weapon_name, colors::WHITE, 2);
 
         // Ammo display
         let mut ammo_buf = [0u8; 16];
         let ammo_str = format_ammo(ammo, max_ammo, &mut ammo_buf);
-        let ammo_width = font::string_width(ammo_str, 2);
-        let ammo_x = (self.fb_width - ammo_width) / 2;
-        font::draw_string_raw(fb, ammo_x, y + slot_size + 30, ammo_str, colors::FN_YELLOW, 2);
+        font::draw_string_centered(fb, y + slot_size + 30, ammo_str, colors::FN_YELLOW, 2);
     }
 
     /// Draw materials count
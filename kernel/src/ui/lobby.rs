@@ -2,10 +2,10 @@
 
 use alloc::vec::Vec;
 use crate::game::state::{GameState, LobbyPlayer, MenuAction, PLAYER_CUSTOMIZATION};
-use crate::graphics::font;
+use crate::graphics::font::{self, Align};
 use crate::graphics::framebuffer::FRAMEBUFFER;
 use crate::graphics::rasterizer::RenderContext;
-use crate::graphics::ui::colors;
+use crate::graphics::ui::{colors, Rect};
 use crate::graphics::ui::list::PlayerList;
 use crate::graphics::ui::panel::{draw_gradient_background_raw, draw_panel_raw, fill_rect_raw};
 
@@ -40,6 +40,12 @@ impl LobbyScreen {
         }
     }
 
+    /// Adopt a new screen size after a display mode change
+    pub fn resize(&mut self, fb_width: usize, fb_height: usize) {
+        self.fb_width = fb_width;
+        self.fb_height = fb_height;
+    }
+
     /// Add a player to the lobby
     pub fn add_player(&mut self, name: &str) -> u8 {
         let id = self.players.len() as u8;
@@ -169,10 +175,12 @@ impl LobbyScreen {
             font::draw_string_raw(fb, status_x + 20, y, "STARTING IN:", colors::FN_YELLOW, scale);
             y += line_height;
 
-            // Draw countdown number (large)
+            // Draw countdown number (large), centered under the label
             let mut num_buf = [0u8; 4];
             let num_str = font::format_number(self.countdown_value as u32, &mut num_buf);
-            font::draw_string_raw(fb, status_x + 100, y, num_str, colors::TITLE, 6);
+            let (label_width, _) = font::measure_string("STARTING IN:", scale);
+            let number_row = Rect::new(status_x + 20, y, label_width, font::char_height(6));
+            font::draw_string_aligned(fb, number_row, num_str, Align::Center, colors::TITLE, 6);
         } else {
             font::draw_string_raw(fb, status_x + 20, y, "WAITING FOR PLAYERS", colors::SUBTITLE, scale);
             y += line_height;
@@ -216,10 +224,8 @@ impl LobbyScreen {
         }
 
         let button_text = if self.local_ready { "READY!" } else { "PRESS ENTER" };
-        let text_width = font::string_width(button_text, scale);
-        let text_x = button_x + (button_width - text_width) / 2;
-        let text_y = button_y + (button_height - font::char_height(scale)) / 2;
-        font::draw_string_raw(fb, text_x, text_y, button_text, colors::WHITE, scale);
+        let button_rect = Rect::new(button_x, button_y, button_width, button_height);
+        font::draw_string_aligned(fb, button_rect, button_text, Align::Center, colors::WHITE, scale);
 
         // Footer
         let footer = "ENTER: READY/UNREADY   ESC: LEAVE";
@@ -17,23 +17,35 @@ pub struct MainMenuScreen {
 
 impl MainMenuScreen {
     pub fn new(fb_width: usize, fb_height: usize) -> Self {
+        Self {
+            buttons: ButtonList::new(Self::layout(fb_width, fb_height), MainMenuOption::COUNT),
+            fb_width,
+            fb_height,
+        }
+    }
+
+    /// Buttons centered on a `fb_width` x `fb_height` screen
+    fn layout(fb_width: usize, fb_height: usize) -> [Button; 4] {
         let button_width = 300;
         let button_height = 60;
         let button_spacing = 20;
         let start_y = fb_height / 2 - 100;
 
-        let buttons = [
+        [
             Button::centered(start_y, button_width, button_height, MainMenuOption::Play.label(), fb_width),
             Button::centered(start_y + button_height + button_spacing, button_width, button_height, MainMenuOption::Settings.label(), fb_width),
             Button::centered(start_y + (button_height + button_spacing) * 2, button_width, button_height, MainMenuOption::Customization.label(), fb_width),
             Button::centered(start_y + (button_height + button_spacing) * 3, button_width, button_height, MainMenuOption::Quit.label(), fb_width),
-        ];
+        ]
+    }
 
-        Self {
-            buttons: ButtonList::new(buttons, MainMenuOption::COUNT),
-            fb_width,
-            fb_height,
-        }
+    /// Lay the buttons out again for a new screen size, keeping the selection
+    pub fn resize(&mut self, fb_width: usize, fb_height: usize) {
+        let selected = self.buttons.selected_index;
+        self.buttons = ButtonList::new(Self::layout(fb_width, fb_height), MainMenuOption::COUNT);
+        self.buttons.select(selected);
+        self.fb_width = fb_width;
+        self.fb_height = fb_height;
     }
 
     /// Handle input and return new state if transitioning
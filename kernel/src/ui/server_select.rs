@@ -2,12 +2,18 @@
 //!
 //! Allows players to choose between hosting a server, joining a server, or playing offline.
 
+use crate::boot;
 use crate::game::state::{GameState, MenuAction, NetworkMode, set_network_mode};
-use crate::graphics::font;
-use crate::graphics::framebuffer::FRAMEBUFFER;
+use crate::net::protocol::{send_join_request, take_join_rejection};
+use crate::graphics::font::{self, Align};
+use crate::graphics::framebuffer::{mark_dirty, DirtyRect, Framebuffer, FRAMEBUFFER};
 use crate::graphics::rasterizer::RenderContext;
-use crate::graphics::ui::colors;
+use crate::graphics::ui::{colors, Rect};
 use crate::graphics::ui::panel::{draw_gradient_background_raw, draw_panel_raw, fill_rect_raw};
+use alloc::format;
+use alloc::string::String;
+use protocol::packets::PROTOCOL_VERSION;
+use smoltcp::wire::Ipv4Address;
 
 /// Server mode options
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -71,6 +77,8 @@ pub struct ServerSelectScreen {
     input_mode: InputMode,
     /// Local IP address for display
     pub local_ip: [u8; 4],
+    /// Protocol version a server demanded when it rejected our join (error dialog)
+    rejected_version: Option<u8>,
     /// Framebuffer dimensions
     pub fb_width: usize,
     pub fb_height: usize,
@@ -85,13 +93,32 @@ impl ServerSelectScreen {
             port: 5000,
             input_mode: InputMode::ModeSelect,
             local_ip: [10, 0, 2, 15], // QEMU default
+            rejected_version: None,
             fb_width,
             fb_height,
         }
     }
 
+    /// Adopt a new screen size after a display mode change
+    pub fn resize(&mut self, fb_width: usize, fb_height: usize) {
+        self.fb_width = fb_width;
+        self.fb_height = fb_height;
+    }
+
     /// Handle input and return new state if transitioning
     pub fn update(&mut self, action: MenuAction) -> Option<GameState> {
+        if let Some(version) = take_join_rejection() {
+            self.rejected_version = Some(version);
+        }
+
+        // Error dialog swallows input until dismissed
+        if self.rejected_version.is_some() {
+            if matches!(action, MenuAction::Select | MenuAction::Back) {
+                self.rejected_version = None;
+            }
+            return None;
+        }
+
         match self.input_mode {
             InputMode::ModeSelect => self.handle_mode_select(action),
             InputMode::IpEntry => self.handle_ip_entry(action),
@@ -171,6 +198,12 @@ impl ServerSelectScreen {
                     server_ip: self.ip_octets,
                     port: self.port,
                 });
+                let [a, b, c, d] = self.ip_octets;
+                // Join as the name= boot option, if one was given
+                let name: String = boot::config()
+                    .player_name_chars()
+                    .map_or_else(|| String::from("Player"), Iterator::collect);
+                send_join_request(Ipv4Address::new(a, b, c, d), self.port, &name);
                 return Some(GameState::PartyLobby);
             }
             MenuAction::Back => {
@@ -249,23 +282,24 @@ impl ServerSelectScreen {
                 3,
             );
 
-            // Mode description
+            // Mode description, wrapped to the panel
             let desc_y = panel_y + 60;
-            font::draw_string_raw(
+            let desc_height = font::draw_string_wrapped(
                 fb,
                 panel_x + 20,
                 desc_y,
+                panel_width - 40,
                 mode.description(),
                 colors::SUBTITLE,
                 1,
             );
-            
+
             // Show scan hint for Join mode
             if mode == ServerMode::Join && selected {
                 font::draw_string_raw(
                     fb,
                     panel_x + 20,
-                    desc_y + 20,
+                    desc_y + desc_height + 10,
                     "(Scan sent automatically on select)",
                     colors::FN_YELLOW,
                     1,
@@ -283,6 +317,11 @@ impl ServerSelectScreen {
             self.draw_ip_entry(fb, fb_width, fb_height);
         }
 
+        // Join rejection dialog on top of everything else
+        if let Some(version) = self.rejected_version {
+            draw_version_error(fb, fb_width, version);
+        }
+
         // Draw local IP info when in Host mode
         if self.selected_mode == 0 && self.input_mode == InputMode::ModeSelect {
             let info_y = start_y + ServerMode::COUNT * (panel_height + panel_spacing) + 20;
@@ -300,23 +339,14 @@ impl ServerSelectScreen {
         font::draw_string_centered_raw(fb, fb_height - 40, footer, colors::SUBTITLE, 2);
     }
 
-    fn draw_ip_entry(&self, fb: &crate::graphics::framebuffer::Framebuffer, fb_width: usize, _fb_height: usize) {
+    fn draw_ip_entry(&self, fb: &Framebuffer, fb_width: usize, _fb_height: usize) {
         // Overlay panel for IP entry
         let panel_width = 500;
         let panel_height = 200;
         let panel_x = (fb_width - panel_width) / 2;
         let panel_y = 250;
 
-        // Dark overlay
-        for y in 0..fb.height {
-            for x in 0..fb.width {
-                let existing = fb.get_pixel(x, y);
-                let r = ((existing >> 16) & 0xFF) / 2;
-                let g = ((existing >> 8) & 0xFF) / 2;
-                let b = (existing & 0xFF) / 2;
-                fb.put_pixel(x, y, (r << 16) | (g << 8) | b);
-            }
-        }
+        dim_background(fb);
 
         draw_panel_raw(fb, panel_x, panel_y, panel_width, panel_height, colors::PANEL_BG);
 
@@ -346,8 +376,8 @@ impl ServerSelectScreen {
             let mut octet_buf = [0u8; 4];
             let octet_str = font::format_number(self.ip_octets[i] as u32, &mut octet_buf);
             let text_color = if is_selected { colors::BLACK } else { colors::WHITE };
-            let text_x = octet_x + (octet_width - font::string_width(octet_str, 3)) / 2;
-            font::draw_string_raw(fb, text_x, ip_y + 8, octet_str, text_color, 3);
+            let octet_rect = Rect::new(octet_x, ip_y, octet_width, 40);
+            font::draw_string_aligned(fb, octet_rect, octet_str, Align::Center, text_color, 3);
 
             // Dot separator
             if i < 3 {
@@ -364,6 +394,39 @@ impl ServerSelectScreen {
     }
 }
 
+/// Darken everything drawn so far (backdrop for overlay panels)
+fn dim_background(fb: &Framebuffer) {
+    mark_dirty(DirtyRect::new(0, 0, fb.width, fb.height));
+    for y in 0..fb.height {
+        for x in 0..fb.width {
+            let existing = fb.get_pixel(x, y);
+            let r = ((existing >> 16) & 0xFF) / 2;
+            let g = ((existing >> 8) & 0xFF) / 2;
+            let b = (existing & 0xFF) / 2;
+            fb.put_pixel(x, y, (r << 16) | (g << 8) | b);
+        }
+    }
+}
+
+/// Error dialog shown when the server rejected our protocol version
+fn draw_version_error(fb: &Framebuffer, fb_width: usize, server_version: u8) {
+    let panel_width = 500;
+    let panel_height = 180;
+    let panel_x = (fb_width - panel_width) / 2;
+    let panel_y = 250;
+
+    dim_background(fb);
+    draw_panel_raw(fb, panel_x, panel_y, panel_width, panel_height, colors::PANEL_BG);
+
+    font::draw_string_raw(fb, panel_x + 20, panel_y + 20, "CONNECTION REJECTED", colors::HEALTH_LOW, 3);
+
+    let server_line = format!("Server requires protocol v{}", server_version);
+    let client_line = format!("This client speaks protocol v{}", PROTOCOL_VERSION);
+    font::draw_string_raw(fb, panel_x + 20, panel_y + 70, &server_line, colors::WHITE, 2);
+    font::draw_string_raw(fb, panel_x + 20, panel_y + 100, &client_line, colors::WHITE, 2);
+    font::draw_string_raw(fb, panel_x + 20, panel_y + 140, "[ENTER] OK", colors::SUBTITLE, 2);
+}
+
 /// Format IP address display
 fn format_ip_display<'a>(prefix: &str, ip: &[u8; 4], buf: &'a mut [u8; 32]) -> &'a str {
     let mut pos = 0;
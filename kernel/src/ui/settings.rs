@@ -2,7 +2,9 @@
 
 use crate::game::state::{GameState, MenuAction, Settings, SettingsOption, SETTINGS};
 use crate::graphics::font;
-use crate::graphics::framebuffer::{Framebuffer, FRAMEBUFFER};
+use crate::graphics::gpu;
+use crate::graphics::gpu_batch;
+use crate::graphics::framebuffer::{mark_dirty, DirtyRect, Framebuffer, FRAMEBUFFER};
 use crate::graphics::rasterizer::RenderContext;
 use crate::graphics::ui::colors;
 use crate::graphics::ui::panel::{draw_gradient_background_raw, draw_panel_raw};
@@ -25,6 +27,12 @@ impl SettingsScreen {
         }
     }
 
+    /// Adopt a new screen size after a display mode change
+    pub fn resize(&mut self, fb_width: usize, fb_height: usize) {
+        self.fb_width = fb_width;
+        self.fb_height = fb_height;
+    }
+
     /// Reload settings from global state
     pub fn reload(&mut self) {
         self.local_settings = *SETTINGS.lock();
@@ -50,7 +58,9 @@ impl SettingsScreen {
             }
             MenuAction::Left => {
                 let option = SettingsOption::from_index(self.selected_index);
-                if option.is_toggle() {
+                if option == SettingsOption::Resolution {
+                    self.cycle_resolution(false);
+                } else if option.is_toggle() {
                     self.local_settings.toggle(option);
                 } else if option.is_range() {
                     self.local_settings.adjust(option, -1);
@@ -58,7 +68,9 @@ impl SettingsScreen {
             }
             MenuAction::Right => {
                 let option = SettingsOption::from_index(self.selected_index);
-                if option.is_toggle() {
+                if option == SettingsOption::Resolution {
+                    self.cycle_resolution(true);
+                } else if option.is_toggle() {
                     self.local_settings.toggle(option);
                 } else if option.is_range() {
                     self.local_settings.adjust(option, 1);
@@ -68,6 +80,8 @@ impl SettingsScreen {
                 let option = SettingsOption::from_index(self.selected_index);
                 if option.is_toggle() {
                     self.local_settings.toggle(option);
+                } else if option == SettingsOption::Resolution {
+                    self.cycle_resolution(true);
                 } else if option == SettingsOption::Back {
                     self.save();
                     return Some(GameState::PartyLobby);
@@ -83,6 +97,28 @@ impl SettingsScreen {
         None
     }
 
+    /// Switch the display to the next (or previous) common mode the device
+    /// accepts; modes it rejects are skipped
+    ///
+    /// The switch is live: the main loop sees the new mode generation and
+    /// resizes the screens and projection before the next frame.
+    fn cycle_resolution(&mut self, forward: bool) {
+        let current = gpu::dimensions();
+        let count = gpu::COMMON_MODES.len();
+        // From an unlisted mode, step onto the first (or last) listed one
+        let base = gpu::COMMON_MODES
+            .iter()
+            .position(|&mode| mode == current)
+            .unwrap_or(if forward { count - 1 } else { 0 });
+        for step in 1..=count {
+            let index = if forward { (base + step) % count } else { (base + count - step) % count };
+            let (width, height) = gpu::COMMON_MODES[index];
+            if (width, height) != current && gpu::set_mode(width, height).is_some() {
+                return;
+            }
+        }
+    }
+
     /// Draw the settings screen
     pub fn draw(&self, _ctx: &RenderContext, fb_width: usize, fb_height: usize) {
         let fb_guard = FRAMEBUFFER.lock();
@@ -101,15 +137,15 @@ impl SettingsScreen {
         font::draw_string_centered_raw(fb, title_y, title, colors::TITLE, title_scale);
 
         // Draw settings panel
+        let item_height = 44;
+        let padding = 20;
         let panel_width = 600;
-        let panel_height = 450;
+        let panel_height = padding * 2 + SettingsOption::COUNT * item_height - 10;
         let panel_x = (fb_width - panel_width) / 2;
         let panel_y = 140;
         draw_panel_raw(fb, panel_x, panel_y, panel_width, panel_height, colors::PANEL_BG);
 
         // Draw settings options
-        let item_height = 60;
-        let padding = 20;
         let item_width = panel_width - padding * 2;
         let scale = 2;
 
@@ -121,12 +157,28 @@ impl SettingsScreen {
             self.draw_option(fb, panel_x + padding, item_y, item_width, item_height - 10, option, selected, scale);
         }
 
+        // Which renderer frames go through right now
+        font::draw_string_centered_raw(fb, panel_y + panel_height + 15, self.renderer_status(), colors::SUBTITLE, 2);
+
         // Draw footer
         let footer = "LEFT/RIGHT TO ADJUST. ESC TO SAVE AND EXIT.";
         let footer_y = fb_height - 50;
         font::draw_string_centered_raw(fb, footer_y, footer, colors::SUBTITLE, 2);
     }
 
+    /// Active rendering path, and why it isn't the GPU when it could be
+    fn renderer_status(&self) -> &'static str {
+        if self.local_settings.force_software {
+            "RENDERER: SOFTWARE (FORCED)"
+        } else if gpu_batch::is_enabled() {
+            "RENDERER: GPU"
+        } else if gpu_batch::has_failed() {
+            "RENDERER: SOFTWARE (GPU FAILED)"
+        } else {
+            "RENDERER: SOFTWARE"
+        }
+    }
+
     fn draw_option(&self, fb: &Framebuffer, x: usize, y: usize, width: usize, height: usize, option: SettingsOption, selected: bool, scale: usize) {
         let bg_color = if selected {
             colors::BUTTON_SELECTED
@@ -135,6 +187,7 @@ impl SettingsScreen {
         };
 
         // Draw background
+        mark_dirty(DirtyRect::new(x, y, width, height));
         for py in y..(y + height).min(fb.height) {
             for px in x..(x + width).min(fb.width) {
                 fb.put_pixel(px, py, bg_color);
@@ -151,7 +204,7 @@ impl SettingsScreen {
         // Draw value based on option type
         if option.is_toggle() {
             let value_str = self.local_settings.get_value_str(option);
-            let value_color = if self.local_settings.get_value(option) == 1 {
+            let value_color = if self.local_settings.get_value(option) != 0 {
                 colors::READY
             } else {
                 colors::NOT_READY
@@ -178,6 +231,7 @@ impl SettingsScreen {
                 SettingsOption::Sensitivity => (self.local_settings.sensitivity, 1, 10),
                 SettingsOption::RenderDistance => (self.local_settings.render_distance, 1, 3),
                 SettingsOption::Volume => (self.local_settings.volume, 0, 100),
+                SettingsOption::FontScale => (self.local_settings.font_scale, 1, 4),
                 _ => (0, 0, 1),
             };
 
@@ -197,6 +251,12 @@ impl SettingsScreen {
             let value_width = font::string_width(value_str, scale);
             let value_x = x + width - value_width - 15;
             font::draw_string_raw(fb, value_x, text_y, value_str, colors::BUTTON_TEXT, scale);
+        } else if option == SettingsOption::Resolution {
+            let (mode_width, mode_height) = gpu::dimensions();
+            let value_str = alloc::format!("{}X{}", mode_width, mode_height);
+            let value_width = font::string_width(&value_str, scale);
+            let value_x = x + width - value_width - 15;
+            font::draw_string_raw(fb, value_x, text_y, &value_str, colors::BUTTON_TEXT, scale);
         } else if option == SettingsOption::Back {
             // Draw back button indicator
             if selected {
@@ -1,8 +1,11 @@
 //! Test map / Model gallery viewer
 //!
-//! A debug screen for viewing all voxel models in the game.
+//! A debug screen for viewing all voxel models in the game. The model can be
+//! drawn by the tile renderer or through either gfx backend, to compare them.
 
 use crate::game::state::{GameState, MenuAction};
+use crate::gfx::backends::svga3d::Svga3DBackend;
+use crate::gfx::device::Backend;
 use crate::graphics::font;
 use crate::graphics::framebuffer::FRAMEBUFFER;
 use crate::graphics::rasterizer::RenderContext;
@@ -37,34 +40,6 @@ const MODEL_NAMES: &[&str] = &[
     "Backpack (Large)",
 ];
 
-/// Model triangle counts (approximate)
-const MODEL_TRI_COUNTS: &[usize] = &[
-    312,   // Player
-    96,    // Shotgun
-    144,   // AR
-    72,    // Pistol
-    108,   // SMG
-    192,   // Sniper
-    84,    // Pickaxe
-    288,   // Glider Red
-    288,   // Glider Blue
-    288,   // Glider Green
-    288,   // Glider Orange
-    432,   // Pine Tree
-    576,   // Oak Tree
-    96,    // Rock
-    384,   // Wall Wood
-    384,   // Wall Brick
-    384,   // Wall Metal
-    192,   // Floor Wood
-    480,   // Ramp Wood
-    864,   // Battle Bus
-    108,   // Chest
-    96,    // Backpack Small
-    144,   // Backpack Medium
-    192,   // Backpack Large
-];
-
 /// Model sizes (width x height x depth in voxels)
 const MODEL_SIZES: &[(usize, usize, usize)] = &[
     (8, 24, 4),   // Player
@@ -101,6 +76,13 @@ pub struct TestMapScreen {
     pub rotation: f32,
     /// Zoom level (1.0 = default)
     pub zoom: f32,
+    /// gfx backend the model is drawn with (None = tile renderer)
+    pub gfx_backend: Option<Backend>,
+    /// Draw the model's greedy mesh instead of the per-voxel-face one
+    pub greedy: bool,
+    /// Triangles of the current model with each mesher (naive, greedy),
+    /// filled in by the renderer
+    pub triangle_counts: Option<(usize, usize)>,
     /// Framebuffer dimensions
     pub fb_width: usize,
     pub fb_height: usize,
@@ -112,11 +94,20 @@ impl TestMapScreen {
             current_model: 0,
             rotation: 0.0,
             zoom: 1.0,
+            gfx_backend: None,
+            greedy: false,
+            triangle_counts: None,
             fb_width,
             fb_height,
         }
     }
 
+    /// Adopt a new screen size after a display mode change
+    pub fn resize(&mut self, fb_width: usize, fb_height: usize) {
+        self.fb_width = fb_width;
+        self.fb_height = fb_height;
+    }
+
     /// Handle input and return new state if transitioning
     pub fn update(&mut self, action: MenuAction) -> Option<GameState> {
         match action {
@@ -126,9 +117,11 @@ impl TestMapScreen {
                 } else {
                     self.current_model -= 1;
                 }
+                self.triangle_counts = None;
             }
             MenuAction::Right => {
                 self.current_model = (self.current_model + 1) % MODEL_NAMES.len();
+                self.triangle_counts = None;
             }
             MenuAction::Up => {
                 // Zoom in
@@ -138,6 +131,9 @@ impl TestMapScreen {
                 // Zoom out
                 self.zoom = (self.zoom - 0.1).max(0.3);
             }
+            MenuAction::Select => {
+                self.gfx_backend = next_renderer(self.gfx_backend, Svga3DBackend::is_available());
+            }
             MenuAction::Back => {
                 return Some(GameState::PartyLobby);
             }
@@ -160,6 +156,11 @@ impl TestMapScreen {
         self.current_model
     }
 
+    /// Name of the current model
+    pub fn model_name(&self) -> &'static str {
+        MODEL_NAMES.get(self.current_model).unwrap_or(&"Unknown")
+    }
+
     /// Get current rotation
     pub fn get_rotation(&self) -> f32 {
         self.rotation
@@ -170,6 +171,11 @@ impl TestMapScreen {
         self.zoom
     }
 
+    /// Get the gfx backend to draw the model with (None = tile renderer)
+    pub fn get_gfx_backend(&self) -> Option<Backend> {
+        self.gfx_backend
+    }
+
     /// Draw the test map UI (2D overlay)
     pub fn draw(&self, _ctx: &RenderContext, fb_width: usize, fb_height: usize) {
         let fb_guard = FRAMEBUFFER.lock();
@@ -185,7 +191,7 @@ impl TestMapScreen {
         font::draw_string_centered_raw(fb, 20, "MODEL VIEWER", colors::TITLE, 3);
 
         // Model name with navigation arrows
-        let name = MODEL_NAMES.get(self.current_model).unwrap_or(&"Unknown");
+        let name = self.model_name();
         let nav_text_scale = 2;
 
         // Left arrow
@@ -203,16 +209,17 @@ impl TestMapScreen {
         draw_panel_raw(fb, 20, panel_y, fb_width - 40, 100, colors::PANEL_BG);
 
         // Model info
-        let tri_count = MODEL_TRI_COUNTS.get(self.current_model).unwrap_or(&0);
         let size = MODEL_SIZES.get(self.current_model).unwrap_or(&(0, 0, 0));
 
         let info_scale = 2;
         let line_height = 30;
 
-        // Triangle count
-        let mut tri_buf = [0u8; 32];
-        let tri_str = format_model_info("Triangles: ", *tri_count, &mut tri_buf);
-        font::draw_string_raw(fb, 40, panel_y + 15, tri_str, colors::WHITE, info_scale);
+        // Triangle count with each mesher
+        if let Some((naive, greedy)) = self.triangle_counts {
+            let mut tri_buf = [0u8; 64];
+            let tri_str = format_triangle_info(naive, greedy, &mut tri_buf);
+            font::draw_string_raw(fb, 40, panel_y + 15, tri_str, colors::WHITE, info_scale);
+        }
 
         // Size
         let mut size_buf = [0u8; 48];
@@ -224,40 +231,51 @@ impl TestMapScreen {
         let zoom_str = format_zoom_info(self.zoom, &mut zoom_buf);
         font::draw_string_raw(fb, 40, panel_y + 15 + line_height * 2, zoom_str, colors::SUBTITLE, info_scale);
 
+        // Renderer, right-aligned on the triangle count line
+        let renderer = renderer_name(self.gfx_backend);
+        let renderer_x = fb_width.saturating_sub(40 + font::string_width(renderer, info_scale));
+        font::draw_string_raw(fb, renderer_x, panel_y + 15, renderer, colors::FN_YELLOW, info_scale);
+
+        // Mesher, right-aligned below it
+        let mesher = if self.greedy { "Mesher: Greedy" } else { "Mesher: Naive" };
+        let mesher_x = fb_width.saturating_sub(40 + font::string_width(mesher, info_scale));
+        font::draw_string_raw(fb, mesher_x, panel_y + 15 + line_height, mesher, colors::FN_YELLOW, info_scale);
+
         // Controls
-        let controls = "[LEFT/RIGHT] Navigate  [UP/DOWN] Zoom  [ESC] Back";
+        let controls = "[LEFT/RIGHT] Navigate  [UP/DOWN] Zoom  [ENTER] Renderer  [TAB] Mesher  [ESC] Back";
         font::draw_string_centered_raw(fb, fb_height - 30, controls, colors::SUBTITLE, 1);
     }
 }
 
-/// Format model info string
-fn format_model_info<'a>(prefix: &str, value: usize, buf: &'a mut [u8; 32]) -> &'a str {
-    let prefix_bytes = prefix.as_bytes();
-    let mut pos = 0;
-
-    for &b in prefix_bytes {
-        if pos < buf.len() {
-            buf[pos] = b;
-            pos += 1;
-        }
+/// Renderer after `current` in the Select cycle:
+/// tiles -> gfx software -> gfx SVGA3D (when available) -> tiles
+fn next_renderer(current: Option<Backend>, svga3d_available: bool) -> Option<Backend> {
+    match current {
+        None => Some(Backend::Software),
+        Some(Backend::Software) if svga3d_available => Some(Backend::Svga3D),
+        Some(_) => None,
     }
+}
 
-    // Write number
-    if value == 0 {
-        if pos < buf.len() {
-            buf[pos] = b'0';
-            pos += 1;
-        }
-    } else {
-        let mut n = value;
-        let start = pos;
-        while n > 0 && pos < buf.len() {
-            buf[pos] = b'0' + (n % 10) as u8;
-            n /= 10;
-            pos += 1;
-        }
-        buf[start..pos].reverse();
+/// Label for the renderer drawing the model
+fn renderer_name(backend: Option<Backend>) -> &'static str {
+    match backend {
+        None => "Tiles",
+        Some(Backend::Software) => "gfx: Software",
+        Some(Backend::Svga3D) => "gfx: SVGA3D",
     }
+}
+
+/// Format the triangle counts of both meshers, e.g.
+/// "Triangles: 864 naive, 210 greedy (-75%)"
+fn format_triangle_info(naive: usize, greedy: usize, buf: &mut [u8; 64]) -> &str {
+    let mut pos = write_bytes(buf, 0, b"Triangles: ");
+    pos = write_number(buf, pos, naive);
+    pos = write_bytes(buf, pos, b" naive, ");
+    pos = write_number(buf, pos, greedy);
+    pos = write_bytes(buf, pos, b" greedy (-");
+    pos = write_number(buf, pos, 100 - greedy.min(naive) * 100 / naive.max(1));
+    pos = write_bytes(buf, pos, b"%)");
 
     unsafe { core::str::from_utf8_unchecked(&buf[..pos]) }
 }
@@ -316,6 +334,13 @@ fn format_zoom_info<'a>(zoom: f32, buf: &'a mut [u8; 24]) -> &'a str {
     unsafe { core::str::from_utf8_unchecked(&buf[..pos]) }
 }
 
+/// Write bytes to buffer (as many as fit) and return new position
+fn write_bytes(buf: &mut [u8], start: usize, bytes: &[u8]) -> usize {
+    let len = bytes.len().min(buf.len() - start);
+    buf[start..start + len].copy_from_slice(&bytes[..len]);
+    start + len
+}
+
 /// Write a number to buffer and return new position
 fn write_number(buf: &mut [u8], start: usize, value: usize) -> usize {
     let mut pos = start;
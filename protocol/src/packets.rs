@@ -16,11 +16,13 @@ pub struct PlayerState {
     pub health: u8,
     pub weapon_id: u8,
     pub state: u8,   // PlayerStateFlags
-    _padding: u8,
+    /// Sequence of the last `ClientInput` the server applied to this
+    /// player, so its client can reconcile its prediction
+    pub input_seq: u32,
 }
 
 impl PlayerState {
-    pub const SIZE: usize = 21; // 1 + 4 + 4 + 4 + 2 + 2 + 1 + 1 + 1 + 1 = 21 bytes
+    pub const SIZE: usize = 24; // 1 + 4 + 4 + 4 + 2 + 2 + 1 + 1 + 1 + 4 = 24 bytes
 
     pub fn new(player_id: u8) -> Self {
         Self {
@@ -33,7 +35,7 @@ impl PlayerState {
             health: 100,
             weapon_id: 0,
             state: 0,
-            _padding: 0,
+            input_seq: 0,
         }
     }
 
@@ -200,13 +202,27 @@ impl WorldStateDelta {
     }
 }
 
+/// Wire protocol version; bump whenever a packet layout changes
+pub const PROTOCOL_VERSION: u8 = 4;
+
+/// Prefix on every datagram so stray traffic on the game port is dropped
+pub const PROTOCOL_MAGIC: [u8; 2] = *b"BR";
+
+/// Longest chat message in bytes (UTF-8)
+pub const MAX_CHAT_LEN: usize = 120;
+
+/// Chat sender id used for server announcements
+pub const SERVER_SENDER_ID: u8 = 0;
+
 /// Packet types
 #[derive(Debug, Clone)]
 pub enum Packet {
     /// Client requests to join game
-    JoinRequest { name: String },
-    /// Server responds with player ID
-    JoinResponse { player_id: u8 },
+    JoinRequest { version: u8, name: String },
+    /// Server accepts the join and assigns a player ID
+    JoinResponse { player_id: u8, version: u8 },
+    /// Server refuses a client built for a different protocol version
+    JoinReject { expected_version: u8 },
     /// Client sends input
     ClientInput(ClientInput),
     /// Server sends world state
@@ -217,7 +233,16 @@ pub enum Packet {
     /// Client requests server info
     Discovery,
     /// Server responds with info
-    DiscoveryResponse { server_name: String, player_count: u8 },
+    DiscoveryResponse { server_name: String, player_count: u8, version: u8 },
+    /// Text message shown in the client HUD (sender 0 = server announcement)
+    Chat { sender_id: u8, message: String },
+    /// Server reports an elimination for the kill feed; resent until acked
+    KillFeed { seq: u16, killer_id: u8, victim_id: u8, weapon_type: u8, rarity: u8 },
+    /// Server reports a pickaxe hit: vegetation `index` is down to `health`
+    /// (0 = harvested); resent until acked
+    Harvest { seq: u16, index: u16, health: u8 },
+    /// Client acknowledges a resent-until-acked packet (kill feed, harvest)
+    Ack { seq: u16 },
 }
 
 impl Packet {
@@ -229,19 +254,31 @@ impl Packet {
     const TYPE_PONG: u8 = 6;
     const TYPE_DISCOVERY: u8 = 7;
     const TYPE_DISCOVERY_RESPONSE: u8 = 8;
+    const TYPE_CHAT: u8 = 9;
+    const TYPE_KILL_FEED: u8 = 10;
+    const TYPE_JOIN_REJECT: u8 = 11;
+    const TYPE_ACK: u8 = 12;
+    const TYPE_HARVEST: u8 = 13;
 
     pub fn encode(&self) -> Vec<u8> {
         let mut buf = Vec::new();
+        buf.extend_from_slice(&PROTOCOL_MAGIC);
 
         match self {
-            Packet::JoinRequest { name } => {
+            Packet::JoinRequest { version, name } => {
                 buf.push(Self::TYPE_JOIN_REQUEST);
+                buf.push(*version);
                 buf.push(name.len() as u8);
                 buf.extend_from_slice(name.as_bytes());
             }
-            Packet::JoinResponse { player_id } => {
+            Packet::JoinResponse { player_id, version } => {
                 buf.push(Self::TYPE_JOIN_RESPONSE);
                 buf.push(*player_id);
+                buf.push(*version);
+            }
+            Packet::JoinReject { expected_version } => {
+                buf.push(Self::TYPE_JOIN_REJECT);
+                buf.push(*expected_version);
             }
             Packet::ClientInput(input) => {
                 buf.push(Self::TYPE_CLIENT_INPUT);
@@ -262,11 +299,41 @@ impl Packet {
             Packet::Discovery => {
                 buf.push(Self::TYPE_DISCOVERY);
             }
-            Packet::DiscoveryResponse { server_name, player_count } => {
+            Packet::DiscoveryResponse { server_name, player_count, version } => {
                 buf.push(Self::TYPE_DISCOVERY_RESPONSE);
                 buf.push(server_name.len() as u8);
                 buf.extend_from_slice(server_name.as_bytes());
                 buf.push(*player_count);
+                buf.push(*version);
+            }
+            Packet::Chat { sender_id, message } => {
+                // Truncate on a char boundary so the receiver never sees split UTF-8
+                let mut len = message.len().min(MAX_CHAT_LEN);
+                while !message.is_char_boundary(len) {
+                    len -= 1;
+                }
+                buf.push(Self::TYPE_CHAT);
+                buf.push(*sender_id);
+                buf.push(len as u8);
+                buf.extend_from_slice(&message.as_bytes()[..len]);
+            }
+            Packet::KillFeed { seq, killer_id, victim_id, weapon_type, rarity } => {
+                buf.push(Self::TYPE_KILL_FEED);
+                buf.extend_from_slice(&seq.to_le_bytes());
+                buf.push(*killer_id);
+                buf.push(*victim_id);
+                buf.push(*weapon_type);
+                buf.push(*rarity);
+            }
+            Packet::Harvest { seq, index, health } => {
+                buf.push(Self::TYPE_HARVEST);
+                buf.extend_from_slice(&seq.to_le_bytes());
+                buf.extend_from_slice(&index.to_le_bytes());
+                buf.push(*health);
+            }
+            Packet::Ack { seq } => {
+                buf.push(Self::TYPE_ACK);
+                buf.extend_from_slice(&seq.to_le_bytes());
             }
         }
 
@@ -274,27 +341,37 @@ impl Packet {
     }
 
     pub fn decode(buf: &[u8]) -> Option<Self> {
+        // Drop anything that isn't ours before looking at the payload
+        let buf = buf.strip_prefix(&PROTOCOL_MAGIC)?;
         if buf.is_empty() {
             return None;
         }
 
         match buf[0] {
             Self::TYPE_JOIN_REQUEST => {
-                if buf.len() < 2 {
+                // Version comes first so any client's request can be decoded and answered
+                if buf.len() < 3 {
                     return None;
                 }
-                let len = buf[1] as usize;
-                if buf.len() < 2 + len {
+                let version = buf[1];
+                let len = buf[2] as usize;
+                if buf.len() < 3 + len {
                     return None;
                 }
-                let name = String::from_utf8_lossy(&buf[2..2 + len]).into_owned();
-                Some(Packet::JoinRequest { name })
+                let name = String::from_utf8_lossy(&buf[3..3 + len]).into_owned();
+                Some(Packet::JoinRequest { version, name })
             }
             Self::TYPE_JOIN_RESPONSE => {
+                if buf.len() < 3 {
+                    return None;
+                }
+                Some(Packet::JoinResponse { player_id: buf[1], version: buf[2] })
+            }
+            Self::TYPE_JOIN_REJECT => {
                 if buf.len() < 2 {
                     return None;
                 }
-                Some(Packet::JoinResponse { player_id: buf[1] })
+                Some(Packet::JoinReject { expected_version: buf[1] })
             }
             Self::TYPE_CLIENT_INPUT => {
                 let input = ClientInput::decode(&buf[1..])?;
@@ -328,17 +405,193 @@ impl Packet {
                     return None;
                 }
                 let len = buf[1] as usize;
-                if buf.len() < 2 + len + 1 {
+                if buf.len() < 2 + len + 2 {
                     return None;
                 }
                 let server_name = String::from_utf8_lossy(&buf[2..2 + len]).into_owned();
                 let player_count = buf[2 + len];
+                let version = buf[3 + len];
                 Some(Packet::DiscoveryResponse {
                     server_name,
                     player_count,
+                    version,
+                })
+            }
+            Self::TYPE_CHAT => {
+                if buf.len() < 3 {
+                    return None;
+                }
+                let sender_id = buf[1];
+                let len = buf[2] as usize;
+                if len > MAX_CHAT_LEN || buf.len() < 3 + len {
+                    return None;
+                }
+                // Reject (rather than repair) invalid UTF-8
+                let message = core::str::from_utf8(&buf[3..3 + len]).ok()?;
+                Some(Packet::Chat {
+                    sender_id,
+                    message: String::from(message),
+                })
+            }
+            Self::TYPE_KILL_FEED => {
+                if buf.len() < 7 {
+                    return None;
+                }
+                Some(Packet::KillFeed {
+                    seq: u16::from_le_bytes([buf[1], buf[2]]),
+                    killer_id: buf[3],
+                    victim_id: buf[4],
+                    weapon_type: buf[5],
+                    rarity: buf[6],
+                })
+            }
+            Self::TYPE_HARVEST => {
+                if buf.len() < 6 {
+                    return None;
+                }
+                Some(Packet::Harvest {
+                    seq: u16::from_le_bytes([buf[1], buf[2]]),
+                    index: u16::from_le_bytes([buf[3], buf[4]]),
+                    health: buf[5],
+                })
+            }
+            Self::TYPE_ACK => {
+                if buf.len() < 3 {
+                    return None;
+                }
+                Some(Packet::Ack {
+                    seq: u16::from_le_bytes([buf[1], buf[2]]),
                 })
             }
             _ => None,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_chat_roundtrip() {
+        let packet = Packet::Chat {
+            sender_id: SERVER_SENDER_ID,
+            message: String::from("Storm collapsing early!"),
+        };
+        match Packet::decode(&packet.encode()) {
+            Some(Packet::Chat { sender_id, message }) => {
+                assert_eq!(sender_id, SERVER_SENDER_ID);
+                assert_eq!(message, "Storm collapsing early!");
+            }
+            other => panic!("unexpected decode result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_chat_encode_truncates_on_char_boundary() {
+        // 119 ASCII bytes + a 2-byte char would straddle the limit
+        let mut message = "a".repeat(MAX_CHAT_LEN - 1);
+        message.push('é');
+        let packet = Packet::Chat { sender_id: 3, message };
+        match Packet::decode(&packet.encode()) {
+            Some(Packet::Chat { message, .. }) => assert_eq!(message.len(), MAX_CHAT_LEN - 1),
+            other => panic!("unexpected decode result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_chat_decode_rejects_invalid() {
+        // Declared length over the limit
+        let mut oversized = vec![b'B', b'R', Packet::TYPE_CHAT, 0, (MAX_CHAT_LEN + 1) as u8];
+        oversized.extend(core::iter::repeat_n(b'a', MAX_CHAT_LEN + 1));
+        assert!(Packet::decode(&oversized).is_none());
+
+        // Truncated payload
+        assert!(Packet::decode(&[b'B', b'R', Packet::TYPE_CHAT, 0, 5, b'h', b'i']).is_none());
+
+        // Invalid UTF-8
+        assert!(Packet::decode(&[b'B', b'R', Packet::TYPE_CHAT, 0, 2, 0xC3, 0x28]).is_none());
+    }
+
+    #[test]
+    fn test_kill_feed_roundtrip() {
+        let packet = Packet::KillFeed { seq: 0x1234, killer_id: 2, victim_id: 7, weapon_type: 3, rarity: 4 };
+        match Packet::decode(&packet.encode()) {
+            Some(Packet::KillFeed { seq, killer_id, victim_id, weapon_type, rarity }) => {
+                assert_eq!((seq, killer_id, victim_id, weapon_type, rarity), (0x1234, 2, 7, 3, 4));
+            }
+            other => panic!("unexpected decode result: {:?}", other),
+        }
+        assert!(Packet::decode(&[b'B', b'R', Packet::TYPE_KILL_FEED, 0, 0, 2, 7]).is_none());
+
+        match Packet::decode(&Packet::Ack { seq: 0x1234 }.encode()) {
+            Some(Packet::Ack { seq }) => assert_eq!(seq, 0x1234),
+            other => panic!("unexpected decode result: {:?}", other),
+        }
+        assert!(Packet::decode(&[b'B', b'R', Packet::TYPE_ACK, 0]).is_none());
+    }
+
+    #[test]
+    fn test_harvest_roundtrip() {
+        let packet = Packet::Harvest { seq: 9, index: 300, health: 50 };
+        match Packet::decode(&packet.encode()) {
+            Some(Packet::Harvest { seq, index, health }) => assert_eq!((seq, index, health), (9, 300, 50)),
+            other => panic!("unexpected decode result: {:?}", other),
+        }
+        assert!(Packet::decode(&[b'B', b'R', Packet::TYPE_HARVEST, 9, 0, 44, 1]).is_none());
+    }
+
+    #[test]
+    fn test_world_delta_carries_input_ack() {
+        let mut player = PlayerState::new(5);
+        player.set_position(10.5, 2.0, -3.25);
+        player.input_seq = 0x0102_0304;
+        let delta = WorldStateDelta {
+            tick: 42,
+            player_count: 1,
+            players: vec![player],
+            ..Default::default()
+        };
+        let encoded = delta.encode();
+        assert_eq!(encoded.len(), 17 + PlayerState::SIZE);
+
+        let decoded = WorldStateDelta::decode(&encoded).unwrap();
+        let state = decoded.players[0];
+        assert_eq!({ state.input_seq }, 0x0102_0304);
+        assert_eq!((state.player_id, state.world_x(), state.world_z()), (5, 10.5, -3.25));
+    }
+
+    #[test]
+    fn test_join_request_carries_version() {
+        // A client from an older build still decodes, so the server can reject it
+        let packet = Packet::JoinRequest { version: PROTOCOL_VERSION - 1, name: String::from("old") };
+        match Packet::decode(&packet.encode()) {
+            Some(Packet::JoinRequest { version, name }) => {
+                assert_ne!(version, PROTOCOL_VERSION);
+                assert_eq!(name, "old");
+            }
+            other => panic!("unexpected decode result: {:?}", other),
+        }
+
+        let reject = Packet::JoinReject { expected_version: PROTOCOL_VERSION };
+        match Packet::decode(&reject.encode()) {
+            Some(Packet::JoinReject { expected_version }) => assert_eq!(expected_version, PROTOCOL_VERSION),
+            other => panic!("unexpected decode result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_decode_rejects_bad_magic() {
+        let mut data = Packet::Discovery.encode();
+        data[0] ^= 0xFF;
+        assert!(Packet::decode(&data).is_none());
+
+        // Unprefixed (pre-magic) packets and short garbage
+        assert!(Packet::decode(&[Packet::TYPE_JOIN_RESPONSE, 1]).is_none());
+        assert!(Packet::decode(&[]).is_none());
+        assert!(Packet::decode(b"B").is_none());
+        assert!(Packet::decode(b"BR").is_none());
+        assert!(Packet::decode(&[0xDE, 0xAD, 0xBE, 0xEF, 0x00, 0x01]).is_none());
+    }
+}
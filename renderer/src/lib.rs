@@ -9,6 +9,7 @@ extern crate alloc;
 pub mod map_mesh;
 pub mod math;
 pub mod mesh;
+pub mod texture;
 pub mod vertex;
 pub mod voxel;
 pub mod voxel_models;
@@ -6,7 +6,7 @@ use alloc::vec::Vec;
 use glam::{Vec2, Vec3};
 use crate::mesh::Mesh;
 use crate::vertex::Vertex;
-use crate::voxel::{VoxelColor, palette};
+use crate::voxel::{MeshingOptions, VoxelColor, palette};
 use crate::voxel_models;
 
 /// Chunk dimensions for terrain generation
@@ -153,7 +153,7 @@ pub fn generate_vegetation_mesh(
         }
     };
 
-    let base_mesh = model.to_mesh(veg_scale * world_scale * 0.2);
+    let base_mesh = model.to_mesh(veg_scale * world_scale * 0.2, MeshingOptions::LIT);
     transform_mesh(base_mesh, position, 0.0)
 }
 
@@ -480,7 +480,7 @@ pub fn create_lobby_platform(scale: f32) -> Mesh {
 /// Generate palm tree mesh for lobby background
 pub fn create_palm_tree_mesh(scale: f32) -> Mesh {
     let model = voxel_models::create_pine_tree(); // Use pine tree as base
-    let mut mesh = model.to_mesh(scale * 0.3);
+    let mut mesh = model.to_mesh(scale * 0.3, MeshingOptions::LIT);
 
     // Recolor to palm tree colors
     let trunk_color = Vec3::new(0.5, 0.35, 0.2);
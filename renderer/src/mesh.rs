@@ -1,6 +1,8 @@
 //! Procedural mesh generation
 
+use crate::texture::Material;
 use crate::vertex::Vertex;
+use alloc::boxed::Box;
 use alloc::vec;
 use alloc::vec::Vec;
 use glam::{Vec2, Vec3};
@@ -10,13 +12,82 @@ use glam::{Vec2, Vec3};
 pub struct Mesh {
     pub vertices: Vec<Vertex>,
     pub indices: Vec<u32>,
+    /// First triangle drawn alpha-blended (None = fully opaque mesh)
+    pub translucent_from: Option<usize>,
+    /// Opacity of the translucent triangles (255 = opaque)
+    pub alpha: u8,
+    /// Texture the rasterizer samples with the vertex UVs (None = vertex colors only)
+    pub material: Material,
+    /// Coarser versions for distant draws: LOD1 and LOD2 (the mesh itself is LOD0)
+    pub lod_meshes: [Option<Box<Mesh>>; 2],
 }
 
+/// Distance from the camera at which LOD1 replaces the full mesh
+pub const LOD1_DISTANCE: f32 = 30.0;
+/// Distance from the camera at which LOD2 replaces LOD1
+pub const LOD2_DISTANCE: f32 = 80.0;
+
 impl Mesh {
     pub fn new() -> Self {
         Self {
             vertices: Vec::new(),
             indices: Vec::new(),
+            translucent_from: None,
+            alpha: 255,
+            material: Material::None,
+            lod_meshes: [None, None],
+        }
+    }
+
+    /// Level of detail to draw at `distance` from the camera
+    /// Falls back to the next finer level when a coarser one is missing.
+    pub fn select_lod(&self, distance: f32) -> &Mesh {
+        let level = if distance >= LOD2_DISTANCE {
+            2
+        } else if distance >= LOD1_DISTANCE {
+            1
+        } else {
+            0
+        };
+        self.lod_meshes[..level]
+            .iter()
+            .rev()
+            .find_map(|lod| lod.as_deref())
+            .unwrap_or(self)
+    }
+
+    /// Draw the whole mesh alpha-blended with the given opacity
+    pub fn with_alpha(mut self, alpha: u8) -> Self {
+        self.translucent_from = Some(0);
+        self.alpha = alpha;
+        self
+    }
+
+    /// Texture the mesh (and its LOD meshes) with a material
+    pub fn with_material(mut self, material: Material) -> Self {
+        self.material = material;
+        for lod in self.lod_meshes.iter_mut().flatten() {
+            lod.material = material;
+        }
+        self
+    }
+
+    /// Append another mesh's triangles as the translucent part of this one
+    /// The LOD meshes no longer match and are dropped.
+    pub fn append_translucent(&mut self, other: &Mesh, alpha: u8) {
+        self.lod_meshes = [None, None];
+        let base = self.vertices.len() as u32;
+        self.translucent_from.get_or_insert(self.triangle_count());
+        self.alpha = alpha;
+        self.vertices.extend_from_slice(&other.vertices);
+        self.indices.extend(other.indices.iter().map(|i| i + base));
+    }
+
+    /// Opacity of a triangle (255 for the opaque part)
+    pub fn triangle_alpha(&self, index: usize) -> u8 {
+        match self.translucent_from {
+            Some(first) if index >= first => self.alpha,
+            _ => 255,
         }
     }
 
@@ -491,6 +562,48 @@ pub fn create_palm_tree(height: f32, frond_count: usize) -> Mesh {
     mesh
 }
 
+/// Create a tree stump: a short closed cylinder standing on y = 0
+pub fn create_stump(segments: usize, radius: f32, height: f32) -> Mesh {
+    let mut mesh = Mesh::new();
+
+    let bark_color = Vec3::new(0.4, 0.28, 0.16);
+    let cut_color = Vec3::new(0.8, 0.65, 0.45);
+
+    // Top cap center, fanned out to the rim below
+    let center = mesh.vertices.len() as u32;
+    mesh.vertices.push(Vertex::new(Vec3::new(0.0, height, 0.0), Vec3::Y, cut_color, Vec2::ZERO));
+
+    for i in 0..segments {
+        let angle1 = (i as f32 / segments as f32) * core::f32::consts::TAU;
+        let angle2 = ((i + 1) as f32 / segments as f32) * core::f32::consts::TAU;
+
+        let x1 = libm::cosf(angle1) * radius;
+        let z1 = libm::sinf(angle1) * radius;
+        let x2 = libm::cosf(angle2) * radius;
+        let z2 = libm::sinf(angle2) * radius;
+
+        // Side strip
+        let normal = Vec3::new((x1 + x2) * 0.5, 0.0, (z1 + z2) * 0.5).normalize();
+        let base = mesh.vertices.len() as u32;
+        mesh.vertices.push(Vertex::new(Vec3::new(x1, 0.0, z1), normal, bark_color * 0.7, Vec2::ZERO));
+        mesh.vertices.push(Vertex::new(Vec3::new(x2, 0.0, z2), normal, bark_color * 0.7, Vec2::ZERO));
+        mesh.vertices.push(Vertex::new(Vec3::new(x2, height, z2), normal, bark_color, Vec2::ZERO));
+        mesh.vertices.push(Vertex::new(Vec3::new(x1, height, z1), normal, bark_color, Vec2::ZERO));
+        mesh.indices.extend([base, base + 2, base + 1, base, base + 3, base + 2]);
+
+        // Top cap wedge
+        let rim = mesh.vertices.len() as u32;
+        mesh.vertices.push(Vertex::new(Vec3::new(x1, height, z1), Vec3::Y, cut_color * 0.9, Vec2::ZERO));
+        mesh.vertices.push(Vertex::new(Vec3::new(x2, height, z2), Vec3::Y, cut_color * 0.9, Vec2::ZERO));
+        mesh.indices.extend([center, rim + 1, rim]);
+    }
+
+    mesh
+}
+
+/// Opacity of the storm wall (a translucent veil over the world behind it)
+pub const STORM_WALL_ALPHA: u8 = 110;
+
 /// Create a cylindrical storm wall mesh
 /// segments: number of vertical strips around the cylinder
 /// height: how tall the wall is
@@ -530,7 +643,24 @@ pub fn create_storm_wall(segments: usize, height: f32) -> Mesh {
         mesh.indices.extend([base, base + 1, base + 2, base, base + 2, base + 3]);
     }
 
-    mesh
+    mesh.with_alpha(STORM_WALL_ALPHA)
+}
+
+/// Opacity of water surfaces (60%)
+pub const WATER_ALPHA: u8 = 153;
+
+/// Create a flat water surface: a unit square at y = 0 from -1 to 1 on X and Z,
+/// scaled by the water body's radius at render time
+pub fn create_water_plane() -> Mesh {
+    let mut mesh = Mesh::new();
+    let color = Vec3::new(30.0 / 255.0, 80.0 / 255.0, 140.0 / 255.0);
+
+    for (x, z) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)] {
+        mesh.vertices.push(Vertex::new(Vec3::new(x, 0.0, z), Vec3::Y, color, Vec2::new((x + 1.0) * 0.5, (z + 1.0) * 0.5)));
+    }
+    mesh.indices.extend([0, 2, 1, 0, 3, 2]);
+
+    mesh.with_alpha(WATER_ALPHA)
 }
 
 /// Helper: Create a box with given dimensions and offset
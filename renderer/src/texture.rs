@@ -0,0 +1,174 @@
+//! Procedural texture atlas
+//!
+//! A handful of small RGBA textures generated once at init, one per
+//! `Material`. Meshes pick a texture with their material id; the tile
+//! rasterizer samples it nearest-neighbor with wrapping UVs and modulates the
+//! texel by the interpolated vertex color, so textures are kept bright and
+//! the vertex color still tints and shades the surface.
+
+use alloc::vec::Vec;
+
+/// Texture side in texels (power of two, so UVs wrap with a mask)
+pub const TEXTURE_SIZE: usize = 64;
+
+/// Surface material of a mesh, indexing the texture atlas
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(u8)]
+pub enum Material {
+    /// Untextured: vertex colors only (the fast path)
+    #[default]
+    None = 0,
+    Grass = 1,
+    Wood = 2,
+    Brick = 3,
+    Metal = 4,
+}
+
+impl Material {
+    /// Every textured material, in atlas order
+    pub const TEXTURED: [Material; 4] = [Material::Grass, Material::Wood, Material::Brick, Material::Metal];
+
+    pub fn from_u8(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(Material::None),
+            1 => Some(Material::Grass),
+            2 => Some(Material::Wood),
+            3 => Some(Material::Brick),
+            4 => Some(Material::Metal),
+            _ => None,
+        }
+    }
+
+    pub fn is_textured(self) -> bool {
+        self != Material::None
+    }
+}
+
+/// A square RGBA texture
+pub struct Texture {
+    texels: Vec<[u8; 4]>,
+}
+
+impl Texture {
+    /// Fill a texture from a function of the texel coordinates
+    fn generate(texel: impl Fn(usize, usize) -> [u8; 4]) -> Self {
+        let mut texels = Vec::with_capacity(TEXTURE_SIZE * TEXTURE_SIZE);
+        for y in 0..TEXTURE_SIZE {
+            for x in 0..TEXTURE_SIZE {
+                texels.push(texel(x, y));
+            }
+        }
+        Self { texels }
+    }
+
+    /// Texel at integer coordinates (wrapped)
+    #[inline(always)]
+    pub fn texel(&self, x: i32, y: i32) -> [u8; 4] {
+        let mask = TEXTURE_SIZE as i32 - 1;
+        self.texels[((y & mask) as usize) * TEXTURE_SIZE + (x & mask) as usize]
+    }
+
+    /// Nearest-neighbor sample; UVs repeat every 1.0
+    #[inline(always)]
+    pub fn sample(&self, u: f32, v: f32) -> [u8; 4] {
+        let size = TEXTURE_SIZE as f32;
+        self.texel(libm::floorf(u * size) as i32, libm::floorf(v * size) as i32)
+    }
+}
+
+/// The textures of every textured material
+pub struct TextureAtlas {
+    textures: Vec<Texture>,
+}
+
+impl TextureAtlas {
+    /// Generate all textures (a few hundred microseconds; call once at init)
+    pub fn generate() -> Self {
+        Self {
+            textures: Material::TEXTURED.iter().map(|&m| generate_texture(m)).collect(),
+        }
+    }
+
+    /// Texture of a material (None for untextured)
+    #[inline]
+    pub fn get(&self, material: Material) -> Option<&Texture> {
+        (material as usize).checked_sub(1).and_then(|i| self.textures.get(i))
+    }
+}
+
+/// Texture for one textured material
+fn generate_texture(material: Material) -> Texture {
+    match material {
+        Material::Grass => Texture::generate(grass_texel),
+        Material::Wood => Texture::generate(wood_texel),
+        Material::Brick => Texture::generate(brick_texel),
+        Material::Metal | Material::None => Texture::generate(metal_texel),
+    }
+}
+
+/// Pseudo-random byte per texel and channel of a pattern
+fn hash(x: usize, y: usize, seed: u32) -> u8 {
+    let n = (x as u32).wrapping_mul(374761393) ^ (y as u32).wrapping_mul(668265263) ^ seed.wrapping_mul(2246822519);
+    let n = (n ^ (n >> 13)).wrapping_mul(1274126177);
+    (n ^ (n >> 16)) as u8
+}
+
+/// Scale a light base color by a brightness in 0..=255
+fn shade(base: [u8; 3], brightness: u8) -> [u8; 4] {
+    let s = |c: u8| ((c as u32 * brightness as u32) / 255) as u8;
+    [s(base[0]), s(base[1]), s(base[2]), 255]
+}
+
+/// Speckled blades: random brightness with occasional dark and light tips
+fn grass_texel(x: usize, y: usize) -> [u8; 4] {
+    let n = hash(x, y, 1);
+    let brightness = match n {
+        0..=24 => 150,
+        232..=255 => 255,
+        _ => 200 + n / 8,
+    };
+    shade([225, 255, 205], brightness)
+}
+
+/// Vertical planks with wavy grain lines and dark seams
+fn wood_texel(x: usize, y: usize) -> [u8; 4] {
+    const PLANK: usize = 16;
+    if x.is_multiple_of(PLANK) {
+        return shade([255, 235, 205], 150);
+    }
+    // Each plank gets its own grain offset and tone
+    let plank = x / PLANK;
+    let wave = libm::sinf(y as f32 * 0.2 + plank as f32 * 1.7) * 2.0;
+    let grain = libm::sinf((x as f32 + wave) * 1.3);
+    let brightness = 215 + (grain * 20.0) as i32 + (hash(plank, 0, 2) / 16) as i32 - (hash(x, y, 3) / 32) as i32;
+    shade([255, 235, 205], brightness.clamp(0, 255) as u8)
+}
+
+/// Running-bond bricks with light mortar joints
+fn brick_texel(x: usize, y: usize) -> [u8; 4] {
+    const BRICK_W: usize = 16;
+    const BRICK_H: usize = 8;
+    let row = y / BRICK_H;
+    let bx = x + if row % 2 == 1 { BRICK_W / 2 } else { 0 };
+    if y.is_multiple_of(BRICK_H) || bx.is_multiple_of(BRICK_W) {
+        return [235, 230, 220, 255];
+    }
+    let brick = hash(bx / BRICK_W, row, 4);
+    let brightness = 190 + brick / 8 + hash(x, y, 5) / 16;
+    shade([255, 215, 200], brightness)
+}
+
+/// Brushed panels with horizontal streaks, seams and corner rivets
+fn metal_texel(x: usize, y: usize) -> [u8; 4] {
+    const PANEL: usize = 32;
+    let (px, py) = (x % PANEL, y % PANEL);
+    if px == 0 || py == 0 {
+        return shade([235, 240, 250], 140);
+    }
+    if (px == 3 || px == PANEL - 3) && (py == 3 || py == PANEL - 3) {
+        return shade([235, 240, 250], 255);
+    }
+    // Streaks run along whole rows, with a little per-texel noise
+    let brightness = 205 + hash(0, y, 6) / 12 + hash(x, y, 7) / 32;
+    shade([235, 240, 250], brightness)
+}
@@ -3,12 +3,16 @@
 //! Provides voxel-based model creation and rendering for blocky Minecraft-style graphics.
 //! Voxels are converted to triangle meshes for rendering with the existing rasterizer.
 
+use alloc::boxed::Box;
 use alloc::vec::Vec;
 use alloc::vec;
 use glam::{Vec2, Vec3};
 use crate::vertex::Vertex;
 use crate::mesh::Mesh;
 
+/// Voxels covered by one repeat of a material texture along each axis
+pub const VOXELS_PER_TEXTURE: f32 = 4.0;
+
 /// A color in the voxel palette (RGB)
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct VoxelColor {
@@ -159,6 +163,8 @@ pub enum Face {
 }
 
 impl Face {
+    pub const ALL: [Face; 6] = [Face::Top, Face::Bottom, Face::Front, Face::Back, Face::Right, Face::Left];
+
     pub fn normal(&self) -> Vec3 {
         match self {
             Face::Top => Vec3::Y,
@@ -170,19 +176,98 @@ impl Face {
         }
     }
 
-    /// Shading factor for each face (simple directional lighting)
-    pub fn shade_factor(&self) -> f32 {
+    /// Corners of this face of a box `size` large, as offsets from the box's
+    /// min corner - CCW winding when viewed from outside
+    fn corners(&self, size: Vec3) -> [Vec3; 4] {
+        let Vec3 { x, y, z } = size;
+        match self {
+            // CCW when viewed from +Y (above): back-left, front-left, front-right, back-right
+            Face::Top => [Vec3::new(0.0, y, 0.0), Vec3::new(0.0, y, z), Vec3::new(x, y, z), Vec3::new(x, y, 0.0)],
+            // CCW when viewed from -Y (below): front-left, back-left, back-right, front-right
+            Face::Bottom => [Vec3::new(0.0, 0.0, z), Vec3::ZERO, Vec3::new(x, 0.0, 0.0), Vec3::new(x, 0.0, z)],
+            Face::Front => [Vec3::new(0.0, 0.0, z), Vec3::new(0.0, y, z), Vec3::new(x, y, z), Vec3::new(x, 0.0, z)],
+            Face::Back => [Vec3::new(x, 0.0, 0.0), Vec3::new(x, y, 0.0), Vec3::new(0.0, y, 0.0), Vec3::ZERO],
+            Face::Right => [Vec3::new(x, 0.0, z), Vec3::new(x, y, z), Vec3::new(x, y, 0.0), Vec3::new(x, 0.0, 0.0)],
+            Face::Left => [Vec3::ZERO, Vec3::new(0.0, y, 0.0), Vec3::new(0.0, y, z), Vec3::new(0.0, 0.0, z)],
+        }
+    }
+
+    /// Axis the face looks along and the two axes of its plane (0 = X, 1 = Y, 2 = Z)
+    fn axes(&self) -> (usize, usize, usize) {
+        match self {
+            Face::Top | Face::Bottom => (1, 0, 2),
+            Face::Front | Face::Back => (2, 0, 1),
+            Face::Right | Face::Left => (0, 2, 1),
+        }
+    }
+
+    /// Unit step to the neighboring voxel this face looks at
+    fn step(&self) -> (i32, i32, i32) {
         match self {
-            Face::Top => 1.0,
-            Face::Bottom => 0.5,
-            Face::Front => 0.85,
-            Face::Back => 0.65,
-            Face::Right => 0.75,
-            Face::Left => 0.75,
+            Face::Top => (0, 1, 0),
+            Face::Bottom => (0, -1, 0),
+            Face::Front => (0, 0, 1),
+            Face::Back => (0, 0, -1),
+            Face::Right => (1, 0, 0),
+            Face::Left => (-1, 0, 0),
         }
     }
 }
 
+/// Brightness of a vertex by how many of its three neighboring voxels
+/// (two sides and the corner between them) are open
+const AO_LEVELS: [f32; 4] = [0.55, 0.7, 0.85, 1.0];
+
+/// Lighting baked into vertex colors by `VoxelModel::to_mesh`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MeshingOptions {
+    /// Direction towards the sun; faces turned away from it fade towards
+    /// `ambient`. None keeps every face at its palette color
+    pub sun: Option<Vec3>,
+    /// Brightness of a face pointing straight away from the sun
+    pub ambient: f32,
+    /// Darken face corners tucked in against neighboring voxels
+    pub ambient_occlusion: bool,
+}
+
+impl MeshingOptions {
+    /// High sun slightly in front and to the right (+Z, +X)
+    pub const DEFAULT_SUN: Vec3 = Vec3::new(0.3, 1.0, 0.5);
+
+    /// Palette colors only
+    pub const FLAT: Self = Self { sun: None, ambient: 1.0, ambient_occlusion: false };
+
+    /// Per-face sun lighting: top bright, sides medium, bottom dark
+    pub const LIT: Self = Self { sun: Some(Self::DEFAULT_SUN), ambient: 0.35, ambient_occlusion: false };
+
+    /// Sun lighting plus per-vertex ambient occlusion (close-up models)
+    pub const SHADED: Self = Self { ambient_occlusion: true, ..Self::LIT };
+
+    /// Same options with the sun in another direction
+    pub fn with_sun(self, direction: Vec3) -> Self {
+        Self { sun: Some(direction), ..self }
+    }
+
+    /// Brightness of a face with this normal
+    ///
+    /// Half-Lambert (N.L remapped from -1..1 to 0..1), so faces turned away
+    /// from the sun still differ from each other instead of all bottoming
+    /// out at `ambient`.
+    pub fn face_light(&self, normal: Vec3) -> f32 {
+        let Some(sun) = self.sun else {
+            return 1.0;
+        };
+        let wrap = normal.dot(sun.normalize_or_zero()) * 0.5 + 0.5;
+        self.ambient + (1.0 - self.ambient) * wrap
+    }
+}
+
+impl Default for MeshingOptions {
+    fn default() -> Self {
+        Self::LIT
+    }
+}
+
 /// A 3D voxel model with fixed dimensions
 #[derive(Clone)]
 pub struct VoxelModel {
@@ -251,6 +336,19 @@ impl VoxelModel {
         }
     }
 
+    /// Move every voxel of `color` into a new model with the same size and origin
+    /// (e.g. to split glass off into a translucent mesh)
+    pub fn extract_color(&mut self, color: VoxelColor) -> VoxelModel {
+        let mut extracted = Self::with_origin(self.width, self.height, self.depth, self.origin);
+        for (src, dst) in self.voxels.iter_mut().zip(extracted.voxels.iter_mut()) {
+            if *src == Voxel::Filled(color) {
+                *dst = *src;
+                *src = Voxel::Empty;
+            }
+        }
+        extracted
+    }
+
     /// Check if a face should be visible (not occluded by adjacent voxel)
     fn face_visible(&self, x: usize, y: usize, z: usize, face: Face) -> bool {
         let (nx, ny, nz) = match face {
@@ -273,8 +371,101 @@ impl VoxelModel {
         matches!(self.get(nx as usize, ny as usize, nz as usize), Voxel::Empty)
     }
 
-    /// Convert voxel model to triangle mesh
-    pub fn to_mesh(&self, scale: f32) -> Mesh {
+    /// Whether the voxel at signed coordinates is filled (outside is empty)
+    fn solid(&self, x: i32, y: i32, z: i32) -> bool {
+        x >= 0 && y >= 0 && z >= 0 && matches!(self.get(x as usize, y as usize, z as usize), Voxel::Filled(_))
+    }
+
+    /// Ambient occlusion level (index into `AO_LEVELS`) of one face corner
+    ///
+    /// `corner` is the corner's offset from the voxel's min corner (0 or 1
+    /// per axis). The voxels checked lie in the layer the face looks into:
+    /// the two beside the corner along the face's edges and the one
+    /// diagonally across it. Both sides filled fully occludes the corner
+    /// regardless of the diagonal.
+    fn corner_occlusion(&self, x: usize, y: usize, z: usize, face: Face, corner: (i32, i32, i32)) -> usize {
+        let (sx, sy, sz) = face.step();
+        let layer = (x as i32 + sx, y as i32 + sy, z as i32 + sz);
+        // Towards the corner along each axis in the face's plane, 0 along the normal
+        let dir = |offset: i32, step: i32| if step != 0 { 0 } else { offset * 2 - 1 };
+        let (dx, dy, dz) = (dir(corner.0, sx), dir(corner.1, sy), dir(corner.2, sz));
+
+        // Split the in-plane direction into the face's two edge directions
+        let (side1, side2) = match face {
+            Face::Top | Face::Bottom => ((dx, 0, 0), (0, 0, dz)),
+            Face::Front | Face::Back => ((dx, 0, 0), (0, dy, 0)),
+            Face::Right | Face::Left => ((0, dy, 0), (0, 0, dz)),
+        };
+        let filled = |(ox, oy, oz): (i32, i32, i32)| self.solid(layer.0 + ox, layer.1 + oy, layer.2 + oz);
+        let (a, b, c) = (filled(side1), filled(side2), filled((dx, dy, dz)));
+        if a && b {
+            0
+        } else {
+            3 - a as usize - b as usize - c as usize
+        }
+    }
+
+    /// Convert voxel model to triangle mesh, with LOD1 and LOD2 attached
+    ///
+    /// LOD1 is built from every other voxel along each axis and LOD2 from
+    /// every third (see `downsampled`), drawn at 2x and 3x the voxel size so
+    /// the model keeps its extent. A level that would not save triangles is
+    /// left out.
+    ///
+    /// `options` picks the lighting baked into the vertex colors.
+    pub fn to_mesh(&self, scale: f32, options: MeshingOptions) -> Mesh {
+        self.mesh_with_lods(scale, &options, Self::surface_mesh)
+    }
+
+    /// `to_mesh` with greedy meshing: visible faces in the same plane with
+    /// the same color are merged into larger quads
+    ///
+    /// Same surface and vertex colors as `to_mesh`, in far fewer triangles
+    /// on models with big flat areas. With ambient occlusion, faces whose
+    /// corners aren't all equally occluded are kept as single voxel faces
+    /// so the shading doesn't change.
+    pub fn to_mesh_greedy(&self, scale: f32, options: MeshingOptions) -> Mesh {
+        self.mesh_with_lods(scale, &options, Self::greedy_surface_mesh)
+    }
+
+    fn mesh_with_lods(&self, scale: f32, options: &MeshingOptions, surface: fn(&Self, f32, &MeshingOptions) -> Mesh) -> Mesh {
+        let mut mesh = surface(self, scale, options);
+        let full = mesh.triangle_count();
+        mesh.lod_meshes = [2, 3].map(|step| {
+            let lod = surface(&self.downsampled(step), scale * step as f32, options);
+            (lod.triangle_count() < full).then(|| Box::new(lod))
+        });
+        mesh
+    }
+
+    /// Model with one voxel per `step` x `step` x `step` block of this one
+    ///
+    /// A block is filled with the color of its first filled voxel, so thin
+    /// parts (trunks, barrels) survive instead of being skipped over.
+    pub fn downsampled(&self, step: usize) -> VoxelModel {
+        let step = step.max(1);
+        let mut coarse = Self::with_origin(
+            self.width.div_ceil(step),
+            self.height.div_ceil(step),
+            self.depth.div_ceil(step),
+            self.origin / step as f32,
+        );
+        for z in 0..self.depth {
+            for y in 0..self.height {
+                for x in 0..self.width {
+                    let voxel = self.get(x, y, z);
+                    let (cx, cy, cz) = (x / step, y / step, z / step);
+                    if voxel != Voxel::Empty && coarse.get(cx, cy, cz) == Voxel::Empty {
+                        coarse.set(cx, cy, cz, voxel);
+                    }
+                }
+            }
+        }
+        coarse
+    }
+
+    /// Triangles of every visible voxel face
+    fn surface_mesh(&self, scale: f32, options: &MeshingOptions) -> Mesh {
         let mut mesh = Mesh::new();
 
         for z in 0..self.depth {
@@ -282,9 +473,9 @@ impl VoxelModel {
                 for x in 0..self.width {
                     if let Voxel::Filled(color) = self.get(x, y, z) {
                         // Check each face
-                        for face in [Face::Top, Face::Bottom, Face::Front, Face::Back, Face::Right, Face::Left] {
+                        for face in Face::ALL {
                             if self.face_visible(x, y, z, face) {
-                                self.add_face(&mut mesh, x, y, z, face, color, scale);
+                                self.add_face(&mut mesh, x, y, z, face, color, scale, options);
                             }
                         }
                     }
@@ -295,76 +486,136 @@ impl VoxelModel {
         mesh
     }
 
+    /// Triangles of every visible voxel face, merged slice by slice
+    ///
+    /// Each slice of voxels along a face's axis gets a mask of the faces
+    /// visible in it, keyed by color and occlusion level. Rectangles are
+    /// grown from the first unmerged face in scan order, first along the
+    /// row and then row by row while the whole span matches, and cleared
+    /// from the mask, so every face ends up in exactly one quad.
+    fn greedy_surface_mesh(&self, scale: f32, options: &MeshingOptions) -> Mesh {
+        let mut mesh = Mesh::new();
+        let dims = [self.width, self.height, self.depth];
+
+        for face in Face::ALL {
+            let (n, u, v) = face.axes();
+            let (du, dv) = (dims[u], dims[v]);
+            let light = options.face_light(face.normal());
+            let mut mask: Vec<Option<(VoxelColor, usize)>> = vec![None; du * dv];
+
+            for slice in 0..dims[n] {
+                let voxel_at = |i: usize, j: usize| {
+                    let mut p = [0; 3];
+                    (p[n], p[u], p[v]) = (slice, i, j);
+                    p
+                };
+
+                for j in 0..dv {
+                    for i in 0..du {
+                        let [x, y, z] = voxel_at(i, j);
+                        mask[i + j * du] = match self.get(x, y, z) {
+                            Voxel::Filled(color) if self.face_visible(x, y, z, face) => {
+                                let ao = self.face_occlusion(x, y, z, face, options);
+                                if ao.iter().all(|&level| level == ao[0]) {
+                                    Some((color, ao[0]))
+                                } else {
+                                    // Shading varies across the face
+                                    self.add_quad(&mut mesh, [x, y, z], [1, 1, 1], face, color.shade(light), scale, ao);
+                                    None
+                                }
+                            }
+                            _ => None,
+                        };
+                    }
+                }
+
+                for j in 0..dv {
+                    let mut i = 0;
+                    while i < du {
+                        let Some(key) = mask[i + j * du] else {
+                            i += 1;
+                            continue;
+                        };
+                        let mut w = 1;
+                        while i + w < du && mask[i + w + j * du] == Some(key) {
+                            w += 1;
+                        }
+                        let mut h = 1;
+                        while j + h < dv && (i..i + w).all(|k| mask[k + (j + h) * du] == Some(key)) {
+                            h += 1;
+                        }
+                        for row in j..j + h {
+                            mask[i + row * du..i + w + row * du].fill(None);
+                        }
+
+                        let mut size = [1; 3];
+                        (size[u], size[v]) = (w, h);
+                        let (color, level) = key;
+                        self.add_quad(&mut mesh, voxel_at(i, j), size, face, color.shade(light), scale, [level; 4]);
+                        i += w;
+                    }
+                }
+            }
+        }
+
+        mesh
+    }
+
     /// Add a single face to the mesh
-    fn add_face(&self, mesh: &mut Mesh, x: usize, y: usize, z: usize, face: Face, color: VoxelColor, scale: f32) {
+    fn add_face(&self, mesh: &mut Mesh, x: usize, y: usize, z: usize, face: Face, color: VoxelColor, scale: f32, options: &MeshingOptions) {
+        let ao = self.face_occlusion(x, y, z, face, options);
+        let shaded_color = color.shade(options.face_light(face.normal()));
+        self.add_quad(mesh, [x, y, z], [1, 1, 1], face, shaded_color, scale, ao);
+    }
+
+    /// Occlusion level of each corner of a face, in `Face::corners` order
+    /// (fully open without AO)
+    fn face_occlusion(&self, x: usize, y: usize, z: usize, face: Face, options: &MeshingOptions) -> [usize; 4] {
+        if !options.ambient_occlusion {
+            return [3; 4];
+        }
+        face.corners(Vec3::ONE).map(|offset| {
+            let corner = |v: f32| (v > 0.5) as i32;
+            self.corner_occlusion(x, y, z, face, (corner(offset.x), corner(offset.y), corner(offset.z)))
+        })
+    }
+
+    /// Add one face of the box of `size` voxels starting at voxel `min`
+    fn add_quad(&self, mesh: &mut Mesh, min: [usize; 3], size: [usize; 3], face: Face, color: Vec3, scale: f32, ao: [usize; 4]) {
         let base_idx = mesh.vertices.len() as u32;
         let normal = face.normal();
-        let shaded_color = color.shade(face.shade_factor());
 
         // Calculate world position with origin offset
-        let wx = (x as f32 - self.origin.x) * scale;
-        let wy = (y as f32 - self.origin.y) * scale;
-        let wz = (z as f32 - self.origin.z) * scale;
-
-        // Define face vertices (4 corners) - CCW winding when viewed from outside
-        let positions: [Vec3; 4] = match face {
-            Face::Top => [
-                // CCW when viewed from +Y (above): back-left, front-left, front-right, back-right
-                Vec3::new(wx, wy + scale, wz),
-                Vec3::new(wx, wy + scale, wz + scale),
-                Vec3::new(wx + scale, wy + scale, wz + scale),
-                Vec3::new(wx + scale, wy + scale, wz),
-            ],
-            Face::Bottom => [
-                // CCW when viewed from -Y (below): front-left, back-left, back-right, front-right
-                Vec3::new(wx, wy, wz + scale),
-                Vec3::new(wx, wy, wz),
-                Vec3::new(wx + scale, wy, wz),
-                Vec3::new(wx + scale, wy, wz + scale),
-            ],
-            Face::Front => [
-                Vec3::new(wx, wy, wz + scale),
-                Vec3::new(wx, wy + scale, wz + scale),
-                Vec3::new(wx + scale, wy + scale, wz + scale),
-                Vec3::new(wx + scale, wy, wz + scale),
-            ],
-            Face::Back => [
-                Vec3::new(wx + scale, wy, wz),
-                Vec3::new(wx + scale, wy + scale, wz),
-                Vec3::new(wx, wy + scale, wz),
-                Vec3::new(wx, wy, wz),
-            ],
-            Face::Right => [
-                Vec3::new(wx + scale, wy, wz + scale),
-                Vec3::new(wx + scale, wy + scale, wz + scale),
-                Vec3::new(wx + scale, wy + scale, wz),
-                Vec3::new(wx + scale, wy, wz),
-            ],
-            Face::Left => [
-                Vec3::new(wx, wy, wz),
-                Vec3::new(wx, wy + scale, wz),
-                Vec3::new(wx, wy + scale, wz + scale),
-                Vec3::new(wx, wy, wz + scale),
-            ],
+        let origin = (Vec3::new(min[0] as f32, min[1] as f32, min[2] as f32) - self.origin) * scale;
+        let extent = Vec3::new(size[0] as f32, size[1] as f32, size[2] as f32);
+        let positions = face.corners(extent).map(|corner| origin + corner * scale);
+
+        // Texture coordinates in the face's plane, one repeat every
+        // VOXELS_PER_TEXTURE voxels (v runs down the sides)
+        let uv = |pos: Vec3| {
+            let p = pos / (scale * VOXELS_PER_TEXTURE);
+            match face {
+                Face::Top | Face::Bottom => Vec2::new(p.x, p.z),
+                Face::Front | Face::Back => Vec2::new(p.x, -p.y),
+                Face::Right | Face::Left => Vec2::new(p.z, -p.y),
+            }
         };
 
         // Add 4 vertices
-        for pos in &positions {
+        for (pos, level) in positions.iter().zip(ao) {
             mesh.vertices.push(Vertex {
                 position: *pos,
                 normal,
-                color: shaded_color,
-                uv: Vec2::ZERO,
+                color: color * AO_LEVELS[level],
+                uv: uv(*pos),
             });
         }
 
         // Add 2 triangles (6 indices) - CCW winding when viewed from outside
-        mesh.indices.push(base_idx);
-        mesh.indices.push(base_idx + 1);
-        mesh.indices.push(base_idx + 2);
-        mesh.indices.push(base_idx);
-        mesh.indices.push(base_idx + 2);
-        mesh.indices.push(base_idx + 3);
+        // Split along the brighter diagonal so occlusion shades the quad
+        // evenly instead of streaking along the split
+        let corners = if ao[0] + ao[2] >= ao[1] + ao[3] { [0, 1, 2, 0, 2, 3] } else { [1, 2, 3, 1, 3, 0] };
+        mesh.indices.extend(corners.map(|i| base_idx + i));
     }
 
     /// Count filled voxels
@@ -462,3 +713,153 @@ impl CharacterCustomization {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Brightness of the top face of the voxel at the origin at corner (x, z)
+    fn top_corner_brightness(mesh: &Mesh, x: f32, z: f32) -> f32 {
+        mesh.vertices
+            .iter()
+            .find(|v| v.normal == Vec3::Y && v.position == Vec3::new(x, 1.0, z))
+            .map(|v| v.color.x)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_ao_darkens_inside_corner() {
+        // Two voxels side by side with a third stacked on the right one: the
+        // left voxel's top meets a wall along x = 1
+        let white = VoxelColor::new(255, 255, 255);
+        let mut model = VoxelModel::new(2, 2, 1);
+        model.set_color(0, 0, 0, white);
+        model.set_color(1, 0, 0, white);
+        model.set_color(1, 1, 0, white);
+
+        let shaded = model.to_mesh(1.0, MeshingOptions::SHADED);
+        let exposed = top_corner_brightness(&shaded, 0.0, 0.0);
+        let inside = top_corner_brightness(&shaded, 1.0, 0.0);
+        assert!(inside < exposed, "inside corner {} should be darker than {}", inside, exposed);
+        assert_eq!(top_corner_brightness(&shaded, 1.0, 1.0), inside);
+
+        // Without AO the whole face has one color
+        let lit = model.to_mesh(1.0, MeshingOptions::LIT);
+        assert_eq!(top_corner_brightness(&lit, 0.0, 0.0), top_corner_brightness(&lit, 1.0, 0.0));
+    }
+
+    #[test]
+    fn test_sun_lighting_orders_faces() {
+        let options = MeshingOptions::LIT;
+        let (top, side, bottom) = (options.face_light(Vec3::Y), options.face_light(Vec3::X), options.face_light(Vec3::NEG_Y));
+        assert!(top > side && side > bottom);
+        assert!(top <= 1.0 && bottom >= options.ambient);
+
+        // Sun from below flips it
+        let under = options.with_sun(Vec3::NEG_Y);
+        assert!(under.face_light(Vec3::NEG_Y) > under.face_light(Vec3::Y));
+        assert_eq!(MeshingOptions::FLAT.face_light(Vec3::NEG_Y), 1.0);
+    }
+
+    /// Orthographic render of `mesh` rotated by `yaw` then `pitch`, framed
+    /// on `bounds` (center, radius): nearest surface color per pixel
+    fn render(mesh: &Mesh, bounds: (Vec3, f32), yaw: f32, pitch: f32) -> Vec<Option<Vec3>> {
+        const SIZE: usize = 96;
+        let rotation = glam::Mat3::from_rotation_x(pitch) * glam::Mat3::from_rotation_y(yaw);
+        let project = |p: Vec3| {
+            let r = rotation * (p - bounds.0) / bounds.1;
+            Vec3::new((r.x * 0.5 + 0.5) * SIZE as f32, (r.y * 0.5 + 0.5) * SIZE as f32, r.z)
+        };
+
+        let mut color = vec![None; SIZE * SIZE];
+        let mut depth = vec![f32::NEG_INFINITY; SIZE * SIZE];
+        for tri in mesh.indices.chunks(3) {
+            let v = [0, 1, 2].map(|k| &mesh.vertices[tri[k] as usize]);
+            let p = v.map(|vertex| project(vertex.position));
+            let edge = |a: Vec3, b: Vec3, x: f32, y: f32| (b.x - a.x) * (y - a.y) - (b.y - a.y) * (x - a.x);
+            let area = edge(p[0], p[1], p[2].x, p[2].y);
+            if area == 0.0 {
+                continue;
+            }
+            let (lo, hi) = (p[0].min(p[1]).min(p[2]), p[0].max(p[1]).max(p[2]));
+            let span = |lo: f32, hi: f32| (lo.max(0.0) as usize)..(hi.max(0.0).ceil() as usize).min(SIZE);
+            for py in span(lo.y, hi.y) {
+                for px in span(lo.x, hi.x) {
+                    let (x, y) = (px as f32 + 0.5, py as f32 + 0.5);
+                    let w = [edge(p[1], p[2], x, y), edge(p[2], p[0], x, y), edge(p[0], p[1], x, y)].map(|e| e / area);
+                    if w.iter().any(|&b| b < 0.0) {
+                        continue;
+                    }
+                    let z = w[0] * p[0].z + w[1] * p[1].z + w[2] * p[2].z;
+                    let i = px + py * SIZE;
+                    if z > depth[i] {
+                        depth[i] = z;
+                        color[i] = Some(w[0] * v[0].color + w[1] * v[1].color + w[2] * v[2].color);
+                    }
+                }
+            }
+        }
+        color
+    }
+
+    /// Total triangle area facing each way (Face::ALL order)
+    fn area_by_face(mesh: &Mesh) -> [f32; 6] {
+        let mut areas = [0.0; 6];
+        for tri in mesh.indices.chunks(3) {
+            let [a, b, c] = [0, 1, 2].map(|k| &mesh.vertices[tri[k] as usize]);
+            let face = Face::ALL.iter().position(|f| f.normal() == a.normal).unwrap();
+            areas[face] += (b.position - a.position).cross(c.position - a.position).length() * 0.5;
+        }
+        areas
+    }
+
+    #[test]
+    fn test_greedy_mesh_matches_naive() {
+        let models = [
+            crate::voxel_models::create_player_model(&CharacterCustomization::default()),
+            crate::voxel_models::create_pine_tree(),
+            crate::voxel_models::create_battle_bus(),
+        ];
+        for model in &models {
+            for options in [MeshingOptions::LIT, MeshingOptions::SHADED] {
+                let naive = model.to_mesh(1.0, options);
+                let greedy = model.to_mesh_greedy(1.0, options);
+                assert!(greedy.triangle_count() < naive.triangle_count());
+
+                // Same surface, no face covered twice
+                for (a, b) in area_by_face(&naive).iter().zip(area_by_face(&greedy)) {
+                    assert!((a - b).abs() <= a * 1e-5, "face area {} vs {}", a, b);
+                }
+
+                // Same picture from several angles: no cracks, same colors
+                let (min, max) = naive.vertices.iter().fold((Vec3::MAX, Vec3::MIN), |(lo, hi), v| (lo.min(v.position), hi.max(v.position)));
+                let bounds = ((min + max) * 0.5, (max - min).length() * 0.5);
+                for (yaw, pitch) in [(0.3, 0.2), (2.1, -0.4), (4.0, 0.9), (5.3, -1.1)] {
+                    let expected = render(&naive, bounds, yaw, pitch);
+                    let actual = render(&greedy, bounds, yaw, pitch);
+                    for (e, a) in expected.iter().zip(&actual) {
+                        match (e, a) {
+                            (Some(e), Some(a)) => assert!(e.distance(*a) < 1e-3, "color {} vs {}", e, a),
+                            (e, a) => assert_eq!(e.is_some(), a.is_some(), "silhouettes differ"),
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_greedy_merges_flat_slab() {
+        // A one-color 4x1x3 slab is one quad per side
+        let mut model = VoxelModel::new(4, 1, 3);
+        model.fill_box(0, 0, 0, 3, 0, 2, palette::STONE_GRAY);
+        assert_eq!(model.to_mesh_greedy(1.0, MeshingOptions::FLAT).triangle_count(), 12);
+        assert_eq!(model.to_mesh(1.0, MeshingOptions::FLAT).triangle_count(), 2 * (4 * 3 * 2 + 4 * 2 + 3 * 2));
+
+        // A second color splits the top and bottom
+        model.set_color(3, 0, 2, palette::BRICK_RED);
+        let split = model.to_mesh_greedy(1.0, MeshingOptions::FLAT);
+        assert!(split.triangle_count() > 12);
+        assert_eq!(area_by_face(&split)[0], 12.0);
+    }
+}
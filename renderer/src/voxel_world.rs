@@ -3,7 +3,7 @@
 //! Creates terrain, buildings, and environmental features using voxels.
 
 use alloc::vec::Vec;
-use crate::voxel::{VoxelModel, VoxelColor, palette};
+use crate::voxel::{MeshingOptions, VoxelModel, VoxelColor, palette};
 use crate::mesh::Mesh;
 use glam::Vec3;
 
@@ -105,7 +105,7 @@ impl TerrainChunk {
             }
         }
 
-        let mut mesh = model.to_mesh(scale);
+        let mut mesh = model.to_mesh(scale, MeshingOptions::LIT);
 
         // Offset mesh to world position
         let offset = Vec3::new(
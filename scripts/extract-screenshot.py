@@ -0,0 +1,65 @@
+#!/usr/bin/env python3
+"""Extract screenshots from a captured serial log.
+
+The kernel streams screenshots (F12, the `screenshot` console command, or
+automatically in test and benchmark runs) as base64 PPM between
+SCREENSHOT_BEGIN and SCREENSHOT_END lines. Each one is checked against its
+SCREENSHOT_CRC32 line and written out as <prefix>-<n>.ppm.
+
+Usage: scripts/extract-screenshot.py serial.log [prefix]
+"""
+
+import base64
+import sys
+import zlib
+
+
+def screenshots(lines):
+    """Yield (width, height, size, base64 body, crc) per dump in the log"""
+    body = None
+    for line in lines:
+        line = line.strip()
+        if line.startswith("SCREENSHOT_BEGIN"):
+            _, width, height, size = line.split()
+            body, crc = [], None
+        elif body is None:
+            continue
+        elif line.startswith("SCREENSHOT_CRC32"):
+            crc = int(line.split()[1], 16)
+        elif line == "SCREENSHOT_END":
+            yield int(width), int(height), int(size), "".join(body), crc
+            body = None
+        else:
+            body.append(line)
+
+
+def main():
+    if len(sys.argv) < 2:
+        print(__doc__.strip())
+        return 2
+    prefix = sys.argv[2] if len(sys.argv) > 2 else "screenshot"
+
+    with open(sys.argv[1], errors="replace") as log:
+        shots = list(screenshots(log))
+    if not shots:
+        print("no screenshots found")
+        return 1
+
+    failed = False
+    for n, (width, height, size, text, crc) in enumerate(shots):
+        data = base64.b64decode(text)
+        actual = zlib.crc32(data)
+        if len(data) != size or actual != crc:
+            print(f"screenshot {n}: corrupt ({len(data)}/{size} bytes, "
+                  f"crc {actual:08x} != {crc if crc is None else format(crc, '08x')})")
+            failed = True
+            continue
+        path = f"{prefix}-{n}.ppm"
+        with open(path, "wb") as out:
+            out.write(data)
+        print(f"{path}: {width}x{height}")
+    return 1 if failed else 0
+
+
+if __name__ == "__main__":
+    sys.exit(main())
@@ -0,0 +1,117 @@
+#!/usr/bin/env python3
+"""Gateway routing check under QEMU.
+
+Boots the server ISO with user networking, asks the kernel (via the serial
+admin console) to send UDP to an off-subnet address, and verifies from a
+packet capture that those frames were addressed to the gateway's MAC.
+
+Usage: scripts/test-gateway.py [server.iso]
+"""
+
+import socket
+import struct
+import subprocess
+import sys
+import time
+
+ISO = sys.argv[1] if len(sys.argv) > 1 else "server.iso"
+PCAP = "gateway.pcap"
+SERIAL_PORT = 4555
+GATEWAY_IP = bytes([10, 0, 2, 2])
+OFF_SUBNET_IP = "192.0.2.10"
+BOOT_TIMEOUT = 60
+
+
+def connect_serial():
+    deadline = time.time() + 10
+    while time.time() < deadline:
+        try:
+            return socket.create_connection(("127.0.0.1", SERIAL_PORT))
+        except OSError:
+            time.sleep(0.2)
+    raise RuntimeError("could not connect to QEMU serial port")
+
+
+def read_until(sock, marker, timeout):
+    sock.settimeout(0.5)
+    data = b""
+    deadline = time.time() + timeout
+    while time.time() < deadline:
+        try:
+            chunk = sock.recv(4096)
+        except socket.timeout:
+            continue
+        if not chunk:
+            break
+        data += chunk
+        if marker in data:
+            return data
+    raise RuntimeError(f"timed out waiting for {marker!r}")
+
+
+def read_pcap(path):
+    with open(path, "rb") as f:
+        header = f.read(24)
+        magic = struct.unpack("<I", header[:4])[0]
+        endian = "<" if magic == 0xA1B2C3D4 else ">"
+        while True:
+            rec = f.read(16)
+            if len(rec) < 16:
+                return
+            _, _, incl_len, _ = struct.unpack(endian + "IIII", rec)
+            yield f.read(incl_len)
+
+
+def main():
+    qemu = subprocess.Popen([
+        "qemu-system-x86_64", "-M", "q35", "-m", "512M", "-smp", "5",
+        "-cdrom", ISO, "-display", "none", "-no-reboot",
+        "-serial", f"tcp:127.0.0.1:{SERIAL_PORT},server=on,wait=off",
+        "-device", "e1000,netdev=net0",
+        "-netdev", "user,id=net0",
+        "-object", f"filter-dump,id=dump0,netdev=net0,file={PCAP}",
+    ])
+
+    try:
+        serial = connect_serial()
+        read_until(serial, b"DEDICATED SERVER STARTED", BOOT_TIMEOUT)
+
+        for _ in range(5):
+            serial.sendall(f"send {OFF_SUBNET_IP} 5000\n".encode())
+            time.sleep(0.2)
+        serial.sendall(b"arp\n")
+        output = read_until(serial, b"ARP: ", 10)
+        time.sleep(1)
+    finally:
+        qemu.terminate()
+        qemu.wait()
+
+    gateway_mac = None
+    probe_dst_macs = set()
+    target = socket.inet_aton(OFF_SUBNET_IP)
+    for frame in read_pcap(PCAP):
+        ethertype = struct.unpack("!H", frame[12:14])[0]
+        if ethertype == 0x0806 and frame[14 + 14:14 + 18] == GATEWAY_IP:
+            gateway_mac = frame[14 + 8:14 + 14]
+        elif ethertype == 0x0800 and frame[14 + 16:14 + 20] == target:
+            probe_dst_macs.add(frame[0:6])
+
+    if gateway_mac is None:
+        print("FAIL: no ARP reply from gateway captured")
+        return 1
+    if not probe_dst_macs:
+        print(f"FAIL: no frames to {OFF_SUBNET_IP} captured")
+        return 1
+    if probe_dst_macs != {gateway_mac}:
+        print(f"FAIL: off-subnet frames sent to {probe_dst_macs}, gateway is {gateway_mac.hex(':')}")
+        return 1
+    if b"10.0.2.2 ->" not in output:
+        print("FAIL: gateway missing from console ARP table")
+        return 1
+
+    print(f"PASS: off-subnet traffic routed via gateway {gateway_mac.hex(':')}")
+    return 0
+
+
+if __name__ == "__main__":
+    sys.exit(main())
@@ -45,10 +45,10 @@ impl AmmoReserves {
     /// Add ammo
     pub fn add(&mut self, ammo_type: AmmoType, amount: u16) {
         match ammo_type {
-            AmmoType::Light => self.light = (self.light + amount).min(999),
-            AmmoType::Medium => self.medium = (self.medium + amount).min(999),
-            AmmoType::Heavy => self.heavy = (self.heavy + amount).min(999),
-            AmmoType::Shells => self.shells = (self.shells + amount).min(999),
+            AmmoType::Light => self.light = self.light.saturating_add(amount).min(999),
+            AmmoType::Medium => self.medium = self.medium.saturating_add(amount).min(999),
+            AmmoType::Heavy => self.heavy = self.heavy.saturating_add(amount).min(999),
+            AmmoType::Shells => self.shells = self.shells.saturating_add(amount).min(999),
         }
     }
 
@@ -103,15 +103,15 @@ impl Materials {
     }
 
     pub fn add_wood(&mut self, amount: u32) {
-        self.wood = (self.wood + amount).min(999);
+        self.wood = self.wood.saturating_add(amount).min(999);
     }
 
     pub fn add_brick(&mut self, amount: u32) {
-        self.brick = (self.brick + amount).min(999);
+        self.brick = self.brick.saturating_add(amount).min(999);
     }
 
     pub fn add_metal(&mut self, amount: u32) {
-        self.metal = (self.metal + amount).min(999);
+        self.metal = self.metal.saturating_add(amount).min(999);
     }
 }
 
@@ -213,10 +213,34 @@ impl Inventory {
         if self.pickaxe_selected {
             None
         } else {
-            let weapon = self.slots[self.selected_slot].take();
+            self.drop_slot(self.selected_slot)
+        }
+    }
+
+    /// Drop the weapon in a slot (0-4)
+    /// Falls back to the pickaxe if the dropped slot was selected
+    pub fn drop_slot(&mut self, slot: usize) -> Option<Weapon> {
+        let weapon = self.slots.get_mut(slot)?.take();
+        if weapon.is_some() && !self.pickaxe_selected && self.selected_slot == slot {
             self.pickaxe_selected = true;
-            weapon
         }
+        weapon
+    }
+
+    /// Swap the contents of two slots (returns false if either is out of range)
+    /// The selection follows the selected weapon to its new slot
+    pub fn swap_slots(&mut self, a: usize, b: usize) -> bool {
+        if a >= INVENTORY_SLOTS || b >= INVENTORY_SLOTS {
+            return false;
+        }
+
+        self.slots.swap(a, b);
+        if self.selected_slot == a {
+            self.selected_slot = b;
+        } else if self.selected_slot == b {
+            self.selected_slot = a;
+        }
+        true
     }
 
     /// Find first empty slot index
@@ -245,18 +269,124 @@ impl Inventory {
     }
 
     /// Reload current weapon from ammo reserves
-    pub fn reload_current(&mut self) {
+    ///
+    /// Moves rounds from the matching reserve into the magazine (up to capacity)
+    /// and starts the reload timer. Returns the number of rounds loaded.
+    pub fn reload_current(&mut self) -> u16 {
         if self.pickaxe_selected {
-            return;
+            return 0;
         }
 
         if let Some(weapon) = &mut self.slots[self.selected_slot] {
             if let Some(ammo_type) = AmmoType::for_weapon(weapon.weapon_type) {
                 if weapon.ammo < weapon.max_ammo && !weapon.is_reloading() {
-                    let _available = self.ammo.get(ammo_type);
-                    weapon.start_reload();
+                    let needed = weapon.max_ammo - weapon.ammo;
+                    let loaded = self.ammo.take(ammo_type, needed);
+                    if loaded > 0 {
+                        weapon.start_reload();
+                        weapon.add_ammo(loaded);
+                    }
+                    return loaded;
                 }
             }
         }
+
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::weapon::{Rarity, WeaponType};
+
+    #[test]
+    fn test_reload_with_empty_reserve() {
+        let mut inv = Inventory::new();
+        inv.add_weapon(Weapon::new(WeaponType::AssaultRifle, Rarity::Common));
+        inv.select_slot(0);
+        inv.selected_weapon_mut().ammo = 10;
+
+        assert_eq!(inv.reload_current(), 0);
+        assert_eq!(inv.selected_weapon().ammo, 10);
+        assert!(!inv.selected_weapon().is_reloading());
+    }
+
+    #[test]
+    fn test_reload_consumes_reserve() {
+        let mut inv = Inventory::new();
+        inv.add_weapon(Weapon::new(WeaponType::Pistol, Rarity::Common));
+        inv.select_slot(0);
+        inv.selected_weapon_mut().ammo = 4;
+        inv.ammo.add(AmmoType::Light, 20);
+
+        assert_eq!(inv.reload_current(), 12);
+        assert_eq!(inv.selected_weapon().ammo, 16);
+        assert_eq!(inv.ammo.light, 8);
+
+        // Firing is blocked until the reload timer runs out, then uses the magazine
+        assert!(!inv.selected_weapon_mut().fire());
+        inv.update(2.0);
+        assert!(inv.selected_weapon_mut().fire());
+        assert_eq!(inv.selected_weapon().ammo, 15);
+    }
+
+    #[test]
+    fn test_swap_occupied_slots() {
+        let mut inv = Inventory::new();
+        inv.add_weapon(Weapon::new(WeaponType::Pistol, Rarity::Common));
+        inv.add_weapon(Weapon::new(WeaponType::Shotgun, Rarity::Rare));
+        inv.select_slot(0);
+
+        assert!(inv.swap_slots(0, 1));
+        assert_eq!(inv.slots[0].as_ref().unwrap().weapon_type, WeaponType::Shotgun);
+        assert_eq!(inv.slots[1].as_ref().unwrap().weapon_type, WeaponType::Pistol);
+        // Selection follows the pistol
+        assert_eq!(inv.selected_slot, 1);
+        assert_eq!(inv.selected_weapon().weapon_type, WeaponType::Pistol);
+
+        assert!(!inv.swap_slots(0, INVENTORY_SLOTS));
+    }
+
+    #[test]
+    fn test_drop_slot() {
+        let mut inv = Inventory::new();
+        inv.add_weapon(Weapon::new(WeaponType::Smg, Rarity::Epic));
+
+        let dropped = inv.drop_slot(0);
+        assert_eq!(dropped.map(|w| w.weapon_type), Some(WeaponType::Smg));
+        assert!(inv.slots[0].is_none());
+        assert!(inv.drop_slot(0).is_none());
+        assert!(inv.drop_slot(INVENTORY_SLOTS).is_none());
+    }
+
+    #[test]
+    fn test_drop_selected_slot_keeps_selection_valid() {
+        let mut inv = Inventory::new();
+        inv.add_weapon(Weapon::new(WeaponType::Pistol, Rarity::Common));
+        inv.add_weapon(Weapon::new(WeaponType::Sniper, Rarity::Legendary));
+        inv.select_slot(1);
+
+        // Dropping another slot leaves the selection alone
+        assert!(inv.drop_slot(0).is_some());
+        assert!(!inv.pickaxe_selected);
+        assert_eq!(inv.selected_weapon().weapon_type, WeaponType::Sniper);
+
+        // Dropping the selected slot falls back to the pickaxe
+        assert!(inv.drop_slot(1).is_some());
+        assert!(inv.pickaxe_selected);
+        assert_eq!(inv.selected_weapon().weapon_type, WeaponType::Pickaxe);
+    }
+
+    #[test]
+    fn test_ammo_pickup_goes_to_matching_reserve() {
+        let mut inv = Inventory::new();
+        inv.ammo.add(AmmoType::Shells, 10);
+
+        assert_eq!(inv.ammo.get(AmmoType::Shells), 10);
+        assert_eq!(inv.ammo.get(AmmoType::Light), 0);
+        assert_eq!(inv.ammo.get(AmmoType::Medium), 0);
+        assert_eq!(inv.ammo.get(AmmoType::Heavy), 0);
+        assert_eq!(AmmoType::for_weapon(WeaponType::Shotgun), Some(AmmoType::Shells));
     }
 }
@@ -27,7 +27,12 @@ pub mod movement {
     /// Sprint speed multiplier
     pub const SPRINT_MULTIPLIER: f32 = 1.5;
     /// Crouch speed multiplier
-    pub const CROUCH_MULTIPLIER: f32 = 0.5;
+    pub const CROUCH_SPEED_MULTIPLIER: f32 = 0.5;
+    /// Collision box heights when standing and crouched
+    pub const STANDING_HEIGHT: f32 = 1.8;
+    pub const CROUCH_HEIGHT: f32 = 1.2;
+    /// Eye (and camera) drop when fully crouched
+    pub const CROUCH_CAMERA_DROP: f32 = 0.4;
     /// Jump velocity
     pub const JUMP_VELOCITY: f32 = 15.0;
     /// Gravity
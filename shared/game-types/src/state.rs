@@ -104,6 +104,7 @@ pub struct Settings {
     pub sensitivity: u8,
     pub render_distance: u8,
     pub volume: u8,
+    pub font_scale: u8,
 }
 
 impl Default for Settings {
@@ -114,6 +115,7 @@ impl Default for Settings {
             sensitivity: 5,
             render_distance: 3,
             volume: 80,
+            font_scale: 2,
         }
     }
 }
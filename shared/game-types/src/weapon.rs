@@ -269,11 +269,9 @@ impl Weapon {
             self.fire_cooldown -= dt;
         }
 
+        // Rounds are loaded when the reload starts; the timer only blocks firing
         if self.reload_timer > 0.0 {
             self.reload_timer -= dt;
-            if self.reload_timer <= 0.0 {
-                self.ammo = self.max_ammo;
-            }
         }
     }
 